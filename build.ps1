@@ -361,6 +361,33 @@ function Create-DataDisk {
     } else {
         Write-Host "Using existing data disk (preserving data): $diskPath" -ForegroundColor Cyan
     }
+    
+    Create-KeystoreDisk
+}
+
+function Create-KeystoreDisk {
+    Write-Step "Creating VirtIO keystore disk (16MB)"
+    
+    $diskPath = "$ProjectRoot\target\x86_64-unknown-none\release\zero-os-keys.img"
+    $keyPath = "$ProjectRoot\target\x86_64-unknown-none\release\zero-os-keystore.key"
+    
+    # Keystore lives on its own disk, separate from VFS data (Invariant 32)
+    if (-not (Test-Path $diskPath)) {
+        $fileStream = [System.IO.File]::Create($diskPath)
+        $fileStream.SetLength(16 * 1024 * 1024)
+        $fileStream.Close()
+        
+        Write-Host "Created new keystore disk: $diskPath" -ForegroundColor Green
+    }
+    
+    # Master key is passed to the guest via fw_cfg, never stored on a guest disk
+    if (-not (Test-Path $keyPath)) {
+        $key = New-Object byte[] 32
+        [System.Security.Cryptography.RandomNumberGenerator]::Create().GetBytes($key)
+        [System.IO.File]::WriteAllBytes($keyPath, $key)
+        
+        Write-Host "Created new keystore master key: $keyPath" -ForegroundColor Green
+    }
 }
 
 function Reset-DataDisk {
@@ -423,6 +450,8 @@ function Run-Qemu {
     $biosImage = "$ProjectRoot\target\x86_64-unknown-none\release\zero-os-bios.img"
     $uefiImage = "$ProjectRoot\target\x86_64-unknown-none\release\zero-os-uefi.img"
     $dataImage = "$ProjectRoot\target\x86_64-unknown-none\release\zero-os-data.img"
+    $keysImage = "$ProjectRoot\target\x86_64-unknown-none\release\zero-os-keys.img"
+    $keystoreKey = "$ProjectRoot\target\x86_64-unknown-none\release\zero-os-keystore.key"
     
     if ($Uefi) {
         $imagePath = $uefiImage
@@ -438,6 +467,10 @@ function Run-Qemu {
     $qemuArgs = @(
         "-drive", "format=raw,file=$imagePath",
         "-drive", "file=$dataImage,if=virtio,format=raw",
+        "-drive", "file=$keysImage,if=virtio,format=raw",
+        "-fw_cfg", "name=opt/zos/keystore-key,file=$keystoreKey",
+        "-netdev", "user,id=net0",
+        "-device", "virtio-net-pci,netdev=net0",
         "-nographic",  # Combines -serial stdio with proper stdin routing on Windows
         "-device", "isa-debug-exit,iobase=0xf4,iosize=0x04",
         "-no-reboot",
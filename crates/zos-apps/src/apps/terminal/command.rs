@@ -43,9 +43,21 @@ pub enum Command {
     /// Show system uptime
     Time,
 
+    /// Show kernel state as of a CommitLog sequence
+    StateAt { seq: u64 },
+
+    /// Show what changed between two CommitLog sequences
+    Diff { from: u64, to: u64 },
+
     /// Clear the terminal screen
     Clear,
 
+    /// Stop services and power off the machine
+    Shutdown,
+
+    /// Stop services and reboot the machine
+    Reboot,
+
     /// Exit the terminal
     Exit,
 
@@ -171,7 +183,48 @@ impl Command {
             }),
 
             "time" | "uptime" => Ok(Command::Time),
+
+            "at" => {
+                if args.is_empty() {
+                    Err(ParseError::MissingArgument {
+                        command: "at",
+                        argument: "seq",
+                    })
+                } else {
+                    args[0]
+                        .parse::<u64>()
+                        .map(|seq| Command::StateAt { seq })
+                        .map_err(|_| ParseError::InvalidArgument {
+                            argument: "seq",
+                            reason: "must be a number",
+                        })
+                }
+            }
+
+            "diff" => {
+                if args.len() < 2 {
+                    return Err(ParseError::MissingArgument {
+                        command: "diff",
+                        argument: "from, to",
+                    });
+                }
+
+                let from = args[0].parse::<u64>().map_err(|_| ParseError::InvalidArgument {
+                    argument: "from",
+                    reason: "must be a number",
+                })?;
+
+                let to = args[1].parse::<u64>().map_err(|_| ParseError::InvalidArgument {
+                    argument: "to",
+                    reason: "must be a number",
+                })?;
+
+                Ok(Command::Diff { from, to })
+            }
+
             "clear" | "cls" => Ok(Command::Clear),
+            "shutdown" | "poweroff" => Ok(Command::Shutdown),
+            "reboot" => Ok(Command::Reboot),
             "exit" | "quit" => Ok(Command::Exit),
 
             _ => Ok(Command::Unknown {
@@ -192,7 +245,11 @@ impl Command {
             Command::Revoke { .. } => "revoke <slot> - Revoke capability",
             Command::Echo { .. } => "echo <text> - Echo text",
             Command::Time => "time - Show system uptime",
+            Command::StateAt { .. } => "at <seq> - Show kernel state at a commit sequence",
+            Command::Diff { .. } => "diff <from> <to> - Show changes between commit sequences",
             Command::Clear => "clear - Clear the screen",
+            Command::Shutdown => "shutdown - Stop services and power off",
+            Command::Reboot => "reboot - Stop services and reboot",
             Command::Exit => "exit - Exit the terminal",
             Command::Unknown { .. } => "Unknown command",
         }
@@ -215,6 +272,9 @@ mod tests {
         assert_eq!(Command::parse("cls"), Ok(Command::Clear));
         assert_eq!(Command::parse("exit"), Ok(Command::Exit));
         assert_eq!(Command::parse("quit"), Ok(Command::Exit));
+        assert_eq!(Command::parse("shutdown"), Ok(Command::Shutdown));
+        assert_eq!(Command::parse("poweroff"), Ok(Command::Shutdown));
+        assert_eq!(Command::parse("reboot"), Ok(Command::Reboot));
     }
 
     #[test]
@@ -256,6 +316,37 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_history() {
+        assert_eq!(Command::parse("at 12"), Ok(Command::StateAt { seq: 12 }));
+        assert_eq!(
+            Command::parse("diff 3 9"),
+            Ok(Command::Diff { from: 3, to: 9 })
+        );
+
+        assert_eq!(
+            Command::parse("at"),
+            Err(ParseError::MissingArgument {
+                command: "at",
+                argument: "seq"
+            })
+        );
+        assert_eq!(
+            Command::parse("diff 3"),
+            Err(ParseError::MissingArgument {
+                command: "diff",
+                argument: "from, to"
+            })
+        );
+        assert_eq!(
+            Command::parse("diff 3 x"),
+            Err(ParseError::InvalidArgument {
+                argument: "to",
+                reason: "must be a number"
+            })
+        );
+    }
+
     #[test]
     fn test_parse_grant() {
         let result = Command::parse("grant 1 42 rw");
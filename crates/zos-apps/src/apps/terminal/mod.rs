@@ -25,12 +25,13 @@ use alloc::format;
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 use crate::protocol::tags;
+use zos_ipc::debug;
 use crate::framework::{
     AppContext, AppError, AppManifest, ControlFlow, Message, ZeroApp,
     TERMINAL_MANIFEST,
 };
 use crate::syscall;
-use zos_process::{error, ObjectType, MSG_CAP_REVOKED};
+use zos_process::{error, ObjectType, ResourceQuota, MSG_CAP_REVOKED};
 
 /// Terminal application state
 #[derive(Default)]
@@ -237,7 +238,11 @@ impl TerminalApp {
             Command::Revoke { slot } => self.cmd_revoke(slot),
             Command::Echo { text } => self.cmd_echo(&text),
             Command::Time => self.cmd_time(),
+            Command::StateAt { seq } => self.cmd_state_at(seq),
+            Command::Diff { from, to } => self.cmd_diff(from, to),
             Command::Clear => self.cmd_clear(),
+            Command::Shutdown => self.cmd_power(false),
+            Command::Reboot => self.cmd_power(true),
             Command::Exit => self.cmd_exit(),
             Command::Unknown { cmd } if cmd.is_empty() => {}
             Command::Unknown { cmd } => {
@@ -265,13 +270,19 @@ impl TerminalApp {
         self.println("  time              - Show system uptime");
         self.println("  clear             - Clear the screen");
         self.println("  exit              - Exit the terminal");
+        self.println("  shutdown          - Stop services and power off");
+        self.println("  reboot            - Stop services and reboot");
+        self.println("");
+        self.println("History:");
+        self.println("  at <seq>          - Show kernel state at a commit");
+        self.println("  diff <from> <to>  - Show changes between commits");
     }
 
     fn cmd_ps(&mut self) {
         let procs = syscall::list_processes();
 
-        self.println("PID  STATE    NAME");
-        self.println("---  -----    ----");
+        self.println("PID  STATE    NAME             QUOTA");
+        self.println("---  -----    ----             -----");
 
         if procs.is_empty() {
             self.println("(no process data available)");
@@ -283,7 +294,13 @@ impl TerminalApp {
                     2 => "Zombie",
                     _ => "???",
                 };
-                self.println(&format!("{:<4} {:<8} {}", proc.pid, state, proc.name));
+                self.println(&format!(
+                    "{:<4} {:<8} {:<16} {}",
+                    proc.pid,
+                    state,
+                    proc.name,
+                    format_quota(&proc.quota)
+                ));
             }
         }
     }
@@ -369,10 +386,36 @@ impl TerminalApp {
         self.println(&format!("Uptime: {}.{:03}s", secs, ms));
     }
 
+    fn cmd_state_at(&mut self, seq: u64) {
+        // The supervisor rebuilds the state from the CommitLog and writes
+        // the result to our console
+        syscall::debug(&format!("{}{}", debug::HISTORY_STATE_AT, seq));
+    }
+
+    fn cmd_diff(&mut self, from: u64, to: u64) {
+        syscall::debug(&format!("{}{}:{}", debug::HISTORY_DIFF, from, to));
+    }
+
     fn cmd_clear(&mut self) {
         self.print("\x1B[2J\x1B[H");
     }
 
+    fn cmd_power(&mut self, reboot: bool) {
+        // Init stops the services and asks the kernel to cut power
+        match syscall::send(
+            syscall::INIT_ENDPOINT_SLOT,
+            syscall::MSG_SHUTDOWN_REQUEST,
+            &[reboot as u8],
+        ) {
+            Ok(()) => {
+                self.println(if reboot { "Requested reboot..." } else { "Requested power-off..." });
+            }
+            Err(e) => {
+                self.println(&format!("Error: could not reach init (error {})", e));
+            }
+        }
+    }
+
     fn cmd_exit(&mut self) {
         self.println("Goodbye!");
         syscall::exit(0);
@@ -429,3 +472,32 @@ impl ZeroApp for TerminalApp {
         syscall::debug("Terminal: shutting down");
     }
 }
+
+/// Summarize a quota for `ps`, e.g. "mem=16M ep=4 rate=100/s" ("-" if unlimited)
+fn format_quota(quota: &ResourceQuota) -> String {
+    if quota.is_unlimited() {
+        return "-".to_string();
+    }
+    let size = |bytes: u64| match bytes {
+        b if b >= 1 << 20 && b % (1 << 20) == 0 => format!("{}M", b >> 20),
+        b if b >= 1 << 10 && b % (1 << 10) == 0 => format!("{}K", b >> 10),
+        b => b.to_string(),
+    };
+    let mut parts = Vec::new();
+    if quota.max_memory != 0 {
+        parts.push(format!("mem={}", size(quota.max_memory)));
+    }
+    if quota.max_endpoints != 0 {
+        parts.push(format!("ep={}", quota.max_endpoints));
+    }
+    if quota.max_cap_slots != 0 {
+        parts.push(format!("caps={}", quota.max_cap_slots));
+    }
+    if quota.max_queued_bytes != 0 {
+        parts.push(format!("queue={}", size(quota.max_queued_bytes)));
+    }
+    if quota.max_syscalls_per_sec != 0 {
+        parts.push(format!("rate={}/s", quota.max_syscalls_per_sec));
+    }
+    parts.join(" ")
+}
@@ -19,7 +19,7 @@ use alloc::vec::Vec;
 use crate::types::{CapSlot, ObjectType, Permissions};
 
 /// A capability token - proof of authority to access a resource
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Capability {
     /// Unique capability ID
     pub id: u64,
@@ -33,6 +33,8 @@ pub struct Capability {
     pub generation: u32,
     /// Expiration timestamp (nanos since boot, 0 = never expires)
     pub expires_at: u64,
+    /// Badge stamped on messages sent through this capability (0 = unbadged)
+    pub badge: u64,
 }
 
 impl Capability {
@@ -184,6 +186,7 @@ mod tests {
             permissions: Permissions::full(),
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
         let slot = cspace.insert(cap);
 
@@ -219,6 +222,7 @@ mod tests {
             permissions: Permissions::full(),
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
         let slot = cspace.insert(cap);
 
@@ -243,6 +247,7 @@ mod tests {
             permissions: Permissions::read_only(),
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
         let slot = cspace.insert(cap);
 
@@ -261,6 +266,7 @@ mod tests {
             permissions: Permissions::full(),
             generation: 0,
             expires_at: 1000,
+            badge: 0,
         };
         let slot = cspace.insert(cap);
 
@@ -279,6 +285,7 @@ mod tests {
             permissions: Permissions::full(),
             generation: 0,
             expires_at: 0, // 0 = never expires
+            badge: 0,
         };
         let slot = cspace.insert(cap);
 
@@ -300,6 +307,7 @@ mod tests {
             permissions: Permissions::full(),
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
         let slot = cspace.insert(cap);
 
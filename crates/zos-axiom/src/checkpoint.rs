@@ -0,0 +1,192 @@
+//! Signed CommitLog Checkpoints
+//!
+//! A checkpoint binds a CommitLog head (sequence number + commit hash) to a
+//! signature from a machine key. Because every commit hash covers the previous
+//! one, a valid signature over the head attests to the entire chain up to that
+//! sequence number.
+//!
+//! # Trust Model
+//!
+//! The hash chain alone only proves internal consistency: anyone able to
+//! rewrite the log can also recompute every hash. Checkpoints let a third
+//! party holding the machine's public key detect such rewrites.
+//!
+//! Axiom has no crypto dependencies of its own for signing. The signing key
+//! lives in the identity layer, which implements [`CheckpointSigner`] and
+//! [`CheckpointVerifier`] for its machine keys.
+
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+use crate::types::CommitId;
+
+/// Domain separator for checkpoint signing messages.
+const CHECKPOINT_DOMAIN: &[u8] = b"zos-axiom/checkpoint/v1";
+
+/// Length of an Ed25519 public key in bytes.
+pub const CHECKPOINT_PUBLIC_KEY_LEN: usize = 32;
+
+/// Length of an Ed25519 signature in bytes.
+pub const CHECKPOINT_SIGNATURE_LEN: usize = 64;
+
+/// Produces checkpoint signatures.
+///
+/// Implemented by the identity layer over a machine signing key.
+pub trait CheckpointSigner {
+    /// Public key that verifies signatures from this signer.
+    fn public_key(&self) -> [u8; CHECKPOINT_PUBLIC_KEY_LEN];
+
+    /// Sign a checkpoint message.
+    fn sign(&self, message: &[u8]) -> [u8; CHECKPOINT_SIGNATURE_LEN];
+}
+
+/// Verifies checkpoint signatures.
+pub trait CheckpointVerifier {
+    /// Returns true if `signature` is a valid signature over `message`
+    /// by `public_key`.
+    fn verify(
+        &self,
+        public_key: &[u8; CHECKPOINT_PUBLIC_KEY_LEN],
+        message: &[u8],
+        signature: &[u8; CHECKPOINT_SIGNATURE_LEN],
+    ) -> bool;
+}
+
+/// A signed attestation of the CommitLog head at a given sequence number.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Sequence number of the attested commit
+    pub seq: u64,
+    /// Hash of the attested commit
+    pub head: CommitId,
+    /// Timestamp when the checkpoint was taken (nanos since boot)
+    pub timestamp: u64,
+    /// Public key of the signer
+    pub signer: [u8; CHECKPOINT_PUBLIC_KEY_LEN],
+    /// Signature over [`Checkpoint::signing_message`]
+    ///
+    /// Stored as a Vec since serde does not derive for `[u8; 64]`.
+    pub signature: Vec<u8>,
+}
+
+impl Checkpoint {
+    /// Create and sign a checkpoint for the given head.
+    pub fn sign<S: CheckpointSigner + ?Sized>(
+        seq: u64,
+        head: CommitId,
+        timestamp: u64,
+        signer: &S,
+    ) -> Self {
+        let message = Self::signing_message(seq, &head, timestamp);
+        Self {
+            seq,
+            head,
+            timestamp,
+            signer: signer.public_key(),
+            signature: signer.sign(&message).to_vec(),
+        }
+    }
+
+    /// Canonical message covered by the signature.
+    ///
+    /// Layout: `domain || seq (u64 LE) || head (32 bytes) || timestamp (u64 LE)`.
+    pub fn signing_message(seq: u64, head: &CommitId, timestamp: u64) -> Vec<u8> {
+        let mut message = Vec::with_capacity(CHECKPOINT_DOMAIN.len() + 8 + 32 + 8);
+        message.extend_from_slice(CHECKPOINT_DOMAIN);
+        message.extend_from_slice(&seq.to_le_bytes());
+        message.extend_from_slice(head);
+        message.extend_from_slice(&timestamp.to_le_bytes());
+        message
+    }
+
+    /// Verify the signature against an expected public key.
+    ///
+    /// The embedded `signer` field is not trusted on its own: a forged log
+    /// could carry checkpoints signed by an attacker's key.
+    pub fn verify<V: CheckpointVerifier + ?Sized>(
+        &self,
+        trusted_key: &[u8; CHECKPOINT_PUBLIC_KEY_LEN],
+        verifier: &V,
+    ) -> bool {
+        if &self.signer != trusted_key {
+            return false;
+        }
+        let signature: [u8; CHECKPOINT_SIGNATURE_LEN] = match self.signature.as_slice().try_into()
+        {
+            Ok(sig) => sig,
+            Err(_) => return false,
+        };
+        let message = Self::signing_message(self.seq, &self.head, self.timestamp);
+        verifier.verify(trusted_key, &message, &signature)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Toy signer for tests: "signature" is the message digest keyed by
+    /// the public key. Not cryptographically meaningful.
+    pub(crate) struct TestSigner(pub [u8; 32]);
+
+    pub(crate) struct TestVerifier;
+
+    fn toy_sign(key: &[u8; 32], message: &[u8]) -> [u8; 64] {
+        let mut sig = [0u8; 64];
+        for (i, byte) in message.iter().enumerate() {
+            sig[i % 64] ^= byte.wrapping_add(key[i % 32]);
+        }
+        sig
+    }
+
+    impl CheckpointSigner for TestSigner {
+        fn public_key(&self) -> [u8; 32] {
+            self.0
+        }
+
+        fn sign(&self, message: &[u8]) -> [u8; 64] {
+            toy_sign(&self.0, message)
+        }
+    }
+
+    impl CheckpointVerifier for TestVerifier {
+        fn verify(&self, public_key: &[u8; 32], message: &[u8], signature: &[u8; 64]) -> bool {
+            &toy_sign(public_key, message) == signature
+        }
+    }
+
+    #[test]
+    fn test_checkpoint_sign_and_verify() {
+        let signer = TestSigner([7u8; 32]);
+        let cp = Checkpoint::sign(10, [3u8; 32], 5000, &signer);
+
+        assert_eq!(cp.signature.len(), CHECKPOINT_SIGNATURE_LEN);
+        assert!(cp.verify(&[7u8; 32], &TestVerifier));
+    }
+
+    #[test]
+    fn test_checkpoint_rejects_untrusted_key() {
+        let signer = TestSigner([7u8; 32]);
+        let cp = Checkpoint::sign(10, [3u8; 32], 5000, &signer);
+
+        assert!(!cp.verify(&[8u8; 32], &TestVerifier));
+    }
+
+    #[test]
+    fn test_checkpoint_rejects_tampered_head() {
+        let signer = TestSigner([7u8; 32]);
+        let mut cp = Checkpoint::sign(10, [3u8; 32], 5000, &signer);
+        cp.head[0] ^= 0xFF;
+
+        assert!(!cp.verify(&[7u8; 32], &TestVerifier));
+    }
+
+    #[test]
+    fn test_checkpoint_rejects_truncated_signature() {
+        let signer = TestSigner([7u8; 32]);
+        let mut cp = Checkpoint::sign(10, [3u8; 32], 5000, &signer);
+        cp.signature.truncate(32);
+
+        assert!(!cp.verify(&[7u8; 32], &TestVerifier));
+    }
+}
@@ -0,0 +1,713 @@
+//! Binary Log File Format
+//!
+//! A stable, versioned encoding for CommitLog and SysLog exports. The same
+//! bytes are written by the x86_64 kernel (persisted CommitLog), by the
+//! browser supervisor (log exports) and read by host tooling, so the format
+//! must never depend on serde or on the target's pointer width.
+//!
+//! # File Format
+//!
+//! All integers are little-endian.
+//!
+//! ```text
+//! header:  magic "ZLOG" | version: u16 | kind: u8
+//! commits: state_hash: (u8 tag [+ [u8; 32]]) | checkpoints | commit*
+//! checkpoints: count: u32 | checkpoint*
+//! checkpoint: seq: u64 | head: [u8; 32] | timestamp: u64 | signer: [u8; 32] | sig_len: u32 | sig
+//! commit:  id: [u8; 32] | len: u32 | canonical: [u8; len]
+//! events:  event*
+//! event:   id: u64 | sender: u64 | timestamp: u64 | type: u8 | body
+//! request: syscall_num: u32 | args: [u32; 4]
+//! response: request_id: u64 | result: i64
+//! ```
+//!
+//! A commit record is its hash followed by [`Commit::canonical_bytes`], the
+//! exact bytes the hash covers, so a reader can verify the chain without
+//! re-encoding anything. The optional state hash is the `state_hash()` of
+//! the kernel after the last commit, used by `replay_and_verify`.
+//!
+//! Version 1 files have no checkpoint section; they still decode, with no
+//! checkpoints.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::checkpoint::Checkpoint;
+use crate::commitlog::{Commit, CommitType};
+use crate::syslog::{SysEvent, SysEventType};
+use crate::types::{CommitId, Permissions, ResourceQuota};
+
+/// Magic bytes at the start of a log file.
+const LOG_MAGIC: &[u8; 4] = b"ZLOG";
+
+/// Current log file format version.
+pub const LOG_VERSION: u16 = 2;
+
+/// First version whose CommitLog files carry checkpoints.
+const CHECKPOINTS_VERSION: u16 = 2;
+
+/// Event type tags in the SysLog encoding.
+const EVENT_REQUEST: u8 = 1;
+const EVENT_RESPONSE: u8 = 2;
+
+/// What a log file contains.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum LogKind {
+    /// CommitLog entries
+    Commits = 1,
+    /// SysLog events
+    Events = 2,
+}
+
+/// Errors decoding a log file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CodecError {
+    /// File does not start with the log magic
+    BadMagic,
+    /// File was written by an unknown format version
+    UnsupportedVersion(u16),
+    /// File holds a different kind of log than requested
+    WrongKind(u8),
+    /// File ends in the middle of a record
+    Truncated,
+    /// Unknown commit type discriminant
+    UnknownCommitType(u8),
+    /// Unknown SysLog event type tag
+    UnknownEventType(u8),
+    /// A commit record's length does not match its contents
+    BadRecordLength,
+    /// A string field is not valid UTF-8
+    InvalidUtf8,
+}
+
+/// A CommitLog export: commits in sequence order, plus the state hash
+/// after the last of them if the writer knew it, and the signed checkpoints
+/// taken over the log.
+#[derive(Clone, Debug, Default)]
+pub struct CommitLogFile {
+    /// Commits (oldest first)
+    pub commits: Vec<Commit>,
+    /// Kernel state hash after the last commit
+    pub state_hash: Option<[u8; 32]>,
+    /// Signed checkpoints (oldest first)
+    pub checkpoints: Vec<Checkpoint>,
+}
+
+impl CommitLogFile {
+    /// Encode the commits in the log file format.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = header(LogKind::Commits);
+        match &self.state_hash {
+            None => out.push(0),
+            Some(hash) => {
+                out.push(1);
+                out.extend_from_slice(hash);
+            }
+        }
+        out.extend_from_slice(&(self.checkpoints.len() as u32).to_le_bytes());
+        for checkpoint in &self.checkpoints {
+            out.extend_from_slice(&checkpoint.seq.to_le_bytes());
+            out.extend_from_slice(&checkpoint.head);
+            out.extend_from_slice(&checkpoint.timestamp.to_le_bytes());
+            out.extend_from_slice(&checkpoint.signer);
+            out.extend_from_slice(&(checkpoint.signature.len() as u32).to_le_bytes());
+            out.extend_from_slice(&checkpoint.signature);
+        }
+        for commit in &self.commits {
+            let canonical = commit.canonical_bytes();
+            out.extend_from_slice(&commit.id);
+            out.extend_from_slice(&(canonical.len() as u32).to_le_bytes());
+            out.extend_from_slice(&canonical);
+        }
+        out
+    }
+
+    /// Decode a CommitLog file.
+    ///
+    /// Commit hashes and checkpoint signatures are taken as stored; use
+    /// `CommitLog::verify_chain` and `CommitLog::verify_checkpoints_in` to
+    /// check them.
+    pub fn decode(bytes: &[u8]) -> Result<Self, CodecError> {
+        let mut reader = Reader { bytes };
+        let version = reader.header(LogKind::Commits)?;
+
+        let state_hash = match reader.u8()? {
+            0 => None,
+            _ => Some(reader.array()?),
+        };
+
+        let mut checkpoints = Vec::new();
+        if version >= CHECKPOINTS_VERSION {
+            for _ in 0..reader.u32()? {
+                checkpoints.push(Checkpoint {
+                    seq: reader.u64()?,
+                    head: reader.array()?,
+                    timestamp: reader.u64()?,
+                    signer: reader.array()?,
+                    signature: reader.bytes()?,
+                });
+            }
+        }
+
+        let mut commits = Vec::new();
+        while !reader.bytes.is_empty() {
+            let id: CommitId = reader.array()?;
+            let len = reader.u32()? as usize;
+            let mut record = Reader {
+                bytes: reader.take(len)?,
+            };
+            let commit = record.commit(id)?;
+            if !record.bytes.is_empty() {
+                return Err(CodecError::BadRecordLength);
+            }
+            commits.push(commit);
+        }
+        Ok(Self {
+            commits,
+            state_hash,
+            checkpoints,
+        })
+    }
+}
+
+/// A SysLog export: events in ID order.
+#[derive(Clone, Debug, Default)]
+pub struct SysLogFile {
+    /// Events (oldest first)
+    pub events: Vec<SysEvent>,
+}
+
+impl SysLogFile {
+    /// Encode the events in the log file format.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = header(LogKind::Events);
+        for event in &self.events {
+            out.extend_from_slice(&event.id.to_le_bytes());
+            out.extend_from_slice(&event.sender.to_le_bytes());
+            out.extend_from_slice(&event.timestamp.to_le_bytes());
+            match &event.event_type {
+                SysEventType::Request { syscall_num, args } => {
+                    out.push(EVENT_REQUEST);
+                    out.extend_from_slice(&syscall_num.to_le_bytes());
+                    for arg in args {
+                        out.extend_from_slice(&arg.to_le_bytes());
+                    }
+                }
+                SysEventType::Response { request_id, result } => {
+                    out.push(EVENT_RESPONSE);
+                    out.extend_from_slice(&request_id.to_le_bytes());
+                    out.extend_from_slice(&result.to_le_bytes());
+                }
+            }
+        }
+        out
+    }
+
+    /// Decode a SysLog file.
+    pub fn decode(bytes: &[u8]) -> Result<Self, CodecError> {
+        let mut reader = Reader { bytes };
+        reader.header(LogKind::Events)?;
+
+        let mut events = Vec::new();
+        while !reader.bytes.is_empty() {
+            let id = reader.u64()?;
+            let sender = reader.u64()?;
+            let timestamp = reader.u64()?;
+            let event_type = match reader.u8()? {
+                EVENT_REQUEST => SysEventType::Request {
+                    syscall_num: reader.u32()?,
+                    args: [reader.u32()?, reader.u32()?, reader.u32()?, reader.u32()?],
+                },
+                EVENT_RESPONSE => SysEventType::Response {
+                    request_id: reader.u64()?,
+                    result: i64::from_le_bytes(reader.array()?),
+                },
+                other => return Err(CodecError::UnknownEventType(other)),
+            };
+            events.push(SysEvent {
+                id,
+                sender,
+                timestamp,
+                event_type,
+            });
+        }
+        Ok(Self { events })
+    }
+}
+
+/// Read the kind of a log file from its header.
+pub fn log_kind(bytes: &[u8]) -> Result<LogKind, CodecError> {
+    let mut reader = Reader { bytes };
+    reader.version()?;
+    match reader.u8()? {
+        1 => Ok(LogKind::Commits),
+        2 => Ok(LogKind::Events),
+        other => Err(CodecError::WrongKind(other)),
+    }
+}
+
+fn header(kind: LogKind) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(LOG_MAGIC);
+    out.extend_from_slice(&LOG_VERSION.to_le_bytes());
+    out.push(kind as u8);
+    out
+}
+
+/// Cursor over an encoded log.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], CodecError> {
+        if self.bytes.len() < len {
+            return Err(CodecError::Truncated);
+        }
+        let (head, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], CodecError> {
+        let mut out = [0u8; N];
+        out.copy_from_slice(self.take(N)?);
+        Ok(out)
+    }
+
+    fn u8(&mut self) -> Result<u8, CodecError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, CodecError> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn u64(&mut self) -> Result<u64, CodecError> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    fn bytes(&mut self) -> Result<Vec<u8>, CodecError> {
+        let len = self.u32()? as usize;
+        Ok(self.take(len)?.to_vec())
+    }
+
+    fn string(&mut self) -> Result<String, CodecError> {
+        String::from_utf8(self.bytes()?).map_err(|_| CodecError::InvalidUtf8)
+    }
+
+    fn version(&mut self) -> Result<u16, CodecError> {
+        if self.take(4)? != LOG_MAGIC {
+            return Err(CodecError::BadMagic);
+        }
+        let version = u16::from_le_bytes(self.array()?);
+        if version == 0 || version > LOG_VERSION {
+            return Err(CodecError::UnsupportedVersion(version));
+        }
+        Ok(version)
+    }
+
+    fn header(&mut self, kind: LogKind) -> Result<u16, CodecError> {
+        let version = self.version()?;
+        let found = self.u8()?;
+        if found != kind as u8 {
+            return Err(CodecError::WrongKind(found));
+        }
+        Ok(version)
+    }
+
+    /// Decode [`Commit::canonical_bytes`].
+    fn commit(&mut self, id: CommitId) -> Result<Commit, CodecError> {
+        let prev_commit = self.array()?;
+        let seq = self.u64()?;
+        let timestamp = self.u64()?;
+        let caused_by = match self.u8()? {
+            0 => None,
+            _ => Some(self.u64()?),
+        };
+        let commit_type = self.commit_type()?;
+        Ok(Commit {
+            id,
+            prev_commit,
+            seq,
+            timestamp,
+            commit_type,
+            caused_by,
+        })
+    }
+
+    /// Decode [`CommitType::encode_canonical`].
+    fn commit_type(&mut self) -> Result<CommitType, CodecError> {
+        Ok(match self.u8()? {
+            0 => CommitType::Genesis,
+            1 => CommitType::ProcessCreated {
+                pid: self.u64()?,
+                parent: self.u64()?,
+                name: self.string()?,
+            },
+            2 => CommitType::ProcessExited {
+                pid: self.u64()?,
+                code: i32::from_le_bytes(self.array()?),
+            },
+            3 => CommitType::ProcessFaulted {
+                pid: self.u64()?,
+                reason: self.u32()?,
+                description: self.string()?,
+            },
+            4 => CommitType::CapInserted {
+                pid: self.u64()?,
+                slot: self.u32()?,
+                cap_id: self.u64()?,
+                object_type: self.u8()?,
+                object_id: self.u64()?,
+                perms: self.u8()?,
+            },
+            5 => CommitType::CapRemoved {
+                pid: self.u64()?,
+                slot: self.u32()?,
+            },
+            6 => CommitType::CapGranted {
+                from_pid: self.u64()?,
+                to_pid: self.u64()?,
+                from_slot: self.u32()?,
+                to_slot: self.u32()?,
+                new_cap_id: self.u64()?,
+                perms: Permissions::from_byte(self.u8()?),
+            },
+            7 => CommitType::EndpointCreated {
+                id: self.u64()?,
+                owner: self.u64()?,
+            },
+            8 => CommitType::EndpointDestroyed { id: self.u64()? },
+            9 => CommitType::MessageSent {
+                from_pid: self.u64()?,
+                to_endpoint: self.u64()?,
+                tag: self.u32()?,
+                size: self.u64()? as usize,
+            },
+            10 => CommitType::Snapshot {
+                state: self.bytes()?,
+                state_hash: self.array()?,
+            },
+            11 => CommitType::QuotaSet {
+                pid: self.u64()?,
+                by: self.u64()?,
+                quota: ResourceQuota::from_bytes(self.take(ResourceQuota::ENCODED_SIZE)?)
+                    .ok_or(CodecError::Truncated)?,
+            },
+            12 => CommitType::QuotaExceeded {
+                pid: self.u64()?,
+                resource: self.u8()?,
+            },
+            13 => CommitType::CapBadged {
+                pid: self.u64()?,
+                slot: self.u32()?,
+                badge: self.u64()?,
+            },
+            14 => CommitType::NotificationCreated {
+                id: self.u64()?,
+                owner: self.u64()?,
+            },
+            15 => CommitType::NotificationSignaled {
+                from: self.u64()?,
+                id: self.u64()?,
+                bits: self.u64()?,
+            },
+            16 => CommitType::NotificationTaken {
+                pid: self.u64()?,
+                id: self.u64()?,
+                bits: self.u64()?,
+            },
+            17 => CommitType::Replied {
+                from: self.u64()?,
+                to: self.u64()?,
+                tag: self.u32()?,
+                size: self.u64()? as usize,
+            },
+            18 => CommitType::CallAborted { caller: self.u64()? },
+            19 => CommitType::IrqBound {
+                irq: self.u8()?,
+                pid: self.u64()?,
+                target_type: self.u8()?,
+                target_id: self.u64()?,
+                bits: self.u64()?,
+            },
+            20 => CommitType::IrqUnbound { irq: self.u8()? },
+            21 => CommitType::IrqRaised { irq: self.u8()? },
+            22 => CommitType::IrqAcked { irq: self.u8()? },
+            23 => CommitType::RegionCreated {
+                id: self.u64()?,
+                owner: self.u64()?,
+                size: self.u64()? as usize,
+            },
+            24 => CommitType::RegionMapped {
+                pid: self.u64()?,
+                id: self.u64()?,
+                writable: self.u8()? != 0,
+                window: self.u32()?,
+            },
+            25 => CommitType::RegionUnmapped {
+                pid: self.u64()?,
+                id: self.u64()?,
+            },
+            26 => CommitType::RegionDestroyed { id: self.u64()? },
+            27 => CommitType::CapExpiry {
+                pid: self.u64()?,
+                slot: self.u32()?,
+                expires_at: self.u64()?,
+            },
+            other => return Err(CodecError::UnknownCommitType(other)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkpoint::tests::{TestSigner, TestVerifier};
+    use crate::commitlog::CommitLog;
+    use crate::syslog::SysLog;
+    use alloc::boxed::Box;
+    use alloc::vec;
+
+    fn sample_log() -> CommitLog {
+        let mut log = CommitLog::new(0);
+        let types = vec![
+            CommitType::ProcessCreated {
+                pid: 1,
+                parent: 0,
+                name: "init".into(),
+            },
+            CommitType::EndpointCreated { id: 1, owner: 1 },
+            CommitType::CapInserted {
+                pid: 1,
+                slot: 0,
+                cap_id: 1,
+                object_type: 1,
+                object_id: 1,
+                perms: 0x07,
+            },
+            CommitType::CapGranted {
+                from_pid: 1,
+                to_pid: 2,
+                from_slot: 0,
+                to_slot: 3,
+                new_cap_id: 2,
+                perms: Permissions::read_only(),
+            },
+            CommitType::MessageSent {
+                from_pid: 1,
+                to_endpoint: 1,
+                tag: 0x40,
+                size: 12,
+            },
+            CommitType::CapRemoved { pid: 2, slot: 3 },
+            CommitType::Snapshot {
+                state: vec![1, 2, 3],
+                state_hash: [9; 32],
+            },
+            CommitType::ProcessFaulted {
+                pid: 1,
+                reason: 2,
+                description: "bad syscall".into(),
+            },
+            CommitType::EndpointDestroyed { id: 1 },
+            CommitType::QuotaSet {
+                pid: 2,
+                by: 1,
+                quota: ResourceQuota {
+                    max_endpoints: 4,
+                    max_syscalls_per_sec: 100,
+                    ..Default::default()
+                },
+            },
+            CommitType::QuotaExceeded { pid: 2, resource: 2 },
+            CommitType::CapBadged {
+                pid: 2,
+                slot: 3,
+                badge: 0xfeed,
+            },
+            CommitType::CapExpiry {
+                pid: 2,
+                slot: 3,
+                expires_at: 60_000,
+            },
+            CommitType::NotificationCreated { id: 1, owner: 2 },
+            CommitType::NotificationSignaled {
+                from: 3,
+                id: 1,
+                bits: 0b101,
+            },
+            CommitType::NotificationTaken {
+                pid: 2,
+                id: 1,
+                bits: 0b101,
+            },
+            CommitType::Replied {
+                from: 2,
+                to: 1,
+                tag: 0x41,
+                size: 4,
+            },
+            CommitType::CallAborted { caller: 1 },
+            CommitType::IrqBound {
+                irq: 4,
+                pid: 2,
+                target_type: 12,
+                target_id: 1,
+                bits: 0b10,
+            },
+            CommitType::IrqRaised { irq: 4 },
+            CommitType::IrqAcked { irq: 4 },
+            CommitType::IrqUnbound { irq: 4 },
+            CommitType::RegionCreated {
+                id: 1,
+                owner: 2,
+                size: 8192,
+            },
+            CommitType::RegionMapped {
+                pid: 2,
+                id: 1,
+                writable: true,
+                window: 0x10000,
+            },
+            CommitType::RegionUnmapped { pid: 2, id: 1 },
+            CommitType::RegionDestroyed { id: 1 },
+            CommitType::ProcessExited { pid: 1, code: -1 },
+        ];
+        for (i, commit_type) in types.into_iter().enumerate() {
+            log.append(commit_type, Some(i as u64), i as u64 * 10);
+        }
+        log
+    }
+
+    #[test]
+    fn test_commitlog_roundtrip() {
+        let log = sample_log();
+        let file = CommitLogFile {
+            commits: log.commits().to_vec(),
+            state_hash: Some([5; 32]),
+            checkpoints: Vec::new(),
+        };
+        let bytes = file.encode();
+        assert_eq!(log_kind(&bytes), Ok(LogKind::Commits));
+
+        let decoded = CommitLogFile::decode(&bytes).unwrap();
+        assert_eq!(decoded.state_hash, Some([5; 32]));
+        assert_eq!(decoded.commits.len(), log.len());
+        assert!(CommitLog::verify_chain(&decoded.commits));
+        for (a, b) in decoded.commits.iter().zip(log.commits()) {
+            assert_eq!(a.id, b.id);
+            assert_eq!(a.canonical_bytes(), b.canonical_bytes());
+        }
+    }
+
+    #[test]
+    fn test_commitlog_checkpoints_roundtrip() {
+        let mut log = CommitLog::new(0);
+        log.set_checkpoint_signer(Box::new(TestSigner([4u8; 32])), 2);
+        for i in 1..=5 {
+            log.append(CommitType::EndpointCreated { id: i, owner: 1 }, None, i * 10);
+        }
+        let file = CommitLogFile {
+            commits: log.commits().to_vec(),
+            state_hash: None,
+            checkpoints: log.checkpoints().to_vec(),
+        };
+
+        let decoded = CommitLogFile::decode(&file.encode()).unwrap();
+        assert_eq!(decoded.checkpoints, file.checkpoints);
+        assert!(CommitLog::verify_checkpoints_in(
+            &decoded.commits,
+            &decoded.checkpoints,
+            &[4u8; 32],
+            &TestVerifier
+        ));
+    }
+
+    #[test]
+    fn test_decode_version_1_commitlog() {
+        let log = sample_log();
+        let mut bytes = CommitLogFile {
+            commits: log.commits().to_vec(),
+            state_hash: None,
+            checkpoints: Vec::new(),
+        }
+        .encode();
+        // Version 1 had no checkpoint count after the state hash
+        bytes[4] = 1;
+        bytes.drain(8..12);
+
+        let decoded = CommitLogFile::decode(&bytes).unwrap();
+        assert!(decoded.checkpoints.is_empty());
+        assert_eq!(decoded.commits.len(), log.len());
+    }
+
+    #[test]
+    fn test_syslog_roundtrip() {
+        let mut syslog = SysLog::new();
+        let req = syslog.log_request(3, 0x40, [1, 2, 3, 4], 100);
+        syslog.log_response(3, req, -5, 200);
+        let file = SysLogFile {
+            events: syslog.events().to_vec(),
+        };
+        let bytes = file.encode();
+        assert_eq!(log_kind(&bytes), Ok(LogKind::Events));
+
+        let decoded = SysLogFile::decode(&bytes).unwrap();
+        assert_eq!(decoded.events.len(), 2);
+        assert!(matches!(
+            decoded.events[0].event_type,
+            SysEventType::Request {
+                syscall_num: 0x40,
+                args: [1, 2, 3, 4]
+            }
+        ));
+        assert!(matches!(
+            decoded.events[1].event_type,
+            SysEventType::Response {
+                request_id: 0,
+                result: -5
+            }
+        ));
+        assert_eq!(decoded.events[1].timestamp, 200);
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let log = sample_log();
+        let bytes = CommitLogFile {
+            commits: log.commits().to_vec(),
+            state_hash: None,
+            checkpoints: Vec::new(),
+        }
+        .encode();
+        assert_eq!(
+            CommitLogFile::decode(&bytes[..bytes.len() - 1]).unwrap_err(),
+            CodecError::Truncated
+        );
+        assert_eq!(
+            SysLogFile::decode(&bytes).unwrap_err(),
+            CodecError::WrongKind(1)
+        );
+        assert_eq!(log_kind(b"ZLOX\x01\x00\x01"), Err(CodecError::BadMagic));
+        assert_eq!(
+            log_kind(b"ZLOG\x03\x00\x01"),
+            Err(CodecError::UnsupportedVersion(3))
+        );
+
+        // A record whose length covers more than its commit
+        let mut padded = bytes[..12].to_vec();
+        let genesis = &log.commits()[0];
+        let mut canonical = genesis.canonical_bytes();
+        canonical.push(0);
+        padded.extend_from_slice(&genesis.id);
+        padded.extend_from_slice(&(canonical.len() as u32).to_le_bytes());
+        padded.extend_from_slice(&canonical);
+        assert_eq!(
+            CommitLogFile::decode(&padded).unwrap_err(),
+            CodecError::BadRecordLength
+        );
+    }
+}
@@ -1,20 +1,36 @@
 //! Commit Log for Deterministic Replay
 //!
 //! Records state mutations as commits for deterministic replay.
-//! Each commit links to the previous via hash chain.
+//! Each commit links to the previous via a SHA-256 hash chain, and the
+//! log can periodically emit signed [`Checkpoint`]s over its head.
 //!
 //! # Core Invariant
 //!
 //! > `reduce(genesis, commits) -> state`
 //!
 //! Replaying the same CommitLog always produces the same state.
+//!
+//! # Snapshots
+//!
+//! The in-memory log is bounded. To keep replay possible once old commits
+//! are dropped, the kernel periodically appends a [`CommitType::Snapshot`]
+//! holding its serialized state. Replay starts from the newest snapshot, and
+//! commits are only ever trimmed from *behind* a snapshot.
 
+use alloc::boxed::Box;
 use alloc::string::String;
 use alloc::vec;
 use alloc::vec::Vec;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::checkpoint::{Checkpoint, CheckpointSigner, CheckpointVerifier};
+use crate::types::{CapSlot, CommitId, EndpointId, EventId, Permissions, ProcessId, ResourceQuota};
 
-use crate::types::{CapSlot, CommitId, EndpointId, EventId, Permissions, ProcessId};
+/// Domain separator for commit hashes.
+///
+/// Bump the version suffix whenever the canonical encoding changes.
+const COMMIT_HASH_DOMAIN: &[u8] = b"zos-axiom/commit/v1";
 
 /// A state mutation record.
 ///
@@ -83,6 +99,33 @@ pub enum CommitType {
         new_cap_id: u64,
         perms: Permissions,
     },
+    /// Badge set on the capability just inserted at `slot`
+    CapBadged {
+        pid: ProcessId,
+        slot: CapSlot,
+        badge: u64,
+    },
+    /// Expiry (nanos since boot) set on the capability just inserted at `slot`
+    CapExpiry {
+        pid: ProcessId,
+        slot: CapSlot,
+        expires_at: u64,
+    },
+
+    // === Resource Quotas ===
+    /// Resource quota set on a process
+    QuotaSet {
+        pid: ProcessId,
+        /// Process that set the quota
+        by: ProcessId,
+        quota: ResourceQuota,
+    },
+    /// Syscall refused because it would exceed a quota
+    QuotaExceeded {
+        pid: ProcessId,
+        /// `QuotaResource` discriminant
+        resource: u8,
+    },
 
     // === Endpoint Lifecycle ===
     /// Endpoint created
@@ -90,6 +133,22 @@ pub enum CommitType {
     /// Endpoint destroyed
     EndpointDestroyed { id: EndpointId },
 
+    // === Notifications ===
+    /// Notification object created
+    NotificationCreated { id: u64, owner: ProcessId },
+    /// Bits OR-ed into a notification's signal word
+    NotificationSignaled {
+        from: ProcessId,
+        id: u64,
+        bits: u64,
+    },
+    /// Pending bits consumed by a wait or poll
+    NotificationTaken {
+        pid: ProcessId,
+        id: u64,
+        bits: u64,
+    },
+
     // === IPC Events ===
     /// Message sent via IPC (optional - for full audit trail)
     /// Note: Message content is NOT stored for privacy/size reasons.
@@ -101,27 +160,361 @@ pub enum CommitType {
         /// Size of the message data in bytes
         size: usize,
     },
+    /// Call answered; the server's reply capability was consumed
+    Replied {
+        from: ProcessId,
+        to: ProcessId,
+        tag: u32,
+        /// Size of the reply data in bytes
+        size: usize,
+    },
+    /// Call ended without a reply because its server went away
+    CallAborted { caller: ProcessId },
+
+    // === Device Access ===
+    /// IRQ line bound to a driver's notification or endpoint
+    IrqBound {
+        irq: u8,
+        pid: ProcessId,
+        /// `ObjectType` of the delivery target
+        target_type: u8,
+        target_id: u64,
+        /// Bits OR-ed into a notification target (0 for an endpoint)
+        bits: u64,
+    },
+    /// IRQ line no longer delivered to user space
+    IrqUnbound { irq: u8 },
+    /// Interrupt delivered; the line is masked until acknowledged
+    IrqRaised { irq: u8 },
+    /// Driver acknowledged the interrupt, unmasking the line
+    IrqAcked { irq: u8 },
+
+    // === Shared Memory ===
+    /// Shared memory region created (size rounded up to whole pages)
+    RegionCreated {
+        id: u64,
+        owner: ProcessId,
+        size: usize,
+    },
+    /// Region mapped into a process at `window` in its linear memory
+    RegionMapped {
+        pid: ProcessId,
+        id: u64,
+        writable: bool,
+        window: u32,
+    },
+    /// Region unmapped from a process
+    RegionUnmapped { pid: ProcessId, id: u64 },
+    /// Region freed once no capability refers to it
+    RegionDestroyed { id: u64 },
+
+    // === Snapshots ===
+    /// Full kernel state at this point in the log.
+    ///
+    /// Replay restores `state` instead of re-applying earlier commits.
+    /// The encoding of `state` is owned by the [`Replayable`] implementor;
+    /// `state_hash` is its `state_hash()` at snapshot time and is checked
+    /// after restoring.
+    ///
+    /// [`Replayable`]: crate::replay::Replayable
+    Snapshot {
+        /// Serialized kernel state
+        state: Vec<u8>,
+        /// StateHasher digest of the state
+        state_hash: [u8; 32],
+    },
+}
+
+impl Commit {
+    /// Canonical byte encoding of everything the commit hash covers.
+    ///
+    /// Layout: `prev_commit (32) || seq (u64) || timestamp (u64) ||
+    /// caused_by (u8 tag [+ u64]) || commit_type`. All integers are
+    /// little-endian; see [`CommitType::encode_canonical`] for the payload.
+    pub fn canonical_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(96);
+        out.extend_from_slice(&self.prev_commit);
+        out.extend_from_slice(&self.seq.to_le_bytes());
+        out.extend_from_slice(&self.timestamp.to_le_bytes());
+        match self.caused_by {
+            None => out.push(0),
+            Some(event_id) => {
+                out.push(1);
+                out.extend_from_slice(&event_id.to_le_bytes());
+            }
+        }
+        self.commit_type.encode_canonical(&mut out);
+        out
+    }
+}
+
+impl CommitType {
+    /// Stable discriminant byte used in the canonical encoding.
+    ///
+    /// These values are part of the hash format: never renumber them.
+    pub fn discriminant(&self) -> u8 {
+        match self {
+            CommitType::Genesis => 0,
+            CommitType::ProcessCreated { .. } => 1,
+            CommitType::ProcessExited { .. } => 2,
+            CommitType::ProcessFaulted { .. } => 3,
+            CommitType::CapInserted { .. } => 4,
+            CommitType::CapRemoved { .. } => 5,
+            CommitType::CapGranted { .. } => 6,
+            CommitType::EndpointCreated { .. } => 7,
+            CommitType::EndpointDestroyed { .. } => 8,
+            CommitType::MessageSent { .. } => 9,
+            CommitType::Snapshot { .. } => 10,
+            CommitType::QuotaSet { .. } => 11,
+            CommitType::QuotaExceeded { .. } => 12,
+            CommitType::CapBadged { .. } => 13,
+            CommitType::NotificationCreated { .. } => 14,
+            CommitType::NotificationSignaled { .. } => 15,
+            CommitType::NotificationTaken { .. } => 16,
+            CommitType::Replied { .. } => 17,
+            CommitType::CallAborted { .. } => 18,
+            CommitType::IrqBound { .. } => 19,
+            CommitType::IrqUnbound { .. } => 20,
+            CommitType::IrqRaised { .. } => 21,
+            CommitType::IrqAcked { .. } => 22,
+            CommitType::RegionCreated { .. } => 23,
+            CommitType::RegionMapped { .. } => 24,
+            CommitType::RegionUnmapped { .. } => 25,
+            CommitType::RegionDestroyed { .. } => 26,
+            CommitType::CapExpiry { .. } => 27,
+        }
+    }
+
+    /// Append the canonical encoding of this commit type to `out`.
+    ///
+    /// The discriminant byte is followed by every field in declaration
+    /// order. Integers are little-endian, `usize` is widened to `u64`, and
+    /// strings are prefixed with their byte length as `u32` so that
+    /// adjacent fields cannot be shifted into one another.
+    pub fn encode_canonical(&self, out: &mut Vec<u8>) {
+        out.push(self.discriminant());
+        match self {
+            CommitType::Genesis => {}
+            CommitType::ProcessCreated { pid, parent, name } => {
+                out.extend_from_slice(&pid.to_le_bytes());
+                out.extend_from_slice(&parent.to_le_bytes());
+                encode_str(out, name);
+            }
+            CommitType::ProcessExited { pid, code } => {
+                out.extend_from_slice(&pid.to_le_bytes());
+                out.extend_from_slice(&code.to_le_bytes());
+            }
+            CommitType::ProcessFaulted {
+                pid,
+                reason,
+                description,
+            } => {
+                out.extend_from_slice(&pid.to_le_bytes());
+                out.extend_from_slice(&reason.to_le_bytes());
+                encode_str(out, description);
+            }
+            CommitType::CapInserted {
+                pid,
+                slot,
+                cap_id,
+                object_type,
+                object_id,
+                perms,
+            } => {
+                out.extend_from_slice(&pid.to_le_bytes());
+                out.extend_from_slice(&slot.to_le_bytes());
+                out.extend_from_slice(&cap_id.to_le_bytes());
+                out.push(*object_type);
+                out.extend_from_slice(&object_id.to_le_bytes());
+                out.push(*perms);
+            }
+            CommitType::CapRemoved { pid, slot } => {
+                out.extend_from_slice(&pid.to_le_bytes());
+                out.extend_from_slice(&slot.to_le_bytes());
+            }
+            CommitType::CapGranted {
+                from_pid,
+                to_pid,
+                from_slot,
+                to_slot,
+                new_cap_id,
+                perms,
+            } => {
+                out.extend_from_slice(&from_pid.to_le_bytes());
+                out.extend_from_slice(&to_pid.to_le_bytes());
+                out.extend_from_slice(&from_slot.to_le_bytes());
+                out.extend_from_slice(&to_slot.to_le_bytes());
+                out.extend_from_slice(&new_cap_id.to_le_bytes());
+                out.push(perms.to_byte());
+            }
+            CommitType::EndpointCreated { id, owner } => {
+                out.extend_from_slice(&id.to_le_bytes());
+                out.extend_from_slice(&owner.to_le_bytes());
+            }
+            CommitType::EndpointDestroyed { id } => {
+                out.extend_from_slice(&id.to_le_bytes());
+            }
+            CommitType::MessageSent {
+                from_pid,
+                to_endpoint,
+                tag,
+                size,
+            } => {
+                out.extend_from_slice(&from_pid.to_le_bytes());
+                out.extend_from_slice(&to_endpoint.to_le_bytes());
+                out.extend_from_slice(&tag.to_le_bytes());
+                out.extend_from_slice(&(*size as u64).to_le_bytes());
+            }
+            CommitType::Snapshot { state, state_hash } => {
+                encode_bytes(out, state);
+                out.extend_from_slice(state_hash);
+            }
+            CommitType::QuotaSet { pid, by, quota } => {
+                out.extend_from_slice(&pid.to_le_bytes());
+                out.extend_from_slice(&by.to_le_bytes());
+                out.extend_from_slice(&quota.to_bytes());
+            }
+            CommitType::QuotaExceeded { pid, resource } => {
+                out.extend_from_slice(&pid.to_le_bytes());
+                out.push(*resource);
+            }
+            CommitType::CapBadged { pid, slot, badge } => {
+                out.extend_from_slice(&pid.to_le_bytes());
+                out.extend_from_slice(&slot.to_le_bytes());
+                out.extend_from_slice(&badge.to_le_bytes());
+            }
+            CommitType::NotificationCreated { id, owner } => {
+                out.extend_from_slice(&id.to_le_bytes());
+                out.extend_from_slice(&owner.to_le_bytes());
+            }
+            CommitType::NotificationSignaled { from, id, bits } => {
+                out.extend_from_slice(&from.to_le_bytes());
+                out.extend_from_slice(&id.to_le_bytes());
+                out.extend_from_slice(&bits.to_le_bytes());
+            }
+            CommitType::NotificationTaken { pid, id, bits } => {
+                out.extend_from_slice(&pid.to_le_bytes());
+                out.extend_from_slice(&id.to_le_bytes());
+                out.extend_from_slice(&bits.to_le_bytes());
+            }
+            CommitType::Replied {
+                from,
+                to,
+                tag,
+                size,
+            } => {
+                out.extend_from_slice(&from.to_le_bytes());
+                out.extend_from_slice(&to.to_le_bytes());
+                out.extend_from_slice(&tag.to_le_bytes());
+                out.extend_from_slice(&(*size as u64).to_le_bytes());
+            }
+            CommitType::CallAborted { caller } => {
+                out.extend_from_slice(&caller.to_le_bytes());
+            }
+            CommitType::IrqBound {
+                irq,
+                pid,
+                target_type,
+                target_id,
+                bits,
+            } => {
+                out.push(*irq);
+                out.extend_from_slice(&pid.to_le_bytes());
+                out.push(*target_type);
+                out.extend_from_slice(&target_id.to_le_bytes());
+                out.extend_from_slice(&bits.to_le_bytes());
+            }
+            CommitType::IrqUnbound { irq }
+            | CommitType::IrqRaised { irq }
+            | CommitType::IrqAcked { irq } => {
+                out.push(*irq);
+            }
+            CommitType::RegionCreated { id, owner, size } => {
+                out.extend_from_slice(&id.to_le_bytes());
+                out.extend_from_slice(&owner.to_le_bytes());
+                out.extend_from_slice(&(*size as u64).to_le_bytes());
+            }
+            CommitType::RegionMapped {
+                pid,
+                id,
+                writable,
+                window,
+            } => {
+                out.extend_from_slice(&pid.to_le_bytes());
+                out.extend_from_slice(&id.to_le_bytes());
+                out.push(*writable as u8);
+                out.extend_from_slice(&window.to_le_bytes());
+            }
+            CommitType::RegionUnmapped { pid, id } => {
+                out.extend_from_slice(&pid.to_le_bytes());
+                out.extend_from_slice(&id.to_le_bytes());
+            }
+            CommitType::RegionDestroyed { id } => {
+                out.extend_from_slice(&id.to_le_bytes());
+            }
+            CommitType::CapExpiry {
+                pid,
+                slot,
+                expires_at,
+            } => {
+                out.extend_from_slice(&pid.to_le_bytes());
+                out.extend_from_slice(&slot.to_le_bytes());
+                out.extend_from_slice(&expires_at.to_le_bytes());
+            }
+        }
+    }
+}
+
+/// Encode a length-prefixed UTF-8 string.
+fn encode_str(out: &mut Vec<u8>, s: &str) {
+    encode_bytes(out, s.as_bytes());
+}
+
+/// Encode a length-prefixed byte string.
+fn encode_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    out.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    out.extend_from_slice(bytes);
 }
 
-/// Maximum number of commits to keep in memory
-const MAX_COMMITLOG_ENTRIES: usize = 100000;
+/// Default maximum number of commits to keep in memory
+pub const MAX_COMMITLOG_ENTRIES: usize = 100000;
+
+/// Maximum number of checkpoints to keep in memory
+const MAX_CHECKPOINTS: usize = 1024;
 
 /// Commit log for deterministic replay.
 ///
 /// All state-changing operations are recorded as commits.
-/// Replaying commits from genesis reconstructs the exact state.
+/// Replaying commits from genesis (or from the newest snapshot)
+/// reconstructs the exact state.
 pub struct CommitLog {
     /// Commit entries (append-only)
     commits: Vec<Commit>,
+    /// Maximum number of commits retained before trimming
+    max_entries: usize,
+    /// Sequence number of the newest snapshot commit
+    last_snapshot_seq: Option<u64>,
     /// Next sequence number
     next_seq: u64,
     /// Hash of the last commit
     last_hash: CommitId,
+    /// Signed checkpoints over the chain head (oldest first)
+    checkpoints: Vec<Checkpoint>,
+    /// Checkpoint signer (None = checkpoints disabled)
+    signer: Option<Box<dyn CheckpointSigner>>,
+    /// Emit a checkpoint every N commits (0 = only on demand)
+    checkpoint_interval: u64,
 }
 
 impl CommitLog {
     /// Create a new CommitLog with a genesis commit.
     pub fn new(timestamp: u64) -> Self {
+        Self::with_max_entries(timestamp, MAX_COMMITLOG_ENTRIES)
+    }
+
+    /// Create a new CommitLog that retains at most `max_entries` commits
+    /// once snapshots allow trimming.
+    pub fn with_max_entries(timestamp: u64, max_entries: usize) -> Self {
         let genesis = Commit {
             id: [0u8; 32], // Will be computed
             prev_commit: [0u8; 32],
@@ -135,9 +528,60 @@ impl CommitLog {
 
         Self {
             commits: vec![genesis],
+            max_entries: max_entries.max(1),
+            last_snapshot_seq: None,
             next_seq: 1,
             last_hash: id,
+            checkpoints: Vec::new(),
+            signer: None,
+            checkpoint_interval: 0,
+        }
+    }
+
+    /// Enable signed checkpoints.
+    ///
+    /// After this call, a checkpoint is appended automatically whenever the
+    /// sequence number of a new commit is a multiple of `interval`. An
+    /// `interval` of 0 disables automatic checkpoints; [`CommitLog::checkpoint`]
+    /// can still be called explicitly (e.g. before persisting the log).
+    pub fn set_checkpoint_signer(&mut self, signer: Box<dyn CheckpointSigner>, interval: u64) {
+        self.signer = Some(signer);
+        self.checkpoint_interval = interval;
+    }
+
+    /// Disable signed checkpoints. Existing checkpoints are kept.
+    pub fn clear_checkpoint_signer(&mut self) {
+        self.signer = None;
+        self.checkpoint_interval = 0;
+    }
+
+    /// Get the automatic checkpoint interval (0 = disabled).
+    pub fn checkpoint_interval(&self) -> u64 {
+        self.checkpoint_interval
+    }
+
+    /// Sign a checkpoint over the current head.
+    ///
+    /// Returns None if no signer is configured.
+    pub fn checkpoint(&mut self, timestamp: u64) -> Option<&Checkpoint> {
+        let signer = self.signer.as_deref()?;
+        let checkpoint = Checkpoint::sign(self.current_seq(), self.last_hash, timestamp, signer);
+        self.checkpoints.push(checkpoint);
+        if self.checkpoints.len() > MAX_CHECKPOINTS {
+            let drain_count = self.checkpoints.len() - MAX_CHECKPOINTS;
+            self.checkpoints.drain(0..drain_count);
         }
+        self.checkpoints.last()
+    }
+
+    /// Get all retained checkpoints (oldest first).
+    pub fn checkpoints(&self) -> &[Checkpoint] {
+        &self.checkpoints
+    }
+
+    /// Get the most recent checkpoint.
+    pub fn latest_checkpoint(&self) -> Option<&Checkpoint> {
+        self.checkpoints.last()
     }
 
     /// Append a new commit to the log.
@@ -160,226 +604,80 @@ impl CommitLog {
         let id = Self::compute_hash(&commit);
         let commit = Commit { id, ..commit };
 
+        let seq = commit.seq;
+        if matches!(commit.commit_type, CommitType::Snapshot { .. }) {
+            self.last_snapshot_seq = Some(seq);
+        }
         self.last_hash = id;
         self.next_seq += 1;
         self.commits.push(commit);
 
         self.trim_if_needed();
+
+        if self.checkpoint_interval > 0 && seq.is_multiple_of(self.checkpoint_interval) {
+            self.checkpoint(timestamp);
+        }
         id
     }
 
     /// Compute hash for a commit.
     ///
-    /// Uses FNV-1a hash for no_std compatibility.
-    /// In production, this could use SHA-256.
+    /// SHA-256 over a domain separator followed by the commit's canonical
+    /// encoding (see [`Commit::canonical_bytes`]). The `id` field itself is
+    /// excluded.
     fn compute_hash(commit: &Commit) -> CommitId {
-        let mut hash = 0xcbf29ce484222325u64; // FNV offset basis
-        const FNV_PRIME: u64 = 0x100000001b3;
+        let mut hasher = Sha256::new();
+        hasher.update(COMMIT_HASH_DOMAIN);
+        hasher.update(commit.canonical_bytes());
+        hasher.finalize().into()
+    }
 
-        // Hash prev_commit
-        for byte in commit.prev_commit {
-            hash ^= byte as u64;
-            hash = hash.wrapping_mul(FNV_PRIME);
-        }
+    /// Get all commits.
+    pub fn commits(&self) -> &[Commit] {
+        &self.commits
+    }
 
-        // Hash seq
-        for byte in commit.seq.to_le_bytes() {
-            hash ^= byte as u64;
-            hash = hash.wrapping_mul(FNV_PRIME);
-        }
+    /// Get the newest snapshot commit, if any is retained.
+    pub fn latest_snapshot(&self) -> Option<&Commit> {
+        let seq = self.last_snapshot_seq?;
+        self.get_by_seq(seq)
+    }
 
-        // Hash timestamp
-        for byte in commit.timestamp.to_le_bytes() {
-            hash ^= byte as u64;
-            hash = hash.wrapping_mul(FNV_PRIME);
+    /// Get the commits replay needs: from the newest snapshot onwards,
+    /// or the whole log if no snapshot has been taken.
+    pub fn replay_base(&self) -> &[Commit] {
+        match self.last_snapshot_seq {
+            Some(seq) => &self.commits[self.index_of(seq)..],
+            None => &self.commits,
         }
+    }
 
-        // Hash commit_type discriminant
-        let type_byte = match &commit.commit_type {
-            CommitType::Genesis => 0u8,
-            CommitType::ProcessCreated { .. } => 1,
-            CommitType::ProcessExited { .. } => 2,
-            CommitType::ProcessFaulted { .. } => 3,
-            CommitType::CapInserted { .. } => 4,
-            CommitType::CapRemoved { .. } => 5,
-            CommitType::CapGranted { .. } => 6,
-            CommitType::EndpointCreated { .. } => 7,
-            CommitType::EndpointDestroyed { .. } => 8,
-            CommitType::MessageSent { .. } => 9,
-        };
-        hash ^= type_byte as u64;
-        hash = hash.wrapping_mul(FNV_PRIME);
+    /// Number of commits appended since the newest snapshot (or genesis).
+    pub fn commits_since_snapshot(&self) -> u64 {
+        self.current_seq() - self.last_snapshot_seq.unwrap_or(0)
+    }
 
-        // Hash additional type-specific data
-        match &commit.commit_type {
-            CommitType::Genesis => {}
-            CommitType::ProcessCreated { pid, parent, name } => {
-                for byte in pid.to_le_bytes() {
-                    hash ^= byte as u64;
-                    hash = hash.wrapping_mul(FNV_PRIME);
-                }
-                for byte in parent.to_le_bytes() {
-                    hash ^= byte as u64;
-                    hash = hash.wrapping_mul(FNV_PRIME);
-                }
-                for byte in name.bytes() {
-                    hash ^= byte as u64;
-                    hash = hash.wrapping_mul(FNV_PRIME);
-                }
-            }
-            CommitType::ProcessExited { pid, code } => {
-                for byte in pid.to_le_bytes() {
-                    hash ^= byte as u64;
-                    hash = hash.wrapping_mul(FNV_PRIME);
-                }
-                for byte in code.to_le_bytes() {
-                    hash ^= byte as u64;
-                    hash = hash.wrapping_mul(FNV_PRIME);
-                }
-            }
-            CommitType::CapInserted {
-                pid,
-                slot,
-                cap_id,
-                object_type,
-                object_id,
-                perms,
-            } => {
-                for byte in pid.to_le_bytes() {
-                    hash ^= byte as u64;
-                    hash = hash.wrapping_mul(FNV_PRIME);
-                }
-                for byte in slot.to_le_bytes() {
-                    hash ^= byte as u64;
-                    hash = hash.wrapping_mul(FNV_PRIME);
-                }
-                for byte in cap_id.to_le_bytes() {
-                    hash ^= byte as u64;
-                    hash = hash.wrapping_mul(FNV_PRIME);
-                }
-                hash ^= *object_type as u64;
-                hash = hash.wrapping_mul(FNV_PRIME);
-                for byte in object_id.to_le_bytes() {
-                    hash ^= byte as u64;
-                    hash = hash.wrapping_mul(FNV_PRIME);
-                }
-                hash ^= *perms as u64;
-                hash = hash.wrapping_mul(FNV_PRIME);
-            }
-            CommitType::CapRemoved { pid, slot } => {
-                for byte in pid.to_le_bytes() {
-                    hash ^= byte as u64;
-                    hash = hash.wrapping_mul(FNV_PRIME);
-                }
-                for byte in slot.to_le_bytes() {
-                    hash ^= byte as u64;
-                    hash = hash.wrapping_mul(FNV_PRIME);
-                }
-            }
-            CommitType::CapGranted {
-                from_pid,
-                to_pid,
-                from_slot,
-                to_slot,
-                new_cap_id,
-                perms,
-            } => {
-                for byte in from_pid.to_le_bytes() {
-                    hash ^= byte as u64;
-                    hash = hash.wrapping_mul(FNV_PRIME);
-                }
-                for byte in to_pid.to_le_bytes() {
-                    hash ^= byte as u64;
-                    hash = hash.wrapping_mul(FNV_PRIME);
-                }
-                for byte in from_slot.to_le_bytes() {
-                    hash ^= byte as u64;
-                    hash = hash.wrapping_mul(FNV_PRIME);
-                }
-                for byte in to_slot.to_le_bytes() {
-                    hash ^= byte as u64;
-                    hash = hash.wrapping_mul(FNV_PRIME);
-                }
-                for byte in new_cap_id.to_le_bytes() {
-                    hash ^= byte as u64;
-                    hash = hash.wrapping_mul(FNV_PRIME);
-                }
-                hash ^= perms.to_byte() as u64;
-                hash = hash.wrapping_mul(FNV_PRIME);
-            }
-            CommitType::EndpointCreated { id, owner } => {
-                for byte in id.to_le_bytes() {
-                    hash ^= byte as u64;
-                    hash = hash.wrapping_mul(FNV_PRIME);
-                }
-                for byte in owner.to_le_bytes() {
-                    hash ^= byte as u64;
-                    hash = hash.wrapping_mul(FNV_PRIME);
-                }
-            }
-            CommitType::EndpointDestroyed { id } => {
-                for byte in id.to_le_bytes() {
-                    hash ^= byte as u64;
-                    hash = hash.wrapping_mul(FNV_PRIME);
-                }
-            }
-            CommitType::ProcessFaulted {
-                pid,
-                reason,
-                description,
-            } => {
-                for byte in pid.to_le_bytes() {
-                    hash ^= byte as u64;
-                    hash = hash.wrapping_mul(FNV_PRIME);
-                }
-                for byte in reason.to_le_bytes() {
-                    hash ^= byte as u64;
-                    hash = hash.wrapping_mul(FNV_PRIME);
-                }
-                for byte in description.bytes() {
-                    hash ^= byte as u64;
-                    hash = hash.wrapping_mul(FNV_PRIME);
-                }
-            }
-            CommitType::MessageSent {
-                from_pid,
-                to_endpoint,
-                tag,
-                size,
-            } => {
-                for byte in from_pid.to_le_bytes() {
-                    hash ^= byte as u64;
-                    hash = hash.wrapping_mul(FNV_PRIME);
-                }
-                for byte in to_endpoint.to_le_bytes() {
-                    hash ^= byte as u64;
-                    hash = hash.wrapping_mul(FNV_PRIME);
-                }
-                for byte in tag.to_le_bytes() {
-                    hash ^= byte as u64;
-                    hash = hash.wrapping_mul(FNV_PRIME);
-                }
-                for byte in (*size as u64).to_le_bytes() {
-                    hash ^= byte as u64;
-                    hash = hash.wrapping_mul(FNV_PRIME);
-                }
-            }
-        }
+    /// Whether the kernel should append a snapshot now.
+    ///
+    /// Snapshots are requested every `max_entries / 2` commits so that the
+    /// retained window always contains one to trim behind.
+    pub fn needs_snapshot(&self) -> bool {
+        self.commits_since_snapshot() >= (self.max_entries as u64 / 2).max(1)
+    }
 
-        // Expand to 32 bytes
-        let mut result = [0u8; 32];
-        let mut h = hash;
-        for chunk in result.chunks_mut(8) {
-            let bytes = h.to_le_bytes();
-            chunk.copy_from_slice(&bytes[..chunk.len()]);
-            h = h.wrapping_mul(FNV_PRIME);
+    /// Get a retained commit by sequence number.
+    pub fn get_by_seq(&self, seq: u64) -> Option<&Commit> {
+        let first_seq = self.commits.first()?.seq;
+        if seq < first_seq {
+            return None;
         }
-        result
+        self.commits.get((seq - first_seq) as usize)
     }
 
-    /// Get all commits.
-    pub fn commits(&self) -> &[Commit] {
-        &self.commits
+    /// Index of a retained sequence number in `commits`.
+    fn index_of(&self, seq: u64) -> usize {
+        let first_seq = self.commits.first().map(|c| c.seq).unwrap_or(0);
+        (seq - first_seq) as usize
     }
 
     /// Get commits in a sequence range.
@@ -422,27 +720,104 @@ impl CommitLog {
         if self.commits.is_empty() {
             return true;
         }
+        Self::verify_chain(&self.commits)
+            && self.commits.last().map(|c| c.id) == Some(self.last_hash)
+    }
+
+    /// Verify a sequence of commits forms an unbroken hash chain.
+    ///
+    /// Every commit hash is recomputed and sequence numbers must be
+    /// contiguous. If the slice starts at genesis (seq 0) its `prev_commit`
+    /// must be zero; otherwise the first commit is taken as the anchor and
+    /// should be vouched for by a [`Checkpoint`].
+    ///
+    /// This does not need a live `CommitLog`, so it can be used on commits
+    /// loaded from storage.
+    pub fn verify_chain(commits: &[Commit]) -> bool {
+        let first = match commits.first() {
+            Some(c) => c,
+            None => return true,
+        };
+        if first.seq == 0 && first.prev_commit != [0u8; 32] {
+            return false;
+        }
 
-        let mut expected_prev = [0u8; 32]; // Genesis has zero prev
+        let mut expected_prev = first.prev_commit;
 
-        for commit in &self.commits {
-            if commit.prev_commit != expected_prev {
+        for (expected_seq, commit) in (first.seq..).zip(commits) {
+            if commit.prev_commit != expected_prev || commit.seq != expected_seq {
                 return false;
             }
-            let computed_hash = Self::compute_hash(commit);
-            if computed_hash != commit.id {
+            if Self::compute_hash(commit) != commit.id {
                 return false;
             }
             expected_prev = commit.id;
         }
 
-        expected_prev == self.last_hash
+        true
+    }
+
+    /// Verify every retained checkpoint against a trusted machine key.
+    ///
+    /// Each checkpoint signature must verify under `trusted_key`, and if the
+    /// attested commit is still in memory its hash must match the
+    /// checkpoint's head. Combined with [`CommitLog::verify_integrity`], this
+    /// proves the log up to the latest checkpoint was produced by the holder
+    /// of the machine key.
+    pub fn verify_checkpoints<V: CheckpointVerifier + ?Sized>(
+        &self,
+        trusted_key: &[u8; 32],
+        verifier: &V,
+    ) -> bool {
+        Self::verify_checkpoints_in(&self.commits, &self.checkpoints, trusted_key, verifier)
+    }
+
+    /// Verify checkpoints against a sequence of commits loaded from storage.
+    ///
+    /// Same rules as [`CommitLog::verify_checkpoints`]: every signature must
+    /// verify under `trusted_key`, and every checkpoint at or after the
+    /// first commit must attest a commit present in `commits`.
+    pub fn verify_checkpoints_in<V: CheckpointVerifier + ?Sized>(
+        commits: &[Commit],
+        checkpoints: &[Checkpoint],
+        trusted_key: &[u8; 32],
+        verifier: &V,
+    ) -> bool {
+        checkpoints
+            .iter()
+            .all(|cp| cp.verify(trusted_key, verifier) && Self::attests(commits, cp))
+    }
+
+    /// Check that a checkpoint's head matches the commit at its sequence
+    /// number, without checking the signature.
+    ///
+    /// Checkpoints older than the first commit attest trimmed history and
+    /// are accepted.
+    pub fn attests(commits: &[Commit], checkpoint: &Checkpoint) -> bool {
+        let first_seq = commits.first().map(|c| c.seq).unwrap_or(0);
+        if checkpoint.seq < first_seq {
+            return true;
+        }
+        match commits.get((checkpoint.seq - first_seq) as usize) {
+            Some(commit) => commit.seq == checkpoint.seq && commit.id == checkpoint.head,
+            None => false,
+        }
     }
 
     /// Trim old commits if exceeding max capacity.
+    ///
+    /// Only commits strictly before the newest snapshot are dropped, so
+    /// replay always has a base. Without a snapshot the log keeps growing.
     fn trim_if_needed(&mut self) {
-        if self.commits.len() > MAX_COMMITLOG_ENTRIES {
-            let drain_count = self.commits.len() - MAX_COMMITLOG_ENTRIES;
+        if self.commits.len() <= self.max_entries {
+            return;
+        }
+        let snapshot_index = match self.last_snapshot_seq {
+            Some(seq) => self.index_of(seq),
+            None => return,
+        };
+        let drain_count = (self.commits.len() - self.max_entries).min(snapshot_index);
+        if drain_count > 0 {
             self.commits.drain(0..drain_count);
         }
     }
@@ -457,6 +832,7 @@ impl Default for CommitLog {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::checkpoint::tests::{TestSigner, TestVerifier};
 
     #[test]
     fn test_commitlog_creation() {
@@ -580,4 +956,202 @@ mod tests {
 
         assert_eq!(log1.head(), log2.head());
     }
+
+    #[test]
+    fn test_commitlog_detects_field_tampering() {
+        let mut log = CommitLog::new(0);
+        log.append(
+            CommitType::CapInserted {
+                pid: 1,
+                slot: 0,
+                cap_id: 1,
+                object_type: 1,
+                object_id: 1,
+                perms: 0x01,
+            },
+            None,
+            1000,
+        );
+        log.append(CommitType::EndpointCreated { id: 2, owner: 1 }, None, 2000);
+        assert!(CommitLog::verify_chain(log.commits()));
+
+        // Escalate permissions without touching the discriminant
+        let mut tampered = log.commits().to_vec();
+        tampered[1].commit_type = CommitType::CapInserted {
+            pid: 1,
+            slot: 0,
+            cap_id: 1,
+            object_type: 1,
+            object_id: 1,
+            perms: 0x07,
+        };
+        assert!(!CommitLog::verify_chain(&tampered));
+
+        // Reattributing the commit to a different syscall is also detected
+        let mut tampered = log.commits().to_vec();
+        tampered[2].caused_by = Some(99);
+        assert!(!CommitLog::verify_chain(&tampered));
+    }
+
+    #[test]
+    fn test_commitlog_verify_chain_rejects_gaps() {
+        let mut log = CommitLog::new(0);
+        for i in 1..=3 {
+            log.append(CommitType::EndpointDestroyed { id: i }, None, i * 1000);
+        }
+
+        let mut commits = log.commits().to_vec();
+        commits.remove(2);
+        assert!(!CommitLog::verify_chain(&commits));
+
+        // A suffix of the chain is valid on its own
+        assert!(CommitLog::verify_chain(&log.commits()[2..]));
+    }
+
+    #[test]
+    fn test_canonical_encoding_length_prefixes_strings() {
+        // Without length prefixes these would encode identically
+        let a = CommitType::ProcessFaulted {
+            pid: 1,
+            reason: 0,
+            description: String::from("ab"),
+        };
+        let b = CommitType::ProcessFaulted {
+            pid: 1,
+            reason: 0,
+            description: String::from("a"),
+        };
+        let mut enc_a = Vec::new();
+        let mut enc_b = Vec::new();
+        a.encode_canonical(&mut enc_a);
+        b.encode_canonical(&mut enc_b);
+        assert_ne!(enc_a, enc_b);
+        assert_eq!(enc_a[0], 3);
+        assert_eq!(&enc_a[13..17], &2u32.to_le_bytes());
+    }
+
+    #[test]
+    fn test_commitlog_checkpoint_requires_signer() {
+        let mut log = CommitLog::new(0);
+        assert!(log.checkpoint(1000).is_none());
+        assert!(log.checkpoints().is_empty());
+    }
+
+    #[test]
+    fn test_commitlog_checkpoint_interval() {
+        let mut log = CommitLog::new(0);
+        log.set_checkpoint_signer(Box::new(TestSigner([1u8; 32])), 4);
+
+        for i in 1..=10 {
+            log.append(CommitType::EndpointCreated { id: i, owner: 1 }, None, i * 1000);
+        }
+
+        let seqs: Vec<u64> = log.checkpoints().iter().map(|c| c.seq).collect();
+        assert_eq!(seqs, vec![4, 8]);
+        assert_eq!(log.checkpoints()[1].head, log.commits()[8].id);
+        assert!(log.verify_checkpoints(&[1u8; 32], &TestVerifier));
+    }
+
+    #[test]
+    fn test_commitlog_verify_checkpoints_rejects_wrong_key() {
+        let mut log = CommitLog::new(0);
+        log.set_checkpoint_signer(Box::new(TestSigner([1u8; 32])), 0);
+        log.append(CommitType::EndpointCreated { id: 1, owner: 1 }, None, 1000);
+
+        let cp = log.checkpoint(2000).cloned().unwrap();
+        assert_eq!(cp.seq, 1);
+        assert_eq!(cp.head, log.head());
+
+        assert!(log.verify_checkpoints(&[1u8; 32], &TestVerifier));
+        assert!(!log.verify_checkpoints(&[2u8; 32], &TestVerifier));
+    }
+
+    #[test]
+    fn test_commitlog_verify_checkpoints_detects_rewritten_head() {
+        let mut log = CommitLog::new(0);
+        log.set_checkpoint_signer(Box::new(TestSigner([1u8; 32])), 1);
+        log.append(CommitType::EndpointCreated { id: 1, owner: 1 }, None, 1000);
+
+        // Rewrite the commit and recompute its hash: the chain is
+        // self-consistent again, but no longer matches the signed head.
+        let mut forged = log.commits[1].clone();
+        forged.commit_type = CommitType::EndpointCreated { id: 1, owner: 666 };
+        forged.id = CommitLog::compute_hash(&forged);
+        log.commits[1] = forged;
+        log.last_hash = log.commits[1].id;
+
+        assert!(log.verify_integrity());
+        assert!(!log.verify_checkpoints(&[1u8; 32], &TestVerifier));
+    }
+
+    fn snapshot(tag: u8) -> CommitType {
+        CommitType::Snapshot {
+            state: vec![tag; 4],
+            state_hash: [tag; 32],
+        }
+    }
+
+    #[test]
+    fn test_commitlog_does_not_trim_without_snapshot() {
+        let mut log = CommitLog::with_max_entries(0, 4);
+        for i in 1..=10 {
+            log.append(CommitType::EndpointDestroyed { id: i }, None, i * 1000);
+        }
+
+        assert_eq!(log.len(), 11);
+        assert_eq!(log.commits()[0].seq, 0);
+        assert!(log.needs_snapshot());
+    }
+
+    #[test]
+    fn test_commitlog_trims_only_behind_snapshot() {
+        let mut log = CommitLog::with_max_entries(0, 4);
+        for i in 1..=5 {
+            log.append(CommitType::EndpointDestroyed { id: i }, None, i * 1000);
+        }
+        log.append(snapshot(1), None, 6000);
+
+        // Trimmed back to capacity now that a snapshot exists
+        assert_eq!(log.len(), 4);
+        assert_eq!(log.commits()[0].seq, 3);
+
+        for i in 7..=9 {
+            log.append(CommitType::EndpointDestroyed { id: i }, None, i * 1000);
+        }
+        assert_eq!(log.commits()[0].seq, 6);
+        assert_eq!(log.latest_snapshot().unwrap().seq, 6);
+        assert!(log.verify_integrity());
+
+        // Over capacity but nothing left behind the snapshot to drop
+        log.append(CommitType::EndpointDestroyed { id: 10 }, None, 10000);
+        assert_eq!(log.commits()[0].seq, 6);
+        assert_eq!(log.len(), 5);
+    }
+
+    #[test]
+    fn test_commitlog_replay_base() {
+        let mut log = CommitLog::new(0);
+        assert_eq!(log.replay_base().len(), 1);
+
+        log.append(CommitType::EndpointDestroyed { id: 1 }, None, 1000);
+        log.append(snapshot(1), None, 2000);
+        log.append(CommitType::EndpointDestroyed { id: 3 }, None, 3000);
+
+        let base = log.replay_base();
+        assert_eq!(base.len(), 2);
+        assert!(matches!(base[0].commit_type, CommitType::Snapshot { .. }));
+        assert_eq!(log.commits_since_snapshot(), 1);
+    }
+
+    #[test]
+    fn test_commitlog_get_by_seq_after_trim() {
+        let mut log = CommitLog::with_max_entries(0, 2);
+        log.append(CommitType::EndpointDestroyed { id: 1 }, None, 1000);
+        log.append(snapshot(1), None, 2000);
+        log.append(CommitType::EndpointDestroyed { id: 3 }, None, 3000);
+
+        assert!(log.get_by_seq(1).is_none());
+        assert_eq!(log.get_by_seq(3).unwrap().seq, 3);
+        assert!(log.get_by_seq(4).is_none());
+    }
 }
@@ -8,11 +8,17 @@
 //!
 //! This ensures all syscalls are audited and all state mutations
 //! are recorded for deterministic replay.
+//!
+//! When recording is on, the gateway also keeps a [`SyscallTrace`] of full
+//! request payloads and results, so a single process run can be replayed.
 
+use alloc::boxed::Box;
 use alloc::vec::Vec;
 
+use crate::checkpoint::CheckpointSigner;
 use crate::commitlog::{CommitLog, CommitType};
 use crate::syslog::SysLog;
+use crate::trace::{SyscallTrace, TraceEvent, TraceEventKind};
 use crate::types::{CommitId, ProcessId};
 
 /// Axiom gateway: Entry point for all syscalls.
@@ -25,6 +31,8 @@ pub struct AxiomGateway {
     syslog: SysLog,
     /// State mutation log
     commitlog: CommitLog,
+    /// Syscall trace, while recording is on
+    recording: Option<SyscallTrace>,
 }
 
 impl AxiomGateway {
@@ -36,6 +44,7 @@ impl AxiomGateway {
         Self {
             syslog: SysLog::new(),
             commitlog: CommitLog::new(timestamp),
+            recording: None,
         }
     }
 
@@ -90,6 +99,7 @@ impl AxiomGateway {
         // 4. Log syscall response
         self.syslog
             .log_response(sender, request_id, result, timestamp);
+        self.record_syscall(sender, syscall_num, args, &[], result, &[], timestamp);
 
         (result, commit_ids)
     }
@@ -124,6 +134,85 @@ impl AxiomGateway {
         self.commitlog.append(commit_type, None, timestamp)
     }
 
+    /// Enable signed CommitLog checkpoints every `interval` commits.
+    ///
+    /// See [`CommitLog::set_checkpoint_signer`].
+    pub fn set_checkpoint_signer(&mut self, signer: Box<dyn CheckpointSigner>, interval: u64) {
+        self.commitlog.set_checkpoint_signer(signer, interval);
+    }
+
+    // ========================================================================
+    // Syscall recording
+    // ========================================================================
+
+    /// Start recording full syscall payloads and results.
+    ///
+    /// Discards any trace recorded so far.
+    pub fn start_recording(&mut self) {
+        self.recording = Some(SyscallTrace::new());
+    }
+
+    /// Stop recording and take the trace.
+    ///
+    /// Returns `None` if recording was not on.
+    pub fn stop_recording(&mut self) -> Option<SyscallTrace> {
+        self.recording.take()
+    }
+
+    /// Check if syscalls are being recorded.
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// Get the trace recorded so far.
+    pub fn recording(&self) -> Option<&SyscallTrace> {
+        self.recording.as_ref()
+    }
+
+    /// Record a completed syscall (no-op unless recording).
+    ///
+    /// `data` is the request payload and `response` the data returned to
+    /// the process along with `result`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn record_syscall(
+        &mut self,
+        sender: ProcessId,
+        syscall_num: u32,
+        args: [u32; 4],
+        data: &[u8],
+        result: i64,
+        response: &[u8],
+        timestamp: u64,
+    ) {
+        if let Some(trace) = &mut self.recording {
+            trace.push(TraceEvent {
+                pid: sender,
+                timestamp,
+                kind: TraceEventKind::Syscall {
+                    syscall_num,
+                    args,
+                    data: data.to_vec(),
+                    result,
+                    response: response.to_vec(),
+                },
+            });
+        }
+    }
+
+    /// Record an async HAL result delivered to `pid` (no-op unless recording).
+    pub fn record_async_result(&mut self, pid: ProcessId, tag: u32, payload: &[u8], timestamp: u64) {
+        if let Some(trace) = &mut self.recording {
+            trace.push(TraceEvent {
+                pid,
+                timestamp,
+                kind: TraceEventKind::AsyncResult {
+                    tag,
+                    payload: payload.to_vec(),
+                },
+            });
+        }
+    }
+
     /// Verify integrity of both logs.
     pub fn verify_integrity(&self) -> bool {
         self.commitlog.verify_integrity()
@@ -244,6 +333,28 @@ mod tests {
         assert_eq!(state.commitlog_seq, 1);
     }
 
+    #[test]
+    fn test_gateway_records_only_when_enabled() {
+        let mut gateway = AxiomGateway::new(0);
+
+        gateway.syscall(1, 0x01, [0, 0, 0, 0], 1000, |_, _| (0, Vec::new()));
+        assert!(!gateway.is_recording());
+
+        gateway.start_recording();
+        gateway.syscall(1, 0x02, [5, 0, 0, 0], 2000, |_, _| (3, Vec::new()));
+        gateway.record_syscall(2, 0x41, [1, 0, 0, 0], b"req", 1, b"msg", 3000);
+        gateway.record_async_result(2, 0x80, b"done", 4000);
+
+        let trace = gateway.stop_recording().unwrap();
+        assert_eq!(trace.len(), 3);
+        assert_eq!(trace.events_for(2).count(), 2);
+        assert!(matches!(
+            &trace.events()[1].kind,
+            TraceEventKind::Syscall { response, .. } if response == b"msg"
+        ));
+        assert!(gateway.stop_recording().is_none());
+    }
+
     #[test]
     fn test_gateway_internal_commit() {
         let mut gateway = AxiomGateway::new(0);
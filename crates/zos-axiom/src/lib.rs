@@ -3,7 +3,10 @@
 //! The Axiom layer provides:
 //! - **SysLog**: Audit trail of all syscalls (request + response)
 //! - **CommitLog**: Deterministic state mutations for replay
+//! - **Checkpoints**: Signed attestations of the CommitLog head
 //! - **AxiomGateway**: Entry point for all syscalls
+//! - **Traces**: Opt-in recording of full syscall payloads for record/replay
+//! - **Codec**: Versioned binary log files shared by the kernel, supervisor and host tools
 //! - **Capability verification**: The `axiom_check` function for authority validation
 //!
 //! # Core Guarantee
@@ -21,22 +24,32 @@
 extern crate alloc;
 
 pub mod capability;
+pub mod checkpoint;
+pub mod codec;
 pub mod commitlog;
 pub mod gateway;
 pub mod replay;
 pub mod syslog;
+pub mod trace;
 pub mod types;
 
 // Re-export capability types
 pub use capability::{axiom_check, AxiomError, Capability, CapabilitySpace};
 
 // Re-export main types
+pub use checkpoint::{Checkpoint, CheckpointSigner, CheckpointVerifier};
+pub use codec::{log_kind, CodecError, CommitLogFile, LogKind, SysLogFile, LOG_VERSION};
 pub use commitlog::{Commit, CommitLog, CommitType};
 pub use gateway::AxiomGateway;
 pub use replay::{
-    apply_commit, replay, replay_and_verify, ReplayError, ReplayResult, Replayable, StateHasher,
+    apply_commit, replay, replay_and_verify, replay_start, replay_to_seq, ReplayError,
+    ReplayResult, Replayable, StateHasher,
 };
 pub use syslog::{SysEvent, SysEventType, SysLog};
+pub use trace::{
+    ProcessReplay, RecordedResult, SyscallTrace, TraceDivergence, TraceError, TraceEvent,
+    TraceEventKind,
+};
 pub use types::*;
 
 #[cfg(test)]
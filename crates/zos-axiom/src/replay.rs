@@ -10,14 +10,18 @@
 //!
 //! ```text
 //! reduce(genesis, commits) -> state
+//! reduce(snapshot, tail)   -> state
 //! ```
 //!
-//! Each commit is a pure state mutation with no side effects.
+//! Each commit is a pure state mutation with no side effects. When the log
+//! contains a `Snapshot` commit, replay restores the newest one and applies
+//! only the commits after it.
 
 use alloc::string::String;
+use alloc::vec::Vec;
 
 use crate::commitlog::{Commit, CommitType};
-use crate::types::{CapSlot, EndpointId, Permissions, ProcessId};
+use crate::types::{CapSlot, EndpointId, Permissions, ProcessId, ResourceQuota};
 
 /// Errors that can occur during replay.
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -37,6 +41,18 @@ pub enum ReplayError {
     },
     /// Unknown object type in commit
     UnknownObjectType(u8),
+    /// Commits start after genesis (trimmed log) and contain no snapshot
+    MissingSnapshot {
+        /// Sequence number of the first available commit
+        first_seq: u64,
+    },
+    /// Requested sequence number is past the end of the log
+    SeqOutOfRange {
+        /// Requested sequence number
+        seq: u64,
+        /// Sequence number of the last available commit
+        last_seq: u64,
+    },
 }
 
 /// Result of applying a commit.
@@ -105,6 +121,69 @@ pub trait Replayable {
         perms: Permissions,
     ) -> ReplayResult<()>;
 
+    /// Set the badge of an inserted capability during replay.
+    fn replay_badge_capability(
+        &mut self,
+        pid: ProcessId,
+        slot: CapSlot,
+        badge: u64,
+    ) -> ReplayResult<()>;
+
+    /// Set the expiry of an inserted capability during replay.
+    fn replay_expire_capability(
+        &mut self,
+        pid: ProcessId,
+        slot: CapSlot,
+        expires_at: u64,
+    ) -> ReplayResult<()>;
+
+    /// Create a notification object during replay.
+    fn replay_create_notification(&mut self, id: u64, owner: ProcessId) -> ReplayResult<()>;
+
+    /// OR `bits` into a notification's signal word during replay.
+    fn replay_signal_notification(&mut self, id: u64, bits: u64) -> ReplayResult<()>;
+
+    /// Clear the `bits` a wait or poll consumed during replay.
+    fn replay_take_notification(&mut self, id: u64, bits: u64) -> ReplayResult<()>;
+
+    /// Bind an IRQ line to a driver's delivery target during replay.
+    ///
+    /// `target_type` is the target's `ObjectType`; `bits` is only used for
+    /// a notification target.
+    fn replay_bind_irq(
+        &mut self,
+        irq: u8,
+        pid: ProcessId,
+        target_type: u8,
+        target_id: u64,
+        bits: u64,
+    ) -> ReplayResult<()>;
+
+    /// Remove an IRQ binding during replay.
+    fn replay_unbind_irq(&mut self, irq: u8) -> ReplayResult<()>;
+
+    /// Mask (interrupt delivered) or unmask (acknowledged) a bound IRQ
+    /// line during replay.
+    fn replay_mask_irq(&mut self, irq: u8, masked: bool) -> ReplayResult<()>;
+
+    /// Create a shared memory region during replay.
+    fn replay_create_region(&mut self, id: u64, owner: ProcessId, size: usize) -> ReplayResult<()>;
+
+    /// Map a region into a process during replay.
+    fn replay_map_region(
+        &mut self,
+        pid: ProcessId,
+        id: u64,
+        writable: bool,
+        window: u32,
+    ) -> ReplayResult<()>;
+
+    /// Unmap a region from a process during replay.
+    fn replay_unmap_region(&mut self, pid: ProcessId, id: u64) -> ReplayResult<()>;
+
+    /// Free a region during replay.
+    fn replay_destroy_region(&mut self, id: u64) -> ReplayResult<()>;
+
     /// Create an endpoint during replay.
     fn replay_create_endpoint(&mut self, id: EndpointId, owner: ProcessId) -> ReplayResult<()>;
 
@@ -123,6 +202,19 @@ pub trait Replayable {
         size: usize,
     ) -> ReplayResult<()>;
 
+    /// Set a process's resource quota during replay.
+    fn replay_set_quota(&mut self, pid: ProcessId, quota: ResourceQuota) -> ReplayResult<()>;
+
+    /// Serialize the current state for a snapshot commit.
+    ///
+    /// Must capture everything `state_hash` covers plus the ID counters,
+    /// so that restoring it and applying later commits is equivalent to
+    /// replaying from genesis.
+    fn snapshot_state(&self) -> Vec<u8>;
+
+    /// Replace the current state with one produced by `snapshot_state`.
+    fn replay_restore_snapshot(&mut self, state: &[u8]) -> ReplayResult<()>;
+
     /// Compute a deterministic hash of the current state.
     ///
     /// This hash covers:
@@ -194,6 +286,26 @@ pub fn apply_commit<R: Replayable>(state: &mut R, commit: &Commit) -> ReplayResu
             *perms,
         ),
 
+        CommitType::CapBadged { pid, slot, badge } => {
+            state.replay_badge_capability(*pid, *slot, *badge)
+        }
+
+        CommitType::CapExpiry {
+            pid,
+            slot,
+            expires_at,
+        } => state.replay_expire_capability(*pid, *slot, *expires_at),
+
+        CommitType::NotificationCreated { id, owner } => {
+            state.replay_create_notification(*id, *owner)
+        }
+
+        CommitType::NotificationSignaled { id, bits, .. } => {
+            state.replay_signal_notification(*id, *bits)
+        }
+
+        CommitType::NotificationTaken { id, bits, .. } => state.replay_take_notification(*id, *bits),
+
         CommitType::EndpointCreated { id, owner } => state.replay_create_endpoint(*id, *owner),
 
         CommitType::EndpointDestroyed { id } => state.replay_destroy_endpoint(*id),
@@ -204,12 +316,67 @@ pub fn apply_commit<R: Replayable>(state: &mut R, commit: &Commit) -> ReplayResu
             tag,
             size,
         } => state.replay_message_sent(*from_pid, *to_endpoint, *tag, *size),
+
+        CommitType::QuotaSet { pid, quota, .. } => state.replay_set_quota(*pid, *quota),
+
+        // Refused syscalls change no state; the commit is for the audit trail
+        CommitType::QuotaExceeded { .. } => Ok(()),
+
+        // Calls in flight are volatile like queued messages; the reply
+        // capability itself is replayed through CapInserted/CapRemoved
+        CommitType::Replied { .. } | CommitType::CallAborted { .. } => Ok(()),
+
+        CommitType::IrqBound {
+            irq,
+            pid,
+            target_type,
+            target_id,
+            bits,
+        } => state.replay_bind_irq(*irq, *pid, *target_type, *target_id, *bits),
+
+        CommitType::IrqUnbound { irq } => state.replay_unbind_irq(*irq),
+
+        CommitType::IrqRaised { irq } => state.replay_mask_irq(*irq, true),
+
+        CommitType::IrqAcked { irq } => state.replay_mask_irq(*irq, false),
+
+        CommitType::RegionCreated { id, owner, size } => {
+            state.replay_create_region(*id, *owner, *size)
+        }
+
+        CommitType::RegionMapped {
+            pid,
+            id,
+            writable,
+            window,
+        } => state.replay_map_region(*pid, *id, *writable, *window),
+
+        CommitType::RegionUnmapped { pid, id } => state.replay_unmap_region(*pid, *id),
+
+        CommitType::RegionDestroyed { id } => state.replay_destroy_region(*id),
+
+        CommitType::Snapshot {
+            state: snapshot,
+            state_hash,
+        } => {
+            state.replay_restore_snapshot(snapshot)?;
+            let actual = state.state_hash();
+            if actual != *state_hash {
+                return Err(ReplayError::HashMismatch {
+                    expected: *state_hash,
+                    actual,
+                });
+            }
+            Ok(())
+        }
     }
 }
 
 /// Replay a sequence of commits to reconstruct state.
 ///
-/// This function applies commits in order, starting from genesis.
+/// This function applies commits in order, starting from the newest
+/// snapshot if there is one, otherwise from genesis. Replaying a trimmed
+/// log with no snapshot fails with [`ReplayError::MissingSnapshot`].
 ///
 /// # Arguments
 /// - `state`: Mutable reference to a Replayable state (should be fresh/empty)
@@ -227,12 +394,54 @@ pub fn apply_commit<R: Replayable>(state: &mut R, commit: &Commit) -> ReplayResu
 /// let hash = kernel.state_hash();
 /// ```
 pub fn replay<R: Replayable>(state: &mut R, commits: &[Commit]) -> ReplayResult<()> {
-    for commit in commits {
+    let start = replay_start(commits)?;
+    for commit in &commits[start..] {
         apply_commit(state, commit)?;
     }
     Ok(())
 }
 
+/// Index of the commit replay should start from.
+///
+/// This is the newest snapshot, or 0 if the commits begin at genesis.
+pub fn replay_start(commits: &[Commit]) -> ReplayResult<usize> {
+    let snapshot = commits
+        .iter()
+        .rposition(|c| matches!(c.commit_type, CommitType::Snapshot { .. }));
+    match (snapshot, commits.first()) {
+        (Some(index), _) => Ok(index),
+        (None, Some(first)) if first.seq != 0 => Err(ReplayError::MissingSnapshot {
+            first_seq: first.seq,
+        }),
+        (None, _) => Ok(0),
+    }
+}
+
+/// Replay commits up to and including sequence number `seq`.
+///
+/// Reconstructs the state as it was right after commit `seq`. Replay starts
+/// from the newest snapshot at or before `seq`, so snapshots taken later in
+/// the log do not get in the way of looking further back.
+///
+/// # Errors
+/// - `SeqOutOfRange` if `seq` is past the last commit
+/// - `MissingSnapshot` if the log was trimmed past `seq`
+pub fn replay_to_seq<R: Replayable>(state: &mut R, commits: &[Commit], seq: u64) -> ReplayResult<()> {
+    let last_seq = commits.last().map_or(0, |c| c.seq);
+    if seq > last_seq {
+        return Err(ReplayError::SeqOutOfRange { seq, last_seq });
+    }
+
+    let end = commits.partition_point(|c| c.seq <= seq);
+    let prefix = &commits[..end];
+    if prefix.is_empty() {
+        return Err(ReplayError::MissingSnapshot {
+            first_seq: commits.first().map_or(0, |c| c.seq),
+        });
+    }
+    replay(state, prefix)
+}
+
 /// Replay commits and verify the final state hash.
 ///
 /// This is the primary verification function for deterministic replay.
@@ -347,6 +556,7 @@ impl Default for StateHasher {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::commitlog::CommitLog;
     use alloc::format;
 
     #[test]
@@ -401,4 +611,234 @@ mod tests {
         };
         assert!(format!("{:?}", err).contains("HashMismatch"));
     }
+
+    /// Minimal replayable state: a list of endpoint IDs.
+    #[derive(Default)]
+    struct Endpoints(Vec<u64>);
+
+    impl Replayable for Endpoints {
+        fn replay_genesis(&mut self) -> ReplayResult<()> {
+            Ok(())
+        }
+        fn replay_create_process(&mut self, _: ProcessId, _: ProcessId, _: String) -> ReplayResult<()> {
+            Ok(())
+        }
+        fn replay_exit_process(&mut self, _: ProcessId, _: i32) -> ReplayResult<()> {
+            Ok(())
+        }
+        fn replay_process_faulted(&mut self, _: ProcessId, _: u32, _: String) -> ReplayResult<()> {
+            Ok(())
+        }
+        fn replay_insert_capability(
+            &mut self,
+            _: ProcessId,
+            _: CapSlot,
+            _: u64,
+            _: u8,
+            _: u64,
+            _: u8,
+        ) -> ReplayResult<()> {
+            Ok(())
+        }
+        fn replay_remove_capability(&mut self, _: ProcessId, _: CapSlot) -> ReplayResult<()> {
+            Ok(())
+        }
+        fn replay_cap_granted(
+            &mut self,
+            _: ProcessId,
+            _: ProcessId,
+            _: CapSlot,
+            _: CapSlot,
+            _: u64,
+            _: Permissions,
+        ) -> ReplayResult<()> {
+            Ok(())
+        }
+        fn replay_create_endpoint(&mut self, id: EndpointId, _: ProcessId) -> ReplayResult<()> {
+            self.0.push(id);
+            Ok(())
+        }
+        fn replay_destroy_endpoint(&mut self, id: EndpointId) -> ReplayResult<()> {
+            self.0.retain(|e| *e != id);
+            Ok(())
+        }
+        fn replay_message_sent(&mut self, _: ProcessId, _: EndpointId, _: u32, _: usize) -> ReplayResult<()> {
+            Ok(())
+        }
+        fn replay_set_quota(&mut self, _: ProcessId, _: ResourceQuota) -> ReplayResult<()> {
+            Ok(())
+        }
+        fn replay_badge_capability(&mut self, _: ProcessId, _: CapSlot, _: u64) -> ReplayResult<()> {
+            Ok(())
+        }
+        fn replay_expire_capability(&mut self, _: ProcessId, _: CapSlot, _: u64) -> ReplayResult<()> {
+            Ok(())
+        }
+        fn replay_create_notification(&mut self, _: u64, _: ProcessId) -> ReplayResult<()> {
+            Ok(())
+        }
+        fn replay_signal_notification(&mut self, _: u64, _: u64) -> ReplayResult<()> {
+            Ok(())
+        }
+        fn replay_take_notification(&mut self, _: u64, _: u64) -> ReplayResult<()> {
+            Ok(())
+        }
+        fn replay_bind_irq(&mut self, _: u8, _: ProcessId, _: u8, _: u64, _: u64) -> ReplayResult<()> {
+            Ok(())
+        }
+        fn replay_unbind_irq(&mut self, _: u8) -> ReplayResult<()> {
+            Ok(())
+        }
+        fn replay_mask_irq(&mut self, _: u8, _: bool) -> ReplayResult<()> {
+            Ok(())
+        }
+        fn replay_create_region(&mut self, _: u64, _: ProcessId, _: usize) -> ReplayResult<()> {
+            Ok(())
+        }
+        fn replay_map_region(&mut self, _: ProcessId, _: u64, _: bool, _: u32) -> ReplayResult<()> {
+            Ok(())
+        }
+        fn replay_unmap_region(&mut self, _: ProcessId, _: u64) -> ReplayResult<()> {
+            Ok(())
+        }
+        fn replay_destroy_region(&mut self, _: u64) -> ReplayResult<()> {
+            Ok(())
+        }
+        fn snapshot_state(&self) -> Vec<u8> {
+            self.0.iter().flat_map(|id| id.to_le_bytes()).collect()
+        }
+        fn replay_restore_snapshot(&mut self, state: &[u8]) -> ReplayResult<()> {
+            self.0 = state
+                .as_chunks::<8>()
+                .0
+                .iter()
+                .map(|c| u64::from_le_bytes(*c))
+                .collect();
+            Ok(())
+        }
+        fn state_hash(&self) -> [u8; 32] {
+            let mut hasher = StateHasher::new();
+            for id in &self.0 {
+                hasher.write_u64(*id);
+            }
+            hasher.finalize()
+        }
+    }
+
+    fn take_snapshot(log: &mut CommitLog, state: &Endpoints, timestamp: u64) {
+        log.append(
+            CommitType::Snapshot {
+                state: state.snapshot_state(),
+                state_hash: state.state_hash(),
+            },
+            None,
+            timestamp,
+        );
+    }
+
+    #[test]
+    fn test_replay_from_snapshot_after_trim() {
+        let mut log = CommitLog::with_max_entries(0, 4);
+        let mut live = Endpoints::default();
+
+        for id in 1..=3 {
+            log.append(CommitType::EndpointCreated { id, owner: 1 }, None, id * 1000);
+            live.0.push(id);
+        }
+        take_snapshot(&mut log, &live, 4000);
+        for id in 5..=7 {
+            log.append(CommitType::EndpointCreated { id, owner: 1 }, None, id * 1000);
+            live.0.push(id);
+        }
+
+        // Genesis and the early endpoints have been trimmed away
+        assert!(matches!(
+            log.commits()[0].commit_type,
+            CommitType::Snapshot { .. }
+        ));
+
+        let mut replayed = Endpoints::default();
+        replay_and_verify(&mut replayed, log.commits(), live.state_hash()).unwrap();
+        assert_eq!(replayed.0, alloc::vec![1, 2, 3, 5, 6, 7]);
+    }
+
+    #[test]
+    fn test_replay_uses_newest_snapshot() {
+        let mut log = CommitLog::new(0);
+        let mut live = Endpoints::default();
+
+        log.append(CommitType::EndpointCreated { id: 1, owner: 1 }, None, 1000);
+        live.0.push(1);
+        take_snapshot(&mut log, &live, 2000);
+        log.append(CommitType::EndpointCreated { id: 2, owner: 1 }, None, 3000);
+        live.0.push(2);
+        take_snapshot(&mut log, &live, 4000);
+
+        assert_eq!(replay_start(log.commits()).unwrap(), 4);
+    }
+
+    #[test]
+    fn test_replay_rejects_trimmed_log_without_snapshot() {
+        let mut log = CommitLog::new(0);
+        log.append(CommitType::EndpointCreated { id: 1, owner: 1 }, None, 1000);
+        log.append(CommitType::EndpointCreated { id: 2, owner: 1 }, None, 2000);
+
+        let mut state = Endpoints::default();
+        let result = replay(&mut state, &log.commits()[1..]);
+        assert_eq!(result, Err(ReplayError::MissingSnapshot { first_seq: 1 }));
+    }
+
+    #[test]
+    fn test_replay_to_seq_skips_later_snapshots() {
+        let mut log = CommitLog::new(0);
+        let mut live = Endpoints::default();
+
+        for id in 1..=3 {
+            log.append(CommitType::EndpointCreated { id, owner: 1 }, None, id * 1000);
+            live.0.push(id);
+        }
+        take_snapshot(&mut log, &live, 4000);
+        log.append(CommitType::EndpointDestroyed { id: 2 }, None, 5000);
+
+        let mut at_two = Endpoints::default();
+        replay_to_seq(&mut at_two, log.commits(), 2).unwrap();
+        assert_eq!(at_two.0, alloc::vec![1, 2]);
+
+        let mut at_end = Endpoints::default();
+        replay_to_seq(&mut at_end, log.commits(), 5).unwrap();
+        assert_eq!(at_end.0, alloc::vec![1, 3]);
+
+        assert_eq!(
+            replay_to_seq(&mut Endpoints::default(), log.commits(), 6),
+            Err(ReplayError::SeqOutOfRange { seq: 6, last_seq: 5 })
+        );
+    }
+
+    #[test]
+    fn test_replay_to_seq_before_trimmed_start() {
+        let mut log = CommitLog::new(0);
+        for id in 1..=3 {
+            log.append(CommitType::EndpointCreated { id, owner: 1 }, None, id * 1000);
+        }
+
+        let result = replay_to_seq(&mut Endpoints::default(), &log.commits()[2..], 1);
+        assert_eq!(result, Err(ReplayError::MissingSnapshot { first_seq: 2 }));
+    }
+
+    #[test]
+    fn test_replay_rejects_corrupt_snapshot() {
+        let mut log = CommitLog::new(0);
+        log.append(
+            CommitType::Snapshot {
+                state: 7u64.to_le_bytes().to_vec(),
+                state_hash: [0u8; 32],
+            },
+            None,
+            1000,
+        );
+
+        let mut state = Endpoints::default();
+        let result = replay(&mut state, log.commits());
+        assert!(matches!(result, Err(ReplayError::HashMismatch { .. })));
+    }
 }
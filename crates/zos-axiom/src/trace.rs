@@ -0,0 +1,470 @@
+//! Syscall Traces for Record/Replay
+//!
+//! SysLog keeps syscall numbers and arguments for auditing, and CommitLog
+//! keeps the state mutations they caused. Neither holds what a process
+//! actually observed. A trace records the full request payload and the full
+//! result of every syscall, so one process can be re-run against exactly the
+//! inputs of a recorded run.
+//!
+//! Recording is opt-in (see `AxiomGateway::start_recording`): every payload
+//! is kept in memory until the trace is taken.
+//!
+//! # What Is Recorded
+//!
+//! - **Syscalls**: number, arguments and request data, plus the result and
+//!   response data. Received IPC messages are the response data of the
+//!   receive syscall.
+//! - **Async results**: storage, keystore and network completions as the
+//!   HAL hands them to the kernel. A process only observes them through a
+//!   later receive, so replay does not consume them; they tie a received
+//!   message back to the completion it came from.
+//!
+//! # File Format
+//!
+//! All integers are little-endian.
+//!
+//! ```text
+//! header:  magic "ZTRC" | version: u16
+//! event:   kind: u8 | pid: u64 | timestamp: u64 | body
+//! syscall: syscall_num: u32 | args: [u32; 4] | data: bytes | result: i64 | response: bytes
+//! async:   tag: u32 | payload: bytes
+//! bytes:   len: u32 | [u8; len]
+//! ```
+
+use alloc::vec::Vec;
+
+use crate::types::ProcessId;
+
+/// Magic bytes at the start of a trace file.
+const TRACE_MAGIC: &[u8; 4] = b"ZTRC";
+
+/// Current trace file format version.
+pub const TRACE_VERSION: u16 = 1;
+
+/// Event kind tags in the file format.
+const KIND_SYSCALL: u8 = 1;
+const KIND_ASYNC_RESULT: u8 = 2;
+
+/// A recorded event.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceEvent {
+    /// Process the event belongs to
+    pub pid: ProcessId,
+    /// Timestamp (nanos since boot)
+    pub timestamp: u64,
+    /// What happened
+    pub kind: TraceEventKind,
+}
+
+/// Type of recorded event.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TraceEventKind {
+    /// A completed syscall
+    Syscall {
+        /// Syscall number
+        syscall_num: u32,
+        /// Syscall arguments
+        args: [u32; 4],
+        /// Request data passed by the process
+        data: Vec<u8>,
+        /// Syscall result (negative = error)
+        result: i64,
+        /// Response data returned to the process
+        response: Vec<u8>,
+    },
+    /// An async HAL result delivered to the process as an IPC message
+    AsyncResult {
+        /// IPC tag of the result message (e.g. MSG_STORAGE_RESULT)
+        tag: u32,
+        /// Result message payload
+        payload: Vec<u8>,
+    },
+}
+
+/// Errors decoding a trace file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TraceError {
+    /// File does not start with the trace magic
+    BadMagic,
+    /// File was written by an unknown format version
+    UnsupportedVersion(u16),
+    /// File ends in the middle of an event
+    Truncated,
+    /// Unknown event kind tag
+    UnknownEventKind(u8),
+}
+
+/// A recorded sequence of syscalls and async results, in kernel order.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SyscallTrace {
+    events: Vec<TraceEvent>,
+}
+
+impl SyscallTrace {
+    /// Create an empty trace.
+    pub fn new() -> Self {
+        Self { events: Vec::new() }
+    }
+
+    /// Append an event.
+    pub fn push(&mut self, event: TraceEvent) {
+        self.events.push(event);
+    }
+
+    /// Get all events.
+    pub fn events(&self) -> &[TraceEvent] {
+        &self.events
+    }
+
+    /// Get the events of one process.
+    pub fn events_for(&self, pid: ProcessId) -> impl Iterator<Item = &TraceEvent> {
+        self.events.iter().filter(move |e| e.pid == pid)
+    }
+
+    /// Get the number of events.
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Check if the trace is empty.
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Encode the trace in the trace file format.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(TRACE_MAGIC);
+        out.extend_from_slice(&TRACE_VERSION.to_le_bytes());
+
+        for event in &self.events {
+            let kind = match event.kind {
+                TraceEventKind::Syscall { .. } => KIND_SYSCALL,
+                TraceEventKind::AsyncResult { .. } => KIND_ASYNC_RESULT,
+            };
+            out.push(kind);
+            out.extend_from_slice(&event.pid.to_le_bytes());
+            out.extend_from_slice(&event.timestamp.to_le_bytes());
+
+            match &event.kind {
+                TraceEventKind::Syscall {
+                    syscall_num,
+                    args,
+                    data,
+                    result,
+                    response,
+                } => {
+                    out.extend_from_slice(&syscall_num.to_le_bytes());
+                    for arg in args {
+                        out.extend_from_slice(&arg.to_le_bytes());
+                    }
+                    put_bytes(&mut out, data);
+                    out.extend_from_slice(&result.to_le_bytes());
+                    put_bytes(&mut out, response);
+                }
+                TraceEventKind::AsyncResult { tag, payload } => {
+                    out.extend_from_slice(&tag.to_le_bytes());
+                    put_bytes(&mut out, payload);
+                }
+            }
+        }
+        out
+    }
+
+    /// Decode a trace file.
+    pub fn decode(bytes: &[u8]) -> Result<Self, TraceError> {
+        let mut reader = Reader { bytes };
+        if reader.take(4)? != TRACE_MAGIC {
+            return Err(TraceError::BadMagic);
+        }
+        let version = u16::from_le_bytes(reader.array()?);
+        if version != TRACE_VERSION {
+            return Err(TraceError::UnsupportedVersion(version));
+        }
+
+        let mut trace = Self::new();
+        while !reader.bytes.is_empty() {
+            let kind_tag = reader.take(1)?[0];
+            let pid = reader.u64()?;
+            let timestamp = reader.u64()?;
+            let kind = match kind_tag {
+                KIND_SYSCALL => TraceEventKind::Syscall {
+                    syscall_num: reader.u32()?,
+                    args: [reader.u32()?, reader.u32()?, reader.u32()?, reader.u32()?],
+                    data: reader.bytes()?,
+                    result: i64::from_le_bytes(reader.array()?),
+                    response: reader.bytes()?,
+                },
+                KIND_ASYNC_RESULT => TraceEventKind::AsyncResult {
+                    tag: reader.u32()?,
+                    payload: reader.bytes()?,
+                },
+                other => return Err(TraceError::UnknownEventKind(other)),
+            };
+            trace.push(TraceEvent {
+                pid,
+                timestamp,
+                kind,
+            });
+        }
+        Ok(trace)
+    }
+}
+
+/// Why a replayed process stopped matching its recording.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TraceDivergence {
+    /// The process made more syscalls than were recorded
+    Exhausted {
+        /// Index of the extra syscall
+        index: usize,
+    },
+    /// The process made a different syscall than the recorded one
+    Mismatch {
+        /// Index of the syscall in the process's recording
+        index: usize,
+        /// Recorded syscall number
+        expected: u32,
+        /// Syscall number the process made
+        actual: u32,
+    },
+    /// The process stopped before making every recorded syscall
+    Incomplete {
+        /// Number of recorded syscalls not made
+        remaining: usize,
+    },
+}
+
+/// The recorded outcome of one syscall.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RecordedResult<'a> {
+    /// Syscall result
+    pub result: i64,
+    /// Response data
+    pub response: &'a [u8],
+    /// Timestamp the syscall was handled at
+    pub timestamp: u64,
+}
+
+/// Feeds one process its recorded syscall results, in order.
+///
+/// Each syscall the process makes must match the next recorded one
+/// (number, arguments and request data). A deterministic process therefore
+/// takes exactly the recorded path; anything else is a divergence.
+pub struct ProcessReplay<'a> {
+    syscalls: Vec<&'a TraceEvent>,
+    next: usize,
+}
+
+impl<'a> ProcessReplay<'a> {
+    /// Prepare to replay the syscalls `pid` made in `trace`.
+    pub fn new(trace: &'a SyscallTrace, pid: ProcessId) -> Self {
+        Self {
+            syscalls: trace
+                .events_for(pid)
+                .filter(|e| matches!(e.kind, TraceEventKind::Syscall { .. }))
+                .collect(),
+            next: 0,
+        }
+    }
+
+    /// Return the recorded result for the process's next syscall.
+    pub fn next_result(
+        &mut self,
+        syscall_num: u32,
+        args: [u32; 4],
+        data: &[u8],
+    ) -> Result<RecordedResult<'a>, TraceDivergence> {
+        let index = self.next;
+        let event = *self
+            .syscalls
+            .get(index)
+            .ok_or(TraceDivergence::Exhausted { index })?;
+        let TraceEventKind::Syscall {
+            syscall_num: expected,
+            args: recorded_args,
+            data: recorded_data,
+            result,
+            response,
+        } = &event.kind
+        else {
+            unreachable!("ProcessReplay only holds syscall events");
+        };
+
+        if *expected != syscall_num || *recorded_args != args || recorded_data[..] != *data {
+            return Err(TraceDivergence::Mismatch {
+                index,
+                expected: *expected,
+                actual: syscall_num,
+            });
+        }
+
+        self.next += 1;
+        Ok(RecordedResult {
+            result: *result,
+            response,
+            timestamp: event.timestamp,
+        })
+    }
+
+    /// Number of recorded syscalls not replayed yet.
+    pub fn remaining(&self) -> usize {
+        self.syscalls.len() - self.next
+    }
+
+    /// Check if every recorded syscall has been replayed.
+    pub fn is_finished(&self) -> bool {
+        self.remaining() == 0
+    }
+
+    /// Finish the replay, failing if recorded syscalls were left over.
+    pub fn finish(&self) -> Result<(), TraceDivergence> {
+        match self.remaining() {
+            0 => Ok(()),
+            remaining => Err(TraceDivergence::Incomplete { remaining }),
+        }
+    }
+}
+
+fn put_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    out.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    out.extend_from_slice(bytes);
+}
+
+/// Cursor over an encoded trace.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], TraceError> {
+        if self.bytes.len() < len {
+            return Err(TraceError::Truncated);
+        }
+        let (head, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], TraceError> {
+        let mut out = [0u8; N];
+        out.copy_from_slice(self.take(N)?);
+        Ok(out)
+    }
+
+    fn u32(&mut self) -> Result<u32, TraceError> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn u64(&mut self) -> Result<u64, TraceError> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    fn bytes(&mut self) -> Result<Vec<u8>, TraceError> {
+        let len = self.u32()? as usize;
+        Ok(self.take(len)?.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn syscall(pid: ProcessId, syscall_num: u32, data: &[u8], result: i64) -> TraceEvent {
+        TraceEvent {
+            pid,
+            timestamp: syscall_num as u64 * 100,
+            kind: TraceEventKind::Syscall {
+                syscall_num,
+                args: [1, 2, 3, 4],
+                data: data.to_vec(),
+                result,
+                response: alloc::vec![result as u8; 3],
+            },
+        }
+    }
+
+    fn sample_trace() -> SyscallTrace {
+        let mut trace = SyscallTrace::new();
+        trace.push(syscall(1, 0x40, b"hello", 0));
+        trace.push(syscall(2, 0x41, b"", 7));
+        trace.push(TraceEvent {
+            pid: 1,
+            timestamp: 500,
+            kind: TraceEventKind::AsyncResult {
+                tag: 0x80,
+                payload: alloc::vec![9, 9],
+            },
+        });
+        trace.push(syscall(1, 0x41, b"", 1));
+        trace
+    }
+
+    #[test]
+    fn test_trace_roundtrip() {
+        let trace = sample_trace();
+        let bytes = trace.encode();
+        assert_eq!(&bytes[..4], TRACE_MAGIC);
+        assert_eq!(SyscallTrace::decode(&bytes), Ok(trace));
+    }
+
+    #[test]
+    fn test_trace_decode_errors() {
+        let bytes = sample_trace().encode();
+        assert_eq!(
+            SyscallTrace::decode(&bytes[..bytes.len() - 1]),
+            Err(TraceError::Truncated)
+        );
+        assert_eq!(SyscallTrace::decode(b"ZTRX\x01\x00"), Err(TraceError::BadMagic));
+        assert_eq!(
+            SyscallTrace::decode(b"ZTRC\x02\x00"),
+            Err(TraceError::UnsupportedVersion(2))
+        );
+
+        let mut bad_kind = bytes[..6].to_vec();
+        bad_kind.extend_from_slice(&[7; 17]);
+        assert_eq!(
+            SyscallTrace::decode(&bad_kind),
+            Err(TraceError::UnknownEventKind(7))
+        );
+    }
+
+    #[test]
+    fn test_process_replay_feeds_one_process() {
+        let trace = sample_trace();
+        let mut replay = ProcessReplay::new(&trace, 1);
+        assert_eq!(replay.remaining(), 2);
+        assert_eq!(replay.finish(), Err(TraceDivergence::Incomplete { remaining: 2 }));
+
+        let first = replay.next_result(0x40, [1, 2, 3, 4], b"hello").unwrap();
+        assert_eq!(first.result, 0);
+        assert_eq!(first.timestamp, 0x40 * 100);
+        let second = replay.next_result(0x41, [1, 2, 3, 4], b"").unwrap();
+        assert_eq!((second.result, second.response), (1, &[1u8, 1, 1][..]));
+        assert!(replay.is_finished());
+        assert_eq!(replay.finish(), Ok(()));
+
+        assert_eq!(
+            replay.next_result(0x41, [1, 2, 3, 4], b""),
+            Err(TraceDivergence::Exhausted { index: 2 })
+        );
+    }
+
+    #[test]
+    fn test_process_replay_detects_divergence() {
+        let trace = sample_trace();
+        let mut replay = ProcessReplay::new(&trace, 1);
+
+        assert_eq!(
+            replay.next_result(0x41, [1, 2, 3, 4], b"hello"),
+            Err(TraceDivergence::Mismatch {
+                index: 0,
+                expected: 0x40,
+                actual: 0x41
+            })
+        );
+        // Different request data is a divergence too
+        assert!(replay.next_result(0x40, [1, 2, 3, 4], b"bye").is_err());
+        assert_eq!(replay.remaining(), 2);
+    }
+}
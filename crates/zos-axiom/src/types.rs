@@ -97,6 +97,10 @@ pub enum ObjectType {
     IoPort = 5,
     /// Console/debug output
     Console = 6,
+    /// Notification (signal word)
+    Notification = 12,
+    /// One-shot reply right for an outstanding call
+    Reply = 13,
 }
 
 impl ObjectType {
@@ -109,11 +113,116 @@ impl ObjectType {
             4 => Some(ObjectType::Irq),
             5 => Some(ObjectType::IoPort),
             6 => Some(ObjectType::Console),
+            12 => Some(ObjectType::Notification),
+            13 => Some(ObjectType::Reply),
             _ => None,
         }
     }
 }
 
+/// Per-process resource limits. A limit of 0 means unlimited.
+///
+/// The wire encoding (SYS_SET_QUOTA, SYS_SPAWN_PROCESS, SYS_PS) is the five
+/// limits in declaration order as little-endian `u64`s.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ResourceQuota {
+    /// Process memory (bytes)
+    pub max_memory: u64,
+    /// Endpoints owned
+    pub max_endpoints: u64,
+    /// Occupied capability slots
+    pub max_cap_slots: u64,
+    /// Bytes sent by the process that are still queued
+    pub max_queued_bytes: u64,
+    /// Syscalls per second
+    pub max_syscalls_per_sec: u64,
+}
+
+impl ResourceQuota {
+    /// Size of the wire encoding in bytes
+    pub const ENCODED_SIZE: usize = 40;
+
+    /// Get the limit for a resource (0 = unlimited)
+    pub fn limit(&self, resource: QuotaResource) -> u64 {
+        match resource {
+            QuotaResource::Memory => self.max_memory,
+            QuotaResource::Endpoints => self.max_endpoints,
+            QuotaResource::CapSlots => self.max_cap_slots,
+            QuotaResource::QueuedBytes => self.max_queued_bytes,
+            QuotaResource::SyscallRate => self.max_syscalls_per_sec,
+        }
+    }
+
+    /// Check if no limit is set
+    pub fn is_unlimited(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Check if every limit is at least as strict as the one in `other`
+    pub fn is_within(&self, other: &Self) -> bool {
+        QuotaResource::ALL.iter().all(|&r| {
+            let (mine, theirs) = (self.limit(r), other.limit(r));
+            theirs == 0 || (mine != 0 && mine <= theirs)
+        })
+    }
+
+    /// Convert to wire encoding
+    pub fn to_bytes(&self) -> [u8; Self::ENCODED_SIZE] {
+        let mut out = [0u8; Self::ENCODED_SIZE];
+        for (i, resource) in QuotaResource::ALL.into_iter().enumerate() {
+            out[i * 8..i * 8 + 8].copy_from_slice(&self.limit(resource).to_le_bytes());
+        }
+        out
+    }
+
+    /// Create from wire encoding (`None` if `bytes` is too short)
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let field = |i: usize| -> Option<u64> {
+            let chunk = bytes.get(i * 8..i * 8 + 8)?;
+            Some(u64::from_le_bytes(chunk.try_into().ok()?))
+        };
+        Some(Self {
+            max_memory: field(0)?,
+            max_endpoints: field(1)?,
+            max_cap_slots: field(2)?,
+            max_queued_bytes: field(3)?,
+            max_syscalls_per_sec: field(4)?,
+        })
+    }
+}
+
+/// A resource limited by [`ResourceQuota`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[repr(u8)]
+pub enum QuotaResource {
+    /// `max_memory`
+    Memory = 1,
+    /// `max_endpoints`
+    Endpoints = 2,
+    /// `max_cap_slots`
+    CapSlots = 3,
+    /// `max_queued_bytes`
+    QueuedBytes = 4,
+    /// `max_syscalls_per_sec`
+    SyscallRate = 5,
+}
+
+impl QuotaResource {
+    /// Every quota resource, in wire encoding order
+    pub const ALL: [QuotaResource; 5] = [
+        QuotaResource::Memory,
+        QuotaResource::Endpoints,
+        QuotaResource::CapSlots,
+        QuotaResource::QueuedBytes,
+        QuotaResource::SyscallRate,
+    ];
+
+    /// Convert from u8
+    pub fn from_u8(v: u8) -> Option<Self> {
+        Self::ALL.into_iter().find(|r| *r as u8 == v)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -137,4 +246,36 @@ mod tests {
         assert_eq!(ObjectType::from_u8(2), Some(ObjectType::Process));
         assert_eq!(ObjectType::from_u8(99), None);
     }
+
+    #[test]
+    fn test_quota_bytes_roundtrip() {
+        let quota = ResourceQuota {
+            max_memory: 1 << 20,
+            max_endpoints: 4,
+            max_cap_slots: 16,
+            max_queued_bytes: 4096,
+            max_syscalls_per_sec: 1000,
+        };
+        assert_eq!(ResourceQuota::from_bytes(&quota.to_bytes()), Some(quota));
+        assert_eq!(ResourceQuota::from_bytes(&quota.to_bytes()[..39]), None);
+        assert!(ResourceQuota::default().is_unlimited());
+    }
+
+    #[test]
+    fn test_quota_is_within() {
+        let parent = ResourceQuota {
+            max_endpoints: 4,
+            ..Default::default()
+        };
+        let tighter = ResourceQuota {
+            max_endpoints: 2,
+            max_cap_slots: 10,
+            ..Default::default()
+        };
+        assert!(tighter.is_within(&parent));
+        assert!(parent.is_within(&ResourceQuota::default()));
+
+        // Dropping a limit the parent has is not allowed
+        assert!(!ResourceQuota::default().is_within(&parent));
+    }
 }
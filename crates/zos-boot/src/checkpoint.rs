@@ -0,0 +1,68 @@
+//! Axiom checkpoint signing key
+//!
+//! The kernel signs periodic checkpoints of its CommitLog with an Ed25519
+//! key whose seed lives in the encrypted keystore, under the namespace the
+//! kernel reserves for itself ([`zos_kernel::KERNEL_KEYSTORE_PREFIX`]). The
+//! CommitLog snapshot sits on the separate storage disk, so rewriting it
+//! there cannot produce checkpoints that verify at the next boot.
+
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use zos_hal::x86_64::{keystore, X86_64Hal};
+use zos_hal::HAL;
+use zos_kernel::{CheckpointSigner, CheckpointVerifier};
+
+/// Keystore key holding the 32-byte checkpoint signing seed
+pub const CHECKPOINT_KEY: &str = "/kernel/checkpoint_key";
+
+/// Commits between signed checkpoints (same as the WASM supervisor)
+pub const CHECKPOINT_INTERVAL: u64 = 256;
+
+/// Signs checkpoints with the machine's Ed25519 checkpoint key
+pub struct Ed25519Signer {
+    key: SigningKey,
+}
+
+impl Ed25519Signer {
+    /// Load the signing key from the keystore, creating it on first boot.
+    ///
+    /// Returns `None` if the keystore is not mounted or the stored seed is
+    /// malformed.
+    pub fn load_or_create(hal: &X86_64Hal) -> Option<Self> {
+        let seed: [u8; 32] = match keystore::read(CHECKPOINT_KEY).ok()? {
+            Some(bytes) => bytes.as_slice().try_into().ok()?,
+            None => {
+                let mut seed = [0u8; 32];
+                hal.random_bytes(&mut seed).ok()?;
+                keystore::write(CHECKPOINT_KEY, &seed).ok()?;
+                seed
+            }
+        };
+        Some(Self {
+            key: SigningKey::from_bytes(&seed),
+        })
+    }
+}
+
+impl CheckpointSigner for Ed25519Signer {
+    fn public_key(&self) -> [u8; 32] {
+        self.key.verifying_key().to_bytes()
+    }
+
+    fn sign(&self, message: &[u8]) -> [u8; 64] {
+        self.key.sign(message).to_bytes()
+    }
+}
+
+/// Verifies Ed25519 checkpoint signatures
+pub struct Ed25519Verifier;
+
+impl CheckpointVerifier for Ed25519Verifier {
+    fn verify(&self, public_key: &[u8; 32], message: &[u8], signature: &[u8; 64]) -> bool {
+        match VerifyingKey::from_bytes(public_key) {
+            Ok(key) => key
+                .verify_strict(message, &Signature::from_bytes(signature))
+                .is_ok(),
+            Err(_) => false,
+        }
+    }
+}
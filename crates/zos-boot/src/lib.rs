@@ -25,6 +25,7 @@
 //!
 //! This crate only contains:
 //! - Kernel heap allocator (static allocation)
+//! - Axiom checkpoint signing key (kept in the keystore)
 //! - Boot constants (name, version)
 
 #![no_std]
@@ -32,6 +33,7 @@
 extern crate alloc;
 
 pub mod allocator;
+pub mod checkpoint;
 
 /// Kernel version
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -17,11 +17,13 @@ extern crate alloc;
 use bootloader_api::info::MemoryRegionKind as BootMemoryRegionKind;
 use bootloader_api::{entry_point, BootInfo, BootloaderConfig};
 use core::panic::PanicInfo;
-use serde::{Deserialize, Serialize};
 use zos_hal::x86_64::vmm::{MemoryRegionDescriptor, MemoryRegionKind};
 use zos_hal::x86_64::X86_64Hal;
-use zos_hal::{serial_println, HAL};
-use zos_kernel::{replay_and_verify, Replayable, System};
+use zos_hal::{serial_println, Priority, HAL};
+use zos_boot::checkpoint::{Ed25519Signer, Ed25519Verifier, CHECKPOINT_INTERVAL};
+use zos_kernel::{
+    axiom_replay, replay_and_verify, CheckpointSigner, CommitLog, CommitLogFile, Replayable, System,
+};
 
 /// The global x86_64 HAL instance
 static HAL: X86_64Hal = X86_64Hal::new();
@@ -34,21 +36,20 @@ pub static BOOTLOADER_CONFIG: BootloaderConfig = {
     config
 };
 
-/// Storage key for persisted CommitLog snapshot
-const COMMITLOG_KEY: &str = "/axiom/commitlog.json";
-
-/// Persisted commit log snapshot for replay
-#[derive(Debug, Serialize, Deserialize)]
-struct CommitLogSnapshot {
-    commits: alloc::vec::Vec<zos_kernel::Commit>,
-    state_hash: [u8; 32],
-}
+/// Storage key for persisted CommitLog snapshot (zos-axiom log file format)
+const COMMITLOG_KEY: &str = "/axiom/commitlog.bin";
 
 entry_point!(kernel_main, config = &BOOTLOADER_CONFIG);
 
 /// Maximum iterations for the main loop (safety limit for testing)
 const MAX_MAIN_LOOP_ITERATIONS: u64 = 10000;
 
+/// Main loop iterations Init gets to stop services once asked to
+const SHUTDOWN_GRACE_ITERATIONS: u64 = 2000;
+
+/// What Init prints on the debug channel once its services are stopped
+const INIT_SHUTDOWN_READY: &str = "INIT:SHUTDOWN_READY";
+
 /// Find the terminal process PID if it exists
 fn find_terminal_pid(system: &System<X86_64Hal>) -> Option<zos_kernel::ProcessId> {
     for (pid, process) in system.list_processes() {
@@ -66,32 +67,65 @@ fn find_terminal_pid(system: &System<X86_64Hal>) -> Option<zos_kernel::ProcessId
 /// 2. Dispatches syscalls through the Axiom verification layer
 /// 3. Completes syscalls and resumes processes
 /// 4. Routes serial input to terminal process
+///
+/// When Init requests it (SYS_SHUTDOWN) or at the iteration limit it shuts
+/// the system down cleanly: Init stops the services, then the CommitLog is
+/// persisted. It returns when the machine can be powered off, with `true`
+/// if it should reboot instead.
 fn run_kernel_main_loop(
     system: &mut System<X86_64Hal>,
     hal: &X86_64Hal,
     storage_ready: bool,
-) {
+) -> bool {
     let mut iteration = 0u64;
     let mut syscall_count = 0u64;
     let start_time = hal.now_nanos();
+    let mut shutdown_deadline: Option<u64> = None;
+    let mut init_stopped = false;
+    let mut reboot = false;
 
     // Run the main loop
     loop {
         iteration += 1;
 
-        // Safety limit for testing - exit after MAX iterations
-        if iteration >= MAX_MAIN_LOOP_ITERATIONS {
-            serial_println!();
-            serial_println!("[kernel] Main loop iteration limit reached ({} iterations)", iteration);
-            serial_println!("[kernel] Total syscalls processed: {}", syscall_count);
-            serial_println!("[kernel] Runtime: {} ms", (hal.now_nanos() - start_time) / 1_000_000);
-            
-            // Persist CommitLog before exit
-            if storage_ready {
-                persist_commitlog(system, hal);
+        // Safety limit for testing - shut down after MAX iterations
+        match shutdown_deadline {
+            None if iteration >= MAX_MAIN_LOOP_ITERATIONS => {
+                serial_println!();
+                serial_println!("[kernel] Main loop iteration limit reached ({} iterations)", iteration);
+                serial_println!("[kernel] Total syscalls processed: {}", syscall_count);
+                serial_println!("[kernel] Runtime: {} ms", (hal.now_nanos() - start_time) / 1_000_000);
+
+                // Keep running processes so Init can stop the services
+                request_shutdown(system, false);
+                shutdown_deadline = Some(iteration + SHUTDOWN_GRACE_ITERATIONS);
             }
-            
-            break;
+            None => {
+                // Power-off or reboot accepted from Init (terminal `shutdown`/`reboot`)
+                if let Some(requested_reboot) = system.take_shutdown_request() {
+                    serial_println!();
+                    serial_println!(
+                        "[kernel] {} requested",
+                        if requested_reboot { "Reboot" } else { "Power-off" }
+                    );
+                    reboot = requested_reboot;
+                    request_shutdown(system, reboot);
+                    shutdown_deadline = Some(iteration + SHUTDOWN_GRACE_ITERATIONS);
+                }
+            }
+            Some(deadline) if init_stopped || iteration >= deadline => {
+                if !init_stopped {
+                    serial_println!("[kernel] Init did not confirm shutdown, stopping anyway");
+                }
+
+                // Persist CommitLog (including the service exits) before power-off
+                if storage_ready {
+                    persist_commitlog(system, hal);
+                }
+
+                break;
+            }
+            _ => {}
         }
 
         // Log progress periodically (disabled for clean output)
@@ -120,8 +154,11 @@ fn run_kernel_main_loop(
                     if !text.is_empty() {
                         serial_println!("{}", text);
                     }
+                    if syscall.pid == 1 && text == INIT_SHUTDOWN_READY {
+                        init_stopped = true;
+                    }
                 }
-                return (0i64, alloc::vec::Vec::new());
+                return Some((0i64, alloc::vec::Vec::new()));
             }
 
             // Debug IPC syscalls - only log actual sends (not idle recv polling)
@@ -145,6 +182,12 @@ fn run_kernel_main_loop(
             let (result, _rich_result, response_data) =
                 system.process_syscall(sender, syscall_num, args, &syscall.data);
 
+            // A timed receive/call with nothing to return yet stays blocked
+            // until resume_parked_syscalls() answers it
+            if system.is_parked(sender) {
+                return None;
+            }
+
             // Debug IPC results - only log sends and successful receives
             if syscall_num == 0x40 {
                 serial_println!("[kernel] SYS_SEND result: {}", result);
@@ -160,12 +203,41 @@ fn run_kernel_main_loop(
                 serial_println!("[kernel] Created endpoint {} for target, Init's cap at slot {}", endpoint_id, init_slot);
             }
 
-            (result, response_data)
+            Some((result, response_data))
         });
 
+        // Collect block I/O completions and deliver finished storage operations
+        deliver_storage_results(system);
+
+        // Deliver completed keystore operations to their requesters
+        deliver_keystore_results(system);
+
+        // Drive the network stack and deliver finished fetches
+        deliver_network_results(system);
+
+        // Hand device interrupts to the drivers that bound them
+        deliver_irqs(system);
+
+        // Wake processes whose timed receive/call got a message or timed out
+        resume_parked_syscalls(system, hal);
+
         // Note: removed hlt() to ensure continuous polling for serial input
         // This uses more CPU but ensures responsive input handling
     }
+
+    reboot
+}
+
+/// Answer parked timed receives, calls and notification waits whose wait
+/// ended.
+///
+/// The processes stay blocked in the scheduler until their result is
+/// delivered here. Deadlines are checked against the APIC clock once per
+/// main loop iteration.
+fn resume_parked_syscalls(system: &mut System<X86_64Hal>, hal: &X86_64Hal) {
+    for (pid, result, data) in system.resume_parked() {
+        hal.complete_syscall(pid.0, result, &data);
+    }
 }
 
 /// Route serial input to terminal via Init (MSG_SUPERVISOR_CONSOLE_INPUT).
@@ -215,23 +287,109 @@ fn route_serial_input_to_init(system: &mut System<X86_64Hal>) {
     }
 }
 
+/// Ask Init to stop all services (MSG_SUPERVISOR_SHUTDOWN).
+///
+/// Init answers with `INIT:SHUTDOWN_READY` on the debug channel once the
+/// processes it started are terminated.
+fn request_shutdown(system: &mut System<X86_64Hal>, reboot: bool) {
+    // MSG_SUPERVISOR_SHUTDOWN tag (from zos-ipc)
+    const MSG_SUPERVISOR_SHUTDOWN: u32 = 0x200A;
+
+    serial_println!("[kernel] Asking Init to stop services...");
+    if let Err(e) = system.inject_to_init(MSG_SUPERVISOR_SHUTDOWN, &[reboot as u8]) {
+        serial_println!("[kernel] Failed to send shutdown request to Init: {:?}", e);
+    }
+}
+
+/// Deliver an async result to a process via Init (MSG_SUPERVISOR_IPC_DELIVERY).
+///
+/// This is the QEMU equivalent of the JS supervisor's `route_ipc_via_init`:
+/// the result is recorded for replay, then Init forwards it to the target
+/// process's input endpoint under `tag`.
+fn deliver_via_init(
+    system: &mut System<X86_64Hal>,
+    pid: u64,
+    tag: u32,
+    data: &[u8],
+) -> Result<(), zos_kernel::KernelError> {
+    // MSG_SUPERVISOR_IPC_DELIVERY tag (from zos-ipc)
+    const MSG_SUPERVISOR_IPC_DELIVERY: u32 = 0x2003;
+
+    // Service input endpoint slot
+    const SERVICE_INPUT_SLOT: u32 = 1;
+
+    system.record_async_result(zos_kernel::ProcessId(pid), tag, data);
+
+    // Build message for Init: [target_pid: u32, endpoint_slot: u32, tag: u32, data_len: u16, data: [u8]]
+    let mut payload = alloc::vec::Vec::with_capacity(14 + data.len());
+    payload.extend_from_slice(&(pid as u32).to_le_bytes());
+    payload.extend_from_slice(&SERVICE_INPUT_SLOT.to_le_bytes());
+    payload.extend_from_slice(&tag.to_le_bytes());
+    payload.extend_from_slice(&(data.len() as u16).to_le_bytes());
+    payload.extend_from_slice(data);
+
+    system.inject_to_init(MSG_SUPERVISOR_IPC_DELIVERY, &payload)
+}
+
+/// Deliver finished storage operations to their requesters as MSG_STORAGE_RESULT.
+fn deliver_storage_results(system: &mut System<X86_64Hal>) {
+    // MSG_STORAGE_RESULT tag (from zos-ipc)
+    const MSG_STORAGE_RESULT: u32 = 0x80;
+
+    for (pid, result) in system.hal().take_storage_results() {
+        if let Err(e) = deliver_via_init(system, pid, MSG_STORAGE_RESULT, &result) {
+            serial_println!("[storage] Failed to deliver result to PID {}: {:?}", pid, e);
+        }
+    }
+}
+
+/// Deliver completed keystore results to their requesters as MSG_KEYSTORE_RESULT.
+fn deliver_keystore_results(system: &mut System<X86_64Hal>) {
+    // MSG_KEYSTORE_RESULT tag (from zos-ipc)
+    const MSG_KEYSTORE_RESULT: u32 = 0x81;
+
+    for (pid, result) in system.hal().take_keystore_results() {
+        if let Err(e) = deliver_via_init(system, pid, MSG_KEYSTORE_RESULT, &result) {
+            serial_println!("[keystore] Failed to deliver result to PID {}: {:?}", pid, e);
+        }
+    }
+}
+
+/// Poll the network stack and deliver finished fetches as MSG_NET_RESULT.
+fn deliver_network_results(system: &mut System<X86_64Hal>) {
+    // MSG_NET_RESULT tag (from zos-ipc)
+    const MSG_NET_RESULT: u32 = 0x9002;
+
+    for (pid, result) in system.hal().take_network_results() {
+        if let Err(e) = deliver_via_init(system, pid, MSG_NET_RESULT, &result) {
+            serial_println!("[net] Failed to deliver result to PID {}: {:?}", pid, e);
+        }
+    }
+}
+
+/// Deliver driver IRQs that fired since the last iteration.
+///
+/// Each line stays masked until its driver acknowledges it with SYS_IRQ_ACK.
+fn deliver_irqs(system: &mut System<X86_64Hal>) {
+    for irq in system.hal().take_irqs() {
+        system.interrupt(irq);
+    }
+}
+
 /// Persist CommitLog snapshot to storage
 fn persist_commitlog(system: &System<X86_64Hal>, hal: &X86_64Hal) {
-    let snapshot = CommitLogSnapshot {
+    let snapshot = CommitLogFile {
         commits: system.commitlog().commits().to_vec(),
-        state_hash: system.state_hash(),
+        state_hash: Some(system.state_hash()),
+        checkpoints: system.checkpoints().to_vec(),
     };
-    match serde_json::to_vec(&snapshot) {
-        Ok(bytes) => match hal.bootstrap_storage_put_inode(COMMITLOG_KEY, &bytes) {
-            Ok(()) => {
-                serial_println!("[replay] CommitLog snapshot persisted ({} bytes)", bytes.len());
-            }
-            Err(e) => {
-                serial_println!("[replay] Failed to persist CommitLog snapshot: {:?}", e);
-            }
-        },
+    let bytes = snapshot.encode();
+    match hal.bootstrap_storage_put_inode(COMMITLOG_KEY, &bytes) {
+        Ok(()) => {
+            serial_println!("[replay] CommitLog snapshot persisted ({} bytes)", bytes.len());
+        }
         Err(e) => {
-            serial_println!("[replay] Failed to serialize CommitLog snapshot: {:?}", e);
+            serial_println!("[replay] Failed to persist CommitLog snapshot: {:?}", e);
         }
     }
 }
@@ -269,9 +427,9 @@ fn kernel_main(boot_info: &'static mut BootInfo) -> ! {
         })
         .collect();
 
-    // Initialize the HAL (serial, GDT, IDT, VMM)
+    // Initialize the HAL (serial, GDT, IDT, VMM, ACPI, APIC)
     unsafe {
-        HAL.init(phys_mem_offset, &memory_regions);
+        HAL.init(phys_mem_offset, &memory_regions, boot_info.rsdp_addr.into_option());
     }
 
     // Print the boot message
@@ -296,6 +454,27 @@ fn kernel_main(boot_info: &'static mut BootInfo) -> ! {
 
     serial_println!("Physical memory offset: 0x{:X}", phys_mem_offset);
 
+    // Print what the firmware's ACPI tables describe
+    match zos_hal::x86_64::acpi::info() {
+        Some(acpi) => {
+            if let Some(madt) = &acpi.madt {
+                serial_println!(
+                    "ACPI: {} CPU(s), {} I/O APIC(s), {} IRQ override(s)",
+                    madt.local_apics.iter().filter(|l| l.enabled).count(),
+                    madt.io_apics.len(),
+                    madt.overrides.len()
+                );
+            }
+            let reset = acpi.fadt.as_ref().is_some_and(|fadt| fadt.reset.is_some());
+            serial_println!(
+                "ACPI: power-off {}, reset register {}",
+                if acpi.s5.is_some() { "via S5" } else { "unavailable" },
+                if reset { "present" } else { "absent" }
+            );
+        }
+        None => serial_println!("ACPI: no RSDP from bootloader"),
+    }
+
     // Print memory map summary
     serial_println!();
     serial_println!("Memory regions:");
@@ -342,14 +521,36 @@ fn kernel_main(boot_info: &'static mut BootInfo) -> ! {
         }
     };
 
+    // Mount the encrypted keystore (second virtio disk, key via fw_cfg)
+    match HAL.keystore_init() {
+        Ok(true) => serial_println!("[keystore] New keystore initialized"),
+        Ok(false) => serial_println!("[keystore] Existing keystore mounted"),
+        Err(e) => serial_println!("[keystore] Keystore unavailable: {:?}", e),
+    }
+
+    // Checkpoints are signed with a key that only lives in the keystore
+    let checkpoint_signer = Ed25519Signer::load_or_create(&HAL);
+    if checkpoint_signer.is_none() {
+        serial_println!("[axiom] No checkpoint key, checkpoints will not be signed");
+    }
+
+    // Bring up the TCP/IP stack (virtio-net, DHCP runs from the main loop)
+    if let Err(e) = HAL.network_init() {
+        serial_println!("[net] Network unavailable: {:?}", e);
+    }
+
     // Stage 2.7 Test: CommitLog replay from storage
     if storage_ready {
         match HAL.bootstrap_storage_get_inode(COMMITLOG_KEY) {
-            Ok(Some(data)) => match serde_json::from_slice::<CommitLogSnapshot>(&data) {
+            Ok(Some(data)) => match CommitLogFile::decode(&data) {
                 Ok(snapshot) => {
                     serial_println!("[replay] Found CommitLog snapshot ({} commits)", snapshot.commits.len());
                     let mut replay_system: System<X86_64Hal> = System::new_for_replay();
-                    match replay_and_verify(&mut replay_system, &snapshot.commits, snapshot.state_hash) {
+                    let result = match snapshot.state_hash {
+                        Some(hash) => replay_and_verify(&mut replay_system, &snapshot.commits, hash),
+                        None => axiom_replay(&mut replay_system, &snapshot.commits),
+                    };
+                    match result {
                         Ok(()) => {
                             serial_println!("[replay] CommitLog replay verified");
                         }
@@ -357,6 +558,32 @@ fn kernel_main(boot_info: &'static mut BootInfo) -> ! {
                             serial_println!("[replay] CommitLog replay failed: {:?}", e);
                         }
                     }
+                    match &checkpoint_signer {
+                        Some(signer) => {
+                            let verified = CommitLog::verify_checkpoints_in(
+                                &snapshot.commits,
+                                &snapshot.checkpoints,
+                                &signer.public_key(),
+                                &Ed25519Verifier,
+                            );
+                            if verified {
+                                serial_println!(
+                                    "[replay] {} checkpoint(s) verified",
+                                    snapshot.checkpoints.len()
+                                );
+                            } else {
+                                serial_println!(
+                                    "[replay] Checkpoint verification failed: CommitLog was not signed by this machine"
+                                );
+                            }
+                        }
+                        None => {
+                            serial_println!(
+                                "[replay] {} checkpoint(s) not verified (no checkpoint key)",
+                                snapshot.checkpoints.len()
+                            );
+                        }
+                    }
                 }
                 Err(e) => {
                     serial_println!("[replay] Failed to parse CommitLog snapshot: {:?}", e);
@@ -437,17 +664,20 @@ fn kernel_main(boot_info: &'static mut BootInfo) -> ! {
     serial_println!("Starting APIC timer (10ms interval)...");
     HAL.start_timer();
     serial_println!("Timer started!");
-    serial_println!("(Printing tick count every second)");
     serial_println!();
 
     // Let the timer run for a few seconds to verify it works
-    // The timer interrupt handler prints every 100 ticks (1 second)
-    serial_println!("Waiting for timer ticks (will show 5 seconds of output)...");
-    serial_println!();
+    serial_println!("Waiting for timer ticks (5 seconds)...");
 
     // Busy wait for about 5 seconds (500 ticks at 10ms each)
+    let mut last_second = 0;
     while zos_hal::x86_64::apic::tick_count() < 500 {
         x86_64::instructions::hlt();
+        let second = zos_hal::x86_64::apic::tick_count() / 100;
+        if second != last_second {
+            last_second = second;
+            serial_println!("[Timer] {} seconds", second);
+        }
     }
 
     // Disable interrupts and show final stats
@@ -577,6 +807,13 @@ fn kernel_main(boot_info: &'static mut BootInfo) -> ! {
     let _wasm_runtime = HAL.wasm_runtime();
     serial_println!("  WASM runtime initialized!");
 
+    // Start the application processors so processes spawned from here on
+    // are spread across all CPUs
+    serial_println!();
+    serial_println!("Starting application processors...");
+    let cpus = unsafe { HAL.start_aps() };
+    serial_println!("  {} CPU(s) running WASM processes", cpus);
+
     // Create a fresh System for the kernel main loop
     serial_println!();
     serial_println!("Creating kernel System...");
@@ -589,6 +826,14 @@ fn kernel_main(boot_info: &'static mut BootInfo) -> ! {
     );
     serial_println!("  Registered kernel as PID 0");
 
+    if let Some(signer) = checkpoint_signer {
+        kernel_system.set_checkpoint_signer(alloc::boxed::Box::new(signer), CHECKPOINT_INTERVAL);
+        serial_println!("  Checkpoints signed every {} commits", CHECKPOINT_INTERVAL);
+    }
+
+    // Set by the main loop when Init asked for a reboot rather than a power-off
+    let mut reboot = false;
+
     // Load and spawn Init (PID 1)
     serial_println!();
     serial_println!("Loading Init process...");
@@ -601,7 +846,7 @@ fn kernel_main(boot_info: &'static mut BootInfo) -> ! {
             serial_println!("  Registered Init as PID {}", init_pid.0);
             
             // Spawn Init via HAL WASM runtime with the kernel-allocated PID
-            match HAL.spawn_process_with_pid(init_pid.0, "init", init_binary) {
+            match HAL.spawn_process_with_priority(init_pid.0, "init", init_binary, Priority::System) {
                 Ok(handle) => {
                     serial_println!("  Spawned Init process (handle {})", handle.id());
                     
@@ -622,7 +867,10 @@ fn kernel_main(boot_info: &'static mut BootInfo) -> ! {
                     serial_println!("========================================");
                     serial_println!();
                     
-                    run_kernel_main_loop(&mut kernel_system, &HAL, storage_ready);
+                    // Block I/O completions arrive as interrupts
+                    HAL.enable_interrupts();
+                    
+                    reboot = run_kernel_main_loop(&mut kernel_system, &HAL, storage_ready);
                 }
                 Err(e) => {
                     serial_println!("  ERROR: Failed to spawn Init: {:?}", e);
@@ -634,10 +882,14 @@ fn kernel_main(boot_info: &'static mut BootInfo) -> ! {
         }
     }
 
-    serial_println!("Kernel main loop exited. Shutting down...");
-
-    // Exit QEMU with success code
-    zos_hal::x86_64::exit_qemu(0)
+    // Flush storage, then reboot or power off (ACPI S5, QEMU debug exit as fallback)
+    if reboot {
+        serial_println!("Kernel main loop exited. Rebooting...");
+        HAL.reboot()
+    } else {
+        serial_println!("Kernel main loop exited. Shutting down...");
+        HAL.shutdown()
+    }
 }
 
 /// Panic handler
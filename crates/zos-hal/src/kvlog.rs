@@ -0,0 +1,1393 @@
+//! Log-structured key-value store
+//!
+//! The on-disk format behind the x86_64 block storage (data and keystore
+//! disks). It has no platform dependencies, so it is tested on the host and
+//! read by offline tools such as `axiom-inspect`.
+//!
+//! # Disk Layout
+//!
+//! ```text
+//! ┌─────────────────────────────────────────────────────────────────────────────┐
+//! │ Sector 0-1: Superblock slots (the valid one with the higher epoch wins)     │
+//! ├─────────────────────────────────────────────────────────────────────────────┤
+//! │ Sector 2-N: Circular log of batches, oldest (tail) to newest (head)         │
+//! └─────────────────────────────────────────────────────────────────────────────┘
+//! ```
+//!
+//! # Records and Batches
+//!
+//! Every record starts with a 32-byte header:
+//!
+//! | Offset | Field |
+//! |--------|-------|
+//! | 0      | magic (`"ZKVR"`) |
+//! | 4      | kind (put, delete, commit, wrap) + 3 reserved bytes |
+//! | 8      | batch sequence number (u64) |
+//! | 16     | key length (u32) |
+//! | 20     | value length (u32) |
+//! | 24     | CRC-32 of header bytes 0..24, key and value |
+//! | 28     | reserved |
+//!
+//! A batch is a run of put/delete records followed by a commit record whose
+//! value holds the previous batch's sequence number, the record count and a
+//! CRC-32 over all preceding records. Batches are padded to a sector
+//! boundary and never straddle the end of the disk; a wrap record (carrying
+//! the batch's sequence number) sends the reader back to the log start.
+//!
+//! A batch only counts once its commit record is intact, so every batch
+//! (including a multi-key `storage_batch_write_async`) is applied atomically.
+//!
+//! # Recovery
+//!
+//! The superblock names the tail. Recovery follows batches from there while
+//! each one is complete, its CRCs match and it links to the batch before it;
+//! the first batch that doesn't is where the head resumes. Each mount leases
+//! a fresh range of sequence numbers (recorded in the superblock first), so
+//! leftovers of a torn batch can never be mistaken for a later one.
+//!
+//! # Garbage Collection
+//!
+//! Space is reclaimed from the tail one batch at a time: records that are
+//! still current are re-appended at the head, then the superblock is moved
+//! past the old batch. A crash between the two steps leaves both copies,
+//! which replay to the same state. Enough space is held back for one
+//! relocation so collection can always make progress.
+
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+/// Sector size in bytes
+pub const SECTOR_SIZE: usize = 512;
+
+/// First sector of the log (after the two superblock slots)
+pub const LOG_START: u32 = 2;
+
+/// Maximum key length (bytes)
+pub const MAX_KEY_LEN: usize = 256;
+
+/// Maximum value length (bytes)
+pub const MAX_VALUE_LEN: usize = 64 * 1024;
+
+/// Largest batch, in sectors (room for one maximum-size record)
+pub const MAX_BATCH_SECTORS: u32 = 136;
+
+/// Largest sum of [`Mutation::encoded_len`] that fits in one batch
+pub const MAX_BATCH_PAYLOAD: usize = MAX_BATCH_SECTORS as usize * SECTOR_SIZE - COMMIT_RECORD_SIZE;
+
+/// Sectors held back for garbage collection: one relocated batch plus the
+/// space a wrap can skip
+const GC_RESERVE: u32 = 2 * MAX_BATCH_SECTORS + 1;
+
+/// Further sectors only batches of deletes may use, so a full log can
+/// still be emptied
+const DELETE_RESERVE: u32 = MAX_BATCH_SECTORS;
+
+/// Smallest usable device, in sectors
+pub const MIN_SECTORS: u64 =
+    LOG_START as u64 + GC_RESERVE as u64 + DELETE_RESERVE as u64 + MAX_BATCH_SECTORS as u64;
+
+/// Sequence numbers leased per superblock write
+const SEQ_LEASE: u64 = 1 << 20;
+
+/// Superblock magic ("ZKVS")
+const SUPERBLOCK_MAGIC: u32 = 0x5A4B5653;
+
+/// Record magic ("ZKVR")
+const RECORD_MAGIC: u32 = 0x5A4B5652;
+
+/// On-disk format version
+const FORMAT_VERSION: u32 = 2;
+
+/// Record header size
+const RECORD_HEADER_SIZE: usize = 32;
+
+/// Commit record size (header + prev_seq, record count, batch CRC)
+const COMMIT_RECORD_SIZE: usize = RECORD_HEADER_SIZE + 16;
+
+/// Record kinds
+mod kind {
+    pub const PUT: u8 = 1;
+    pub const DELETE: u8 = 2;
+    pub const COMMIT: u8 = 3;
+    pub const WRAP: u8 = 4;
+}
+
+/// Key-value store error
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KvError {
+    /// The device failed a read, write or flush
+    Io,
+    /// Not enough free space, even after garbage collection
+    NoSpace,
+    /// Key, value or batch too large, or device too small
+    InvalidArgument,
+    /// A record that should be intact failed its checks
+    Corrupt,
+    /// The device holds no store
+    Unformatted,
+}
+
+impl fmt::Display for KvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KvError::Io => write!(f, "block device I/O error"),
+            KvError::NoSpace => write!(f, "no space left in the log"),
+            KvError::InvalidArgument => write!(f, "invalid argument"),
+            KvError::Corrupt => write!(f, "corrupt record"),
+            KvError::Unformatted => write!(f, "no key-value store on device"),
+        }
+    }
+}
+
+/// Sector-addressed storage the log lives on
+pub trait BlockDevice {
+    /// Device size in sectors
+    fn sector_count(&self) -> u64;
+    /// Read whole sectors starting at `sector`
+    fn read(&self, sector: u64, buf: &mut [u8]) -> Result<(), KvError>;
+    /// Write whole sectors starting at `sector`
+    fn write(&mut self, sector: u64, data: &[u8]) -> Result<(), KvError>;
+    /// Make all completed writes durable
+    fn flush(&mut self) -> Result<(), KvError>;
+}
+
+/// A change applied by a batch
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Mutation {
+    /// Store a value under a key
+    Put(String, Vec<u8>),
+    /// Remove a key
+    Delete(String),
+}
+
+impl Mutation {
+    /// The key this mutation changes
+    pub fn key(&self) -> &str {
+        match self {
+            Mutation::Put(key, _) | Mutation::Delete(key) => key,
+        }
+    }
+
+    /// Bytes this mutation takes up in a batch
+    pub fn encoded_len(&self) -> usize {
+        match self {
+            Mutation::Put(key, value) => RECORD_HEADER_SIZE + key.len() + value.len(),
+            Mutation::Delete(key) => RECORD_HEADER_SIZE + key.len(),
+        }
+    }
+}
+
+/// How [`KvLog::open`] found the device
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Opened {
+    /// An existing store was recovered
+    Existing,
+    /// The device was blank and has been formatted
+    Created,
+    /// A store in the old append-only format was converted
+    Migrated,
+}
+
+// =============================================================================
+// CRC-32
+// =============================================================================
+
+/// CRC-32 (IEEE) lookup table
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 { 0xEDB8_8320 ^ (crc >> 1) } else { crc >> 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// Continue a CRC-32 over more bytes (start with `0`)
+fn crc32_update(crc: u32, bytes: &[u8]) -> u32 {
+    let mut crc = !crc;
+    for &b in bytes {
+        crc = CRC_TABLE[((crc ^ b as u32) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+/// CRC-32 (IEEE) of `bytes`
+pub fn crc32(bytes: &[u8]) -> u32 {
+    crc32_update(0, bytes)
+}
+
+// =============================================================================
+// Encoding
+// =============================================================================
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3]])
+}
+
+fn u64_at(bytes: &[u8], offset: usize) -> u64 {
+    u32_at(bytes, offset) as u64 | (u32_at(bytes, offset + 4) as u64) << 32
+}
+
+/// Append one record to `out`
+fn encode_record(out: &mut Vec<u8>, kind: u8, seq: u64, key: &[u8], value: &[u8]) {
+    let start = out.len();
+    out.extend_from_slice(&RECORD_MAGIC.to_le_bytes());
+    out.extend_from_slice(&[kind, 0, 0, 0]);
+    out.extend_from_slice(&seq.to_le_bytes());
+    out.extend_from_slice(&(key.len() as u32).to_le_bytes());
+    out.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    let crc = crc32_update(crc32_update(crc32(&out[start..start + 24]), key), value);
+    out.extend_from_slice(&crc.to_le_bytes());
+    out.extend_from_slice(&0u32.to_le_bytes());
+    out.extend_from_slice(key);
+    out.extend_from_slice(value);
+}
+
+/// Commit record value
+fn commit_payload(prev_seq: u64, count: u32, batch_crc: u32) -> [u8; 16] {
+    let mut payload = [0u8; 16];
+    payload[0..8].copy_from_slice(&prev_seq.to_le_bytes());
+    payload[8..12].copy_from_slice(&count.to_le_bytes());
+    payload[12..16].copy_from_slice(&batch_crc.to_le_bytes());
+    payload
+}
+
+/// Pad a buffer with zeros to a sector boundary
+fn pad_to_sector(buf: &mut Vec<u8>) {
+    buf.resize(buf.len().div_ceil(SECTOR_SIZE) * SECTOR_SIZE, 0);
+}
+
+/// A decoded record
+#[derive(Debug)]
+struct Record<'a> {
+    kind: u8,
+    seq: u64,
+    key: &'a [u8],
+    value: &'a [u8],
+}
+
+/// Total size of the record whose header starts `bytes`, if the header is plausible
+fn record_size(bytes: &[u8]) -> Option<usize> {
+    if bytes.len() < RECORD_HEADER_SIZE || u32_at(bytes, 0) != RECORD_MAGIC {
+        return None;
+    }
+    let key_len = u32_at(bytes, 16) as usize;
+    let value_len = u32_at(bytes, 20) as usize;
+    if key_len > MAX_KEY_LEN || value_len > MAX_VALUE_LEN {
+        return None;
+    }
+    Some(RECORD_HEADER_SIZE + key_len + value_len)
+}
+
+/// Decode and verify a complete record
+fn parse_record(bytes: &[u8]) -> Option<Record<'_>> {
+    let size = record_size(bytes)?;
+    if bytes.len() < size {
+        return None;
+    }
+    let key_len = u32_at(bytes, 16) as usize;
+    let key = &bytes[RECORD_HEADER_SIZE..RECORD_HEADER_SIZE + key_len];
+    let value = &bytes[RECORD_HEADER_SIZE + key_len..size];
+    let crc = crc32_update(crc32_update(crc32(&bytes[..24]), key), value);
+    if crc != u32_at(bytes, 24) {
+        return None;
+    }
+    Some(Record { kind: bytes[4], seq: u64_at(bytes, 8), key, value })
+}
+
+/// Superblock (one sector)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Superblock {
+    /// Incremented on every write; the slot is `epoch % 2`
+    epoch: u64,
+    /// End of the log (exclusive)
+    log_end: u32,
+    /// Where recovery starts
+    tail: u32,
+    /// Lowest sequence number the batch at `tail` may have
+    tail_seq: u64,
+    /// Every sequence number ever written is below this
+    seq_limit: u64,
+}
+
+impl Superblock {
+    fn encode(&self) -> [u8; SECTOR_SIZE] {
+        let mut bytes = [0u8; SECTOR_SIZE];
+        bytes[0..4].copy_from_slice(&SUPERBLOCK_MAGIC.to_le_bytes());
+        bytes[4..8].copy_from_slice(&FORMAT_VERSION.to_le_bytes());
+        bytes[8..16].copy_from_slice(&self.epoch.to_le_bytes());
+        bytes[16..20].copy_from_slice(&LOG_START.to_le_bytes());
+        bytes[20..24].copy_from_slice(&self.log_end.to_le_bytes());
+        bytes[24..28].copy_from_slice(&self.tail.to_le_bytes());
+        bytes[32..40].copy_from_slice(&self.tail_seq.to_le_bytes());
+        bytes[40..48].copy_from_slice(&self.seq_limit.to_le_bytes());
+        let crc = crc32(&bytes[..SECTOR_SIZE - 4]);
+        bytes[SECTOR_SIZE - 4..].copy_from_slice(&crc.to_le_bytes());
+        bytes
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < SECTOR_SIZE
+            || u32_at(bytes, 0) != SUPERBLOCK_MAGIC
+            || u32_at(bytes, 4) != FORMAT_VERSION
+            || crc32(&bytes[..SECTOR_SIZE - 4]) != u32_at(bytes, SECTOR_SIZE - 4)
+            || u32_at(bytes, 16) != LOG_START
+        {
+            return None;
+        }
+        let sb = Self {
+            epoch: u64_at(bytes, 8),
+            log_end: u32_at(bytes, 20),
+            tail: u32_at(bytes, 24),
+            tail_seq: u64_at(bytes, 32),
+            seq_limit: u64_at(bytes, 40),
+        };
+        let sane = (sb.log_end as u64) >= MIN_SECTORS && sb.tail >= LOG_START && sb.tail < sb.log_end;
+        sane.then_some(sb)
+    }
+}
+
+/// Read the newest valid superblock
+fn read_superblock<D: BlockDevice>(dev: &D) -> Result<Option<Superblock>, KvError> {
+    let mut bytes = [0u8; 2 * SECTOR_SIZE];
+    dev.read(0, &mut bytes)?;
+    let newest = bytes
+        .chunks(SECTOR_SIZE)
+        .filter_map(Superblock::decode)
+        .max_by_key(|sb| sb.epoch);
+    match newest {
+        Some(sb) if sb.log_end as u64 > dev.sector_count() => Err(KvError::Corrupt),
+        other => Ok(other),
+    }
+}
+
+// =============================================================================
+// Store
+// =============================================================================
+
+/// Where a record is stored
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RecordLocation {
+    /// First sector of the batch holding the record
+    pub batch: u32,
+    /// Byte offset of the record within the batch
+    pub offset: u32,
+    /// Record size in bytes
+    pub size: u32,
+}
+
+impl RecordLocation {
+    /// First sector to read
+    pub fn first_sector(&self) -> u64 {
+        self.batch as u64 + (self.offset as usize / SECTOR_SIZE) as u64
+    }
+
+    /// Number of bytes (whole sectors) to read
+    pub fn read_len(&self) -> usize {
+        let start = self.offset as usize % SECTOR_SIZE;
+        (start + self.size as usize).div_ceil(SECTOR_SIZE) * SECTOR_SIZE
+    }
+
+    /// Extract the value from the sectors read at this location
+    pub fn decode_value(&self, sectors: &[u8]) -> Result<Vec<u8>, KvError> {
+        let start = self.offset as usize % SECTOR_SIZE;
+        let bytes = sectors.get(start..start + self.size as usize).ok_or(KvError::Corrupt)?;
+        match parse_record(bytes) {
+            Some(record) if record.kind == kind::PUT => Ok(record.value.to_vec()),
+            _ => Err(KvError::Corrupt),
+        }
+    }
+}
+
+/// A batch ready to be written
+///
+/// Produced by [`KvLog::prepare`]. Write `writes` in order, flush, then
+/// hand the batch to [`KvLog::apply`] (or [`KvLog::abandon`] on failure).
+#[derive(Debug)]
+pub struct PreparedBatch {
+    /// Sequence number
+    seq: u64,
+    /// `(sector, data)` writes: an optional wrap record, then the batch
+    pub writes: Vec<(u64, Vec<u8>)>,
+    /// First sector of the batch
+    start: u32,
+    /// Batch length in sectors
+    sectors: u32,
+    /// Sectors consumed, including any skipped by a wrap
+    footprint: u32,
+    /// Index changes (`None` = deleted)
+    updates: Vec<(String, Option<RecordLocation>)>,
+}
+
+impl PreparedBatch {
+    /// Whether the batch changes nothing (and has nothing to write)
+    pub fn is_empty(&self) -> bool {
+        self.writes.is_empty()
+    }
+}
+
+/// A committed batch still in the log
+#[derive(Clone, Copy, Debug)]
+struct BatchInfo {
+    /// Where reading starts (the wrap record, if any)
+    pos: u32,
+    /// First sector of the batch
+    start: u32,
+    /// Batch length in sectors
+    sectors: u32,
+    /// Sectors consumed, including any skipped by a wrap
+    footprint: u32,
+    /// Sequence number
+    seq: u64,
+}
+
+/// Which sequence numbers the next batch may have
+#[derive(Clone, Copy, Debug)]
+enum Expect {
+    /// First batch at the tail: at least this
+    AtLeast(u64),
+    /// Any later batch: above this, and linked to it
+    After(u64),
+}
+
+impl Expect {
+    fn accepts(self, seq: u64) -> bool {
+        match self {
+            Expect::AtLeast(min) => seq >= min,
+            Expect::After(prev) => seq > prev,
+        }
+    }
+
+    fn links(self, prev_seq: u64) -> bool {
+        match self {
+            Expect::AtLeast(_) => true,
+            Expect::After(prev) => prev_seq == prev,
+        }
+    }
+}
+
+/// A batch found by the recovery scan
+struct ScannedBatch {
+    info: BatchInfo,
+    updates: Vec<(String, Option<RecordLocation>)>,
+}
+
+/// Log-structured key-value store on a [`BlockDevice`]
+pub struct KvLog<D: BlockDevice> {
+    /// Underlying device
+    dev: D,
+    /// End of the log (exclusive)
+    log_end: u32,
+    /// Epoch of the last superblock written
+    epoch: u64,
+    /// Every sequence number ever written is below this
+    seq_limit: u64,
+    /// Lowest sequence number for the first batch at the tail
+    tail_seq: u64,
+    /// Next sector to write
+    head: u32,
+    /// Sequence number for the next batch
+    next_seq: u64,
+    /// Sequence number of the newest committed batch
+    last_seq: u64,
+    /// Sectors between tail and head
+    used: u32,
+    /// Committed batches, oldest first
+    batches: VecDeque<BatchInfo>,
+    /// Current record for every live key
+    index: BTreeMap<String, RecordLocation>,
+    /// Total size of the records in `index`
+    live_bytes: u64,
+}
+
+impl<D: BlockDevice> KvLog<D> {
+    /// Open the store on `dev`, recovering, migrating or formatting it
+    pub fn open(dev: D) -> Result<(Self, Opened), KvError> {
+        let sectors = dev.sector_count();
+        if sectors < MIN_SECTORS {
+            return Err(KvError::InvalidArgument);
+        }
+
+        if let Some(sb) = read_superblock(&dev)? {
+            let mut log = Self::recover(dev, sb)?;
+            // Lease fresh sequence numbers before writing anything
+            log.next_seq = log.next_seq.max(sb.seq_limit);
+            if log.batches.is_empty() {
+                log.tail_seq = log.next_seq;
+            }
+            log.write_superblock(log.tail(), log.tail_seq)?;
+            return Ok((log, Opened::Existing));
+        }
+
+        let log_end = sectors.min(u32::MAX as u64) as u32;
+        match legacy::read(&dev)? {
+            Some(store) => {
+                let log = Self::migrate(dev, log_end, store.next_free, store.entries)?;
+                Ok((log, Opened::Migrated))
+            }
+            None => {
+                let mut log = Self::empty(dev, log_end, LOG_START, 1);
+                log.format()?;
+                Ok((log, Opened::Created))
+            }
+        }
+    }
+
+    /// An empty in-memory store with the head at `head`
+    fn empty(dev: D, log_end: u32, head: u32, next_seq: u64) -> Self {
+        Self {
+            dev,
+            log_end,
+            epoch: 0,
+            seq_limit: 0,
+            tail_seq: next_seq,
+            head,
+            next_seq,
+            last_seq: 0,
+            used: 0,
+            batches: VecDeque::new(),
+            index: BTreeMap::new(),
+            live_bytes: 0,
+        }
+    }
+
+    /// Write both superblock slots for an empty log
+    fn format(&mut self) -> Result<(), KvError> {
+        let tail = self.tail();
+        self.write_superblock(tail, self.tail_seq)?;
+        self.write_superblock(tail, self.tail_seq)
+    }
+
+    /// Convert an old append-only store
+    ///
+    /// The new log starts past the old entries, which stay untouched until
+    /// the superblock is written, so a crash part way leaves the old store
+    /// to migrate again.
+    fn migrate(
+        dev: D,
+        log_end: u32,
+        next_free: u32,
+        entries: BTreeMap<String, Vec<u8>>,
+    ) -> Result<Self, KvError> {
+        let head = next_free.max(LOG_START);
+        if head >= log_end {
+            return Err(KvError::NoSpace);
+        }
+        let mut log = Self::empty(dev, log_end, head, 1);
+        // Covered by the superblock written at the end
+        log.seq_limit = SEQ_LEASE;
+
+        let mut chunk = Vec::new();
+        let mut chunk_len = 0;
+        let mut entries = entries.into_iter().peekable();
+        while let Some((key, value)) = entries.next() {
+            let mutation = Mutation::Put(key, value);
+            chunk_len += mutation.encoded_len();
+            chunk.push(mutation);
+
+            let next_len = entries.peek().map_or(usize::MAX, |(k, v)| RECORD_HEADER_SIZE + k.len() + v.len());
+            if chunk_len.saturating_add(next_len) > MAX_BATCH_PAYLOAD {
+                let batch = log.prepare_with(&chunk, 0)?;
+                // Wrapping would overwrite the old entries
+                if batch.start != log.head || batch.writes.len() != 1 {
+                    return Err(KvError::NoSpace);
+                }
+                log.write_batch(&batch)?;
+                log.apply(batch)?;
+                chunk.clear();
+                chunk_len = 0;
+            }
+        }
+
+        log.format()?;
+        Ok(log)
+    }
+
+    /// Rebuild the in-memory state by scanning from the superblock's tail
+    fn recover(dev: D, sb: Superblock) -> Result<Self, KvError> {
+        let mut log = Self::empty(dev, sb.log_end, sb.tail, sb.tail_seq);
+        log.epoch = sb.epoch;
+        log.seq_limit = sb.seq_limit;
+
+        let mut expect = Expect::AtLeast(sb.tail_seq);
+        while let Some(batch) = log.scan_batch(log.head, expect)? {
+            let info = batch.info;
+            for (key, location) in batch.updates {
+                log.update_index(key, location);
+            }
+            log.batches.push_back(info);
+            log.used += info.footprint;
+            log.head = log.wrap_position(info.start + info.sectors);
+            log.last_seq = info.seq;
+            log.next_seq = info.seq + 1;
+            expect = Expect::After(info.seq);
+        }
+        Ok(log)
+    }
+
+    /// Map a position at the end of the log back to its start
+    fn wrap_position(&self, sector: u32) -> u32 {
+        if sector >= self.log_end {
+            LOG_START
+        } else {
+            sector
+        }
+    }
+
+    /// Read one batch at `pos` if it is complete and valid
+    ///
+    /// Only device errors are reported as errors; anything malformed simply
+    /// ends the scan.
+    fn scan_batch(&self, pos: u32, expect: Expect) -> Result<Option<ScannedBatch>, KvError> {
+        let room = self.capacity() - self.used;
+        let mut sector = [0u8; SECTOR_SIZE];
+        self.dev.read(pos as u64, &mut sector)?;
+
+        // A wrap record fixes the batch's sequence number and moves to the start
+        let (start, skipped, wrap_seq) = match parse_record(&sector) {
+            Some(r) if r.kind == kind::WRAP && r.value.len() == 8 => {
+                if !expect.accepts(r.seq) || !expect.links(u64_at(r.value, 0)) {
+                    return Ok(None);
+                }
+                (LOG_START, self.log_end - pos, Some(r.seq))
+            }
+            _ => (pos, 0, None),
+        };
+        let max_sectors = MAX_BATCH_SECTORS.min(self.log_end - start).min(room.saturating_sub(skipped));
+
+        let mut buf: Vec<u8> = Vec::new();
+        let mut offset = 0usize;
+        let mut seq = wrap_seq;
+        let mut batch_crc = 0u32;
+        let mut updates = Vec::new();
+
+        loop {
+            // Header first, then the whole record
+            if !self.fill(&mut buf, start, offset + RECORD_HEADER_SIZE, max_sectors)? {
+                return Ok(None);
+            }
+            let Some(size) = record_size(&buf[offset..]) else {
+                return Ok(None);
+            };
+            if !self.fill(&mut buf, start, offset + size, max_sectors)? {
+                return Ok(None);
+            }
+            let Some(record) = parse_record(&buf[offset..offset + size]) else {
+                return Ok(None);
+            };
+
+            match seq {
+                Some(s) if s != record.seq => return Ok(None),
+                None if !expect.accepts(record.seq) => return Ok(None),
+                _ => seq = Some(record.seq),
+            }
+
+            match record.kind {
+                kind::PUT | kind::DELETE => {
+                    let Ok(key) = core::str::from_utf8(record.key) else {
+                        return Ok(None);
+                    };
+                    let location = (record.kind == kind::PUT).then_some(RecordLocation {
+                        batch: start,
+                        offset: offset as u32,
+                        size: size as u32,
+                    });
+                    updates.push((String::from(key), location));
+                    batch_crc = crc32_update(batch_crc, &buf[offset..offset + size]);
+                }
+                kind::COMMIT if record.value.len() == 16 => {
+                    let prev_seq = u64_at(record.value, 0);
+                    let count = u32_at(record.value, 8) as usize;
+                    let crc = u32_at(record.value, 12);
+                    if !expect.links(prev_seq) || count != updates.len() || crc != batch_crc || count == 0 {
+                        return Ok(None);
+                    }
+                    let sectors = (offset + size).div_ceil(SECTOR_SIZE) as u32;
+                    return Ok(Some(ScannedBatch {
+                        info: BatchInfo {
+                            pos,
+                            start,
+                            sectors,
+                            footprint: skipped + sectors,
+                            seq: record.seq,
+                        },
+                        updates,
+                    }));
+                }
+                _ => return Ok(None),
+            }
+            offset += size;
+        }
+    }
+
+    /// Read more sectors of a batch until `buf` holds `len` bytes
+    ///
+    /// Returns `false` if that would run past `max_sectors`.
+    fn fill(&self, buf: &mut Vec<u8>, start: u32, len: usize, max_sectors: u32) -> Result<bool, KvError> {
+        let needed = len.div_ceil(SECTOR_SIZE);
+        if needed > max_sectors as usize {
+            return Ok(false);
+        }
+        let have = buf.len() / SECTOR_SIZE;
+        if needed > have {
+            buf.resize(needed * SECTOR_SIZE, 0);
+            self.dev.read(start as u64 + have as u64, &mut buf[have * SECTOR_SIZE..])?;
+        }
+        Ok(true)
+    }
+
+    /// Apply one index change
+    fn update_index(&mut self, key: String, location: Option<RecordLocation>) {
+        let old = match location {
+            Some(location) => {
+                self.live_bytes += location.size as u64;
+                self.index.insert(key, location)
+            }
+            None => self.index.remove(&key),
+        };
+        if let Some(old) = old {
+            self.live_bytes -= old.size as u64;
+        }
+    }
+
+    /// Where recovery would start
+    fn tail(&self) -> u32 {
+        self.batches.front().map_or(self.head, |b| b.pos)
+    }
+
+    /// Log size in sectors
+    fn capacity(&self) -> u32 {
+        self.log_end - LOG_START
+    }
+
+    /// Write the next superblock slot and flush
+    fn write_superblock(&mut self, tail: u32, tail_seq: u64) -> Result<(), KvError> {
+        let sb = Superblock {
+            epoch: self.epoch + 1,
+            log_end: self.log_end,
+            tail,
+            tail_seq,
+            seq_limit: self.next_seq + SEQ_LEASE,
+        };
+        self.dev.write(sb.epoch % 2, &sb.encode())?;
+        self.dev.flush()?;
+        self.epoch = sb.epoch;
+        self.seq_limit = sb.seq_limit;
+        self.tail_seq = tail_seq;
+        Ok(())
+    }
+
+    /// Check if a key exists
+    pub fn contains(&self, key: &str) -> bool {
+        self.index.contains_key(key)
+    }
+
+    /// Number of live keys
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    /// Whether the store holds no keys
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Live keys starting with `prefix`, in order
+    pub fn keys<'a>(&'a self, prefix: &'a str) -> impl Iterator<Item = &'a String> + 'a {
+        self.index.keys().filter(move |k| k.starts_with(prefix))
+    }
+
+    /// Where a key's current record is stored
+    pub fn locate(&self, key: &str) -> Option<RecordLocation> {
+        self.index.get(key).copied()
+    }
+
+    /// Read a value
+    pub fn get(&self, key: &str) -> Result<Option<Vec<u8>>, KvError> {
+        let Some(location) = self.locate(key) else {
+            return Ok(None);
+        };
+        let mut sectors = vec![0u8; location.read_len()];
+        self.dev.read(location.first_sector(), &mut sectors)?;
+        location.decode_value(&sectors).map(Some)
+    }
+
+    /// Sectors in use (live and garbage)
+    pub fn used_sectors(&self) -> u32 {
+        self.used
+    }
+
+    /// Sectors free for new batches
+    pub fn free_sectors(&self) -> u32 {
+        self.capacity() - self.used
+    }
+
+    /// Whether idle-time garbage collection is worthwhile
+    ///
+    /// True once the log is over half full and at least a quarter of the
+    /// disk is garbage, so collection isn't spent shuffling live data.
+    pub fn wants_gc(&self) -> bool {
+        let capacity = self.capacity();
+        let live = self.live_bytes.div_ceil(SECTOR_SIZE as u64).min(u32::MAX as u64) as u32;
+        let garbage = self.used.saturating_sub(live);
+        self.used > capacity / 2 && garbage > capacity / 4
+    }
+
+    /// Build (but don't write) a batch applying `mutations` in order
+    ///
+    /// Deleting a key that doesn't exist is skipped; if nothing is left the
+    /// batch is empty. Fails with `NoSpace` if the log is too full (see
+    /// [`KvLog::make_room`]).
+    pub fn prepare(&self, mutations: &[Mutation]) -> Result<PreparedBatch, KvError> {
+        let deletes_only = mutations.iter().all(|m| matches!(m, Mutation::Delete(_)));
+        let reserve = if deletes_only { GC_RESERVE } else { GC_RESERVE + DELETE_RESERVE };
+        self.prepare_with(mutations, reserve)
+    }
+
+    fn prepare_with(&self, mutations: &[Mutation], reserve: u32) -> Result<PreparedBatch, KvError> {
+        if self.next_seq >= self.seq_limit {
+            return Err(KvError::Io);
+        }
+        let seq = self.next_seq;
+
+        // Keys created or removed earlier in this batch
+        let mut pending: BTreeMap<&str, bool> = BTreeMap::new();
+        let mut buf = Vec::new();
+        let mut updates = Vec::new();
+
+        for mutation in mutations {
+            let key = mutation.key();
+            if key.len() > MAX_KEY_LEN {
+                return Err(KvError::InvalidArgument);
+            }
+            let offset = buf.len() as u32;
+            match mutation {
+                Mutation::Put(_, value) => {
+                    if value.len() > MAX_VALUE_LEN {
+                        return Err(KvError::InvalidArgument);
+                    }
+                    encode_record(&mut buf, kind::PUT, seq, key.as_bytes(), value);
+                    let location = RecordLocation { batch: 0, offset, size: buf.len() as u32 - offset };
+                    updates.push((String::from(key), Some(location)));
+                    pending.insert(key, true);
+                }
+                Mutation::Delete(_) => {
+                    let exists = pending.get(key).copied().unwrap_or_else(|| self.index.contains_key(key));
+                    if !exists {
+                        continue;
+                    }
+                    encode_record(&mut buf, kind::DELETE, seq, key.as_bytes(), &[]);
+                    updates.push((String::from(key), None));
+                    pending.insert(key, false);
+                }
+            }
+            if buf.len() > MAX_BATCH_PAYLOAD {
+                return Err(KvError::InvalidArgument);
+            }
+        }
+
+        if updates.is_empty() {
+            return Ok(PreparedBatch { seq, writes: Vec::new(), start: self.head, sectors: 0, footprint: 0, updates });
+        }
+
+        let batch_crc = crc32(&buf);
+        let payload = commit_payload(self.last_seq, updates.len() as u32, batch_crc);
+        encode_record(&mut buf, kind::COMMIT, seq, &[], &payload);
+        pad_to_sector(&mut buf);
+        let sectors = (buf.len() / SECTOR_SIZE) as u32;
+
+        // Batches never straddle the end of the log
+        let (start, skipped) = if self.head + sectors <= self.log_end {
+            (self.head, 0)
+        } else {
+            (LOG_START, self.log_end - self.head)
+        };
+        let footprint = skipped + sectors;
+        if self.used + footprint + reserve > self.capacity() {
+            return Err(KvError::NoSpace);
+        }
+
+        let mut writes = Vec::with_capacity(2);
+        if skipped > 0 {
+            let mut wrap = Vec::with_capacity(SECTOR_SIZE);
+            encode_record(&mut wrap, kind::WRAP, seq, &[], &self.last_seq.to_le_bytes());
+            pad_to_sector(&mut wrap);
+            writes.push((self.head as u64, wrap));
+        }
+        writes.push((start as u64, buf));
+
+        for (_, location) in updates.iter_mut() {
+            if let Some(location) = location {
+                location.batch = start;
+            }
+        }
+
+        Ok(PreparedBatch { seq, writes, start, sectors, footprint, updates })
+    }
+
+    /// Record a batch whose writes are durable
+    pub fn apply(&mut self, batch: PreparedBatch) -> Result<(), KvError> {
+        if batch.seq != self.next_seq {
+            return Err(KvError::InvalidArgument);
+        }
+        if batch.is_empty() {
+            return Ok(());
+        }
+
+        let pos = self.head;
+        for (key, location) in batch.updates {
+            self.update_index(key, location);
+        }
+        self.batches.push_back(BatchInfo {
+            pos,
+            start: batch.start,
+            sectors: batch.sectors,
+            footprint: batch.footprint,
+            seq: batch.seq,
+        });
+        self.used += batch.footprint;
+        self.head = self.wrap_position(batch.start + batch.sectors);
+        self.last_seq = batch.seq;
+        self.advance_seq(batch.seq)
+    }
+
+    /// Give up on a batch whose writes failed
+    ///
+    /// Its sequence number is not reused, so any part of it that reached the
+    /// disk can't be mistaken for a later batch.
+    pub fn abandon(&mut self, batch: PreparedBatch) -> Result<(), KvError> {
+        if batch.seq != self.next_seq || batch.is_empty() {
+            return Ok(());
+        }
+        self.advance_seq(batch.seq)
+    }
+
+    /// Move past `seq`, extending the lease when it runs out
+    fn advance_seq(&mut self, seq: u64) -> Result<(), KvError> {
+        self.next_seq = seq + 1;
+        if self.next_seq >= self.seq_limit {
+            self.write_superblock(self.tail(), self.tail_seq)?;
+        }
+        Ok(())
+    }
+
+    /// Write a prepared batch and flush
+    fn write_batch(&mut self, batch: &PreparedBatch) -> Result<(), KvError> {
+        for (sector, data) in &batch.writes {
+            self.dev.write(*sector, data)?;
+        }
+        self.dev.flush()
+    }
+
+    /// Apply `mutations` atomically and durably
+    pub fn commit(&mut self, mutations: &[Mutation]) -> Result<(), KvError> {
+        let batch = self.make_room(mutations)?;
+        if batch.is_empty() {
+            return Ok(());
+        }
+        if let Err(e) = self.write_batch(&batch) {
+            let _ = self.abandon(batch);
+            return Err(e);
+        }
+        self.apply(batch)
+    }
+
+    /// Prepare a batch, collecting garbage first if the log is too full
+    pub fn make_room(&mut self, mutations: &[Mutation]) -> Result<PreparedBatch, KvError> {
+        // One pass over the log is enough to reclaim all garbage
+        let mut steps = self.batches.len();
+        loop {
+            match self.prepare(mutations) {
+                Err(KvError::NoSpace) if steps > 0 => {
+                    steps -= 1;
+                    self.gc_step()?;
+                }
+                other => return other,
+            }
+        }
+    }
+
+    /// Reclaim the oldest batch
+    ///
+    /// Its live records are re-appended at the head, then the tail moves
+    /// past it. Returns `false` if the log is empty.
+    pub fn gc_step(&mut self) -> Result<bool, KvError> {
+        let Some(oldest) = self.batches.front().copied() else {
+            return Ok(false);
+        };
+
+        let mut buf = vec![0u8; oldest.sectors as usize * SECTOR_SIZE];
+        self.dev.read(oldest.start as u64, &mut buf)?;
+
+        let mut live = Vec::new();
+        let mut offset = 0usize;
+        loop {
+            let record = record_size(&buf[offset..])
+                .and_then(|size| Some((size, parse_record(buf.get(offset..offset + size)?)?)));
+            let Some((size, record)) = record else {
+                return Err(KvError::Corrupt);
+            };
+            if record.kind == kind::COMMIT {
+                break;
+            }
+            let current = RecordLocation { batch: oldest.start, offset: offset as u32, size: size as u32 };
+            if record.kind == kind::PUT {
+                let key = core::str::from_utf8(record.key).map_err(|_| KvError::Corrupt)?;
+                if self.index.get(key) == Some(&current) {
+                    live.push(Mutation::Put(String::from(key), record.value.to_vec()));
+                }
+            }
+            offset += size;
+        }
+
+        if !live.is_empty() {
+            let batch = self.prepare_with(&live, 0)?;
+            if let Err(e) = self.write_batch(&batch) {
+                let _ = self.abandon(batch);
+                return Err(e);
+            }
+            self.apply(batch)?;
+        }
+
+        // The old batch's sectors are only reused once the superblock no
+        // longer points at them
+        let (tail, tail_seq) = match self.batches.get(1) {
+            Some(next) => (next.pos, next.seq),
+            None => (self.head, self.next_seq),
+        };
+        self.write_superblock(tail, tail_seq)?;
+        self.batches.pop_front();
+        self.used -= oldest.footprint;
+        Ok(true)
+    }
+
+    /// Remove every key
+    ///
+    /// Sequence numbers keep counting up, so old records are never replayed.
+    pub fn clear(&mut self) -> Result<(), KvError> {
+        let (tail, tail_seq) = (self.head, self.next_seq);
+        self.write_superblock(tail, tail_seq)?;
+        self.batches.clear();
+        self.index.clear();
+        self.live_bytes = 0;
+        self.used = 0;
+        Ok(())
+    }
+}
+
+// =============================================================================
+// Offline access
+// =============================================================================
+
+/// A disk image in memory (read-only)
+struct ImageDevice<'a>(&'a [u8]);
+
+impl BlockDevice for ImageDevice<'_> {
+    fn sector_count(&self) -> u64 {
+        (self.0.len() / SECTOR_SIZE) as u64
+    }
+
+    fn read(&self, sector: u64, buf: &mut [u8]) -> Result<(), KvError> {
+        let start = sector as usize * SECTOR_SIZE;
+        let bytes = self.0.get(start..start + buf.len()).ok_or(KvError::Io)?;
+        buf.copy_from_slice(bytes);
+        Ok(())
+    }
+
+    fn write(&mut self, _sector: u64, _data: &[u8]) -> Result<(), KvError> {
+        Err(KvError::Io)
+    }
+
+    fn flush(&mut self) -> Result<(), KvError> {
+        Err(KvError::Io)
+    }
+}
+
+/// Check whether `image` looks like a store (either format)
+pub fn is_image(image: &[u8]) -> bool {
+    let magic_at = |sector: usize| {
+        let offset = sector * SECTOR_SIZE;
+        (image.len() >= offset + SECTOR_SIZE).then(|| u32_at(image, offset))
+    };
+    magic_at(0) == Some(SUPERBLOCK_MAGIC)
+        || magic_at(1) == Some(SUPERBLOCK_MAGIC)
+        || magic_at(0) == Some(legacy::SUPERBLOCK_MAGIC)
+}
+
+/// Read every live entry of a disk image without modifying it
+///
+/// Recovers exactly what [`KvLog::open`] would, including stores in the
+/// old format.
+pub fn read_image(image: &[u8]) -> Result<BTreeMap<String, Vec<u8>>, KvError> {
+    let dev = ImageDevice(image);
+    if let Some(sb) = read_superblock(&dev)? {
+        let log = KvLog::recover(dev, sb)?;
+        let mut entries = BTreeMap::new();
+        for key in log.index.keys() {
+            if let Some(value) = log.get(key)? {
+                entries.insert(key.clone(), value);
+            }
+        }
+        return Ok(entries);
+    }
+    match legacy::read(&dev)? {
+        Some(store) => Ok(store.entries),
+        None => Err(KvError::Unformatted),
+    }
+}
+
+/// Reader for the original append-only format (version 1)
+///
+/// Sector 0 held a superblock (byte-sum checksum, next free sector at
+/// offset 12); entries followed from sector 1, each
+/// `magic | flags | key_len | value_len | key | value` padded to a sector.
+/// A later entry for a key replaces an earlier one; a deleted entry
+/// removes it.
+mod legacy {
+    use super::{u32_at, BlockDevice, KvError, SECTOR_SIZE};
+    use alloc::collections::BTreeMap;
+    use alloc::string::String;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    /// Superblock magic ("ZOS\0")
+    pub const SUPERBLOCK_MAGIC: u32 = 0x5A4F5300;
+    /// Entry magic ("ZOSE")
+    const ENTRY_MAGIC: u32 = 0x5A4F5345;
+    const VERSION: u32 = 1;
+    const FLAG_VALID: u32 = 1;
+    const FLAG_DELETED: u32 = 0;
+    const ENTRY_HEADER_SIZE: usize = 16;
+
+    /// Contents of an old store
+    pub struct Store {
+        /// First sector past the last entry
+        pub next_free: u32,
+        /// Live entries
+        pub entries: BTreeMap<String, Vec<u8>>,
+    }
+
+    /// Read an old store, or `None` if the device doesn't hold one
+    pub fn read<D: BlockDevice>(dev: &D) -> Result<Option<Store>, KvError> {
+        let mut sector_buf = [0u8; SECTOR_SIZE];
+        dev.read(0, &mut sector_buf)?;
+        let checksum = sector_buf[..SECTOR_SIZE - 4]
+            .iter()
+            .fold(0u32, |sum, b| sum.wrapping_add(*b as u32));
+        if u32_at(&sector_buf, 0) != SUPERBLOCK_MAGIC
+            || u32_at(&sector_buf, 4) != VERSION
+            || checksum != u32_at(&sector_buf, SECTOR_SIZE - 4)
+        {
+            return Ok(None);
+        }
+        let next_free = u32_at(&sector_buf, 12);
+        let end = (next_free as u64).min(dev.sector_count());
+
+        let mut entries = BTreeMap::new();
+        let mut sector = 1u64;
+        while sector < end {
+            dev.read(sector, &mut sector_buf)?;
+            if u32_at(&sector_buf, 0) != ENTRY_MAGIC {
+                break;
+            }
+            let flags = u32_at(&sector_buf, 4);
+            let key_len = u32_at(&sector_buf, 8) as usize;
+            let value_len = u32_at(&sector_buf, 12) as usize;
+            let total = ENTRY_HEADER_SIZE + key_len + value_len;
+            let entry_sectors = total.div_ceil(SECTOR_SIZE) as u64;
+            if sector + entry_sectors > end {
+                break;
+            }
+
+            let mut entry = vec![0u8; entry_sectors as usize * SECTOR_SIZE];
+            dev.read(sector, &mut entry)?;
+            let key = &entry[ENTRY_HEADER_SIZE..ENTRY_HEADER_SIZE + key_len];
+            if let Ok(key) = core::str::from_utf8(key) {
+                match flags {
+                    FLAG_VALID => {
+                        entries.insert(String::from(key), entry[ENTRY_HEADER_SIZE + key_len..total].to_vec());
+                    }
+                    FLAG_DELETED => {
+                        entries.remove(key);
+                    }
+                    _ => {}
+                }
+            }
+            sector += entry_sectors;
+        }
+        Ok(Some(Store { next_free, entries }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// In-memory device
+    struct MemDevice(Vec<u8>);
+
+    impl MemDevice {
+        fn new(sectors: usize) -> Self {
+            Self(vec![0u8; sectors * SECTOR_SIZE])
+        }
+    }
+
+    impl BlockDevice for MemDevice {
+        fn sector_count(&self) -> u64 {
+            (self.0.len() / SECTOR_SIZE) as u64
+        }
+
+        fn read(&self, sector: u64, buf: &mut [u8]) -> Result<(), KvError> {
+            ImageDevice(&self.0).read(sector, buf)
+        }
+
+        fn write(&mut self, sector: u64, data: &[u8]) -> Result<(), KvError> {
+            let start = sector as usize * SECTOR_SIZE;
+            self.0[start..start + data.len()].copy_from_slice(data);
+            Ok(())
+        }
+
+        fn flush(&mut self) -> Result<(), KvError> {
+            Ok(())
+        }
+    }
+
+    fn put(key: &str, value: &[u8]) -> Mutation {
+        Mutation::Put(String::from(key), value.to_vec())
+    }
+
+    fn reopen(log: KvLog<MemDevice>) -> KvLog<MemDevice> {
+        let (log, opened) = KvLog::open(log.dev).unwrap();
+        assert_eq!(opened, Opened::Existing);
+        log
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32_update(crc32(b"1234"), b"56789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_put_get_delete_across_reopen() {
+        let (mut log, opened) = KvLog::open(MemDevice::new(1024)).unwrap();
+        assert_eq!(opened, Opened::Created);
+
+        log.commit(&[put("/a", b"one"), put("/b", &[7u8; 2000])]).unwrap();
+        log.commit(&[put("/a", b"two"), Mutation::Delete(String::from("/b"))]).unwrap();
+        log.commit(&[Mutation::Delete(String::from("/missing"))]).unwrap();
+
+        let log = reopen(log);
+        assert_eq!(log.get("/a").unwrap(), Some(b"two".to_vec()));
+        assert_eq!(log.get("/b").unwrap(), None);
+        assert_eq!(log.len(), 1);
+        assert_eq!(read_image(&log.dev.0).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_rejects_oversized() {
+        let (mut log, _) = KvLog::open(MemDevice::new(1024)).unwrap();
+        let long_key = "k".repeat(MAX_KEY_LEN + 1);
+        assert_eq!(log.commit(&[put(&long_key, b"")]), Err(KvError::InvalidArgument));
+        assert_eq!(log.commit(&[put("/v", &vec![0u8; MAX_VALUE_LEN + 1])]), Err(KvError::InvalidArgument));
+        log.commit(&[put("/v", &vec![1u8; MAX_VALUE_LEN])]).unwrap();
+        assert_eq!(KvLog::open(MemDevice::new(64)).err(), Some(KvError::InvalidArgument));
+    }
+
+    #[test]
+    fn test_wraps_and_collects_garbage() {
+        let (mut log, _) = KvLog::open(MemDevice::new(MIN_SECTORS as usize + 64)).unwrap();
+        for i in 0..400u32 {
+            let value = vec![i as u8; 300 + (i as usize % 7) * 100];
+            log.commit(&[put(&alloc::format!("/k{}", i % 5), &value)]).unwrap();
+        }
+        assert!(log.free_sectors() > 0);
+
+        let log = reopen(log);
+        assert_eq!(log.len(), 5);
+        for k in 395..400u32 {
+            let value = log.get(&alloc::format!("/k{}", k % 5)).unwrap().unwrap();
+            assert_eq!(value, vec![k as u8; 300 + (k as usize % 7) * 100]);
+        }
+    }
+
+    #[test]
+    fn test_full_log_reports_no_space() {
+        let (mut log, _) = KvLog::open(MemDevice::new(MIN_SECTORS as usize + 64)).unwrap();
+        let mut i = 0;
+        let err = loop {
+            match log.commit(&[put(&alloc::format!("/k{}", i), &[0u8; 1000])]) {
+                Ok(()) => i += 1,
+                Err(e) => break e,
+            }
+        };
+        assert_eq!(err, KvError::NoSpace);
+
+        // Deleting frees space again
+        let deletes: Vec<Mutation> = (0..i).map(|k| Mutation::Delete(alloc::format!("/k{}", k))).collect();
+        log.commit(&deletes).unwrap();
+        log.commit(&[put("/again", &[1u8; 1000])]).unwrap();
+        assert_eq!(reopen(log).len(), 1);
+    }
+
+    #[test]
+    fn test_clear_does_not_resurrect() {
+        let (mut log, _) = KvLog::open(MemDevice::new(1024)).unwrap();
+        log.commit(&[put("/a", b"1")]).unwrap();
+        log.clear().unwrap();
+        log.commit(&[put("/b", b"2")]).unwrap();
+        let log = reopen(log);
+        assert!(!log.contains("/a"));
+        assert!(log.contains("/b"));
+    }
+
+    #[test]
+    fn test_migrates_old_format() {
+        let mut image = vec![0u8; 1024 * SECTOR_SIZE];
+        let mut sector = 1;
+        for (flags, key, value) in [(1u32, "/a", &b"old"[..]), (1, "/b", b"bee"), (0, "/a", b""), (1, "/c", b"sea")] {
+            let offset = sector * SECTOR_SIZE;
+            for (i, word) in [0x5A4F5345u32, flags, key.len() as u32, value.len() as u32].iter().enumerate() {
+                image[offset + i * 4..offset + i * 4 + 4].copy_from_slice(&word.to_le_bytes());
+            }
+            image[offset + 16..offset + 16 + key.len()].copy_from_slice(key.as_bytes());
+            image[offset + 16 + key.len()..offset + 16 + key.len() + value.len()].copy_from_slice(value);
+            sector += 1;
+        }
+        image[0..4].copy_from_slice(&legacy::SUPERBLOCK_MAGIC.to_le_bytes());
+        image[4..8].copy_from_slice(&1u32.to_le_bytes());
+        image[12..16].copy_from_slice(&(sector as u32).to_le_bytes());
+        let checksum = image[..SECTOR_SIZE - 4].iter().fold(0u32, |sum, b| sum.wrapping_add(*b as u32));
+        image[SECTOR_SIZE - 4..SECTOR_SIZE].copy_from_slice(&checksum.to_le_bytes());
+
+        assert!(is_image(&image));
+        assert_eq!(read_image(&image).unwrap().len(), 2);
+
+        let (log, opened) = KvLog::open(MemDevice(image)).unwrap();
+        assert_eq!(opened, Opened::Migrated);
+        let log = reopen(log);
+        assert_eq!(log.get("/a").unwrap(), None);
+        assert_eq!(log.get("/b").unwrap(), Some(b"bee".to_vec()));
+        assert_eq!(log.get("/c").unwrap(), Some(b"sea".to_vec()));
+    }
+
+    #[test]
+    fn test_detects_bit_rot() {
+        let (mut log, _) = KvLog::open(MemDevice::new(1024)).unwrap();
+        log.commit(&[put("/a", b"value")]).unwrap();
+        let location = log.locate("/a").unwrap();
+        let byte = location.first_sector() as usize * SECTOR_SIZE + location.offset as usize % SECTOR_SIZE + 36;
+        log.dev.0[byte] ^= 0xFF;
+        assert_eq!(log.get("/a"), Err(KvError::Corrupt));
+    }
+}
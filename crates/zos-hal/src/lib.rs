@@ -19,6 +19,9 @@
 
 extern crate alloc;
 
+// Log-structured key-value store (x86_64 block storage format)
+pub mod kvlog;
+
 // x86_64 platform implementation (enabled by feature)
 #[cfg(feature = "x86_64")]
 pub mod x86_64;
@@ -86,6 +89,27 @@ pub trait HAL: Send + Sync + 'static {
         self.spawn_process(name, binary)
     }
 
+    /// Spawn a process with a specific PID in a scheduling priority class
+    ///
+    /// The spawner (Init) picks the class. Platforms without priority
+    /// scheduling ignore it.
+    ///
+    /// # Arguments
+    /// * `pid` - The process ID to use (must match kernel's allocated PID)
+    /// * `name` - Human-readable process name for debugging
+    /// * `binary` - WASM binary to execute
+    /// * `priority` - Scheduling class for the new process
+    fn spawn_process_with_priority(
+        &self,
+        pid: u64,
+        name: &str,
+        binary: &[u8],
+        priority: Priority,
+    ) -> Result<Self::ProcessHandle, HalError> {
+        let _ = priority;
+        self.spawn_process_with_pid(pid, name, binary)
+    }
+
     /// Terminate a process
     ///
     /// # Arguments
@@ -497,6 +521,105 @@ pub trait HAL: Send + Sync + 'static {
         Err(HalError::NotSupported)
     }
 
+    // === Power Control (QEMU Native Runtime) ===
+
+    /// Whether the platform can power off or reboot the machine.
+    ///
+    /// The kernel uses this to accept or refuse SYS_SHUTDOWN. The power-off
+    /// itself happens in the boot loop once Init has stopped services.
+    ///
+    /// # Platform Behavior
+    /// - **QEMU**: `true` (ACPI / keyboard controller)
+    /// - **WASM**: `false` (the browser tab owns the machine)
+    fn supports_power_control(&self) -> bool {
+        false
+    }
+
+    // === Device Access (User-Space Drivers) ===
+    // The kernel checks the driver's IRQ or I/O port capability before calling
+    // these; the HAL only refuses lines and ports the platform keeps for itself.
+
+    /// Read `width` (1, 2 or 4) bytes from an I/O port.
+    ///
+    /// # Platform Behavior
+    /// - **QEMU**: `in` instruction
+    /// - **WASM**: Returns `NotSupported` (no port I/O in the browser)
+    fn io_port_read(&self, _port: u16, _width: u8) -> Result<u32, HalError> {
+        Err(HalError::NotSupported)
+    }
+
+    /// Write `width` (1, 2 or 4) bytes to an I/O port.
+    ///
+    /// # Platform Behavior
+    /// - **QEMU**: `out` instruction
+    /// - **WASM**: Returns `NotSupported`
+    fn io_port_write(&self, _port: u16, _width: u8, _value: u32) -> Result<(), HalError> {
+        Err(HalError::NotSupported)
+    }
+
+    /// Route an IRQ line to user-space delivery and unmask it.
+    ///
+    /// Called when a driver binds the line and each time it acknowledges an
+    /// interrupt. The platform masks the line when the interrupt fires, so
+    /// it stays quiet until the driver has handled it.
+    ///
+    /// # Platform Behavior
+    /// - **QEMU**: ISA IRQs not used by the kernel itself
+    /// - **WASM**: Returns `NotSupported`
+    fn irq_enable(&self, _irq: u8) -> Result<(), HalError> {
+        Err(HalError::NotSupported)
+    }
+
+    /// Mask an IRQ line no driver is bound to any more.
+    fn irq_disable(&self, _irq: u8) {}
+
+    // === Shared Memory Regions ===
+    // The HAL owns region contents; the kernel owns the region objects and
+    // checks the caller's capability and mapping before any copy. A process
+    // sees a region through a window in its own linear memory that is
+    // refreshed from, and published to, the region at explicit points.
+
+    /// Allocate zeroed backing for region `id` of `size` bytes (a whole
+    /// number of pages).
+    ///
+    /// # Platform Behavior
+    /// - **QEMU**: Physical frames from the frame allocator
+    /// - **WASM**: A buffer in the supervisor
+    fn region_alloc(&self, _id: u64, _size: usize) -> Result<(), HalError> {
+        Err(HalError::NotSupported)
+    }
+
+    /// Free the backing of region `id`.
+    fn region_free(&self, _id: u64) {}
+
+    /// Copy `len` bytes at `offset` in region `id` into process `pid`'s
+    /// linear memory at `addr`.
+    ///
+    /// The process is blocked in a syscall while this runs.
+    fn region_copy_to_process(
+        &self,
+        _id: u64,
+        _offset: usize,
+        _pid: u64,
+        _addr: u32,
+        _len: usize,
+    ) -> Result<(), HalError> {
+        Err(HalError::NotSupported)
+    }
+
+    /// Copy `len` bytes from process `pid`'s linear memory at `addr` into
+    /// region `id` at `offset`.
+    fn region_copy_from_process(
+        &self,
+        _id: u64,
+        _offset: usize,
+        _pid: u64,
+        _addr: u32,
+        _len: usize,
+    ) -> Result<(), HalError> {
+        Err(HalError::NotSupported)
+    }
+
     // === Bootstrap Storage (Supervisor Only) ===
     // These methods are used ONLY during supervisor initialization before processes exist.
     // They provide direct storage access for bootstrap operations like creating the root
@@ -630,6 +753,17 @@ impl ProcessMessageType {
     }
 }
 
+/// Scheduling priority class of a process (highest first)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    /// Core services (init, VFS, keystore, ...)
+    System,
+    /// User-facing applications
+    Interactive,
+    /// Everything else
+    Background,
+}
+
 /// A simple process handle for platforms that use numeric IDs
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct NumericProcessHandle(pub u64);
@@ -659,6 +793,11 @@ impl TestHal {
             time: core::sync::atomic::AtomicU64::new(0),
         }
     }
+
+    /// Set the time returned by `now_nanos()`
+    pub fn set_now_nanos(&self, nanos: u64) {
+        self.time.store(nanos, core::sync::atomic::Ordering::SeqCst);
+    }
 }
 
 unsafe impl Send for TestHal {}
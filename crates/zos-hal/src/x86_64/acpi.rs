@@ -0,0 +1,707 @@
+//! ACPI table discovery and power control
+//!
+//! Walks RSDP → RSDT/XSDT to the tables the kernel needs:
+//!
+//! - **MADT** (`APIC`): local APICs for SMP bring-up, I/O APICs and ISA
+//!   interrupt source overrides for interrupt routing
+//! - **FADT** (`FACP`): PM1 control blocks, the reset register and the DSDT
+//! - **DSDT**: only scanned for the `\_S5_` package, which holds the
+//!   SLP_TYP values for soft-off
+//!
+//! There is no AML interpreter. `\_S5_` is a plain data package in the
+//! DSDT of QEMU's firmware and of common PC firmware, so a byte-pattern
+//! scan is enough to power off.
+//!
+//! # MADT Entries
+//!
+//! | Type | Description |
+//! |------|-------------|
+//! | 0    | Processor local APIC |
+//! | 1    | I/O APIC |
+//! | 2    | Interrupt source override |
+//! | 5    | Local APIC address override |
+//!
+//! x2APIC entries (type 9) are ignored: the LAPIC driver uses xAPIC mode,
+//! which can only address APIC IDs up to 255.
+
+use alloc::vec::Vec;
+use x86_64::instructions::port::Port;
+use x86_64::PhysAddr;
+
+use super::vmm::phys_to_virt;
+
+/// Size of the common ACPI table header
+const SDT_HEADER_LEN: usize = 36;
+
+/// Generic address space: system memory
+pub const SPACE_MEMORY: u8 = 0;
+
+/// Generic address space: system I/O ports
+pub const SPACE_IO: u8 = 1;
+
+/// FADT flag: the reset register is supported
+const FADT_RESET_REG_SUP: u32 = 1 << 10;
+
+/// PM1 control register bits
+mod pm1_cnt {
+    pub const SCI_EN: u64 = 1;
+    pub const SLP_TYP_SHIFT: u32 = 10;
+    pub const SLP_TYP_MASK: u64 = 0b111 << SLP_TYP_SHIFT;
+    pub const SLP_EN: u64 = 1 << 13;
+}
+
+/// Spin iterations to wait for the firmware to act on a register write
+const SETTLE_SPINS: u32 = 10_000_000;
+
+/// A processor's local APIC as listed in the MADT
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LocalApic {
+    /// ACPI processor UID
+    pub processor_id: u8,
+    /// Local APIC ID (target for INIT/SIPI)
+    pub apic_id: u8,
+    /// Processor is usable (enabled or online-capable)
+    pub enabled: bool,
+}
+
+/// An I/O APIC as listed in the MADT
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IoApic {
+    pub id: u8,
+    /// Physical MMIO base
+    pub address: u32,
+    /// First global system interrupt handled by this I/O APIC
+    pub gsi_base: u32,
+}
+
+/// An ISA IRQ wired to a different GSI, polarity or trigger mode
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InterruptOverride {
+    /// ISA IRQ number
+    pub source: u8,
+    /// Global system interrupt the IRQ arrives on
+    pub gsi: u32,
+    /// MPS INTI flags (bits 0-1: polarity, bits 2-3: trigger mode)
+    pub flags: u16,
+}
+
+/// Where and how an ISA IRQ arrives at the I/O APICs
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IrqRoute {
+    pub gsi: u32,
+    pub active_low: bool,
+    pub level_triggered: bool,
+}
+
+/// Parsed Multiple APIC Description Table
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Madt {
+    /// Physical address of the local APIC registers
+    pub lapic_address: u64,
+    pub local_apics: Vec<LocalApic>,
+    pub io_apics: Vec<IoApic>,
+    pub overrides: Vec<InterruptOverride>,
+}
+
+impl Madt {
+    /// Route an ISA IRQ through the interrupt source overrides
+    ///
+    /// Without an override the IRQ is identity mapped, active high and
+    /// edge triggered, as on the ISA bus.
+    pub fn route_isa_irq(&self, irq: u8) -> IrqRoute {
+        match self.overrides.iter().find(|o| o.source == irq) {
+            Some(o) => IrqRoute {
+                gsi: o.gsi,
+                // 0b00 in either field means "conforms to the bus" (ISA)
+                active_low: o.flags & 0b11 == 0b11,
+                level_triggered: (o.flags >> 2) & 0b11 == 0b11,
+            },
+            None => IrqRoute { gsi: irq as u32, active_low: false, level_triggered: false },
+        }
+    }
+
+    /// The I/O APIC whose inputs include `gsi`
+    pub fn io_apic_for(&self, gsi: u32) -> Option<&IoApic> {
+        self.io_apics
+            .iter()
+            .filter(|io| io.gsi_base <= gsi)
+            .max_by_key(|io| io.gsi_base)
+    }
+}
+
+/// A register location in ACPI Generic Address Structure form
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GenericAddress {
+    /// Address space ([`SPACE_MEMORY`] or [`SPACE_IO`]; others are not
+    /// supported)
+    pub space: u8,
+    /// Access width in bits
+    pub bit_width: u8,
+    pub address: u64,
+}
+
+/// Parsed Fixed ACPI Description Table (the power management parts)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Fadt {
+    /// Physical address of the DSDT
+    pub dsdt: u64,
+    /// I/O port that switches the firmware to ACPI mode (0 = always in it)
+    pub smi_command: u32,
+    /// Value to write to `smi_command` to enable ACPI mode
+    pub acpi_enable: u8,
+    pub pm1a_control: Option<GenericAddress>,
+    pub pm1b_control: Option<GenericAddress>,
+    /// Reset register and the value that resets the machine
+    pub reset: Option<(GenericAddress, u8)>,
+}
+
+/// SLP_TYP values for a sleep state, one per PM1 control block
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SleepType {
+    pub a: u8,
+    pub b: u8,
+}
+
+/// Everything the kernel uses from the firmware's ACPI tables
+#[derive(Clone, Debug)]
+pub struct AcpiInfo {
+    pub madt: Option<Madt>,
+    pub fadt: Option<Fadt>,
+    /// SLP_TYP values for soft-off (S5)
+    pub s5: Option<SleepType>,
+}
+
+/// Tables read at boot
+static ACPI: spin::Once<AcpiInfo> = spin::Once::new();
+
+/// Read the ACPI tables, starting at the RSDP
+///
+/// Only the first call reads; later calls return the same information.
+///
+/// # Safety
+/// `rsdp_addr` must be the physical address of a valid RSDP and the
+/// physical memory mapping (VMM) must be initialized.
+pub unsafe fn init(rsdp_addr: u64) -> &'static AcpiInfo {
+    ACPI.call_once(|| {
+        let read = |addr: u64, len: usize| {
+            // SAFETY: ACPI tables are in mapped physical memory (caller contract)
+            let ptr = phys_to_virt(PhysAddr::new(addr)).as_ptr::<u8>();
+            Some(core::slice::from_raw_parts(ptr, len))
+        };
+        let fadt = find_table(rsdp_addr, *b"FACP", read).and_then(parse_fadt);
+        let s5 = fadt
+            .as_ref()
+            .and_then(|fadt| read_table(fadt.dsdt, &read))
+            .and_then(find_s5);
+        AcpiInfo {
+            madt: find_table(rsdp_addr, *b"APIC", read).and_then(parse_madt),
+            fadt,
+            s5,
+        }
+    })
+}
+
+/// The tables read by [`init`], if it ran
+pub fn info() -> Option<&'static AcpiInfo> {
+    ACPI.get()
+}
+
+/// Find an ACPI table by signature
+///
+/// `read(addr, len)` returns `len` bytes of physical memory at `addr`.
+pub fn find_table<'a, F>(rsdp_addr: u64, signature: [u8; 4], read: F) -> Option<&'a [u8]>
+where
+    F: Fn(u64, usize) -> Option<&'a [u8]>,
+{
+    let rsdp = read(rsdp_addr, 20)?;
+    if &rsdp[..8] != b"RSD PTR " || checksum(rsdp) != 0 {
+        return None;
+    }
+
+    // ACPI 2.0+ provides a 64-bit XSDT; fall back to the 32-bit RSDT
+    let revision = rsdp[15];
+    let (root_addr, entry_size) = match revision {
+        0 => (u32_at(rsdp, 16)? as u64, 4),
+        _ => {
+            let rsdp = read(rsdp_addr, 36)?;
+            (u64_at(rsdp, 24)?, 8)
+        }
+    };
+
+    let root = read_table(root_addr, &read)?;
+    root[SDT_HEADER_LEN..]
+        .chunks_exact(entry_size)
+        .map(|entry| match entry_size {
+            4 => u32_at(entry, 0).map(u64::from),
+            _ => u64_at(entry, 0),
+        })
+        .filter_map(|addr| read_table(addr?, &read))
+        .find(|table| table[..4] == signature)
+}
+
+/// Read a whole table whose header is at `addr`, checking its checksum
+fn read_table<'a, F>(addr: u64, read: &F) -> Option<&'a [u8]>
+where
+    F: Fn(u64, usize) -> Option<&'a [u8]>,
+{
+    let header = read(addr, SDT_HEADER_LEN)?;
+    let len = u32_at(header, 4)? as usize;
+    if len < SDT_HEADER_LEN {
+        return None;
+    }
+    let table = read(addr, len)?;
+    (checksum(table) == 0).then_some(table)
+}
+
+/// Parse a MADT (including its header)
+pub fn parse_madt(table: &[u8]) -> Option<Madt> {
+    if table.len() < SDT_HEADER_LEN + 8 || &table[..4] != b"APIC" {
+        return None;
+    }
+    let mut madt = Madt {
+        lapic_address: u32_at(table, SDT_HEADER_LEN)? as u64,
+        local_apics: Vec::new(),
+        io_apics: Vec::new(),
+        overrides: Vec::new(),
+    };
+
+    let mut entries = &table[SDT_HEADER_LEN + 8..];
+    while entries.len() >= 2 {
+        let (kind, len) = (entries[0], entries[1] as usize);
+        if len < 2 || len > entries.len() {
+            return None;
+        }
+        let entry = &entries[..len];
+        match kind {
+            0 if len >= 8 => {
+                let flags = u32_at(entry, 4)?;
+                madt.local_apics.push(LocalApic {
+                    processor_id: entry[2],
+                    apic_id: entry[3],
+                    // bit 0: enabled, bit 1: online capable
+                    enabled: flags & 0b11 != 0,
+                });
+            }
+            1 if len >= 12 => madt.io_apics.push(IoApic {
+                id: entry[2],
+                address: u32_at(entry, 4)?,
+                gsi_base: u32_at(entry, 8)?,
+            }),
+            // entry[2] is the bus, which is always ISA
+            2 if len >= 10 => madt.overrides.push(InterruptOverride {
+                source: entry[3],
+                gsi: u32_at(entry, 4)?,
+                flags: u16::from_le_bytes([entry[8], entry[9]]),
+            }),
+            5 if len >= 12 => madt.lapic_address = u64_at(entry, 4)?,
+            _ => {}
+        }
+        entries = &entries[len..];
+    }
+    Some(madt)
+}
+
+/// Parse a FADT (including its header)
+///
+/// ACPI 1.0 FADTs end after the flags field; the reset register and the
+/// 64-bit `X_` fields are only used when the table is long enough and
+/// they are filled in.
+pub fn parse_fadt(table: &[u8]) -> Option<Fadt> {
+    if table.len() < 72 || &table[..4] != b"FACP" {
+        return None;
+    }
+    let legacy_pm1 = |offset| {
+        let port = u32_at(table, offset)?;
+        (port != 0).then_some(GenericAddress {
+            space: SPACE_IO,
+            bit_width: 16,
+            address: port as u64,
+        })
+    };
+    let flags = u32_at(table, 112).unwrap_or(0);
+    let reset = match flags & FADT_RESET_REG_SUP {
+        0 => None,
+        _ => generic_address_at(table, 116).zip(table.get(128).copied()),
+    };
+
+    Some(Fadt {
+        dsdt: match u64_at(table, 140) {
+            Some(x_dsdt) if x_dsdt != 0 => x_dsdt,
+            _ => u32_at(table, 40)? as u64,
+        },
+        smi_command: u32_at(table, 48)?,
+        acpi_enable: table[52],
+        pm1a_control: generic_address_at(table, 172).or_else(|| legacy_pm1(64)),
+        pm1b_control: generic_address_at(table, 184).or_else(|| legacy_pm1(68)),
+        reset,
+    })
+}
+
+/// Read a Generic Address Structure; `None` if absent (address 0)
+fn generic_address_at(bytes: &[u8], offset: usize) -> Option<GenericAddress> {
+    let gas = bytes.get(offset..offset + 12)?;
+    let address = u64_at(gas, 4)?;
+    if address == 0 {
+        return None;
+    }
+    // Prefer the access size (1-4 = byte..qword) over the register width
+    let bit_width = match gas[3] {
+        1 => 8,
+        2 => 16,
+        3 => 32,
+        4 => 64,
+        _ => gas[1],
+    };
+    Some(GenericAddress { space: gas[0], bit_width, address })
+}
+
+/// Find the `\_S5_` (soft-off) package in a DSDT
+///
+/// Matches `NameOp _S5_ PackageOp PkgLength NumElements` followed by two
+/// integer constants, the form AML compilers emit for it.
+pub fn find_s5(dsdt: &[u8]) -> Option<SleepType> {
+    const NAME_OP: u8 = 0x08;
+    const PACKAGE_OP: u8 = 0x12;
+    const ROOT_PREFIX: u8 = b'\\';
+
+    let aml = dsdt.get(SDT_HEADER_LEN..)?;
+    let mut from = 0;
+    while let Some(found) = aml[from..].windows(4).position(|w| w == b"_S5_") {
+        let at = from + found;
+        from = at + 4;
+
+        let before = |back: usize| at.checked_sub(back).map(|i| aml[i]);
+        let name_op = match before(1) {
+            Some(ROOT_PREFIX) => before(2),
+            op => op,
+        };
+        if name_op != Some(NAME_OP) || aml.get(at + 4) != Some(&PACKAGE_OP) {
+            continue;
+        }
+
+        // Bits 6-7 of the PkgLength lead byte count the bytes that follow it
+        let pkg_length = *aml.get(at + 5)?;
+        let mut cursor = at + 6 + (pkg_length >> 6) as usize + 1;
+        let a = aml_integer(aml, &mut cursor)?;
+        let b = aml_integer(aml, &mut cursor)?;
+        return Some(SleepType { a, b });
+    }
+    None
+}
+
+/// Read an AML integer constant at `cursor`, advancing past it
+///
+/// Only the low byte is returned; SLP_TYP values are 3 bits.
+fn aml_integer(aml: &[u8], cursor: &mut usize) -> Option<u8> {
+    let (value, len) = match *aml.get(*cursor)? {
+        // ZeroOp, OneOp
+        0x00 => (0, 1),
+        0x01 => (1, 1),
+        // BytePrefix, WordPrefix, DWordPrefix
+        0x0A => (*aml.get(*cursor + 1)?, 2),
+        0x0B => (*aml.get(*cursor + 1)?, 3),
+        0x0C => (*aml.get(*cursor + 1)?, 5),
+        _ => return None,
+    };
+    *cursor += len;
+    Some(value)
+}
+
+/// Enter S5 (soft-off) through the PM1 control blocks
+///
+/// Returns if the firmware described no way to do it, or the machine is
+/// still running after the write.
+///
+/// # Safety
+/// Powers the machine off: pending writes are lost. Interrupts should be
+/// disabled.
+pub unsafe fn power_off() {
+    let Some(info) = info() else { return };
+    let (Some(fadt), Some(s5)) = (info.fadt.as_ref(), info.s5) else {
+        return;
+    };
+    let Some(pm1a) = fadt.pm1a_control else { return };
+    enable_acpi_mode(fadt, &pm1a);
+
+    let blocks = [(Some(pm1a), s5.a), (fadt.pm1b_control, s5.b)];
+    let blocks = blocks.iter().filter_map(|(reg, typ)| reg.map(|reg| (reg, *typ)));
+
+    // Both blocks get their SLP_TYP before either gets SLP_EN
+    for (reg, typ) in blocks.clone() {
+        let value = read_register(&reg).unwrap_or(0) & !(pm1_cnt::SLP_TYP_MASK | pm1_cnt::SLP_EN);
+        write_register(&reg, value | ((typ as u64 & 0b111) << pm1_cnt::SLP_TYP_SHIFT));
+    }
+    for (reg, _) in blocks {
+        write_register(&reg, read_register(&reg).unwrap_or(0) | pm1_cnt::SLP_EN);
+    }
+    settle();
+}
+
+/// Reset the machine through the FADT reset register
+///
+/// Returns if there is none or the write had no effect.
+///
+/// # Safety
+/// Resets the machine: pending writes are lost.
+pub unsafe fn reset() {
+    let reset = info().and_then(|info| info.fadt.as_ref()).and_then(|fadt| fadt.reset);
+    if let Some((reg, value)) = reset {
+        write_register(&reg, value as u64);
+        settle();
+    }
+}
+
+/// Switch the firmware from legacy (SMM) mode to ACPI mode
+///
+/// Firmware that boots in ACPI mode, like QEMU's, has SCI_EN set already.
+unsafe fn enable_acpi_mode(fadt: &Fadt, pm1a: &GenericAddress) {
+    let enabled = || read_register(pm1a).is_some_and(|v| v & pm1_cnt::SCI_EN != 0);
+    if enabled() || fadt.smi_command == 0 || fadt.acpi_enable == 0 {
+        return;
+    }
+    Port::<u8>::new(fadt.smi_command as u16).write(fadt.acpi_enable);
+    for _ in 0..SETTLE_SPINS {
+        if enabled() {
+            return;
+        }
+        core::hint::spin_loop();
+    }
+}
+
+/// Give the chipset time to act on a power or reset write
+fn settle() {
+    for _ in 0..SETTLE_SPINS {
+        core::hint::spin_loop();
+    }
+}
+
+/// Read an I/O port or memory-mapped register
+unsafe fn read_register(reg: &GenericAddress) -> Option<u64> {
+    let port = reg.address as u16;
+    let mmio = phys_to_virt(PhysAddr::new(reg.address));
+    let value = match (reg.space, reg.bit_width) {
+        (SPACE_IO, 8) => Port::<u8>::new(port).read() as u64,
+        (SPACE_IO, 16) => Port::<u16>::new(port).read() as u64,
+        (SPACE_IO, 32) => Port::<u32>::new(port).read() as u64,
+        (SPACE_MEMORY, 8) => core::ptr::read_volatile(mmio.as_ptr::<u8>()) as u64,
+        (SPACE_MEMORY, 16) => core::ptr::read_volatile(mmio.as_ptr::<u16>()) as u64,
+        (SPACE_MEMORY, 32) => core::ptr::read_volatile(mmio.as_ptr::<u32>()) as u64,
+        (SPACE_MEMORY, 64) => core::ptr::read_volatile(mmio.as_ptr::<u64>()),
+        _ => return None,
+    };
+    Some(value)
+}
+
+/// Write an I/O port or memory-mapped register (other spaces are ignored)
+unsafe fn write_register(reg: &GenericAddress, value: u64) {
+    let port = reg.address as u16;
+    let mmio = phys_to_virt(PhysAddr::new(reg.address));
+    match (reg.space, reg.bit_width) {
+        (SPACE_IO, 8) => Port::<u8>::new(port).write(value as u8),
+        (SPACE_IO, 16) => Port::<u16>::new(port).write(value as u16),
+        (SPACE_IO, 32) => Port::<u32>::new(port).write(value as u32),
+        (SPACE_MEMORY, 8) => core::ptr::write_volatile(mmio.as_mut_ptr::<u8>(), value as u8),
+        (SPACE_MEMORY, 16) => core::ptr::write_volatile(mmio.as_mut_ptr::<u16>(), value as u16),
+        (SPACE_MEMORY, 32) => core::ptr::write_volatile(mmio.as_mut_ptr::<u32>(), value as u32),
+        (SPACE_MEMORY, 64) => core::ptr::write_volatile(mmio.as_mut_ptr::<u64>(), value),
+        _ => {}
+    }
+}
+
+fn checksum(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0u8, |sum, b| sum.wrapping_add(*b))
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(offset..offset + 4)?.try_into().ok()?))
+}
+
+fn u64_at(bytes: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(bytes.get(offset..offset + 8)?.try_into().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a table with a valid header and checksum
+    fn table(signature: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut table = alloc::vec![0u8; SDT_HEADER_LEN];
+        table[..4].copy_from_slice(signature);
+        table.extend_from_slice(body);
+        let len = table.len() as u32;
+        table[4..8].copy_from_slice(&len.to_le_bytes());
+        table[9] = 0u8.wrapping_sub(checksum(&table));
+        table
+    }
+
+    fn madt_body() -> Vec<u8> {
+        let mut body = Vec::new();
+        body.extend_from_slice(&0xFEE0_0000u32.to_le_bytes());
+        body.extend_from_slice(&1u32.to_le_bytes());
+        // Two enabled CPUs, one disabled
+        body.extend_from_slice(&[0, 8, 0, 0, 1, 0, 0, 0]);
+        body.extend_from_slice(&[0, 8, 1, 2, 1, 0, 0, 0]);
+        body.extend_from_slice(&[0, 8, 2, 4, 0, 0, 0, 0]);
+        // I/O APIC
+        body.extend_from_slice(&[1, 12, 5, 0]);
+        body.extend_from_slice(&0xFEC0_0000u32.to_le_bytes());
+        body.extend_from_slice(&0u32.to_le_bytes());
+        // Overrides as QEMU lists them: the PIT on GSI 2, SCI level triggered
+        body.extend_from_slice(&[2, 10, 0, 0, 2, 0, 0, 0, 0x00, 0]);
+        body.extend_from_slice(&[2, 10, 0, 9, 9, 0, 0, 0, 0x0D, 0]);
+        body
+    }
+
+    /// A FADT of `len` bytes with `fields` written at their table offsets
+    fn fadt(len: usize, fields: &[(usize, &[u8])]) -> Vec<u8> {
+        let mut body = alloc::vec![0u8; len - SDT_HEADER_LEN];
+        for (offset, bytes) in fields {
+            body[offset - SDT_HEADER_LEN..][..bytes.len()].copy_from_slice(bytes);
+        }
+        table(b"FACP", &body)
+    }
+
+    /// A Generic Address Structure with no access size
+    fn gas(space: u8, bit_width: u8, address: u64) -> [u8; 12] {
+        let mut gas = [0u8; 12];
+        gas[0] = space;
+        gas[1] = bit_width;
+        gas[4..].copy_from_slice(&address.to_le_bytes());
+        gas
+    }
+
+    // ========================================================================
+    // MADT tests
+    // ========================================================================
+
+    #[test]
+    fn test_parse_madt() {
+        let madt = parse_madt(&table(b"APIC", &madt_body())).unwrap();
+        assert_eq!(madt.lapic_address, 0xFEE0_0000);
+        let ids: Vec<_> = madt.local_apics.iter().map(|l| (l.apic_id, l.enabled)).collect();
+        assert_eq!(ids, [(0, true), (2, true), (4, false)]);
+        assert_eq!(madt.io_apics[0].address, 0xFEC0_0000);
+        assert_eq!(
+            madt.overrides,
+            [
+                InterruptOverride { source: 0, gsi: 2, flags: 0 },
+                InterruptOverride { source: 9, gsi: 9, flags: 0x0D },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_madt_rejects_truncated_entry() {
+        let mut body = madt_body();
+        body.extend_from_slice(&[0, 8, 3]);
+        assert_eq!(parse_madt(&table(b"APIC", &body)), None);
+    }
+
+    #[test]
+    fn test_route_isa_irq() {
+        let mut madt = parse_madt(&table(b"APIC", &madt_body())).unwrap();
+        let route = |gsi, active_low, level_triggered| IrqRoute { gsi, active_low, level_triggered };
+        assert_eq!(madt.route_isa_irq(0), route(2, false, false));
+        assert_eq!(madt.route_isa_irq(9), route(9, false, true));
+        assert_eq!(madt.route_isa_irq(4), route(4, false, false));
+
+        madt.io_apics.push(IoApic { id: 6, address: 0xFEC0_1000, gsi_base: 24 });
+        assert_eq!(madt.io_apic_for(23).map(|io| io.id), Some(5));
+        assert_eq!(madt.io_apic_for(30).map(|io| io.id), Some(6));
+    }
+
+    #[test]
+    fn test_find_table_through_xsdt() {
+        // Physical memory: RSDP at 0, XSDT at 0x100, MADT at 0x200
+        let mut mem = alloc::vec![0u8; 0x400];
+        let madt = table(b"APIC", &madt_body());
+        mem[0x200..0x200 + madt.len()].copy_from_slice(&madt);
+        let xsdt = table(b"XSDT", &0x200u64.to_le_bytes());
+        mem[0x100..0x100 + xsdt.len()].copy_from_slice(&xsdt);
+
+        let mut rsdp = [0u8; 36];
+        rsdp[..8].copy_from_slice(b"RSD PTR ");
+        rsdp[15] = 2;
+        rsdp[24..32].copy_from_slice(&0x100u64.to_le_bytes());
+        rsdp[8] = 0u8.wrapping_sub(checksum(&rsdp[..20]));
+        mem[..36].copy_from_slice(&rsdp);
+
+        let read = |addr: u64, len: usize| mem.get(addr as usize..addr as usize + len);
+        assert_eq!(find_table(0, *b"APIC", read), Some(&madt[..]));
+        assert_eq!(find_table(0, *b"HPET", read), None);
+    }
+
+    // ========================================================================
+    // FADT tests
+    // ========================================================================
+
+    #[test]
+    fn test_parse_fadt_acpi1() {
+        let table = fadt(116, &[
+            (40, &0x7FE_0040u32.to_le_bytes()),
+            (48, &0xB2u32.to_le_bytes()),
+            (52, &[0xF1]),
+            (64, &0x604u32.to_le_bytes()),
+        ]);
+        let fadt = parse_fadt(&table).unwrap();
+        assert_eq!(fadt.dsdt, 0x7FE_0040);
+        assert_eq!((fadt.smi_command, fadt.acpi_enable), (0xB2, 0xF1));
+        assert_eq!(
+            fadt.pm1a_control,
+            Some(GenericAddress { space: SPACE_IO, bit_width: 16, address: 0x604 })
+        );
+        assert_eq!(fadt.pm1b_control, None);
+        assert_eq!(fadt.reset, None);
+    }
+
+    #[test]
+    fn test_parse_fadt_prefers_extended_fields() {
+        let mut reset = gas(SPACE_IO, 8, 0xCF9);
+        reset[3] = 1;
+        let table = |flags: u32| {
+            fadt(244, &[
+                (40, &0x1000u32.to_le_bytes()),
+                (64, &0x604u32.to_le_bytes()),
+                (112, &flags.to_le_bytes()),
+                (116, &reset),
+                (128, &[0x06]),
+                (140, &0x1_0000_2000u64.to_le_bytes()),
+                (172, &gas(SPACE_MEMORY, 32, 0xFED0_0004)),
+            ])
+        };
+        let fadt = parse_fadt(&table(FADT_RESET_REG_SUP)).unwrap();
+        assert_eq!(fadt.dsdt, 0x1_0000_2000);
+        assert_eq!(
+            fadt.pm1a_control,
+            Some(GenericAddress { space: SPACE_MEMORY, bit_width: 32, address: 0xFED0_0004 })
+        );
+        assert_eq!(
+            fadt.reset,
+            Some((GenericAddress { space: SPACE_IO, bit_width: 8, address: 0xCF9 }, 0x06))
+        );
+
+        // The reset register only counts when the flags say it is supported
+        assert_eq!(parse_fadt(&table(0)).unwrap().reset, None);
+    }
+
+    // ========================================================================
+    // DSDT tests
+    // ========================================================================
+
+    #[test]
+    fn test_find_s5() {
+        // A reference to _S5_ in a method body comes before the definition
+        let mut aml = alloc::vec![0x70, b'_', b'S', b'5', b'_', 0x60];
+        // Name (\_S5, Package (0x04) { 0x05, Zero, Zero, Zero })
+        aml.extend_from_slice(&[0x08, b'\\', b'_', b'S', b'5', b'_', 0x12, 0x07, 0x04]);
+        aml.extend_from_slice(&[0x0A, 0x05, 0x00, 0x00, 0x00]);
+        assert_eq!(find_s5(&table(b"DSDT", &aml)), Some(SleepType { a: 5, b: 0 }));
+
+        // Two-byte PkgLength, no root prefix
+        let aml = [0x08, b'_', b'S', b'5', b'_', 0x12, 0x40, 0x00, 0x02, 0x01, 0x0A, 0x07];
+        assert_eq!(find_s5(&table(b"DSDT", &aml)), Some(SleepType { a: 1, b: 7 }));
+
+        assert_eq!(find_s5(&table(b"DSDT", &[0x08, b'_', b'S', b'3', b'_'])), None);
+    }
+}
@@ -7,17 +7,34 @@
 //!
 //! The LAPIC timer is used for preemptive scheduling. It's configured
 //! to fire every 10ms (100Hz) by default.
+//!
+//! # SMP
+//!
+//! Every CPU has its own LAPIC at the same address. The boot processor
+//! runs `init()`; application processors run `init_ap()` and are started
+//! with `send_init()` / `send_startup()`.
+//!
+//! # Discovery
+//!
+//! Register bases and IRQ routing come from the ACPI MADT when
+//! `acpi::init()` ran before `init()`: the LAPIC address override, the
+//! I/O APIC covering each GSI, and the ISA interrupt source overrides.
+//! Without ACPI the standard PC locations and identity routing are used.
 
 use core::sync::atomic::{AtomicU64, Ordering};
 use x86_64::VirtAddr;
 
+use crate::x86_64::acpi::{self, IrqRoute};
 use crate::x86_64::vmm::phys_mem_offset;
 
-/// LAPIC base physical address (standard x86_64 location)
-const LAPIC_BASE_PHYS: u64 = 0xFEE0_0000;
+/// LAPIC base physical address when ACPI does not provide one
+const DEFAULT_LAPIC_BASE_PHYS: u64 = 0xFEE0_0000;
+
+/// IOAPIC base physical address when ACPI does not provide one
+const DEFAULT_IOAPIC_BASE_PHYS: u64 = 0xFEC0_0000;
 
-/// IOAPIC base physical address (standard x86_64 location)
-const IOAPIC_BASE_PHYS: u64 = 0xFEC0_0000;
+/// LAPIC base physical address (from the MADT once `init()` ran)
+static LAPIC_BASE_PHYS: AtomicU64 = AtomicU64::new(DEFAULT_LAPIC_BASE_PHYS);
 
 /// Timer tick period in nanoseconds (10ms = 10,000,000 ns)
 pub const TICK_NANOS: u64 = 10_000_000;
@@ -54,6 +71,14 @@ pub const TIMER_VECTOR: u8 = 32;
 /// Spurious interrupt vector number
 const SPURIOUS_VECTOR: u8 = 255;
 
+/// ICR delivery modes and flags
+mod icr {
+    pub const INIT: u32 = 0x500;
+    pub const STARTUP: u32 = 0x600;
+    pub const LEVEL_ASSERT: u32 = 0x4000;
+    pub const DELIVERY_PENDING: u32 = 0x1000;
+}
+
 /// Timer LVT flags
 mod timer_lvt {
     pub const PERIODIC: u32 = 0x20000;   // Periodic mode (bit 17)
@@ -68,13 +93,23 @@ static APIC_INITIALIZED: AtomicU64 = AtomicU64::new(0);
 
 /// Get the virtual address of the LAPIC
 fn lapic_virt_addr() -> VirtAddr {
-    VirtAddr::new(LAPIC_BASE_PHYS + phys_mem_offset())
+    VirtAddr::new(LAPIC_BASE_PHYS.load(Ordering::Relaxed) + phys_mem_offset())
 }
 
-/// Get the virtual address of the IOAPIC
-#[allow(dead_code)]
-fn ioapic_virt_addr() -> VirtAddr {
-    VirtAddr::new(IOAPIC_BASE_PHYS + phys_mem_offset())
+/// Find where an ISA IRQ arrives: the IOAPIC's virtual address, the pin
+/// on that IOAPIC, and the line's polarity and trigger mode
+fn ioapic_route(irq: u8) -> (VirtAddr, u8, IrqRoute) {
+    let madt = acpi::info().and_then(|info| info.madt.as_ref());
+    let route = match madt {
+        Some(madt) => madt.route_isa_irq(irq),
+        None => IrqRoute { gsi: irq as u32, active_low: false, level_triggered: false },
+    };
+    let (base, gsi_base) = match madt.and_then(|madt| madt.io_apic_for(route.gsi)) {
+        Some(io_apic) => (io_apic.address as u64, io_apic.gsi_base),
+        None => (DEFAULT_IOAPIC_BASE_PHYS, 0),
+    };
+    let pin = (route.gsi - gsi_base) as u8;
+    (VirtAddr::new(base + phys_mem_offset()), pin, route)
 }
 
 /// Read a LAPIC register
@@ -89,20 +124,16 @@ unsafe fn write_lapic(offset: usize, value: u32) {
     core::ptr::write_volatile(addr.as_mut_ptr::<u32>(), value);
 }
 
-/// Read an IOAPIC register
-#[allow(dead_code)]
-unsafe fn read_ioapic(reg: u8) -> u32 {
-    let base = ioapic_virt_addr();
+/// Read a register of the IOAPIC at `base`
+unsafe fn read_ioapic(base: VirtAddr, reg: u8) -> u32 {
     // Write register selector
     core::ptr::write_volatile((base.as_u64() + ioapic_reg::IOREGSEL as u64) as *mut u32, reg as u32);
     // Read value
     core::ptr::read_volatile((base.as_u64() + ioapic_reg::IOWIN as u64) as *const u32)
 }
 
-/// Write an IOAPIC register
-#[allow(dead_code)]
-unsafe fn write_ioapic(reg: u8, value: u32) {
-    let base = ioapic_virt_addr();
+/// Write a register of the IOAPIC at `base`
+unsafe fn write_ioapic(base: VirtAddr, reg: u8, value: u32) {
     // Write register selector
     core::ptr::write_volatile((base.as_u64() + ioapic_reg::IOREGSEL as u64) as *mut u32, reg as u32);
     // Write value
@@ -159,9 +190,30 @@ pub unsafe fn init() {
         return;
     }
 
+    if let Some(madt) = acpi::info().and_then(|info| info.madt.as_ref()) {
+        LAPIC_BASE_PHYS.store(madt.lapic_address, Ordering::Relaxed);
+    }
+
     // First, disable the legacy PIC to prevent spurious interrupts
     disable_pic();
 
+    enable_lapic();
+
+    APIC_INITIALIZED.store(1, Ordering::Release);
+}
+
+/// Initialize the Local APIC of an application processor
+///
+/// Same LAPIC setup as `init()`, without touching the (shared) legacy PIC.
+///
+/// # Safety
+/// Must be called once on each AP, after the BSP ran `init()`.
+pub unsafe fn init_ap() {
+    enable_lapic();
+}
+
+/// Enable the current CPU's LAPIC with its timer configured but masked
+unsafe fn enable_lapic() {
     // Read current SVR
     let svr = read_lapic(lapic_reg::SVR);
     
@@ -183,8 +235,6 @@ pub unsafe fn init() {
 
     // Set initial count to 0 (timer not running)
     write_lapic(lapic_reg::TIMER_ICR, 0);
-
-    APIC_INITIALIZED.store(1, Ordering::Release);
 }
 
 /// Start the LAPIC timer
@@ -260,6 +310,31 @@ pub fn enable_timer() {
     }
 }
 
+/// Send an INIT IPI to the CPU with the given APIC ID
+///
+/// # Safety
+/// Resets the target CPU; it must not be running anything.
+pub unsafe fn send_init(apic_id: u8) {
+    send_ipi(apic_id, icr::INIT | icr::LEVEL_ASSERT);
+}
+
+/// Send a STARTUP IPI: the target starts in real mode at `page * 4096`
+///
+/// # Safety
+/// Startup code must be present at that page.
+pub unsafe fn send_startup(apic_id: u8, page: u8) {
+    send_ipi(apic_id, icr::STARTUP | icr::LEVEL_ASSERT | page as u32);
+}
+
+/// Write the ICR and wait until the IPI has been accepted
+unsafe fn send_ipi(apic_id: u8, command: u32) {
+    write_lapic(lapic_reg::ICR_HIGH, (apic_id as u32) << 24);
+    write_lapic(lapic_reg::ICR_LOW, command);
+    while read_lapic(lapic_reg::ICR_LOW) & icr::DELIVERY_PENDING != 0 {
+        core::hint::spin_loop();
+    }
+}
+
 /// Get LAPIC ID
 pub fn lapic_id() -> u32 {
     unsafe { read_lapic(lapic_reg::ID) >> 24 }
@@ -275,31 +350,75 @@ pub fn is_initialized() -> bool {
     APIC_INITIALIZED.load(Ordering::Acquire) != 0
 }
 
-/// Configure IOAPIC redirection entry for an IRQ
+/// Redirection entry flags (low dword)
+mod redirection {
+    pub const ACTIVE_LOW: u32 = 1 << 13;
+    pub const LEVEL_TRIGGERED: u32 = 1 << 15;
+    pub const MASKED: u32 = 1 << 16;
+}
+
+/// Configure IOAPIC redirection entry for an ISA IRQ
+///
+/// The IRQ is routed through the MADT's interrupt source overrides, which
+/// also give its polarity and trigger mode.
 #[allow(dead_code)]
 pub unsafe fn ioapic_configure(irq: u8, vector: u8, dest_cpu: u8) {
-    let reg = ioapic_reg::REDTBL_BASE + irq * 2;
-    // Low dword: vector, active high, edge triggered, fixed delivery, unmasked
-    let low = vector as u32;
+    let (base, pin, route) = ioapic_route(irq);
+    program_redirection(base, pin, vector, dest_cpu, route.active_low, route.level_triggered);
+}
+
+/// Configure IOAPIC redirection entry for a level-triggered IRQ
+///
+/// Used for PCI INTx lines. The line is routed through the MADT's
+/// interrupt source overrides (QEMU's PIIX lists its PCI link IRQs there
+/// as level-triggered, active-high); it is level triggered either way.
+///
+/// # Safety
+/// A handler for `vector` must be installed.
+pub unsafe fn ioapic_configure_level(irq: u8, vector: u8, dest_cpu: u8) {
+    let (base, pin, route) = ioapic_route(irq);
+    program_redirection(base, pin, vector, dest_cpu, route.active_low, true);
+}
+
+/// Write an unmasked, fixed-delivery redirection entry
+unsafe fn program_redirection(
+    base: VirtAddr,
+    pin: u8,
+    vector: u8,
+    dest_cpu: u8,
+    active_low: bool,
+    level_triggered: bool,
+) {
+    let reg = ioapic_reg::REDTBL_BASE + pin * 2;
+    let mut low = vector as u32;
+    if active_low {
+        low |= redirection::ACTIVE_LOW;
+    }
+    if level_triggered {
+        low |= redirection::LEVEL_TRIGGERED;
+    }
     // High dword: destination CPU in bits 24-31
     let high = (dest_cpu as u32) << 24;
 
-    write_ioapic(reg, low);
-    write_ioapic(reg + 1, high);
+    // Destination first, so the entry is never live with a stale one
+    write_ioapic(base, reg + 1, high);
+    write_ioapic(base, reg, low);
 }
 
 /// Mask an IRQ in IOAPIC
 #[allow(dead_code)]
 pub unsafe fn ioapic_mask(irq: u8) {
-    let reg = ioapic_reg::REDTBL_BASE + irq * 2;
-    let low = read_ioapic(reg);
-    write_ioapic(reg, low | (1 << 16)); // Set mask bit
+    let (base, pin, _) = ioapic_route(irq);
+    let reg = ioapic_reg::REDTBL_BASE + pin * 2;
+    let low = read_ioapic(base, reg);
+    write_ioapic(base, reg, low | redirection::MASKED);
 }
 
 /// Unmask an IRQ in IOAPIC
 #[allow(dead_code)]
 pub unsafe fn ioapic_unmask(irq: u8) {
-    let reg = ioapic_reg::REDTBL_BASE + irq * 2;
-    let low = read_ioapic(reg);
-    write_ioapic(reg, low & !(1 << 16)); // Clear mask bit
+    let (base, pin, _) = ioapic_route(irq);
+    let reg = ioapic_reg::REDTBL_BASE + pin * 2;
+    let low = read_ioapic(base, reg);
+    write_ioapic(base, reg, low & !redirection::MASKED);
 }
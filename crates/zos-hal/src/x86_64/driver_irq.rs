@@ -0,0 +1,82 @@
+//! ISA IRQ lines delegated to user-space drivers
+//!
+//! A line bound by a driver is routed through the I/O APIC to vector
+//! `DRIVER_IRQ_BASE + irq`. Its handler masks the line and records it as
+//! pending; the kernel loop takes pending lines and hands them to the
+//! kernel, which delivers them to the bound notification or endpoint. The
+//! line is unmasked again when the driver acknowledges the interrupt.
+//!
+//! Lines the kernel drives itself (the PIT, the PIC cascade, COM1 and any
+//! virtio-blk INTx line) are refused.
+
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicU16, Ordering};
+
+use super::apic;
+use crate::HalError;
+
+/// Number of ISA IRQ lines
+pub const LINES: u8 = 16;
+
+/// First IDT vector used for driver IRQs (ISA IRQ n arrives on base + n)
+pub const DRIVER_IRQ_BASE: u8 = 64;
+
+/// PIT (0), PIC cascade (2) and COM1 (4)
+const KERNEL_LINES: u16 = (1 << 0) | (1 << 2) | (1 << 4);
+
+/// Lines claimed by kernel drivers at runtime (virtio-blk INTx)
+static RESERVED: AtomicU16 = AtomicU16::new(KERNEL_LINES);
+/// Lines routed to user-space delivery
+static ROUTED: AtomicU16 = AtomicU16::new(0);
+/// Lines that fired since the kernel loop last looked
+static PENDING: AtomicU16 = AtomicU16::new(0);
+
+/// Keep a line the kernel uses for one of its own drivers
+pub fn reserve(irq: u8) {
+    if irq < LINES {
+        RESERVED.fetch_or(1 << irq, Ordering::AcqRel);
+    }
+}
+
+/// Route `irq` to its driver vector, or unmask it if already routed
+pub fn enable(irq: u8) -> Result<(), HalError> {
+    if irq >= LINES || RESERVED.load(Ordering::Acquire) & (1 << irq) != 0 {
+        return Err(HalError::InvalidArgument);
+    }
+    let bit = 1 << irq;
+    // SAFETY: vectors DRIVER_IRQ_BASE.. have handlers installed by the IDT
+    unsafe {
+        if ROUTED.fetch_or(bit, Ordering::AcqRel) & bit == 0 {
+            apic::ioapic_configure(irq, DRIVER_IRQ_BASE + irq, apic::lapic_id() as u8);
+        } else {
+            apic::ioapic_unmask(irq);
+        }
+    }
+    Ok(())
+}
+
+/// Mask `irq` and forget any interrupt still pending on it
+pub fn disable(irq: u8) {
+    if irq >= LINES {
+        return;
+    }
+    let bit = 1 << irq;
+    if ROUTED.fetch_and(!bit, Ordering::AcqRel) & bit != 0 {
+        // SAFETY: the line was routed by `enable`, so its entry exists
+        unsafe { apic::ioapic_mask(irq) };
+    }
+    PENDING.fetch_and(!bit, Ordering::AcqRel);
+}
+
+/// Interrupt handler body for `irq`: mask the line until acknowledged
+pub(super) fn handle(irq: u8) {
+    // SAFETY: only reachable through a vector `enable` routed
+    unsafe { apic::ioapic_mask(irq) };
+    PENDING.fetch_or(1 << irq, Ordering::AcqRel);
+}
+
+/// Take the lines that fired since the last call
+pub fn take_pending() -> Vec<u8> {
+    let pending = PENDING.swap(0, Ordering::AcqRel) & ROUTED.load(Ordering::Acquire);
+    (0..LINES).filter(|irq| pending & (1 << irq) != 0).collect()
+}
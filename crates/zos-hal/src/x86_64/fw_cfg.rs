@@ -0,0 +1,96 @@
+//! QEMU firmware configuration (fw_cfg) reader
+//!
+//! QEMU exposes named blobs to the guest through the fw_cfg device. The host
+//! adds them with `-fw_cfg name=opt/...,file=...`; the guest finds them by
+//! name in the file directory. Zero OS uses this to receive secrets (such as
+//! the keystore master key) that must not live on any guest-visible disk.
+//!
+//! # I/O Ports
+//!
+//! - 0x510: selector port (16-bit write)
+//! - 0x511: data port (8-bit read, auto-increments)
+//!
+//! # File Directory (selector 0x19)
+//!
+//! All integers are big-endian:
+//! - 4 bytes: file count
+//! - 64 bytes per file: size (u32), select (u16), reserved (u16), name (56 bytes, NUL-padded)
+
+use alloc::vec::Vec;
+use x86_64::instructions::port::Port;
+
+/// fw_cfg selector port
+const FW_CFG_SELECTOR: u16 = 0x510;
+/// fw_cfg data port
+const FW_CFG_DATA: u16 = 0x511;
+
+/// Selector for the "QEMU" signature
+const FW_CFG_SIGNATURE: u16 = 0x00;
+/// Selector for the file directory
+const FW_CFG_FILE_DIR: u16 = 0x19;
+
+/// Size of a file directory entry
+const FILE_ENTRY_SIZE: usize = 64;
+/// Size of the name field in a file directory entry
+const FILE_NAME_LEN: usize = 56;
+
+/// Select an item and read `buf.len()` bytes from its start
+///
+/// # Safety
+/// This function performs raw I/O port access.
+unsafe fn read_item(selector: u16, buf: &mut [u8]) {
+    let mut selector_port: Port<u16> = Port::new(FW_CFG_SELECTOR);
+    selector_port.write(selector);
+    read_more(buf);
+}
+
+/// Continue reading the currently selected item
+///
+/// # Safety
+/// This function performs raw I/O port access.
+unsafe fn read_more(buf: &mut [u8]) {
+    let mut data_port: Port<u8> = Port::new(FW_CFG_DATA);
+    for byte in buf.iter_mut() {
+        *byte = data_port.read();
+    }
+}
+
+/// Check whether the fw_cfg device is present
+pub fn is_present() -> bool {
+    let mut signature = [0u8; 4];
+    unsafe { read_item(FW_CFG_SIGNATURE, &mut signature) };
+    &signature == b"QEMU"
+}
+
+/// Read a named fw_cfg file
+///
+/// Returns `None` if there is no fw_cfg device or no file with that name.
+pub fn read_file(name: &str) -> Option<Vec<u8>> {
+    if !is_present() || name.len() >= FILE_NAME_LEN {
+        return None;
+    }
+
+    let mut count = [0u8; 4];
+    unsafe { read_item(FW_CFG_FILE_DIR, &mut count) };
+    let count = u32::from_be_bytes(count);
+
+    // Walk the directory looking for the name, then select the file
+    let mut entry = [0u8; FILE_ENTRY_SIZE];
+    let mut found = None;
+    for _ in 0..count {
+        unsafe { read_more(&mut entry) };
+        let entry_name = &entry[8..8 + FILE_NAME_LEN];
+        let name_len = entry_name.iter().position(|&b| b == 0).unwrap_or(FILE_NAME_LEN);
+        if &entry_name[..name_len] == name.as_bytes() {
+            let size = u32::from_be_bytes([entry[0], entry[1], entry[2], entry[3]]);
+            let select = u16::from_be_bytes([entry[4], entry[5]]);
+            found = Some((size as usize, select));
+            break;
+        }
+    }
+
+    let (size, select) = found?;
+    let mut data = alloc::vec![0u8; size];
+    unsafe { read_item(select, &mut data) };
+    Some(data)
+}
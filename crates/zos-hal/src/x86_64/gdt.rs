@@ -2,7 +2,13 @@
 //!
 //! The GDT defines segment descriptors for kernel and user mode code/data,
 //! as well as the Task State Segment (TSS) for interrupt stack switching.
+//!
+//! The boot processor uses the static tables below. Each application
+//! processor needs its own TSS (a TSS descriptor is marked busy once
+//! loaded) and interrupt stacks, so `init_ap()` builds a separate set on
+//! the heap.
 
+use alloc::boxed::Box;
 use x86_64::instructions::segmentation::{Segment, CS, DS, ES, FS, GS, SS};
 use x86_64::instructions::tables::load_tss;
 use x86_64::structures::gdt::{Descriptor, GlobalDescriptorTable, SegmentSelector};
@@ -61,24 +67,50 @@ pub unsafe fn init() {
         VirtAddr::from_ptr((*page_fault_stack_ptr).as_ptr()) + PAGE_FAULT_STACK_SIZE as u64;
     (*tss_ptr).interrupt_stack_table[PAGE_FAULT_IST_INDEX as usize] = page_fault_stack_end;
 
-    // Build the GDT
+    let gdt_ptr = &raw mut GDT;
+    *gdt_ptr = Some(build(&*tss_ptr));
+
+    // Load the GDT
+    let (gdt, selectors) = (*gdt_ptr).as_ref().unwrap();
+    load(gdt, selectors);
+}
+
+/// Initialize a GDT and TSS for the calling application processor
+///
+/// The tables and interrupt stacks are leaked: APs never go offline.
+///
+/// # Safety
+/// Must be called once on each AP, with the heap initialized.
+pub unsafe fn init_ap() {
+    let tss = Box::leak(Box::new(TaskStateSegment::new()));
+    for index in [DOUBLE_FAULT_IST_INDEX, PAGE_FAULT_IST_INDEX] {
+        let stack = Box::leak(alloc::vec![0u8; INTERRUPT_STACK_SIZE].into_boxed_slice());
+        tss.interrupt_stack_table[index as usize] =
+            VirtAddr::from_ptr(stack.as_ptr()) + INTERRUPT_STACK_SIZE as u64;
+    }
+
+    let (gdt, selectors) = &*Box::leak(Box::new(build(tss)));
+    load(gdt, selectors);
+}
+
+/// Build a GDT with kernel code/data segments and the given TSS
+fn build(tss: &'static TaskStateSegment) -> (GlobalDescriptorTable, Selectors) {
     let mut gdt = GlobalDescriptorTable::new();
     let code_selector = gdt.append(Descriptor::kernel_code_segment());
     let data_selector = gdt.append(Descriptor::kernel_data_segment());
-    let tss_selector = gdt.append(Descriptor::tss_segment(&*tss_ptr));
-
-    let gdt_ptr = &raw mut GDT;
-    *gdt_ptr = Some((
+    let tss_selector = gdt.append(Descriptor::tss_segment(tss));
+    (
         gdt,
         Selectors {
             code_selector,
             data_selector,
             tss_selector,
         },
-    ));
+    )
+}
 
-    // Load the GDT
-    let (gdt, selectors) = (*gdt_ptr).as_ref().unwrap();
+/// Load a GDT, reload the segment registers and load the TSS
+unsafe fn load(gdt: &'static GlobalDescriptorTable, selectors: &Selectors) {
     gdt.load();
 
     // Reload segment registers
@@ -8,13 +8,19 @@
 //! |--------|-------------|
 //! | 0-31   | CPU exceptions |
 //! | 32     | Timer interrupt (APIC) |
+//! | 36     | Serial COM1 (IRQ4) |
+//! | 48     | VirtIO block, data disk (MSI-X or INTx) |
+//! | 49     | VirtIO block, keystore disk (MSI-X or INTx) |
+//! | 64-79  | ISA IRQs 0-15 bound to user-space drivers |
 //! | 33-255 | Available for IRQs |
 
 use crate::serial_println;
-use super::apic;
+use super::{apic, driver_irq, smp};
 use super::gdt::{DOUBLE_FAULT_IST_INDEX, PAGE_FAULT_IST_INDEX};
 use spin::Lazy;
-use x86_64::structures::idt::{InterruptDescriptorTable, InterruptStackFrame, PageFaultErrorCode};
+use x86_64::structures::idt::{
+    HandlerFunc, InterruptDescriptorTable, InterruptStackFrame, PageFaultErrorCode,
+};
 
 /// Interrupt index for hardware interrupts
 #[derive(Debug, Clone, Copy)]
@@ -23,6 +29,10 @@ pub enum InterruptIndex {
     Timer = 32,
     /// Serial COM1 interrupt (IRQ4)
     SerialInput = 36,
+    /// VirtIO block completions for the data disk
+    BlockData = 48,
+    /// VirtIO block completions for the keystore disk
+    BlockKeystore = 49,
 }
 
 impl InterruptIndex {
@@ -76,6 +86,15 @@ static IDT: Lazy<InterruptDescriptorTable> = Lazy::new(|| {
     // Serial input interrupt (vector 36 = IRQ4)
     idt[InterruptIndex::SerialInput.as_u8()].set_handler_fn(serial_input_handler);
 
+    // VirtIO block completion interrupts (vectors 48-49)
+    idt[InterruptIndex::BlockData.as_u8()].set_handler_fn(block_data_handler);
+    idt[InterruptIndex::BlockKeystore.as_u8()].set_handler_fn(block_keystore_handler);
+
+    // ISA IRQs delegated to user-space drivers (vectors 64-79)
+    for (irq, handler) in DRIVER_IRQ_HANDLERS.iter().enumerate() {
+        idt[driver_irq::DRIVER_IRQ_BASE + irq as u8].set_handler_fn(*handler);
+    }
+
     idt
 });
 
@@ -233,18 +252,20 @@ extern "x86-interrupt" fn simd_floating_point_handler(stack_frame: InterruptStac
 
 /// Timer interrupt handler (vector 32)
 ///
-/// This handler is called every ~10ms by the LAPIC timer.
+/// This handler is called every ~10ms by the LAPIC timer of each CPU.
 /// It updates the system time and will eventually trigger the scheduler.
 extern "x86-interrupt" fn timer_interrupt_handler(_stack_frame: InterruptStackFrame) {
-    // Handle the timer tick (increments tick counter)
-    let tick = apic::handle_timer_tick();
-    
-    // Print every 100 ticks (once per second) for debugging
-    // Remove this once verified working
-    if tick % 100 == 0 {
-        serial_println!("[Timer] Tick {} ({} seconds)", tick, tick / 100);
+    // Only the BSP's timer drives system time; APs just count their ticks
+    let cpu = smp::current_cpu();
+    if cpu != smp::BOOT_CPU {
+        smp::record_tick(cpu);
+        apic::eoi();
+        return;
     }
     
+    // Handle the timer tick (increments tick counter)
+    apic::handle_timer_tick();
+    
     // Send End-Of-Interrupt to LAPIC
     // This must be done AFTER processing to allow nested interrupts
     apic::eoi();
@@ -266,3 +287,56 @@ extern "x86-interrupt" fn serial_input_handler(_stack_frame: InterruptStackFrame
     // Send End-Of-Interrupt to LAPIC
     apic::eoi();
 }
+
+/// VirtIO block interrupt handler for the data disk (vector 48)
+///
+/// Only flags the device; completions are collected by the kernel loop.
+extern "x86-interrupt" fn block_data_handler(_stack_frame: InterruptStackFrame) {
+    use super::virtio::blk_pci::{self, Disk};
+
+    blk_pci::handle_interrupt(Disk::Data);
+    apic::eoi();
+}
+
+/// VirtIO block interrupt handler for the keystore disk (vector 49)
+extern "x86-interrupt" fn block_keystore_handler(_stack_frame: InterruptStackFrame) {
+    use super::virtio::blk_pci::{self, Disk};
+
+    blk_pci::handle_interrupt(Disk::Keystore);
+    apic::eoi();
+}
+
+/// Define one handler per driver IRQ line; an x86-interrupt handler is not
+/// told which vector it was entered through.
+macro_rules! driver_irq_handlers {
+    ($($name:ident = $irq:literal),* $(,)?) => {
+        $(
+            extern "x86-interrupt" fn $name(_stack_frame: InterruptStackFrame) {
+                driver_irq::handle($irq);
+                apic::eoi();
+            }
+        )*
+
+        /// Handlers for vectors `DRIVER_IRQ_BASE..`, indexed by ISA IRQ
+        const DRIVER_IRQ_HANDLERS: [HandlerFunc; driver_irq::LINES as usize] = [$($name),*];
+    };
+}
+
+driver_irq_handlers! {
+    driver_irq_0 = 0,
+    driver_irq_1 = 1,
+    driver_irq_2 = 2,
+    driver_irq_3 = 3,
+    driver_irq_4 = 4,
+    driver_irq_5 = 5,
+    driver_irq_6 = 6,
+    driver_irq_7 = 7,
+    driver_irq_8 = 8,
+    driver_irq_9 = 9,
+    driver_irq_10 = 10,
+    driver_irq_11 = 11,
+    driver_irq_12 = 12,
+    driver_irq_13 = 13,
+    driver_irq_14 = 14,
+    driver_irq_15 = 15,
+}
@@ -0,0 +1,355 @@
+//! Keystore - Encrypted Key Storage on a Dedicated VirtIO Block Device
+//!
+//! Backs the HAL `keystore_*_async` methods on x86_64. Per Invariant 32 key
+//! material is physically separate from VFS data: the keystore lives on the
+//! second virtio-blk device ([`Disk::Keystore`]) and uses its own
+//! [`BlockStorage`] instance, never the data disk.
+//!
+//! # Encryption at Rest
+//!
+//! The master key is supplied by the host through QEMU fw_cfg
+//! (`-fw_cfg name=opt/zos/keystore-key,file=...`), so it never touches either
+//! disk. Without it the keystore stays unavailable rather than falling back to
+//! plaintext.
+//!
+//! - Data key: `SHA-256("zos-keystore/v1" || master key)`
+//! - Slot name: hex of `SHA-256(data key || "slot" || key name)`, so key names
+//!   are not visible on disk
+//! - Slot value: `salt (32 bytes) || AES-256-GCM(name_len u32 | name | value)`
+//!   with a per-record key `SHA-256(data key || salt)` and the slot name as
+//!   associated data, so records cannot be swapped between slots
+//!
+//! A verifier record written on first mount detects a wrong master key before
+//! any record is read or overwritten.
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use aes_gcm::aead::{Aead, Payload};
+use aes_gcm::{Aes256Gcm, Key, KeyInit, Nonce};
+use sha2::{Digest, Sha256};
+use spin::Mutex;
+
+use super::fw_cfg;
+use super::storage::BlockStorage;
+use super::virtio::blk_pci::Disk;
+use super::virtio::VirtioError;
+
+/// fw_cfg file holding the keystore master key
+const MASTER_KEY_FILE: &str = "opt/zos/keystore-key";
+
+/// Minimum master key length in bytes
+const MIN_MASTER_KEY_LEN: usize = 16;
+
+/// Domain separator for the data key derivation
+const KEY_DOMAIN: &[u8] = b"zos-keystore/v1";
+
+/// Slot holding the verifier record
+const VERIFIER_SLOT: &str = "verifier";
+
+/// Plaintext of the verifier record
+const VERIFIER_PLAINTEXT: &[u8] = b"zos-keystore verifier";
+
+/// Per-record salt length
+const SALT_LEN: usize = 32;
+
+/// Keystore errors
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeystoreError {
+    /// No master key was supplied via fw_cfg
+    NoMasterKey,
+    /// The master key does not match the one the keystore was created with
+    WrongMasterKey,
+    /// Keystore not mounted
+    NotInitialized,
+    /// A record failed to decrypt or is malformed
+    Corrupt,
+    /// Underlying block device error
+    Device(VirtioError),
+}
+
+impl From<VirtioError> for KeystoreError {
+    fn from(e: VirtioError) -> Self {
+        KeystoreError::Device(e)
+    }
+}
+
+/// Result type for keystore operations
+pub type KeystoreResult<T> = Result<T, KeystoreError>;
+
+/// Mounted keystore state
+struct Keystore {
+    /// Records on the keystore disk
+    storage: BlockStorage,
+    /// Key derived from the master key
+    data_key: [u8; 32],
+    /// In-memory index: key name -> slot name
+    names: BTreeMap<String, String>,
+    /// Whether the keystore is mounted
+    initialized: bool,
+}
+
+/// Global keystore instance
+static KEYSTORE: Mutex<Keystore> = Mutex::new(Keystore {
+    storage: BlockStorage::new(Disk::Keystore),
+    data_key: [0; 32],
+    names: BTreeMap::new(),
+    initialized: false,
+});
+
+/// Counter mixed into salts so two records never share one
+static SALT_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Derive the data key from the master key
+fn derive_data_key(master_key: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(KEY_DOMAIN);
+    hasher.update(master_key);
+    hasher.finalize().into()
+}
+
+/// Hex-encode a digest
+fn to_hex(bytes: &[u8]) -> String {
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+    let mut out = String::with_capacity(bytes.len() * 2);
+    for b in bytes {
+        out.push(HEX[(b >> 4) as usize] as char);
+        out.push(HEX[(b & 0xF) as usize] as char);
+    }
+    out
+}
+
+/// Generate a fresh record salt
+///
+/// RDRAND output is hashed together with the TSC and a counter, so salts stay
+/// unique even when RDRAND is unavailable.
+fn new_salt() -> [u8; SALT_LEN] {
+    let mut random = [0u8; SALT_LEN];
+    let _ = super::random::fill_random_bytes(&mut random);
+    let mut hasher = Sha256::new();
+    hasher.update(random);
+    hasher.update(super::read_tsc().to_le_bytes());
+    hasher.update(SALT_COUNTER.fetch_add(1, Ordering::Relaxed).to_le_bytes());
+    hasher.finalize().into()
+}
+
+impl Keystore {
+    /// Slot name for a key name
+    fn slot_for(&self, name: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.data_key);
+        hasher.update(b"slot");
+        hasher.update(name.as_bytes());
+        to_hex(&hasher.finalize())
+    }
+
+    /// Cipher for one record
+    fn record_cipher(&self, salt: &[u8]) -> Aes256Gcm {
+        let mut hasher = Sha256::new();
+        hasher.update(self.data_key);
+        hasher.update(salt);
+        let key: [u8; 32] = hasher.finalize().into();
+        Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key))
+    }
+
+    /// Encrypt a plaintext record for `slot`
+    fn seal(&self, slot: &str, plaintext: &[u8]) -> KeystoreResult<Vec<u8>> {
+        let salt = new_salt();
+        // Each record key is used exactly once, so a fixed nonce is safe
+        let ciphertext = self
+            .record_cipher(&salt)
+            .encrypt(
+                Nonce::from_slice(&[0u8; 12]),
+                Payload {
+                    msg: plaintext,
+                    aad: slot.as_bytes(),
+                },
+            )
+            .map_err(|_| KeystoreError::Corrupt)?;
+        let mut sealed = Vec::with_capacity(SALT_LEN + ciphertext.len());
+        sealed.extend_from_slice(&salt);
+        sealed.extend_from_slice(&ciphertext);
+        Ok(sealed)
+    }
+
+    /// Decrypt a record stored in `slot`
+    fn open(&self, slot: &str, sealed: &[u8]) -> KeystoreResult<Vec<u8>> {
+        if sealed.len() < SALT_LEN {
+            return Err(KeystoreError::Corrupt);
+        }
+        let (salt, ciphertext) = sealed.split_at(SALT_LEN);
+        self.record_cipher(salt)
+            .decrypt(
+                Nonce::from_slice(&[0u8; 12]),
+                Payload {
+                    msg: ciphertext,
+                    aad: slot.as_bytes(),
+                },
+            )
+            .map_err(|_| KeystoreError::Corrupt)
+    }
+
+    /// Decrypt a record and split it into key name and value
+    fn open_record(&self, slot: &str, sealed: &[u8]) -> KeystoreResult<(String, Vec<u8>)> {
+        let plaintext = self.open(slot, sealed)?;
+        if plaintext.len() < 4 {
+            return Err(KeystoreError::Corrupt);
+        }
+        let name_len = u32::from_le_bytes([plaintext[0], plaintext[1], plaintext[2], plaintext[3]]) as usize;
+        let name_end = 4usize.checked_add(name_len).ok_or(KeystoreError::Corrupt)?;
+        if name_end > plaintext.len() {
+            return Err(KeystoreError::Corrupt);
+        }
+        let name = core::str::from_utf8(&plaintext[4..name_end]).map_err(|_| KeystoreError::Corrupt)?;
+        Ok((String::from(name), plaintext[name_end..].to_vec()))
+    }
+
+    /// Mount the keystore disk with the given master key
+    fn mount(&mut self, master_key: &[u8]) -> KeystoreResult<bool> {
+        let is_new = self.storage.init()?;
+        self.data_key = derive_data_key(master_key);
+
+        // Check (or create) the verifier before touching any record
+        match self.storage.read(VERIFIER_SLOT)? {
+            Some(sealed) => match self.open(VERIFIER_SLOT, &sealed) {
+                Ok(plaintext) if plaintext == VERIFIER_PLAINTEXT => {}
+                _ => {
+                    self.data_key = [0; 32];
+                    return Err(KeystoreError::WrongMasterKey);
+                }
+            },
+            None => {
+                let sealed = self.seal(VERIFIER_SLOT, VERIFIER_PLAINTEXT)?;
+                self.storage.write(VERIFIER_SLOT, &sealed)?;
+            }
+        }
+
+        // Rebuild the name index; unreadable records are skipped
+        self.names.clear();
+        for slot in self.storage.list("") {
+            if slot == VERIFIER_SLOT {
+                continue;
+            }
+            if let Some(sealed) = self.storage.read(&slot)? {
+                match self.open_record(&slot, &sealed) {
+                    Ok((name, _)) => {
+                        self.names.insert(name, slot);
+                    }
+                    Err(_) => {
+                        crate::serial_println!("[keystore] Skipping unreadable record {}", slot);
+                    }
+                }
+            }
+        }
+
+        self.initialized = true;
+        Ok(is_new)
+    }
+
+    fn read(&self, name: &str) -> KeystoreResult<Option<Vec<u8>>> {
+        let slot = match self.names.get(name) {
+            Some(slot) => slot,
+            None => return Ok(None),
+        };
+        match self.storage.read(slot)? {
+            Some(sealed) => {
+                let (stored_name, value) = self.open_record(slot, &sealed)?;
+                if stored_name != name {
+                    return Err(KeystoreError::Corrupt);
+                }
+                Ok(Some(value))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn write(&mut self, name: &str, value: &[u8]) -> KeystoreResult<()> {
+        let slot = self.slot_for(name);
+        let mut plaintext = Vec::with_capacity(4 + name.len() + value.len());
+        plaintext.extend_from_slice(&(name.len() as u32).to_le_bytes());
+        plaintext.extend_from_slice(name.as_bytes());
+        plaintext.extend_from_slice(value);
+        let sealed = self.seal(&slot, &plaintext)?;
+        self.storage.write(&slot, &sealed)?;
+        self.names.insert(String::from(name), slot);
+        Ok(())
+    }
+
+    fn delete(&mut self, name: &str) -> KeystoreResult<bool> {
+        match self.names.remove(name) {
+            Some(slot) => Ok(self.storage.delete(&slot)?),
+            None => Ok(false),
+        }
+    }
+
+    fn list(&self, prefix: &str) -> Vec<String> {
+        self.names
+            .keys()
+            .filter(|name| name.starts_with(prefix))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Mount the keystore using the master key from fw_cfg
+///
+/// Returns `Ok(true)` if a new keystore was created.
+pub fn init() -> KeystoreResult<bool> {
+    let master_key = fw_cfg::read_file(MASTER_KEY_FILE).ok_or(KeystoreError::NoMasterKey)?;
+    if master_key.len() < MIN_MASTER_KEY_LEN {
+        return Err(KeystoreError::NoMasterKey);
+    }
+    KEYSTORE.lock().mount(&master_key)
+}
+
+/// Check if the keystore is mounted
+pub fn is_initialized() -> bool {
+    KEYSTORE.lock().initialized
+}
+
+/// Read a key's value
+pub fn read(name: &str) -> KeystoreResult<Option<Vec<u8>>> {
+    let keystore = KEYSTORE.lock();
+    if !keystore.initialized {
+        return Err(KeystoreError::NotInitialized);
+    }
+    keystore.read(name)
+}
+
+/// Write a key's value
+pub fn write(name: &str, value: &[u8]) -> KeystoreResult<()> {
+    let mut keystore = KEYSTORE.lock();
+    if !keystore.initialized {
+        return Err(KeystoreError::NotInitialized);
+    }
+    keystore.write(name, value)
+}
+
+/// Delete a key
+pub fn delete(name: &str) -> KeystoreResult<bool> {
+    let mut keystore = KEYSTORE.lock();
+    if !keystore.initialized {
+        return Err(KeystoreError::NotInitialized);
+    }
+    keystore.delete(name)
+}
+
+/// List key names with prefix
+pub fn list(prefix: &str) -> KeystoreResult<Vec<String>> {
+    let keystore = KEYSTORE.lock();
+    if !keystore.initialized {
+        return Err(KeystoreError::NotInitialized);
+    }
+    Ok(keystore.list(prefix))
+}
+
+/// Check if a key exists
+pub fn exists(name: &str) -> KeystoreResult<bool> {
+    let keystore = KEYSTORE.lock();
+    if !keystore.initialized {
+        return Err(KeystoreError::NotInitialized);
+    }
+    Ok(keystore.names.contains_key(name))
+}
@@ -10,17 +10,31 @@
 //! - **Interrupts**: Interrupt Descriptor Table for exception handling
 //! - **VMM**: Virtual Memory Manager with 4-level page tables
 //! - **APIC**: Local APIC for timer and interrupt handling
+//! - **Driver IRQs**: ISA IRQ lines delegated to user-space drivers
+//! - **ACPI**: CPU, I/O APIC and IRQ override discovery; power-off and reset
+//! - **SMP**: Application processor startup and per-CPU setup
+//! - **Shared regions**: Frame-backed memory shared between processes
 //! - **VirtIO**: VirtIO device drivers (block, network, etc.)
+//! - **Keystore**: Encrypted key storage on a dedicated virtio-blk device
+//! - **fw_cfg**: QEMU firmware configuration reader (keystore master key)
+//! - **Net**: TCP/IP stack (DHCP, DNS) and HTTP/1.1 client over virtio-net
 //! - **WASM**: WASM runtime for executing service binaries
 
+pub mod acpi;
 pub mod apic;
+pub mod driver_irq;
+pub mod fw_cfg;
 pub mod gdt;
 pub mod interrupts;
+pub mod keystore;
+pub mod net;
 pub mod pci;
 pub mod random;
 pub mod rtc;
 #[macro_use]
 pub mod serial;
+pub mod shared_region;
+pub mod smp;
 pub mod storage;
 pub mod virtio;
 pub mod vmm;
@@ -69,7 +83,7 @@ use alloc::vec::Vec;
 use core::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 use spin::Mutex;
 
-use crate::{HalError, NumericProcessHandle, StorageRequestId, HAL};
+use crate::{HalError, NetworkRequestId, NumericProcessHandle, Priority, StorageRequestId, HAL};
 
 // Re-export WASM runtime types
 pub use wasm::{WasmRuntime, PendingSyscall};
@@ -82,21 +96,110 @@ pub use wasm::{WasmRuntime, PendingSyscall};
 /// result in separate WasmRuntimes that don't share process state.
 static GLOBAL_WASM_RUNTIME: spin::Once<WasmRuntime> = spin::Once::new();
 
+/// Get or initialize the global WASM runtime
+pub(crate) fn global_wasm_runtime() -> &'static WasmRuntime {
+    GLOBAL_WASM_RUNTIME.call_once(WasmRuntime::new)
+}
+
 /// Maximum pending storage requests
 const MAX_PENDING_STORAGE_REQUESTS: usize = 1000;
 
-/// Storage request state
+/// Maximum in-flight network fetches
+const MAX_PENDING_NETWORK_REQUESTS: usize = 64;
+
+/// Largest serialized response that fits in one IPC message once wrapped
+/// for delivery through Init (kernel MAX_MESSAGE_SIZE minus both headers)
+const MAX_NETWORK_RESULT_DATA: usize = 16384 - 14 - 9;
+
+/// Result types for MSG_STORAGE_RESULT and MSG_KEYSTORE_RESULT (from zos-ipc)
+mod storage_result {
+    pub const READ_OK: u8 = 0;
+    pub const WRITE_OK: u8 = 1;
+    pub const NOT_FOUND: u8 = 2;
+    pub const ERROR: u8 = 3;
+    pub const LIST_OK: u8 = 4;
+    pub const EXISTS_OK: u8 = 5;
+}
+
+/// Network result types for MSG_NET_RESULT (from zos-ipc)
+mod net_result {
+    pub const NET_OK: u8 = 0;
+    pub const NET_ERROR: u8 = 1;
+}
+
+/// Storage and keystore request state
+///
+/// The result waits here until the kernel loop delivers it to the
+/// requesting process.
 #[derive(Clone, Debug)]
-#[allow(dead_code)] // Fields are stored for future retrieval but read operations not yet implemented
 enum StorageRequestState {
-    /// Read completed with result
+    /// Submitted to the block device, not finished yet
+    Pending,
+    /// Read completed (`None` if the key does not exist)
     ReadComplete(Option<Vec<u8>>),
     /// Write/delete completed
-    WriteComplete(bool),
-    /// List completed with keys
+    WriteComplete,
+    /// List completed with key names
     ListComplete(Vec<String>),
     /// Exists check completed
     ExistsComplete(bool),
+    /// Operation failed
+    Failed,
+}
+
+impl StorageRequestState {
+    /// State for a finished block storage operation
+    fn from_outcome(outcome: storage::Outcome) -> Self {
+        match outcome {
+            storage::Outcome::Read(value) => StorageRequestState::ReadComplete(value),
+            storage::Outcome::Committed => StorageRequestState::WriteComplete,
+            storage::Outcome::Failed => StorageRequestState::Failed,
+        }
+    }
+
+    /// Build the MSG_STORAGE_RESULT / MSG_KEYSTORE_RESULT payload for this result
+    ///
+    /// Format: [request_id: u32, result_type: u8, data_len: u32, data: [u8]]
+    fn to_payload(&self, request_id: StorageRequestId, error: &str) -> Vec<u8> {
+        let (result_type, data) = match self {
+            StorageRequestState::ReadComplete(Some(value)) => (storage_result::READ_OK, value.clone()),
+            StorageRequestState::ReadComplete(None) => (storage_result::NOT_FOUND, Vec::new()),
+            StorageRequestState::WriteComplete => (storage_result::WRITE_OK, Vec::new()),
+            StorageRequestState::ListComplete(keys) => (storage_result::LIST_OK, keys_to_json(keys).into_bytes()),
+            StorageRequestState::ExistsComplete(exists) => (storage_result::EXISTS_OK, alloc::vec![*exists as u8]),
+            StorageRequestState::Pending | StorageRequestState::Failed => {
+                (storage_result::ERROR, error.as_bytes().to_vec())
+            }
+        };
+        let mut payload = Vec::with_capacity(9 + data.len());
+        payload.extend_from_slice(&request_id.to_le_bytes());
+        payload.push(result_type);
+        payload.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        payload.extend_from_slice(&data);
+        payload
+    }
+}
+
+/// Encode key names as a JSON array of strings
+fn keys_to_json(keys: &[String]) -> String {
+    let mut json = String::from("[");
+    for (i, key) in keys.iter().enumerate() {
+        if i > 0 {
+            json.push(',');
+        }
+        json.push('"');
+        for c in key.chars() {
+            match c {
+                '"' => json.push_str("\\\""),
+                '\\' => json.push_str("\\\\"),
+                c if (c as u32) < 0x20 => json.push_str(&alloc::format!("\\u{:04x}", c as u32)),
+                c => json.push(c),
+            }
+        }
+        json.push('"');
+    }
+    json.push(']');
+    json
 }
 
 /// x86_64 Hardware Abstraction Layer implementation
@@ -107,6 +210,7 @@ enum StorageRequestState {
 /// - Entropy via RDRAND (currently stubbed)
 /// - VMM for memory management
 /// - VirtIO block storage
+/// - VirtIO network with TCP/IP stack for HTTP fetch
 /// - WASM runtime for executing service binaries
 pub struct X86_64Hal {
     /// Monotonic time counter (nanoseconds since boot)
@@ -117,10 +221,18 @@ pub struct X86_64Hal {
     messages: Mutex<Vec<(NumericProcessHandle, Vec<u8>)>>,
     /// Next storage request ID
     next_storage_request_id: AtomicU32,
-    /// Pending storage requests: request_id -> (pid, result)
+    /// Storage requests awaiting delivery: request_id -> (pid, result)
     storage_requests: Mutex<BTreeMap<StorageRequestId, (u64, StorageRequestState)>>,
+    /// In-flight block storage operations: op -> request_id
+    storage_ops: Mutex<BTreeMap<storage::OpId, StorageRequestId>>,
     /// Storage initialized flag
     storage_initialized: Mutex<bool>,
+    /// Completed keystore requests awaiting delivery: request_id -> (pid, result)
+    keystore_requests: Mutex<BTreeMap<StorageRequestId, (u64, StorageRequestState)>>,
+    /// Next network request ID
+    next_network_request_id: AtomicU32,
+    /// In-flight network fetches: request_id -> pid
+    network_requests: Mutex<BTreeMap<NetworkRequestId, u64>>,
     /// Pending IPC messages for processes: pid -> Vec<message_bytes>
     pending_ipc: Mutex<BTreeMap<u64, Vec<Vec<u8>>>>,
 }
@@ -134,7 +246,11 @@ impl X86_64Hal {
             messages: Mutex::new(Vec::new()),
             next_storage_request_id: AtomicU32::new(1),
             storage_requests: Mutex::new(BTreeMap::new()),
+            storage_ops: Mutex::new(BTreeMap::new()),
             storage_initialized: Mutex::new(false),
+            keystore_requests: Mutex::new(BTreeMap::new()),
+            next_network_request_id: AtomicU32::new(1),
+            network_requests: Mutex::new(BTreeMap::new()),
             pending_ipc: Mutex::new(BTreeMap::new()),
         }
     }
@@ -145,7 +261,7 @@ impl X86_64Hal {
     /// This ensures that processes spawned via any HAL instance are visible
     /// to the scheduler running on any HAL instance.
     pub fn wasm_runtime(&self) -> &'static WasmRuntime {
-        GLOBAL_WASM_RUNTIME.call_once(|| WasmRuntime::new())
+        global_wasm_runtime()
     }
     
     /// Allocate a new storage request ID
@@ -153,6 +269,182 @@ impl X86_64Hal {
         self.next_storage_request_id.fetch_add(1, Ordering::SeqCst)
     }
     
+    /// Record a storage request
+    ///
+    /// `submit` starts the block storage operation; if it can't be started,
+    /// the request fails and the error is delivered like any other result.
+    fn start_storage_request(
+        &self,
+        pid: u64,
+        submit: impl FnOnce() -> Result<storage::OpId, virtio::VirtioError>,
+    ) -> Result<StorageRequestId, HalError> {
+        let mut requests = self.storage_requests.lock();
+        if requests.len() >= MAX_PENDING_STORAGE_REQUESTS {
+            return Err(HalError::ResourceExhausted);
+        }
+
+        let request_id = self.alloc_storage_request_id();
+        let state = match submit() {
+            Ok(op) => {
+                self.storage_ops.lock().insert(op, request_id);
+                StorageRequestState::Pending
+            }
+            Err(_) => StorageRequestState::Failed,
+        };
+        requests.insert(request_id, (pid, state));
+        Ok(request_id)
+    }
+
+    /// Record a storage request answered from the in-memory index
+    fn complete_storage_request(
+        &self,
+        pid: u64,
+        state: StorageRequestState,
+    ) -> Result<StorageRequestId, HalError> {
+        let mut requests = self.storage_requests.lock();
+        if requests.len() >= MAX_PENDING_STORAGE_REQUESTS {
+            return Err(HalError::ResourceExhausted);
+        }
+
+        let request_id = self.alloc_storage_request_id();
+        requests.insert(request_id, (pid, state));
+        Ok(request_id)
+    }
+
+    /// Poll block storage and take all finished storage requests
+    ///
+    /// Returns `(pid, payload)` pairs, where payload is the MSG_STORAGE_RESULT
+    /// body to deliver to that process.
+    pub fn take_storage_results(&self) -> Vec<(u64, Vec<u8>)> {
+        let finished = storage::poll();
+        let mut requests = self.storage_requests.lock();
+        {
+            let mut ops = self.storage_ops.lock();
+            for (op, outcome) in finished {
+                let Some(request_id) = ops.remove(&op) else {
+                    continue;
+                };
+                if let Some((_, state)) = requests.get_mut(&request_id) {
+                    *state = StorageRequestState::from_outcome(outcome);
+                }
+            }
+        }
+
+        let done: Vec<StorageRequestId> = requests
+            .iter()
+            .filter(|(_, (_, state))| !matches!(state, StorageRequestState::Pending))
+            .map(|(request_id, _)| *request_id)
+            .collect();
+        done.into_iter()
+            .filter_map(|request_id| {
+                let (pid, state) = requests.remove(&request_id)?;
+                Some((pid, state.to_payload(request_id, "storage error")))
+            })
+            .collect()
+    }
+
+    /// Record a completed keystore request
+    fn complete_keystore_request(
+        &self,
+        pid: u64,
+        state: StorageRequestState,
+    ) -> Result<StorageRequestId, HalError> {
+        if !keystore::is_initialized() {
+            return Err(HalError::NotSupported);
+        }
+        let mut requests = self.keystore_requests.lock();
+        if requests.len() >= MAX_PENDING_STORAGE_REQUESTS {
+            return Err(HalError::ResourceExhausted);
+        }
+        // Keystore requests share the storage request ID space
+        let request_id = self.alloc_storage_request_id();
+        requests.insert(request_id, (pid, state));
+        Ok(request_id)
+    }
+
+    /// Mount the keystore disk
+    ///
+    /// Requires the second virtio-blk device and the master key supplied via
+    /// fw_cfg. Returns `Ok(true)` if a new keystore was created.
+    pub fn keystore_init(&self) -> Result<bool, HalError> {
+        if !virtio::blk_pci::is_initialized(virtio::blk_pci::Disk::Keystore) {
+            return Err(HalError::NotSupported);
+        }
+        match keystore::init() {
+            Ok(is_new) => Ok(is_new),
+            Err(keystore::KeystoreError::NoMasterKey) => Err(HalError::NotSupported),
+            Err(e) => {
+                crate::serial_println!("[keystore] Mount failed: {:?}", e);
+                Err(HalError::StorageError)
+            }
+        }
+    }
+
+    /// Take all completed keystore results
+    ///
+    /// Returns `(pid, payload)` pairs, where payload is the MSG_KEYSTORE_RESULT
+    /// body to deliver to that process.
+    pub fn take_keystore_results(&self) -> Vec<(u64, Vec<u8>)> {
+        let requests = core::mem::take(&mut *self.keystore_requests.lock());
+        requests
+            .into_iter()
+            .map(|(request_id, (pid, state))| (pid, state.to_payload(request_id, "keystore error")))
+            .collect()
+    }
+
+    /// Bring up the TCP/IP stack on the virtio-net device
+    ///
+    /// DHCP completes in the background while results are polled.
+    pub fn network_init(&self) -> Result<(), HalError> {
+        if !virtio::net_pci::is_initialized() || !net::init() {
+            return Err(HalError::NotSupported);
+        }
+        Ok(())
+    }
+
+    /// Poll the network stack and take all finished fetches
+    ///
+    /// Returns `(pid, payload)` pairs, where payload is the MSG_NET_RESULT
+    /// body to deliver to that process.
+    ///
+    /// Format: [request_id: u32, result_type: u8, data_len: u32, data: [u8]]
+    /// with data being the JSON-serialized `HttpResponse`.
+    pub fn take_network_results(&self) -> Vec<(u64, Vec<u8>)> {
+        let finished = net::poll();
+        let mut requests = self.network_requests.lock();
+        finished
+            .into_iter()
+            .filter_map(|(request_id, response)| {
+                let pid = requests.remove(&request_id)?;
+                let too_large = || {
+                    let error = zos_network::NetworkError::Other(String::from("Response too large"));
+                    serde_json::to_vec(&zos_network::HttpResponse::err(error))
+                };
+                let json = match serde_json::to_vec(&response) {
+                    Ok(json) if json.len() > MAX_NETWORK_RESULT_DATA => too_large(),
+                    other => other,
+                };
+                let (result_type, data) = match json {
+                    Ok(json) => (net_result::NET_OK, json),
+                    Err(_) => (net_result::NET_ERROR, Vec::new()),
+                };
+                let mut payload = Vec::with_capacity(9 + data.len());
+                payload.extend_from_slice(&request_id.to_le_bytes());
+                payload.push(result_type);
+                payload.extend_from_slice(&(data.len() as u32).to_le_bytes());
+                payload.extend_from_slice(&data);
+                Some((pid, payload))
+            })
+            .collect()
+    }
+
+    /// Take the driver IRQ lines that fired since the last call
+    ///
+    /// Each line stays masked until its driver acknowledges the interrupt.
+    pub fn take_irqs(&self) -> Vec<u8> {
+        driver_irq::take_pending()
+    }
+
     /// Allocate a new process ID
     fn alloc_pid(&self) -> u64 {
         self.next_pid.fetch_add(1, Ordering::Relaxed)
@@ -173,10 +465,12 @@ impl X86_64Hal {
     ///
     /// This variant processes syscalls synchronously as they are made,
     /// ensuring the process doesn't continue until the syscall is complete.
-    /// Handler returns (i64, Vec<u8>) to support 64-bit packed return values.
+    /// Handler returns (i64, Vec<u8>) to support 64-bit packed return values,
+    /// or None if the syscall parked the process; `complete_syscall` then
+    /// resumes it once the kernel has the result.
     pub fn run_scheduler_with_handler<F>(&self, mut handler: F)
     where
-        F: FnMut(PendingSyscall) -> (i64, Vec<u8>),
+        F: FnMut(PendingSyscall) -> Option<(i64, Vec<u8>)>,
     {
         self.wasm_runtime().run_all_processes_with_handler(&mut handler)
     }
@@ -193,12 +487,19 @@ impl X86_64Hal {
     /// - GDT with TSS
     /// - IDT with exception handlers
     /// - VMM (page tables, frame allocator)
+    /// - ACPI tables, if the bootloader found an RSDP
     /// - APIC timer for preemptive scheduling
     ///
     /// # Safety
     /// Must be called only once during kernel initialization.
     /// Must be called after the bootloader has set up initial paging.
-    pub unsafe fn init(&self, physical_memory_offset: u64, memory_regions: &[vmm::MemoryRegionDescriptor]) {
+    /// `rsdp_addr` must be the physical address of a valid ACPI RSDP.
+    pub unsafe fn init(
+        &self,
+        physical_memory_offset: u64,
+        memory_regions: &[vmm::MemoryRegionDescriptor],
+        rsdp_addr: Option<u64>,
+    ) {
         // Initialize serial first for debug output
         serial::init();
 
@@ -211,6 +512,12 @@ impl X86_64Hal {
         // Initialize VMM with physical memory info
         vmm::init(physical_memory_offset, memory_regions);
 
+        // Read the ACPI tables before the APIC, which takes its register
+        // bases and IRQ routing from them
+        if let Some(rsdp_addr) = rsdp_addr {
+            acpi::init(rsdp_addr);
+        }
+
         // Initialize APIC (timer will start after interrupts are enabled)
         apic::init();
 
@@ -251,6 +558,47 @@ impl X86_64Hal {
         }
     }
 
+    /// Start the application processors
+    ///
+    /// Brings up every CPU listed in the ACPI MADT and spreads WASM
+    /// processes spawned from now on across them. Returns the number of
+    /// CPUs online, including the boot processor (1 without ACPI).
+    ///
+    /// # Safety
+    /// Must be called once, after `init()`.
+    pub unsafe fn start_aps(&self) -> usize {
+        let cpus = smp::start_aps();
+        self.wasm_runtime().set_cpu_count(cpus);
+        cpus
+    }
+
+    /// Flush storage and power the machine off
+    ///
+    /// Callers stop processes and write their last state first; this only
+    /// waits for storage operations still in flight.
+    pub fn shutdown(&self) -> ! {
+        self.sync_storage();
+        power_off()
+    }
+
+    /// Flush storage and reset the machine
+    ///
+    /// Same contract as [`shutdown`](Self::shutdown).
+    pub fn reboot(&self) -> ! {
+        self.sync_storage();
+        reboot()
+    }
+
+    /// Wait for every in-flight storage operation to reach the disk
+    fn sync_storage(&self) {
+        if !*self.storage_initialized.lock() {
+            return;
+        }
+        if let Err(e) = storage::sync() {
+            serial::write_str(&alloc::format!("[storage] Sync before power-off failed: {:?}\n", e));
+        }
+    }
+
     /// Update the monotonic time counter
     ///
     /// Called by timer interrupt handler to advance time.
@@ -310,6 +658,22 @@ impl HAL for X86_64Hal {
         self.wasm_runtime().spawn(pid, name, binary)
     }
 
+    fn spawn_process_with_priority(
+        &self,
+        pid: u64,
+        name: &str,
+        binary: &[u8],
+        priority: Priority,
+    ) -> Result<Self::ProcessHandle, HalError> {
+        serial::write_str(&alloc::format!(
+            "[x86_64-hal] spawn_process_with_priority: name='{}', pid={}, {:?}\n",
+            name, pid, priority
+        ));
+        
+        let sched = wasm::SchedContext::for_priority(priority);
+        self.wasm_runtime().spawn_with_sched(pid, name, binary, priority, sched)
+    }
+
     fn kill_process(&self, handle: &Self::ProcessHandle) -> Result<(), HalError> {
         serial::write_str(&alloc::format!(
             "[x86_64-hal] kill_process: pid={}\n", handle.id()
@@ -425,81 +789,35 @@ impl HAL for X86_64Hal {
     }
 
     // === Async Storage Operations ===
-    // On x86_64, storage operations are synchronous internally but use the
-    // async API pattern for consistency with other platforms.
+    // Reads and writes are queued on the virtio-blk device and return
+    // immediately; list/exists are answered from the in-memory index. All
+    // results are delivered by the kernel loop via take_storage_results().
 
     fn storage_read_async(&self, pid: u64, key: &str) -> Result<StorageRequestId, HalError> {
-        let mut requests = self.storage_requests.lock();
-        if requests.len() >= MAX_PENDING_STORAGE_REQUESTS {
-            return Err(HalError::ResourceExhausted);
-        }
-
-        let request_id = self.alloc_storage_request_id();
-        
-        // Perform synchronous read
-        let result = match storage::read(key) {
-            Ok(data) => StorageRequestState::ReadComplete(data),
-            Err(_) => StorageRequestState::ReadComplete(None),
-        };
-        
-        requests.insert(request_id, (pid, result));
-        Ok(request_id)
+        self.start_storage_request(pid, || storage::submit_read(key))
     }
 
     fn storage_write_async(&self, pid: u64, key: &str, value: &[u8]) -> Result<StorageRequestId, HalError> {
-        let mut requests = self.storage_requests.lock();
-        if requests.len() >= MAX_PENDING_STORAGE_REQUESTS {
-            return Err(HalError::ResourceExhausted);
-        }
-
-        let request_id = self.alloc_storage_request_id();
-        
-        // Perform synchronous write
-        let success = storage::write(key, value).is_ok();
-        requests.insert(request_id, (pid, StorageRequestState::WriteComplete(success)));
-        Ok(request_id)
+        self.start_storage_request(pid, || {
+            storage::submit_mutations(alloc::vec![storage::Mutation::Put(String::from(key), value.to_vec())])
+        })
     }
 
     fn storage_delete_async(&self, pid: u64, key: &str) -> Result<StorageRequestId, HalError> {
-        let mut requests = self.storage_requests.lock();
-        if requests.len() >= MAX_PENDING_STORAGE_REQUESTS {
-            return Err(HalError::ResourceExhausted);
-        }
-
-        let request_id = self.alloc_storage_request_id();
-        
-        // Perform synchronous delete
-        let success = storage::delete(key).unwrap_or(false);
-        requests.insert(request_id, (pid, StorageRequestState::WriteComplete(success)));
-        Ok(request_id)
+        // Deleting a missing key succeeds
+        self.start_storage_request(pid, || {
+            storage::submit_mutations(alloc::vec![storage::Mutation::Delete(String::from(key))])
+        })
     }
 
     fn storage_list_async(&self, pid: u64, prefix: &str) -> Result<StorageRequestId, HalError> {
-        let mut requests = self.storage_requests.lock();
-        if requests.len() >= MAX_PENDING_STORAGE_REQUESTS {
-            return Err(HalError::ResourceExhausted);
-        }
-
-        let request_id = self.alloc_storage_request_id();
-        
-        // Perform synchronous list
         let keys = storage::list(prefix);
-        requests.insert(request_id, (pid, StorageRequestState::ListComplete(keys)));
-        Ok(request_id)
+        self.complete_storage_request(pid, StorageRequestState::ListComplete(keys))
     }
 
     fn storage_exists_async(&self, pid: u64, key: &str) -> Result<StorageRequestId, HalError> {
-        let mut requests = self.storage_requests.lock();
-        if requests.len() >= MAX_PENDING_STORAGE_REQUESTS {
-            return Err(HalError::ResourceExhausted);
-        }
-
-        let request_id = self.alloc_storage_request_id();
-        
-        // Perform synchronous exists check
         let exists = storage::exists(key).unwrap_or(false);
-        requests.insert(request_id, (pid, StorageRequestState::ExistsComplete(exists)));
-        Ok(request_id)
+        self.complete_storage_request(pid, StorageRequestState::ExistsComplete(exists))
     }
 
     fn storage_batch_write_async(
@@ -507,32 +825,111 @@ impl HAL for X86_64Hal {
         pid: u64,
         items: &[(&str, &[u8])],
     ) -> Result<StorageRequestId, HalError> {
-        let mut requests = self.storage_requests.lock();
-        if requests.len() >= MAX_PENDING_STORAGE_REQUESTS {
-            return Err(HalError::ResourceExhausted);
-        }
+        // The whole batch shares one group commit
+        self.start_storage_request(pid, || {
+            let mutations = items
+                .iter()
+                .map(|(key, value)| storage::Mutation::Put(String::from(*key), value.to_vec()))
+                .collect();
+            storage::submit_mutations(mutations)
+        })
+    }
 
-        let request_id = self.alloc_storage_request_id();
+    fn get_storage_request_pid(&self, request_id: StorageRequestId) -> Option<u64> {
+        self.storage_requests.lock().get(&request_id).map(|(pid, _)| *pid)
+    }
 
-        // Perform synchronous batch write (x86_64 doesn't have true batch, just iterate)
-        let mut success = true;
-        for (key, value) in items {
-            if storage::write(key, value).is_err() {
-                success = false;
-                break;
-            }
+    fn take_storage_request_pid(&self, request_id: StorageRequestId) -> Option<u64> {
+        self.storage_requests.lock().remove(&request_id).map(|(pid, _)| pid)
+    }
+
+    // === Async Keystore (KeystoreService Only) ===
+    // Backed by the encrypted keystore disk. Operations complete synchronously;
+    // results are delivered by the kernel loop via take_keystore_results().
+
+    fn keystore_read_async(&self, pid: u64, key: &str) -> Result<StorageRequestId, HalError> {
+        let state = match keystore::read(key) {
+            Ok(value) => StorageRequestState::ReadComplete(value),
+            Err(_) => StorageRequestState::Failed,
+        };
+        self.complete_keystore_request(pid, state)
+    }
+
+    fn keystore_write_async(
+        &self,
+        pid: u64,
+        key: &str,
+        value: &[u8],
+    ) -> Result<StorageRequestId, HalError> {
+        let state = match keystore::write(key, value) {
+            Ok(()) => StorageRequestState::WriteComplete,
+            Err(_) => StorageRequestState::Failed,
+        };
+        self.complete_keystore_request(pid, state)
+    }
+
+    fn keystore_delete_async(&self, pid: u64, key: &str) -> Result<StorageRequestId, HalError> {
+        // Deleting a missing key succeeds, matching the browser keystore
+        let state = match keystore::delete(key) {
+            Ok(_) => StorageRequestState::WriteComplete,
+            Err(_) => StorageRequestState::Failed,
+        };
+        self.complete_keystore_request(pid, state)
+    }
+
+    fn keystore_list_async(&self, pid: u64, prefix: &str) -> Result<StorageRequestId, HalError> {
+        let state = match keystore::list(prefix) {
+            Ok(keys) => StorageRequestState::ListComplete(keys),
+            Err(_) => StorageRequestState::Failed,
+        };
+        self.complete_keystore_request(pid, state)
+    }
+
+    fn keystore_exists_async(&self, pid: u64, key: &str) -> Result<StorageRequestId, HalError> {
+        let state = match keystore::exists(key) {
+            Ok(exists) => StorageRequestState::ExistsComplete(exists),
+            Err(_) => StorageRequestState::Failed,
+        };
+        self.complete_keystore_request(pid, state)
+    }
+
+    fn get_keystore_request_pid(&self, request_id: StorageRequestId) -> Option<u64> {
+        self.keystore_requests.lock().get(&request_id).map(|(pid, _)| *pid)
+    }
+
+    fn take_keystore_request_pid(&self, request_id: StorageRequestId) -> Option<u64> {
+        self.keystore_requests.lock().remove(&request_id).map(|(pid, _)| pid)
+    }
+
+    // === Async Network Operations ===
+    // Fetches run on the embedded TCP/IP stack; results are delivered by the
+    // kernel loop via take_network_results().
+
+    fn network_fetch_async(&self, pid: u64, request: &[u8]) -> Result<NetworkRequestId, HalError> {
+        let request: zos_network::HttpRequest =
+            serde_json::from_slice(request).map_err(|_| HalError::InvalidArgument)?;
+        if !net::is_initialized() {
+            return Err(HalError::NotSupported);
         }
 
-        requests.insert(request_id, (pid, StorageRequestState::WriteComplete(success)));
+        let mut requests = self.network_requests.lock();
+        if requests.len() >= MAX_PENDING_NETWORK_REQUESTS {
+            return Err(HalError::ResourceExhausted);
+        }
+        let request_id = self.next_network_request_id.fetch_add(1, Ordering::SeqCst);
+        requests.insert(request_id, pid);
+        drop(requests);
+
+        net::start_fetch(request_id, request);
         Ok(request_id)
     }
 
-    fn get_storage_request_pid(&self, request_id: StorageRequestId) -> Option<u64> {
-        self.storage_requests.lock().get(&request_id).map(|(pid, _)| *pid)
+    fn get_network_request_pid(&self, request_id: NetworkRequestId) -> Option<u64> {
+        self.network_requests.lock().get(&request_id).copied()
     }
 
-    fn take_storage_request_pid(&self, request_id: StorageRequestId) -> Option<u64> {
-        self.storage_requests.lock().remove(&request_id).map(|(pid, _)| pid)
+    fn take_network_request_pid(&self, request_id: NetworkRequestId) -> Option<u64> {
+        self.network_requests.lock().remove(&request_id)
     }
 
     // === Bootstrap Storage (Supervisor Only) ===
@@ -612,6 +1009,96 @@ impl HAL for X86_64Hal {
             }
         }
     }
+
+    // === Power Control ===
+
+    fn supports_power_control(&self) -> bool {
+        true
+    }
+
+    // === Device Access ===
+
+    fn io_port_read(&self, port: u16, width: u8) -> Result<u32, HalError> {
+        use x86_64::instructions::port::Port;
+
+        // SAFETY: the kernel checked the caller's I/O port capability
+        unsafe {
+            match width {
+                1 => Ok(Port::<u8>::new(port).read() as u32),
+                2 => Ok(Port::<u16>::new(port).read() as u32),
+                4 => Ok(Port::<u32>::new(port).read()),
+                _ => Err(HalError::InvalidArgument),
+            }
+        }
+    }
+
+    fn io_port_write(&self, port: u16, width: u8, value: u32) -> Result<(), HalError> {
+        use x86_64::instructions::port::Port;
+
+        // SAFETY: the kernel checked the caller's I/O port capability
+        unsafe {
+            match width {
+                1 => Port::<u8>::new(port).write(value as u8),
+                2 => Port::<u16>::new(port).write(value as u16),
+                4 => Port::<u32>::new(port).write(value),
+                _ => return Err(HalError::InvalidArgument),
+            }
+        }
+        Ok(())
+    }
+
+    fn irq_enable(&self, irq: u8) -> Result<(), HalError> {
+        driver_irq::enable(irq)
+    }
+
+    fn irq_disable(&self, irq: u8) {
+        driver_irq::disable(irq)
+    }
+
+    // === Shared Memory Regions ===
+
+    fn region_alloc(&self, id: u64, size: usize) -> Result<(), HalError> {
+        shared_region::alloc(id, size)
+    }
+
+    fn region_free(&self, id: u64) {
+        shared_region::free(id)
+    }
+
+    fn region_copy_to_process(
+        &self,
+        id: u64,
+        offset: usize,
+        pid: u64,
+        addr: u32,
+        len: usize,
+    ) -> Result<(), HalError> {
+        global_wasm_runtime().with_memory(pid, |memory| {
+            shared_region::read(id, offset, window(memory, addr, len)?)
+        })?
+    }
+
+    fn region_copy_from_process(
+        &self,
+        id: u64,
+        offset: usize,
+        pid: u64,
+        addr: u32,
+        len: usize,
+    ) -> Result<(), HalError> {
+        global_wasm_runtime().with_memory(pid, |memory| {
+            shared_region::write(id, offset, window(memory, addr, len)?)
+        })?
+    }
+}
+
+/// The `len` bytes at `addr` in a process's linear memory
+fn window(memory: &mut [u8], addr: u32, len: usize) -> Result<&mut [u8], HalError> {
+    let start = addr as usize;
+    start
+        .checked_add(len)
+        .and_then(|end| memory.get_mut(start..end))
+        .ok_or(HalError::InvalidArgument)
 }
 
 /// Check if RDRAND instruction is supported
@@ -682,6 +1169,57 @@ pub fn halt_loop() -> ! {
     }
 }
 
+/// Power the machine off
+///
+/// Enters ACPI S5 when the firmware describes it. Falls back to QEMU's
+/// debug-exit device, then halts.
+pub fn power_off() -> ! {
+    x86_64::instructions::interrupts::disable();
+    // SAFETY: interrupts are off; callers flushed what they need
+    unsafe { acpi::power_off() };
+    serial::write_str("[acpi] S5 power-off unavailable, trying QEMU debug exit\n");
+    exit_qemu(0)
+}
+
+/// Reset the machine
+///
+/// Tries the ACPI reset register, then the 8042 keyboard controller's
+/// reset line, then forces a triple fault.
+pub fn reboot() -> ! {
+    use x86_64::instructions::port::Port;
+
+    x86_64::instructions::interrupts::disable();
+    // SAFETY: interrupts are off; callers flushed what they need
+    unsafe { acpi::reset() };
+
+    serial::write_str("[acpi] Reset register unavailable, pulsing 8042 reset line\n");
+    unsafe {
+        let mut status: Port<u8> = Port::new(0x64);
+        // Wait for the controller's input buffer to drain (bit 1)
+        for _ in 0..100_000 {
+            if status.read() & 0x02 == 0 {
+                break;
+            }
+            core::hint::spin_loop();
+        }
+        status.write(0xFE);
+    }
+    for _ in 0..10_000_000 {
+        core::hint::spin_loop();
+    }
+
+    // An empty IDT turns the next exception into a triple fault
+    unsafe {
+        let empty = x86_64::structures::DescriptorTablePointer {
+            limit: 0,
+            base: x86_64::VirtAddr::zero(),
+        };
+        x86_64::instructions::tables::lidt(&empty);
+        x86_64::instructions::interrupts::int3();
+    }
+    halt_loop()
+}
+
 /// Exit QEMU with a success code
 ///
 /// This uses the QEMU debug exit device (isa-debug-exit).
@@ -0,0 +1,294 @@
+//! Minimal HTTP/1.1 client protocol
+//!
+//! Builds request bytes from a `zos_network::HttpRequest` and parses the
+//! response stream back into an `HttpSuccess`. Transport is plain TCP; the
+//! connection is opened per request and closed by `Connection: close`.
+//!
+//! Response bodies are framed by `Content-Length`, chunked
+//! `Transfer-Encoding`, or end of stream, in that order of preference.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use zos_network::{HttpMethod, HttpRequest, HttpSuccess, NetworkError};
+
+/// Default port for `http://` URLs
+const HTTP_PORT: u16 = 80;
+
+/// Largest response accepted (headers and body)
+pub const MAX_RESPONSE_SIZE: usize = 4 * 1024 * 1024;
+
+/// Target of an HTTP request
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Url {
+    /// Host name or IPv4 literal
+    pub host: String,
+    /// TCP port
+    pub port: u16,
+    /// Path and query, always starting with '/'
+    pub path: String,
+}
+
+/// Parse an `http://host[:port][/path]` URL
+///
+/// `https://` URLs are rejected with `TlsError` since there is no TLS stack.
+pub fn parse_url(url: &str) -> Result<Url, NetworkError> {
+    let rest = if let Some(rest) = url.strip_prefix("http://") {
+        rest
+    } else if url.starts_with("https://") {
+        return Err(NetworkError::TlsError);
+    } else {
+        return Err(NetworkError::InvalidUrl);
+    };
+
+    let (authority, path) = match rest.find(['/', '?']) {
+        Some(i) if rest.as_bytes()[i] == b'/' => (&rest[..i], rest[i..].to_string()),
+        Some(i) => (&rest[..i], format!("/{}", &rest[i..])),
+        None => (rest, String::from("/")),
+    };
+    // Fragments are never sent to the server
+    let path = match path.find('#') {
+        Some(i) => path[..i].to_string(),
+        None => path,
+    };
+
+    let (host, port) = match authority.rsplit_once(':') {
+        Some((host, port)) => (host, port.parse().map_err(|_| NetworkError::InvalidUrl)?),
+        None => (authority, HTTP_PORT),
+    };
+    if host.is_empty() || host.contains('@') {
+        return Err(NetworkError::InvalidUrl);
+    }
+
+    Ok(Url {
+        host: host.to_string(),
+        port,
+        path,
+    })
+}
+
+/// Serialize a request for the wire
+pub fn build_request(request: &HttpRequest, url: &Url) -> Vec<u8> {
+    let mut head = format!("{} {} HTTP/1.1\r\n", request.method.as_str(), url.path);
+    if url.port == HTTP_PORT {
+        head.push_str(&format!("Host: {}\r\n", url.host));
+    } else {
+        head.push_str(&format!("Host: {}:{}\r\n", url.host, url.port));
+    }
+    head.push_str("Connection: close\r\n");
+
+    let mut has_user_agent = false;
+    for (name, value) in &request.headers {
+        // Framing headers are owned by the client
+        if name.eq_ignore_ascii_case("host")
+            || name.eq_ignore_ascii_case("connection")
+            || name.eq_ignore_ascii_case("content-length")
+            || name.eq_ignore_ascii_case("transfer-encoding")
+        {
+            continue;
+        }
+        has_user_agent |= name.eq_ignore_ascii_case("user-agent");
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    if !has_user_agent {
+        head.push_str("User-Agent: zero-os\r\n");
+    }
+
+    let body = request.body.as_deref().unwrap_or(&[]);
+    if !body.is_empty() || matches!(request.method, HttpMethod::Post | HttpMethod::Put | HttpMethod::Patch) {
+        head.push_str(&format!("Content-Length: {}\r\n", body.len()));
+    }
+    head.push_str("\r\n");
+
+    let mut bytes = head.into_bytes();
+    bytes.extend_from_slice(body);
+    bytes
+}
+
+/// Find the end of the header block (index just past the blank line)
+fn header_end(buf: &[u8]) -> Option<usize> {
+    buf.windows(4).position(|w| w == b"\r\n\r\n").map(|i| i + 4)
+}
+
+/// Decode a chunked body
+///
+/// Returns `Ok(None)` while more data is needed.
+fn decode_chunked(mut data: &[u8]) -> Result<Option<Vec<u8>>, NetworkError> {
+    let mut body = Vec::new();
+    loop {
+        let line_end = match data.windows(2).position(|w| w == b"\r\n") {
+            Some(i) => i,
+            None => return Ok(None),
+        };
+        let line = core::str::from_utf8(&data[..line_end]).map_err(|_| malformed())?;
+        // Chunk extensions follow a ';'
+        let size_str = line.split(';').next().unwrap_or("").trim();
+        let size = usize::from_str_radix(size_str, 16).map_err(|_| malformed())?;
+        data = &data[line_end + 2..];
+
+        if size == 0 {
+            // Trailers end with a blank line
+            if data.starts_with(b"\r\n") || header_end(data).is_some() {
+                return Ok(Some(body));
+            }
+            return Ok(None);
+        }
+        if data.len() < size + 2 {
+            return Ok(None);
+        }
+        body.extend_from_slice(&data[..size]);
+        if &data[size..size + 2] != b"\r\n" {
+            return Err(malformed());
+        }
+        data = &data[size + 2..];
+    }
+}
+
+fn malformed() -> NetworkError {
+    NetworkError::Other(String::from("Malformed HTTP response"))
+}
+
+/// Parse a response received so far
+///
+/// `eof` is true once the server has closed the connection. Returns
+/// `Ok(None)` while the response is incomplete.
+pub fn parse_response(
+    buf: &[u8],
+    eof: bool,
+    method: HttpMethod,
+) -> Result<Option<HttpSuccess>, NetworkError> {
+    let head_len = match header_end(buf) {
+        Some(len) => len,
+        None if eof => return Err(malformed()),
+        None => return Ok(None),
+    };
+    let head = core::str::from_utf8(&buf[..head_len]).map_err(|_| malformed())?;
+    let mut lines = head.split("\r\n");
+
+    // Status line: HTTP/1.x CODE REASON
+    let status_line = lines.next().ok_or_else(malformed)?;
+    let mut parts = status_line.splitn(3, ' ');
+    let version = parts.next().unwrap_or("");
+    if !version.starts_with("HTTP/1.") {
+        return Err(malformed());
+    }
+    let status: u16 = parts
+        .next()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(malformed)?;
+
+    // Header names are lowercased, matching what the browser fetch API reports
+    let mut headers = Vec::new();
+    for line in lines.filter(|l| !l.is_empty()) {
+        let (name, value) = line.split_once(':').ok_or_else(malformed)?;
+        headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+    }
+    let header = |name: &str| {
+        headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    };
+
+    let data = &buf[head_len..];
+    let no_body = method == HttpMethod::Head || status / 100 == 1 || status == 204 || status == 304;
+
+    let body = if no_body {
+        Vec::new()
+    } else if header("transfer-encoding").is_some_and(|te| te.to_ascii_lowercase().contains("chunked")) {
+        match decode_chunked(data)? {
+            Some(body) => body,
+            None if eof => return Err(malformed()),
+            None => return Ok(None),
+        }
+    } else if let Some(len) = header("content-length") {
+        let len: usize = len.parse().map_err(|_| malformed())?;
+        if data.len() < len {
+            return if eof { Err(malformed()) } else { Ok(None) };
+        }
+        data[..len].to_vec()
+    } else if eof {
+        data.to_vec()
+    } else {
+        return Ok(None);
+    };
+
+    Ok(Some(HttpSuccess {
+        status,
+        headers,
+        body,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_url() {
+        let url = parse_url("http://10.0.2.2:8080/v1/identity?x=1#frag").unwrap();
+        assert_eq!(url.host, "10.0.2.2");
+        assert_eq!(url.port, 8080);
+        assert_eq!(url.path, "/v1/identity?x=1");
+
+        let url = parse_url("http://example.com").unwrap();
+        assert_eq!((url.port, url.path.as_str()), (80, "/"));
+        assert_eq!(parse_url("http://example.com?q").unwrap().path, "/?q");
+
+        assert_eq!(parse_url("https://example.com/"), Err(NetworkError::TlsError));
+        assert_eq!(parse_url("ftp://example.com/"), Err(NetworkError::InvalidUrl));
+        assert_eq!(parse_url("http://:80/"), Err(NetworkError::InvalidUrl));
+        assert_eq!(parse_url("http://host:port/"), Err(NetworkError::InvalidUrl));
+    }
+
+    #[test]
+    fn test_build_request() {
+        let request = HttpRequest::post("http://api.local:8080/login")
+            .with_json_body(b"{}".to_vec())
+            .with_header("Host", "ignored");
+        let url = parse_url(&request.url).unwrap();
+        let bytes = build_request(&request, &url);
+        let text = core::str::from_utf8(&bytes).unwrap();
+
+        assert!(text.starts_with("POST /login HTTP/1.1\r\nHost: api.local:8080\r\n"));
+        assert!(text.contains("Content-Type: application/json\r\n"));
+        assert!(text.contains("Content-Length: 2\r\n"));
+        assert!(!text.contains("ignored"));
+        assert!(text.ends_with("\r\n\r\n{}"));
+    }
+
+    #[test]
+    fn test_parse_content_length() {
+        let full = b"HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 5\r\n\r\nhello";
+        assert!(matches!(parse_response(&full[..full.len() - 1], false, HttpMethod::Get), Ok(None)));
+
+        let response = parse_response(full, false, HttpMethod::Get).unwrap().unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, b"hello");
+        assert_eq!(response.headers[0], ("content-type".into(), "text/plain".into()));
+    }
+
+    #[test]
+    fn test_parse_chunked() {
+        let full = b"HTTP/1.1 201 Created\r\nTransfer-Encoding: chunked\r\n\r\n4;ext\r\nzero\r\n3\r\n-os\r\n0\r\n\r\n";
+        assert!(matches!(parse_response(&full[..full.len() - 2], false, HttpMethod::Post), Ok(None)));
+
+        let response = parse_response(full, false, HttpMethod::Post).unwrap().unwrap();
+        assert_eq!(response.status, 201);
+        assert_eq!(response.body, b"zero-os");
+    }
+
+    #[test]
+    fn test_parse_until_eof() {
+        let full = b"HTTP/1.0 404 Not Found\r\n\r\nmissing";
+        assert!(matches!(parse_response(full, false, HttpMethod::Get), Ok(None)));
+
+        let response = parse_response(full, true, HttpMethod::Get).unwrap().unwrap();
+        assert_eq!(response.status, 404);
+        assert_eq!(response.body, b"missing");
+
+        let head = b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\n";
+        assert_eq!(parse_response(head, false, HttpMethod::Head).unwrap().unwrap().body, b"");
+        assert!(parse_response(b"garbage", true, HttpMethod::Get).is_err());
+    }
+}
@@ -0,0 +1,459 @@
+//! TCP/IP Network Stack
+//!
+//! Connects the virtio-net driver to an embedded smoltcp stack and runs HTTP
+//! fetches for `HAL::network_fetch_async` on x86_64.
+//!
+//! # Components
+//!
+//! - **DHCPv4**: configures address, default route and DNS servers (QEMU
+//!   user-mode networking hands out 10.0.2.15, gateway 10.0.2.2, DNS 10.0.2.3)
+//! - **DNS**: resolves host names to IPv4 addresses
+//! - **TCP**: one connection per fetch
+//! - **http**: HTTP/1.1 request/response framing
+//!
+//! # Fetch Lifecycle
+//!
+//! ```text
+//! WaitingForNetwork ──(DHCP bound)──► Resolving ──(A record)──► Tcp ──► done
+//!         │                                                       ▲
+//!         └────────────────(IPv4 literal host)────────────────────┘
+//! ```
+//!
+//! Everything is polled from the kernel main loop via [`poll`]; there are no
+//! interrupts. Finished fetches are returned as `HttpResponse`s.
+
+pub mod http;
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use spin::Mutex;
+
+use smoltcp::iface::{Config, Interface, SocketHandle, SocketSet};
+use smoltcp::phy::{self, DeviceCapabilities, Medium};
+use smoltcp::socket::{dhcpv4, dns, tcp};
+use smoltcp::time::Instant;
+use smoltcp::wire::{DnsQueryType, EthernetAddress, HardwareAddress, IpAddress, IpCidr, Ipv4Address};
+use zos_network::{HttpMethod, HttpRequest, HttpResponse, NetworkError};
+
+use super::virtio::net_pci;
+use crate::NetworkRequestId;
+
+/// Timeout used when a request does not specify one
+const DEFAULT_TIMEOUT_MS: u64 = 30_000;
+
+/// TCP receive buffer per connection
+const TCP_RX_BUFFER_SIZE: usize = 64 * 1024;
+
+/// TCP transmit buffer per connection
+const TCP_TX_BUFFER_SIZE: usize = 16 * 1024;
+
+/// First ephemeral local port
+const EPHEMERAL_PORT_START: u16 = 49152;
+
+/// smoltcp device backed by the global virtio-net driver
+struct NetDevice;
+
+/// A received frame handed to smoltcp
+struct NetRxToken(Vec<u8>);
+
+/// Permission to transmit one frame
+struct NetTxToken;
+
+impl phy::Device for NetDevice {
+    type RxToken<'a> = NetRxToken where Self: 'a;
+    type TxToken<'a> = NetTxToken where Self: 'a;
+
+    fn receive(&mut self, _timestamp: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+        let frame = net_pci::receive()?;
+        Some((NetRxToken(frame), NetTxToken))
+    }
+
+    fn transmit(&mut self, _timestamp: Instant) -> Option<Self::TxToken<'_>> {
+        net_pci::can_transmit().then_some(NetTxToken)
+    }
+
+    fn capabilities(&self) -> DeviceCapabilities {
+        let mut caps = DeviceCapabilities::default();
+        caps.medium = Medium::Ethernet;
+        caps.max_transmission_unit = net_pci::MAX_FRAME_SIZE;
+        caps
+    }
+}
+
+impl phy::RxToken for NetRxToken {
+    fn consume<R, F>(mut self, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        f(&mut self.0)
+    }
+}
+
+impl phy::TxToken for NetTxToken {
+    fn consume<R, F>(self, len: usize, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        let mut frame = vec![0u8; len];
+        let result = f(&mut frame);
+        if net_pci::transmit(&frame).is_err() {
+            crate::serial_println!("[net] Dropped outgoing frame ({} bytes)", len);
+        }
+        result
+    }
+}
+
+/// Where a fetch is in its lifecycle
+enum FetchState {
+    /// Waiting for DHCP to configure the interface
+    WaitingForNetwork,
+    /// DNS query in flight
+    Resolving(dns::QueryHandle),
+    /// TCP connection open: sending the request and collecting the response
+    Tcp {
+        socket: SocketHandle,
+        sent: usize,
+        response: Vec<u8>,
+        established: bool,
+    },
+    /// Failed before any network activity
+    Failed(NetworkError),
+}
+
+/// An in-progress HTTP fetch
+struct Fetch {
+    /// Request method (decides whether a body is expected)
+    method: HttpMethod,
+    /// Parsed target
+    url: http::Url,
+    /// Serialized request
+    request: Vec<u8>,
+    /// Time after which the fetch fails with `Timeout`
+    deadline_ms: u64,
+    /// Current state
+    state: FetchState,
+}
+
+/// Outcome of advancing a fetch by one poll
+enum Step {
+    /// Nothing to do until more packets arrive
+    Wait,
+    /// Move to a new state and keep going
+    Next(FetchState),
+    /// Fetch finished
+    Done(HttpResponse),
+}
+
+/// The network stack
+struct NetStack {
+    /// smoltcp interface
+    iface: Interface,
+    /// Device adapter
+    device: NetDevice,
+    /// All sockets (DHCP, DNS and one TCP socket per fetch)
+    sockets: SocketSet<'static>,
+    /// DHCP client socket
+    dhcp: SocketHandle,
+    /// DNS resolver socket
+    dns: SocketHandle,
+    /// Whether DHCP has configured an address
+    configured: bool,
+    /// In-progress fetches
+    fetches: BTreeMap<NetworkRequestId, Fetch>,
+    /// Next ephemeral local port
+    next_port: u16,
+}
+
+/// Global network stack
+static STACK: Mutex<Option<NetStack>> = Mutex::new(None);
+
+/// Milliseconds since boot
+fn now_ms() -> u64 {
+    super::apic::elapsed_nanos() / 1_000_000
+}
+
+/// Parse a dotted-quad IPv4 literal
+fn parse_ipv4(host: &str) -> Option<Ipv4Address> {
+    let mut octets = [0u8; 4];
+    let mut parts = host.split('.');
+    for octet in octets.iter_mut() {
+        *octet = parts.next()?.parse().ok()?;
+    }
+    if parts.next().is_some() {
+        return None;
+    }
+    Some(Ipv4Address(octets))
+}
+
+impl NetStack {
+    /// Create the stack on top of an initialized virtio-net device
+    fn new(mac: [u8; 6]) -> Self {
+        let mut device = NetDevice;
+        let mut config = Config::new(HardwareAddress::Ethernet(EthernetAddress(mac)));
+        let mut seed = [0u8; 8];
+        if !super::random::fill_random_bytes(&mut seed) {
+            seed = super::read_tsc().to_le_bytes();
+        }
+        config.random_seed = u64::from_le_bytes(seed);
+
+        let iface = Interface::new(config, &mut device, Instant::from_millis(now_ms() as i64));
+        let mut sockets = SocketSet::new(Vec::new());
+        let dhcp = sockets.add(dhcpv4::Socket::new());
+        let dns = sockets.add(dns::Socket::new(&[], Vec::new()));
+
+        Self {
+            iface,
+            device,
+            sockets,
+            dhcp,
+            dns,
+            configured: false,
+            fetches: BTreeMap::new(),
+            next_port: EPHEMERAL_PORT_START,
+        }
+    }
+
+    /// Apply DHCP lease changes
+    fn poll_dhcp(&mut self) {
+        let event = self.sockets.get_mut::<dhcpv4::Socket>(self.dhcp).poll();
+        let (address, router, dns_servers) = match event {
+            None => return,
+            Some(dhcpv4::Event::Configured(config)) => (
+                config.address,
+                config.router,
+                config.dns_servers.iter().map(|s| IpAddress::Ipv4(*s)).collect::<Vec<_>>(),
+            ),
+            Some(dhcpv4::Event::Deconfigured) => {
+                crate::serial_println!("[net] DHCP lease lost");
+                self.iface.update_ip_addrs(|addrs| addrs.clear());
+                self.iface.routes_mut().remove_default_ipv4_route();
+                self.configured = false;
+                return;
+            }
+        };
+
+        crate::serial_println!("[net] DHCP: address {}, router {:?}, {} DNS server(s)",
+            address, router, dns_servers.len());
+        self.iface.update_ip_addrs(|addrs| {
+            addrs.clear();
+            let _ = addrs.push(IpCidr::Ipv4(address));
+        });
+        match router {
+            Some(router) => {
+                let _ = self.iface.routes_mut().add_default_ipv4_route(router);
+            }
+            None => {
+                self.iface.routes_mut().remove_default_ipv4_route();
+            }
+        }
+        self.sockets.get_mut::<dns::Socket>(self.dns).update_servers(&dns_servers);
+        self.configured = true;
+    }
+
+    /// Allocate an ephemeral local port
+    fn alloc_port(&mut self) -> u16 {
+        let port = self.next_port;
+        self.next_port = if port == u16::MAX { EPHEMERAL_PORT_START } else { port + 1 };
+        port
+    }
+
+    /// Open a TCP connection to the fetch's target
+    fn connect(&mut self, addr: Ipv4Address, port: u16) -> Step {
+        let mut socket = tcp::Socket::new(
+            tcp::SocketBuffer::new(vec![0u8; TCP_RX_BUFFER_SIZE]),
+            tcp::SocketBuffer::new(vec![0u8; TCP_TX_BUFFER_SIZE]),
+        );
+        let local_port = self.alloc_port();
+        if socket.connect(self.iface.context(), (IpAddress::Ipv4(addr), port), local_port).is_err() {
+            return Step::Done(HttpResponse::err(NetworkError::ConnectionFailed));
+        }
+        Step::Next(FetchState::Tcp {
+            socket: self.sockets.add(socket),
+            sent: 0,
+            response: Vec::new(),
+            established: false,
+        })
+    }
+
+    /// Advance one fetch as far as possible
+    fn advance(&mut self, fetch: &mut Fetch, now_ms: u64) -> Option<HttpResponse> {
+        if now_ms >= fetch.deadline_ms {
+            return Some(HttpResponse::err(NetworkError::Timeout));
+        }
+
+        loop {
+            let step = match &mut fetch.state {
+                FetchState::Failed(error) => Step::Done(HttpResponse::err(error.clone())),
+
+                FetchState::WaitingForNetwork if !self.configured => Step::Wait,
+                FetchState::WaitingForNetwork => match parse_ipv4(&fetch.url.host) {
+                    Some(addr) => self.connect(addr, fetch.url.port),
+                    None => {
+                        let dns = self.sockets.get_mut::<dns::Socket>(self.dns);
+                        match dns.start_query(self.iface.context(), &fetch.url.host, DnsQueryType::A) {
+                            Ok(query) => Step::Next(FetchState::Resolving(query)),
+                            Err(_) => Step::Done(HttpResponse::err(NetworkError::DnsError)),
+                        }
+                    }
+                },
+
+                FetchState::Resolving(query) => {
+                    let dns = self.sockets.get_mut::<dns::Socket>(self.dns);
+                    match dns.get_query_result(*query) {
+                        Ok(addrs) => {
+                            let addr = addrs.iter().find_map(|addr| match addr {
+                                IpAddress::Ipv4(v4) => Some(*v4),
+                                #[allow(unreachable_patterns)]
+                                _ => None,
+                            });
+                            match addr {
+                                Some(addr) => self.connect(addr, fetch.url.port),
+                                None => Step::Done(HttpResponse::err(NetworkError::DnsError)),
+                            }
+                        }
+                        Err(dns::GetQueryResultError::Pending) => Step::Wait,
+                        Err(_) => Step::Done(HttpResponse::err(NetworkError::DnsError)),
+                    }
+                }
+
+                FetchState::Tcp { socket, sent, response, established } => {
+                    let socket = self.sockets.get_mut::<tcp::Socket>(*socket);
+                    *established |= socket.may_send();
+
+                    if socket.can_send() && *sent < fetch.request.len() {
+                        if let Ok(n) = socket.send_slice(&fetch.request[*sent..]) {
+                            *sent += n;
+                        }
+                    }
+
+                    let mut chunk = [0u8; 2048];
+                    while socket.can_recv() {
+                        match socket.recv_slice(&mut chunk) {
+                            Ok(0) | Err(_) => break,
+                            Ok(n) => response.extend_from_slice(&chunk[..n]),
+                        }
+                    }
+
+                    if !*established && socket.state() == tcp::State::Closed {
+                        Step::Done(HttpResponse::err(NetworkError::ConnectionFailed))
+                    } else if response.len() > http::MAX_RESPONSE_SIZE {
+                        Step::Done(HttpResponse::err(NetworkError::Other(String::from("Response too large"))))
+                    } else {
+                        // The server closing its side ends an unframed body
+                        let eof = *established && !socket.may_recv();
+                        match http::parse_response(response, eof, fetch.method) {
+                            Ok(Some(success)) => Step::Done(HttpResponse { result: Ok(success) }),
+                            Ok(None) => Step::Wait,
+                            Err(error) => Step::Done(HttpResponse::err(error)),
+                        }
+                    }
+                }
+            };
+
+            match step {
+                Step::Wait => return None,
+                Step::Next(state) => fetch.state = state,
+                Step::Done(response) => return Some(response),
+            }
+        }
+    }
+
+    /// Release the sockets or queries a fetch still holds
+    fn release(&mut self, state: &FetchState) {
+        match state {
+            FetchState::Resolving(query) => {
+                self.sockets.get_mut::<dns::Socket>(self.dns).cancel_query(*query);
+            }
+            FetchState::Tcp { socket, .. } => {
+                self.sockets.get_mut::<tcp::Socket>(*socket).abort();
+                self.sockets.remove(*socket);
+            }
+            _ => {}
+        }
+    }
+
+    /// Run the interface and advance every fetch
+    fn poll(&mut self) -> Vec<(NetworkRequestId, HttpResponse)> {
+        let now = now_ms();
+        self.iface.poll(Instant::from_millis(now as i64), &mut self.device, &mut self.sockets);
+        self.poll_dhcp();
+
+        let mut done = Vec::new();
+        for (request_id, mut fetch) in core::mem::take(&mut self.fetches) {
+            match self.advance(&mut fetch, now) {
+                Some(response) => {
+                    self.release(&fetch.state);
+                    done.push((request_id, response));
+                }
+                None => {
+                    self.fetches.insert(request_id, fetch);
+                }
+            }
+        }
+
+        // Flush anything the fetches queued
+        self.iface.poll(Instant::from_millis(now as i64), &mut self.device, &mut self.sockets);
+        done
+    }
+}
+
+/// Bring up the network stack on the virtio-net device
+///
+/// Returns false if there is no initialized network device. DHCP runs in the
+/// background from [`poll`].
+pub fn init() -> bool {
+    let mac = match net_pci::mac_address() {
+        Some(mac) => mac,
+        None => return false,
+    };
+    *STACK.lock() = Some(NetStack::new(mac));
+    crate::serial_println!("[net] Network stack initialized, waiting for DHCP");
+    true
+}
+
+/// Check if the network stack is up
+pub fn is_initialized() -> bool {
+    STACK.lock().is_some()
+}
+
+/// Start an HTTP fetch
+///
+/// The result is returned by a later [`poll`] under the same request ID.
+pub fn start_fetch(request_id: NetworkRequestId, request: HttpRequest) {
+    let mut guard = STACK.lock();
+    let stack = match guard.as_mut() {
+        Some(stack) => stack,
+        None => return,
+    };
+
+    let timeout_ms = match request.timeout_ms {
+        0 => DEFAULT_TIMEOUT_MS,
+        ms => ms as u64,
+    };
+    let (url, state) = match http::parse_url(&request.url) {
+        Ok(url) => (url, FetchState::WaitingForNetwork),
+        Err(error) => (
+            http::Url { host: String::new(), port: 0, path: String::new() },
+            FetchState::Failed(error),
+        ),
+    };
+    let bytes = http::build_request(&request, &url);
+
+    stack.fetches.insert(request_id, Fetch {
+        method: request.method,
+        url,
+        request: bytes,
+        deadline_ms: now_ms().saturating_add(timeout_ms),
+        state,
+    });
+}
+
+/// Poll the network and return finished fetches
+pub fn poll() -> Vec<(NetworkRequestId, HttpResponse)> {
+    match STACK.lock().as_mut() {
+        Some(stack) => stack.poll(),
+        None => Vec::new(),
+    }
+}
@@ -143,6 +143,38 @@ pub mod command {
     pub const INTERRUPT_DISABLE: u16 = 1 << 10;
 }
 
+/// PCI status register bits
+pub mod status {
+    /// Device has a capability list at CAP_PTR
+    pub const CAPABILITIES_LIST: u16 = 1 << 4;
+}
+
+/// PCI capability IDs
+pub mod capability {
+    pub const MSI: u8 = 0x05;
+    pub const MSIX: u8 = 0x11;
+}
+
+/// MSI-X capability layout
+mod msix {
+    /// Offset of Message Control within the capability
+    pub const MESSAGE_CONTROL: u8 = 0x02;
+    /// Offset of the Table Offset/BIR dword within the capability
+    pub const TABLE: u8 = 0x04;
+    /// Message Control: table size minus one
+    pub const TABLE_SIZE_MASK: u16 = 0x07FF;
+    /// Message Control: mask all vectors
+    pub const FUNCTION_MASK: u16 = 1 << 14;
+    /// Message Control: MSI-X enable
+    pub const ENABLE: u16 = 1 << 15;
+    /// Size of one table entry
+    pub const ENTRY_SIZE: u64 = 16;
+    /// Vector Control: entry masked
+    pub const VECTOR_MASKED: u32 = 1;
+    /// Message address for fixed delivery to a LAPIC
+    pub const MSG_ADDRESS_BASE: u32 = 0xFEE0_0000;
+}
+
 /// A discovered PCI device
 #[derive(Clone, Copy, Debug)]
 pub struct PciDevice {
@@ -235,6 +267,88 @@ impl PciDevice {
         let new_cmd = cmd | command::INTERRUPT_DISABLE;
         config_write_u32(self.addr, regs::COMMAND, new_cmd as u32);
     }
+
+    /// Legacy INTx line assigned by firmware, if any
+    pub fn interrupt_line(&self) -> Option<u8> {
+        let pin = config_read_u8(self.addr, regs::INTERRUPT_PIN);
+        let line = config_read_u8(self.addr, regs::INTERRUPT_LINE);
+        // Pin 0 means no INTx; line 0xFF means "not connected"
+        (pin != 0 && line != 0xFF).then_some(line)
+    }
+
+    /// Find a capability in the capability list, returning its config offset
+    pub fn find_capability(&self, id: u8) -> Option<u8> {
+        if config_read_u16(self.addr, regs::STATUS) & status::CAPABILITIES_LIST == 0 {
+            return None;
+        }
+
+        let mut ptr = config_read_u8(self.addr, regs::CAP_PTR) & 0xFC;
+        // At most 48 capabilities fit in the 192 bytes after the header
+        for _ in 0..48 {
+            if ptr == 0 {
+                return None;
+            }
+            if config_read_u8(self.addr, ptr) == id {
+                return Some(ptr);
+            }
+            ptr = config_read_u8(self.addr, ptr + 1) & 0xFC;
+        }
+        None
+    }
+
+    /// Route MSI-X table entry `entry` to `vector` on the CPU with `apic_id`
+    /// and enable MSI-X
+    ///
+    /// All other table entries stay masked. Returns false if the device has
+    /// no MSI-X capability or the entry does not exist.
+    ///
+    /// # Safety
+    /// The table BAR must be mapped through the physical memory offset, and
+    /// `vector` must have a handler installed.
+    pub unsafe fn enable_msix(&self, entry: u16, vector: u8, apic_id: u8) -> bool {
+        let cap = match self.find_capability(capability::MSIX) {
+            Some(cap) => cap,
+            None => return false,
+        };
+
+        let control = config_read_u16(self.addr, cap + msix::MESSAGE_CONTROL);
+        let table_size = (control & msix::TABLE_SIZE_MASK) + 1;
+        if entry >= table_size {
+            return false;
+        }
+
+        let table = config_read_u32(self.addr, cap + msix::TABLE);
+        let bir = (table & 0x7) as u8;
+        let table_phys = self.bar_address(bir) + (table & !0x7) as u64;
+        let table_virt = table_phys + crate::x86_64::vmm::phys_mem_offset();
+
+        // Keep the function masked while the table is programmed
+        self.write_msix_control(cap, control | msix::ENABLE | msix::FUNCTION_MASK);
+
+        for i in 0..table_size {
+            let entry_addr = table_virt + i as u64 * msix::ENTRY_SIZE;
+            let vector_control = (entry_addr + 12) as *mut u32;
+            if i == entry {
+                core::ptr::write_volatile(entry_addr as *mut u32, msix::MSG_ADDRESS_BASE | ((apic_id as u32) << 12));
+                core::ptr::write_volatile((entry_addr + 4) as *mut u32, 0);
+                core::ptr::write_volatile((entry_addr + 8) as *mut u32, vector as u32);
+                core::ptr::write_volatile(vector_control, 0);
+            } else {
+                core::ptr::write_volatile(vector_control, msix::VECTOR_MASKED);
+            }
+        }
+
+        // INTx is unused once MSI-X is on
+        self.disable_interrupts();
+        self.write_msix_control(cap, (control | msix::ENABLE) & !msix::FUNCTION_MASK);
+        true
+    }
+
+    /// Write the MSI-X Message Control word (upper half of the capability's first dword)
+    fn write_msix_control(&self, cap: u8, control: u16) {
+        let dword = config_read_u32(self.addr, cap);
+        config_write_u32(self.addr, cap, (dword & 0xFFFF) | ((control as u32) << 16));
+    }
 }
 
 /// Enumerate all PCI devices
@@ -0,0 +1,93 @@
+//! Shared memory regions backed by physical frames
+//!
+//! Each region is a list of zeroed 4 KiB frames from the VMM frame
+//! allocator, reached through the kernel's physical memory mapping. WASM
+//! processes run inside wasmi in the kernel address space and have no page
+//! tables of their own, so a process sees a region through a window in its
+//! linear memory; copies between the window and the region go frame by
+//! frame.
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use spin::Mutex;
+use x86_64::structures::paging::PhysFrame;
+
+use super::vmm::{self, PAGE_SIZE};
+use crate::HalError;
+
+/// Frames backing each region, by region ID
+static REGIONS: Mutex<BTreeMap<u64, Vec<PhysFrame>>> = Mutex::new(BTreeMap::new());
+
+/// Back region `id` with enough zeroed frames for `size` bytes
+pub fn alloc(id: u64, size: usize) -> Result<(), HalError> {
+    let pages = size.div_ceil(PAGE_SIZE);
+    let mut frames = Vec::with_capacity(pages);
+    for _ in 0..pages {
+        let Some(frame) = vmm::allocate_frame() else {
+            frames.into_iter().for_each(vmm::free_frame);
+            return Err(HalError::OutOfMemory);
+        };
+        frame_bytes(frame).fill(0);
+        frames.push(frame);
+    }
+    REGIONS.lock().insert(id, frames);
+    Ok(())
+}
+
+/// Return the frames of region `id` to the frame allocator
+pub fn free(id: u64) {
+    if let Some(frames) = REGIONS.lock().remove(&id) {
+        frames.into_iter().for_each(vmm::free_frame);
+    }
+}
+
+/// Copy bytes at `offset` in region `id` into `dst`
+pub fn read(id: u64, offset: usize, dst: &mut [u8]) -> Result<(), HalError> {
+    let mut pos = 0;
+    for_each_chunk(id, offset, dst.len(), |chunk| {
+        dst[pos..pos + chunk.len()].copy_from_slice(chunk);
+        pos += chunk.len();
+    })
+}
+
+/// Copy `src` into region `id` at `offset`
+pub fn write(id: u64, offset: usize, src: &[u8]) -> Result<(), HalError> {
+    let mut pos = 0;
+    for_each_chunk(id, offset, src.len(), |chunk| {
+        chunk.copy_from_slice(&src[pos..pos + chunk.len()]);
+        pos += chunk.len();
+    })
+}
+
+/// Visit `offset..offset + len` of region `id` one frame-sized piece at a
+/// time, in order
+fn for_each_chunk(
+    id: u64,
+    offset: usize,
+    len: usize,
+    mut f: impl FnMut(&mut [u8]),
+) -> Result<(), HalError> {
+    let regions = REGIONS.lock();
+    let frames = regions.get(&id).ok_or(HalError::InvalidArgument)?;
+    let end = offset.checked_add(len).ok_or(HalError::InvalidArgument)?;
+    if end > frames.len() * PAGE_SIZE {
+        return Err(HalError::InvalidArgument);
+    }
+    let mut at = offset;
+    while at < end {
+        let start = at % PAGE_SIZE;
+        let take = (PAGE_SIZE - start).min(end - at);
+        f(&mut frame_bytes(frames[at / PAGE_SIZE])[start..start + take]);
+        at += take;
+    }
+    Ok(())
+}
+
+/// The bytes of a frame through the physical memory mapping
+fn frame_bytes(frame: PhysFrame) -> &'static mut [u8] {
+    let ptr = vmm::phys_to_virt(frame.start_address()).as_mut_ptr::<u8>();
+    // SAFETY: the frame came from the frame allocator and belongs to one
+    // region; the REGIONS lock (or exclusive ownership during alloc)
+    // serialises every access to it
+    unsafe { core::slice::from_raw_parts_mut(ptr, PAGE_SIZE) }
+}
@@ -0,0 +1,339 @@
+//! Symmetric multiprocessing (SMP) bring-up
+//!
+//! The boot processor (BSP) finds the other CPUs in the ACPI MADT and
+//! starts each application processor (AP) with the INIT/SIPI/SIPI
+//! sequence. An AP starts in real mode at [`TRAMPOLINE_PHYS`], where a copy
+//! of the trampoline below switches it to long mode on the kernel's page
+//! tables and calls [`ap_main`].
+//!
+//! # Division of Work
+//!
+//! APs only execute WASM: each one runs the processes on its own run queue
+//! (see `WasmRuntime::run_cpu`) and hands their kernel syscalls back to the
+//! BSP. The BSP dispatches every syscall through Axiom, so the CommitLog
+//! stays a single total order no matter which CPU a process runs on.
+
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use x86_64::instructions::port::Port;
+use x86_64::{PhysAddr, VirtAddr};
+
+use super::vmm::{self, page_table, phys_to_virt, PageFlags};
+use super::{acpi, apic, gdt, interrupts, serial};
+
+/// Maximum number of CPUs brought online
+pub const MAX_CPUS: usize = 16;
+
+/// Index of the boot processor
+pub const BOOT_CPU: usize = 0;
+
+/// Physical address the trampoline is copied to (must be below 1MB and
+/// page aligned; the frame allocator never hands out memory below 1MB)
+pub const TRAMPOLINE_PHYS: u64 = 0x8000;
+
+/// Kernel stack size for each AP
+const AP_STACK_SIZE: usize = 64 * 1024;
+
+/// How long to wait for an AP to report in, in microseconds
+const AP_STARTUP_TIMEOUT_US: u32 = 100_000;
+
+/// Idle scheduler rounds before an AP halts until its next timer tick
+const AP_IDLE_SPINS: u32 = 10_000;
+
+/// Bookkeeping for one CPU
+struct CpuSlot {
+    apic_id: AtomicU32,
+    online: AtomicBool,
+    /// LAPIC timer ticks seen by this CPU
+    ticks: AtomicU64,
+}
+
+#[allow(clippy::declare_interior_mutable_const)]
+const EMPTY_SLOT: CpuSlot = CpuSlot {
+    apic_id: AtomicU32::new(0),
+    online: AtomicBool::new(false),
+    ticks: AtomicU64::new(0),
+};
+
+/// Per-CPU slots, indexed by CPU number (0 = BSP)
+static CPUS: [CpuSlot; MAX_CPUS] = [EMPTY_SLOT; MAX_CPUS];
+
+/// Number of CPUs online (CPU numbers are `0..CPU_COUNT`)
+static CPU_COUNT: AtomicUsize = AtomicUsize::new(1);
+
+// Real mode → protected mode → long mode trampoline.
+//
+// Position dependent: it only runs from its copy at TRAMPOLINE_PHYS, so all
+// addresses are computed relative to that. The BSP fills in the data words
+// at the end (CR3, stack, entry point, CPU number) before each SIPI.
+core::arch::global_asm!(
+    ".pushsection .text.zos_ap_trampoline, \"ax\"",
+    ".code16",
+    ".global zos_ap_trampoline_start",
+    "zos_ap_trampoline_start:",
+    "    cli",
+    "    cld",
+    "    xorw %ax, %ax",
+    "    movw %ax, %ds",
+    "    lgdtl {base} + zos_ap_gdt_ptr - zos_ap_trampoline_start",
+    "    movl %cr0, %eax",
+    "    orl $1, %eax",
+    "    movl %eax, %cr0",
+    "    ljmpl $0x08, ${base} + zos_ap_pm32 - zos_ap_trampoline_start",
+    ".code32",
+    "zos_ap_pm32:",
+    "    movw $0x10, %ax",
+    "    movw %ax, %ds",
+    "    movw %ax, %es",
+    "    movw %ax, %ss",
+    // CR4: PAE | OSFXSR | OSXMMEXCPT
+    "    movl %cr4, %eax",
+    "    orl $0x620, %eax",
+    "    movl %eax, %cr4",
+    "    movl {base} + zos_ap_cr3 - zos_ap_trampoline_start, %eax",
+    "    movl %eax, %cr3",
+    // EFER: LME | NXE
+    "    movl $0xC0000080, %ecx",
+    "    rdmsr",
+    "    orl $0x900, %eax",
+    "    wrmsr",
+    // CR0: clear EM, set PG | WP | MP
+    "    movl %cr0, %eax",
+    "    andl $0xFFFFFFFB, %eax",
+    "    orl $0x80010002, %eax",
+    "    movl %eax, %cr0",
+    "    ljmpl $0x18, ${base} + zos_ap_lm64 - zos_ap_trampoline_start",
+    ".code64",
+    "zos_ap_lm64:",
+    "    movw $0x10, %ax",
+    "    movw %ax, %ds",
+    "    movw %ax, %es",
+    "    movw %ax, %ss",
+    "    movw %ax, %fs",
+    "    movw %ax, %gs",
+    "    movq {base} + zos_ap_stack - zos_ap_trampoline_start, %rsp",
+    "    movq {base} + zos_ap_cpu - zos_ap_trampoline_start, %rdi",
+    "    movq {base} + zos_ap_entry - zos_ap_trampoline_start, %rax",
+    // Fake return address keeps the SysV stack alignment at entry
+    "    pushq $0",
+    "    jmpq *%rax",
+    ".balign 8",
+    "zos_ap_gdt:",
+    "    .quad 0",
+    "    .quad 0x00CF9A000000FFFF",
+    "    .quad 0x00CF92000000FFFF",
+    "    .quad 0x00AF9A000000FFFF",
+    "zos_ap_gdt_ptr:",
+    "    .word 31",
+    "    .long {base} + zos_ap_gdt - zos_ap_trampoline_start",
+    ".balign 8",
+    ".global zos_ap_cr3",
+    "zos_ap_cr3: .quad 0",
+    ".global zos_ap_stack",
+    "zos_ap_stack: .quad 0",
+    ".global zos_ap_entry",
+    "zos_ap_entry: .quad 0",
+    ".global zos_ap_cpu",
+    "zos_ap_cpu: .quad 0",
+    ".global zos_ap_trampoline_end",
+    "zos_ap_trampoline_end:",
+    ".popsection",
+    base = const TRAMPOLINE_PHYS,
+    options(att_syntax),
+);
+
+extern "C" {
+    static zos_ap_trampoline_start: u8;
+    static zos_ap_trampoline_end: u8;
+    static zos_ap_cr3: u8;
+    static zos_ap_stack: u8;
+    static zos_ap_entry: u8;
+    static zos_ap_cpu: u8;
+}
+
+/// Number of CPUs online
+pub fn cpu_count() -> usize {
+    CPU_COUNT.load(Ordering::Acquire)
+}
+
+/// CPU number of the caller (0 = BSP)
+pub fn current_cpu() -> usize {
+    let apic_id = apic::lapic_id();
+    (1..MAX_CPUS)
+        .find(|cpu| {
+            let slot = &CPUS[*cpu];
+            slot.online.load(Ordering::Acquire) && slot.apic_id.load(Ordering::Relaxed) == apic_id
+        })
+        .unwrap_or(BOOT_CPU)
+}
+
+/// Timer ticks seen by a CPU
+pub fn cpu_ticks(cpu: usize) -> u64 {
+    CPUS.get(cpu).map_or(0, |slot| slot.ticks.load(Ordering::Relaxed))
+}
+
+/// Count a timer tick on an application processor
+pub fn record_tick(cpu: usize) {
+    if let Some(slot) = CPUS.get(cpu) {
+        slot.ticks.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Start all application processors listed in the MADT
+///
+/// APs are started one at a time; the trampoline's data words are reused
+/// for each. Returns the number of CPUs online, including the BSP.
+///
+/// # Safety
+/// Must be called once on the BSP, after `acpi::init()`, `apic::init()`
+/// and VMM init.
+pub unsafe fn start_aps() -> usize {
+    let bsp_apic_id = apic::lapic_id();
+    CPUS[BOOT_CPU].apic_id.store(bsp_apic_id, Ordering::Relaxed);
+    CPUS[BOOT_CPU].online.store(true, Ordering::Release);
+
+    let madt = match acpi::info().and_then(|info| info.madt.as_ref()) {
+        Some(madt) => madt,
+        None => {
+            serial::write_str("[smp] No MADT found, running on the BSP only\n");
+            return 1;
+        }
+    };
+    let aps: Vec<u8> = madt
+        .local_apics
+        .iter()
+        .filter(|lapic| lapic.enabled && lapic.apic_id as u32 != bsp_apic_id)
+        .map(|lapic| lapic.apic_id)
+        .collect();
+    if aps.is_empty() {
+        return 1;
+    }
+
+    if let Err(e) = install_trampoline() {
+        serial::write_str(&alloc::format!("[smp] Cannot install AP trampoline: {}\n", e));
+        return 1;
+    }
+
+    let mut online = 1;
+    for apic_id in aps {
+        if online == MAX_CPUS {
+            serial::write_str("[smp] CPU limit reached, ignoring remaining CPUs\n");
+            break;
+        }
+        if start_ap(online, apic_id) {
+            online += 1;
+            CPU_COUNT.store(online, Ordering::Release);
+        } else {
+            serial::write_str(&alloc::format!(
+                "[smp] CPU with APIC ID {} did not start\n", apic_id
+            ));
+        }
+    }
+
+    serial::write_str(&alloc::format!("[smp] {} CPU(s) online\n", online));
+    online
+}
+
+/// Copy the trampoline to low memory and identity map it
+unsafe fn install_trampoline() -> Result<(), &'static str> {
+    let start = &raw const zos_ap_trampoline_start as usize;
+    let len = &raw const zos_ap_trampoline_end as usize - start;
+    if len > vmm::PAGE_SIZE {
+        return Err("trampoline larger than a page");
+    }
+
+    // The trampoline loads CR3 while still in 32-bit mode
+    let cr3 = vmm::tlb::current_cr3();
+    if cr3.as_u64() > u32::MAX as u64 {
+        return Err("kernel page tables above 4GB");
+    }
+
+    // Paging is switched on while executing from the trampoline page, so
+    // it has to be mapped at its physical address
+    let phys = PhysAddr::new(TRAMPOLINE_PHYS);
+    page_table::map_page(cr3, VirtAddr::new(TRAMPOLINE_PHYS), phys, PageFlags::WRITABLE, || {
+        vmm::allocate_frame().map(|frame| frame.start_address())
+    })?;
+    vmm::tlb::flush_page(VirtAddr::new(TRAMPOLINE_PHYS));
+
+    let dst = phys_to_virt(phys).as_mut_ptr::<u8>();
+    core::ptr::copy_nonoverlapping(start as *const u8, dst, len);
+    write_trampoline_word(&raw const zos_ap_cr3, cr3.as_u64());
+    write_trampoline_word(&raw const zos_ap_entry, ap_main as *const () as u64);
+    Ok(())
+}
+
+/// Write one of the trampoline's data words in the low-memory copy
+unsafe fn write_trampoline_word(symbol: *const u8, value: u64) {
+    let offset = symbol as usize - &raw const zos_ap_trampoline_start as usize;
+    let ptr = phys_to_virt(PhysAddr::new(TRAMPOLINE_PHYS + offset as u64)).as_mut_ptr::<u64>();
+    core::ptr::write_volatile(ptr, value);
+}
+
+/// Start one AP as CPU number `cpu` and wait for it to come online
+unsafe fn start_ap(cpu: usize, apic_id: u8) -> bool {
+    let stack = alloc::vec![0u8; AP_STACK_SIZE].leak();
+    let stack_top = (stack.as_ptr() as u64 + AP_STACK_SIZE as u64) & !0xF;
+    write_trampoline_word(&raw const zos_ap_stack, stack_top);
+    write_trampoline_word(&raw const zos_ap_cpu, cpu as u64);
+    CPUS[cpu].apic_id.store(apic_id as u32, Ordering::Relaxed);
+
+    let page = (TRAMPOLINE_PHYS >> 12) as u8;
+    apic::send_init(apic_id);
+    delay_us(10_000);
+    for _ in 0..2 {
+        apic::send_startup(apic_id, page);
+        delay_us(200);
+        if CPUS[cpu].online.load(Ordering::Acquire) {
+            return true;
+        }
+    }
+
+    for _ in 0..AP_STARTUP_TIMEOUT_US / 100 {
+        if CPUS[cpu].online.load(Ordering::Acquire) {
+            return true;
+        }
+        delay_us(100);
+    }
+    false
+}
+
+/// Busy-wait roughly `us` microseconds (one port 0x80 write is ~1µs)
+fn delay_us(us: u32) {
+    let mut port: Port<u8> = Port::new(0x80);
+    for _ in 0..us {
+        // SAFETY: port 0x80 is the POST diagnostic port, writes are harmless
+        unsafe { port.write(0) };
+    }
+}
+
+/// Rust entry point of an application processor
+///
+/// Sets up this CPU's GDT/TSS, IDT and LAPIC, reports online, then runs
+/// its WASM run queue forever.
+extern "C" fn ap_main(cpu: u64) -> ! {
+    let cpu = cpu as usize;
+    unsafe {
+        gdt::init_ap();
+        interrupts::init();
+        apic::init_ap();
+    }
+    CPUS[cpu].online.store(true, Ordering::Release);
+
+    x86_64::instructions::interrupts::enable();
+    unsafe { apic::start_timer() };
+
+    let runtime = super::global_wasm_runtime();
+    let mut idle = 0;
+    loop {
+        if runtime.run_cpu(cpu) {
+            idle = 0;
+        } else if idle < AP_IDLE_SPINS {
+            idle += 1;
+            core::hint::spin_loop();
+        } else {
+            idle = 0;
+            x86_64::instructions::hlt();
+        }
+    }
+}
@@ -1,538 +1,581 @@
 //! Block Storage - Key-Value Storage on VirtIO Block Device
 //!
-//! Provides a simple key-value storage abstraction on top of the raw block device.
-//! This is used by the HAL storage methods to persist data.
+//! Provides a key-value storage abstraction on top of the raw block device.
+//! This is used by the HAL storage methods (data disk) and the keystore
+//! (keystore disk) to persist data.
 //!
-//! # Disk Layout
+//! The on-disk format is the crash-consistent log in [`crate::kvlog`]:
+//! checksummed records, batches made atomic by a commit record, recovery
+//! that stops at the last intact batch, and garbage collection from the
+//! tail. Disks in the original append-only format are converted on mount.
 //!
-//! ```text
-//! ┌─────────────────────────────────────────────────────────────────────────────┐
-//! │ Sector 0: Superblock (magic, version, entry count, next free sector)        │
-//! ├─────────────────────────────────────────────────────────────────────────────┤
-//! │ Sector 1-N: Key-Value entries                                               │
-//! │   Each entry: | entry_header | key bytes | value bytes | padding |          │
-//! └─────────────────────────────────────────────────────────────────────────────┘
-//! ```
+//! # Asynchronous Operations
 //!
-//! # Entry Format
+//! Operations are submitted with [`submit_read`] / [`submit_mutations`] and
+//! return an [`OpId`]. Mutations are staged, and everything staged is
+//! written as one batch (group commit) once the previous batch is durable:
+//! the batch's sectors are written, then the device is flushed. Each
+//! operation is reported as [`Outcome::Committed`] after that flush, or as
+//! [`Outcome::Failed`] if any write of its batch failed, in which case none
+//! of its changes are applied. Until then its values are served from
+//! memory, so reads always see earlier writes.
 //!
-//! Each entry starts at a sector boundary:
-//! - 4 bytes: magic (0x5A4F5345 = "ZOSE")
-//! - 4 bytes: flags (1 = valid, 0 = deleted)
-//! - 4 bytes: key length
-//! - 4 bytes: value length
-//! - N bytes: key (UTF-8)
-//! - M bytes: value
-//! - Padding to sector boundary
+//! Progress is made by [`poll`], which the kernel loop drives through
+//! `take_storage_results()`. When nothing is in flight, `poll` also
+//! collects garbage, one batch at a time and synchronously. The synchronous
+//! `read`/`write`/`delete` helpers (used by bootstrap and the keystore)
+//! submit and then spin on the same machinery.
 
 use alloc::collections::BTreeMap;
 use alloc::string::String;
+use alloc::vec;
 use alloc::vec::Vec;
 use spin::Mutex;
 
-use super::virtio::blk_pci as blk;
-use super::virtio::blk::SECTOR_SIZE;
+use crate::kvlog::{self, BlockDevice, KvError, KvLog, Opened, PreparedBatch, RecordLocation, SECTOR_SIZE};
+use super::virtio::blk_pci::{self as blk, BlockCompletion, BlockOp, BlockRequestToken, Disk};
 use super::virtio::{VirtioError, VirtioResult};
 
-/// Storage magic number for superblock ("ZOS\0")
-const SUPERBLOCK_MAGIC: u32 = 0x5A4F5300;
-
-/// Storage magic number for entries ("ZOSE")
-const ENTRY_MAGIC: u32 = 0x5A4F5345;
-
-/// Storage version
-const STORAGE_VERSION: u32 = 1;
-
-/// Entry flag: valid
-const FLAG_VALID: u32 = 1;
-
-/// Entry flag: deleted
-const FLAG_DELETED: u32 = 0;
-
-/// Maximum key length (bytes)
-const MAX_KEY_LEN: usize = 256;
-
-/// Maximum value length (bytes)
-const MAX_VALUE_LEN: usize = 64 * 1024; // 64 KB
-
-/// Entry header size
-const ENTRY_HEADER_SIZE: usize = 16;
-
-/// Superblock structure (fits in one sector)
-#[repr(C)]
-#[derive(Clone, Copy, Debug)]
-struct Superblock {
-    /// Magic number
-    magic: u32,
-    /// Version
-    version: u32,
-    /// Number of valid entries
-    entry_count: u32,
-    /// Next free sector
-    next_free_sector: u32,
-    /// Reserved for future use
-    reserved: [u32; 123],
-    /// Checksum
-    checksum: u32,
-}
+pub use crate::kvlog::Mutation;
 
-impl Superblock {
-    fn new() -> Self {
-        let mut sb = Self {
-            magic: SUPERBLOCK_MAGIC,
-            version: STORAGE_VERSION,
-            entry_count: 0,
-            next_free_sector: 1, // Sector 0 is superblock
-            reserved: [0; 123],
-            checksum: 0,
-        };
-        sb.update_checksum();
-        sb
-    }
-
-    fn update_checksum(&mut self) {
-        self.checksum = 0;
-        let bytes = unsafe {
-            core::slice::from_raw_parts(
-                self as *const _ as *const u8,
-                SECTOR_SIZE - 4, // Exclude checksum itself
-            )
-        };
-        let mut sum: u32 = 0;
-        for &b in bytes {
-            sum = sum.wrapping_add(b as u32);
-        }
-        self.checksum = sum;
-    }
-
-    fn verify_checksum(&self) -> bool {
-        let bytes = unsafe {
-            core::slice::from_raw_parts(
-                self as *const _ as *const u8,
-                SECTOR_SIZE - 4,
-            )
-        };
-        let mut sum: u32 = 0;
-        for &b in bytes {
-            sum = sum.wrapping_add(b as u32);
-        }
-        sum == self.checksum
-    }
+/// Maximum spins while waiting for an operation
+const MAX_WAIT_ITERATIONS: u64 = 1_000_000_000;
 
-    fn is_valid(&self) -> bool {
-        self.magic == SUPERBLOCK_MAGIC && self.version == STORAGE_VERSION && self.verify_checksum()
-    }
+/// Identifies a submitted storage operation
+pub type OpId = u64;
 
-    fn to_bytes(&self) -> [u8; SECTOR_SIZE] {
-        let mut bytes = [0u8; SECTOR_SIZE];
-        unsafe {
-            core::ptr::copy_nonoverlapping(
-                self as *const _ as *const u8,
-                bytes.as_mut_ptr(),
-                core::mem::size_of::<Self>(),
-            );
-        }
-        bytes
-    }
+/// Result of a finished storage operation
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// Read finished (`None` if the key does not exist)
+    Read(Option<Vec<u8>>),
+    /// Mutations are durable on disk
+    Committed,
+    /// The operation failed
+    Failed,
+}
 
-    fn from_bytes(bytes: &[u8; SECTOR_SIZE]) -> Self {
-        let mut sb = Self::new();
-        unsafe {
-            core::ptr::copy_nonoverlapping(
-                bytes.as_ptr(),
-                &mut sb as *mut _ as *mut u8,
-                core::mem::size_of::<Self>(),
-            );
+impl From<KvError> for VirtioError {
+    fn from(e: KvError) -> Self {
+        match e {
+            KvError::NoSpace => VirtioError::OutOfMemory,
+            KvError::InvalidArgument => VirtioError::InvalidArgument,
+            KvError::Unformatted => VirtioError::DeviceNotFound,
+            KvError::Io | KvError::Corrupt => VirtioError::IoError,
         }
-        sb
     }
 }
 
-/// Entry header
-#[repr(C)]
-#[derive(Clone, Copy, Debug)]
-struct EntryHeader {
-    /// Magic number
-    magic: u32,
-    /// Flags (valid/deleted)
-    flags: u32,
-    /// Key length
-    key_len: u32,
-    /// Value length
-    value_len: u32,
-}
+/// A virtio-blk device as seen by the log (blocking requests)
+pub struct VirtioDisk(Disk);
 
-impl EntryHeader {
-    fn new(key_len: u32, value_len: u32) -> Self {
-        Self {
-            magic: ENTRY_MAGIC,
-            flags: FLAG_VALID,
-            key_len,
-            value_len,
-        }
+impl BlockDevice for VirtioDisk {
+    fn sector_count(&self) -> u64 {
+        blk::capacity_bytes(self.0).unwrap_or(0) / SECTOR_SIZE as u64
     }
 
-    fn is_valid(&self) -> bool {
-        self.magic == ENTRY_MAGIC && self.flags == FLAG_VALID
+    fn read(&self, sector: u64, buf: &mut [u8]) -> Result<(), KvError> {
+        blk::read_sectors(self.0, sector, buf).map_err(|_| KvError::Io)
     }
 
-    #[allow(dead_code)] // Available for future compaction/recovery operations
-    fn is_deleted(&self) -> bool {
-        self.magic == ENTRY_MAGIC && self.flags == FLAG_DELETED
+    fn write(&mut self, sector: u64, data: &[u8]) -> Result<(), KvError> {
+        blk::write_sectors(self.0, sector, data).map_err(|_| KvError::Io)
     }
 
-    /// Total size of this entry in bytes (including header)
-    fn total_size(&self) -> usize {
-        ENTRY_HEADER_SIZE + self.key_len as usize + self.value_len as usize
+    fn flush(&mut self) -> Result<(), KvError> {
+        blk::flush_device(self.0).map_err(|_| KvError::Io)
     }
+}
 
-    /// Number of sectors needed for this entry
-    fn sectors_needed(&self) -> u32 {
-        ((self.total_size() + SECTOR_SIZE - 1) / SECTOR_SIZE) as u32
-    }
+/// Mutations waiting for the next batch
+#[derive(Debug)]
+struct StagedOp {
+    /// Operation ID
+    op: OpId,
+    /// Changes, in order
+    mutations: Vec<Mutation>,
+    /// Bytes the changes take up in a batch
+    size: usize,
 }
 
-/// In-memory index entry
-#[derive(Clone, Debug)]
-struct IndexEntry {
-    /// Starting sector
-    sector: u32,
-    /// Number of sectors
-    num_sectors: u32,
-    /// Value length (for quick access without disk read)
-    #[allow(dead_code)]
-    value_len: u32,
+/// Batch in flight
+#[derive(Debug)]
+struct CommitBatch {
+    /// Operations made durable by this batch
+    ops: Vec<OpId>,
+    /// The batch, applied to the log once durable
+    batch: PreparedBatch,
+    /// Sector writes, then flush
+    stage: CommitStage,
+    /// A request of this batch failed
+    failed: bool,
+}
+
+/// Current step of a batch commit
+#[derive(Debug)]
+enum CommitStage {
+    /// Waiting for the batch's sector writes
+    Writes(Vec<BlockRequestToken>),
+    /// Waiting for the flush
+    Flush(BlockRequestToken),
 }
 
 /// Block storage manager
 pub struct BlockStorage {
-    /// In-memory index: key -> sector location
-    index: BTreeMap<String, IndexEntry>,
-    /// Superblock (cached)
-    superblock: Superblock,
-    /// Whether storage is initialized
-    initialized: bool,
+    /// Block device this storage lives on
+    disk: Disk,
+    /// The mounted log (`None` until initialized)
+    log: Option<KvLog<VirtioDisk>>,
+    /// Next operation ID
+    next_op: OpId,
+    /// Values written but not yet committed (`None` = deleted), with the
+    /// operation that wrote them
+    unflushed: BTreeMap<String, (OpId, Option<Vec<u8>>)>,
+    /// Reads in flight: block token -> (operation, record read)
+    reads: BTreeMap<BlockRequestToken, (OpId, RecordLocation)>,
+    /// Mutations waiting for the next batch, in submission order
+    staged: Vec<StagedOp>,
+    /// Batch in flight
+    commit: Option<CommitBatch>,
+    /// Finished operations not yet taken
+    finished: BTreeMap<OpId, Outcome>,
 }
 
 impl BlockStorage {
-    /// Create a new uninitialized storage manager
-    pub const fn new() -> Self {
+    /// Create a new uninitialized storage manager on `disk`
+    pub const fn new(disk: Disk) -> Self {
         Self {
-            index: BTreeMap::new(),
-            superblock: Superblock {
-                magic: 0,
-                version: 0,
-                entry_count: 0,
-                next_free_sector: 1,
-                reserved: [0; 123],
-                checksum: 0,
-            },
-            initialized: false,
+            disk,
+            log: None,
+            next_op: 1,
+            unflushed: BTreeMap::new(),
+            reads: BTreeMap::new(),
+            staged: Vec::new(),
+            commit: None,
+            finished: BTreeMap::new(),
         }
     }
 
-    /// Initialize storage, loading index from disk
+    /// Initialize storage, recovering the log from disk
+    ///
+    /// Returns `true` if the disk was blank and has been formatted.
     pub fn init(&mut self) -> VirtioResult<bool> {
-        if !blk::is_initialized() {
+        if !blk::is_initialized(self.disk) {
             return Err(VirtioError::DeviceNotFound);
         }
 
-        // Read superblock
-        let mut sector_buf = [0u8; SECTOR_SIZE];
-        blk::read_sectors(0, &mut sector_buf)?;
-
-        let sb = Superblock::from_bytes(&sector_buf);
-        
-        if sb.is_valid() {
-            // Existing storage, load index
-            crate::serial_println!("[storage] Found existing storage with {} entries", sb.entry_count);
-            self.superblock = sb;
-            self.load_index()?;
-            self.initialized = true;
-            Ok(false) // Not newly created
-        } else {
-            // Format new storage
-            crate::serial_println!("[storage] Formatting new storage...");
-            self.superblock = Superblock::new();
-            self.write_superblock()?;
-            self.index.clear();
-            self.initialized = true;
-            Ok(true) // Newly created
-        }
-    }
-
-    /// Load index from disk by scanning entries
-    fn load_index(&mut self) -> VirtioResult<()> {
-        self.index.clear();
-        
-        let mut sector = 1u64; // Start after superblock
-        let max_sector = self.superblock.next_free_sector as u64;
-        
-        let mut sector_buf = [0u8; SECTOR_SIZE];
-        
-        while sector < max_sector {
-            // Read entry header
-            blk::read_sectors(sector, &mut sector_buf)?;
-            
-            let header = unsafe {
-                core::ptr::read_unaligned(sector_buf.as_ptr() as *const EntryHeader)
-            };
-            
-            if header.magic != ENTRY_MAGIC {
-                // End of entries or corruption
-                break;
+        let (log, opened) = KvLog::open(VirtioDisk(self.disk))?;
+        match opened {
+            Opened::Existing => crate::serial_println!("[storage] Recovered {} entries", log.len()),
+            Opened::Migrated => {
+                crate::serial_println!("[storage] Converted {} entries to the log format", log.len())
             }
-            
-            let entry_sectors = header.sectors_needed();
-            
-            if header.is_valid() {
-                // Read key (may span multiple sectors)
-                let key_start = ENTRY_HEADER_SIZE;
-                let key_end = key_start + header.key_len as usize;
-
-                let key_bytes: Vec<u8>;
-                let key_slice = if key_end <= SECTOR_SIZE {
-                    &sector_buf[key_start..key_end]
-                } else {
-                    let total_bytes = entry_sectors as usize * SECTOR_SIZE;
-                    let mut entry_buf = alloc::vec![0u8; total_bytes];
-                    blk::read_sectors(sector, &mut entry_buf)?;
-                    key_bytes = entry_buf[key_start..key_end.min(total_bytes)].to_vec();
-                    key_bytes.as_slice()
-                };
-                
-                if let Ok(key) = core::str::from_utf8(key_slice) {
-                    self.index.insert(
-                        String::from(key),
-                        IndexEntry {
-                            sector: sector as u32,
-                            num_sectors: entry_sectors,
-                            value_len: header.value_len,
-                        },
-                    );
-                }
-            }
-            
-            sector += entry_sectors as u64;
+            Opened::Created => crate::serial_println!("[storage] Formatted new storage"),
         }
-        
-        crate::serial_println!("[storage] Loaded {} entries into index", self.index.len());
-        Ok(())
+        self.log = Some(log);
+        Ok(opened == Opened::Created)
     }
 
-    /// Write superblock to disk
-    fn write_superblock(&mut self) -> VirtioResult<()> {
-        self.superblock.update_checksum();
-        let bytes = self.superblock.to_bytes();
-        blk::write_sectors(0, &bytes)?;
-        blk::flush_device()
+    /// The mounted log
+    fn log(&self) -> VirtioResult<&KvLog<VirtioDisk>> {
+        self.log.as_ref().ok_or(VirtioError::DeviceNotFound)
     }
 
     /// Check if a key exists
     pub fn exists(&self, key: &str) -> bool {
-        self.index.contains_key(key)
+        match self.unflushed.get(key) {
+            Some((_, value)) => value.is_some(),
+            None => self.log.as_ref().is_some_and(|log| log.contains(key)),
+        }
     }
 
-    /// Read a value by key
+    /// Read a value by key (blocking)
     pub fn read(&self, key: &str) -> VirtioResult<Option<Vec<u8>>> {
-        let entry = match self.index.get(key) {
-            Some(e) => e,
-            None => return Ok(None),
-        };
-
-        // Read all sectors for this entry
-        let total_bytes = entry.num_sectors as usize * SECTOR_SIZE;
-        let mut buffer = alloc::vec![0u8; total_bytes];
-        blk::read_sectors(entry.sector as u64, &mut buffer)?;
-
-        // Parse header
-        let header = unsafe {
-            core::ptr::read_unaligned(buffer.as_ptr() as *const EntryHeader)
-        };
-
-        if !header.is_valid() {
-            return Ok(None);
+        if let Some((_, value)) = self.unflushed.get(key) {
+            return Ok(value.clone());
         }
+        Ok(self.log()?.get(key)?)
+    }
 
-        // Extract value
-        let value_start = ENTRY_HEADER_SIZE + header.key_len as usize;
-        let value_end = value_start + header.value_len as usize;
+    /// Write a key-value pair (blocking until committed)
+    pub fn write(&mut self, key: &str, value: &[u8]) -> VirtioResult<()> {
+        let op = self.submit_mutations(vec![Mutation::Put(String::from(key), value.to_vec())])?;
+        match self.wait(op)? {
+            Outcome::Committed => Ok(()),
+            _ => Err(VirtioError::IoError),
+        }
+    }
 
-        if value_end <= buffer.len() {
-            Ok(Some(buffer[value_start..value_end].to_vec()))
-        } else {
-            Err(VirtioError::IoError)
+    /// Delete a key (blocking until committed)
+    pub fn delete(&mut self, key: &str) -> VirtioResult<bool> {
+        if !self.exists(key) {
+            return Ok(false);
+        }
+        let op = self.submit_mutations(vec![Mutation::Delete(String::from(key))])?;
+        match self.wait(op)? {
+            Outcome::Committed => Ok(true),
+            _ => Err(VirtioError::IoError),
         }
     }
 
-    /// Write a key-value pair
-    pub fn write(&mut self, key: &str, value: &[u8]) -> VirtioResult<()> {
-        if key.len() > MAX_KEY_LEN || value.len() > MAX_VALUE_LEN {
-            return Err(VirtioError::InvalidArgument);
+    /// Allocate an operation ID
+    fn alloc_op(&mut self) -> OpId {
+        let op = self.next_op;
+        self.next_op += 1;
+        op
+    }
+
+    /// Start an asynchronous read
+    ///
+    /// Values that are cached or absent finish immediately.
+    pub fn submit_read(&mut self, key: &str) -> VirtioResult<OpId> {
+        let location = self.log()?.locate(key);
+        let op = self.alloc_op();
+
+        if let Some((_, value)) = self.unflushed.get(key) {
+            self.finished.insert(op, Outcome::Read(value.clone()));
+            return Ok(op);
         }
 
-        // Delete existing entry if present (mark as deleted)
-        if self.index.contains_key(key) {
-            self.delete(key)?;
+        match location {
+            Some(location) => {
+                let read = BlockOp::Read { sector: location.first_sector(), len: location.read_len() };
+                let token = blk::submit(self.disk, read)?;
+                self.reads.insert(token, (op, location));
+            }
+            None => {
+                self.finished.insert(op, Outcome::Read(None));
+            }
         }
+        Ok(op)
+    }
 
-        // Create entry
-        let header = EntryHeader::new(key.len() as u32, value.len() as u32);
-        let entry_sectors = header.sectors_needed();
-
-        // Check if we have space
-        let capacity = blk::capacity_bytes().ok_or(VirtioError::DeviceNotFound)?;
-        let max_sectors = (capacity / SECTOR_SIZE as u64) as u32;
-        
-        if self.superblock.next_free_sector + entry_sectors > max_sectors {
-            // Try compaction to reclaim deleted space
-            self.compact()?;
-            if self.superblock.next_free_sector + entry_sectors > max_sectors {
-                return Err(VirtioError::OutOfMemory);
+    /// Start applying a set of mutations atomically
+    ///
+    /// Reads and listings reflect the mutations immediately; the operation
+    /// finishes once they are committed to disk. Deleting a missing key is a
+    /// no-op.
+    pub fn submit_mutations(&mut self, mutations: Vec<Mutation>) -> VirtioResult<OpId> {
+        self.log()?;
+
+        // Reject a bad argument before anything becomes visible
+        let mut size = 0;
+        for mutation in &mutations {
+            let too_long = match mutation {
+                Mutation::Put(key, value) => {
+                    key.len() > kvlog::MAX_KEY_LEN || value.len() > kvlog::MAX_VALUE_LEN
+                }
+                Mutation::Delete(key) => key.len() > kvlog::MAX_KEY_LEN,
+            };
+            if too_long {
+                return Err(VirtioError::InvalidArgument);
             }
+            size += mutation.encoded_len();
+        }
+        if size > kvlog::MAX_BATCH_PAYLOAD {
+            return Err(VirtioError::InvalidArgument);
         }
 
-        // Build entry buffer
-        let buffer_size = entry_sectors as usize * SECTOR_SIZE;
-        let mut buffer = alloc::vec![0u8; buffer_size];
+        let op = self.alloc_op();
+        for mutation in &mutations {
+            let value = match mutation {
+                Mutation::Put(_, value) => Some(value.clone()),
+                Mutation::Delete(_) => None,
+            };
+            self.unflushed.insert(String::from(mutation.key()), (op, value));
+        }
+        self.staged.push(StagedOp { op, mutations, size });
+        Ok(op)
+    }
 
-        // Write header
-        unsafe {
-            core::ptr::write_unaligned(buffer.as_mut_ptr() as *mut EntryHeader, header);
+    /// Make progress: collect device completions and start commits
+    ///
+    /// `force` checks the device even if no completion interrupt arrived.
+    pub fn poll(&mut self, force: bool) {
+        if self.log.is_none() {
+            return;
         }
 
-        // Write key
-        buffer[ENTRY_HEADER_SIZE..ENTRY_HEADER_SIZE + key.len()].copy_from_slice(key.as_bytes());
-
-        // Write value
-        let value_start = ENTRY_HEADER_SIZE + key.len();
-        buffer[value_start..value_start + value.len()].copy_from_slice(value);
-
-        // Write to disk
-        let sector = self.superblock.next_free_sector;
-        blk::write_sectors(sector as u64, &buffer)?;
-
-        // Update index
-        self.index.insert(
-            String::from(key),
-            IndexEntry {
-                sector,
-                num_sectors: entry_sectors,
-                value_len: value.len() as u32,
-            },
-        );
+        for completion in blk::take_completions(self.disk, force) {
+            self.complete(completion);
+        }
 
-        // Update superblock
-        self.superblock.entry_count += 1;
-        self.superblock.next_free_sector += entry_sectors;
-        self.write_superblock()?;
+        self.start_commit();
 
-        Ok(())
+        if !self.busy() {
+            self.collect_garbage();
+        }
     }
 
-    /// Delete a key
-    pub fn delete(&mut self, key: &str) -> VirtioResult<bool> {
-        let entry = match self.index.remove(key) {
-            Some(e) => e,
-            None => return Ok(false),
+    /// Handle one block completion
+    fn complete(&mut self, completion: BlockCompletion) {
+        let token = completion.token;
+
+        if let Some((op, location)) = self.reads.remove(&token) {
+            let value = completion
+                .result
+                .and_then(|()| location.decode_value(&completion.data).map_err(VirtioError::from));
+            let outcome = match value {
+                Ok(value) => Outcome::Read(Some(value)),
+                Err(_) => Outcome::Failed,
+            };
+            self.finished.insert(op, outcome);
+            return;
+        }
+
+        let Some(commit) = self.commit.as_mut() else {
+            return;
         };
+        let done = match &mut commit.stage {
+            CommitStage::Writes(tokens) if tokens.contains(&token) => {
+                tokens.retain(|t| *t != token);
+                commit.failed |= completion.result.is_err();
+                if !tokens.is_empty() {
+                    false
+                } else if commit.failed {
+                    true
+                } else {
+                    // Every sector is written; make them durable
+                    match blk::submit(self.disk, BlockOp::Flush) {
+                        Ok(flush) => {
+                            commit.stage = CommitStage::Flush(flush);
+                            false
+                        }
+                        Err(_) => {
+                            commit.failed = true;
+                            true
+                        }
+                    }
+                }
+            }
+            CommitStage::Flush(flush) if *flush == token => {
+                commit.failed |= completion.result.is_err();
+                true
+            }
+            _ => false,
+        };
+        if done {
+            self.finish_commit();
+        }
+    }
 
-        // Mark entry as deleted on disk
-        let mut sector_buf = [0u8; SECTOR_SIZE];
-        blk::read_sectors(entry.sector as u64, &mut sector_buf)?;
+    /// Write everything staged as one batch
+    fn start_commit(&mut self) {
+        if self.commit.is_some() || self.staged.is_empty() {
+            return;
+        }
 
-        // Update flags to deleted
-        let header = unsafe {
-            &mut *(sector_buf.as_mut_ptr() as *mut EntryHeader)
+        // Take as many operations as fit in one batch
+        let mut size = 0;
+        let count = self
+            .staged
+            .iter()
+            .take_while(|staged| {
+                size += staged.size;
+                size <= kvlog::MAX_BATCH_PAYLOAD
+            })
+            .count()
+            .max(1);
+        let mutations: Vec<Mutation> = self.staged[..count]
+            .iter()
+            .flat_map(|staged| staged.mutations.iter().cloned())
+            .collect();
+
+        let reads_in_flight = !self.reads.is_empty();
+        let Some(log) = self.log.as_mut() else {
+            return;
         };
-        header.flags = FLAG_DELETED;
-
-        blk::write_sectors(entry.sector as u64, &sector_buf)?;
+        let prepared = match log.prepare(&mutations) {
+            // Collecting garbage could reuse sectors that reads in flight
+            // point at, so wait for them
+            Err(KvError::NoSpace) if reads_in_flight => return,
+            Err(KvError::NoSpace) => log.make_room(&mutations),
+            other => other,
+        };
+        let ops: Vec<OpId> = self.staged.drain(..count).map(|staged| staged.op).collect();
 
-        // Update superblock
-        self.superblock.entry_count = self.superblock.entry_count.saturating_sub(1);
-        self.write_superblock()?;
+        let batch = match prepared {
+            Ok(batch) => batch,
+            Err(e) => {
+                crate::serial_println!("[storage] Batch of {} operations rejected: {}", ops.len(), e);
+                return self.fail_ops(&ops);
+            }
+        };
+        if batch.is_empty() {
+            // Only deletes of missing keys
+            return self.commit_ops(&ops);
+        }
 
-        Ok(true)
+        let mut tokens = Vec::with_capacity(batch.writes.len());
+        let mut failed = false;
+        for (sector, data) in &batch.writes {
+            match blk::submit(self.disk, BlockOp::Write { sector: *sector, data: data.clone() }) {
+                Ok(token) => tokens.push(token),
+                Err(_) => {
+                    failed = true;
+                    break;
+                }
+            }
+        }
+        // Even a failed batch waits for the writes already submitted
+        let idle = tokens.is_empty();
+        self.commit = Some(CommitBatch { ops, batch, stage: CommitStage::Writes(tokens), failed });
+        if idle {
+            self.finish_commit();
+        }
     }
 
-    /// List all keys with a given prefix
-    pub fn list(&self, prefix: &str) -> Vec<String> {
-        self.index
-            .keys()
-            .filter(|k| k.starts_with(prefix))
-            .cloned()
-            .collect()
-    }
+    /// Conclude the batch in flight once all of its requests are done
+    fn finish_commit(&mut self) {
+        let (Some(commit), Some(log)) = (self.commit.take(), self.log.as_mut()) else {
+            return;
+        };
 
-    /// Get entry count
-    pub fn count(&self) -> usize {
-        self.index.len()
+        let result = if commit.failed {
+            // The batch may be partly on disk; retiring its sequence number
+            // keeps it from being taken for a later batch
+            let _ = log.abandon(commit.batch);
+            Err(KvError::Io)
+        } else {
+            log.apply(commit.batch)
+        };
+
+        match result {
+            Ok(()) => self.commit_ops(&commit.ops),
+            Err(e) => {
+                crate::serial_println!("[storage] Batch write failed: {}", e);
+                self.fail_ops(&commit.ops);
+            }
+        }
     }
 
-    /// Check if initialized
-    pub fn is_initialized(&self) -> bool {
-        self.initialized
+    /// Report operations as durable
+    fn commit_ops(&mut self, ops: &[OpId]) {
+        self.unflushed.retain(|_, (writer, _)| !ops.contains(writer));
+        for &op in ops {
+            self.finished.insert(op, Outcome::Committed);
+        }
     }
 
-    /// Clear all storage (for testing/reset)
-    pub fn clear(&mut self) -> VirtioResult<()> {
-        self.superblock = Superblock::new();
-        self.index.clear();
-        self.write_superblock()
+    /// Report operations as failed and forget their changes
+    fn fail_ops(&mut self, ops: &[OpId]) {
+        self.unflushed.retain(|_, (writer, _)| !ops.contains(writer));
+        for &op in ops {
+            self.finished.insert(op, Outcome::Failed);
+        }
     }
 
-    /// Compact storage by rewriting valid entries contiguously
-    fn compact(&mut self) -> VirtioResult<()> {
-        if !self.initialized {
-            return Err(VirtioError::InvalidArgument);
+    /// Reclaim one batch of garbage if the log wants it
+    fn collect_garbage(&mut self) {
+        let Some(log) = self.log.as_mut() else {
+            return;
+        };
+        if log.wants_gc() {
+            if let Err(e) = log.gc_step() {
+                crate::serial_println!("[storage] Garbage collection failed: {}", e);
+            }
         }
+    }
 
-        let capacity = blk::capacity_bytes().ok_or(VirtioError::DeviceNotFound)?;
-        let max_sectors = (capacity / SECTOR_SIZE as u64) as u32;
+    /// Take all finished operations
+    pub fn take_finished(&mut self) -> Vec<(OpId, Outcome)> {
+        core::mem::take(&mut self.finished).into_iter().collect()
+    }
 
-        let mut next_sector = 1u32;
-        let mut new_index = BTreeMap::new();
+    /// Whether any operation is still in progress
+    fn busy(&self) -> bool {
+        !self.reads.is_empty() || !self.staged.is_empty() || self.commit.is_some()
+    }
 
-        for (key, entry) in self.index.iter() {
-            let total_bytes = entry.num_sectors as usize * SECTOR_SIZE;
-            let mut buffer = alloc::vec![0u8; total_bytes];
-            blk::read_sectors(entry.sector as u64, &mut buffer)?;
+    /// Spin until an operation finishes
+    ///
+    /// Other finished operations stay queued for `take_finished`.
+    pub fn wait(&mut self, op: OpId) -> VirtioResult<Outcome> {
+        let mut iterations = 0u64;
+        loop {
+            if let Some(outcome) = self.finished.remove(&op) {
+                return Ok(outcome);
+            }
+            self.poll(true);
 
-            let header = unsafe {
-                core::ptr::read_unaligned(buffer.as_ptr() as *const EntryHeader)
-            };
-            if !header.is_valid() {
-                continue;
+            iterations += 1;
+            if iterations > MAX_WAIT_ITERATIONS {
+                return Err(VirtioError::Timeout);
             }
 
-            if next_sector + entry.num_sectors > max_sectors {
-                return Err(VirtioError::OutOfMemory);
+            core::hint::spin_loop();
+        }
+    }
+
+    /// Spin until every operation in progress has finished
+    fn drain(&mut self) -> VirtioResult<()> {
+        let mut iterations = 0u64;
+        while self.busy() {
+            self.poll(true);
+
+            iterations += 1;
+            if iterations > MAX_WAIT_ITERATIONS {
+                return Err(VirtioError::Timeout);
             }
 
-            blk::write_sectors(next_sector as u64, &buffer)?;
+            core::hint::spin_loop();
+        }
+        Ok(())
+    }
 
-            new_index.insert(
-                key.clone(),
-                IndexEntry {
-                    sector: next_sector,
-                    num_sectors: entry.num_sectors,
-                    value_len: entry.value_len,
-                },
-            );
+    /// List all keys with a given prefix
+    pub fn list(&self, prefix: &str) -> Vec<String> {
+        let mut keys: Vec<String> = match self.log.as_ref() {
+            Some(log) => log
+                .keys(prefix)
+                .filter(|key| !self.unflushed.contains_key(key.as_str()))
+                .cloned()
+                .collect(),
+            None => Vec::new(),
+        };
+        keys.extend(
+            self.unflushed
+                .iter()
+                .filter(|(key, (_, value))| value.is_some() && key.starts_with(prefix))
+                .map(|(key, _)| key.clone()),
+        );
+        keys.sort();
+        keys
+    }
 
-            next_sector += entry.num_sectors;
+    /// Get entry count
+    pub fn count(&self) -> usize {
+        let Some(log) = self.log.as_ref() else {
+            return 0;
+        };
+        let mut count = log.len();
+        for (key, (_, value)) in &self.unflushed {
+            match (log.contains(key), value.is_some()) {
+                (false, true) => count += 1,
+                (true, false) => count -= 1,
+                _ => {}
+            }
         }
+        count
+    }
 
-        self.index = new_index;
-        self.superblock.entry_count = self.index.len() as u32;
-        self.superblock.next_free_sector = next_sector;
-        self.write_superblock()
+    /// Check if initialized
+    pub fn is_initialized(&self) -> bool {
+        self.log.is_some()
+    }
+
+    /// Clear all storage (for testing/reset)
+    pub fn clear(&mut self) -> VirtioResult<()> {
+        self.drain()?;
+        match self.log.as_mut() {
+            Some(log) => Ok(log.clear()?),
+            None => Err(VirtioError::DeviceNotFound),
+        }
     }
 }
 
 /// Global storage instance
-static STORAGE: Mutex<BlockStorage> = Mutex::new(BlockStorage::new());
+static STORAGE: Mutex<BlockStorage> = Mutex::new(BlockStorage::new(Disk::Data));
 
 /// Initialize the global storage
 pub fn init() -> VirtioResult<bool> {
@@ -549,6 +592,28 @@ pub fn exists(key: &str) -> VirtioResult<bool> {
     Ok(STORAGE.lock().exists(key))
 }
 
+/// Start an asynchronous read
+pub fn submit_read(key: &str) -> VirtioResult<OpId> {
+    STORAGE.lock().submit_read(key)
+}
+
+/// Start applying a set of mutations
+pub fn submit_mutations(mutations: Vec<Mutation>) -> VirtioResult<OpId> {
+    STORAGE.lock().submit_mutations(mutations)
+}
+
+/// Collect device completions and take finished operations
+pub fn poll() -> Vec<(OpId, Outcome)> {
+    let mut storage = STORAGE.lock();
+    storage.poll(false);
+    storage.take_finished()
+}
+
+/// Wait until every operation in progress has finished
+pub fn sync() -> VirtioResult<()> {
+    STORAGE.lock().drain()
+}
+
 /// Read a value
 pub fn read(key: &str) -> VirtioResult<Option<Vec<u8>>> {
     STORAGE.lock().read(key)
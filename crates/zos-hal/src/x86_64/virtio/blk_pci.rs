@@ -2,14 +2,36 @@
 //!
 //! Implements a driver for VirtIO block devices using the PCI transport.
 //! This is the typical configuration for QEMU x86_64.
+//!
+//! # Request Queue
+//!
+//! Requests are submitted with [`submit`] and identified by a
+//! [`BlockRequestToken`]. Up to a queue's worth of requests are in flight at
+//! once; the rest wait in a software queue until descriptors free up.
+//! Completions are collected with [`take_completions`].
+//!
+//! # Interrupts
+//!
+//! Each disk has its own interrupt vector. MSI-X is used when the device
+//! offers it, otherwise the legacy INTx line is routed through the IOAPIC.
+//! The interrupt handler only acknowledges the device and flags the disk;
+//! the used ring is drained by the kernel loop, never in interrupt context.
+//! Without either mechanism the device is polled.
+//!
+//! The blocking `read_sectors`/`write_sectors`/`flush_device` helpers go
+//! through the same queue and spin until their own request completes.
 
 use alloc::boxed::Box;
-use alloc::collections::BTreeMap;
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::vec;
 use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, AtomicU8, Ordering};
 use spin::Mutex;
 
+use crate::x86_64::{apic, driver_irq};
+use crate::x86_64::interrupts::InterruptIndex;
 use crate::x86_64::pci::{self, PciDevice};
-use super::pci::{PciTransport, init_device, finalize_device};
+use super::pci::{PciTransport, init_device, finalize_device, NO_MSIX_VECTOR};
 use super::queue::Virtqueue;
 use super::{DeviceId, VirtioError, VirtioResult};
 use super::blk::{BlockRequestHeader, BlockRequestType, BlockStatus, SECTOR_SIZE, blk_features};
@@ -53,6 +75,56 @@ impl LegacyQueueLayout {
     }
 }
 
+/// Identifies a submitted block request
+pub type BlockRequestToken = u64;
+
+/// A block operation
+#[derive(Debug)]
+pub enum BlockOp {
+    /// Read `len` bytes (a multiple of the sector size) starting at `sector`
+    Read { sector: u64, len: usize },
+    /// Write `data` (a multiple of the sector size) starting at `sector`
+    Write { sector: u64, data: Vec<u8> },
+    /// Flush the device's write cache
+    Flush,
+}
+
+impl BlockOp {
+    /// Number of descriptors the request chain needs
+    fn descriptors(&self) -> u16 {
+        match self {
+            BlockOp::Flush => 2,
+            _ => 3,
+        }
+    }
+}
+
+/// A completed block request
+#[derive(Debug)]
+pub struct BlockCompletion {
+    /// Token returned by `submit`
+    pub token: BlockRequestToken,
+    /// Request status
+    pub result: VirtioResult<()>,
+    /// Data read (for reads) or the written buffer (for writes)
+    pub data: Vec<u8>,
+}
+
+/// How a device signals completions
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum IrqMode {
+    /// No interrupt; the used ring is checked on every poll
+    Polled = 0,
+    /// Legacy INTx through the IOAPIC
+    Legacy = 1,
+    /// MSI-X table entry 0
+    MsiX = 2,
+}
+
+/// Maximum spins for a blocking request
+const MAX_WAIT_ITERATIONS: u64 = 1_000_000_000;
+
 /// VirtIO Block Device (PCI)
 pub struct VirtioBlkPci {
     /// PCI transport
@@ -66,23 +138,29 @@ pub struct VirtioBlkPci {
     capacity: u64,
     /// Block size (usually 512)
     block_size: u32,
-    /// In-flight requests keyed by descriptor index
+    /// In-flight requests keyed by head descriptor index
     in_flight: BTreeMap<u16, InFlightRequest>,
-    /// Next request ID
-    next_request_id: u64,
+    /// Requests waiting for free descriptors
+    waiting: VecDeque<(BlockRequestToken, BlockOp)>,
+    /// Completed requests not yet taken
+    completed: BTreeMap<BlockRequestToken, BlockCompletion>,
+    /// Next request token
+    next_token: BlockRequestToken,
 }
 
 /// In-flight request tracking
 ///
-/// Fields are stored to keep allocations alive during DMA and for debugging.
+/// Owns every buffer the device may access until the request completes.
 #[allow(dead_code)]
 struct InFlightRequest {
+    /// Token returned by `submit`
+    token: BlockRequestToken,
     /// Request header
     header: Box<BlockRequestHeader>,
     /// Status byte
     status: Box<u8>,
-    /// Is this a read operation?
-    is_read: bool,
+    /// Data buffer (empty for flush)
+    data: Vec<u8>,
 }
 
 // SAFETY: VirtioBlkPci is designed for single-threaded access
@@ -93,8 +171,8 @@ impl VirtioBlkPci {
     ///
     /// # Safety
     /// The PCI device must be a valid VirtIO block device.
-    pub unsafe fn new(pci_device: PciDevice, queue_memory: u64) -> VirtioResult<Self> {
-        let transport = PciTransport::new(pci_device)?;
+    pub unsafe fn new(pci_device: PciDevice, disk: Disk, queue_memory: u64) -> VirtioResult<Self> {
+        let mut transport = PciTransport::new(pci_device)?;
         
         // Verify device type
         if transport.device_id() != DeviceId::Block {
@@ -113,6 +191,13 @@ impl VirtioBlkPci {
         crate::serial_println!("[virtio-blk-pci] Capacity: {} sectors ({} MB)",
             capacity, capacity * 512 / (1024 * 1024));
         
+        // MSI-X must be enabled before the queue vector can be assigned
+        let msix = pci_device.enable_msix(0, disk.vector(), apic::lapic_id() as u8);
+        transport.set_msix_enabled(msix);
+        if msix {
+            transport.set_config_msix_vector(NO_MSIX_VECTOR);
+        }
+        
         // Select queue 0 (request queue)
         transport.select_queue(0);
         let max_size = transport.queue_size();
@@ -133,9 +218,23 @@ impl VirtioBlkPci {
         
         crate::serial_println!("[virtio-blk-pci] Queue address PFN: {}", pfn);
         
+        let irq_mode = if msix && transport.set_queue_msix_vector(0) {
+            IrqMode::MsiX
+        } else if let Some(line) = pci_device.interrupt_line().filter(|_| !msix) {
+            driver_irq::reserve(line);
+            apic::ioapic_configure_level(line, disk.vector(), apic::lapic_id() as u8);
+            IrqMode::Legacy
+        } else {
+            IrqMode::Polled
+        };
+        crate::serial_println!("[virtio-blk-pci] Completion interrupts: {:?}", irq_mode);
+        
         // Complete initialization
         finalize_device(&transport);
         
+        *IRQ_TRANSPORT[disk as usize].lock() = Some(transport);
+        IRQ_MODE[disk as usize].store(irq_mode as u8, Ordering::Release);
+        
         crate::serial_println!("[virtio-blk-pci] Device initialized successfully");
         
         let queue_layout = LegacyQueueLayout {
@@ -150,7 +249,9 @@ impl VirtioBlkPci {
             capacity,
             block_size,
             in_flight: BTreeMap::new(),
-            next_request_id: 0,
+            waiting: VecDeque::new(),
+            completed: BTreeMap::new(),
+            next_token: 1,
         })
     }
     
@@ -174,138 +275,158 @@ impl VirtioBlkPci {
         self.queue.has_pending()
     }
     
-    /// Submit a read request (async)
-    pub fn read_async(&mut self, sector: u64, buffer: &mut [u8]) -> VirtioResult<u64> {
-        if sector >= self.capacity {
-            return Err(VirtioError::InvalidArgument);
-        }
-        if buffer.len() % SECTOR_SIZE != 0 || buffer.is_empty() {
-            return Err(VirtioError::InvalidArgument);
+    /// Number of requests submitted but not yet completed
+    pub fn outstanding(&self) -> usize {
+        self.in_flight.len() + self.waiting.len()
+    }
+    
+    /// Queue a request
+    ///
+    /// The request is handed to the device as soon as descriptors are free.
+    pub fn submit(&mut self, op: BlockOp) -> VirtioResult<BlockRequestToken> {
+        match &op {
+            BlockOp::Read { sector, len } => self.check_range(*sector, *len)?,
+            BlockOp::Write { sector, data } => self.check_range(*sector, data.len())?,
+            BlockOp::Flush => {}
         }
         
-        self.submit_request(BlockRequestType::In, sector, buffer.as_mut_ptr() as u64, buffer.len() as u32, true)
+        let token = self.next_token;
+        self.next_token += 1;
+        self.waiting.push_back((token, op));
+        self.dispatch();
+        Ok(token)
     }
     
-    /// Submit a write request (async)
-    pub fn write_async(&mut self, sector: u64, buffer: &[u8]) -> VirtioResult<u64> {
-        if sector >= self.capacity {
+    /// Validate a sector range
+    fn check_range(&self, sector: u64, len: usize) -> VirtioResult<()> {
+        if len == 0 || !len.is_multiple_of(SECTOR_SIZE) {
             return Err(VirtioError::InvalidArgument);
         }
-        if buffer.len() % SECTOR_SIZE != 0 || buffer.is_empty() {
+        let sectors = (len / SECTOR_SIZE) as u64;
+        if sector.checked_add(sectors).is_none_or(|end| end > self.capacity) {
             return Err(VirtioError::InvalidArgument);
         }
-        
-        self.submit_request(BlockRequestType::Out, sector, buffer.as_ptr() as u64, buffer.len() as u32, false)
-    }
-    
-    /// Submit a flush request (async)
-    pub fn flush_async(&mut self) -> VirtioResult<u64> {
-        self.submit_request(BlockRequestType::Flush, 0, 0, 0, false)
+        Ok(())
     }
     
-    /// Submit a block request
-    fn submit_request(
-        &mut self,
-        request_type: BlockRequestType,
-        sector: u64,
-        data_addr: u64,
-        data_len: u32,
-        is_read: bool,
-    ) -> VirtioResult<u64> {
-        // Allocate request header and status on heap
-        let header = Box::new(BlockRequestHeader::new(request_type, sector));
-        let status = Box::new(0xFFu8);
-        
-        let header_addr = header.as_ref() as *const _ as u64;
-        let status_addr = status.as_ref() as *const _ as u64;
+    /// Move waiting requests onto the virtqueue while descriptors are free
+    fn dispatch(&mut self) {
+        let mut notify = false;
         
-        // Build descriptor chain
-        let mut buffers: Vec<(u64, u32, bool)> = Vec::new();
-        
-        // Header: device-readable
-        buffers.push((header_addr, BlockRequestHeader::SIZE as u32, false));
-        
-        // Data buffer (if any)
-        if data_len > 0 {
-            buffers.push((data_addr, data_len, is_read));
+        while let Some((_, op)) = self.waiting.front() {
+            if self.queue.num_free() < op.descriptors() {
+                break;
+            }
+            let (token, op) = match self.waiting.pop_front() {
+                Some(request) => request,
+                None => break,
+            };
+            
+            let (request_type, sector, mut data, is_read) = match op {
+                BlockOp::Read { sector, len } => (BlockRequestType::In, sector, vec![0u8; len], true),
+                BlockOp::Write { sector, data } => (BlockRequestType::Out, sector, data, false),
+                BlockOp::Flush => (BlockRequestType::Flush, 0, Vec::new(), false),
+            };
+            
+            // Allocate request header and status on heap
+            let header = Box::new(BlockRequestHeader::new(request_type, sector));
+            let status = Box::new(0xFFu8);
+            
+            // Build descriptor chain: header (device-readable), data, status (device-writable)
+            let mut buffers: Vec<(u64, u32, bool)> = Vec::with_capacity(3);
+            buffers.push((header.as_ref() as *const _ as u64, BlockRequestHeader::SIZE as u32, false));
+            if !data.is_empty() {
+                buffers.push((data.as_mut_ptr() as u64, data.len() as u32, is_read));
+            }
+            buffers.push((status.as_ref() as *const _ as u64, 1, true));
+            
+            match self.queue.add_buffer_chain(&buffers) {
+                Ok(desc_idx) => {
+                    self.in_flight.insert(desc_idx, InFlightRequest { token, header, status, data });
+                    notify = true;
+                }
+                Err(e) => {
+                    self.completed.insert(token, BlockCompletion { token, result: Err(e), data });
+                }
+            }
         }
         
-        // Status: device-writable
-        buffers.push((status_addr, 1, true));
-        
-        // Add to queue
-        let desc_idx = self.queue.add_buffer_chain(&buffers)?;
-        
-        // Track request
-        let request_id = self.next_request_id;
-        self.next_request_id += 1;
-        
-        self.in_flight.insert(desc_idx, InFlightRequest {
-            header,
-            status,
-            is_read,
-        });
-        
-        // Notify device
-        self.transport.notify_queue(0);
-        
-        Ok(request_id)
+        if notify {
+            self.transport.notify_queue(0);
+        }
     }
     
-    /// Poll for completed requests
-    pub fn poll_completions(&mut self) -> Vec<(u16, BlockStatus)> {
-        let mut completions = Vec::new();
-        
+    /// Drain the used ring into the completed set and refill the virtqueue
+    fn process_used(&mut self) {
         while let Some((desc_idx, _bytes)) = self.queue.pop_used() {
             if let Some(request) = self.in_flight.remove(&desc_idx) {
-                let status = BlockStatus::from(*request.status);
-                completions.push((desc_idx, status));
+                let result = match BlockStatus::from(*request.status) {
+                    BlockStatus::Ok => Ok(()),
+                    BlockStatus::IoErr => Err(VirtioError::IoError),
+                    BlockStatus::Unsupported => Err(VirtioError::InvalidArgument),
+                };
+                self.completed.insert(request.token, BlockCompletion {
+                    token: request.token,
+                    result,
+                    data: request.data,
+                });
             }
         }
-        
-        completions
+        self.dispatch();
     }
     
-    /// Blocking read
-    pub fn read(&mut self, sector: u64, buffer: &mut [u8]) -> VirtioResult<()> {
-        self.read_async(sector, buffer)?;
-        self.wait_for_completion()
-    }
-    
-    /// Blocking write
-    pub fn write(&mut self, sector: u64, buffer: &[u8]) -> VirtioResult<()> {
-        self.write_async(sector, buffer)?;
-        self.wait_for_completion()
-    }
-    
-    /// Blocking flush
-    pub fn flush(&mut self) -> VirtioResult<()> {
-        self.flush_async()?;
-        self.wait_for_completion()
+    /// Take all completed requests
+    ///
+    /// With interrupts configured, the used ring is only read after the
+    /// device has signalled (or when `force` is set).
+    pub fn take_completions(&mut self, disk: Disk, force: bool) -> Vec<BlockCompletion> {
+        let signalled = IRQ_PENDING[disk as usize].swap(false, Ordering::AcqRel);
+        if force || signalled || irq_mode(disk) == IrqMode::Polled {
+            self.process_used();
+        }
+        core::mem::take(&mut self.completed).into_values().collect()
     }
     
-    /// Wait for completion
-    fn wait_for_completion(&mut self) -> VirtioResult<()> {
+    /// Spin until one request completes
+    ///
+    /// Other completions stay queued for `take_completions`.
+    pub fn wait(&mut self, token: BlockRequestToken) -> VirtioResult<BlockCompletion> {
         let mut iterations = 0u64;
         loop {
-            let completions = self.poll_completions();
-            if !completions.is_empty() {
-                let (_, status) = completions[0];
-                return match status {
-                    BlockStatus::Ok => Ok(()),
-                    BlockStatus::IoErr => Err(VirtioError::IoError),
-                    BlockStatus::Unsupported => Err(VirtioError::InvalidArgument),
-                };
+            self.process_used();
+            if let Some(completion) = self.completed.remove(&token) {
+                return Ok(completion);
             }
             
             iterations += 1;
-            if iterations > 1_000_000_000 {
+            if iterations > MAX_WAIT_ITERATIONS {
                 return Err(VirtioError::Timeout);
             }
             
             core::hint::spin_loop();
         }
     }
+    
+    /// Blocking read
+    pub fn read(&mut self, sector: u64, buffer: &mut [u8]) -> VirtioResult<()> {
+        let token = self.submit(BlockOp::Read { sector, len: buffer.len() })?;
+        let completion = self.wait(token)?;
+        completion.result?;
+        buffer.copy_from_slice(&completion.data);
+        Ok(())
+    }
+    
+    /// Blocking write
+    pub fn write(&mut self, sector: u64, buffer: &[u8]) -> VirtioResult<()> {
+        let token = self.submit(BlockOp::Write { sector, data: buffer.to_vec() })?;
+        self.wait(token)?.result
+    }
+    
+    /// Blocking flush
+    pub fn flush(&mut self) -> VirtioResult<()> {
+        let token = self.submit(BlockOp::Flush)?;
+        self.wait(token)?.result
+    }
 }
 
 impl Drop for VirtioBlkPci {
@@ -314,62 +435,143 @@ impl Drop for VirtioBlkPci {
     }
 }
 
-/// Global VirtIO block device (PCI) instance
-static VIRTIO_BLK_PCI: Mutex<Option<VirtioBlkPci>> = Mutex::new(None);
+/// Which virtio-blk device a request is for.
+///
+/// Devices are assigned in PCI enumeration order, which for QEMU follows
+/// the order of the `-drive if=virtio` arguments.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Disk {
+    /// First virtio-blk device: key-value storage for VFS and the CommitLog
+    Data = 0,
+    /// Second virtio-blk device: the keystore, kept on its own device so key
+    /// material never shares a disk with VFS data (Invariant 32)
+    Keystore = 1,
+}
 
-/// Initialize the global VirtIO block device from PCI
+impl Disk {
+    /// Interrupt vector for this disk's completions
+    pub fn vector(self) -> u8 {
+        match self {
+            Disk::Data => InterruptIndex::BlockData.as_u8(),
+            Disk::Keystore => InterruptIndex::BlockKeystore.as_u8(),
+        }
+    }
+}
+
+/// Global VirtIO block device (PCI) instances, indexed by [`Disk`]
+static VIRTIO_BLK_PCI: [Mutex<Option<VirtioBlkPci>>; 2] = [Mutex::new(None), Mutex::new(None)];
+
+/// Completion signalling mode per disk (an [`IrqMode`])
+static IRQ_MODE: [AtomicU8; 2] = [AtomicU8::new(IrqMode::Polled as u8), AtomicU8::new(IrqMode::Polled as u8)];
+
+/// Set by the interrupt handler when a disk has completions
+static IRQ_PENDING: [AtomicBool; 2] = [AtomicBool::new(false), AtomicBool::new(false)];
+
+/// Copy of each disk's transport for the interrupt handler
+///
+/// Kept apart from the device lock so the handler never waits on code it
+/// interrupted.
+static IRQ_TRANSPORT: [Mutex<Option<PciTransport>>; 2] = [Mutex::new(None), Mutex::new(None)];
+
+/// Get a disk's completion signalling mode
+pub fn irq_mode(disk: Disk) -> IrqMode {
+    match IRQ_MODE[disk as usize].load(Ordering::Acquire) {
+        1 => IrqMode::Legacy,
+        2 => IrqMode::MsiX,
+        _ => IrqMode::Polled,
+    }
+}
+
+/// Handle a completion interrupt (called from the IDT handler)
+///
+/// Reading the ISR status deasserts a level-triggered INTx line. MSI-X
+/// messages need no acknowledgement at the device.
+pub fn handle_interrupt(disk: Disk) {
+    if irq_mode(disk) == IrqMode::Legacy {
+        if let Some(transport) = IRQ_TRANSPORT[disk as usize].try_lock().and_then(|t| *t) {
+            // Bit 0: queue interrupt; a shared line may have fired for another device
+            if transport.isr_status() & 1 == 0 {
+                return;
+            }
+        }
+    }
+    IRQ_PENDING[disk as usize].store(true, Ordering::Release);
+}
+
+/// Initialize one of the global VirtIO block devices from PCI
 ///
 /// # Safety
 /// Must be called after PCI and VMM initialization.
-pub unsafe fn init_from_pci(queue_memory: u64) -> VirtioResult<()> {
-    // Find VirtIO block device on PCI bus
-    let pci_device = pci::find_virtio_block()
+pub unsafe fn init_from_pci(disk: Disk, queue_memory: u64) -> VirtioResult<()> {
+    // Find the matching VirtIO block device on the PCI bus
+    let pci_device = pci::enumerate_devices()
+        .filter(|d| d.is_virtio() && d.device_id == pci::virtio_device::BLOCK)
+        .nth(disk as usize)
         .ok_or(VirtioError::DeviceNotFound)?;
     
-    crate::serial_println!("[virtio-blk-pci] Found device at {:02x}:{:02x}.{}",
-        pci_device.addr.bus, pci_device.addr.device, pci_device.addr.function);
+    crate::serial_println!("[virtio-blk-pci] Found {:?} device at {:02x}:{:02x}.{}",
+        disk, pci_device.addr.bus, pci_device.addr.device, pci_device.addr.function);
     
-    let device = VirtioBlkPci::new(pci_device, queue_memory)?;
+    let device = VirtioBlkPci::new(pci_device, disk, queue_memory)?;
     
-    let mut guard = VIRTIO_BLK_PCI.lock();
+    let mut guard = VIRTIO_BLK_PCI[disk as usize].lock();
     *guard = Some(device);
     
     Ok(())
 }
 
-/// Check if the VirtIO block device (PCI) is initialized
-pub fn is_initialized() -> bool {
-    VIRTIO_BLK_PCI.lock().is_some()
+/// Check if a VirtIO block device (PCI) is initialized
+pub fn is_initialized(disk: Disk) -> bool {
+    VIRTIO_BLK_PCI[disk as usize].lock().is_some()
 }
 
-/// Read sectors from the global device
-pub fn read_sectors(sector: u64, buffer: &mut [u8]) -> VirtioResult<()> {
-    let mut guard = VIRTIO_BLK_PCI.lock();
+/// Read sectors from a global device
+pub fn read_sectors(disk: Disk, sector: u64, buffer: &mut [u8]) -> VirtioResult<()> {
+    let mut guard = VIRTIO_BLK_PCI[disk as usize].lock();
     match guard.as_mut() {
         Some(device) => device.read(sector, buffer),
         None => Err(VirtioError::DeviceNotFound),
     }
 }
 
-/// Write sectors to the global device
-pub fn write_sectors(sector: u64, buffer: &[u8]) -> VirtioResult<()> {
-    let mut guard = VIRTIO_BLK_PCI.lock();
+/// Write sectors to a global device
+pub fn write_sectors(disk: Disk, sector: u64, buffer: &[u8]) -> VirtioResult<()> {
+    let mut guard = VIRTIO_BLK_PCI[disk as usize].lock();
     match guard.as_mut() {
         Some(device) => device.write(sector, buffer),
         None => Err(VirtioError::DeviceNotFound),
     }
 }
 
-/// Flush the global device
-pub fn flush_device() -> VirtioResult<()> {
-    let mut guard = VIRTIO_BLK_PCI.lock();
+/// Flush a global device
+pub fn flush_device(disk: Disk) -> VirtioResult<()> {
+    let mut guard = VIRTIO_BLK_PCI[disk as usize].lock();
     match guard.as_mut() {
         Some(device) => device.flush(),
         None => Err(VirtioError::DeviceNotFound),
     }
 }
 
-/// Get device capacity in bytes
-pub fn capacity_bytes() -> Option<u64> {
-    VIRTIO_BLK_PCI.lock().as_ref().map(|d| d.capacity_bytes())
+/// Submit a request to a global device
+pub fn submit(disk: Disk, op: BlockOp) -> VirtioResult<BlockRequestToken> {
+    let mut guard = VIRTIO_BLK_PCI[disk as usize].lock();
+    match guard.as_mut() {
+        Some(device) => device.submit(op),
+        None => Err(VirtioError::DeviceNotFound),
+    }
+}
+
+/// Take completed requests from a global device
+///
+/// `force` reads the used ring even if no interrupt has arrived.
+pub fn take_completions(disk: Disk, force: bool) -> Vec<BlockCompletion> {
+    match VIRTIO_BLK_PCI[disk as usize].lock().as_mut() {
+        Some(device) => device.take_completions(disk, force),
+        None => Vec::new(),
+    }
+}
+
+/// Get a device's capacity in bytes
+pub fn capacity_bytes(disk: Disk) -> Option<u64> {
+    VIRTIO_BLK_PCI[disk as usize].lock().as_ref().map(|d| d.capacity_bytes())
 }
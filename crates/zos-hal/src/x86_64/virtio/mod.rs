@@ -8,6 +8,7 @@
 //! - **transport**: MMIO transport for device discovery and access
 //! - **queue**: Virtqueue implementation (split virtqueue)
 //! - **blk**: VirtIO block device driver
+//! - **net_pci**: VirtIO network device driver (PCI)
 //!
 //! # References
 //!
@@ -18,6 +19,7 @@ pub mod transport;
 pub mod pci;
 pub mod blk;
 pub mod blk_pci;
+pub mod net_pci;
 
 use core::fmt;
 
@@ -197,6 +199,13 @@ pub fn init() {
         crate::serial_println!("[virtio] No VirtIO block device found");
     }
     
+    // Check for VirtIO network device
+    if crate::x86_64::pci::enumerate_devices()
+        .any(|d| d.is_virtio() && d.device_id == crate::x86_64::pci::virtio_device::NET)
+    {
+        crate::serial_println!("[virtio] Found VirtIO network device");
+    }
+    
     crate::serial_println!("[virtio] VirtIO initialization complete");
 }
 
@@ -205,5 +214,23 @@ pub fn init() {
 /// # Safety
 /// Must be called after VMM initialization with valid queue memory.
 pub unsafe fn init_block_device(queue_memory: u64) -> VirtioResult<()> {
-    blk_pci::init_from_pci(queue_memory)
+    blk_pci::init_from_pci(blk_pci::Disk::Data, queue_memory)
+}
+
+/// Initialize the keystore's VirtIO block device (the second one on the
+/// PCI bus) with provided queue memory
+///
+/// # Safety
+/// Must be called after VMM initialization with valid queue memory.
+pub unsafe fn init_keystore_device(queue_memory: u64) -> VirtioResult<()> {
+    blk_pci::init_from_pci(blk_pci::Disk::Keystore, queue_memory)
+}
+
+/// Initialize the VirtIO network device with provided queue memory
+/// (one region each for the receive and transmit queues)
+///
+/// # Safety
+/// Must be called after VMM initialization with valid queue memory.
+pub unsafe fn init_network_device(rx_queue_memory: u64, tx_queue_memory: u64) -> VirtioResult<()> {
+    net_pci::init_from_pci(rx_queue_memory, tx_queue_memory)
 }
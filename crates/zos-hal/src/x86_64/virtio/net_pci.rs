@@ -0,0 +1,269 @@
+//! VirtIO Network Device Driver (PCI Transport)
+//!
+//! Implements a polled driver for legacy VirtIO network devices using the PCI
+//! transport. Frames are raw Ethernet; the TCP/IP stack in
+//! `crate::x86_64::net` sits on top.
+//!
+//! # Queues
+//!
+//! - Queue 0 (receiveq): pre-posted device-writable buffers
+//! - Queue 1 (transmitq): one device-readable buffer per frame
+//!
+//! Every buffer starts with a 10-byte `virtio_net_hdr` (no mergeable RX
+//! buffers, no offloads are negotiated).
+
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::vec;
+use alloc::vec::Vec;
+use spin::Mutex;
+
+use crate::x86_64::pci::{self, PciDevice};
+use super::pci::{PciTransport, init_device, finalize_device};
+use super::queue::Virtqueue;
+use super::{DeviceId, VirtioError, VirtioResult};
+
+/// Page size for legacy queue address
+const PAGE_SIZE: u64 = 4096;
+
+/// Default queue size
+const DEFAULT_QUEUE_SIZE: u16 = 128;
+
+/// Receive queue index
+const RX_QUEUE: u16 = 0;
+
+/// Transmit queue index
+const TX_QUEUE: u16 = 1;
+
+/// Size of the legacy virtio_net_hdr (without num_buffers)
+const NET_HDR_SIZE: usize = 10;
+
+/// Largest Ethernet frame we send or receive (without FCS)
+pub const MAX_FRAME_SIZE: usize = 1514;
+
+/// Size of each receive buffer
+const RX_BUFFER_SIZE: usize = NET_HDR_SIZE + MAX_FRAME_SIZE;
+
+/// Receive buffers kept posted to the device
+const RX_BUFFER_COUNT: usize = 32;
+
+/// Frames received but not yet taken by the stack
+const MAX_QUEUED_FRAMES: usize = 64;
+
+/// VirtIO network feature bits
+pub mod net_features {
+    /// Device has a MAC address in config space
+    pub const MAC: u32 = 1 << 5;
+    /// Device reports link status
+    pub const STATUS: u32 = 1 << 16;
+}
+
+/// VirtIO Network Device (PCI)
+pub struct VirtioNetPci {
+    /// PCI transport
+    transport: PciTransport,
+    /// Receive queue
+    rx_queue: Virtqueue,
+    /// Transmit queue
+    tx_queue: Virtqueue,
+    /// MAC address
+    mac: [u8; 6],
+    /// Posted receive buffers keyed by descriptor index
+    rx_buffers: BTreeMap<u16, Vec<u8>>,
+    /// In-flight transmit buffers keyed by descriptor index (kept alive during DMA)
+    tx_in_flight: BTreeMap<u16, Vec<u8>>,
+    /// Received frames (without virtio header)
+    rx_frames: VecDeque<Vec<u8>>,
+}
+
+// SAFETY: VirtioNetPci is designed for single-threaded access
+unsafe impl Send for VirtioNetPci {}
+
+impl VirtioNetPci {
+    /// Initialize a VirtIO network device from a PCI device
+    ///
+    /// # Safety
+    /// The PCI device must be a valid VirtIO network device, and each queue
+    /// memory region must be large enough for a queue of `DEFAULT_QUEUE_SIZE`.
+    pub unsafe fn new(pci_device: PciDevice, rx_queue_memory: u64, tx_queue_memory: u64) -> VirtioResult<Self> {
+        let transport = PciTransport::new(pci_device)?;
+
+        // Verify device type
+        if transport.device_id() != DeviceId::Network {
+            return Err(VirtioError::DeviceNotFound);
+        }
+
+        init_device(&transport, net_features::MAC)?;
+
+        // Read MAC address from device config (offset 0)
+        let mut mac = [0u8; 6];
+        for (i, byte) in mac.iter_mut().enumerate() {
+            *byte = transport.read_config_u8(i as u16);
+        }
+        crate::serial_println!("[virtio-net-pci] MAC: {:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}",
+            mac[0], mac[1], mac[2], mac[3], mac[4], mac[5]);
+
+        let rx_queue = Self::setup_queue(&transport, RX_QUEUE, rx_queue_memory)?;
+        let tx_queue = Self::setup_queue(&transport, TX_QUEUE, tx_queue_memory)?;
+
+        finalize_device(&transport);
+
+        let mut device = Self {
+            transport,
+            rx_queue,
+            tx_queue,
+            mac,
+            rx_buffers: BTreeMap::new(),
+            tx_in_flight: BTreeMap::new(),
+            rx_frames: VecDeque::new(),
+        };
+
+        // Give the device somewhere to put incoming frames
+        for _ in 0..RX_BUFFER_COUNT {
+            device.post_rx_buffer(vec![0u8; RX_BUFFER_SIZE])?;
+        }
+        device.transport.notify_queue(RX_QUEUE);
+
+        crate::serial_println!("[virtio-net-pci] Device initialized successfully");
+
+        Ok(device)
+    }
+
+    /// Select and configure one virtqueue
+    unsafe fn setup_queue(transport: &PciTransport, index: u16, memory: u64) -> VirtioResult<Virtqueue> {
+        transport.select_queue(index);
+        let max_size = transport.queue_size();
+        if max_size == 0 {
+            return Err(VirtioError::QueueNotAvailable);
+        }
+
+        let queue_size = max_size.min(DEFAULT_QUEUE_SIZE);
+        let queue = Virtqueue::new(index, queue_size, memory)?;
+        transport.set_queue_address((memory / PAGE_SIZE) as u32);
+
+        crate::serial_println!("[virtio-net-pci] Queue {} size: {} (max: {})", index, queue_size, max_size);
+
+        Ok(queue)
+    }
+
+    /// Get the MAC address
+    pub fn mac_address(&self) -> [u8; 6] {
+        self.mac
+    }
+
+    /// Post a buffer to the receive queue
+    fn post_rx_buffer(&mut self, mut buffer: Vec<u8>) -> VirtioResult<()> {
+        let desc_idx = self.rx_queue.add_buffer(buffer.as_mut_ptr() as u64, buffer.len() as u32, true)?;
+        self.rx_buffers.insert(desc_idx, buffer);
+        Ok(())
+    }
+
+    /// Collect completed receive and transmit buffers
+    pub fn poll(&mut self) {
+        // Reclaim transmitted buffers
+        while let Some((desc_idx, _)) = self.tx_queue.pop_used() {
+            self.tx_in_flight.remove(&desc_idx);
+        }
+
+        // Take received frames and re-post their buffers
+        let mut reposted = false;
+        while let Some((desc_idx, len)) = self.rx_queue.pop_used() {
+            let Some(buffer) = self.rx_buffers.remove(&desc_idx) else {
+                continue;
+            };
+            let len = (len as usize).min(buffer.len());
+            if len > NET_HDR_SIZE && self.rx_frames.len() < MAX_QUEUED_FRAMES {
+                self.rx_frames.push_back(buffer[NET_HDR_SIZE..len].to_vec());
+            }
+            if self.post_rx_buffer(buffer).is_ok() {
+                reposted = true;
+            }
+        }
+        if reposted {
+            self.transport.notify_queue(RX_QUEUE);
+        }
+    }
+
+    /// Take the next received frame
+    pub fn receive(&mut self) -> Option<Vec<u8>> {
+        self.poll();
+        self.rx_frames.pop_front()
+    }
+
+    /// Check whether a frame can be queued for transmit
+    pub fn can_transmit(&self) -> bool {
+        self.tx_queue.num_free() > 0
+    }
+
+    /// Queue an Ethernet frame for transmit
+    pub fn transmit(&mut self, frame: &[u8]) -> VirtioResult<()> {
+        if frame.len() > MAX_FRAME_SIZE {
+            return Err(VirtioError::BufferTooSmall);
+        }
+        self.poll();
+
+        // Zeroed header: no checksum offload, no GSO
+        let mut buffer = vec![0u8; NET_HDR_SIZE + frame.len()];
+        buffer[NET_HDR_SIZE..].copy_from_slice(frame);
+
+        let desc_idx = self.tx_queue.add_buffer(buffer.as_ptr() as u64, buffer.len() as u32, false)?;
+        self.tx_in_flight.insert(desc_idx, buffer);
+        self.transport.notify_queue(TX_QUEUE);
+        Ok(())
+    }
+}
+
+impl Drop for VirtioNetPci {
+    fn drop(&mut self) {
+        self.transport.reset();
+    }
+}
+
+/// Global VirtIO network device (PCI) instance
+static VIRTIO_NET_PCI: Mutex<Option<VirtioNetPci>> = Mutex::new(None);
+
+/// Initialize the global VirtIO network device from PCI
+///
+/// # Safety
+/// Must be called after PCI and VMM initialization.
+pub unsafe fn init_from_pci(rx_queue_memory: u64, tx_queue_memory: u64) -> VirtioResult<()> {
+    let pci_device = pci::enumerate_devices()
+        .find(|d| d.is_virtio() && d.device_id == pci::virtio_device::NET)
+        .ok_or(VirtioError::DeviceNotFound)?;
+
+    crate::serial_println!("[virtio-net-pci] Found device at {:02x}:{:02x}.{}",
+        pci_device.addr.bus, pci_device.addr.device, pci_device.addr.function);
+
+    let device = VirtioNetPci::new(pci_device, rx_queue_memory, tx_queue_memory)?;
+    *VIRTIO_NET_PCI.lock() = Some(device);
+
+    Ok(())
+}
+
+/// Check if the VirtIO network device (PCI) is initialized
+pub fn is_initialized() -> bool {
+    VIRTIO_NET_PCI.lock().is_some()
+}
+
+/// Get the device's MAC address
+pub fn mac_address() -> Option<[u8; 6]> {
+    VIRTIO_NET_PCI.lock().as_ref().map(|d| d.mac_address())
+}
+
+/// Take the next received frame from the global device
+pub fn receive() -> Option<Vec<u8>> {
+    VIRTIO_NET_PCI.lock().as_mut().and_then(|d| d.receive())
+}
+
+/// Check whether the global device can accept a frame for transmit
+pub fn can_transmit() -> bool {
+    VIRTIO_NET_PCI.lock().as_ref().is_some_and(|d| d.can_transmit())
+}
+
+/// Transmit a frame on the global device
+pub fn transmit(frame: &[u8]) -> VirtioResult<()> {
+    let mut guard = VIRTIO_NET_PCI.lock();
+    match guard.as_mut() {
+        Some(device) => device.transmit(frame),
+        None => Err(VirtioError::DeviceNotFound),
+    }
+}
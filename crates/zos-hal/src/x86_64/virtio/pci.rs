@@ -19,6 +19,15 @@
 //! | 0x12   | 1    | Device status |
 //! | 0x13   | 1    | ISR status |
 //! | 0x14+  | var  | Device config |
+//!
+//! With MSI-X enabled, two vector registers are inserted before the device
+//! config, which moves to 0x18:
+//!
+//! | Offset | Size | Name |
+//! |--------|------|------|
+//! | 0x14   | 2    | Config change MSI-X vector |
+//! | 0x16   | 2    | Queue MSI-X vector (selected queue) |
+//! | 0x18+  | var  | Device config |
 
 use core::ptr::{read_volatile, write_volatile};
 use x86_64::instructions::port::Port;
@@ -37,8 +46,14 @@ mod legacy_regs {
     pub const DEVICE_STATUS: u16 = 0x12;
     pub const ISR_STATUS: u16 = 0x13;
     pub const CONFIG: u16 = 0x14;
+    pub const CONFIG_MSIX_VECTOR: u16 = 0x14;
+    pub const QUEUE_MSIX_VECTOR: u16 = 0x16;
+    pub const CONFIG_WITH_MSIX: u16 = 0x18;
 }
 
+/// MSI-X vector value meaning "no vector"
+pub const NO_MSIX_VECTOR: u16 = 0xFFFF;
+
 /// VirtIO PCI Transport (Legacy Mode)
 ///
 /// Provides access to a VirtIO device via PCI legacy (transitional) interface.
@@ -52,6 +67,8 @@ pub struct PciTransport {
     is_io_port: bool,
     /// MMIO base address (if memory-mapped)
     mmio_base: u64,
+    /// Whether MSI-X is enabled (moves the device config)
+    msix_enabled: bool,
 }
 
 impl PciTransport {
@@ -81,6 +98,7 @@ impl PciTransport {
             io_base,
             is_io_port,
             mmio_base,
+            msix_enabled: false,
         })
     }
 
@@ -153,14 +171,44 @@ impl PciTransport {
         self.read_u8(legacy_regs::ISR_STATUS)
     }
 
+    /// Record that MSI-X was enabled on the PCI function
+    ///
+    /// Must be called right after enabling MSI-X in PCI config space, since
+    /// it changes where the device config lives.
+    pub fn set_msix_enabled(&mut self, enabled: bool) {
+        self.msix_enabled = enabled;
+    }
+
+    /// Set the MSI-X vector for configuration change interrupts
+    pub fn set_config_msix_vector(&self, vector: u16) {
+        self.write_u16(legacy_regs::CONFIG_MSIX_VECTOR, vector);
+    }
+
+    /// Set the MSI-X vector for the selected queue
+    ///
+    /// Returns false if the device rejected the vector.
+    pub fn set_queue_msix_vector(&self, vector: u16) -> bool {
+        self.write_u16(legacy_regs::QUEUE_MSIX_VECTOR, vector);
+        self.read_u16(legacy_regs::QUEUE_MSIX_VECTOR) == vector
+    }
+
+    /// Offset of the device config for the current MSI-X state
+    fn config_base(&self) -> u16 {
+        if self.msix_enabled {
+            legacy_regs::CONFIG_WITH_MSIX
+        } else {
+            legacy_regs::CONFIG
+        }
+    }
+
     /// Read a config byte
     pub fn read_config_u8(&self, offset: u16) -> u8 {
-        self.read_u8(legacy_regs::CONFIG + offset)
+        self.read_u8(self.config_base() + offset)
     }
 
     /// Read a config u32
     pub fn read_config_u32(&self, offset: u16) -> u32 {
-        self.read_u32(legacy_regs::CONFIG + offset)
+        self.read_u32(self.config_base() + offset)
     }
 
     /// Read a config u64
@@ -238,6 +286,7 @@ impl core::fmt::Debug for PciTransport {
             .field("device_id", &self.device_id())
             .field("io_base", &format_args!("0x{:x}", self.io_base))
             .field("is_io_port", &self.is_io_port)
+            .field("msix_enabled", &self.msix_enabled)
             .field("status", &self.status())
             .finish()
     }
@@ -6,6 +6,7 @@
 use alloc::vec::Vec;
 use wasmi::{Caller, Linker};
 
+use super::preempt::{fuel_needed, SYS_PREEMPT};
 use super::serial;
 
 /// Host state for a WASM process
@@ -29,8 +30,11 @@ pub struct HostState {
     /// Pending syscall to dispatch
     pub pending_syscall: Option<PendingSyscallInfo>,
     /// True if the last trap was from zos_yield() which returns () not i32
-    /// This affects how we resume - yield needs empty return, syscall needs i32
+    /// This affects how we resume - yield needs empty return, syscall needs i64
     pub trapped_from_yield: bool,
+    /// Fuel the module's preemption checks keep in reserve (0 if the
+    /// module is not instrumented)
+    pub preempt_reserve: u64,
 }
 
 /// Information about a pending syscall
@@ -54,6 +58,7 @@ impl HostState {
             yielded: false,
             pending_syscall: None,
             trapped_from_yield: false,
+            preempt_reserve: 0,
         }
     }
     
@@ -93,6 +98,13 @@ pub fn register_host_functions(linker: &mut Linker<HostState>) {
     // Returns i64 to support 64-bit return values (e.g., packed slot|endpoint_id)
     // Returns Result to allow triggering resumable pauses for syscalls that need kernel processing
     linker.func_wrap("env", "zos_syscall", |mut caller: Caller<'_, HostState>, syscall_num: u32, arg1: u32, arg2: u32, arg3: u32| -> Result<i64, wasmi::Error> {
+        // Preemption checks keep running while the timeslice has fuel to spare
+        if syscall_num == SYS_PREEMPT
+            && caller.get_fuel().unwrap_or(0) >= fuel_needed(caller.data().preempt_reserve, arg2, arg1)
+        {
+            return Ok(0);
+        }
+        
         let host = caller.data_mut();
         let pid = host.pid;
         
@@ -128,10 +140,10 @@ pub fn register_host_functions(linker: &mut Linker<HostState>) {
                 }
             }
             
-            SYS_YIELD => {
+            SYS_YIELD | SYS_PREEMPT => {
                 // Mark as yielded and trigger a resumable pause
                 host.yielded = true;
-                host.trapped_from_yield = false; // zos_syscall returns i32, not ()
+                host.trapped_from_yield = false; // zos_syscall returns i64, not ()
                 // Return an error to trigger a resumable pause from the host
                 // This allows wasmi to return Resumable instead of just continuing execution
                 return Err(wasmi::Error::from(wasmi::core::TrapCode::OutOfFuel));
@@ -169,7 +181,7 @@ pub fn register_host_functions(linker: &mut Linker<HostState>) {
         
         // Mark that we need to wait for a syscall result
         host.waiting_for_syscall = true;
-        host.trapped_from_yield = false; // zos_syscall returns i32, not ()
+        host.trapped_from_yield = false; // zos_syscall returns i64, not ()
         
         // Trigger a resumable pause by returning an error from the host function
         // This allows wasmi to return Resumable (host trap) instead of Err (wasm trap)
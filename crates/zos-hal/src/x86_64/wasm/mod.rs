@@ -25,24 +25,53 @@
 //! 2. The process's `_start` function is called
 //! 3. Process makes syscalls via host functions
 //! 4. `kill_process()` terminates the instance
+//!
+//! ## Scheduling
+//!
+//! Each call to the scheduler is one tick. Within a tick the runtime keeps
+//! picking the highest-priority ready process that has not run yet and
+//! still has budget left in its [`SchedContext`]. Fuel burned in a
+//! timeslice is charged to the budget, so a CPU-bound background process
+//! runs at most once per period while system services run first every tick.
+//!
+//! ## Multiple CPUs
+//!
+//! Each process is assigned to one CPU's run queue when it is spawned (the
+//! least loaded one). The boot CPU runs its queue through
+//! `run_all_processes*`; application processors call [`WasmRuntime::run_cpu`]
+//! and leave kernel syscalls in a shared queue. The boot CPU's scheduler
+//! hands those to its syscall handler first, so the kernel still sees one
+//! syscall at a time.
+//!
+//! ## Preemption
+//!
+//! Modules are instrumented at spawn (see [`preempt`]) so that code which
+//! never makes a syscall still checks in with the host periodically. Once
+//! the timeslice's fuel runs low the process is paused like a yield and
+//! resumed on its next timeslice instead of being killed.
 
 /// Enable verbose scheduler debug logging (set to false for clean output)
 const DEBUG_SCHEDULER: bool = false;
 
 pub mod host;
+pub mod preempt;
 pub mod process;
 
 use alloc::collections::BTreeMap;
 use alloc::string::String;
+use alloc::sync::Arc;
 use alloc::vec::Vec;
+use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use spin::Mutex;
 use wasmi::{Engine, Linker, Module, Store};
 
 use super::serial;
+use super::smp::{BOOT_CPU, MAX_CPUS};
 use crate::{HalError, NumericProcessHandle};
 
 pub use host::HostState;
-pub use process::{WasmProcess, ProcessState};
+pub use process::{WasmProcess, ProcessState, SchedContext};
+pub use crate::Priority;
 
 /// Maximum syscall data buffer size (matches WASM HAL)
 pub const MAX_SYSCALL_BUFFER: usize = 16384;
@@ -54,7 +83,9 @@ pub const MAX_SYSCALL_BUFFER: usize = 16384;
 /// - Loop iterations before the next syscall/yield
 /// 
 /// Note: Fuel exhaustion during WASM execution (not in a host function) returns
-/// an unresumable Err. So we need enough fuel to reach the next syscall or yield.
+/// an unresumable Err. Instrumented modules pause themselves before that
+/// happens; only modules without preemption points still depend on reaching
+/// the next syscall or yield in time.
 const FUEL_PER_TIMESLICE: u64 = 100_000_000;
 
 /// WASM runtime manager
@@ -65,12 +96,20 @@ pub struct WasmRuntime {
     engine: Engine,
     /// Linker with host functions
     linker: Linker<HostState>,
-    /// Active processes: pid -> WasmProcess
-    processes: Mutex<BTreeMap<u64, WasmProcess>>,
-    /// Pending syscalls from processes: pid -> (syscall_num, args, data)
+    /// Active processes: pid -> WasmProcess, each locked separately so
+    /// that different CPUs can run different processes at the same time
+    processes: Mutex<BTreeMap<u64, Arc<Mutex<WasmProcess>>>>,
+    /// Syscalls made on application processors, waiting for the boot CPU
     pending_syscalls: Mutex<Vec<PendingSyscall>>,
     /// Pending IPC messages to deliver to processes: pid -> messages
     pending_messages: Mutex<BTreeMap<u64, Vec<Vec<u8>>>>,
+    /// Scheduler ticks so far (one per scheduler run)
+    tick: AtomicU64,
+    /// Number of CPUs with a run queue
+    cpu_count: AtomicUsize,
+    /// Live processes per CPU run queue, kept outside the process locks
+    /// so placement never waits for a running timeslice
+    cpu_load: [AtomicUsize; MAX_CPUS],
 }
 
 /// A pending syscall from a WASM process
@@ -117,11 +156,36 @@ impl WasmRuntime {
             processes: Mutex::new(BTreeMap::new()),
             pending_syscalls: Mutex::new(Vec::new()),
             pending_messages: Mutex::new(BTreeMap::new()),
+            tick: AtomicU64::new(0),
+            cpu_count: AtomicUsize::new(1),
+            cpu_load: core::array::from_fn(|_| AtomicUsize::new(0)),
         }
     }
     
+    /// Set the number of CPUs that run processes
+    ///
+    /// Only affects where new processes are placed.
+    pub fn set_cpu_count(&self, cpus: usize) {
+        self.cpu_count.store(cpus.clamp(1, MAX_CPUS), Ordering::Relaxed);
+    }
+    
+    /// Look up a process
+    fn process(&self, pid: u64) -> Option<Arc<Mutex<WasmProcess>>> {
+        self.processes.lock().get(&pid).cloned()
+    }
+    
+    /// The CPU with the fewest live processes
+    fn least_loaded_cpu(&self) -> usize {
+        (0..self.cpu_count.load(Ordering::Relaxed))
+            .min_by_key(|cpu| self.cpu_load[*cpu].load(Ordering::Relaxed))
+            .unwrap_or(BOOT_CPU)
+    }
+    
     /// Spawn a new WASM process
     ///
+    /// The process gets the lowest priority class and its default
+    /// scheduling context. Use [`WasmRuntime::spawn_with_sched`] to pick one.
+    ///
     /// # Arguments
     /// * `pid` - Process ID assigned by the kernel
     /// * `name` - Human-readable process name
@@ -130,21 +194,48 @@ impl WasmRuntime {
     /// # Returns
     /// Handle to the spawned process
     pub fn spawn(&self, pid: u64, name: &str, binary: &[u8]) -> Result<NumericProcessHandle, HalError> {
+        let priority = Priority::Background;
+        self.spawn_with_sched(pid, name, binary, priority, SchedContext::for_priority(priority))
+    }
+
+    /// Spawn a new WASM process with an explicit priority and scheduling context
+    pub fn spawn_with_sched(
+        &self,
+        pid: u64,
+        name: &str,
+        binary: &[u8],
+        priority: Priority,
+        sched: SchedContext,
+    ) -> Result<NumericProcessHandle, HalError> {
         serial::write_str(&alloc::format!(
-            "[wasm-rt] Spawning process '{}' with PID {}\n",
-            name, pid
+            "[wasm-rt] Spawning process '{}' with PID {} ({:?})\n",
+            name, pid, priority
         ));
         
-        // Parse the WASM module
-        let module = Module::new(&self.engine, binary).map_err(|e| {
-            serial::write_str(&alloc::format!(
-                "[wasm-rt] Failed to parse WASM module: {:?}\n", e
-            ));
-            HalError::ProcessSpawnFailed
-        })?;
+        // Parse the WASM module, with preemption points if they can be added
+        let instrumented = preempt::instrument(binary).and_then(|instrumented| {
+            let module = Module::new(&self.engine, &instrumented.binary[..]).ok()?;
+            Some((module, instrumented.fuel_reserve))
+        });
+        let (module, preempt_reserve) = match instrumented {
+            Some(instrumented) => instrumented,
+            None => {
+                serial::write_str(&alloc::format!(
+                    "[wasm-rt] Process '{}' has no preemption points\n", name
+                ));
+                let module = Module::new(&self.engine, binary).map_err(|e| {
+                    serial::write_str(&alloc::format!(
+                        "[wasm-rt] Failed to parse WASM module: {:?}\n", e
+                    ));
+                    HalError::ProcessSpawnFailed
+                })?;
+                (module, 0)
+            }
+        };
         
         // Create store with host state
-        let host_state = HostState::new(pid);
+        let mut host_state = HostState::new(pid);
+        host_state.preempt_reserve = preempt_reserve;
         let mut store = Store::new(&self.engine, host_state);
         
         // Instantiate the module with host functions
@@ -176,21 +267,50 @@ impl WasmRuntime {
             start_func,
             resumable: None,
             memory_size: 65536, // Default, will be updated
+            priority,
+            sched,
+            budget_left: sched.budget,
+            period_start: self.tick.load(Ordering::Relaxed),
+            cpu: self.least_loaded_cpu(),
         };
+        let cpu = process.cpu;
+        self.cpu_load[cpu].fetch_add(1, Ordering::Relaxed);
         
         // Store the process
-        self.processes.lock().insert(pid, process);
+        self.processes.lock().insert(pid, Arc::new(Mutex::new(process)));
         
         serial::write_str(&alloc::format!(
-            "[wasm-rt] Process '{}' (PID {}) spawned successfully\n",
-            name, pid
+            "[wasm-rt] Process '{}' (PID {}) spawned successfully on CPU {}\n",
+            name, pid, cpu
         ));
         
         Ok(NumericProcessHandle::new(pid))
     }
     
+    /// Pick the next process to run in this scheduler round
+    ///
+    /// Returns the highest-priority ready process on `cpu`'s run queue with
+    /// budget left that is not in `ran` (lowest PID first within a class).
+    fn pick_next(&self, cpu: usize, ran: &[u64]) -> Option<u64> {
+        let tick = self.tick.load(Ordering::Relaxed);
+        let processes = self.processes.lock();
+        processes
+            .values()
+            // Skip processes momentarily locked by another CPU
+            .filter_map(|p| p.try_lock())
+            .filter(|p| p.cpu == cpu && p.state == ProcessState::Ready && !ran.contains(&p.pid))
+            .filter_map(|mut p| {
+                p.replenish(tick);
+                p.has_budget().then_some((p.priority, p.pid))
+            })
+            .min()
+            .map(|(_, pid)| pid)
+    }
+
     /// Run a process until it yields, exhausts fuel, or makes a syscall
     ///
+    /// The fuel it burns is charged to its budget.
+    ///
     /// Returns (is_alive, has_pending_syscall, yielded)
     fn run_process_internal(&self, process: &mut WasmProcess) -> (bool, bool, bool) {
         if process.state != ProcessState::Ready {
@@ -202,6 +322,15 @@ impl WasmRuntime {
         // Refuel the process with a small amount for syscall-heavy code
         let _ = process.store.set_fuel(FUEL_PER_TIMESLICE);
         
+        let outcome = self.run_timeslice(process);
+        
+        let fuel_left = process.store.get_fuel().unwrap_or(0);
+        process.charge(FUEL_PER_TIMESLICE.saturating_sub(fuel_left));
+        outcome
+    }
+    
+    /// Execute a refuelled process (body of `run_process_internal`)
+    fn run_timeslice(&self, process: &mut WasmProcess) -> (bool, bool, bool) {
         // Check if we have a resumable invocation (continuing from previous yield)
         if let Some(resumable) = process.resumable.take() {
             // Check if we're resuming from zos_yield() which returns () not i32
@@ -235,11 +364,11 @@ impl WasmRuntime {
             
             // Provide the return value as input when resuming
             // zos_yield() returns () so we provide empty slice
-            // zos_syscall() returns i32 so we provide the result value
+            // zos_syscall() returns i64 so we provide the result value
             let result = if trapped_from_yield {
                 resumable.resume(&mut process.store, &[])
             } else {
-                resumable.resume(&mut process.store, &[wasmi::Val::I32(return_value as i32)])
+                resumable.resume(&mut process.store, &[wasmi::Val::I64(return_value)])
             };
             let (is_alive, has_pending, yielded) = self.handle_execution_result(process, result);
             
@@ -292,9 +421,8 @@ impl WasmRuntime {
     ///
     /// Returns true if the process is still running, false if it exited.
     pub fn run_process(&self, pid: u64) -> Result<bool, HalError> {
-        let mut processes = self.processes.lock();
-        let process = processes.get_mut(&pid).ok_or(HalError::ProcessNotFound)?;
-        let (is_alive, _has_pending, _yielded) = self.run_process_internal(process);
+        let process = self.process(pid).ok_or(HalError::ProcessNotFound)?;
+        let (is_alive, _has_pending, _yielded) = self.run_process_internal(&mut process.lock());
         Ok(is_alive)
     }
     
@@ -396,9 +524,12 @@ impl WasmRuntime {
     
     /// Kill a process
     pub fn kill(&self, pid: u64) -> Result<(), HalError> {
-        let mut processes = self.processes.lock();
-        if let Some(mut process) = processes.remove(&pid) {
+        let removed = self.processes.lock().remove(&pid);
+        if let Some(process) = removed {
+            // Waits for the end of the timeslice if it is running on another CPU
+            let mut process = process.lock();
             process.state = ProcessState::Terminated;
+            self.cpu_load[process.cpu].fetch_sub(1, Ordering::Relaxed);
             serial::write_str(&alloc::format!(
                 "[wasm-rt] Process {} killed\n", pid
             ));
@@ -410,29 +541,39 @@ impl WasmRuntime {
     
     /// Check if a process is alive
     pub fn is_alive(&self, pid: u64) -> bool {
-        self.processes
-            .lock()
-            .get(&pid)
-            .map(|p| p.state != ProcessState::Terminated)
+        self.process(pid)
+            .map(|p| p.lock().state != ProcessState::Terminated)
             .unwrap_or(false)
     }
     
     /// Get memory size of a process
     pub fn memory_size(&self, pid: u64) -> Result<usize, HalError> {
-        self.processes
-            .lock()
-            .get(&pid)
-            .map(|p| p.memory_size)
+        self.process(pid)
+            .map(|p| p.lock().memory_size)
             .ok_or(HalError::ProcessNotFound)
     }
     
+    /// Run `f` on a process's linear memory
+    ///
+    /// Must not be called while the process's lock is held (syscall
+    /// handlers run without it).
+    pub fn with_memory<R>(&self, pid: u64, f: impl FnOnce(&mut [u8]) -> R) -> Result<R, HalError> {
+        let process = self.process(pid).ok_or(HalError::ProcessNotFound)?;
+        let mut process = process.lock();
+        let process = &mut *process;
+        match process.instance.get_export(&process.store, "memory") {
+            Some(wasmi::Extern::Memory(memory)) => Ok(f(memory.data_mut(&mut process.store))),
+            _ => Err(HalError::InvalidArgument),
+        }
+    }
+    
     /// Queue a message for delivery to a process
     pub fn queue_message(&self, pid: u64, msg: Vec<u8>) {
         let mut messages = self.pending_messages.lock();
         messages.entry(pid).or_insert_with(Vec::new).push(msg);
     }
     
-    /// Get pending syscalls from all processes
+    /// Take the syscalls made on application processors
     pub fn take_pending_syscalls(&self) -> Vec<PendingSyscall> {
         core::mem::take(&mut *self.pending_syscalls.lock())
     }
@@ -444,45 +585,57 @@ impl WasmRuntime {
     /// 2. Collects any syscalls they made
     /// 3. Returns the pending syscalls for the kernel to process
     pub fn run_all_processes(&self) -> Vec<PendingSyscall> {
-        let mut syscalls = Vec::new();
-        
-        // Get list of PIDs to run
-        let pids: Vec<u64> = {
-            self.processes
-                .lock()
-                .iter()
-                .filter(|(_, p)| p.state == ProcessState::Ready)
-                .map(|(pid, _)| *pid)
-                .collect()
-        };
+        // Syscalls made on application processors come first
+        let mut syscalls = self.take_pending_syscalls();
+        self.tick.fetch_add(1, Ordering::Relaxed);
         
-        // Run each ready process
-        for pid in pids {
-            let mut processes = self.processes.lock();
-            let process = match processes.get_mut(&pid) {
-                Some(p) => p,
-                None => continue,
-            };
-            
-            let (_is_alive, has_pending, _yielded) = self.run_process_internal(process);
-            
-            // Collect pending syscall if any
-            if has_pending {
-                if let Some(pending) = process.store.data_mut().pending_syscall.take() {
-                    syscalls.push(PendingSyscall {
-                        pid,
-                        syscall_num: pending.syscall_num,
-                        args: pending.args,
-                        data: pending.data,
-                    });
-                    // State should already be Blocked from handle_execution_result
-                }
+        // Run each ready process, highest priority first
+        let mut ran = Vec::new();
+        while let Some(pid) = self.pick_next(BOOT_CPU, &ran) {
+            ran.push(pid);
+            if let Some(syscall) = self.run_and_take_syscall(pid) {
+                syscalls.push(syscall);
             }
         }
         
         syscalls
     }
     
+    /// Run one scheduler round on an application processor
+    ///
+    /// Runs each ready process on `cpu`'s run queue for one timeslice. Kernel
+    /// syscalls are queued for the boot CPU, whose scheduler completes them.
+    /// Returns false if nothing was ready to run.
+    pub fn run_cpu(&self, cpu: usize) -> bool {
+        let mut ran = Vec::new();
+        while let Some(pid) = self.pick_next(cpu, &ran) {
+            ran.push(pid);
+            if let Some(syscall) = self.run_and_take_syscall(pid) {
+                self.pending_syscalls.lock().push(syscall);
+            }
+        }
+        !ran.is_empty()
+    }
+    
+    /// Run one timeslice of a process and take the kernel syscall it made
+    fn run_and_take_syscall(&self, pid: u64) -> Option<PendingSyscall> {
+        let process = self.process(pid)?;
+        let mut process = process.lock();
+        let (_is_alive, has_pending, _yielded) = self.run_process_internal(&mut process);
+        if !has_pending {
+            return None;
+        }
+        
+        // State should already be Blocked from handle_execution_result
+        let pending = process.store.data_mut().pending_syscall.take()?;
+        Some(PendingSyscall {
+            pid,
+            syscall_num: pending.syscall_num,
+            args: pending.args,
+            data: pending.data,
+        })
+    }
+    
     /// Run all processes with synchronous syscall handling
     ///
     /// This variant processes syscalls immediately as they are made,
@@ -490,42 +643,46 @@ impl WasmRuntime {
     /// for the next scheduler tick.
     ///
     /// Runs multiple rounds to ensure newly spawned processes get scheduled.
-    /// Each round refreshes the PID list to include processes spawned during
-    /// the previous round.
+    /// Each round picks processes by priority until every ready process with
+    /// budget left has run once, including ones spawned during the round.
+    ///
+    /// The handler returns None for a syscall that parked its caller (a
+    /// timed receive or call). The process then stays blocked until the
+    /// kernel finishes the wait and calls `complete_syscall`.
     pub fn run_all_processes_with_handler<F>(&self, handler: &mut F)
     where
-        F: FnMut(PendingSyscall) -> (i64, Vec<u8>),
+        F: FnMut(PendingSyscall) -> Option<(i64, Vec<u8>)>,
     {
         const MAX_ROUNDS: usize = 10;
         
+        // Syscalls made on application processors go through the same handler
+        for syscall in self.take_pending_syscalls() {
+            let pid = syscall.pid;
+            if let Some((result, data)) = handler(syscall) {
+                self.complete_syscall(pid, result, &data);
+            }
+        }
+        
+        self.tick.fetch_add(1, Ordering::Relaxed);
+        
         for round in 0..MAX_ROUNDS {
-            // Refresh PID list each round to include newly spawned processes
-            let pids: Vec<u64> = {
-                self.processes
-                    .lock()
-                    .iter()
-                    .filter(|(_, p)| p.state == ProcessState::Ready)
-                    .map(|(pid, _)| *pid)
-                    .collect()
-            };
+            // Run ready processes with synchronous syscall handling
+            let mut ran = Vec::new();
+            while let Some(pid) = self.pick_next(BOOT_CPU, &ran) {
+                ran.push(pid);
+                self.run_process_with_handler(pid, handler);
+            }
             
-            if pids.is_empty() {
+            if ran.is_empty() {
                 // No ready processes - done for this tick
                 break;
             }
             
-            if round > 0 {
-                if DEBUG_SCHEDULER {
-                    serial::write_str(&alloc::format!(
-                        "[wasm-rt] Scheduler round {}: {} ready processes (PIDs: {:?})\n",
-                        round, pids.len(), pids
-                    ));
-                }
-            }
-            
-            // Run each ready process with synchronous syscall handling
-            for pid in pids {
-                self.run_process_with_handler(pid, handler);
+            if round > 0 && DEBUG_SCHEDULER {
+                serial::write_str(&alloc::format!(
+                    "[wasm-rt] Scheduler round {}: ran {} processes (PIDs: {:?})\n",
+                    round, ran.len(), ran
+                ));
             }
         }
     }
@@ -533,24 +690,26 @@ impl WasmRuntime {
     /// Run a single process with synchronous syscall handling
     ///
     /// Runs the process in a loop, processing syscalls immediately as they
-    /// are made, until the process yields explicitly or terminates.
-    /// When a process yields, we return early to give other processes a turn.
+    /// are made, until the process yields explicitly, terminates or spends
+    /// its budget. When a process yields, we return early to give other
+    /// processes a turn.
     fn run_process_with_handler<F>(&self, pid: u64, handler: &mut F)
     where
-        F: FnMut(PendingSyscall) -> (i64, Vec<u8>),
+        F: FnMut(PendingSyscall) -> Option<(i64, Vec<u8>)>,
     {
         const MAX_SYSCALLS_PER_PROCESS: usize = 100; // Safety limit
         let mut syscall_count = 0;
         
         loop {
             // Run one timeslice
-            let (is_alive, has_pending, yielded) = {
-                let mut processes = self.processes.lock();
-                let process = match processes.get_mut(&pid) {
+            let (is_alive, has_pending, yielded, has_budget) = {
+                let process = match self.process(pid) {
                     Some(p) => p,
                     None => return,
                 };
-                self.run_process_internal(process)
+                let mut process = process.lock();
+                let (is_alive, has_pending, yielded) = self.run_process_internal(&mut process);
+                (is_alive, has_pending, yielded, process.has_budget())
             };
             
             if !is_alive {
@@ -575,26 +734,31 @@ impl WasmRuntime {
                 }
                 
                 // Get the pending syscall
-                let pending = {
-                    let mut processes = self.processes.lock();
-                    let process = match processes.get_mut(&pid) {
-                        Some(p) => p,
-                        None => return,
-                    };
-                    process.store.data_mut().pending_syscall.take()
+                let pending = match self.process(pid) {
+                    Some(process) => process.lock().store.data_mut().pending_syscall.take(),
+                    None => return,
                 };
                 
                 if let Some(pending) = pending {
                     // Process the syscall synchronously
-                    let (result, data) = handler(PendingSyscall {
+                    let completion = handler(PendingSyscall {
                         pid,
                         syscall_num: pending.syscall_num,
                         args: pending.args,
                         data: pending.data,
                     });
                     
-                    // Complete the syscall
-                    self.complete_syscall(pid, result, &data);
+                    // Complete the syscall, or leave the process blocked
+                    // while the kernel has it parked
+                    match completion {
+                        Some((result, data)) => self.complete_syscall(pid, result, &data),
+                        None => return,
+                    }
+                }
+                
+                // Out of budget - resume in a later period
+                if !has_budget {
+                    return;
                 }
             } else {
                 // No pending syscall and no yield - process is done for now
@@ -605,7 +769,8 @@ impl WasmRuntime {
     
     /// Complete a syscall for a process (result is i64 to support packed 64-bit returns)
     pub fn complete_syscall(&self, pid: u64, result: i64, data: &[u8]) {
-        if let Some(process) = self.processes.lock().get_mut(&pid) {
+        if let Some(process) = self.process(pid) {
+            let mut process = process.lock();
             // Store result in host state for the process to retrieve
             process.store.data_mut().set_syscall_result(result, data);
             process.state = ProcessState::Ready;
@@ -619,7 +784,242 @@ impl Default for WasmRuntime {
     }
 }
 
-// SAFETY: WasmRuntime is designed to be used from a single-threaded kernel
-// All mutable state is protected by Mutex
+// SAFETY: All mutable state is protected by Mutex; each process has its own,
+// so a process only ever runs on one CPU at a time
 unsafe impl Send for WasmRuntime {}
 unsafe impl Sync for WasmRuntime {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// `_start` calls `zos_syscall(0x40, 0, 0, 0)` and returns
+    const SYSCALL_ONCE: &[u8] = &[
+        0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00, // header
+        0x01, 0x0C, 0x02, 0x60, 0x04, 0x7F, 0x7F, 0x7F, 0x7F, 0x01, 0x7E, 0x60, 0x00, 0x00,
+        0x02, 0x13, 0x01, 0x03, b'e', b'n', b'v', 0x0B, b'z', b'o', b's', b'_', b's', b'y',
+        b's', b'c', b'a', b'l', b'l', 0x00, 0x00, // import
+        0x03, 0x02, 0x01, 0x01, // function
+        0x07, 0x0A, 0x01, 0x06, b'_', b's', b't', b'a', b'r', b't', 0x00, 0x01, // export
+        0x0A, 0x10, 0x01, 0x0E, 0x00, 0x41, 0xC0, 0x00, 0x41, 0x00, 0x41, 0x00, 0x41, 0x00,
+        0x10, 0x00, 0x1A, 0x0B, // code
+    ];
+    
+    /// `(func (export "_start") (loop (br 0)))`, importing `zos_syscall`
+    /// so that spawning instruments it with preemption checks
+    const SPIN: &[u8] = &[
+        0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00, // header
+        0x01, 0x0C, 0x02, 0x60, 0x04, 0x7F, 0x7F, 0x7F, 0x7F, 0x01, 0x7E, 0x60, 0x00, 0x00,
+        0x02, 0x13, 0x01, 0x03, b'e', b'n', b'v', 0x0B, b'z', b'o', b's', b'_', b's', b'y',
+        b's', b'c', b'a', b'l', b'l', 0x00, 0x00, // import
+        0x03, 0x02, 0x01, 0x01, // function
+        0x07, 0x0A, 0x01, 0x06, b'_', b's', b't', b'a', b'r', b't', 0x00, 0x01, // export
+        0x0A, 0x09, 0x01, 0x07, 0x00, 0x03, 0x40, 0x0C, 0x00, 0x0B, 0x0B, // code
+    ];
+    
+    /// `_start` loops over `zos_syscall(0x40, 0, 0, 0)` then a yield
+    const SERVICE_LOOP: &[u8] = &[
+        0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00, // header
+        0x01, 0x0C, 0x02, 0x60, 0x04, 0x7F, 0x7F, 0x7F, 0x7F, 0x01, 0x7E, 0x60, 0x00, 0x00,
+        0x02, 0x13, 0x01, 0x03, b'e', b'n', b'v', 0x0B, b'z', b'o', b's', b'_', b's', b'y',
+        b's', b'c', b'a', b'l', b'l', 0x00, 0x00, // import
+        0x03, 0x02, 0x01, 0x01, // function
+        0x07, 0x0A, 0x01, 0x06, b'_', b's', b't', b'a', b'r', b't', 0x00, 0x01, // export
+        0x0A, 0x20, 0x01, 0x1E, 0x00, 0x03, 0x40, 0x41, 0xC0, 0x00, 0x41, 0x00, 0x41, 0x00,
+        0x41, 0x00, 0x10, 0x00, 0x1A, 0x41, 0x04, 0x41, 0x00, 0x41, 0x00, 0x41, 0x00, 0x10,
+        0x00, 0x1A, 0x0C, 0x00, 0x0B, 0x0B, // code
+    ];
+    
+    /// Insert a ready process built from an empty module on the boot CPU
+    fn insert_process(runtime: &WasmRuntime, pid: u64, priority: Priority, sched: SchedContext) {
+        insert_module(runtime, pid, b"\0asm\x01\0\0\0", priority, sched, BOOT_CPU);
+    }
+    
+    /// Insert a ready process running `binary` on `cpu` (bypasses `spawn`,
+    /// which logs to the serial port)
+    fn insert_module(
+        runtime: &WasmRuntime,
+        pid: u64,
+        binary: &[u8],
+        priority: Priority,
+        sched: SchedContext,
+        cpu: usize,
+    ) {
+        let mut store = Store::new(&runtime.engine, HostState::new(pid));
+        let module = Module::new(&runtime.engine, binary).unwrap();
+        let instance = runtime
+            .linker
+            .instantiate(&mut store, &module)
+            .unwrap()
+            .start(&mut store)
+            .unwrap();
+        let start_func = instance.get_typed_func::<(), ()>(&store, "_start").ok();
+        let process = WasmProcess {
+            pid,
+            name: String::from("test"),
+            state: ProcessState::Ready,
+            store,
+            instance,
+            start_func,
+            resumable: None,
+            memory_size: 0,
+            priority,
+            sched,
+            budget_left: sched.budget,
+            period_start: 0,
+            cpu,
+        };
+        runtime.processes.lock().insert(pid, Arc::new(Mutex::new(process)));
+        runtime.cpu_load[cpu].fetch_add(1, Ordering::Relaxed);
+    }
+    
+    #[test]
+    fn test_pick_next_by_priority() {
+        let runtime = WasmRuntime::new();
+        let priorities = [Priority::Background, Priority::Interactive, Priority::System];
+        for (pid, priority) in (1..).zip(priorities) {
+            insert_process(&runtime, pid, priority, SchedContext::for_priority(priority));
+        }
+        
+        let mut ran = Vec::new();
+        while let Some(pid) = runtime.pick_next(BOOT_CPU, &ran) {
+            ran.push(pid);
+        }
+        assert_eq!(ran, [3, 2, 1]);
+    }
+    
+    #[test]
+    fn test_spent_budget_waits_for_next_period() {
+        let runtime = WasmRuntime::new();
+        let sched = SchedContext { budget: 100, period: 4 };
+        insert_process(&runtime, 1, Priority::Background, sched);
+        
+        runtime.process(1).unwrap().lock().charge(150);
+        for tick in 1..4 {
+            runtime.tick.store(tick, Ordering::Relaxed);
+            assert_eq!(runtime.pick_next(BOOT_CPU, &[]), None);
+        }
+        
+        runtime.tick.store(4, Ordering::Relaxed);
+        assert_eq!(runtime.pick_next(BOOT_CPU, &[]), Some(1));
+        assert_eq!(runtime.process(1).unwrap().lock().budget_left, 100);
+    }
+    
+    #[test]
+    fn test_processes_spread_across_cpus() {
+        let runtime = WasmRuntime::new();
+        let sched = SchedContext::for_priority(Priority::System);
+        assert_eq!(runtime.least_loaded_cpu(), BOOT_CPU);
+        
+        runtime.set_cpu_count(2);
+        insert_process(&runtime, 1, Priority::System, sched);
+        assert_eq!(runtime.least_loaded_cpu(), 1);
+        
+        // Each CPU only picks from its own run queue
+        insert_module(&runtime, 2, b"\0asm\x01\0\0\0", Priority::System, sched, 1);
+        assert_eq!(runtime.pick_next(BOOT_CPU, &[]), Some(1));
+        assert_eq!(runtime.pick_next(1, &[]), Some(2));
+    }
+    
+    #[test]
+    fn test_placement_does_not_wait_for_running_process() {
+        let runtime = WasmRuntime::new();
+        runtime.set_cpu_count(2);
+        let sched = SchedContext::for_priority(Priority::System);
+        insert_process(&runtime, 1, Priority::System, sched);
+        
+        // Process 1 is mid-timeslice on its CPU
+        let running = runtime.process(1).unwrap();
+        let guard = running.lock();
+        assert_eq!(runtime.least_loaded_cpu(), 1);
+        drop(guard);
+        
+        // Killing it frees its slot on the boot CPU
+        insert_module(&runtime, 2, b"\0asm\x01\0\0\0", Priority::System, sched, 1);
+        runtime.kill(1).unwrap();
+        assert_eq!(runtime.least_loaded_cpu(), BOOT_CPU);
+    }
+    
+    #[test]
+    fn test_ap_syscalls_go_through_boot_cpu_handler() {
+        let runtime = WasmRuntime::new();
+        runtime.set_cpu_count(2);
+        let sched = SchedContext::for_priority(Priority::Background);
+        insert_module(&runtime, 1, SYSCALL_ONCE, Priority::Background, sched, 1);
+        
+        // The AP runs the process until it makes a kernel syscall
+        assert!(runtime.run_cpu(1));
+        assert!(!runtime.run_cpu(1));
+        
+        let mut handled = Vec::new();
+        runtime.run_all_processes_with_handler(&mut |syscall: PendingSyscall| {
+            handled.push((syscall.pid, syscall.syscall_num));
+            Some((7, Vec::new()))
+        });
+        assert_eq!(handled, [(1, 0x40)]);
+        
+        // With the result delivered, the AP resumes it to completion
+        assert!(runtime.run_cpu(1));
+        assert!(!runtime.is_alive(1));
+    }
+    
+    #[test]
+    fn test_parked_syscall_blocks_until_completed() {
+        let runtime = WasmRuntime::new();
+        let sched = SchedContext::for_priority(Priority::Interactive);
+        insert_module(&runtime, 1, SYSCALL_ONCE, Priority::Interactive, sched, BOOT_CPU);
+        
+        // The kernel parks the syscall, so the process is not resumed
+        let mut handled = 0;
+        for _ in 0..3 {
+            runtime.run_all_processes_with_handler(&mut |_syscall: PendingSyscall| {
+                handled += 1;
+                None
+            });
+        }
+        assert_eq!(handled, 1);
+        assert!(runtime.is_alive(1));
+        
+        // Once the wait ends the result is delivered and the process finishes
+        runtime.complete_syscall(1, 1, &[]);
+        runtime.run_all_processes_with_handler(&mut |_syscall: PendingSyscall| {
+            Some((0, Vec::new()))
+        });
+        assert!(!runtime.is_alive(1));
+    }
+    
+    #[test]
+    fn test_background_memhog_cannot_delay_system_process() {
+        let runtime = WasmRuntime::new();
+        let background = Priority::Background;
+        let system = Priority::System;
+        runtime
+            .spawn_with_sched(1, "memhog", SPIN, background, SchedContext::for_priority(background))
+            .unwrap();
+        runtime
+            .spawn_with_sched(2, "vfs", SERVICE_LOOP, system, SchedContext::for_priority(system))
+            .unwrap();
+        let memhog = runtime.process(1).unwrap();
+        let full_budget = memhog.lock().sched.budget;
+        
+        // Spawned first, the memhog would run first without priorities.
+        // Three ticks stay inside its period (a fourth would refill it).
+        let mut ticks: Vec<Vec<u64>> = Vec::new();
+        for _ in 0..3 {
+            let mut memhog_budget_at_syscall = Vec::new();
+            runtime.run_all_processes_with_handler(&mut |syscall: PendingSyscall| {
+                assert_eq!(syscall.pid, 2);
+                memhog_budget_at_syscall.push(memhog.lock().budget_left);
+                Some((0, Vec::new()))
+            });
+            ticks.push(memhog_budget_at_syscall);
+        }
+        
+        // The service is served every tick, the first time before the
+        // memhog burned anything, and the memhog sits out its period
+        assert!(ticks.iter().all(|tick| !tick.is_empty()));
+        assert_eq!(ticks[0][0], full_budget);
+        assert!(ticks[1..].iter().flatten().all(|budget| *budget < full_budget));
+        assert!(runtime.is_alive(1));
+    }
+}
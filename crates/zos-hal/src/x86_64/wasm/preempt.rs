@@ -0,0 +1,608 @@
+//! Preemption points for CPU-bound WASM code
+//!
+//! wasmi can only pause an invocation when a host function returns an
+//! error; running out of fuel inside WASM code is an unresumable trap. To
+//! let long computations (e.g. Argon2 key derivation) span several
+//! timeslices, [`instrument`] rewrites each module at spawn time:
+//!
+//! - a mutable `i32` countdown global is appended to the globals
+//! - every function entry, every `loop` header and every return from a call
+//!   decrements it
+//! - when it reaches zero, an appended helper function resets it and calls
+//!   `zos_syscall(SYS_PREEMPT, 0, 0, 0)`
+//! - bulk instructions (`memory.fill`, `table.copy`, `memory.grow`, ...)
+//!   first pass their length to a second helper, which calls
+//!   `zos_syscall(SYS_PREEMPT, len, kind, 0)`
+//!
+//! The host answers `SYS_PREEMPT` immediately while the timeslice still has
+//! [`fuel_needed`] left, and otherwise pauses the process like a yield so it
+//! resumes where it left off on its next timeslice.
+//!
+//! ## Fuel reserve
+//!
+//! With a check after every call, code between two checks runs inside one
+//! function and only forwards (a branch back to a `loop` passes its check),
+//! so it executes each byte of that body at most once. wasmi charges at
+//! most [`FUEL_PER_CODE_BYTE`] per byte of Wasm code, which bounds the fuel
+//! between checks by the largest body; bulk instructions are the exception
+//! and are checked against their own length. The countdown interval is
+//! picked per module so that interval times that bound stays within
+//! [`MAX_FUEL_RESERVE`], and the product is the module's reserve.
+//!
+//! Only appends are made (two types, one global, two functions), so
+//! existing indices stay valid and every other section is copied verbatim.
+
+use alloc::vec::Vec;
+use wasmparser::{CodeSectionReader, ImportSectionReader, Operator, TypeRef};
+
+use super::FUEL_PER_TIMESLICE;
+
+/// Host-only syscall number used by the inserted preemption checks
+pub const SYS_PREEMPT: u32 = u32::MAX;
+
+/// Largest fuel reserve a module may need between two countdown checks
+///
+/// Leaves room in a fresh timeslice for the largest bulk instruction (see
+/// below), so a bulk instruction paused for lack of fuel always fits after
+/// resuming.
+pub const MAX_FUEL_RESERVE: u64 = FUEL_PER_TIMESLICE / 4;
+
+/// Upper bound on the fuel wasmi charges per byte of Wasm code
+///
+/// Every operator is at least one byte and wasmi charges it one base cost
+/// per instruction it compiles to (at most two), plus one fuel per eight
+/// register copies for calls and returns, whose operands take bytes of
+/// their own.
+pub const FUEL_PER_CODE_BYTE: u64 = 2;
+
+/// Bytes copied, filled or grown per unit of fuel (wasmi's default costs)
+const BYTES_PER_FUEL: u64 = 64;
+
+/// Table elements copied, filled or grown per unit of fuel
+const ELEMENTS_PER_FUEL: u64 = 8;
+
+/// Bytes in a Wasm page
+const WASM_PAGE_SIZE: u64 = 65536;
+
+// A bulk instruction covers at most a full 4 GiB memory
+const _: () = assert!(MAX_FUEL_RESERVE + (1 << 32) / BYTES_PER_FUEL <= FUEL_PER_TIMESLICE);
+
+/// Most checks between two countdown host calls
+const CHECK_INTERVAL: i32 = 100;
+
+/// `kind` argument of a bulk check: the length counts bytes
+const BULK_BYTES: u32 = 1;
+/// `kind` argument of a bulk check: the length counts table elements
+const BULK_ELEMENTS: u32 = 2;
+/// `kind` argument of a bulk check: the length counts memory pages
+const BULK_PAGES: u32 = 3;
+
+/// A module rewritten with preemption checks
+#[derive(Debug, PartialEq)]
+pub struct Instrumented {
+    /// The instrumented binary
+    pub binary: Vec<u8>,
+    /// Fuel a countdown check needs left in the timeslice to continue
+    pub fuel_reserve: u64,
+}
+
+/// Fuel a preemption check needs left to let the process continue
+///
+/// `reserve` is the module's [`Instrumented::fuel_reserve`]; `kind` and
+/// `len` are the check's arguments. Bulk checks add the instruction's own
+/// cost on top of the reserve.
+pub fn fuel_needed(reserve: u64, kind: u32, len: u32) -> u64 {
+    let len = u64::from(len);
+    let bulk = match kind {
+        BULK_BYTES => len / BYTES_PER_FUEL,
+        BULK_ELEMENTS => len / ELEMENTS_PER_FUEL,
+        BULK_PAGES => len * WASM_PAGE_SIZE / BYTES_PER_FUEL,
+        _ => 0,
+    };
+    reserve + bulk
+}
+
+// Section ids
+const SECTION_TYPE: u8 = 1;
+const SECTION_IMPORT: u8 = 2;
+const SECTION_FUNCTION: u8 = 3;
+const SECTION_GLOBAL: u8 = 6;
+const SECTION_CODE: u8 = 10;
+
+/// A raw section of a WASM binary
+struct Section<'a> {
+    id: u8,
+    body: &'a [u8],
+}
+
+/// Insert preemption checks into a WASM module
+///
+/// Returns `None` if the module does not import `env.zos_syscall`, has no
+/// function bodies, or cannot be parsed; callers then use it unchanged.
+pub fn instrument(binary: &[u8]) -> Option<Instrumented> {
+    instrument_within(binary, MAX_FUEL_RESERVE)
+}
+
+/// [`instrument`] with the countdown interval fitted to `max_reserve`
+fn instrument_within(binary: &[u8], max_reserve: u64) -> Option<Instrumented> {
+    let sections = split_sections(binary)?;
+    let find = |id: u8| sections.iter().find(|s| s.id == id).map(|s| s.body);
+
+    // Locate the syscall import and count imported functions and globals
+    let mut func_imports = 0u32;
+    let mut global_imports = 0u32;
+    let mut syscall = None;
+    if let Some(body) = find(SECTION_IMPORT) {
+        for import in ImportSectionReader::new(body, 0).ok()? {
+            let import = import.ok()?;
+            match import.ty {
+                TypeRef::Func(_) => {
+                    if import.module == "env" && import.name == "zos_syscall" {
+                        syscall = Some(func_imports);
+                    }
+                    func_imports += 1;
+                }
+                TypeRef::Global(_) => global_imports += 1,
+                _ => {}
+            }
+        }
+    }
+    let syscall = syscall?;
+
+    let type_index = read_count(find(SECTION_TYPE)?)?;
+    let defined_funcs = read_count(find(SECTION_FUNCTION)?)?;
+    let helper = func_imports.checked_add(defined_funcs)?;
+    let global = global_imports + find(SECTION_GLOBAL).map_or(Some(0), read_count)?;
+
+    let checks = Checks {
+        countdown: check_sequence(global, helper),
+        bulk_helper: helper + 1,
+    };
+    let code = instrument_code(find(SECTION_CODE)?, &checks, global, syscall, max_reserve)?;
+
+    let mut out = Vec::with_capacity(binary.len() + binary.len() / 8);
+    out.extend_from_slice(&binary[..8]);
+    let mut global_written = false;
+    for section in &sections {
+        // A new global section goes before the first section ordered after it
+        if !global_written && section.id != 0 && order(section.id) > order(SECTION_GLOBAL) {
+            let entry = global_entry(code.interval);
+            write_section(&mut out, SECTION_GLOBAL, &append_entry(&[0], &entry)?);
+            global_written = true;
+        }
+        let body = match section.id {
+            SECTION_TYPE => {
+                let body = append_entry(section.body, &[0x60, 0x00, 0x00])?;
+                append_entry(&body, &[0x60, 0x02, 0x7F, 0x7F, 0x01, 0x7F])?
+            }
+            SECTION_FUNCTION => {
+                let mut countdown = Vec::new();
+                write_u32(&mut countdown, type_index);
+                let mut bulk = Vec::new();
+                write_u32(&mut bulk, type_index.checked_add(1)?);
+                append_entry(&append_entry(section.body, &countdown)?, &bulk)?
+            }
+            SECTION_GLOBAL => {
+                global_written = true;
+                append_entry(section.body, &global_entry(code.interval))?
+            }
+            SECTION_CODE => code.body.clone(),
+            _ => section.body.to_vec(),
+        };
+        write_section(&mut out, section.id, &body);
+    }
+    if !global_written {
+        let entry = global_entry(code.interval);
+        write_section(&mut out, SECTION_GLOBAL, &append_entry(&[0], &entry)?);
+    }
+    Some(Instrumented {
+        binary: out,
+        fuel_reserve: code.reserve,
+    })
+}
+
+/// Split a binary into its sections after checking the header
+fn split_sections(binary: &[u8]) -> Option<Vec<Section<'_>>> {
+    if binary.len() < 8 || &binary[..4] != b"\0asm" || binary[4..8] != [1, 0, 0, 0] {
+        return None;
+    }
+    let mut sections = Vec::new();
+    let mut pos = 8;
+    while pos < binary.len() {
+        let id = binary[pos];
+        pos += 1;
+        let size = read_u32(binary, &mut pos)? as usize;
+        let end = pos.checked_add(size).filter(|end| *end <= binary.len())?;
+        sections.push(Section { id, body: &binary[pos..end] });
+        pos = end;
+    }
+    Some(sections)
+}
+
+/// Position of a non-custom section id in the required section order
+fn order(id: u8) -> u8 {
+    match id {
+        // tag section sits between memory and global
+        13 => 6,
+        6..=9 => id + 1,
+        // data count section comes before code and data
+        12 => 11,
+        10 | 11 => id + 2,
+        _ => id,
+    }
+}
+
+/// Check sequences inserted into function bodies
+struct Checks {
+    /// Countdown check (function entry, loop header, after a call)
+    countdown: Vec<u8>,
+    /// Index of the bulk check helper
+    bulk_helper: u32,
+}
+
+/// An instrumented code section
+struct Code {
+    body: Vec<u8>,
+    /// Countdown checks between two host calls
+    interval: i32,
+    /// Fuel needed for `interval` checks' worth of code
+    reserve: u64,
+}
+
+/// Rewrite the code section: checks in every body, helper bodies appended
+fn instrument_code(
+    body: &[u8],
+    checks: &Checks,
+    global: u32,
+    syscall: u32,
+    max_reserve: u64,
+) -> Option<Code> {
+    let reader = CodeSectionReader::new(body, 0).ok()?;
+    let mut out = Vec::with_capacity(body.len() + body.len() / 8);
+    write_u32(&mut out, reader.count().checked_add(2)?);
+
+    let bulk = bulk_helper(syscall);
+    // Bytes of code one pass between two checks can run, at most
+    let mut largest = 0;
+    for func in reader {
+        let func = func.ok()?;
+        let range = func.range();
+        let mut ops = func.get_operators_reader().ok()?;
+
+        // Locals are copied as-is; the first check goes right after them
+        let mut code = Vec::with_capacity(range.len() + checks.countdown.len());
+        let mut copied = ops.original_position();
+        let mut bulk_sites = 0;
+        code.extend_from_slice(&body[range.start..copied]);
+        code.extend_from_slice(&checks.countdown);
+        while !ops.eof() {
+            let start = ops.original_position();
+            let kind = match ops.read().ok()? {
+                Operator::Loop { .. } | Operator::Call { .. } | Operator::CallIndirect { .. } => {
+                    let pos = ops.original_position();
+                    code.extend_from_slice(&body[copied..pos]);
+                    code.extend_from_slice(&checks.countdown);
+                    copied = pos;
+                    continue;
+                }
+                Operator::MemoryFill { .. }
+                | Operator::MemoryCopy { .. }
+                | Operator::MemoryInit { .. } => BULK_BYTES,
+                Operator::TableFill { .. }
+                | Operator::TableCopy { .. }
+                | Operator::TableInit { .. }
+                | Operator::TableGrow { .. } => BULK_ELEMENTS,
+                Operator::MemoryGrow { .. } => BULK_PAGES,
+                _ => continue,
+            };
+            // `i32.const kind; call bulk_helper` before the instruction
+            code.extend_from_slice(&body[copied..start]);
+            code.push(0x41);
+            write_i32(&mut code, kind as i32);
+            code.push(0x10);
+            write_u32(&mut code, checks.bulk_helper);
+            copied = start;
+            bulk_sites += 1;
+        }
+        code.extend_from_slice(&body[copied..range.end]);
+        largest = largest.max(code.len() + bulk_sites * bulk.len());
+
+        write_u32(&mut out, code.len() as u32);
+        out.extend_from_slice(&code);
+    }
+
+    // Fit the interval so that its worth of code stays within the reserve;
+    // the countdown helper body is under 32 bytes
+    let segment = (largest as u64 + 32) * FUEL_PER_CODE_BYTE;
+    let interval = (max_reserve / segment).clamp(1, CHECK_INTERVAL as u64) as i32;
+
+    // Countdown helper: reset the countdown, then ask the host whether to pause
+    let mut helper = alloc::vec![0x00, 0x41];
+    write_i32(&mut helper, interval);
+    helper.push(0x24);
+    write_u32(&mut helper, global);
+    helper.push(0x41);
+    write_i32(&mut helper, SYS_PREEMPT as i32);
+    helper.extend_from_slice(&[0x41, 0x00, 0x41, 0x00, 0x41, 0x00, 0x10]);
+    write_u32(&mut helper, syscall);
+    helper.extend_from_slice(&[0x1A, 0x0B]);
+
+    write_u32(&mut out, helper.len() as u32);
+    out.extend_from_slice(&helper);
+    write_u32(&mut out, bulk.len() as u32);
+    out.extend_from_slice(&bulk);
+    Some(Code {
+        body: out,
+        interval,
+        reserve: interval as u64 * segment,
+    })
+}
+
+/// `(func (param $len i32) (param $kind i32) (result i32)
+///   zos_syscall(SYS_PREEMPT, $len, $kind, 0) drop $len)`
+fn bulk_helper(syscall: u32) -> Vec<u8> {
+    let mut helper = alloc::vec![0x00, 0x41];
+    write_i32(&mut helper, SYS_PREEMPT as i32);
+    helper.extend_from_slice(&[0x20, 0x00, 0x20, 0x01, 0x41, 0x00, 0x10]);
+    write_u32(&mut helper, syscall);
+    helper.extend_from_slice(&[0x1A, 0x20, 0x00, 0x0B]);
+    helper
+}
+
+/// `global.get g; i32.const 1; i32.sub; global.set g;
+///  global.get g; i32.eqz; if; call helper; end`
+fn check_sequence(global: u32, helper: u32) -> Vec<u8> {
+    let mut check = alloc::vec![0x23];
+    write_u32(&mut check, global);
+    check.extend_from_slice(&[0x41, 0x01, 0x6B, 0x24]);
+    write_u32(&mut check, global);
+    check.push(0x23);
+    write_u32(&mut check, global);
+    check.extend_from_slice(&[0x45, 0x04, 0x40, 0x10]);
+    write_u32(&mut check, helper);
+    check.push(0x0B);
+    check
+}
+
+/// `(global (mut i32) (i32.const interval))`
+fn global_entry(interval: i32) -> Vec<u8> {
+    let mut entry = alloc::vec![0x7F, 0x01, 0x41];
+    write_i32(&mut entry, interval);
+    entry.push(0x0B);
+    entry
+}
+
+/// Append one entry to a count-prefixed section body
+fn append_entry(body: &[u8], entry: &[u8]) -> Option<Vec<u8>> {
+    let mut pos = 0;
+    let count = read_u32(body, &mut pos)?;
+    let mut out = Vec::with_capacity(body.len() + entry.len() + 1);
+    write_u32(&mut out, count.checked_add(1)?);
+    out.extend_from_slice(&body[pos..]);
+    out.extend_from_slice(entry);
+    Some(out)
+}
+
+fn read_count(body: &[u8]) -> Option<u32> {
+    read_u32(body, &mut 0)
+}
+
+fn write_section(out: &mut Vec<u8>, id: u8, body: &[u8]) {
+    out.push(id);
+    write_u32(out, body.len() as u32);
+    out.extend_from_slice(body);
+}
+
+/// Read an unsigned LEB128 value
+fn read_u32(bytes: &[u8], pos: &mut usize) -> Option<u32> {
+    let mut result = 0u32;
+    for shift in (0..35).step_by(7) {
+        let byte = *bytes.get(*pos)?;
+        *pos += 1;
+        result |= ((byte & 0x7F) as u32).checked_shl(shift)?;
+        if byte & 0x80 == 0 {
+            return Some(result);
+        }
+    }
+    None
+}
+
+/// Write an unsigned LEB128 value
+fn write_u32(out: &mut Vec<u8>, mut value: u32) {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+/// Write a signed LEB128 value
+fn write_i32(out: &mut Vec<u8>, mut value: i32) {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0) {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::x86_64::wasm::{HostState, WasmRuntime};
+    use wasmi::{Module, Store, TypedFunc, TypedResumableCall};
+
+    /// `(import "env" "zos_syscall" (func (param i32 i32 i32 i32) (result i64)))`
+    /// `(func (export "_start") (loop (br 0)))`
+    const SPIN: &[u8] = &[
+        0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00, // header
+        0x01, 0x0C, 0x02, 0x60, 0x04, 0x7F, 0x7F, 0x7F, 0x7F, 0x01, 0x7E, 0x60, 0x00, 0x00,
+        0x02, 0x13, 0x01, 0x03, b'e', b'n', b'v', 0x0B, b'z', b'o', b's', b'_', b's', b'y',
+        b's', b'c', b'a', b'l', b'l', 0x00, 0x00, // import
+        0x03, 0x02, 0x01, 0x01, // function
+        0x07, 0x0A, 0x01, 0x06, b'_', b's', b't', b'a', b'r', b't', 0x00, 0x01, // export
+        0x0A, 0x09, 0x01, 0x07, 0x00, 0x03, 0x40, 0x0C, 0x00, 0x0B, 0x0B, // code
+    ];
+
+    // ========================================================================
+    // Instrumentation tests
+    // ========================================================================
+
+    #[test]
+    fn test_leb128_roundtrip() {
+        for value in [0, 1, 127, 128, 300, u32::MAX] {
+            let mut bytes = Vec::new();
+            write_u32(&mut bytes, value);
+            assert_eq!(read_u32(&bytes, &mut 0), Some(value));
+        }
+        let mut bytes = Vec::new();
+        write_i32(&mut bytes, -1);
+        assert_eq!(bytes, [0x7F]);
+    }
+
+    #[test]
+    fn test_module_without_syscall_import_is_unchanged() {
+        // SPIN without its import section
+        let mut binary = SPIN[..22].to_vec();
+        binary.extend_from_slice(&SPIN[43..]);
+        assert_eq!(instrument(&binary), None);
+        assert_eq!(instrument(b"not wasm"), None);
+    }
+
+    /// A module whose `_start` runs `body` in an endless loop, with one
+    /// mutable `i32` global and `pages` pages of memory
+    fn looping_module(body: &[u8], pages: u32) -> Vec<u8> {
+        // Header, type and import sections
+        let mut binary = SPIN[..43].to_vec();
+        write_section(&mut binary, SECTION_FUNCTION, &[0x01, 0x01]);
+        let mut memory = alloc::vec![0x01, 0x00];
+        write_u32(&mut memory, pages);
+        write_section(&mut binary, 5, &memory);
+        write_section(&mut binary, SECTION_GLOBAL, &[0x01, 0x7F, 0x01, 0x41, 0x00, 0x0B]);
+        write_section(&mut binary, 7, &SPIN[49..59]);
+        let mut code = alloc::vec![0x00, 0x03, 0x40];
+        code.extend_from_slice(body);
+        code.extend_from_slice(&[0x0C, 0x00, 0x0B, 0x0B]);
+        let mut section = alloc::vec![0x01];
+        write_u32(&mut section, code.len() as u32);
+        section.extend_from_slice(&code);
+        write_section(&mut binary, SECTION_CODE, &section);
+        binary
+    }
+
+    /// Instantiate an instrumented module the way `spawn` does
+    fn start(
+        runtime: &WasmRuntime,
+        instrumented: &Instrumented,
+    ) -> (Store<HostState>, TypedFunc<(), ()>) {
+        let module = Module::new(&runtime.engine, &instrumented.binary[..]).unwrap();
+        let mut host = HostState::new(1);
+        host.preempt_reserve = instrumented.fuel_reserve;
+        let mut store = Store::new(&runtime.engine, host);
+        let instance = runtime
+            .linker
+            .instantiate(&mut store, &module)
+            .unwrap()
+            .start(&mut store)
+            .unwrap();
+        let start = instance.get_typed_func::<(), ()>(&store, "_start").unwrap();
+        (store, start)
+    }
+
+    /// Run `_start` for one timeslice per entry of `fuel`, checking that
+    /// each ends in a preemption pause rather than a trap
+    fn assert_preempted(store: &mut Store<HostState>, start: TypedFunc<(), ()>, fuel: &[u64]) {
+        store.set_fuel(fuel[0]).unwrap();
+        let mut call = start.call_resumable(&mut *store, ());
+        for (slice, next) in fuel.iter().skip(1).chain([&0]).enumerate() {
+            let invocation = match call {
+                Ok(TypedResumableCall::Resumable(invocation)) => invocation,
+                _ => panic!("timeslice {} did not end in a resumable pause", slice),
+            };
+            assert!(store.data().yielded);
+            store.data_mut().yielded = false;
+            if *next == 0 {
+                return;
+            }
+            store.set_fuel(*next).unwrap();
+            call = invocation.resume(&mut *store, &[wasmi::Val::I64(0)]);
+        }
+    }
+
+    #[test]
+    fn test_spinning_process_is_preempted_and_resumed() {
+        let runtime = WasmRuntime::new();
+        let instrumented = instrument(SPIN).unwrap();
+        let (mut store, start) = start(&runtime, &instrumented);
+
+        // Below the reserve the first check pauses the invocation
+        assert_preempted(&mut store, start, &[instrumented.fuel_reserve / 2; 4]);
+    }
+
+    #[test]
+    fn test_large_loop_body_fits_the_reserve() {
+        // 10_000 x `global.get 0; i32.const 1; i32.add; global.set 0`
+        let body = [0x23, 0x00, 0x41, 0x01, 0x6A, 0x24, 0x00].repeat(10_000);
+        let binary = looping_module(&body, 0);
+
+        // A small budget keeps the test fast; a hundred iterations of this
+        // body would not fit in it, so the interval has to shrink
+        let budget = 1_000_000;
+        let instrumented = instrument_within(&binary, budget).unwrap();
+        let reserve = instrumented.fuel_reserve;
+        assert!(reserve <= budget);
+        assert!(reserve >= body.len() as u64);
+
+        // Checks that continue must leave enough for the next interval
+        let runtime = WasmRuntime::new();
+        let (mut store, start) = start(&runtime, &instrumented);
+        let fuel = [reserve, 2 * reserve, reserve + reserve / 2, 2 * reserve - 1];
+        assert_preempted(&mut store, start, &fuel);
+    }
+
+    #[test]
+    fn test_bulk_instruction_waits_for_its_fuel() {
+        // memory.fill(0, 0, 1 MiB) in a 16-page memory
+        let len = 1 << 20;
+        let mut body = alloc::vec![0x41, 0x00, 0x41, 0x00, 0x41];
+        write_i32(&mut body, len);
+        body.extend_from_slice(&[0xFC, 0x0B, 0x00]);
+        let binary = looping_module(&body, 16);
+
+        let instrumented = instrument(&binary).unwrap();
+        let fill = fuel_needed(0, BULK_BYTES, len as u32);
+        assert_eq!(fill, 16_384);
+        // The countdown alone would let far more fills through than fit
+        assert!(fill * CHECK_INTERVAL as u64 > instrumented.fuel_reserve + 3 * fill);
+
+        // Each timeslice ends partway through the fills it could afford
+        let runtime = WasmRuntime::new();
+        let (mut store, start) = start(&runtime, &instrumented);
+        let fuel = instrumented.fuel_reserve + 2 * fill + fill / 2;
+        assert_preempted(&mut store, start, &[fuel; 4]);
+    }
+
+    #[test]
+    fn test_uninstrumented_spin_runs_out_of_fuel() {
+        let runtime = WasmRuntime::new();
+        let module = Module::new(&runtime.engine, SPIN).unwrap();
+        let mut store = Store::new(&runtime.engine, HostState::new(1));
+        let instance = runtime
+            .linker
+            .instantiate(&mut store, &module)
+            .unwrap()
+            .start(&mut store)
+            .unwrap();
+        let start = instance.get_typed_func::<(), ()>(&store, "_start").unwrap();
+
+        store.set_fuel(1_000).unwrap();
+        assert!(start.call_resumable(&mut store, ()).is_err());
+    }
+}
@@ -6,6 +6,8 @@ use alloc::string::String;
 use wasmi::{Instance, Store, TypedFunc, TypedResumableInvocation};
 
 use super::host::HostState;
+use super::FUEL_PER_TIMESLICE;
+use crate::Priority;
 
 /// State of a WASM process
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -20,6 +22,39 @@ pub enum ProcessState {
     Terminated,
 }
 
+/// Budget/period scheduling context
+///
+/// A process may burn `budget` fuel every `period` scheduler ticks. Once
+/// the budget is spent the process is skipped until its next period, so a
+/// busy process cannot take time away from higher-priority ones.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SchedContext {
+    /// Fuel per period
+    pub budget: u64,
+    /// Period length in scheduler ticks
+    pub period: u64,
+}
+
+impl SchedContext {
+    /// Default context for a priority class
+    pub fn for_priority(priority: Priority) -> Self {
+        match priority {
+            Priority::System => Self {
+                budget: 8 * FUEL_PER_TIMESLICE,
+                period: 1,
+            },
+            Priority::Interactive => Self {
+                budget: 4 * FUEL_PER_TIMESLICE,
+                period: 1,
+            },
+            Priority::Background => Self {
+                budget: FUEL_PER_TIMESLICE,
+                period: 4,
+            },
+        }
+    }
+}
+
 /// A running WASM process
 pub struct WasmProcess {
     /// Process ID
@@ -38,6 +73,16 @@ pub struct WasmProcess {
     pub resumable: Option<TypedResumableInvocation<()>>,
     /// Memory size in bytes
     pub memory_size: usize,
+    /// Scheduling priority class
+    pub priority: Priority,
+    /// Budget and period
+    pub sched: SchedContext,
+    /// Fuel left in the current period
+    pub budget_left: u64,
+    /// Tick at which the current period started
+    pub period_start: u64,
+    /// CPU whose run queue holds this process
+    pub cpu: usize,
 }
 
 impl WasmProcess {
@@ -49,6 +94,24 @@ impl WasmProcess {
         }
     }
     
+    /// Start a new period if the current one is over
+    pub fn replenish(&mut self, tick: u64) {
+        if tick.saturating_sub(self.period_start) >= self.sched.period.max(1) {
+            self.period_start = tick;
+            self.budget_left = self.sched.budget;
+        }
+    }
+
+    /// Check if the process may run in the current period
+    pub fn has_budget(&self) -> bool {
+        self.budget_left > 0
+    }
+
+    /// Deduct fuel burned in a timeslice from the budget
+    pub fn charge(&mut self, fuel: u64) {
+        self.budget_left = self.budget_left.saturating_sub(fuel);
+    }
+
     /// Get access to the host state
     pub fn host_state(&self) -> &HostState {
         self.store.data()
@@ -60,6 +123,7 @@ impl WasmProcess {
     }
 }
 
-// SAFETY: WasmProcess is only accessed from a single-threaded kernel context
+// SAFETY: WasmProcess is only accessed through its Mutex in WasmRuntime, so
+// one CPU at a time
 unsafe impl Send for WasmProcess {}
 unsafe impl Sync for WasmProcess {}
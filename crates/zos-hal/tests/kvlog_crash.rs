@@ -0,0 +1,222 @@
+//! Crash-Consistency Tests for the Log-Structured KV Store
+//!
+//! Runs a workload (multi-key batches, overwrites, deletes, log wrap and
+//! garbage collection) and cuts power after every possible number of
+//! sector writes. After each cut the store must reopen to exactly the
+//! acknowledged batches, optionally plus the one that was in flight, and
+//! keep working.
+
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use zos_hal::kvlog::{BlockDevice, KvError, KvLog, Mutation, MIN_SECTORS, SECTOR_SIZE};
+
+/// Order in which the sectors of one write reach the disk
+#[derive(Clone, Copy, Debug)]
+enum Order {
+    Forward,
+    Reverse,
+}
+
+/// Disk contents, write count and power budget, shared with the store
+#[derive(Clone)]
+struct Disk {
+    data: Rc<RefCell<Vec<u8>>>,
+    writes: Rc<Cell<usize>>,
+    /// Sector writes left before the cut (`None` = never)
+    budget: Rc<Cell<Option<usize>>>,
+}
+
+impl Disk {
+    fn new(image: Vec<u8>) -> Self {
+        Self {
+            data: Rc::new(RefCell::new(image)),
+            writes: Rc::new(Cell::new(0)),
+            budget: Rc::new(Cell::new(None)),
+        }
+    }
+
+    fn device(&self, order: Order) -> CrashDevice {
+        CrashDevice { disk: self.clone(), order }
+    }
+}
+
+/// In-memory disk that loses power once its budget runs out
+struct CrashDevice {
+    disk: Disk,
+    order: Order,
+}
+
+impl BlockDevice for CrashDevice {
+    fn sector_count(&self) -> u64 {
+        (self.disk.data.borrow().len() / SECTOR_SIZE) as u64
+    }
+
+    fn read(&self, sector: u64, buf: &mut [u8]) -> Result<(), KvError> {
+        let data = self.disk.data.borrow();
+        let start = sector as usize * SECTOR_SIZE;
+        buf.copy_from_slice(data.get(start..start + buf.len()).ok_or(KvError::Io)?);
+        Ok(())
+    }
+
+    fn write(&mut self, sector: u64, bytes: &[u8]) -> Result<(), KvError> {
+        let count = bytes.len() / SECTOR_SIZE;
+        let mut order: Vec<usize> = (0..count).collect();
+        if let Order::Reverse = self.order {
+            order.reverse();
+        }
+        for i in order {
+            if let Some(budget) = self.disk.budget.get() {
+                if budget == 0 {
+                    return Err(KvError::Io);
+                }
+                self.disk.budget.set(Some(budget - 1));
+            }
+            let start = (sector as usize + i) * SECTOR_SIZE;
+            self.disk.data.borrow_mut()[start..start + SECTOR_SIZE]
+                .copy_from_slice(&bytes[i * SECTOR_SIZE..(i + 1) * SECTOR_SIZE]);
+            self.disk.writes.set(self.disk.writes.get() + 1);
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), KvError> {
+        match self.disk.budget.get() {
+            Some(0) => Err(KvError::Io),
+            _ => Ok(()),
+        }
+    }
+}
+
+type State = BTreeMap<String, Vec<u8>>;
+
+const DEVICE_SECTORS: usize = MIN_SECTORS as usize + 40;
+
+fn apply(state: &mut State, batch: &[Mutation]) {
+    for mutation in batch {
+        match mutation {
+            Mutation::Put(key, value) => {
+                state.insert(key.clone(), value.clone());
+            }
+            Mutation::Delete(key) => {
+                state.remove(key);
+            }
+        }
+    }
+}
+
+fn value(seed: usize, len: usize) -> Vec<u8> {
+    (0..len).map(|i| (seed * 31 + i * 7) as u8).collect()
+}
+
+/// Batches that overwrite a small key set, so the log fills with garbage
+/// and has to wrap and collect
+fn workload() -> Vec<Vec<Mutation>> {
+    let key = |k: usize| format!("/data/key{}", k);
+    (0..200usize)
+        .map(|i| match i % 5 {
+            0 => vec![Mutation::Put(key(i % 4), value(i, 100))],
+            1 => vec![
+                Mutation::Put(key(i % 4), value(i, 3000)),
+                Mutation::Put(key((i + 1) % 4), value(i + 1, 20)),
+                Mutation::Delete(key((i + 2) % 4)),
+            ],
+            2 => vec![Mutation::Put(key(4), value(i, 9000))],
+            3 => vec![Mutation::Delete(key(i % 4)), Mutation::Put(key(5), value(i, 700))],
+            _ => vec![Mutation::Put(key(6), vec![]), Mutation::Put(key(i % 3), value(i, 1500))],
+        })
+        .collect()
+}
+
+/// Everything stored, read back through the log
+fn contents<D: BlockDevice>(log: &KvLog<D>) -> State {
+    let keys: Vec<String> = log.keys("").cloned().collect();
+    keys.into_iter()
+        .map(|key| {
+            let value = log.get(&key).unwrap().expect("indexed key has a value");
+            (key, value)
+        })
+        .collect()
+}
+
+/// Reopen `disk` after a cut and check it holds `acked`, optionally with
+/// `in_flight` applied, then check the store still takes writes
+fn check_recovery(disk: &Disk, order: Order, acked: &State, in_flight: &[Mutation], what: &str) {
+    disk.budget.set(None);
+    let (mut log, _) = KvLog::open(disk.device(order))
+        .unwrap_or_else(|e| panic!("{}: reopen failed: {}", what, e));
+    let recovered = contents(&log);
+    let mut with_in_flight = acked.clone();
+    apply(&mut with_in_flight, in_flight);
+    assert!(
+        recovered == *acked || recovered == with_in_flight,
+        "{}: recovered keys {:?}, acked keys {:?}",
+        what,
+        recovered.keys().collect::<Vec<_>>(),
+        acked.keys().collect::<Vec<_>>(),
+    );
+
+    let mut expected = recovered;
+    let batch = [
+        Mutation::Put(String::from("/after/crash"), value(expected.len(), 600)),
+        Mutation::Delete(String::from("/data/key4")),
+    ];
+    log.commit(&batch).unwrap();
+    apply(&mut expected, &batch);
+    drop(log);
+
+    let (log, _) = KvLog::open(disk.device(order)).unwrap();
+    assert_eq!(contents(&log), expected, "{}: second reopen", what);
+}
+
+/// Run the workload, and for every commit cut power after each possible
+/// number of its sector writes (on a copy of the disk as it was before)
+fn check_every_cut(order: Order) {
+    let blank = vec![0u8; DEVICE_SECTORS * SECTOR_SIZE];
+
+    // Formatting a blank disk
+    for cut in 0..2 {
+        let disk = Disk::new(blank.clone());
+        disk.budget.set(Some(cut));
+        assert!(KvLog::open(disk.device(order)).is_err());
+        check_recovery(&disk, order, &State::new(), &[], &format!("format, cut after {}", cut));
+    }
+
+    let disk = Disk::new(blank);
+    let (mut log, _) = KvLog::open(disk.device(order)).unwrap();
+    let mut acked = State::new();
+
+    for (i, batch) in workload().iter().enumerate() {
+        let image = disk.data.borrow().clone();
+        let before = disk.writes.get();
+        log.commit(batch).unwrap();
+        let writes = disk.writes.get() - before;
+
+        for cut in 0..writes {
+            let trial = Disk::new(image.clone());
+            let (mut trial_log, _) = KvLog::open(trial.device(order)).unwrap();
+            trial.budget.set(Some(cut));
+            assert_eq!(trial_log.commit(batch), Err(KvError::Io));
+            drop(trial_log);
+            let what = format!("batch {} cut after {} of {} writes ({:?})", i, cut, writes, order);
+            check_recovery(&trial, order, &acked, batch, &what);
+        }
+        apply(&mut acked, batch);
+    }
+
+    // The workload went around the log at least twice
+    assert!(disk.writes.get() > 2 * DEVICE_SECTORS);
+    drop(log);
+    check_recovery(&disk, order, &acked, &[], "end of workload");
+}
+
+#[test]
+fn test_cut_at_every_sector_forward() {
+    check_every_cut(Order::Forward);
+}
+
+#[test]
+fn test_cut_at_every_sector_reverse() {
+    check_every_cut(Order::Reverse);
+}
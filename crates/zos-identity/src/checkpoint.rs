@@ -0,0 +1,57 @@
+//! Machine-key signing for Axiom CommitLog checkpoints.
+//!
+//! Bridges the identity layer's machine keys to the `zos-axiom` checkpoint
+//! traits, so the kernel can attest its CommitLog head without depending on
+//! zid-crypto directly.
+//!
+//! A third-party auditor only needs the machine's signing public key (as
+//! recorded in its `MachineKeyRecord`) and [`MachineCheckpointVerifier`].
+
+use zos_axiom::{CheckpointSigner, CheckpointVerifier};
+
+use crate::crypto::{sign_message, verify_signature, MachineKeyPair, ZidMachineKeyCapabilities};
+use crate::error::KeyError;
+
+/// Signs CommitLog checkpoints with a machine key's Ed25519 signing component.
+pub struct MachineCheckpointSigner {
+    keypair: MachineKeyPair,
+}
+
+impl MachineCheckpointSigner {
+    /// Wrap a machine keypair for checkpoint signing.
+    ///
+    /// The keypair should carry the SIGN capability.
+    pub fn new(keypair: MachineKeyPair) -> Self {
+        Self { keypair }
+    }
+
+    /// Rebuild the signer from stored seed material.
+    ///
+    /// Used at boot by the browser supervisor, which keeps the machine's
+    /// checkpoint key as a pair of 32-byte seeds in the keystore, under the
+    /// kernel's reserved namespace and apart from the CommitLog it signs.
+    pub fn from_seeds(signing_seed: &[u8; 32], encryption_seed: &[u8; 32]) -> Result<Self, KeyError> {
+        MachineKeyPair::from_seeds(signing_seed, encryption_seed, ZidMachineKeyCapabilities::all())
+            .map(Self::new)
+            .map_err(|e| KeyError::CryptoError(alloc::format!("Failed to build checkpoint key: {:?}", e)))
+    }
+}
+
+impl CheckpointSigner for MachineCheckpointSigner {
+    fn public_key(&self) -> [u8; 32] {
+        self.keypair.signing_public_key()
+    }
+
+    fn sign(&self, message: &[u8]) -> [u8; 64] {
+        sign_message(&self.keypair.signing_key_pair(), message)
+    }
+}
+
+/// Verifies CommitLog checkpoint signatures made by a machine key.
+pub struct MachineCheckpointVerifier;
+
+impl CheckpointVerifier for MachineCheckpointVerifier {
+    fn verify(&self, public_key: &[u8; 32], message: &[u8], signature: &[u8; 64]) -> bool {
+        verify_signature(public_key, message, signature).is_ok()
+    }
+}
@@ -59,6 +59,7 @@
 #![no_std]
 extern crate alloc;
 
+pub mod checkpoint;
 pub mod client;
 pub mod crypto;
 pub mod error;
@@ -71,6 +72,7 @@ pub mod session;
 pub mod types;
 
 // Re-export main types
+pub use checkpoint::{MachineCheckpointSigner, MachineCheckpointVerifier};
 pub use error::{IdentityError, KeyError, SessionError, UserError};
 pub use keystore::{
     EncryptedPrivateKeys, KeyDerivation, KeyScheme, LocalKeyStore, MachineKeyCapabilities,
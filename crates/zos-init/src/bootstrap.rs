@@ -85,22 +85,11 @@ impl Init {
                 self.log(&format!("Spawning Terminal (PID 7) for QEMU console..."));
                 self.log(&format!("Loaded terminal ({} bytes)", binary.len()));
 
-                match syscall::spawn_process("terminal", &binary) {
+                match syscall::spawn_process("terminal", &binary, syscall::priority::INTERACTIVE) {
                     Ok(pid) => {
                         self.log(&format!("Spawned terminal as PID {}", pid));
 
-                        // Setup endpoint and capability for terminal
-                        if let Ok((endpoint_id, slot)) = syscall::create_endpoint_for(pid) {
-                            self.log(&format!(
-                                "DEBUG: create_endpoint_for({}) returned endpoint={}, slot={}",
-                                pid, endpoint_id, slot
-                            ));
-                            self.service_cap_slots.insert(pid, slot);
-                            self.log(&format!(
-                                "Created endpoint {} for terminal (cap slot {})",
-                                endpoint_id, slot
-                            ));
-                        }
+                        self.setup_qemu_terminal_slots(pid);
                     }
                     Err(e) => {
                         self.log(&format!("Failed to spawn terminal: error {}", e));
@@ -118,6 +107,41 @@ impl Init {
         }
     }
 
+    /// Give the QEMU terminal the same slot layout the browser supervisor
+    /// sets up for apps: output endpoint (slot 0), input endpoint (slot 1)
+    /// and a send-only capability to Init's endpoint (slot 2), which it uses
+    /// for `shutdown`/`reboot`.
+    fn setup_qemu_terminal_slots(&mut self, pid: u32) {
+        if let Err(e) = syscall::create_endpoint_for(pid) {
+            self.log(&format!("Failed to create terminal output endpoint: error {}", e));
+            return;
+        }
+
+        match syscall::create_endpoint_for(pid) {
+            Ok((endpoint_id, slot)) => {
+                self.service_cap_slots.insert(pid, slot);
+                self.log(&format!(
+                    "Created endpoint {} for terminal (cap slot {})",
+                    endpoint_id, slot
+                ));
+            }
+            Err(e) => {
+                self.log(&format!("Failed to create terminal input endpoint: error {}", e));
+                return;
+            }
+        }
+
+        match syscall::cap_grant(self.endpoint_slot, pid, syscall::Permissions::write_only()) {
+            Ok(slot) if slot == syscall::INIT_ENDPOINT_SLOT => {}
+            Ok(slot) => self.log(&format!(
+                "Terminal got Init's endpoint at slot {}, expected {}",
+                slot,
+                syscall::INIT_ENDPOINT_SLOT
+            )),
+            Err(e) => self.log(&format!("Failed to grant Init's endpoint to terminal: error {}", e)),
+        }
+    }
+
     /// Spawn a service using the pure microkernel approach.
     ///
     /// This method tries the pure microkernel path first (QEMU) and falls back
@@ -129,7 +153,7 @@ impl Init {
                 // QEMU path: Got binary, spawn directly via syscall
                 self.log(&format!("Loaded {} ({} bytes)", name, binary.len()));
                 
-                match syscall::spawn_process(name, &binary) {
+                match syscall::spawn_process(name, &binary, syscall::priority::SYSTEM) {
                     Ok(pid) => {
                         self.log(&format!("Spawned {} as PID {}", name, pid));
                         
@@ -43,6 +43,27 @@ impl Init {
         syscall::debug(&format!("INIT:SPAWN:{}", name));
     }
 
+    /// Handle power-off/reboot request (e.g. terminal `shutdown`/`reboot`).
+    ///
+    /// Init passes the request on to the kernel, which then sends
+    /// MSG_SUPERVISOR_SHUTDOWN back here so services are stopped before
+    /// power is cut. Holding a capability to Init's endpoint is the authority.
+    ///
+    /// Payload: [reboot: u8]
+    pub fn handle_shutdown_request(&mut self, msg: &syscall::ReceivedMessage) {
+        let reboot = msg.data.first().is_some_and(|&b| b != 0);
+
+        self.log(&format!(
+            "{} request from PID {}",
+            if reboot { "Reboot" } else { "Power-off" },
+            msg.from_pid
+        ));
+
+        if let Err(e) = syscall::shutdown(reboot) {
+            self.log(&format!("Shutdown refused by kernel: error {}", e));
+        }
+    }
+
     /// Handle service capability pre-registration from supervisor.
     ///
     /// The supervisor sends this BEFORE spawning the worker to pre-register
@@ -135,6 +135,46 @@ impl Init {
         }
     }
 
+    /// Handle supervisor request to stop all services before power-off.
+    ///
+    /// Init terminates every process it started, newest first, so apps go
+    /// before the services they depend on. It then tells the supervisor it
+    /// is done; the supervisor flushes the CommitLog and storage itself.
+    ///
+    /// Payload: [reboot: u8]
+    pub fn handle_supervisor_shutdown(&mut self, msg: &syscall::ReceivedMessage) {
+        // Verify sender is supervisor (PID 0)
+        if msg.from_pid != 0 {
+            self.log(&format!(
+                "SECURITY: Shutdown request from non-supervisor PID {}",
+                msg.from_pid
+            ));
+            return;
+        }
+
+        let reboot = msg.data.first().is_some_and(|&b| b != 0);
+        self.log(if reboot {
+            "System reboot requested, stopping services"
+        } else {
+            "System power-off requested, stopping services"
+        });
+
+        let pids: Vec<u32> = self.service_cap_slots.keys().rev().copied().collect();
+        for pid in pids {
+            match syscall::kill(pid) {
+                Ok(()) => self.log(&format!("Stopped PID {}", pid)),
+                Err(e) => self.log(&format!("Failed to stop PID {}: error {}", pid, e)),
+            }
+        }
+        self.services.clear();
+        self.service_cap_slots.clear();
+        self.service_vfs_slots.clear();
+        self.pending_deliveries.clear();
+
+        self.log("All services stopped");
+        syscall::debug("INIT:SHUTDOWN_READY");
+    }
+
     /// Handle supervisor request to deliver an IPC message to a process.
     ///
     /// The supervisor routes messages that need capability-checked delivery.
@@ -17,6 +17,7 @@
 //! - `MSG_LOOKUP_SERVICE (0x1001)`: Look up a service by name
 //! - `MSG_LOOKUP_RESPONSE (0x1002)`: Response to a lookup request
 //! - `MSG_SPAWN_SERVICE (0x1003)`: Request init to spawn a new service
+//! - `MSG_SHUTDOWN_REQUEST (0x1009)`: Request a power-off or reboot
 
 #![cfg_attr(target_arch = "wasm32", no_std)]
 
@@ -66,8 +67,8 @@ mod registry;
 
 pub use zos_process::{
     MSG_LOOKUP_RESPONSE, MSG_LOOKUP_SERVICE, MSG_REGISTER_SERVICE, MSG_SERVICE_READY,
-    MSG_SPAWN_RESPONSE, MSG_SPAWN_SERVICE, MSG_SUPERVISOR_CONSOLE_INPUT,
-    MSG_SUPERVISOR_IPC_DELIVERY, MSG_SUPERVISOR_KILL_PROCESS,
+    MSG_SHUTDOWN_REQUEST, MSG_SPAWN_RESPONSE, MSG_SPAWN_SERVICE, MSG_SUPERVISOR_CONSOLE_INPUT,
+    MSG_SUPERVISOR_IPC_DELIVERY, MSG_SUPERVISOR_KILL_PROCESS, MSG_SUPERVISOR_SHUTDOWN,
 };
 
 // Additional Init-specific constants from zos-ipc
@@ -201,10 +202,12 @@ impl Init {
             MSG_LOOKUP_SERVICE => self.handle_lookup(msg),
             MSG_SERVICE_READY => self.handle_ready(msg),
             MSG_SPAWN_SERVICE => self.handle_spawn_request(msg),
+            MSG_SHUTDOWN_REQUEST => self.handle_shutdown_request(msg),
 
             // Supervisor → Init protocol
             MSG_SUPERVISOR_CONSOLE_INPUT => self.handle_supervisor_console_input(msg),
             MSG_SUPERVISOR_KILL_PROCESS => self.handle_supervisor_kill_process(msg),
+            MSG_SUPERVISOR_SHUTDOWN => self.handle_supervisor_shutdown(msg),
             MSG_SUPERVISOR_IPC_DELIVERY => {
                 self.log(&format!("AGENT_LOG:dispatching_to_ipc_delivery_handler:tag=0x{:x}", msg.tag));
                 self.handle_supervisor_ipc_delivery(msg);
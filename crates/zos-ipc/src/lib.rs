@@ -16,9 +16,11 @@
 //! | 0x30-0x3F | Capability (grant, revoke, inspect) |
 //! | 0x40-0x4F | IPC (send, receive, call, reply) |
 //! | 0x50-0x5F | System (list processes) |
+//! | 0x60-0x6F | Memory (shared regions) |
 //! | 0x70-0x7F | Platform Storage (async ops) |
 //! | 0x80-0x8F | Keystore (async key storage) |
 //! | 0x90-0x9F | Network (async HTTP) |
+//! | 0xA0-0xAF | Devices (IRQs, I/O ports) |
 //!
 //! # IPC Message Range Allocation
 //!
@@ -98,6 +100,10 @@ pub enum ObjectType {
     Identity = 10,
     /// Cryptographic keystore - for secure key storage
     Keystore = 11,
+    /// Notification - a word of signal bits for lightweight wakeups
+    Notification = 12,
+    /// Reply - single-use right to answer one outstanding Call
+    Reply = 13,
 }
 
 impl ObjectType {
@@ -117,6 +123,8 @@ impl ObjectType {
             9 => Some(ObjectType::Filesystem),
             10 => Some(ObjectType::Identity),
             11 => Some(ObjectType::Keystore),
+            12 => Some(ObjectType::Notification),
+            13 => Some(ObjectType::Reply),
             _ => None,
         }
     }
@@ -135,6 +143,8 @@ impl ObjectType {
             ObjectType::Filesystem => "Filesystem",
             ObjectType::Identity => "Identity",
             ObjectType::Keystore => "Keystore",
+            ObjectType::Notification => "Notification",
+            ObjectType::Reply => "Reply",
         }
     }
 }
@@ -165,7 +175,13 @@ pub mod syscall {
 
     // === Process (0x10 - 0x1F) ===
     /// Create an IPC endpoint
+    /// arg1 = queue capacity (0 = 64, at most 1024),
+    /// arg2 = `ENDPOINT_BLOCK_WHEN_FULL` to make senders to a full queue
+    /// retry instead of failing with `QUEUE_FULL`
     pub const SYS_CREATE_ENDPOINT: u32 = 0x11;
+    /// Flag in arg2 of `SYS_CREATE_ENDPOINT` selecting the blocking
+    /// overflow policy
+    pub const ENDPOINT_BLOCK_WHEN_FULL: u32 = 1;
     /// Delete an endpoint
     pub const SYS_DELETE_ENDPOINT: u32 = 0x12;
     /// Kill a process (requires Process capability with kill permission)
@@ -181,9 +197,32 @@ pub mod syscall {
     /// Returns: Binary data in syscall result buffer, or error code
     pub const SYS_LOAD_BINARY: u32 = 0x16;
     /// Spawn a process from binary data (Init-only).
-    /// Payload: [name_len: u32 (LE), name: [u8], binary: [u8]]
+    /// arg2 = scheduling class (see `priority`)
+    /// arg3 = `quota::SPAWN_WITH_QUOTA` if a quota record follows the name
+    /// Payload: [name_len: u32 (LE), name: [u8], quota?: [u8; 40], binary: [u8]]
     /// Returns: PID on success (>0), negative error code on failure
     pub const SYS_SPAWN_PROCESS: u32 = 0x17;
+    /// Power off or reboot the machine (Init-only).
+    /// arg1 = 1 to reboot, 0 to power off
+    /// Returns 0 once the request is accepted; the kernel then asks Init to
+    /// stop services before cutting power. NOT_SUPPORTED on the browser.
+    pub const SYS_SHUTDOWN: u32 = 0x18;
+    /// Replace a process's resource quota (Init-only).
+    /// arg1 = target PID
+    /// Payload: quota record (see `quota`)
+    /// Returns 0 on success; the new quota may not exceed Init's own.
+    pub const SYS_SET_QUOTA: u32 = 0x19;
+    /// Reap a dead child without blocking.
+    /// arg1 = child PID, or 0 for any child
+    /// Returns the child's PID and writes `[pid: u64, kind: u8, value: u64]`
+    /// (as in `MSG_CHILD_EXITED`) to the syscall result buffer. WOULD_BLOCK
+    /// if the matching children are all alive, NOT_FOUND if there are none.
+    pub const SYS_WAIT_CHILD: u32 = 0x1A;
+    /// Kill a process and all of its descendants.
+    /// arg1 = PID of the subtree root (the caller or one of its descendants;
+    /// Init may name any process)
+    /// Returns the number of processes killed.
+    pub const SYS_KILL_TREE: u32 = 0x1B;
 
     // === Capability (0x30 - 0x3F) ===
     /// Grant a capability to another process
@@ -195,26 +234,103 @@ pub mod syscall {
     /// Inspect a capability (get info)
     pub const SYS_CAP_INSPECT: u32 = 0x33;
     /// Derive a new capability with reduced permissions
+    ///
+    /// args: [slot, perms]. Optional payload: [badge: u64]. A non-zero badge
+    /// derives a badged endpoint capability from an unbadged one.
     pub const SYS_CAP_DERIVE: u32 = 0x34;
     /// List all capabilities
     pub const SYS_CAP_LIST: u32 = 0x35;
+    /// Grant a capability that expires after a lifetime
+    ///
+    /// args: [from_slot, to_pid, perms], payload: [ttl_ns: u64]. The copy
+    /// never outlives its source; it is removed with a `MSG_CAP_REVOKED`
+    /// (`revoke_reason::EXPIRED`) once the TTL has passed.
+    pub const SYS_CAP_GRANT_TTL: u32 = 0x36;
+    /// Derive a capability that expires after a lifetime
+    ///
+    /// args: [slot, perms], payload: [ttl_ns: u64]
+    pub const SYS_CAP_DERIVE_TTL: u32 = 0x37;
 
     // === IPC (0x40 - 0x4F) ===
     /// Send a message
     pub const SYS_SEND: u32 = 0x40;
     /// Receive a message
     pub const SYS_RECV: u32 = 0x41;
-    /// Call (send + wait for reply)
+    /// Call (send + wait for reply). The endpoint owner receives a one-shot
+    /// reply capability naming the caller, and the caller parks until the
+    /// reply arrives.
+    /// arg1 = endpoint slot, arg2 = tag. Payload: the request
+    /// Returns 1 with the reply message in the result buffer, or -1 if the
+    /// server died or dropped the reply capability
     pub const SYS_CALL: u32 = 0x42;
-    /// Reply to a call
+    /// Reply to a call, consuming the reply capability for the caller
+    /// arg1 = caller PID, arg2 = tag. Payload: the reply
     pub const SYS_REPLY: u32 = 0x43;
     /// Send with capability transfer
     pub const SYS_SEND_CAP: u32 = 0x44;
+    /// Receive from the first ready of several endpoints, parking the caller
+    /// until a message arrives or the timeout passes.
+    /// Payload: [timeout_ns: u64 (LE, 0 = none), slots: [u32 (LE)]]
+    /// (at most `wait::MAX_WAIT_SLOTS`)
+    /// Returns 1 with [slot: u32, message] in the result buffer, or
+    /// `syscall_error::TIMED_OUT`
+    pub const SYS_RECV_TIMEOUT: u32 = 0x45;
+    /// Send a request and park until a reply arrives or the timeout passes.
+    /// arg1 = endpoint slot, arg2 = tag, arg3 = slot the reply arrives on
+    /// Payload: [timeout_ns: u64 (LE, 0 = none), request: [u8]]
+    /// Returns 1 with the reply message in the result buffer, or
+    /// `syscall_error::TIMED_OUT`
+    pub const SYS_CALL_TIMEOUT: u32 = 0x46;
+    /// Create a notification (a word of signal bits)
+    /// Returns packed (slot << 32) | notification_id, like endpoints
+    pub const SYS_NOTIFY_CREATE: u32 = 0x47;
+    /// OR bits into a notification's word (needs write)
+    /// arg1 = notification slot, arg2 = bits low word, arg3 = bits high word
+    pub const SYS_NOTIFY_SIGNAL: u32 = 0x48;
+    /// Take a notification's word, parking until it is non-zero or the
+    /// timeout passes (needs read)
+    /// arg1 = notification slot, arg2/arg3 = timeout_ns low/high word
+    /// (0 = none)
+    /// Returns 0 with [bits: u64 (LE)] in the result buffer, or
+    /// `syscall_error::TIMED_OUT`
+    pub const SYS_NOTIFY_WAIT: u32 = 0x49;
+    /// Take a notification's word without blocking (needs read)
+    /// arg1 = notification slot
+    /// Returns 0 with [bits: u64 (LE)] in the result buffer; bits may be 0
+    pub const SYS_NOTIFY_POLL: u32 = 0x4A;
 
     // === System (0x50 - 0x5F) ===
     /// List all processes (supervisor only)
+    /// Returns: [count: u32, then per process { pid: u32, name_len: u16,
+    /// name: [u8], quota: [u8; 40] }] (see `quota`)
     pub const SYS_PS: u32 = 0x50;
 
+    // === Memory (0x60 - 0x6F) ===
+    // Shared regions for bulk data. A region is granted over IPC like any
+    // capability; each holder maps it as a window in its linear memory
+    // that is refreshed from, and flushed to, the region explicitly.
+    /// Create a zeroed region, rounded up to whole pages
+    /// arg1 = size in bytes (at most 16 MiB)
+    /// Returns the slot of a full capability for the region
+    pub const SYS_REGION_CREATE: u32 = 0x60;
+    /// Map a region and copy its contents into the window (cap needs read,
+    /// and write too for a writable mapping)
+    /// arg1 = region slot, arg2 = window address,
+    /// arg3 = window length | `REGION_MAP_WRITABLE` for a writable mapping
+    /// Returns the region size; a window shorter than that is refused
+    pub const SYS_REGION_MAP: u32 = 0x61;
+    /// Flag in arg3 of `SYS_REGION_MAP` asking for a writable mapping
+    pub const REGION_MAP_WRITABLE: u32 = 1 << 31;
+    /// Drop the caller's mapping of a region
+    /// arg1 = region slot
+    pub const SYS_REGION_UNMAP: u32 = 0x62;
+    /// Publish part of the window into the region (writable mapping only)
+    /// arg1 = region slot, arg2 = offset, arg3 = length
+    pub const SYS_REGION_FLUSH: u32 = 0x63;
+    /// Copy part of the region into the window
+    /// arg1 = region slot, arg2 = offset, arg3 = length
+    pub const SYS_REGION_REFRESH: u32 = 0x64;
+
     // === Platform Storage (0x70 - 0x7F) ===
     // HAL-level key-value storage operations. VfsService uses these for persistence.
     // Applications should use zos_vfs::VfsClient. All storage syscalls are ASYNC.
@@ -252,6 +368,37 @@ pub mod syscall {
     // Network syscalls are ASYNC and return a request_id immediately.
     /// Start async HTTP fetch (returns request_id)
     pub const SYS_NETWORK_FETCH: u32 = 0x90;
+
+    // === Devices (0xA0 - 0xAF) ===
+    // IRQ and I/O port capabilities for user-space drivers. Init mints them
+    // and the permission service grants them to drivers.
+    /// Mint an IRQ capability into Init's CSpace (Init-only)
+    /// arg1 = ISA IRQ line
+    /// Returns the capability slot
+    pub const SYS_IRQ_CAP_CREATE: u32 = 0xA0;
+    /// Mint an I/O port range capability into Init's CSpace (Init-only)
+    /// arg1 = first port, arg2 = number of ports
+    /// Returns the capability slot
+    pub const SYS_IO_PORT_CAP_CREATE: u32 = 0xA1;
+    /// Bind an IRQ line to a notification or endpoint (IRQ cap needs read,
+    /// target needs write)
+    /// arg1 = IRQ slot, arg2 = target slot
+    /// Payload: [bits: u64 (LE)] to OR into a notification target; empty
+    /// for an endpoint target, which receives `kernel::MSG_IRQ` messages
+    pub const SYS_IRQ_BIND: u32 = 0xA2;
+    /// Acknowledge an interrupt, unmasking the line (IRQ cap needs read)
+    /// arg1 = IRQ slot
+    pub const SYS_IRQ_ACK: u32 = 0xA3;
+    /// Stop delivering an IRQ line
+    /// arg1 = IRQ slot
+    pub const SYS_IRQ_UNBIND: u32 = 0xA4;
+    /// Read from an I/O port (port cap needs read)
+    /// arg1 = port slot, arg2 = port, arg3 = width in bytes (1, 2 or 4)
+    /// Returns the value read (>= 0) or a negative error code
+    pub const SYS_IO_IN: u32 = 0xA5;
+    /// Write to an I/O port (port cap needs write)
+    /// arg1 = port slot, arg2 = (width << 16) | port, arg3 = value
+    pub const SYS_IO_OUT: u32 = 0xA6;
 }
 
 // Re-export syscall constants at crate root for convenience
@@ -400,6 +547,11 @@ pub mod init {
     /// arriving after spawn can be delivered without waiting for async grant.
     /// Payload: [service_pid: u32, cap_slot: u32]
     pub const MSG_SERVICE_CAP_PREREGISTER: u32 = 0x1008;
+
+    /// Request a power-off or reboot (terminal → init).
+    /// Init forwards it to the kernel via SYS_SHUTDOWN.
+    /// Payload: [reboot: u8]
+    pub const MSG_SHUTDOWN_REQUEST: u32 = 0x1009;
 }
 
 // =============================================================================
@@ -447,7 +599,7 @@ pub mod supervisor {
     pub const MSG_SUPERVISOR_IPC_DELIVERY: u32 = 0x2003;
 
     // =========================================================================
-    // Init-Driven Spawn Protocol (0x2004 - 0x200F)
+    // Init-Driven Spawn Protocol (0x2004 - 0x2009)
     // =========================================================================
     // These messages implement the Init-driven spawn protocol where ALL process
     // lifecycle operations flow through Init. This ensures:
@@ -487,6 +639,16 @@ pub mod supervisor {
     /// Payload: [success: u8, new_slot: u32]
     pub const MSG_SUPERVISOR_CAP_RESPONSE: u32 = 0x2009;
 
+    // =========================================================================
+    // Shutdown (0x200A)
+    // =========================================================================
+
+    /// Supervisor requests Init to stop all services before power-off or reboot.
+    /// Payload: [reboot: u8] (0 = power off, 1 = reboot)
+    /// Init terminates the processes it started, newest first, then reports
+    /// `INIT:SHUTDOWN_READY` on the debug channel.
+    pub const MSG_SUPERVISOR_SHUTDOWN: u32 = 0x200A;
+
     /// Supervisor requests PermissionService to revoke a capability from a process.
     /// Payload: [target_pid: u32, slot: u32, reason: u8]
     ///
@@ -534,6 +696,51 @@ pub mod kernel {
     /// Notification that a capability was revoked from this process.
     /// Payload: [slot: u32, object_type: u8, object_id: u64, reason: u8]
     pub const MSG_CAP_REVOKED: u32 = 0x3010;
+
+    /// Interrupt raised on an IRQ line bound to the receiving endpoint.
+    /// The line stays masked until the driver acknowledges it.
+    /// Payload: [irq: u8]
+    pub const MSG_IRQ: u32 = 0x3011;
+
+    /// A child of the receiving process died and awaits reaping.
+    /// Payload: [pid: u64, kind: u8, value: u64] (see `exit_kind`)
+    pub const MSG_CHILD_EXITED: u32 = 0x3012;
+}
+
+/// How a process died, as reported in `MSG_CHILD_EXITED`.
+pub mod exit_kind {
+    /// Process exited; value is the exit code (sign-extended).
+    pub const EXITED: u8 = 1;
+    /// Process was killed; value is the killer's PID.
+    pub const KILLED: u8 = 2;
+    /// Process faulted; value is the fault reason.
+    pub const FAULTED: u8 = 3;
+}
+
+/// Scheduling classes for `SYS_SPAWN_PROCESS`, highest last.
+pub mod priority {
+    /// Everything else (the default).
+    pub const BACKGROUND: u32 = 0;
+    /// User-facing applications.
+    pub const INTERACTIVE: u32 = 1;
+    /// Core services.
+    pub const SYSTEM: u32 = 2;
+}
+
+/// Resource quota records for `SYS_SPAWN_PROCESS` and `SYS_SET_QUOTA`.
+pub mod quota {
+    /// Size of an encoded record: max_memory, max_endpoints, max_cap_slots,
+    /// max_queued_bytes and max_syscalls_per_sec as little-endian `u64`s,
+    /// in that order. A limit of 0 means unlimited.
+    pub const RECORD_SIZE: usize = 40;
+    /// `SYS_SPAWN_PROCESS` arg3: a quota record follows the name.
+    pub const SPAWN_WITH_QUOTA: u32 = 1;
+}
+
+/// Limits for `SYS_RECV_TIMEOUT` and `SYS_CALL_TIMEOUT`.
+pub mod wait {
+    /// Most endpoint slots a single `SYS_RECV_TIMEOUT` may wait on.
+    pub const MAX_WAIT_SLOTS: usize = 16;
 }
 
 /// Capability revocation reasons.
@@ -1056,6 +1263,12 @@ pub mod debug {
     /// Capability response: "CAP:RESPONSE:{hex_data}"
     pub const CAP_RESPONSE: &str = "CAP:RESPONSE:";
 
+    // === History Queries ===
+    /// State at a commit sequence: "HISTORY:STATE_AT:{seq}"
+    pub const HISTORY_STATE_AT: &str = "HISTORY:STATE_AT:";
+    /// Diff between two commit sequences: "HISTORY:DIFF:{from}:{to}"
+    pub const HISTORY_DIFF: &str = "HISTORY:DIFF:";
+
     // === Debug/Instrumentation ===
     /// Agent log prefix for debug instrumentation: "AGENT_LOG:{message}"
     pub const AGENT_LOG: &str = "AGENT_LOG:";
@@ -1141,6 +1354,15 @@ pub mod syscall_error {
     pub const INVALID_ARGUMENT: i32 = -5;
     /// Process spawn failed
     pub const SPAWN_FAILED: i32 = -6;
+    /// The caller's resource quota does not allow the operation
+    pub const RESOURCE_EXHAUSTED: i32 = -7;
+    /// A timed wait reached its deadline without a message
+    pub const TIMED_OUT: i32 = -8;
+    /// Nothing to collect yet (e.g. every matching child is still alive),
+    /// or the destination queue is full and blocks its senders
+    pub const WOULD_BLOCK: i32 = -9;
+    /// The destination endpoint queue is full and rejects new messages
+    pub const QUEUE_FULL: i32 = -10;
 }
 
 #[cfg(test)]
@@ -1152,6 +1374,7 @@ mod tests {
         // Ensure critical constants have expected values
         assert_eq!(supervisor::MSG_SUPERVISOR_REVOKE_CAP, 0x2020);
         assert_eq!(pm::MSG_REQUEST_CAPABILITY, 0x2010);
+        assert_eq!(supervisor::MSG_SUPERVISOR_SHUTDOWN, 0x200A);
 
         // These should NOT be equal
         assert_ne!(
@@ -1161,6 +1384,8 @@ mod tests {
 
         // Ensure kernel and console don't conflict
         assert_ne!(kernel::MSG_CAP_REVOKED, console::MSG_CONSOLE_INPUT);
+        assert_ne!(kernel::MSG_IRQ, console::MSG_CONSOLE_INPUT);
+        assert_ne!(kernel::MSG_CHILD_EXITED, console::MSG_CONSOLE_INPUT);
     }
 
     #[test]
@@ -1205,17 +1430,19 @@ mod tests {
         assert_eq!(ObjectType::Filesystem as u8, 9);
         assert_eq!(ObjectType::Identity as u8, 10);
         assert_eq!(ObjectType::Keystore as u8, 11);
+        assert_eq!(ObjectType::Notification as u8, 12);
+        assert_eq!(ObjectType::Reply as u8, 13);
     }
 
     #[test]
     fn test_object_type_from_u8_roundtrip() {
-        for val in 1..=11u8 {
+        for val in 1..=13u8 {
             let obj_type = ObjectType::from_u8(val).expect("valid value");
             assert_eq!(obj_type as u8, val);
         }
         // Invalid values should return None
         assert!(ObjectType::from_u8(0).is_none());
-        assert!(ObjectType::from_u8(12).is_none());
+        assert!(ObjectType::from_u8(14).is_none());
         assert!(ObjectType::from_u8(255).is_none());
     }
 }
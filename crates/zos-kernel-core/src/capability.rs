@@ -3,6 +3,7 @@
 //! This module implements the Axiom layer's capability verification:
 //! - Capability tokens with permissions
 //! - Capability spaces (per-process)
+//! - The derivation tree linking granted/derived caps to their source
 //! - The `axiom_check` function for authority verification
 //!
 //! # Security Properties (Verification Targets)
@@ -31,6 +32,8 @@ pub struct Capability {
     pub generation: u32,
     /// Expiration timestamp (nanos since boot, 0 = never expires)
     pub expires_at: u64,
+    /// Badge attached to messages sent through this capability (0 = unbadged)
+    pub badge: u64,
 }
 
 impl Capability {
@@ -48,6 +51,7 @@ impl Capability {
 }
 
 /// Per-process capability table
+#[derive(Clone)]
 pub struct CapabilitySpace {
     /// Capability slots
     pub slots: BTreeMap<CapSlot, Capability>,
@@ -114,6 +118,117 @@ impl Default for CapabilitySpace {
     }
 }
 
+// ============================================================================
+// Capability Derivation Tree
+// ============================================================================
+
+/// Parent/child links between capability IDs.
+///
+/// Every grant or derive records the new capability as a child of its source,
+/// so revoking a capability can reach every downstream copy regardless of
+/// which process holds it.
+#[derive(Clone, Debug, Default)]
+pub struct DerivationTree {
+    /// Child cap ID -> parent cap ID
+    parents: BTreeMap<u64, u64>,
+    /// Parent cap ID -> child cap IDs (in derivation order)
+    children: BTreeMap<u64, Vec<u64>>,
+}
+
+impl DerivationTree {
+    /// Create an empty derivation tree
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record `child` as derived from `parent`
+    pub fn link(&mut self, parent: u64, child: u64) {
+        self.parents.insert(child, parent);
+        self.children.entry(parent).or_default().push(child);
+    }
+
+    /// Get the parent of a capability, if it was derived
+    pub fn parent(&self, cap_id: u64) -> Option<u64> {
+        self.parents.get(&cap_id).copied()
+    }
+
+    /// Get the direct children of a capability
+    pub fn children(&self, cap_id: u64) -> &[u64] {
+        self.children.get(&cap_id).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Get a capability and all of its descendants, in pre-order
+    pub fn subtree(&self, cap_id: u64) -> Vec<u64> {
+        let mut result = Vec::new();
+        let mut stack = alloc::vec![cap_id];
+        while let Some(id) = stack.pop() {
+            result.push(id);
+            // Push in reverse so children are visited in derivation order
+            stack.extend(self.children(id).iter().rev());
+        }
+        result
+    }
+
+    /// Remove a single capability from the tree.
+    ///
+    /// Its children are re-attached to its parent (or become roots), so a
+    /// later revoke of an ancestor still reaches them.
+    pub fn remove(&mut self, cap_id: u64) {
+        let parent = self.detach(cap_id);
+        let orphans = self.children.remove(&cap_id).unwrap_or_default();
+
+        match parent {
+            Some(parent) => {
+                for &orphan in &orphans {
+                    self.parents.insert(orphan, parent);
+                }
+                if !orphans.is_empty() {
+                    self.children.entry(parent).or_default().extend(orphans);
+                }
+            }
+            None => {
+                for orphan in orphans {
+                    self.parents.remove(&orphan);
+                }
+            }
+        }
+    }
+
+    /// Remove a capability and all of its descendants, returning the removed
+    /// IDs in pre-order
+    pub fn remove_subtree(&mut self, cap_id: u64) -> Vec<u64> {
+        let ids = self.subtree(cap_id);
+        self.detach(cap_id);
+        for id in &ids {
+            self.parents.remove(id);
+            self.children.remove(id);
+        }
+        ids
+    }
+
+    /// Unlink a capability from its parent, returning the parent
+    fn detach(&mut self, cap_id: u64) -> Option<u64> {
+        let parent = self.parents.remove(&cap_id)?;
+        if let Some(siblings) = self.children.get_mut(&parent) {
+            siblings.retain(|&id| id != cap_id);
+            if siblings.is_empty() {
+                self.children.remove(&parent);
+            }
+        }
+        Some(parent)
+    }
+
+    /// Number of derived capabilities tracked (capabilities with a parent)
+    pub fn len(&self) -> usize {
+        self.parents.len()
+    }
+
+    /// Check if no derivations are tracked
+    pub fn is_empty(&self) -> bool {
+        self.parents.is_empty()
+    }
+}
+
 // ============================================================================
 // Axiom Capability Checking - THE verification target
 // ============================================================================
@@ -211,6 +326,7 @@ mod proofs {
             permissions: Permissions::full(),
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
         let inserted_object_id = cap.object_id;
         let slot = cspace.insert(cap);
@@ -251,6 +367,7 @@ mod proofs {
             permissions: cap_perms,
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
         let slot = cspace.insert(cap);
 
@@ -315,6 +432,7 @@ mod proofs {
             permissions: Permissions::full(),
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
         let slot = cspace.insert(cap);
 
@@ -346,6 +464,7 @@ mod proofs {
             permissions: Permissions::read_only(),
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
         let slot = cspace.insert(cap);
 
@@ -373,6 +492,7 @@ mod proofs {
             permissions: Permissions::full(),
             generation: 0,
             expires_at: expiry,
+            badge: 0,
         };
         let slot = cspace.insert(cap);
 
@@ -401,6 +521,7 @@ mod tests {
             permissions: Permissions::full(),
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
         let slot = cspace.insert(cap);
 
@@ -436,6 +557,7 @@ mod tests {
             permissions: Permissions::full(),
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
         let slot = cspace.insert(cap);
 
@@ -460,6 +582,7 @@ mod tests {
             permissions: Permissions::read_only(),
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
         let slot = cspace.insert(cap);
 
@@ -478,6 +601,7 @@ mod tests {
             permissions: Permissions::full(),
             generation: 0,
             expires_at: 1000,
+            badge: 0,
         };
         let slot = cspace.insert(cap);
 
@@ -496,6 +620,7 @@ mod tests {
             permissions: Permissions::full(),
             generation: 0,
             expires_at: 0, // 0 = never expires
+            badge: 0,
         };
         let slot = cspace.insert(cap);
 
@@ -533,6 +658,7 @@ mod tests {
             permissions: Permissions::full(),
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
         cspace.insert(cap);
 
@@ -550,6 +676,7 @@ mod tests {
             permissions: Permissions::full(),
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
         let slot = cspace.insert(cap);
 
@@ -567,6 +694,7 @@ mod tests {
             permissions: Permissions::full(),
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
         let slot = cspace.insert(cap);
 
@@ -590,6 +718,7 @@ mod tests {
             permissions: Permissions::full(),
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
         let cap2 = Capability {
             id: 2,
@@ -598,6 +727,7 @@ mod tests {
             permissions: Permissions::read_only(),
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
 
         let slot1 = cspace.insert(cap1);
@@ -619,6 +749,7 @@ mod tests {
             permissions: Permissions::read_only(),
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
         let slot = cspace.insert(cap);
 
@@ -643,6 +774,7 @@ mod tests {
             permissions: Permissions::full(),
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
         let slot1 = cspace.insert(cap1);
         assert_eq!(slot1, 0);
@@ -655,6 +787,7 @@ mod tests {
             permissions: Permissions::full(),
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
         let slot2 = cspace.insert(cap2);
         assert_eq!(slot2, 1);
@@ -675,6 +808,7 @@ mod tests {
             permissions: Permissions::full(),
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
 
         // Full cap should satisfy any permission combination
@@ -693,6 +827,7 @@ mod tests {
             permissions: Permissions::read_only(),
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
 
         assert!(read_cap.has_permissions(&Permissions::read_only()));
@@ -709,6 +844,7 @@ mod tests {
             permissions: Permissions::write_only(),
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
 
         assert!(write_cap.has_permissions(&Permissions::write_only()));
@@ -724,6 +860,7 @@ mod tests {
             permissions: Permissions { read: false, write: false, grant: true },
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
 
         assert!(grant_cap.has_permissions(&Permissions { read: false, write: false, grant: true }));
@@ -748,6 +885,7 @@ mod tests {
             permissions: Permissions::full(),
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
         let slot1 = cspace.insert(endpoint_cap);
 
@@ -758,6 +896,7 @@ mod tests {
             permissions: Permissions::full(),
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
         let slot2 = cspace.insert(process_cap);
 
@@ -781,6 +920,7 @@ mod tests {
             permissions: Permissions::full(),
             generation: 0,
             expires_at: 1000,
+            badge: 0,
         };
         let slot = cspace.insert(cap);
 
@@ -804,6 +944,7 @@ mod tests {
             permissions: Permissions::default(), // No permissions
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
         let slot = cspace.insert(cap);
 
@@ -837,6 +978,7 @@ mod tests {
                 permissions: Permissions::full(),
                 generation: 0,
                 expires_at: 0,
+                badge: 0,
             };
             let slot = cspace.insert(cap);
 
@@ -865,6 +1007,7 @@ mod tests {
             permissions: Permissions::full(),
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
         assert!(!cap_never.is_expired(0));
         assert!(!cap_never.is_expired(u64::MAX));
@@ -877,6 +1020,7 @@ mod tests {
             permissions: Permissions::full(),
             generation: 0,
             expires_at: 1000,
+            badge: 0,
         };
         assert!(!cap_expires.is_expired(0));
         assert!(!cap_expires.is_expired(999));
@@ -884,4 +1028,63 @@ mod tests {
         assert!(cap_expires.is_expired(1001));
         assert!(cap_expires.is_expired(u64::MAX));
     }
+
+    // ========================================================================
+    // DerivationTree tests
+    // ========================================================================
+
+    #[test]
+    fn test_derivation_tree_subtree_preorder() {
+        let mut tree = DerivationTree::new();
+        tree.link(1, 2);
+        tree.link(2, 4);
+        tree.link(1, 3);
+
+        assert_eq!(tree.subtree(1), alloc::vec![1, 2, 4, 3]);
+        assert_eq!(tree.subtree(2), alloc::vec![2, 4]);
+        assert_eq!(tree.subtree(9), alloc::vec![9]);
+        assert_eq!(tree.parent(4), Some(2));
+        assert_eq!(tree.parent(1), None);
+    }
+
+    #[test]
+    fn test_derivation_tree_remove_reparents_children() {
+        let mut tree = DerivationTree::new();
+        tree.link(1, 2);
+        tree.link(2, 3);
+        tree.link(2, 4);
+
+        tree.remove(2);
+
+        assert_eq!(tree.parent(3), Some(1));
+        assert_eq!(tree.parent(4), Some(1));
+        assert_eq!(tree.children(1), &[3, 4]);
+        assert_eq!(tree.subtree(1), alloc::vec![1, 3, 4]);
+    }
+
+    #[test]
+    fn test_derivation_tree_remove_root_orphans_children() {
+        let mut tree = DerivationTree::new();
+        tree.link(1, 2);
+
+        tree.remove(1);
+
+        assert_eq!(tree.parent(2), None);
+        assert!(tree.is_empty());
+    }
+
+    #[test]
+    fn test_derivation_tree_remove_subtree() {
+        let mut tree = DerivationTree::new();
+        tree.link(1, 2);
+        tree.link(2, 3);
+        tree.link(1, 4);
+
+        let removed = tree.remove_subtree(2);
+
+        assert_eq!(removed, alloc::vec![2, 3]);
+        assert_eq!(tree.children(1), &[4]);
+        assert_eq!(tree.parent(3), None);
+        assert_eq!(tree.len(), 1);
+    }
 }
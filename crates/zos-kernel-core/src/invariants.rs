@@ -13,12 +13,22 @@
 //! 3. **Capability Object Validity**: Every capability references a valid object
 //! 4. **No Orphan Endpoints**: Endpoints without valid owners should not exist
 //! 5. **ID Monotonicity**: Next IDs are always greater than existing IDs
+//! 6. **Queue Bound**: No endpoint queue holds more messages than its capacity
+//! 7. **Mapping Authority**: Every region mapping is backed by a capability
+//!    granting at least the mapped access
+//! 8. **IRQ Binding Validity**: Every bound IRQ line is owned by a holder of
+//!    its capability and delivers to an existing object
+//! 9. **Process Tree**: Every parent link names a live process (or the kernel)
+//! 10. **Deadline Validity**: Only blocked processes have a wake-up deadline
 
 use alloc::string::String;
 use alloc::vec::Vec;
 
 use crate::state::KernelState;
-use crate::types::{EndpointId, ObjectType, ProcessId, ProcessState};
+use crate::types::{
+    CallState, EndpointId, IrqTarget, NotificationId, ObjectType, ProcessId, ProcessState,
+    RegionId,
+};
 
 /// An invariant violation with details
 #[derive(Clone, Debug)]
@@ -39,6 +49,11 @@ pub fn check_all_invariants(state: &KernelState) -> Vec<InvariantViolation> {
     violations.extend(check_endpoint_ownership(state));
     violations.extend(check_capability_object_validity(state));
     violations.extend(check_id_monotonicity(state));
+    violations.extend(check_queue_bound(state));
+    violations.extend(check_mapping_authority(state));
+    violations.extend(check_irq_binding_validity(state));
+    violations.extend(check_process_tree(state));
+    violations.extend(check_deadline_validity(state));
 
     violations
 }
@@ -147,7 +162,53 @@ fn check_capability_object_validity(state: &KernelState) -> Vec<InvariantViolati
                         });
                     }
                 }
-                // Other object types are not validated here (Memory, Irq, IoPort, Console)
+                ObjectType::Notification => {
+                    let notification_id = NotificationId(cap.object_id);
+                    if !state.notifications.contains_key(&notification_id) {
+                        violations.push(InvariantViolation {
+                            invariant: "capability_object_validity",
+                            description: alloc::format!(
+                                "Process {} slot {} references non-existent notification {}",
+                                pid.0,
+                                slot,
+                                cap.object_id
+                            ),
+                        });
+                    }
+                }
+                ObjectType::Memory => {
+                    let region_id = RegionId(cap.object_id);
+                    if !state.regions.contains_key(&region_id) {
+                        violations.push(InvariantViolation {
+                            invariant: "capability_object_validity",
+                            description: alloc::format!(
+                                "Process {} slot {} references non-existent region {}",
+                                pid.0,
+                                slot,
+                                cap.object_id
+                            ),
+                        });
+                    }
+                }
+                ObjectType::Reply => {
+                    let caller = ProcessId(cap.object_id);
+                    let pending = matches!(
+                        state.calls.get(&caller),
+                        Some(CallState::Pending { server }) if server == pid
+                    );
+                    if !pending {
+                        violations.push(InvariantViolation {
+                            invariant: "capability_object_validity",
+                            description: alloc::format!(
+                                "Process {} slot {} holds reply cap for {} with no pending call",
+                                pid.0,
+                                slot,
+                                cap.object_id
+                            ),
+                        });
+                    }
+                }
+                // Other object types are not validated here (Irq, IoPort, Console)
                 _ => {}
             }
         }
@@ -188,6 +249,34 @@ fn check_id_monotonicity(state: &KernelState) -> Vec<InvariantViolation> {
         }
     }
 
+    // Check notification IDs
+    for nid in state.notifications.keys() {
+        if nid.0 >= state.next_notification_id {
+            violations.push(InvariantViolation {
+                invariant: "id_monotonicity",
+                description: alloc::format!(
+                    "Notification {} exists but next_notification_id is {}",
+                    nid.0,
+                    state.next_notification_id
+                ),
+            });
+        }
+    }
+
+    // Check region IDs
+    for rid in state.regions.keys() {
+        if rid.0 >= state.next_region_id {
+            violations.push(InvariantViolation {
+                invariant: "id_monotonicity",
+                description: alloc::format!(
+                    "Region {} exists but next_region_id is {}",
+                    rid.0,
+                    state.next_region_id
+                ),
+            });
+        }
+    }
+
     // Check capability IDs
     for cspace in state.cap_spaces.values() {
         for cap in cspace.slots.values() {
@@ -207,6 +296,155 @@ fn check_id_monotonicity(state: &KernelState) -> Vec<InvariantViolation> {
     violations
 }
 
+/// Invariant 5: No endpoint queue exceeds its capacity
+fn check_queue_bound(state: &KernelState) -> Vec<InvariantViolation> {
+    let mut violations = Vec::new();
+
+    for (eid, endpoint) in &state.endpoints {
+        if endpoint.pending_messages.len() > endpoint.capacity {
+            violations.push(InvariantViolation {
+                invariant: "queue_bound",
+                description: alloc::format!(
+                    "Endpoint {} holds {} messages but capacity is {}",
+                    eid.0,
+                    endpoint.pending_messages.len(),
+                    endpoint.capacity
+                ),
+            });
+        }
+    }
+
+    violations
+}
+
+/// Invariant 6: A process only maps a region it holds a sufficient cap for
+fn check_mapping_authority(state: &KernelState) -> Vec<InvariantViolation> {
+    let mut violations = Vec::new();
+
+    for (rid, region) in &state.regions {
+        for (pid, &writable) in &region.mappings {
+            let backed = state.cap_spaces.get(pid).is_some_and(|cspace| {
+                cspace.slots.values().any(|cap| {
+                    cap.object_type == ObjectType::Memory
+                        && cap.object_id == rid.0
+                        && cap.permissions.read
+                        && (cap.permissions.write || !writable)
+                })
+            });
+            if !backed {
+                violations.push(InvariantViolation {
+                    invariant: "mapping_authority",
+                    description: alloc::format!(
+                        "Process {} maps region {} ({}) without a matching capability",
+                        pid.0,
+                        rid.0,
+                        if writable { "rw" } else { "ro" }
+                    ),
+                });
+            }
+        }
+    }
+
+    violations
+}
+
+/// Invariant 7: IRQ bindings have authority and a live target
+fn check_irq_binding_validity(state: &KernelState) -> Vec<InvariantViolation> {
+    let mut violations = Vec::new();
+
+    for (irq, binding) in &state.irq_bindings {
+        let authorized = state.cap_spaces.get(&binding.owner).is_some_and(|cspace| {
+            cspace.slots.values().any(|cap| {
+                cap.object_type == ObjectType::Irq && cap.object_id == *irq as u64
+            })
+        });
+        if !authorized {
+            violations.push(InvariantViolation {
+                invariant: "irq_binding_validity",
+                description: alloc::format!(
+                    "IRQ {} bound by process {} which holds no capability for it",
+                    irq,
+                    binding.owner.0
+                ),
+            });
+        }
+
+        let target_exists = match binding.target {
+            IrqTarget::Notification { id, .. } => state.notifications.contains_key(&id),
+            IrqTarget::Endpoint(id) => state.endpoints.contains_key(&id),
+        };
+        if !target_exists {
+            violations.push(InvariantViolation {
+                invariant: "irq_binding_validity",
+                description: alloc::format!("IRQ {} delivers to a non-existent object", irq),
+            });
+        }
+    }
+
+    violations
+}
+
+/// Invariant 8: Parents are live, since orphans are adopted on death
+fn check_process_tree(state: &KernelState) -> Vec<InvariantViolation> {
+    let mut violations = Vec::new();
+
+    for (pid, proc) in &state.processes {
+        if proc.parent == ProcessId(0) {
+            continue;
+        }
+        let parent_alive = state
+            .processes
+            .get(&proc.parent)
+            .is_some_and(|p| p.state != ProcessState::Zombie);
+        if !parent_alive {
+            violations.push(InvariantViolation {
+                invariant: "process_tree",
+                description: alloc::format!(
+                    "Process {} has dead or missing parent {}",
+                    pid.0,
+                    proc.parent.0
+                ),
+            });
+        }
+    }
+
+    violations
+}
+
+/// Invariant 9: A deadline or wait set is dropped as soon as its process
+/// wakes or dies
+fn check_deadline_validity(state: &KernelState) -> Vec<InvariantViolation> {
+    let mut violations = Vec::new();
+
+    for pid in state.deadlines.keys() {
+        let blocked = state
+            .processes
+            .get(pid)
+            .is_some_and(|p| p.state == ProcessState::Blocked);
+        if !blocked {
+            violations.push(InvariantViolation {
+                invariant: "deadline_validity",
+                description: alloc::format!("Process {} has a deadline but is not blocked", pid.0),
+            });
+        }
+    }
+
+    for pid in state.waits.keys() {
+        let blocked = state
+            .processes
+            .get(pid)
+            .is_some_and(|p| p.state == ProcessState::Blocked);
+        if !blocked {
+            violations.push(InvariantViolation {
+                invariant: "deadline_validity",
+                description: alloc::format!("Process {} is waiting but is not blocked", pid.0),
+            });
+        }
+    }
+
+    violations
+}
+
 /// Assert all invariants hold (panic if not)
 pub fn assert_invariants(state: &KernelState) {
     let violations = check_all_invariants(state);
@@ -351,6 +589,7 @@ mod tests {
             permissions: Permissions::full(),
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
         state.get_cap_space_mut(pid).unwrap().insert(bad_cap);
 
@@ -446,6 +685,7 @@ mod tests {
             permissions: Permissions::full(),
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
         state.get_cap_space_mut(pid).unwrap().insert(bad_cap);
 
@@ -482,11 +722,12 @@ mod tests {
         let endpoint = state.get_endpoint_mut(eid).unwrap();
         let msg = crate::types::Message {
             sender,
+            badge: 0,
             tag: 42,
             data: vec![1, 2, 3],
             caps: vec![],
         };
-        endpoint.enqueue(msg);
+        endpoint.enqueue(msg).unwrap();
 
         // Invariants should still hold
         let violations = check_all_invariants(&state);
@@ -508,6 +749,7 @@ mod tests {
             permissions: Permissions::full(),
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
         let slot = state.get_cap_space_mut(pid1).unwrap().insert(cap);
 
@@ -519,6 +761,7 @@ mod tests {
             permissions: Permissions::read_only(),
             generation: 1,
             expires_at: 0,
+            badge: 0,
         };
         state.get_cap_space_mut(pid2).unwrap().insert(cap2);
 
@@ -548,6 +791,7 @@ mod tests {
             permissions: Permissions::full(),
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
         let slot = state.get_cap_space_mut(pid).unwrap().insert(cap);
 
@@ -574,6 +818,7 @@ mod tests {
             permissions: Permissions::full(),
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
         state.get_cap_space_mut(pid).unwrap().insert(cap);
 
@@ -605,6 +850,7 @@ mod tests {
             permissions: Permissions::full(),
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
         state.get_cap_space_mut(pid).unwrap().insert(bad_cap);
 
@@ -629,6 +875,7 @@ mod tests {
             permissions: Permissions::full(),
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
         state.get_cap_space_mut(pid1).unwrap().insert(cap);
 
@@ -694,6 +941,7 @@ mod tests {
             permissions: Permissions::full(),
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
         state.get_cap_space_mut(pid).unwrap().insert(cap);
 
@@ -706,4 +954,103 @@ mod tests {
             .iter()
             .any(|v| v.invariant == "id_monotonicity"));
     }
+
+    // ========================================================================
+    // Queue bound tests
+    // ========================================================================
+
+    #[test]
+    fn test_detects_queue_bound_violation() {
+        let mut state = KernelState::new();
+        let pid = state.register_process("test", 1000);
+        let eid = state.create_endpoint_with_capacity(pid, 1, crate::types::OverflowPolicy::Reject);
+
+        let endpoint = state.get_endpoint_mut(eid).unwrap();
+        endpoint
+            .enqueue(crate::types::Message { sender: pid, badge: 0, tag: 0, data: vec![], caps: vec![] })
+            .unwrap();
+        assert!(check_all_invariants(&state).is_empty());
+
+        // Shrink the capacity underneath the queued message
+        state.get_endpoint_mut(eid).unwrap().capacity = 0;
+
+        let violations = check_all_invariants(&state);
+        assert!(violations.iter().any(|v| v.invariant == "queue_bound"));
+    }
+
+    // ========================================================================
+    // Mapping authority tests
+    // ========================================================================
+
+    #[test]
+    fn test_detects_unbacked_region_mapping() {
+        let mut state = KernelState::new();
+        let pid = state.register_process("test", 1000);
+        let rid = state.create_region(pid, 4096);
+
+        state.get_region_mut(rid).unwrap().map(pid, false);
+
+        let violations = check_all_invariants(&state);
+        assert!(violations.iter().any(|v| v.invariant == "mapping_authority"));
+    }
+
+    // ========================================================================
+    // IRQ binding tests
+    // ========================================================================
+
+    #[test]
+    fn test_detects_irq_binding_without_cap() {
+        let mut state = KernelState::new();
+        let pid = state.register_process("driver", 1000);
+        let eid = state.create_endpoint(pid);
+
+        state.irq_bindings.insert(
+            4,
+            crate::types::IrqBinding {
+                owner: pid,
+                target: IrqTarget::Endpoint(eid),
+                masked: false,
+            },
+        );
+        let violations = check_all_invariants(&state);
+        assert!(violations.iter().any(|v| v.invariant == "irq_binding_validity"));
+
+        state.mint_irq_cap(pid, 4);
+        assert!(check_all_invariants(&state).is_empty());
+    }
+
+    // ========================================================================
+    // Process tree tests
+    // ========================================================================
+
+    #[test]
+    fn test_detects_missing_parent() {
+        let mut state = KernelState::new();
+        let parent = state.register_process("init", 1000);
+        state.register_child(parent, "svc", 1000);
+        assert!(check_all_invariants(&state).is_empty());
+
+        state.processes.remove(&parent);
+        state.cap_spaces.remove(&parent);
+
+        let violations = check_all_invariants(&state);
+        assert!(violations.iter().any(|v| v.invariant == "process_tree"));
+    }
+
+    // ========================================================================
+    // Deadline tests
+    // ========================================================================
+
+    #[test]
+    fn test_detects_deadline_on_running_process() {
+        let mut state = KernelState::new();
+        let pid = state.register_process("svc", 1000);
+        state.deadlines.insert(pid, 5000);
+
+        let violations = check_all_invariants(&state);
+        assert!(violations.iter().any(|v| v.invariant == "deadline_validity"));
+
+        state.get_process_mut(pid).unwrap().state = ProcessState::Blocked;
+        assert!(check_all_invariants(&state).is_empty());
+    }
 }
@@ -51,7 +51,7 @@
 //! # Module Organization
 //!
 //! - `types` - Core kernel types (ProcessId, EndpointId, etc.)
-//! - `capability` - Capability tokens and `axiom_check` verification
+//! - `capability` - Capability tokens, derivation tree and `axiom_check` verification
 //! - `state` - KernelState struct with all kernel data
 //! - `step` - Pure `step(state, syscall) -> (state', result)` function
 //! - `invariants` - Formal invariant assertions for verification
@@ -66,12 +66,15 @@ pub mod step;
 pub mod types;
 
 // Re-export all public types for convenient access
-pub use capability::{axiom_check, AxiomError, Capability, CapabilitySpace};
+pub use capability::{axiom_check, AxiomError, Capability, CapabilitySpace, DerivationTree};
 pub use invariants::{check_all_invariants, InvariantViolation};
 pub use state::KernelState;
-pub use step::{step, Commit, CommitType, StepResult, Syscall, SyscallResult};
+pub use step::{fault, interrupt, step, Commit, CommitType, StepResult, Syscall, SyscallResult};
 pub use types::{
-    CapSlot, Endpoint, EndpointId, EndpointMetrics, Message, ObjectType, Permissions, Process,
-    ProcessId, ProcessMetrics, ProcessState, SystemMetrics, TransferredCap, MAX_CAPS_PER_MESSAGE,
-    MAX_MESSAGE_SIZE,
+    CallState, CapSlot, Endpoint, EndpointId, EndpointMetrics, ExitStatus, IoPortRange, IrqBinding,
+    IrqTarget, MemoryRegion, Message, Notification, NotificationId, ObjectType, OverflowPolicy,
+    Permissions, Process, ProcessId, ProcessMetrics, ProcessState, QuotaResource, RegionId,
+    ResourceQuota, SystemMetrics, TransferredCap, DEFAULT_QUEUE_CAPACITY, KERNEL_QUEUE_RESERVE,
+    MAX_CAPS_PER_MESSAGE, MAX_MESSAGE_SIZE, MAX_QUEUE_CAPACITY, MAX_REGION_SIZE, MAX_WAIT_SLOTS,
+    PAGE_SIZE, RATE_WINDOW_NS,
 };
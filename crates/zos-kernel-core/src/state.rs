@@ -6,10 +6,12 @@
 
 use alloc::collections::BTreeMap;
 
-use crate::capability::CapabilitySpace;
+use crate::capability::{Capability, CapabilitySpace, DerivationTree};
 use crate::types::{
-    Endpoint, EndpointDetail, EndpointId, EndpointInfo, MessageSummary, Process, ProcessId,
-    ProcessMetrics, ProcessState, SystemMetrics,
+    CallState, CapSlot, Endpoint, EndpointDetail, EndpointId, EndpointInfo, IoPortRange, IrqBinding,
+    MemoryRegion, MessageSummary, Notification, NotificationId, ObjectType, OverflowPolicy,
+    Permissions, Process, ProcessId, ProcessMetrics, ProcessState, QuotaResource, RegionId,
+    ResourceQuota, SystemMetrics, RATE_WINDOW_NS,
 };
 use alloc::string::ToString;
 use alloc::vec::Vec;
@@ -18,17 +20,36 @@ use alloc::vec::Vec;
 ///
 /// All state transformations are done via the `step` function.
 /// This struct is the verification target.
+#[derive(Clone)]
 pub struct KernelState {
     /// Process table
     pub processes: BTreeMap<ProcessId, Process>,
     /// Capability spaces (per-process)
     pub cap_spaces: BTreeMap<ProcessId, CapabilitySpace>,
+    /// Capability derivation tree (parent/child links by cap ID)
+    pub cap_derivations: DerivationTree,
     /// IPC endpoints
     pub endpoints: BTreeMap<EndpointId, Endpoint>,
+    /// Notification objects
+    pub notifications: BTreeMap<NotificationId, Notification>,
+    /// Shared memory regions
+    pub regions: BTreeMap<RegionId, MemoryRegion>,
+    /// Outstanding calls, keyed by caller
+    pub calls: BTreeMap<ProcessId, CallState>,
+    /// IRQ lines bound by user-space drivers
+    pub irq_bindings: BTreeMap<u8, IrqBinding>,
+    /// Wake-up deadlines of processes parked by a timed wait
+    pub deadlines: BTreeMap<ProcessId, u64>,
+    /// Endpoints each process parked by `ReceiveTimeout` is waiting on
+    pub waits: BTreeMap<ProcessId, Vec<EndpointId>>,
     /// Next process ID to allocate
     pub next_pid: u64,
     /// Next endpoint ID to allocate
     pub next_endpoint_id: u64,
+    /// Next notification ID to allocate
+    pub next_notification_id: u64,
+    /// Next region ID to allocate
+    pub next_region_id: u64,
     /// Next capability ID to allocate
     pub next_cap_id: u64,
     /// Total IPC messages since boot
@@ -41,9 +62,18 @@ impl KernelState {
         Self {
             processes: BTreeMap::new(),
             cap_spaces: BTreeMap::new(),
+            cap_derivations: DerivationTree::new(),
             endpoints: BTreeMap::new(),
+            notifications: BTreeMap::new(),
+            regions: BTreeMap::new(),
+            calls: BTreeMap::new(),
+            irq_bindings: BTreeMap::new(),
+            deadlines: BTreeMap::new(),
+            waits: BTreeMap::new(),
             next_pid: 1,
             next_endpoint_id: 1,
+            next_notification_id: 1,
+            next_region_id: 1,
             next_cap_id: 1,
             total_ipc_count: 0,
         }
@@ -63,6 +93,20 @@ impl KernelState {
         id
     }
 
+    /// Generate next notification ID
+    pub fn alloc_notification_id(&mut self) -> NotificationId {
+        let id = NotificationId(self.next_notification_id);
+        self.next_notification_id += 1;
+        id
+    }
+
+    /// Generate next region ID
+    pub fn alloc_region_id(&mut self) -> RegionId {
+        let id = RegionId(self.next_region_id);
+        self.next_region_id += 1;
+        id
+    }
+
     /// Generate next capability ID
     pub fn alloc_cap_id(&mut self) -> u64 {
         let id = self.next_cap_id;
@@ -89,6 +133,44 @@ impl KernelState {
         self.processes.iter().map(|(&pid, p)| (pid, p)).collect()
     }
 
+    /// Direct children of a process
+    pub fn children(&self, pid: ProcessId) -> Vec<ProcessId> {
+        self.processes
+            .values()
+            .filter(|p| p.parent == pid && p.pid != pid)
+            .map(|p| p.pid)
+            .collect()
+    }
+
+    /// A process and all of its descendants, parents before children
+    pub fn descendants(&self, pid: ProcessId) -> Vec<ProcessId> {
+        let mut out = Vec::new();
+        let mut stack = alloc::vec![pid];
+        while let Some(next) = stack.pop() {
+            out.push(next);
+            let mut children = self.children(next);
+            children.reverse();
+            stack.extend(children);
+        }
+        out
+    }
+
+    /// Whether `ancestor` is `pid` itself or one of its ancestors
+    pub fn is_ancestor_or_self(&self, ancestor: ProcessId, pid: ProcessId) -> bool {
+        let mut current = pid;
+        // Bounded by the table size in case parent links ever form a cycle
+        for _ in 0..=self.processes.len() {
+            if current == ancestor {
+                return true;
+            }
+            match self.processes.get(&current) {
+                Some(proc) if proc.parent != current => current = proc.parent,
+                _ => return false,
+            }
+        }
+        false
+    }
+
     /// Get capability space for a process
     pub fn get_cap_space(&self, pid: ProcessId) -> Option<&CapabilitySpace> {
         self.cap_spaces.get(&pid)
@@ -99,6 +181,21 @@ impl KernelState {
         self.cap_spaces.get_mut(&pid)
     }
 
+    /// Find where each of the given capability IDs lives.
+    ///
+    /// IDs that no longer exist in any capability space are omitted.
+    pub fn locate_caps(&self, cap_ids: &[u64]) -> BTreeMap<u64, (ProcessId, CapSlot)> {
+        let mut found = BTreeMap::new();
+        for (&pid, cspace) in &self.cap_spaces {
+            for (&slot, cap) in &cspace.slots {
+                if cap_ids.contains(&cap.id) {
+                    found.insert(cap.id, (pid, slot));
+                }
+            }
+        }
+        found
+    }
+
     /// Get endpoint by ID
     pub fn get_endpoint(&self, id: EndpointId) -> Option<&Endpoint> {
         self.endpoints.get(&id)
@@ -109,6 +206,26 @@ impl KernelState {
         self.endpoints.get_mut(&id)
     }
 
+    /// Get notification by ID
+    pub fn get_notification(&self, id: NotificationId) -> Option<&Notification> {
+        self.notifications.get(&id)
+    }
+
+    /// Get mutable notification by ID
+    pub fn get_notification_mut(&mut self, id: NotificationId) -> Option<&mut Notification> {
+        self.notifications.get_mut(&id)
+    }
+
+    /// Get a shared memory region
+    pub fn get_region(&self, id: RegionId) -> Option<&MemoryRegion> {
+        self.regions.get(&id)
+    }
+
+    /// Get a shared memory region (mutable)
+    pub fn get_region_mut(&mut self, id: RegionId) -> Option<&mut MemoryRegion> {
+        self.regions.get_mut(&id)
+    }
+
     /// List all endpoints
     pub fn list_endpoints(&self) -> Vec<EndpointInfo> {
         self.endpoints
@@ -117,6 +234,7 @@ impl KernelState {
                 id: e.id,
                 owner: e.owner,
                 queue_depth: e.pending_messages.len(),
+                capacity: e.capacity,
             })
             .collect()
     }
@@ -171,11 +289,27 @@ impl KernelState {
 
     /// Register a new process, returns the PID
     pub fn register_process(&mut self, name: &str, timestamp: u64) -> ProcessId {
+        self.register_child(ProcessId(0), name, timestamp)
+    }
+
+    /// Register a process spawned by `parent`.
+    ///
+    /// The parent is told when the child dies and is responsible for
+    /// reaping it. The child starts with the parent's quota.
+    pub fn register_child(&mut self, parent: ProcessId, name: &str, timestamp: u64) -> ProcessId {
         let pid = self.alloc_pid();
+        let quota = self
+            .processes
+            .get(&parent)
+            .map(|p| p.quota)
+            .unwrap_or_default();
         let process = Process {
             pid,
+            parent,
             name: name.to_string(),
             state: ProcessState::Running,
+            exit_status: None,
+            quota,
             metrics: ProcessMetrics {
                 start_time_ns: timestamp,
                 ..Default::default()
@@ -200,8 +334,11 @@ impl KernelState {
 
         let process = Process {
             pid,
+            parent: ProcessId(0),
             name: name.to_string(),
             state: ProcessState::Running,
+            exit_status: None,
+            quota: ResourceQuota::default(),
             metrics: ProcessMetrics {
                 start_time_ns: timestamp,
                 ..Default::default()
@@ -224,6 +361,17 @@ impl KernelState {
 
     /// Remove a process completely
     pub fn remove_process(&mut self, pid: ProcessId) -> bool {
+        if let Some(cspace) = self.cap_spaces.get(&pid) {
+            for cap in cspace.slots.values() {
+                self.cap_derivations.remove(cap.id);
+            }
+        }
+        for region in self.regions.values_mut() {
+            region.unmap(pid);
+        }
+        self.irq_bindings.retain(|_, binding| binding.owner != pid);
+        self.deadlines.remove(&pid);
+        self.waits.remove(&pid);
         self.processes.remove(&pid).is_some() && self.cap_spaces.remove(&pid).is_some()
     }
 
@@ -235,6 +383,67 @@ impl KernelState {
         id
     }
 
+    /// Create an endpoint with an explicit queue capacity and overflow policy
+    pub fn create_endpoint_with_capacity(
+        &mut self,
+        owner: ProcessId,
+        capacity: usize,
+        policy: OverflowPolicy,
+    ) -> EndpointId {
+        let id = self.alloc_endpoint_id();
+        let endpoint = Endpoint::with_capacity(id, owner, capacity, policy);
+        self.endpoints.insert(id, endpoint);
+        id
+    }
+
+    /// Create a notification object
+    pub fn create_notification(&mut self, owner: ProcessId) -> NotificationId {
+        let id = self.alloc_notification_id();
+        self.notifications.insert(id, Notification::new(id, owner));
+        id
+    }
+
+    /// Allocate a shared memory region of at least `size` bytes
+    pub fn create_region(&mut self, owner: ProcessId, size: usize) -> RegionId {
+        let id = self.alloc_region_id();
+        self.regions.insert(id, MemoryRegion::new(id, owner, size));
+        id
+    }
+
+    /// Give `pid` a root capability for an IRQ line.
+    ///
+    /// Device capabilities are not created by syscalls; boot hands them to
+    /// the permission service, which grants them on to drivers.
+    pub fn mint_irq_cap(&mut self, pid: ProcessId, irq: u8) -> Option<CapSlot> {
+        self.mint_device_cap(pid, ObjectType::Irq, irq as u64)
+    }
+
+    /// Give `pid` a root capability for a range of I/O ports
+    pub fn mint_io_port_cap(&mut self, pid: ProcessId, range: IoPortRange) -> Option<CapSlot> {
+        self.mint_device_cap(pid, ObjectType::IoPort, range.to_object_id())
+    }
+
+    fn mint_device_cap(
+        &mut self,
+        pid: ProcessId,
+        object_type: ObjectType,
+        object_id: u64,
+    ) -> Option<CapSlot> {
+        if !self.cap_spaces.contains_key(&pid) {
+            return None;
+        }
+        let cap = Capability {
+            id: self.alloc_cap_id(),
+            object_type,
+            object_id,
+            permissions: Permissions::full(),
+            generation: 0,
+            expires_at: 0,
+            badge: 0,
+        };
+        self.get_cap_space_mut(pid).map(|cs| cs.insert(cap))
+    }
+
     /// Remove an endpoint
     pub fn remove_endpoint(&mut self, id: EndpointId) -> bool {
         self.endpoints.remove(&id).is_some()
@@ -253,8 +462,75 @@ impl KernelState {
         if let Some(proc) = self.processes.get_mut(&pid) {
             proc.metrics.syscall_count += 1;
             proc.metrics.last_active_ns = timestamp;
+
+            let metrics = &mut proc.metrics;
+            if timestamp.saturating_sub(metrics.rate_window_start_ns) >= RATE_WINDOW_NS {
+                metrics.rate_window_start_ns = timestamp;
+                metrics.rate_window_count = 0;
+            }
+            metrics.rate_window_count += 1;
         }
     }
+
+    /// Earliest deadline of any parked process.
+    ///
+    /// The runtime arms its timer for this and retries the waits that are
+    /// due, which then complete with `SyscallResult::TimedOut`.
+    pub fn next_deadline(&self) -> Option<u64> {
+        self.deadlines.values().min().copied()
+    }
+
+    /// Parked processes whose deadline is at or before `timestamp`
+    pub fn deadlines_due(&self, timestamp: u64) -> Vec<ProcessId> {
+        self.deadlines
+            .iter()
+            .filter(|(_, &deadline)| deadline <= timestamp)
+            .map(|(&pid, _)| pid)
+            .collect()
+    }
+
+    /// Current usage of a quota resource by `pid`
+    pub fn quota_usage(&self, pid: ProcessId, resource: QuotaResource) -> u64 {
+        let usage = match resource {
+            QuotaResource::Memory => {
+                let own = self.processes.get(&pid).map_or(0, |p| p.metrics.memory_size);
+                let regions: usize = self
+                    .regions
+                    .values()
+                    .filter(|r| r.owner == pid)
+                    .map(|r| r.size)
+                    .sum();
+                own + regions
+            }
+            QuotaResource::Endpoints => {
+                self.endpoints.values().filter(|e| e.owner == pid).count()
+            }
+            QuotaResource::CapSlots => self.cap_spaces.get(&pid).map_or(0, |cs| cs.len()),
+            QuotaResource::QueuedBytes => self
+                .endpoints
+                .values()
+                .flat_map(|e| e.pending_messages.iter())
+                .filter(|m| m.sender == pid)
+                .map(|m| m.data.len())
+                .sum(),
+            QuotaResource::SyscallRate => {
+                return self.processes.get(&pid).map_or(0, |p| p.metrics.rate_window_count)
+            }
+        };
+        usage as u64
+    }
+
+    /// Check if `pid` may take `amount` more of a resource.
+    ///
+    /// Unknown processes have no quota, so the check passes and the caller
+    /// reports the missing process itself.
+    pub fn quota_allows(&self, pid: ProcessId, resource: QuotaResource, amount: u64) -> bool {
+        let limit = self
+            .processes
+            .get(&pid)
+            .map_or(0, |p| p.quota.limit(resource));
+        limit == 0 || self.quota_usage(pid, resource).saturating_add(amount) <= limit
+    }
 }
 
 impl Default for KernelState {
@@ -420,6 +696,7 @@ mod tests {
             permissions: crate::types::Permissions::full(),
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
         cspace.insert(cap);
         assert_eq!(state.get_cap_space(pid).unwrap().len(), 1);
@@ -513,11 +790,12 @@ mod tests {
         let endpoint = state.get_endpoint_mut(eid).unwrap();
         let msg = crate::types::Message {
             sender: pid,
+            badge: 0,
             tag: 42,
             data: vec![1, 2, 3],
             caps: vec![],
         };
-        endpoint.enqueue(msg);
+        endpoint.enqueue(msg).unwrap();
 
         // List endpoints
         let endpoints = state.list_endpoints();
@@ -538,16 +816,18 @@ mod tests {
         let endpoint = state.get_endpoint_mut(eid).unwrap();
         endpoint.enqueue(crate::types::Message {
             sender: pid1,
+            badge: 0,
             tag: 100,
             data: vec![1, 2, 3, 4, 5],
             caps: vec![],
-        });
+        }).unwrap();
         endpoint.enqueue(crate::types::Message {
             sender: pid1,
+            badge: 0,
             tag: 200,
             data: vec![],
             caps: vec![],
-        });
+        }).unwrap();
 
         // Get detail
         let detail = state.get_endpoint_detail(eid).unwrap();
@@ -598,11 +878,11 @@ mod tests {
 
         // Add messages to endpoints
         let ep1 = state.get_endpoint_mut(eid1).unwrap();
-        ep1.enqueue(crate::types::Message { sender: pid, tag: 0, data: vec![], caps: vec![] });
-        ep1.enqueue(crate::types::Message { sender: pid, tag: 0, data: vec![], caps: vec![] });
+        ep1.enqueue(crate::types::Message { sender: pid, badge: 0, tag: 0, data: vec![], caps: vec![] }).unwrap();
+        ep1.enqueue(crate::types::Message { sender: pid, badge: 0, tag: 0, data: vec![], caps: vec![] }).unwrap();
 
         let ep2 = state.get_endpoint_mut(eid2).unwrap();
-        ep2.enqueue(crate::types::Message { sender: pid, tag: 0, data: vec![], caps: vec![] });
+        ep2.enqueue(crate::types::Message { sender: pid, badge: 0, tag: 0, data: vec![], caps: vec![] }).unwrap();
 
         assert_eq!(state.total_pending_messages(), 3);
     }
@@ -625,6 +905,72 @@ mod tests {
         assert_eq!(state.next_endpoint_id, 3);
     }
 
+    #[test]
+    fn test_create_notification() {
+        let mut state = KernelState::new();
+        let pid = state.register_process("test", 1000);
+
+        let nid = state.create_notification(pid);
+        assert_eq!(nid, NotificationId(1));
+        assert_eq!(state.next_notification_id, 2);
+
+        let notification = state.get_notification(nid).unwrap();
+        assert_eq!(notification.owner, pid);
+        assert_eq!(notification.bits, 0);
+    }
+
+    #[test]
+    fn test_create_region_and_remove_mapper() {
+        let mut state = KernelState::new();
+        let owner = state.register_process("owner", 1000);
+        let peer = state.register_process("peer", 1000);
+
+        let rid = state.create_region(owner, 100);
+        assert_eq!(rid, RegionId(1));
+        assert_eq!(state.next_region_id, 2);
+        assert_eq!(state.get_region(rid).unwrap().size, crate::types::PAGE_SIZE);
+
+        state.get_region_mut(rid).unwrap().map(peer, false);
+        state.remove_process(peer);
+
+        // Mappings die with the process; the region stays
+        assert!(state.get_region(rid).unwrap().mappings.is_empty());
+    }
+
+    #[test]
+    fn test_register_child_and_descendants() {
+        let mut state = KernelState::new();
+        let init = state.register_process("init", 1000);
+        let a = state.register_child(init, "a", 1000);
+        let b = state.register_child(init, "b", 1000);
+        let a1 = state.register_child(a, "a1", 1000);
+
+        assert_eq!(state.get_process(a).unwrap().parent, init);
+        assert_eq!(state.get_process(init).unwrap().parent, ProcessId(0));
+        assert_eq!(state.children(init), vec![a, b]);
+        assert_eq!(state.descendants(init), vec![init, a, a1, b]);
+    }
+
+    #[test]
+    fn test_mint_device_caps() {
+        let mut state = KernelState::new();
+        let pid = state.register_process("pm", 1000);
+
+        let irq_slot = state.mint_irq_cap(pid, 4).unwrap();
+        let range = IoPortRange { base: 0x3F8, len: 8 };
+        let port_slot = state.mint_io_port_cap(pid, range).unwrap();
+
+        let cspace = state.get_cap_space(pid).unwrap();
+        let irq_cap = cspace.get(irq_slot).unwrap();
+        assert_eq!(irq_cap.object_type, ObjectType::Irq);
+        assert_eq!(irq_cap.object_id, 4);
+        let port_cap = cspace.get(port_slot).unwrap();
+        assert_eq!(port_cap.object_type, ObjectType::IoPort);
+        assert_eq!(IoPortRange::from_object_id(port_cap.object_id), range);
+
+        assert!(state.mint_irq_cap(ProcessId(99), 4).is_none());
+    }
+
     #[test]
     fn test_alloc_cap_id() {
         let mut state = KernelState::new();
@@ -24,12 +24,17 @@ use alloc::string::String;
 use alloc::vec;
 use alloc::vec::Vec;
 
-use crate::capability::{axiom_check, AxiomError, Capability};
+use crate::capability::{axiom_check, AxiomError, Capability, CapabilitySpace};
 use crate::state::KernelState;
 use crate::types::{
-    CapInfo, CapSlot, Endpoint, EndpointId, Message, ObjectType, Permissions, ProcessId,
-    ProcessState, TransferredCap,
+    CallState, CapInfo, CapSlot, ChildExitNotification, Endpoint, EndpointId, ExitStatus,
+    IoPortRange, IrqBinding, IrqTarget, Message, NotificationId, ObjectType, OverflowPolicy,
+    Permissions, Process, ProcessId, ProcessState, QuotaResource, RegionId, ResourceQuota,
+    RevokeNotification, TransferredCap, DEFAULT_QUEUE_CAPACITY, MAX_QUEUE_CAPACITY, MAX_REGION_SIZE,
+    MAX_WAIT_SLOTS, PAGE_SIZE,
 };
+use zos_ipc::kernel::{MSG_CAP_REVOKED, MSG_CHILD_EXITED, MSG_IRQ};
+use zos_ipc::revoke_reason;
 
 // ============================================================================
 // Syscall definitions
@@ -53,12 +58,30 @@ pub enum Syscall {
     /// Kill another process
     Kill { target_pid: ProcessId },
 
+    /// Kill a process and all of its descendants
+    KillTree { target_pid: ProcessId },
+
+    /// Reap a dead child (`None` = any child), blocking while it lives
+    WaitChild { pid: Option<ProcessId> },
+
+    /// Set a child's resource quota. It may not be looser than the caller's.
+    SetQuota {
+        target_pid: ProcessId,
+        quota: ResourceQuota,
+    },
+
     /// List all processes
     ListProcesses,
 
     /// Create an IPC endpoint
     CreateEndpoint,
 
+    /// Create an IPC endpoint with a bounded queue
+    CreateEndpointWithCapacity {
+        capacity: usize,
+        policy: OverflowPolicy,
+    },
+
     /// Send IPC message
     Send {
         endpoint_slot: CapSlot,
@@ -69,6 +92,16 @@ pub enum Syscall {
     /// Receive IPC message
     Receive { endpoint_slot: CapSlot },
 
+    /// Receive from the first listed endpoint that has a message.
+    ///
+    /// Parks the caller until a message arrives or `deadline_ns` passes
+    /// (0 = no deadline). Retrying after `WouldBlock` returns the message
+    /// or `TimedOut`.
+    ReceiveTimeout {
+        slots: Vec<CapSlot>,
+        deadline_ns: u64,
+    },
+
     /// Send IPC with capability transfer
     SendWithCaps {
         endpoint_slot: CapSlot,
@@ -78,12 +111,30 @@ pub enum Syscall {
     },
 
     /// Call (send + wait for reply)
+    ///
+    /// Mints a one-shot reply capability for the endpoint owner and blocks.
+    /// Retrying the call after `WouldBlock` collects the reply.
     Call {
         endpoint_slot: CapSlot,
         tag: u32,
         data: Vec<u8>,
     },
 
+    /// `Call` that gives up with `TimedOut` once `deadline_ns` has passed
+    CallTimeout {
+        endpoint_slot: CapSlot,
+        tag: u32,
+        data: Vec<u8>,
+        deadline_ns: u64,
+    },
+
+    /// Answer a caller's outstanding `Call`, consuming the reply capability
+    Reply {
+        caller: ProcessId,
+        tag: u32,
+        data: Vec<u8>,
+    },
+
     /// List capabilities
     ListCaps,
 
@@ -108,6 +159,90 @@ pub enum Syscall {
         slot: CapSlot,
         new_permissions: Permissions,
     },
+
+    /// Grant capability that expires `ttl_ns` after the grant
+    CapGrantWithTtl {
+        from_slot: CapSlot,
+        to_pid: ProcessId,
+        permissions: Permissions,
+        ttl_ns: u64,
+    },
+
+    /// Derive capability that expires `ttl_ns` after the derive
+    CapDeriveWithTtl {
+        slot: CapSlot,
+        new_permissions: Permissions,
+        ttl_ns: u64,
+    },
+
+    /// Create a notification object
+    CreateNotification,
+
+    /// OR `bits` into a notification's signal word
+    Signal { notification_slot: CapSlot, bits: u64 },
+
+    /// Take the signal word, blocking if no bits are pending
+    Wait { notification_slot: CapSlot },
+
+    /// Take the signal word without blocking (may be 0)
+    Poll { notification_slot: CapSlot },
+
+    /// Allocate a shared memory region of at least `size` bytes
+    CreateRegion { size: usize },
+
+    /// Map a shared region into the caller (read-only unless `writable`)
+    MapRegion { region_slot: CapSlot, writable: bool },
+
+    /// Unmap a shared region from the caller
+    UnmapRegion { region_slot: CapSlot },
+
+    /// Deliver an IRQ line to a notification by OR-ing in `bits`
+    IrqBindNotification {
+        irq_slot: CapSlot,
+        notification_slot: CapSlot,
+        bits: u64,
+    },
+
+    /// Deliver an IRQ line to an endpoint as `MSG_IRQ` messages
+    IrqBindEndpoint {
+        irq_slot: CapSlot,
+        endpoint_slot: CapSlot,
+    },
+
+    /// Unmask an IRQ line after handling its interrupt
+    IrqAck { irq_slot: CapSlot },
+
+    /// Stop delivering an IRQ line
+    IrqUnbind { irq_slot: CapSlot },
+
+    /// Read `width` (1, 2 or 4) bytes from an I/O port.
+    ///
+    /// The kernel core only authorizes the access; on `Ok` the runtime
+    /// performs it through the HAL and returns the value read.
+    IoPortIn {
+        port_slot: CapSlot,
+        port: u16,
+        width: u8,
+    },
+
+    /// Write `width` (1, 2 or 4) bytes to an I/O port (performed by the
+    /// runtime on `Ok`, as for `IoPortIn`)
+    IoPortOut {
+        port_slot: CapSlot,
+        port: u16,
+        width: u8,
+        value: u32,
+    },
+
+    /// Derive an endpoint capability stamped with a non-zero badge.
+    ///
+    /// Every message sent through the derived cap (or caps derived from it)
+    /// carries the badge, so the receiver can identify the client.
+    CapDeriveBadged {
+        slot: CapSlot,
+        new_permissions: Permissions,
+        badge: u64,
+    },
 }
 
 // ============================================================================
@@ -123,6 +258,10 @@ pub enum SyscallResult {
     Err(KernelError),
     /// Message received
     Message(Message),
+    /// Message received by `ReceiveTimeout`, with the slot it came through
+    MessageFrom { slot: CapSlot, message: Message },
+    /// Deadline passed before the wait completed
+    TimedOut,
     /// Would block (no message available)
     WouldBlock,
     /// Capability list
@@ -130,7 +269,9 @@ pub enum SyscallResult {
     /// Capability info
     CapInfo(CapInfo),
     /// Process list
-    ProcessList(Vec<(ProcessId, String, ProcessState)>),
+    ProcessList(Vec<(ProcessId, String, ProcessState, ResourceQuota)>),
+    /// Child reaped by `WaitChild`
+    Reaped { pid: ProcessId, status: ExitStatus },
 }
 
 /// Kernel errors
@@ -152,6 +293,8 @@ pub enum KernelError {
     InvalidArgument,
     /// Resource exhausted
     ResourceExhausted,
+    /// Endpoint queue is full
+    QueueFull,
 }
 
 impl From<AxiomError> for KernelError {
@@ -181,10 +324,36 @@ pub enum CommitType {
     ProcessExited { pid: u64, code: i32 },
     /// Process killed
     ProcessKilled { pid: u64, by: u64 },
+    /// Process faulted
+    ProcessFaulted { pid: u64, reason: u32 },
+    /// Orphaned process adopted by its grandparent
+    ProcessReparented { pid: u64, parent: u64 },
+    /// Dead process reaped and removed
+    ProcessReaped { pid: u64, by: u64 },
+    /// Resource quota set
+    QuotaSet {
+        pid: u64,
+        by: u64,
+        quota: ResourceQuota,
+    },
+    /// Syscall refused because it would exceed a quota
+    QuotaExceeded { pid: u64, resource: QuotaResource },
     /// Endpoint created
     EndpointCreated { id: u64, owner: u64 },
     /// Endpoint deleted
     EndpointDeleted { id: u64 },
+    /// Notification created
+    NotificationCreated { id: u64, owner: u64 },
+    /// Notification signalled
+    NotificationSignaled { from: u64, id: u64, bits: u64 },
+    /// Pending notification bits consumed by Wait or Poll
+    NotificationTaken { pid: u64, id: u64, bits: u64 },
+    /// Shared memory region created
+    RegionCreated { id: u64, owner: u64, size: usize },
+    /// Shared memory region mapped into a process
+    RegionMapped { pid: u64, id: u64, writable: bool },
+    /// Shared memory region unmapped from a process
+    RegionUnmapped { pid: u64, id: u64 },
     /// IPC message sent
     IpcSent {
         from: u64,
@@ -192,6 +361,27 @@ pub enum CommitType {
         tag: u32,
         size: usize,
     },
+    /// Reply capability minted for a call
+    ReplyCapMinted { server: u64, slot: u32, caller: u64 },
+    /// Call answered (reply capability consumed)
+    Replied {
+        from: u64,
+        to: u64,
+        tag: u32,
+        size: usize,
+    },
+    /// Call aborted because its server went away or dropped the reply cap
+    CallAborted { caller: u64 },
+    /// Call given up by its caller at the deadline
+    CallTimedOut { caller: u64 },
+    /// IRQ line bound to a driver
+    IrqBound { irq: u8, pid: u64 },
+    /// IRQ line unbound
+    IrqUnbound { irq: u8 },
+    /// IRQ delivered to its target (line now masked)
+    IrqRaised { irq: u8 },
+    /// IRQ acknowledged by the driver (line unmasked)
+    IrqAcked { irq: u8 },
     /// Capability granted
     CapGranted {
         from_pid: u64,
@@ -266,10 +456,28 @@ pub struct StepResult {
 /// 1. **Deterministic**: Same state + syscall always produces same result
 /// 2. **No side effects**: Only mutates the provided state
 /// 3. **Authority checked**: All capability operations go through axiom_check
+///
+/// A process over its syscall rate quota gets `ResourceExhausted` for
+/// everything but `Exit`.
+///
+/// After the syscall runs, capabilities that expired before `timestamp` are
+/// reaped and their commits appended to the result.
 pub fn step(state: &mut KernelState, from_pid: ProcessId, syscall: Syscall, timestamp: u64) -> StepResult {
     // Update metrics
     state.update_syscall_metrics(from_pid, timestamp);
 
+    let rate_limited = !matches!(syscall, Syscall::Exit { .. })
+        && !state.quota_allows(from_pid, QuotaResource::SyscallRate, 0);
+    let mut result = if rate_limited {
+        quota_exceeded(from_pid, QuotaResource::SyscallRate, timestamp)
+    } else {
+        dispatch(state, from_pid, syscall, timestamp)
+    };
+    result.commits.extend(sweep_expired(state, timestamp));
+    result
+}
+
+fn dispatch(state: &mut KernelState, from_pid: ProcessId, syscall: Syscall, timestamp: u64) -> StepResult {
     match syscall {
         Syscall::Debug { .. } => StepResult {
             result: SyscallResult::Ok(0),
@@ -288,14 +496,31 @@ pub fn step(state: &mut KernelState, from_pid: ProcessId, syscall: Syscall, time
 
         Syscall::Exit { code } => step_exit(state, from_pid, code, timestamp),
         Syscall::Kill { target_pid } => step_kill(state, from_pid, target_pid, timestamp),
+        Syscall::KillTree { target_pid } => step_kill_tree(state, from_pid, target_pid, timestamp),
+        Syscall::WaitChild { pid } => step_wait_child(state, from_pid, pid, timestamp),
+        Syscall::SetQuota { target_pid, quota } => {
+            step_set_quota(state, from_pid, target_pid, quota, timestamp)
+        }
         Syscall::ListProcesses => step_list_processes(state),
-        Syscall::CreateEndpoint => step_create_endpoint(state, from_pid, timestamp),
+        Syscall::CreateEndpoint => step_create_endpoint(
+            state,
+            from_pid,
+            DEFAULT_QUEUE_CAPACITY,
+            OverflowPolicy::Reject,
+            timestamp,
+        ),
+        Syscall::CreateEndpointWithCapacity { capacity, policy } => {
+            step_create_endpoint(state, from_pid, capacity, policy, timestamp)
+        }
         Syscall::Send {
             endpoint_slot,
             tag,
             data,
         } => step_send(state, from_pid, endpoint_slot, tag, data, timestamp),
         Syscall::Receive { endpoint_slot } => step_receive(state, from_pid, endpoint_slot, timestamp),
+        Syscall::ReceiveTimeout { slots, deadline_ns } => {
+            step_receive_timeout(state, from_pid, slots, deadline_ns, timestamp)
+        }
         Syscall::SendWithCaps {
             endpoint_slot,
             tag,
@@ -306,20 +531,93 @@ pub fn step(state: &mut KernelState, from_pid: ProcessId, syscall: Syscall, time
             endpoint_slot,
             tag,
             data,
-        } => step_call(state, from_pid, endpoint_slot, tag, data, timestamp),
+        } => step_call(state, from_pid, endpoint_slot, tag, data, 0, timestamp),
+        Syscall::CallTimeout {
+            endpoint_slot,
+            tag,
+            data,
+            deadline_ns,
+        } => step_call(state, from_pid, endpoint_slot, tag, data, deadline_ns, timestamp),
+        Syscall::Reply { caller, tag, data } => {
+            step_reply(state, from_pid, caller, tag, data, timestamp)
+        }
         Syscall::ListCaps => step_list_caps(state, from_pid),
         Syscall::CapGrant {
             from_slot,
             to_pid,
             permissions,
-        } => step_cap_grant(state, from_pid, from_slot, to_pid, permissions, timestamp),
+        } => step_cap_grant(state, from_pid, from_slot, to_pid, permissions, None, timestamp),
         Syscall::CapRevoke { slot } => step_cap_revoke(state, from_pid, slot, timestamp),
         Syscall::CapDelete { slot } => step_cap_delete(state, from_pid, slot, timestamp),
         Syscall::CapInspect { slot } => step_cap_inspect(state, from_pid, slot),
         Syscall::CapDerive {
             slot,
             new_permissions,
-        } => step_cap_derive(state, from_pid, slot, new_permissions, timestamp),
+        } => step_cap_derive(state, from_pid, slot, new_permissions, None, None, timestamp),
+        Syscall::CapGrantWithTtl {
+            from_slot,
+            to_pid,
+            permissions,
+            ttl_ns,
+        } => step_cap_grant(state, from_pid, from_slot, to_pid, permissions, Some(ttl_ns), timestamp),
+        Syscall::CapDeriveWithTtl {
+            slot,
+            new_permissions,
+            ttl_ns,
+        } => step_cap_derive(state, from_pid, slot, new_permissions, Some(ttl_ns), None, timestamp),
+        Syscall::CapDeriveBadged {
+            slot,
+            new_permissions,
+            badge,
+        } => step_cap_derive(state, from_pid, slot, new_permissions, None, Some(badge), timestamp),
+        Syscall::CreateNotification => step_create_notification(state, from_pid, timestamp),
+        Syscall::Signal {
+            notification_slot,
+            bits,
+        } => step_signal(state, from_pid, notification_slot, bits, timestamp),
+        Syscall::Wait { notification_slot } => {
+            step_take_signals(state, from_pid, notification_slot, true, timestamp)
+        }
+        Syscall::Poll { notification_slot } => {
+            step_take_signals(state, from_pid, notification_slot, false, timestamp)
+        }
+        Syscall::CreateRegion { size } => step_create_region(state, from_pid, size, timestamp),
+        Syscall::MapRegion {
+            region_slot,
+            writable,
+        } => step_map_region(state, from_pid, region_slot, writable, timestamp),
+        Syscall::UnmapRegion { region_slot } => {
+            step_unmap_region(state, from_pid, region_slot, timestamp)
+        }
+        Syscall::IrqBindNotification {
+            irq_slot,
+            notification_slot,
+            bits,
+        } => step_irq_bind_notification(
+            state,
+            from_pid,
+            irq_slot,
+            notification_slot,
+            bits,
+            timestamp,
+        ),
+        Syscall::IrqBindEndpoint {
+            irq_slot,
+            endpoint_slot,
+        } => step_irq_bind_endpoint(state, from_pid, irq_slot, endpoint_slot, timestamp),
+        Syscall::IrqAck { irq_slot } => step_irq_ack(state, from_pid, irq_slot, timestamp),
+        Syscall::IrqUnbind { irq_slot } => step_irq_unbind(state, from_pid, irq_slot, timestamp),
+        Syscall::IoPortIn {
+            port_slot,
+            port,
+            width,
+        } => step_io_port(state, from_pid, port_slot, port, width, false, timestamp),
+        Syscall::IoPortOut {
+            port_slot,
+            port,
+            width,
+            ..
+        } => step_io_port(state, from_pid, port_slot, port, width, true, timestamp),
     }
 }
 
@@ -328,19 +626,18 @@ pub fn step(state: &mut KernelState, from_pid: ProcessId, syscall: Syscall, time
 // ============================================================================
 
 fn step_exit(state: &mut KernelState, from_pid: ProcessId, code: i32, timestamp: u64) -> StepResult {
-    if let Some(proc) = state.processes.get_mut(&from_pid) {
-        proc.state = ProcessState::Zombie;
-    }
+    let mut commits = vec![Commit::new(
+        CommitType::ProcessExited {
+            pid: from_pid.0,
+            code,
+        },
+        timestamp,
+    )];
+    commits.extend(process_died(state, from_pid, ExitStatus::Exited(code), timestamp));
 
     StepResult {
         result: SyscallResult::Ok(code as u64),
-        commits: vec![Commit::new(
-            CommitType::ProcessExited {
-                pid: from_pid.0,
-                code,
-            },
-            timestamp,
-        )],
+        commits,
     }
 }
 
@@ -359,25 +656,347 @@ fn step_kill(
     }
 
     // Kill the target
-    state.kill_process(target_pid);
+    let mut commits = vec![Commit::new(
+        CommitType::ProcessKilled {
+            pid: target_pid.0,
+            by: from_pid.0,
+        },
+        timestamp,
+    )];
+    let status = ExitStatus::Killed { by: from_pid };
+    commits.extend(process_died(state, target_pid, status, timestamp));
 
     StepResult {
         result: SyscallResult::Ok(0),
-        commits: vec![Commit::new(
+        commits,
+    }
+}
+
+/// Kill a process and every descendant, returning how many died.
+///
+/// Only the target itself or one of its ancestors may do this. Processes
+/// are killed top-down, so each victim's children are first handed to the
+/// target's parent, which is told about every death.
+fn step_kill_tree(
+    state: &mut KernelState,
+    from_pid: ProcessId,
+    target_pid: ProcessId,
+    timestamp: u64,
+) -> StepResult {
+    if !state.processes.contains_key(&target_pid) {
+        return StepResult {
+            result: SyscallResult::Err(KernelError::ProcessNotFound),
+            commits: vec![],
+        };
+    }
+
+    if !state.is_ancestor_or_self(from_pid, target_pid) {
+        return StepResult {
+            result: SyscallResult::Err(KernelError::PermissionDenied),
+            commits: vec![],
+        };
+    }
+
+    let victims: Vec<ProcessId> = state
+        .descendants(target_pid)
+        .into_iter()
+        .filter(|pid| state.process_exists(*pid))
+        .collect();
+
+    let mut commits = Vec::new();
+    for &pid in &victims {
+        commits.push(Commit::new(
             CommitType::ProcessKilled {
+                pid: pid.0,
+                by: from_pid.0,
+            },
+            timestamp,
+        ));
+        let status = ExitStatus::Killed { by: from_pid };
+        commits.extend(process_died(state, pid, status, timestamp));
+    }
+
+    StepResult {
+        result: SyscallResult::Ok(victims.len() as u64),
+        commits,
+    }
+}
+
+fn step_wait_child(
+    state: &mut KernelState,
+    from_pid: ProcessId,
+    target: Option<ProcessId>,
+    timestamp: u64,
+) -> StepResult {
+    let children: Vec<&Process> = state
+        .processes
+        .values()
+        .filter(|p| p.parent == from_pid && target.is_none_or(|t| p.pid == t))
+        .collect();
+    if children.is_empty() {
+        return StepResult {
+            result: SyscallResult::Err(KernelError::ProcessNotFound),
+            commits: vec![],
+        };
+    }
+
+    let dead = children
+        .iter()
+        .find(|p| p.state == ProcessState::Zombie)
+        .map(|p| (p.pid, p.exit_status));
+    let (pid, status) = match dead {
+        Some(d) => d,
+        None => {
+            return StepResult {
+                result: SyscallResult::WouldBlock,
+                commits: vec![],
+            }
+        }
+    };
+
+    // Zombies made directly through KernelState::kill_process carry no status
+    let status = status.unwrap_or(ExitStatus::Killed { by: ProcessId(0) });
+    let commits = reap(state, pid, from_pid, timestamp);
+
+    StepResult {
+        result: SyscallResult::Reaped { pid, status },
+        commits,
+    }
+}
+
+// ============================================================================
+// Process lifecycle
+// ============================================================================
+
+/// Report a fault in `pid` (trap, fuel exhaustion, ...).
+///
+/// Like [`interrupt`], this is an entry point for the runtime rather than a
+/// syscall. The process dies as if killed, with the fault as its status.
+pub fn fault(state: &mut KernelState, pid: ProcessId, reason: u32, timestamp: u64) -> Vec<Commit> {
+    if !state.process_exists(pid) {
+        return vec![];
+    }
+    let mut commits = vec![Commit::new(
+        CommitType::ProcessFaulted { pid: pid.0, reason },
+        timestamp,
+    )];
+    commits.extend(process_died(state, pid, ExitStatus::Faulted { reason }, timestamp));
+    commits
+}
+
+/// Turn `pid` into a zombie and unwind what it was taking part in.
+///
+/// Outstanding calls are aborted, IRQ lines unbound, endpoints deleted,
+/// children handed to the parent, and the parent sent `MSG_CHILD_EXITED`.
+/// A process that is already dead keeps its original status.
+fn process_died(
+    state: &mut KernelState,
+    pid: ProcessId,
+    status: ExitStatus,
+    timestamp: u64,
+) -> Vec<Commit> {
+    let parent = match state.processes.get_mut(&pid) {
+        Some(proc) if proc.state != ProcessState::Zombie => {
+            proc.state = ProcessState::Zombie;
+            proc.exit_status = Some(status);
+            proc.parent
+        }
+        _ => return vec![],
+    };
+    state.deadlines.remove(&pid);
+    state.waits.remove(&pid);
+
+    let mut commits = abort_calls(state, pid, timestamp);
+    commits.extend(unbind_irqs(state, pid, timestamp));
+    commits.extend(delete_endpoints(state, pid, timestamp));
+
+    for child in state.children(pid) {
+        if let Some(proc) = state.processes.get_mut(&child) {
+            proc.parent = parent;
+        }
+        commits.push(Commit::new(
+            CommitType::ProcessReparented {
+                pid: child.0,
+                parent: parent.0,
+            },
+            timestamp,
+        ));
+    }
+
+    let data = ChildExitNotification { pid, status }.to_payload();
+    commits.extend(notify_process(state, parent, MSG_CHILD_EXITED, data, timestamp));
+    commits
+}
+
+/// Delete the endpoints `pid` owns, revoking every capability to them.
+///
+/// Runs at death rather than at reap, so nobody can queue messages for
+/// (or block sending to) a process that will never receive them.
+fn delete_endpoints(state: &mut KernelState, pid: ProcessId, timestamp: u64) -> Vec<Commit> {
+    let endpoints: Vec<EndpointId> = state
+        .endpoints
+        .values()
+        .filter(|e| e.owner == pid)
+        .map(|e| e.id)
+        .collect();
+    if endpoints.is_empty() {
+        return vec![];
+    }
+
+    let mut commits = revoke_dangling(state, timestamp, |cap| {
+        cap.object_type == ObjectType::Endpoint && endpoints.contains(&EndpointId(cap.object_id))
+    });
+    for id in endpoints {
+        state.remove_endpoint(id);
+        commits.push(Commit::new(CommitType::EndpointDeleted { id: id.0 }, timestamp));
+    }
+    commits
+}
+
+/// Remove a dead process and everything that still names it.
+///
+/// Capabilities held by others for the process itself are revoked with
+/// `revoke_reason::PROCESS_EXIT`, then the process is removed. Its
+/// endpoints went when it died.
+fn reap(state: &mut KernelState, pid: ProcessId, by: ProcessId, timestamp: u64) -> Vec<Commit> {
+    let mut commits = revoke_dangling(state, timestamp, |cap| {
+        cap.object_type == ObjectType::Process && cap.object_id == pid.0
+    });
+
+    state.remove_process(pid);
+    commits.push(Commit::new(
+        CommitType::ProcessReaped {
+            pid: pid.0,
+            by: by.0,
+        },
+        timestamp,
+    ));
+    commits
+}
+
+/// Revoke every capability matching `names_dead` with
+/// `revoke_reason::PROCESS_EXIT`, notifying each holder.
+fn revoke_dangling(
+    state: &mut KernelState,
+    timestamp: u64,
+    names_dead: impl Fn(&Capability) -> bool,
+) -> Vec<Commit> {
+    let dangling: Vec<(ProcessId, CapSlot)> = state
+        .cap_spaces
+        .iter()
+        .flat_map(|(&holder, cspace)| {
+            cspace
+                .slots
+                .iter()
+                .filter(|(_, cap)| names_dead(cap))
+                .map(move |(&slot, _)| (holder, slot))
+        })
+        .collect();
+
+    let mut commits = Vec::new();
+    for (holder, slot) in dangling {
+        let cap = match state.get_cap_space_mut(holder).and_then(|cs| cs.remove(slot)) {
+            Some(cap) => cap,
+            None => continue,
+        };
+        state.cap_derivations.remove(cap.id);
+        commits.push(Commit::new(
+            CommitType::CapRevoked {
+                pid: holder.0,
+                slot,
+            },
+            timestamp,
+        ));
+
+        let notification = RevokeNotification {
+            pid: holder,
+            slot,
+            object_type: cap.object_type as u8,
+            object_id: cap.object_id,
+            reason: revoke_reason::PROCESS_EXIT,
+        };
+        commits.extend(notify_revoked(state, &notification, timestamp));
+    }
+    commits
+}
+
+fn step_set_quota(
+    state: &mut KernelState,
+    from_pid: ProcessId,
+    target_pid: ProcessId,
+    quota: ResourceQuota,
+    timestamp: u64,
+) -> StepResult {
+    let parent = match state.get_process(target_pid) {
+        Some(proc) => proc.parent,
+        None => {
+            return StepResult {
+                result: SyscallResult::Err(KernelError::ProcessNotFound),
+                commits: vec![],
+            }
+        }
+    };
+
+    // Only the spawner sets limits, and it cannot hand out more than it has
+    let caller_quota = state.get_process(from_pid).map(|p| p.quota).unwrap_or_default();
+    if parent != from_pid || !quota.is_within(&caller_quota) {
+        return StepResult {
+            result: SyscallResult::Err(KernelError::PermissionDenied),
+            commits: vec![],
+        };
+    }
+
+    if let Some(proc) = state.get_process_mut(target_pid) {
+        proc.quota = quota;
+    }
+
+    StepResult {
+        result: SyscallResult::Ok(0),
+        commits: vec![Commit::new(
+            CommitType::QuotaSet {
                 pid: target_pid.0,
                 by: from_pid.0,
+                quota,
+            },
+            timestamp,
+        )],
+    }
+}
+
+/// Result of a syscall that would take `pid` over its quota
+fn quota_exceeded(pid: ProcessId, resource: QuotaResource, timestamp: u64) -> StepResult {
+    StepResult {
+        result: SyscallResult::Err(KernelError::ResourceExhausted),
+        commits: vec![Commit::new(
+            CommitType::QuotaExceeded {
+                pid: pid.0,
+                resource,
             },
             timestamp,
         )],
     }
 }
 
+/// Refuse the syscall if `pid` cannot take `amount` more of `resource`
+fn check_quota(
+    state: &KernelState,
+    pid: ProcessId,
+    resource: QuotaResource,
+    amount: u64,
+    timestamp: u64,
+) -> Option<StepResult> {
+    if state.quota_allows(pid, resource, amount) {
+        None
+    } else {
+        Some(quota_exceeded(pid, resource, timestamp))
+    }
+}
+
 fn step_list_processes(state: &KernelState) -> StepResult {
     let procs: Vec<_> = state
         .processes
         .iter()
-        .map(|(pid, p)| (*pid, p.name.clone(), p.state))
+        .map(|(pid, p)| (*pid, p.name.clone(), p.state, p.quota))
         .collect();
 
     StepResult {
@@ -386,7 +1005,13 @@ fn step_list_processes(state: &KernelState) -> StepResult {
     }
 }
 
-fn step_create_endpoint(state: &mut KernelState, from_pid: ProcessId, timestamp: u64) -> StepResult {
+fn step_create_endpoint(
+    state: &mut KernelState,
+    from_pid: ProcessId,
+    capacity: usize,
+    policy: OverflowPolicy,
+    timestamp: u64,
+) -> StepResult {
     // Verify process exists
     if !state.process_exists(from_pid) {
         return StepResult {
@@ -395,9 +1020,23 @@ fn step_create_endpoint(state: &mut KernelState, from_pid: ProcessId, timestamp:
         };
     }
 
+    if capacity == 0 || capacity > MAX_QUEUE_CAPACITY {
+        return StepResult {
+            result: SyscallResult::Err(KernelError::InvalidArgument),
+            commits: vec![],
+        };
+    }
+
+    let needs = [(QuotaResource::Endpoints, 1), (QuotaResource::CapSlots, 1)];
+    for (resource, amount) in needs {
+        if let Some(exceeded) = check_quota(state, from_pid, resource, amount, timestamp) {
+            return exceeded;
+        }
+    }
+
     // Create endpoint
     let endpoint_id = state.alloc_endpoint_id();
-    let endpoint = Endpoint::new(endpoint_id, from_pid);
+    let endpoint = Endpoint::with_capacity(endpoint_id, from_pid, capacity, policy);
     state.endpoints.insert(endpoint_id, endpoint);
 
     // Create capability for the endpoint
@@ -408,6 +1047,7 @@ fn step_create_endpoint(state: &mut KernelState, from_pid: ProcessId, timestamp:
         permissions: Permissions::full(),
         generation: 0,
         expires_at: 0,
+        badge: 0,
     };
 
     let slot = state
@@ -476,8 +1116,14 @@ fn step_send(
     };
 
     let endpoint_id = EndpointId(cap.object_id);
+    let badge = cap.badge;
     let data_size = data.len();
 
+    let queued = QuotaResource::QueuedBytes;
+    if let Some(exceeded) = check_quota(state, from_pid, queued, data_size as u64, timestamp) {
+        return exceeded;
+    }
+
     // Enqueue message
     let endpoint = match state.get_endpoint_mut(endpoint_id) {
         Some(e) => e,
@@ -491,11 +1137,15 @@ fn step_send(
 
     let msg = Message {
         sender: from_pid,
+        badge,
         tag,
         data,
         caps: vec![],
     };
-    endpoint.enqueue(msg);
+    if endpoint.enqueue(msg).is_err() {
+        return queue_full(endpoint.policy);
+    }
+    wake_receivers(state, endpoint_id);
 
     // Update sender metrics
     if let Some(proc) = state.get_process_mut(from_pid) {
@@ -519,10 +1169,25 @@ fn step_send(
     }
 }
 
-fn step_receive(
-    state: &mut KernelState,
-    from_pid: ProcessId,
-    endpoint_slot: CapSlot,
+/// Result of a send that found the endpoint queue full.
+///
+/// Under `Block` the sender gets `WouldBlock` and retries the syscall later,
+/// just like a receive on an empty queue.
+fn queue_full(policy: OverflowPolicy) -> StepResult {
+    let result = match policy {
+        OverflowPolicy::Reject => SyscallResult::Err(KernelError::QueueFull),
+        OverflowPolicy::Block => SyscallResult::WouldBlock,
+    };
+    StepResult {
+        result,
+        commits: vec![],
+    }
+}
+
+fn step_receive(
+    state: &mut KernelState,
+    from_pid: ProcessId,
+    endpoint_slot: CapSlot,
     timestamp: u64,
 ) -> StepResult {
     // Verify capability
@@ -586,6 +1251,135 @@ fn step_receive(
     }
 }
 
+fn step_receive_timeout(
+    state: &mut KernelState,
+    from_pid: ProcessId,
+    slots: Vec<CapSlot>,
+    deadline_ns: u64,
+    timestamp: u64,
+) -> StepResult {
+    if slots.is_empty() || slots.len() > MAX_WAIT_SLOTS {
+        return StepResult {
+            result: SyscallResult::Err(KernelError::InvalidArgument),
+            commits: vec![],
+        };
+    }
+
+    let cspace = match state.get_cap_space(from_pid) {
+        Some(cs) => cs,
+        None => {
+            return StepResult {
+                result: SyscallResult::Err(KernelError::ProcessNotFound),
+                commits: vec![],
+            }
+        }
+    };
+
+    // Every slot must be receivable, even if an earlier one has a message
+    let mut endpoints = Vec::with_capacity(slots.len());
+    for &slot in &slots {
+        let cap = match axiom_check(
+            cspace,
+            slot,
+            &Permissions::read_only(),
+            Some(ObjectType::Endpoint),
+            timestamp,
+        ) {
+            Ok(c) => c,
+            Err(e) => {
+                return StepResult {
+                    result: SyscallResult::Err(e.into()),
+                    commits: vec![],
+                }
+            }
+        };
+        let endpoint_id = EndpointId(cap.object_id);
+        if state.get_endpoint(endpoint_id).is_none() {
+            return StepResult {
+                result: SyscallResult::Err(KernelError::EndpointNotFound),
+                commits: vec![],
+            };
+        }
+        endpoints.push((slot, endpoint_id));
+    }
+
+    // Listed order is priority order
+    for &(slot, endpoint_id) in &endpoints {
+        let message = match state.get_endpoint_mut(endpoint_id).and_then(|e| e.dequeue()) {
+            Some(msg) => msg,
+            None => continue,
+        };
+        let data_size = message.data.len() as u64;
+        if let Some(proc) = state.get_process_mut(from_pid) {
+            proc.metrics.ipc_received += 1;
+            proc.metrics.ipc_bytes_received += data_size;
+        }
+        wake(state, from_pid);
+        return StepResult {
+            result: SyscallResult::MessageFrom { slot, message },
+            commits: vec![],
+        };
+    }
+
+    if deadline_passed(deadline_ns, timestamp) {
+        wake(state, from_pid);
+        return StepResult {
+            result: SyscallResult::TimedOut,
+            commits: vec![],
+        };
+    }
+
+    park(state, from_pid, deadline_ns);
+    let waiting_on = endpoints.into_iter().map(|(_, id)| id).collect();
+    state.waits.insert(from_pid, waiting_on);
+    StepResult {
+        result: SyscallResult::WouldBlock,
+        commits: vec![],
+    }
+}
+
+/// Block `pid` in a wait that ends at `deadline_ns` (0 = no deadline).
+fn park(state: &mut KernelState, pid: ProcessId, deadline_ns: u64) {
+    match state.get_process_mut(pid) {
+        Some(proc) if proc.state != ProcessState::Zombie => proc.state = ProcessState::Blocked,
+        _ => return,
+    }
+    if deadline_ns == 0 {
+        state.deadlines.remove(&pid);
+    } else {
+        state.deadlines.insert(pid, deadline_ns);
+    }
+}
+
+/// Let a parked process run again and forget its deadline.
+fn wake(state: &mut KernelState, pid: ProcessId) {
+    state.deadlines.remove(&pid);
+    state.waits.remove(&pid);
+    if let Some(proc) = state.get_process_mut(pid) {
+        if proc.state == ProcessState::Blocked {
+            proc.state = ProcessState::Running;
+        }
+    }
+}
+
+/// Wake every receiver parked on `endpoint_id` so its retried
+/// `ReceiveTimeout` picks up the message that was just queued.
+fn wake_receivers(state: &mut KernelState, endpoint_id: EndpointId) {
+    let waiting: Vec<ProcessId> = state
+        .waits
+        .iter()
+        .filter(|(_, endpoints)| endpoints.contains(&endpoint_id))
+        .map(|(pid, _)| *pid)
+        .collect();
+    for pid in waiting {
+        wake(state, pid);
+    }
+}
+
+fn deadline_passed(deadline_ns: u64, timestamp: u64) -> bool {
+    deadline_ns != 0 && timestamp >= deadline_ns
+}
+
 fn step_send_with_caps(
     state: &mut KernelState,
     from_pid: ProcessId,
@@ -661,6 +1455,11 @@ fn step_send_with_caps(
     let endpoint_id = EndpointId(endpoint_cap.object_id);
     let data_size = data.len();
 
+    let queued = QuotaResource::QueuedBytes;
+    if let Some(exceeded) = check_quota(state, from_pid, queued, data_size as u64, timestamp) {
+        return exceeded;
+    }
+
     // Enqueue message
     let endpoint = match state.get_endpoint_mut(endpoint_id) {
         Some(e) => e,
@@ -674,11 +1473,15 @@ fn step_send_with_caps(
 
     let msg = Message {
         sender: from_pid,
+        badge: endpoint_cap.badge,
         tag,
         data,
         caps: transferred_caps,
     };
-    endpoint.enqueue(msg);
+    if endpoint.enqueue(msg).is_err() {
+        return queue_full(endpoint.policy);
+    }
+    wake_receivers(state, endpoint_id);
 
     state.total_ipc_count += 1;
 
@@ -702,21 +1505,272 @@ fn step_call(
     endpoint_slot: CapSlot,
     tag: u32,
     data: Vec<u8>,
+    deadline_ns: u64,
     timestamp: u64,
 ) -> StepResult {
+    // A retried call collects the outcome of the outstanding one
+    match state.calls.remove(&from_pid) {
+        Some(CallState::Replied(msg)) => {
+            let data_size = msg.data.len() as u64;
+            if let Some(proc) = state.get_process_mut(from_pid) {
+                proc.metrics.ipc_received += 1;
+                proc.metrics.ipc_bytes_received += data_size;
+            }
+            return StepResult {
+                result: SyscallResult::Message(msg),
+                commits: vec![],
+            };
+        }
+        Some(CallState::Aborted) => {
+            return StepResult {
+                result: SyscallResult::Err(KernelError::ProcessNotFound),
+                commits: vec![],
+            };
+        }
+        Some(CallState::Pending { server }) if deadline_passed(deadline_ns, timestamp) => {
+            // A late reply now finds no capability to answer with
+            let mut commits: Vec<Commit> =
+                drop_reply_cap(state, server, from_pid, timestamp).into_iter().collect();
+            commits.push(Commit::new(
+                CommitType::CallTimedOut {
+                    caller: from_pid.0,
+                },
+                timestamp,
+            ));
+            wake(state, from_pid);
+            return StepResult {
+                result: SyscallResult::TimedOut,
+                commits,
+            };
+        }
+        Some(pending) => {
+            state.calls.insert(from_pid, pending);
+            park(state, from_pid, deadline_ns);
+            return StepResult {
+                result: SyscallResult::WouldBlock,
+                commits: vec![],
+            };
+        }
+        None => {}
+    }
+
+    if deadline_passed(deadline_ns, timestamp) {
+        return StepResult {
+            result: SyscallResult::TimedOut,
+            commits: vec![],
+        };
+    }
+
+    // The endpoint owner serves the call. Bad slots fall through to
+    // step_send, which reports them.
+    let server = state
+        .get_cap_space(from_pid)
+        .and_then(|cs| cs.get(endpoint_slot))
+        .filter(|cap| cap.object_type == ObjectType::Endpoint)
+        .and_then(|cap| state.get_endpoint(EndpointId(cap.object_id)))
+        .map(|endpoint| endpoint.owner);
+    if let Some(server) = server {
+        if !state.process_exists(server) {
+            return StepResult {
+                result: SyscallResult::Err(KernelError::ProcessNotFound),
+                commits: vec![],
+            };
+        }
+    }
+
     // Send the message first
     let send_result = step_send(state, from_pid, endpoint_slot, tag, data, timestamp);
+    let (server, mut commits) = match (send_result.result, server) {
+        (SyscallResult::Ok(_), Some(server)) => (server, send_result.commits),
+        (err, _) => {
+            return StepResult {
+                result: err,
+                commits: send_result.commits,
+            }
+        }
+    };
 
-    match send_result.result {
-        SyscallResult::Ok(_) => StepResult {
-            result: SyscallResult::WouldBlock, // Block waiting for reply
-            commits: send_result.commits,
-        },
-        err => StepResult {
-            result: err,
-            commits: send_result.commits,
+    // Mint the reply capability into the server's CSpace. It names the
+    // caller and carries no grant right, so it cannot be passed on.
+    let reply_cap = Capability {
+        id: state.alloc_cap_id(),
+        object_type: ObjectType::Reply,
+        object_id: from_pid.0,
+        permissions: Permissions::write_only(),
+        generation: 0,
+        expires_at: 0,
+        badge: 0,
+    };
+    let slot = state
+        .get_cap_space_mut(server)
+        .map(|cs| cs.insert(reply_cap))
+        .unwrap_or(0);
+
+    state.calls.insert(from_pid, CallState::Pending { server });
+    park(state, from_pid, deadline_ns);
+
+    commits.push(Commit::new(
+        CommitType::ReplyCapMinted {
+            server: server.0,
+            slot,
+            caller: from_pid.0,
         },
+        timestamp,
+    ));
+
+    StepResult {
+        result: SyscallResult::WouldBlock, // Block waiting for reply
+        commits,
+    }
+}
+
+fn step_reply(
+    state: &mut KernelState,
+    from_pid: ProcessId,
+    caller: ProcessId,
+    tag: u32,
+    data: Vec<u8>,
+    timestamp: u64,
+) -> StepResult {
+    use crate::types::MAX_MESSAGE_SIZE;
+
+    if data.len() > MAX_MESSAGE_SIZE {
+        return StepResult {
+            result: SyscallResult::Err(KernelError::MessageTooLarge),
+            commits: vec![],
+        };
+    }
+
+    let cspace = match state.get_cap_space(from_pid) {
+        Some(cs) => cs,
+        None => {
+            return StepResult {
+                result: SyscallResult::Err(KernelError::ProcessNotFound),
+                commits: vec![],
+            }
+        }
+    };
+
+    // Only the holder of the caller's reply capability may answer
+    let slot = match find_reply_cap(cspace, caller) {
+        Some(slot) => slot,
+        None => {
+            return StepResult {
+                result: SyscallResult::Err(KernelError::InvalidCapability),
+                commits: vec![],
+            }
+        }
+    };
+    if let Err(e) = axiom_check(
+        cspace,
+        slot,
+        &Permissions::write_only(),
+        Some(ObjectType::Reply),
+        timestamp,
+    ) {
+        return StepResult {
+            result: SyscallResult::Err(e.into()),
+            commits: vec![],
+        };
+    }
+
+    // Consume the capability: it answers exactly one call
+    if let Some(cap) = state.get_cap_space_mut(from_pid).and_then(|cs| cs.remove(slot)) {
+        state.cap_derivations.remove(cap.id);
+    }
+
+    let size = data.len();
+    state.calls.insert(
+        caller,
+        CallState::Replied(Message {
+            sender: from_pid,
+            badge: 0,
+            tag,
+            data,
+            caps: vec![],
+        }),
+    );
+    wake(state, caller);
+    if let Some(proc) = state.get_process_mut(from_pid) {
+        proc.metrics.ipc_sent += 1;
+        proc.metrics.ipc_bytes_sent += size as u64;
+    }
+    state.total_ipc_count += 1;
+
+    StepResult {
+        result: SyscallResult::Ok(0),
+        commits: vec![Commit::new(
+            CommitType::Replied {
+                from: from_pid.0,
+                to: caller.0,
+                tag,
+                size,
+            },
+            timestamp,
+        )],
+    }
+}
+
+/// Slot of the reply capability naming `caller`, if the CSpace holds one.
+fn find_reply_cap(cspace: &CapabilitySpace, caller: ProcessId) -> Option<CapSlot> {
+    cspace
+        .slots
+        .iter()
+        .find(|(_, cap)| cap.object_type == ObjectType::Reply && cap.object_id == caller.0)
+        .map(|(&slot, _)| slot)
+}
+
+/// Wake a pending caller with `CallState::Aborted`.
+fn abort_call(state: &mut KernelState, caller: ProcessId, timestamp: u64) -> Commit {
+    state.calls.insert(caller, CallState::Aborted);
+    wake(state, caller);
+    Commit::new(CommitType::CallAborted { caller: caller.0 }, timestamp)
+}
+
+/// Unwind the calls a dying process takes part in.
+///
+/// Its own outstanding call is dropped together with the reply capability
+/// the server holds for it. Calls it was serving are aborted so their
+/// callers wake up instead of blocking forever.
+fn abort_calls(state: &mut KernelState, pid: ProcessId, timestamp: u64) -> Vec<Commit> {
+    let mut commits = Vec::new();
+
+    if let Some(CallState::Pending { server }) = state.calls.remove(&pid) {
+        commits.extend(drop_reply_cap(state, server, pid, timestamp));
+    }
+
+    let served: Vec<ProcessId> = state
+        .calls
+        .iter()
+        .filter(|(_, call)| matches!(call, CallState::Pending { server } if *server == pid))
+        .map(|(&caller, _)| caller)
+        .collect();
+    for caller in served {
+        commits.extend(drop_reply_cap(state, pid, caller, timestamp));
+        commits.push(abort_call(state, caller, timestamp));
     }
+
+    commits
+}
+
+/// Remove the reply capability `holder` has for `caller`.
+fn drop_reply_cap(
+    state: &mut KernelState,
+    holder: ProcessId,
+    caller: ProcessId,
+    timestamp: u64,
+) -> Option<Commit> {
+    let cspace = state.get_cap_space_mut(holder)?;
+    let slot = find_reply_cap(cspace, caller)?;
+    let cap = cspace.remove(slot)?;
+    state.cap_derivations.remove(cap.id);
+    Some(Commit::new(
+        CommitType::CapRevoked {
+            pid: holder.0,
+            slot,
+        },
+        timestamp,
+    ))
 }
 
 fn step_list_caps(state: &KernelState, from_pid: ProcessId) -> StepResult {
@@ -737,8 +1791,19 @@ fn step_cap_grant(
     from_slot: CapSlot,
     to_pid: ProcessId,
     permissions: Permissions,
+    ttl_ns: Option<u64>,
     timestamp: u64,
 ) -> StepResult {
+    let expires_at_override = match ttl_expiry(ttl_ns, timestamp) {
+        Ok(e) => e,
+        Err(e) => {
+            return StepResult {
+                result: SyscallResult::Err(e),
+                commits: vec![],
+            }
+        }
+    };
+
     // Verify source capability has grant permission
     let source_cap = {
         let cspace = match state.get_cap_space(from_pid) {
@@ -784,6 +1849,11 @@ fn step_cap_grant(
         };
     }
 
+    // The new slot counts against the recipient's quota
+    if let Some(exceeded) = check_quota(state, to_pid, QuotaResource::CapSlots, 1, timestamp) {
+        return exceeded;
+    }
+
     // Create new capability in target's cspace
     let new_cap = Capability {
         id: state.alloc_cap_id(),
@@ -791,8 +1861,10 @@ fn step_cap_grant(
         object_id: source_cap.object_id,
         permissions,
         generation: source_cap.generation + 1,
-        expires_at: source_cap.expires_at,
+        expires_at: child_expiry(source_cap.expires_at, expires_at_override),
+        badge: source_cap.badge,
     };
+    state.cap_derivations.link(source_cap.id, new_cap.id);
 
     let new_slot = state
         .get_cap_space_mut(to_pid)
@@ -813,46 +1885,183 @@ fn step_cap_grant(
     }
 }
 
+/// Revoke a capability and everything derived from it.
+///
+/// Every capability in the subtree is removed in one step with a `CapRevoked`
+/// commit per slot. Holders other than the revoker are sent a
+/// `MSG_CAP_REVOKED` notification on their first endpoint.
 fn step_cap_revoke(state: &mut KernelState, from_pid: ProcessId, slot: CapSlot, timestamp: u64) -> StepResult {
-    // For now, revoke just removes the capability (full revocation tree would be more complex)
-    let removed = state
-        .get_cap_space_mut(from_pid)
-        .and_then(|cs| cs.remove(slot));
+    let root_id = match state.get_cap_space(from_pid).and_then(|cs| cs.get(slot)) {
+        Some(cap) => cap.id,
+        None => {
+            return StepResult {
+                result: SyscallResult::Err(KernelError::InvalidCapability),
+                commits: vec![],
+            }
+        }
+    };
 
-    match removed {
-        Some(_) => StepResult {
-            result: SyscallResult::Ok(0),
-            commits: vec![Commit::new(
-                CommitType::CapRevoked {
-                    pid: from_pid.0,
-                    slot,
-                },
-                timestamp,
-            )],
-        },
-        None => StepResult {
-            result: SyscallResult::Err(KernelError::InvalidCapability),
-            commits: vec![],
-        },
-    }
-}
+    let subtree = state.cap_derivations.remove_subtree(root_id);
+    let locations = state.locate_caps(&subtree);
 
-fn step_cap_delete(state: &mut KernelState, from_pid: ProcessId, slot: CapSlot, timestamp: u64) -> StepResult {
-    let removed = state
-        .get_cap_space_mut(from_pid)
-        .and_then(|cs| cs.remove(slot));
+    let mut commits = Vec::new();
+    for cap_id in subtree {
+        let (pid, cap_slot) = match locations.get(&cap_id) {
+            Some(&loc) => loc,
+            None => continue,
+        };
+        let cap = match state.get_cap_space_mut(pid).and_then(|cs| cs.remove(cap_slot)) {
+            Some(cap) => cap,
+            None => continue,
+        };
+
+        commits.push(Commit::new(
+            CommitType::CapRevoked {
+                pid: pid.0,
+                slot: cap_slot,
+            },
+            timestamp,
+        ));
+
+        if cap_id != root_id {
+            let notification = RevokeNotification {
+                pid,
+                slot: cap_slot,
+                object_type: cap.object_type as u8,
+                object_id: cap.object_id,
+                reason: revoke_reason::EXPLICIT,
+            };
+            commits.extend(notify_revoked(state, &notification, timestamp));
+        }
+        commits.extend(release_cap(state, pid, &cap, timestamp));
+    }
+
+    StepResult {
+        result: SyscallResult::Ok(0),
+        commits,
+    }
+}
+
+/// Undo what a removed capability was holding open for `pid`.
+///
+/// Dropping a region cap may leave a mapping unbacked, dropping a reply
+/// cap leaves its caller with nobody to answer, and dropping an IRQ cap
+/// may leave a binding without authority.
+fn release_cap(
+    state: &mut KernelState,
+    pid: ProcessId,
+    cap: &Capability,
+    timestamp: u64,
+) -> Vec<Commit> {
+    match cap.object_type {
+        ObjectType::Memory => unmap_unbacked(state, pid, RegionId(cap.object_id), timestamp)
+            .into_iter()
+            .collect(),
+        ObjectType::Reply => {
+            let caller = ProcessId(cap.object_id);
+            match state.calls.get(&caller) {
+                Some(CallState::Pending { server }) if *server == pid => {
+                    vec![abort_call(state, caller, timestamp)]
+                }
+                _ => vec![],
+            }
+        }
+        ObjectType::Irq => {
+            let irq = cap.object_id as u8;
+            let owned = matches!(state.irq_bindings.get(&irq), Some(b) if b.owner == pid);
+            let still_held = state
+                .get_cap_space(pid)
+                .is_some_and(|cs| find_irq_cap(cs, irq).is_some());
+            if owned && !still_held {
+                state.irq_bindings.remove(&irq);
+                vec![Commit::new(CommitType::IrqUnbound { irq }, timestamp)]
+            } else {
+                vec![]
+            }
+        }
+        _ => vec![],
+    }
+}
+
+/// Queue a `MSG_CAP_REVOKED` message on the holder's first endpoint.
+fn notify_revoked(
+    state: &mut KernelState,
+    notification: &RevokeNotification,
+    timestamp: u64,
+) -> Option<Commit> {
+    let data = notification.to_payload();
+    notify_process(state, notification.pid, MSG_CAP_REVOKED, data, timestamp)
+}
+
+/// Queue a kernel message on `pid`'s first endpoint.
+///
+/// The kernel (PID 0) is the sender, so no capability check applies, and
+/// the message may use the queue's kernel reserve. Dead processes,
+/// processes without an endpoint, and processes whose reserve is used up
+/// are not notified; a dead child stays a zombie that `WaitChild` still
+/// collects either way.
+fn notify_process(
+    state: &mut KernelState,
+    pid: ProcessId,
+    tag: u32,
+    data: Vec<u8>,
+    timestamp: u64,
+) -> Option<Commit> {
+    if !state.process_exists(pid) {
+        return None;
+    }
+
+    let endpoint = state.endpoints.values_mut().find(|e| e.owner == pid)?;
+
+    let size = data.len();
+    endpoint
+        .enqueue_kernel(Message {
+            sender: ProcessId(0),
+            badge: 0,
+            tag,
+            data,
+            caps: vec![],
+        })
+        .ok()?;
+    let endpoint_id = endpoint.id;
+    wake_receivers(state, endpoint_id);
+
+    Some(Commit::new(
+        CommitType::IpcSent {
+            from: 0,
+            endpoint: endpoint_id.0,
+            tag,
+            size,
+        },
+        timestamp,
+    ))
+}
+
+fn step_cap_delete(state: &mut KernelState, from_pid: ProcessId, slot: CapSlot, timestamp: u64) -> StepResult {
+    let removed = state
+        .get_cap_space_mut(from_pid)
+        .and_then(|cs| cs.remove(slot));
+
+    // Caps derived from this one stay revocable through its parent
+    if let Some(cap) = &removed {
+        state.cap_derivations.remove(cap.id);
+    }
 
     match removed {
-        Some(_) => StepResult {
-            result: SyscallResult::Ok(0),
-            commits: vec![Commit::new(
+        Some(cap) => {
+            let mut commits = vec![Commit::new(
                 CommitType::CapDeleted {
                     pid: from_pid.0,
                     slot,
                 },
                 timestamp,
-            )],
-        },
+            )];
+            commits.extend(release_cap(state, from_pid, &cap, timestamp));
+            StepResult {
+                result: SyscallResult::Ok(0),
+                commits,
+            }
+        }
         None => StepResult {
             result: SyscallResult::Err(KernelError::InvalidCapability),
             commits: vec![],
@@ -880,6 +2089,7 @@ fn step_cap_inspect(state: &KernelState, from_pid: ProcessId, slot: CapSlot) ->
                 permissions: cap.permissions.to_byte(),
                 generation: cap.generation,
                 expires_at: cap.expires_at,
+                badge: cap.badge,
             }),
             commits: vec![],
         },
@@ -895,8 +2105,20 @@ fn step_cap_derive(
     from_pid: ProcessId,
     slot: CapSlot,
     new_permissions: Permissions,
+    ttl_ns: Option<u64>,
+    badge: Option<u64>,
     timestamp: u64,
 ) -> StepResult {
+    let expires_at_override = match ttl_expiry(ttl_ns, timestamp) {
+        Ok(e) => e,
+        Err(e) => {
+            return StepResult {
+                result: SyscallResult::Err(e),
+                commits: vec![],
+            }
+        }
+    };
+
     // Get source capability
     let source_cap = {
         let cspace = match state.get_cap_space(from_pid) {
@@ -920,6 +2142,14 @@ fn step_cap_derive(
         }
     };
 
+    // Reply capabilities are single-use and cannot be copied
+    if source_cap.object_type == ObjectType::Reply {
+        return StepResult {
+            result: SyscallResult::Err(KernelError::PermissionDenied),
+            commits: vec![],
+        };
+    }
+
     // Verify new permissions are subset of source
     if !new_permissions.is_subset_of(&source_cap.permissions) {
         return StepResult {
@@ -928,6 +2158,26 @@ fn step_cap_derive(
         };
     }
 
+    // Badges only apply to endpoints, are non-zero, and can be set only once
+    if let Some(badge) = badge {
+        if badge == 0 || source_cap.object_type != ObjectType::Endpoint {
+            return StepResult {
+                result: SyscallResult::Err(KernelError::InvalidArgument),
+                commits: vec![],
+            };
+        }
+        if source_cap.badge != 0 {
+            return StepResult {
+                result: SyscallResult::Err(KernelError::PermissionDenied),
+                commits: vec![],
+            };
+        }
+    }
+
+    if let Some(exceeded) = check_quota(state, from_pid, QuotaResource::CapSlots, 1, timestamp) {
+        return exceeded;
+    }
+
     // Create derived capability
     let new_cap = Capability {
         id: state.alloc_cap_id(),
@@ -935,8 +2185,10 @@ fn step_cap_derive(
         object_id: source_cap.object_id,
         permissions: new_permissions,
         generation: source_cap.generation + 1,
-        expires_at: source_cap.expires_at,
+        expires_at: child_expiry(source_cap.expires_at, expires_at_override),
+        badge: badge.unwrap_or(source_cap.badge),
     };
+    state.cap_derivations.link(source_cap.id, new_cap.id);
 
     let new_slot = state
         .get_cap_space_mut(from_pid)
@@ -956,668 +2208,3276 @@ fn step_cap_derive(
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_step_get_time() {
-        let mut state = KernelState::new();
-        let pid = state.register_process("test", 1000);
+// ============================================================================
+// Notification handlers
+// ============================================================================
 
-        let result = step(&mut state, pid, Syscall::GetTime, 5000);
+fn step_create_notification(
+    state: &mut KernelState,
+    from_pid: ProcessId,
+    timestamp: u64,
+) -> StepResult {
+    if !state.process_exists(from_pid) {
+        return StepResult {
+            result: SyscallResult::Err(KernelError::ProcessNotFound),
+            commits: vec![],
+        };
+    }
 
-        match result.result {
-            SyscallResult::Ok(t) => assert_eq!(t, 5000),
-            _ => panic!("Expected Ok"),
-        }
+    if let Some(exceeded) = check_quota(state, from_pid, QuotaResource::CapSlots, 1, timestamp) {
+        return exceeded;
     }
 
-    #[test]
-    fn test_step_create_endpoint() {
-        let mut state = KernelState::new();
-        let pid = state.register_process("test", 1000);
+    let notification_id = state.create_notification(from_pid);
 
-        let result = step(&mut state, pid, Syscall::CreateEndpoint, 2000);
+    let cap = Capability {
+        id: state.alloc_cap_id(),
+        object_type: ObjectType::Notification,
+        object_id: notification_id.0,
+        permissions: Permissions::full(),
+        generation: 0,
+        expires_at: 0,
+        badge: 0,
+    };
 
-        match result.result {
-            SyscallResult::Ok(packed) => {
-                let endpoint_id = (packed & 0xFFFFFFFF) as u64;
-                assert_eq!(endpoint_id, 1);
-            }
-            _ => panic!("Expected Ok"),
-        }
+    let slot = state
+        .get_cap_space_mut(from_pid)
+        .map(|cs| cs.insert(cap))
+        .unwrap_or(0);
 
-        assert_eq!(result.commits.len(), 1);
-        assert!(state.get_endpoint(EndpointId(1)).is_some());
-    }
+    // Pack result as (slot << 32) | notification_id, same as endpoints
+    let result = ((slot as u64) << 32) | (notification_id.0 & 0xFFFFFFFF);
 
-    #[test]
-    fn test_step_send_receive() {
-        let mut state = KernelState::new();
-        let sender = state.register_process("sender", 1000);
-        let receiver = state.register_process("receiver", 1000);
+    StepResult {
+        result: SyscallResult::Ok(result),
+        commits: vec![Commit::new(
+            CommitType::NotificationCreated {
+                id: notification_id.0,
+                owner: from_pid.0,
+            },
+            timestamp,
+        )],
+    }
+}
 
-        // Create endpoint owned by receiver
-        state.create_endpoint(receiver);
-        let endpoint_id = EndpointId(1);
+/// Look up the notification behind a slot, checking the required rights.
+fn notification_for_slot(
+    state: &KernelState,
+    from_pid: ProcessId,
+    slot: CapSlot,
+    required: &Permissions,
+    timestamp: u64,
+) -> Result<NotificationId, KernelError> {
+    let cspace = state
+        .get_cap_space(from_pid)
+        .ok_or(KernelError::ProcessNotFound)?;
+    let cap = axiom_check(cspace, slot, required, Some(ObjectType::Notification), timestamp)?;
+    let id = NotificationId(cap.object_id);
+    if state.get_notification(id).is_none() {
+        return Err(KernelError::InvalidCapability);
+    }
+    Ok(id)
+}
 
-        // Give sender a capability to the endpoint
-        let cap = Capability {
-            id: state.alloc_cap_id(),
-            object_type: ObjectType::Endpoint,
-            object_id: endpoint_id.0,
-            permissions: Permissions::full(),
-            generation: 0,
-            expires_at: 0,
+fn step_signal(
+    state: &mut KernelState,
+    from_pid: ProcessId,
+    slot: CapSlot,
+    bits: u64,
+    timestamp: u64,
+) -> StepResult {
+    if bits == 0 {
+        return StepResult {
+            result: SyscallResult::Err(KernelError::InvalidArgument),
+            commits: vec![],
         };
-        let slot = state.get_cap_space_mut(sender).unwrap().insert(cap);
+    }
 
-        // Give receiver a capability too
-        let recv_cap = Capability {
-            id: state.alloc_cap_id(),
-            object_type: ObjectType::Endpoint,
-            object_id: endpoint_id.0,
-            permissions: Permissions::full(),
-            generation: 0,
-            expires_at: 0,
-        };
-        let recv_slot = state.get_cap_space_mut(receiver).unwrap().insert(recv_cap);
+    let required = Permissions::write_only();
+    let id = match notification_for_slot(state, from_pid, slot, &required, timestamp) {
+        Ok(id) => id,
+        Err(e) => {
+            return StepResult {
+                result: SyscallResult::Err(e),
+                commits: vec![],
+            }
+        }
+    };
 
-        // Send message
-        let send_result = step(
-            &mut state,
-            sender,
-            Syscall::Send {
-                endpoint_slot: slot,
-                tag: 42,
-                data: vec![1, 2, 3],
-            },
-            2000,
-        );
-        assert!(matches!(send_result.result, SyscallResult::Ok(0)));
+    if let Some(notification) = state.get_notification_mut(id) {
+        notification.signal(bits);
+    }
 
-        // Receive message
-        let recv_result = step(
-            &mut state,
-            receiver,
-            Syscall::Receive {
-                endpoint_slot: recv_slot,
+    StepResult {
+        result: SyscallResult::Ok(0),
+        commits: vec![Commit::new(
+            CommitType::NotificationSignaled {
+                from: from_pid.0,
+                id: id.0,
+                bits,
             },
-            3000,
-        );
-        match recv_result.result {
-            SyscallResult::Message(msg) => {
-                assert_eq!(msg.sender, sender);
-                assert_eq!(msg.tag, 42);
-                assert_eq!(msg.data, vec![1, 2, 3]);
-            }
-            _ => panic!("Expected Message"),
-        }
+            timestamp,
+        )],
     }
+}
 
-    #[test]
-    fn test_step_capability_grant() {
-        let mut state = KernelState::new();
-        let granter = state.register_process("granter", 1000);
-        let grantee = state.register_process("grantee", 1000);
+/// Shared body of `Wait` and `Poll`: take the pending signal word.
+///
+/// With `block` set, an empty word yields `WouldBlock` instead of `Ok(0)`.
+fn step_take_signals(
+    state: &mut KernelState,
+    from_pid: ProcessId,
+    slot: CapSlot,
+    block: bool,
+    timestamp: u64,
+) -> StepResult {
+    let required = Permissions::read_only();
+    let id = match notification_for_slot(state, from_pid, slot, &required, timestamp) {
+        Ok(id) => id,
+        Err(e) => {
+            return StepResult {
+                result: SyscallResult::Err(e),
+                commits: vec![],
+            }
+        }
+    };
 
-        // Create endpoint
-        state.create_endpoint(granter);
-        let endpoint_id = EndpointId(1);
+    let bits = state
+        .get_notification_mut(id)
+        .map(|n| n.take())
+        .unwrap_or(0);
 
-        // Give granter a capability with grant permission
-        let cap = Capability {
-            id: state.alloc_cap_id(),
-            object_type: ObjectType::Endpoint,
-            object_id: endpoint_id.0,
-            permissions: Permissions::full(),
-            generation: 0,
-            expires_at: 0,
-        };
-        let slot = state.get_cap_space_mut(granter).unwrap().insert(cap);
+    let result = if bits == 0 && block {
+        SyscallResult::WouldBlock
+    } else {
+        SyscallResult::Ok(bits)
+    };
+
+    // Clearing the word is a state change, so replay must see it
+    let commits = if bits != 0 {
+        vec![Commit::new(
+            CommitType::NotificationTaken {
+                pid: from_pid.0,
+                id: id.0,
+                bits,
+            },
+            timestamp,
+        )]
+    } else {
+        vec![]
+    };
+
+    StepResult { result, commits }
+}
+
+// ============================================================================
+// Shared memory handlers
+// ============================================================================
+
+fn step_create_region(
+    state: &mut KernelState,
+    from_pid: ProcessId,
+    size: usize,
+    timestamp: u64,
+) -> StepResult {
+    if size == 0 || size > MAX_REGION_SIZE {
+        return StepResult {
+            result: SyscallResult::Err(KernelError::InvalidArgument),
+            commits: vec![],
+        };
+    }
+    if !state.process_exists(from_pid) {
+        return StepResult {
+            result: SyscallResult::Err(KernelError::ProcessNotFound),
+            commits: vec![],
+        };
+    }
+
+    let pages = size.div_ceil(PAGE_SIZE) * PAGE_SIZE;
+    let needs = [(QuotaResource::Memory, pages as u64), (QuotaResource::CapSlots, 1)];
+    for (resource, amount) in needs {
+        if let Some(exceeded) = check_quota(state, from_pid, resource, amount, timestamp) {
+            return exceeded;
+        }
+    }
+
+    let region_id = state.create_region(from_pid, size);
+    let size = state.get_region(region_id).map(|r| r.size).unwrap_or(0);
+
+    let cap = Capability {
+        id: state.alloc_cap_id(),
+        object_type: ObjectType::Memory,
+        object_id: region_id.0,
+        permissions: Permissions::full(),
+        generation: 0,
+        expires_at: 0,
+        badge: 0,
+    };
+
+    let slot = state
+        .get_cap_space_mut(from_pid)
+        .map(|cs| cs.insert(cap))
+        .unwrap_or(0);
+
+    // Pack result as (slot << 32) | region_id, same as endpoints
+    let result = ((slot as u64) << 32) | (region_id.0 & 0xFFFFFFFF);
+
+    StepResult {
+        result: SyscallResult::Ok(result),
+        commits: vec![Commit::new(
+            CommitType::RegionCreated {
+                id: region_id.0,
+                owner: from_pid.0,
+                size,
+            },
+            timestamp,
+        )],
+    }
+}
+
+/// Map a region through a capability and return its size.
+///
+/// A read-only cap can only produce a read-only mapping; mapping again
+/// replaces the previous access.
+fn step_map_region(
+    state: &mut KernelState,
+    from_pid: ProcessId,
+    slot: CapSlot,
+    writable: bool,
+    timestamp: u64,
+) -> StepResult {
+    let required = if writable {
+        Permissions {
+            read: true,
+            write: true,
+            grant: false,
+        }
+    } else {
+        Permissions::read_only()
+    };
+
+    let region_id = match region_for_slot(state, from_pid, slot, &required, timestamp) {
+        Ok(id) => id,
+        Err(e) => {
+            return StepResult {
+                result: SyscallResult::Err(e),
+                commits: vec![],
+            }
+        }
+    };
+
+    let size = match state.get_region_mut(region_id) {
+        Some(region) => {
+            region.map(from_pid, writable);
+            region.size
+        }
+        None => 0,
+    };
+
+    StepResult {
+        result: SyscallResult::Ok(size as u64),
+        commits: vec![Commit::new(
+            CommitType::RegionMapped {
+                pid: from_pid.0,
+                id: region_id.0,
+                writable,
+            },
+            timestamp,
+        )],
+    }
+}
+
+fn step_unmap_region(
+    state: &mut KernelState,
+    from_pid: ProcessId,
+    slot: CapSlot,
+    timestamp: u64,
+) -> StepResult {
+    // Unmapping needs no rights beyond holding a cap to the region
+    let required = Permissions::default();
+    let region_id = match region_for_slot(state, from_pid, slot, &required, timestamp) {
+        Ok(id) => id,
+        Err(e) => {
+            return StepResult {
+                result: SyscallResult::Err(e),
+                commits: vec![],
+            }
+        }
+    };
+
+    let unmapped = state
+        .get_region_mut(region_id)
+        .map(|r| r.unmap(from_pid))
+        .unwrap_or(false);
+    if !unmapped {
+        return StepResult {
+            result: SyscallResult::Err(KernelError::InvalidArgument),
+            commits: vec![],
+        };
+    }
+
+    StepResult {
+        result: SyscallResult::Ok(0),
+        commits: vec![Commit::new(
+            CommitType::RegionUnmapped {
+                pid: from_pid.0,
+                id: region_id.0,
+            },
+            timestamp,
+        )],
+    }
+}
+
+/// Look up the region behind a slot, checking the required rights.
+fn region_for_slot(
+    state: &KernelState,
+    from_pid: ProcessId,
+    slot: CapSlot,
+    required: &Permissions,
+    timestamp: u64,
+) -> Result<RegionId, KernelError> {
+    let cspace = state
+        .get_cap_space(from_pid)
+        .ok_or(KernelError::ProcessNotFound)?;
+    let cap = axiom_check(cspace, slot, required, Some(ObjectType::Memory), timestamp)?;
+    let id = RegionId(cap.object_id);
+    if state.get_region(id).is_none() {
+        return Err(KernelError::InvalidCapability);
+    }
+    Ok(id)
+}
+
+/// Tear down `pid`'s mapping of a region once no remaining capability
+/// grants the access it was mapped with.
+fn unmap_unbacked(
+    state: &mut KernelState,
+    pid: ProcessId,
+    region_id: RegionId,
+    timestamp: u64,
+) -> Option<Commit> {
+    let writable = *state.get_region(region_id)?.mappings.get(&pid)?;
+    let backed = state.get_cap_space(pid).is_some_and(|cs| {
+        cs.slots.values().any(|cap| {
+            cap.object_type == ObjectType::Memory
+                && cap.object_id == region_id.0
+                && cap.permissions.read
+                && (cap.permissions.write || !writable)
+        })
+    });
+    if backed {
+        return None;
+    }
+
+    state.get_region_mut(region_id)?.unmap(pid);
+    Some(Commit::new(
+        CommitType::RegionUnmapped {
+            pid: pid.0,
+            id: region_id.0,
+        },
+        timestamp,
+    ))
+}
+
+// ============================================================================
+// Device handlers
+// ============================================================================
+
+/// Check an IRQ capability and return the line it names.
+fn irq_for_slot(
+    state: &KernelState,
+    from_pid: ProcessId,
+    slot: CapSlot,
+    required: &Permissions,
+    timestamp: u64,
+) -> Result<u8, KernelError> {
+    let cspace = state
+        .get_cap_space(from_pid)
+        .ok_or(KernelError::ProcessNotFound)?;
+    let cap = axiom_check(cspace, slot, required, Some(ObjectType::Irq), timestamp)?;
+    Ok(cap.object_id as u8)
+}
+
+/// Slot of an IRQ capability for `irq`, if the CSpace holds one.
+fn find_irq_cap(cspace: &CapabilitySpace, irq: u8) -> Option<CapSlot> {
+    cspace
+        .slots
+        .iter()
+        .find(|(_, cap)| cap.object_type == ObjectType::Irq && cap.object_id == irq as u64)
+        .map(|(&slot, _)| slot)
+}
+
+fn step_irq_bind_notification(
+    state: &mut KernelState,
+    from_pid: ProcessId,
+    irq_slot: CapSlot,
+    notification_slot: CapSlot,
+    bits: u64,
+    timestamp: u64,
+) -> StepResult {
+    if bits == 0 {
+        return StepResult {
+            result: SyscallResult::Err(KernelError::InvalidArgument),
+            commits: vec![],
+        };
+    }
+    let required = Permissions::write_only();
+    let target = notification_for_slot(state, from_pid, notification_slot, &required, timestamp)
+        .map(|id| IrqTarget::Notification { id, bits });
+    bind_irq(state, from_pid, irq_slot, target, timestamp)
+}
+
+fn step_irq_bind_endpoint(
+    state: &mut KernelState,
+    from_pid: ProcessId,
+    irq_slot: CapSlot,
+    endpoint_slot: CapSlot,
+    timestamp: u64,
+) -> StepResult {
+    let target = state
+        .get_cap_space(from_pid)
+        .ok_or(KernelError::ProcessNotFound)
+        .and_then(|cspace| {
+            axiom_check(
+                cspace,
+                endpoint_slot,
+                &Permissions::write_only(),
+                Some(ObjectType::Endpoint),
+                timestamp,
+            )
+            .map_err(KernelError::from)
+        })
+        .map(|cap| EndpointId(cap.object_id))
+        .and_then(|id| match state.get_endpoint(id) {
+            Some(_) => Ok(IrqTarget::Endpoint(id)),
+            None => Err(KernelError::EndpointNotFound),
+        });
+    bind_irq(state, from_pid, irq_slot, target, timestamp)
+}
+
+/// Shared tail of the bind syscalls, once the target has been checked.
+///
+/// A line bound by another driver is refused; the same driver may rebind
+/// to move delivery elsewhere.
+fn bind_irq(
+    state: &mut KernelState,
+    from_pid: ProcessId,
+    irq_slot: CapSlot,
+    target: Result<IrqTarget, KernelError>,
+    timestamp: u64,
+) -> StepResult {
+    let bound = target.and_then(|target| {
+        let required = Permissions::read_only();
+        let irq = irq_for_slot(state, from_pid, irq_slot, &required, timestamp)?;
+        match state.irq_bindings.get(&irq) {
+            Some(binding) if binding.owner != from_pid => Err(KernelError::InvalidArgument),
+            _ => Ok((irq, target)),
+        }
+    });
+    let (irq, target) = match bound {
+        Ok(b) => b,
+        Err(e) => {
+            return StepResult {
+                result: SyscallResult::Err(e),
+                commits: vec![],
+            }
+        }
+    };
+
+    state.irq_bindings.insert(
+        irq,
+        IrqBinding {
+            owner: from_pid,
+            target,
+            masked: false,
+        },
+    );
+
+    StepResult {
+        result: SyscallResult::Ok(0),
+        commits: vec![Commit::new(
+            CommitType::IrqBound {
+                irq,
+                pid: from_pid.0,
+            },
+            timestamp,
+        )],
+    }
+}
+
+fn step_irq_ack(
+    state: &mut KernelState,
+    from_pid: ProcessId,
+    irq_slot: CapSlot,
+    timestamp: u64,
+) -> StepResult {
+    let required = Permissions::read_only();
+    let irq = match irq_for_slot(state, from_pid, irq_slot, &required, timestamp) {
+        Ok(irq) => irq,
+        Err(e) => {
+            return StepResult {
+                result: SyscallResult::Err(e),
+                commits: vec![],
+            }
+        }
+    };
+
+    match state.irq_bindings.get_mut(&irq) {
+        Some(binding) if binding.owner == from_pid => {
+            // Acking an unmasked line is harmless and records nothing
+            let was_masked = core::mem::replace(&mut binding.masked, false);
+            StepResult {
+                result: SyscallResult::Ok(0),
+                commits: if was_masked {
+                    vec![Commit::new(CommitType::IrqAcked { irq }, timestamp)]
+                } else {
+                    vec![]
+                },
+            }
+        }
+        _ => StepResult {
+            result: SyscallResult::Err(KernelError::InvalidArgument),
+            commits: vec![],
+        },
+    }
+}
+
+fn step_irq_unbind(
+    state: &mut KernelState,
+    from_pid: ProcessId,
+    irq_slot: CapSlot,
+    timestamp: u64,
+) -> StepResult {
+    let required = Permissions::default();
+    let irq = match irq_for_slot(state, from_pid, irq_slot, &required, timestamp) {
+        Ok(irq) => irq,
+        Err(e) => {
+            return StepResult {
+                result: SyscallResult::Err(e),
+                commits: vec![],
+            }
+        }
+    };
+
+    match state.irq_bindings.get(&irq) {
+        Some(binding) if binding.owner == from_pid => {
+            state.irq_bindings.remove(&irq);
+            StepResult {
+                result: SyscallResult::Ok(0),
+                commits: vec![Commit::new(CommitType::IrqUnbound { irq }, timestamp)],
+            }
+        }
+        _ => StepResult {
+            result: SyscallResult::Err(KernelError::InvalidArgument),
+            commits: vec![],
+        },
+    }
+}
+
+/// Drop every IRQ binding held by a dying process.
+fn unbind_irqs(state: &mut KernelState, pid: ProcessId, timestamp: u64) -> Vec<Commit> {
+    let owned: Vec<u8> = state
+        .irq_bindings
+        .iter()
+        .filter(|(_, binding)| binding.owner == pid)
+        .map(|(&irq, _)| irq)
+        .collect();
+    owned
+        .into_iter()
+        .map(|irq| {
+            state.irq_bindings.remove(&irq);
+            Commit::new(CommitType::IrqUnbound { irq }, timestamp)
+        })
+        .collect()
+}
+
+/// Authorize a port access. Reads need `read`, writes need `write`, and
+/// the whole access must fall inside the capability's port range.
+fn step_io_port(
+    state: &KernelState,
+    from_pid: ProcessId,
+    port_slot: CapSlot,
+    port: u16,
+    width: u8,
+    write: bool,
+    timestamp: u64,
+) -> StepResult {
+    if !matches!(width, 1 | 2 | 4) {
+        return StepResult {
+            result: SyscallResult::Err(KernelError::InvalidArgument),
+            commits: vec![],
+        };
+    }
+
+    let cspace = match state.get_cap_space(from_pid) {
+        Some(cs) => cs,
+        None => {
+            return StepResult {
+                result: SyscallResult::Err(KernelError::ProcessNotFound),
+                commits: vec![],
+            }
+        }
+    };
+
+    let required = if write {
+        Permissions::write_only()
+    } else {
+        Permissions::read_only()
+    };
+    let cap = axiom_check(cspace, port_slot, &required, Some(ObjectType::IoPort), timestamp);
+    let range = match cap {
+        Ok(cap) => IoPortRange::from_object_id(cap.object_id),
+        Err(e) => {
+            return StepResult {
+                result: SyscallResult::Err(e.into()),
+                commits: vec![],
+            }
+        }
+    };
+
+    let result = if range.contains(port, width) {
+        SyscallResult::Ok(0)
+    } else {
+        SyscallResult::Err(KernelError::PermissionDenied)
+    };
+    StepResult {
+        result,
+        commits: vec![],
+    }
+}
+
+/// Absolute expiry for a requested lifetime, if any.
+///
+/// A zero TTL is rejected: `expires_at == 0` already means "never expires".
+fn ttl_expiry(ttl_ns: Option<u64>, timestamp: u64) -> Result<Option<u64>, KernelError> {
+    match ttl_ns {
+        None => Ok(None),
+        Some(0) => Err(KernelError::InvalidArgument),
+        Some(ttl) => Ok(Some(timestamp.saturating_add(ttl))),
+    }
+}
+
+/// Expiry of a capability created from `source_expires_at`.
+///
+/// A child can never outlive its source, so a requested expiry is clamped
+/// to the source's.
+fn child_expiry(source_expires_at: u64, requested: Option<u64>) -> u64 {
+    match (source_expires_at, requested) {
+        (source, None) => source,
+        (0, Some(requested)) => requested,
+        (source, Some(requested)) => source.min(requested),
+    }
+}
+
+// ============================================================================
+// Interrupt delivery
+// ============================================================================
+
+/// Deliver a hardware interrupt on `irq`.
+///
+/// This is the entry point for interrupts the runtime receives from the
+/// HAL; it is not a syscall. An unbound or masked line is ignored. On
+/// delivery the line is masked until the driver sends `IrqAck`. If the
+/// target endpoint's queue is full the interrupt is dropped and the line
+/// stays unmasked, so the next one is delivered.
+pub fn interrupt(state: &mut KernelState, irq: u8, timestamp: u64) -> Vec<Commit> {
+    let target = match state.irq_bindings.get(&irq) {
+        Some(binding) if !binding.masked => binding.target,
+        _ => return vec![],
+    };
+
+    let mut commits = Vec::new();
+    match target {
+        IrqTarget::Notification { id, bits } => {
+            let notification = match state.get_notification_mut(id) {
+                Some(n) => n,
+                None => return vec![],
+            };
+            notification.signal(bits);
+            commits.push(Commit::new(
+                CommitType::NotificationSignaled {
+                    from: 0,
+                    id: id.0,
+                    bits,
+                },
+                timestamp,
+            ));
+        }
+        IrqTarget::Endpoint(id) => {
+            let endpoint = match state.get_endpoint_mut(id) {
+                Some(e) => e,
+                None => return vec![],
+            };
+            let msg = Message {
+                sender: ProcessId(0),
+                badge: 0,
+                tag: MSG_IRQ,
+                data: vec![irq],
+                caps: vec![],
+            };
+            if endpoint.enqueue(msg).is_err() {
+                return vec![];
+            }
+            wake_receivers(state, id);
+            commits.push(Commit::new(
+                CommitType::IpcSent {
+                    from: 0,
+                    endpoint: id.0,
+                    tag: MSG_IRQ,
+                    size: 1,
+                },
+                timestamp,
+            ));
+        }
+    }
+
+    if let Some(binding) = state.irq_bindings.get_mut(&irq) {
+        binding.masked = true;
+    }
+    commits.push(Commit::new(CommitType::IrqRaised { irq }, timestamp));
+    commits
+}
+
+// ============================================================================
+// Expiry sweep
+// ============================================================================
+
+/// Remove every capability that has expired at `timestamp`.
+///
+/// Emits a `CapRevoked` commit per slot and notifies each live holder with
+/// `revoke_reason::EXPIRED`.
+fn sweep_expired(state: &mut KernelState, timestamp: u64) -> Vec<Commit> {
+    let expired: Vec<(ProcessId, CapSlot)> = state
+        .cap_spaces
+        .iter()
+        .flat_map(|(&pid, cspace)| {
+            cspace
+                .slots
+                .iter()
+                .filter(|(_, cap)| cap.is_expired(timestamp))
+                .map(move |(&slot, _)| (pid, slot))
+        })
+        .collect();
+
+    let mut commits = Vec::new();
+    for (pid, slot) in expired {
+        let cap = match state.get_cap_space_mut(pid).and_then(|cs| cs.remove(slot)) {
+            Some(cap) => cap,
+            None => continue,
+        };
+        state.cap_derivations.remove(cap.id);
+
+        commits.push(Commit::new(
+            CommitType::CapRevoked { pid: pid.0, slot },
+            timestamp,
+        ));
+
+        let notification = RevokeNotification {
+            pid,
+            slot,
+            object_type: cap.object_type as u8,
+            object_id: cap.object_id,
+            reason: revoke_reason::EXPIRED,
+        };
+        commits.extend(notify_revoked(state, &notification, timestamp));
+        commits.extend(release_cap(state, pid, &cap, timestamp));
+    }
+    commits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_step_get_time() {
+        let mut state = KernelState::new();
+        let pid = state.register_process("test", 1000);
+
+        let result = step(&mut state, pid, Syscall::GetTime, 5000);
+
+        match result.result {
+            SyscallResult::Ok(t) => assert_eq!(t, 5000),
+            _ => panic!("Expected Ok"),
+        }
+    }
+
+    #[test]
+    fn test_step_create_endpoint() {
+        let mut state = KernelState::new();
+        let pid = state.register_process("test", 1000);
+
+        let result = step(&mut state, pid, Syscall::CreateEndpoint, 2000);
+
+        match result.result {
+            SyscallResult::Ok(packed) => {
+                let endpoint_id = (packed & 0xFFFFFFFF) as u64;
+                assert_eq!(endpoint_id, 1);
+            }
+            _ => panic!("Expected Ok"),
+        }
+
+        assert_eq!(result.commits.len(), 1);
+        assert!(state.get_endpoint(EndpointId(1)).is_some());
+    }
+
+    #[test]
+    fn test_step_send_receive() {
+        let mut state = KernelState::new();
+        let sender = state.register_process("sender", 1000);
+        let receiver = state.register_process("receiver", 1000);
+
+        // Create endpoint owned by receiver
+        state.create_endpoint(receiver);
+        let endpoint_id = EndpointId(1);
+
+        // Give sender a capability to the endpoint
+        let cap = Capability {
+            id: state.alloc_cap_id(),
+            object_type: ObjectType::Endpoint,
+            object_id: endpoint_id.0,
+            permissions: Permissions::full(),
+            generation: 0,
+            expires_at: 0,
+            badge: 0,
+        };
+        let slot = state.get_cap_space_mut(sender).unwrap().insert(cap);
+
+        // Give receiver a capability too
+        let recv_cap = Capability {
+            id: state.alloc_cap_id(),
+            object_type: ObjectType::Endpoint,
+            object_id: endpoint_id.0,
+            permissions: Permissions::full(),
+            generation: 0,
+            expires_at: 0,
+            badge: 0,
+        };
+        let recv_slot = state.get_cap_space_mut(receiver).unwrap().insert(recv_cap);
+
+        // Send message
+        let send_result = step(
+            &mut state,
+            sender,
+            Syscall::Send {
+                endpoint_slot: slot,
+                tag: 42,
+                data: vec![1, 2, 3],
+            },
+            2000,
+        );
+        assert!(matches!(send_result.result, SyscallResult::Ok(0)));
+
+        // Receive message
+        let recv_result = step(
+            &mut state,
+            receiver,
+            Syscall::Receive {
+                endpoint_slot: recv_slot,
+            },
+            3000,
+        );
+        match recv_result.result {
+            SyscallResult::Message(msg) => {
+                assert_eq!(msg.sender, sender);
+                assert_eq!(msg.tag, 42);
+                assert_eq!(msg.data, vec![1, 2, 3]);
+            }
+            _ => panic!("Expected Message"),
+        }
+    }
+
+    #[test]
+    fn test_step_capability_grant() {
+        let mut state = KernelState::new();
+        let granter = state.register_process("granter", 1000);
+        let grantee = state.register_process("grantee", 1000);
+
+        // Create endpoint
+        state.create_endpoint(granter);
+        let endpoint_id = EndpointId(1);
+
+        // Give granter a capability with grant permission
+        let cap = Capability {
+            id: state.alloc_cap_id(),
+            object_type: ObjectType::Endpoint,
+            object_id: endpoint_id.0,
+            permissions: Permissions::full(),
+            generation: 0,
+            expires_at: 0,
+            badge: 0,
+        };
+        let slot = state.get_cap_space_mut(granter).unwrap().insert(cap);
 
         // Grant to grantee with reduced permissions
         let result = step(
             &mut state,
-            granter,
-            Syscall::CapGrant {
-                from_slot: slot,
-                to_pid: grantee,
-                permissions: Permissions::read_only(),
+            granter,
+            Syscall::CapGrant {
+                from_slot: slot,
+                to_pid: grantee,
+                permissions: Permissions::read_only(),
+            },
+            2000,
+        );
+
+        match result.result {
+            SyscallResult::Ok(new_slot) => {
+                // Verify grantee has the capability
+                let grantee_cap = state
+                    .get_cap_space(grantee)
+                    .unwrap()
+                    .get(new_slot as u32)
+                    .unwrap();
+                assert_eq!(grantee_cap.object_id, endpoint_id.0);
+                assert!(grantee_cap.permissions.read);
+                assert!(!grantee_cap.permissions.write);
+            }
+            _ => panic!("Expected Ok"),
+        }
+    }
+
+    // ========================================================================
+    // Simple syscalls: Debug, Yield, Exit
+    // ========================================================================
+
+    #[test]
+    fn test_step_debug() {
+        let mut state = KernelState::new();
+        let pid = state.register_process("test", 1000);
+
+        let result = step(
+            &mut state,
+            pid,
+            Syscall::Debug {
+                msg: alloc::string::String::from("Hello debug!"),
+            },
+            2000,
+        );
+
+        assert!(matches!(result.result, SyscallResult::Ok(0)));
+        assert!(result.commits.is_empty()); // Debug doesn't generate commits
+    }
+
+    #[test]
+    fn test_step_yield() {
+        let mut state = KernelState::new();
+        let pid = state.register_process("test", 1000);
+
+        let result = step(&mut state, pid, Syscall::Yield, 2000);
+
+        assert!(matches!(result.result, SyscallResult::Ok(0)));
+        assert!(result.commits.is_empty()); // Yield doesn't generate commits
+    }
+
+    #[test]
+    fn test_step_exit() {
+        let mut state = KernelState::new();
+        let pid = state.register_process("test", 1000);
+
+        // Process should be running
+        assert_eq!(state.get_process(pid).unwrap().state, ProcessState::Running);
+
+        let result = step(&mut state, pid, Syscall::Exit { code: 42 }, 2000);
+
+        // Should return exit code
+        match result.result {
+            SyscallResult::Ok(code) => assert_eq!(code, 42),
+            _ => panic!("Expected Ok with exit code"),
+        }
+
+        // Process should be zombie
+        assert_eq!(state.get_process(pid).unwrap().state, ProcessState::Zombie);
+
+        // Should generate commit
+        assert_eq!(result.commits.len(), 1);
+        match &result.commits[0].commit_type {
+            CommitType::ProcessExited { pid: exit_pid, code } => {
+                assert_eq!(*exit_pid, pid.0);
+                assert_eq!(*code, 42);
+            }
+            _ => panic!("Expected ProcessExited commit"),
+        }
+    }
+
+    #[test]
+    fn test_step_exit_negative_code() {
+        let mut state = KernelState::new();
+        let pid = state.register_process("test", 1000);
+
+        let result = step(&mut state, pid, Syscall::Exit { code: -1 }, 2000);
+
+        // Should handle negative exit codes
+        match result.result {
+            SyscallResult::Ok(code) => assert_eq!(code as i64, -1i32 as i64),
+            _ => panic!("Expected Ok with exit code"),
+        }
+    }
+
+    // ========================================================================
+    // Kill syscall tests
+    // ========================================================================
+
+    #[test]
+    fn test_step_kill_success() {
+        let mut state = KernelState::new();
+        let killer = state.register_process("killer", 1000);
+        let victim = state.register_process("victim", 1000);
+
+        let result = step(
+            &mut state,
+            killer,
+            Syscall::Kill { target_pid: victim },
+            2000,
+        );
+
+        assert!(matches!(result.result, SyscallResult::Ok(0)));
+        assert_eq!(state.get_process(victim).unwrap().state, ProcessState::Zombie);
+
+        // Should generate commit
+        assert_eq!(result.commits.len(), 1);
+        match &result.commits[0].commit_type {
+            CommitType::ProcessKilled { pid, by } => {
+                assert_eq!(*pid, victim.0);
+                assert_eq!(*by, killer.0);
+            }
+            _ => panic!("Expected ProcessKilled commit"),
+        }
+    }
+
+    #[test]
+    fn test_step_kill_target_not_found() {
+        let mut state = KernelState::new();
+        let pid = state.register_process("test", 1000);
+
+        let result = step(
+            &mut state,
+            pid,
+            Syscall::Kill {
+                target_pid: ProcessId(999),
+            },
+            2000,
+        );
+
+        assert!(matches!(
+            result.result,
+            SyscallResult::Err(KernelError::ProcessNotFound)
+        ));
+        assert!(result.commits.is_empty());
+    }
+
+    #[test]
+    fn test_step_kill_self() {
+        let mut state = KernelState::new();
+        let pid = state.register_process("test", 1000);
+
+        // Process can kill itself
+        let result = step(&mut state, pid, Syscall::Kill { target_pid: pid }, 2000);
+
+        assert!(matches!(result.result, SyscallResult::Ok(0)));
+        assert_eq!(state.get_process(pid).unwrap().state, ProcessState::Zombie);
+    }
+
+    // ========================================================================
+    // ListProcesses tests
+    // ========================================================================
+
+    #[test]
+    fn test_step_list_processes() {
+        let mut state = KernelState::new();
+        let pid1 = state.register_process("proc1", 1000);
+        let pid2 = state.register_process("proc2", 1000);
+        state.kill_process(pid2); // Make one a zombie
+
+        let result = step(&mut state, pid1, Syscall::ListProcesses, 2000);
+
+        match result.result {
+            SyscallResult::ProcessList(procs) => {
+                assert_eq!(procs.len(), 2);
+
+                let proc1 = procs.iter().find(|(p, _, _, _)| *p == pid1).unwrap();
+                assert_eq!(proc1.1, "proc1");
+                assert_eq!(proc1.2, ProcessState::Running);
+
+                let proc2 = procs.iter().find(|(p, _, _, _)| *p == pid2).unwrap();
+                assert_eq!(proc2.1, "proc2");
+                assert_eq!(proc2.2, ProcessState::Zombie);
+            }
+            _ => panic!("Expected ProcessList"),
+        }
+    }
+
+    // ========================================================================
+    // SendWithCaps tests
+    // ========================================================================
+
+    #[test]
+    fn test_step_send_with_caps() {
+        let mut state = KernelState::new();
+        let sender = state.register_process("sender", 1000);
+        let receiver = state.register_process("receiver", 1000);
+
+        // Create endpoint
+        state.create_endpoint(receiver);
+        let endpoint_id = EndpointId(1);
+
+        // Give sender capability to endpoint (with write permission)
+        let endpoint_cap = Capability {
+            id: state.alloc_cap_id(),
+            object_type: ObjectType::Endpoint,
+            object_id: endpoint_id.0,
+            permissions: Permissions::full(),
+            generation: 0,
+            expires_at: 0,
+            badge: 0,
+        };
+        let endpoint_slot = state.get_cap_space_mut(sender).unwrap().insert(endpoint_cap);
+
+        // Create a capability to transfer
+        state.create_endpoint(sender);
+        let transfer_endpoint_id = EndpointId(2);
+        let transfer_cap = Capability {
+            id: state.alloc_cap_id(),
+            object_type: ObjectType::Endpoint,
+            object_id: transfer_endpoint_id.0,
+            permissions: Permissions::read_only(),
+            generation: 0,
+            expires_at: 0,
+            badge: 0,
+        };
+        let transfer_slot = state.get_cap_space_mut(sender).unwrap().insert(transfer_cap);
+
+        // Sender should have the transfer cap
+        assert!(state.get_cap_space(sender).unwrap().contains(transfer_slot));
+
+        // Send message with capability
+        let result = step(
+            &mut state,
+            sender,
+            Syscall::SendWithCaps {
+                endpoint_slot,
+                tag: 42,
+                data: vec![1, 2, 3],
+                cap_slots: vec![transfer_slot],
+            },
+            2000,
+        );
+
+        assert!(matches!(result.result, SyscallResult::Ok(0)));
+
+        // Message should be in endpoint queue with transferred caps
+        let endpoint = state.get_endpoint(endpoint_id).unwrap();
+        assert_eq!(endpoint.pending_messages.len(), 1);
+        let msg = &endpoint.pending_messages[0];
+        assert_eq!(msg.sender, sender);
+        assert_eq!(msg.tag, 42);
+        assert_eq!(msg.caps.len(), 1);
+        assert_eq!(msg.caps[0].object_id, transfer_endpoint_id.0);
+    }
+
+    #[test]
+    fn test_step_send_with_caps_too_many_caps() {
+        use crate::types::MAX_CAPS_PER_MESSAGE;
+
+        let mut state = KernelState::new();
+        let sender = state.register_process("sender", 1000);
+        let receiver = state.register_process("receiver", 1000);
+
+        // Create endpoint
+        state.create_endpoint(receiver);
+        let endpoint_id = EndpointId(1);
+
+        let endpoint_cap = Capability {
+            id: state.alloc_cap_id(),
+            object_type: ObjectType::Endpoint,
+            object_id: endpoint_id.0,
+            permissions: Permissions::full(),
+            generation: 0,
+            expires_at: 0,
+            badge: 0,
+        };
+        let endpoint_slot = state.get_cap_space_mut(sender).unwrap().insert(endpoint_cap);
+
+        // Create too many capabilities
+        let mut cap_slots = Vec::new();
+        for _ in 0..=MAX_CAPS_PER_MESSAGE {
+            let cap = Capability {
+                id: state.alloc_cap_id(),
+                object_type: ObjectType::Endpoint,
+                object_id: 100,
+                permissions: Permissions::full(),
+                generation: 0,
+                expires_at: 0,
+                badge: 0,
+            };
+            let slot = state.get_cap_space_mut(sender).unwrap().insert(cap);
+            cap_slots.push(slot);
+        }
+
+        let result = step(
+            &mut state,
+            sender,
+            Syscall::SendWithCaps {
+                endpoint_slot,
+                tag: 42,
+                data: vec![],
+                cap_slots,
+            },
+            2000,
+        );
+
+        assert!(matches!(
+            result.result,
+            SyscallResult::Err(KernelError::TooManyCaps)
+        ));
+    }
+
+    #[test]
+    fn test_step_send_with_caps_invalid_cap_slot() {
+        let mut state = KernelState::new();
+        let sender = state.register_process("sender", 1000);
+        let receiver = state.register_process("receiver", 1000);
+
+        // Create endpoint
+        state.create_endpoint(receiver);
+        let endpoint_id = EndpointId(1);
+
+        let endpoint_cap = Capability {
+            id: state.alloc_cap_id(),
+            object_type: ObjectType::Endpoint,
+            object_id: endpoint_id.0,
+            permissions: Permissions::full(),
+            generation: 0,
+            expires_at: 0,
+            badge: 0,
+        };
+        let endpoint_slot = state.get_cap_space_mut(sender).unwrap().insert(endpoint_cap);
+
+        // Try to send with invalid cap slot
+        let result = step(
+            &mut state,
+            sender,
+            Syscall::SendWithCaps {
+                endpoint_slot,
+                tag: 42,
+                data: vec![],
+                cap_slots: vec![999], // Invalid slot
+            },
+            2000,
+        );
+
+        assert!(matches!(
+            result.result,
+            SyscallResult::Err(KernelError::InvalidCapability)
+        ));
+    }
+
+    // ========================================================================
+    // Message size limits tests
+    // ========================================================================
+
+    #[test]
+    fn test_step_send_message_too_large() {
+        use crate::types::MAX_MESSAGE_SIZE;
+
+        let mut state = KernelState::new();
+        let sender = state.register_process("sender", 1000);
+        let receiver = state.register_process("receiver", 1000);
+
+        // Create endpoint
+        state.create_endpoint(receiver);
+        let endpoint_id = EndpointId(1);
+
+        let cap = Capability {
+            id: state.alloc_cap_id(),
+            object_type: ObjectType::Endpoint,
+            object_id: endpoint_id.0,
+            permissions: Permissions::full(),
+            generation: 0,
+            expires_at: 0,
+            badge: 0,
+        };
+        let slot = state.get_cap_space_mut(sender).unwrap().insert(cap);
+
+        // Try to send oversized message
+        let result = step(
+            &mut state,
+            sender,
+            Syscall::Send {
+                endpoint_slot: slot,
+                tag: 42,
+                data: vec![0u8; MAX_MESSAGE_SIZE + 1],
+            },
+            2000,
+        );
+
+        assert!(matches!(
+            result.result,
+            SyscallResult::Err(KernelError::MessageTooLarge)
+        ));
+    }
+
+    #[test]
+    fn test_step_send_with_caps_message_too_large() {
+        use crate::types::MAX_MESSAGE_SIZE;
+
+        let mut state = KernelState::new();
+        let sender = state.register_process("sender", 1000);
+        let receiver = state.register_process("receiver", 1000);
+
+        // Create endpoint
+        state.create_endpoint(receiver);
+        let endpoint_id = EndpointId(1);
+
+        let cap = Capability {
+            id: state.alloc_cap_id(),
+            object_type: ObjectType::Endpoint,
+            object_id: endpoint_id.0,
+            permissions: Permissions::full(),
+            generation: 0,
+            expires_at: 0,
+            badge: 0,
+        };
+        let slot = state.get_cap_space_mut(sender).unwrap().insert(cap);
+
+        // Try to send oversized message
+        let result = step(
+            &mut state,
+            sender,
+            Syscall::SendWithCaps {
+                endpoint_slot: slot,
+                tag: 42,
+                data: vec![0u8; MAX_MESSAGE_SIZE + 1],
+                cap_slots: vec![],
+            },
+            2000,
+        );
+
+        assert!(matches!(
+            result.result,
+            SyscallResult::Err(KernelError::MessageTooLarge)
+        ));
+    }
+
+    // ========================================================================
+    // Call syscall tests
+    // ========================================================================
+
+    #[test]
+    fn test_step_call_sends_and_blocks() {
+        let mut state = KernelState::new();
+        let caller = state.register_process("caller", 1000);
+        let receiver = state.register_process("receiver", 1000);
+
+        // Create endpoint
+        state.create_endpoint(receiver);
+        let endpoint_id = EndpointId(1);
+
+        let cap = Capability {
+            id: state.alloc_cap_id(),
+            object_type: ObjectType::Endpoint,
+            object_id: endpoint_id.0,
+            permissions: Permissions::full(),
+            generation: 0,
+            expires_at: 0,
+            badge: 0,
+        };
+        let slot = state.get_cap_space_mut(caller).unwrap().insert(cap);
+
+        let result = step(
+            &mut state,
+            caller,
+            Syscall::Call {
+                endpoint_slot: slot,
+                tag: 42,
+                data: vec![1, 2, 3],
+            },
+            2000,
+        );
+
+        // Call should return WouldBlock
+        assert!(matches!(result.result, SyscallResult::WouldBlock));
+
+        // But message should be sent
+        let endpoint = state.get_endpoint(endpoint_id).unwrap();
+        assert_eq!(endpoint.pending_messages.len(), 1);
+
+        // Should generate commit for the send
+        assert!(!result.commits.is_empty());
+    }
+
+    fn setup_call(state: &mut KernelState) -> (ProcessId, ProcessId, CapSlot) {
+        let caller = state.register_process("caller", 1000);
+        let (server, server_slot) = setup_endpoint_owner(state, "server");
+        let slot = grant(state, server, server_slot, caller);
+
+        let result = step(
+            state,
+            caller,
+            Syscall::Call {
+                endpoint_slot: slot,
+                tag: 7,
+                data: vec![1],
+            },
+            2000,
+        );
+        assert!(matches!(result.result, SyscallResult::WouldBlock));
+        (caller, server, slot)
+    }
+
+    fn reply(state: &mut KernelState, from: ProcessId, caller: ProcessId) -> SyscallResult {
+        step(
+            state,
+            from,
+            Syscall::Reply {
+                caller,
+                tag: 8,
+                data: vec![2, 3],
+            },
+            3000,
+        )
+        .result
+    }
+
+    #[test]
+    fn test_step_call_reply_roundtrip() {
+        let mut state = KernelState::new();
+        let (caller, server, slot) = setup_call(&mut state);
+
+        assert_eq!(state.get_process(caller).unwrap().state, ProcessState::Blocked);
+        assert!(crate::invariants::check_all_invariants(&state).is_empty());
+
+        // Retrying before the reply does not resend
+        let result = step(
+            &mut state,
+            caller,
+            Syscall::Call {
+                endpoint_slot: slot,
+                tag: 7,
+                data: vec![1],
+            },
+            2500,
+        );
+        assert!(matches!(result.result, SyscallResult::WouldBlock));
+        assert!(result.commits.is_empty());
+
+        assert!(matches!(reply(&mut state, server, caller), SyscallResult::Ok(0)));
+        assert_eq!(state.get_process(caller).unwrap().state, ProcessState::Running);
+
+        let result = step(
+            &mut state,
+            caller,
+            Syscall::Call {
+                endpoint_slot: slot,
+                tag: 7,
+                data: vec![1],
+            },
+            4000,
+        );
+        match result.result {
+            SyscallResult::Message(msg) => {
+                assert_eq!(msg.sender, server);
+                assert_eq!(msg.tag, 8);
+                assert_eq!(msg.data, vec![2, 3]);
+            }
+            _ => panic!("Expected reply message"),
+        }
+        assert!(state.calls.is_empty());
+    }
+
+    #[test]
+    fn test_step_reply_cap_is_single_use() {
+        let mut state = KernelState::new();
+        let (caller, server, _) = setup_call(&mut state);
+
+        assert!(matches!(reply(&mut state, server, caller), SyscallResult::Ok(0)));
+        assert!(matches!(
+            reply(&mut state, server, caller),
+            SyscallResult::Err(KernelError::InvalidCapability)
+        ));
+    }
+
+    #[test]
+    fn test_step_reply_without_cap_is_rejected() {
+        let mut state = KernelState::new();
+        let (caller, _, _) = setup_call(&mut state);
+        let spoofer = state.register_process("spoofer", 1000);
+
+        assert!(matches!(
+            reply(&mut state, spoofer, caller),
+            SyscallResult::Err(KernelError::InvalidCapability)
+        ));
+        assert!(matches!(
+            state.calls.get(&caller),
+            Some(CallState::Pending { .. })
+        ));
+    }
+
+    #[test]
+    fn test_step_reply_cap_cannot_be_derived() {
+        let mut state = KernelState::new();
+        let (caller, server, _) = setup_call(&mut state);
+        let reply_slot = find_reply_cap(state.get_cap_space(server).unwrap(), caller).unwrap();
+
+        let result = step(
+            &mut state,
+            server,
+            Syscall::CapDerive {
+                slot: reply_slot,
+                new_permissions: Permissions::write_only(),
+            },
+            3000,
+        );
+        assert!(matches!(
+            result.result,
+            SyscallResult::Err(KernelError::PermissionDenied)
+        ));
+    }
+
+    #[test]
+    fn test_step_call_aborted_when_server_dies() {
+        let mut state = KernelState::new();
+        let (caller, server, slot) = setup_call(&mut state);
+
+        let result = step(&mut state, server, Syscall::Exit { code: 0 }, 3000);
+        assert!(result.commits.iter().any(|c| matches!(
+            c.commit_type,
+            CommitType::CallAborted { caller: pid } if pid == caller.0
+        )));
+        assert_eq!(state.get_process(caller).unwrap().state, ProcessState::Running);
+
+        let result = step(
+            &mut state,
+            caller,
+            Syscall::Call {
+                endpoint_slot: slot,
+                tag: 7,
+                data: vec![1],
+            },
+            4000,
+        );
+        assert!(matches!(
+            result.result,
+            SyscallResult::Err(KernelError::ProcessNotFound)
+        ));
+        assert!(state.calls.is_empty());
+    }
+
+    #[test]
+    fn test_step_call_dropped_when_caller_killed() {
+        let mut state = KernelState::new();
+        let (caller, server, _) = setup_call(&mut state);
+
+        step(&mut state, server, Syscall::Kill { target_pid: caller }, 3000);
+
+        assert!(state.calls.is_empty());
+        assert!(find_reply_cap(state.get_cap_space(server).unwrap(), caller).is_none());
+        assert!(matches!(
+            reply(&mut state, server, caller),
+            SyscallResult::Err(KernelError::InvalidCapability)
+        ));
+    }
+
+    #[test]
+    fn test_step_deleting_reply_cap_aborts_call() {
+        let mut state = KernelState::new();
+        let (caller, server, _) = setup_call(&mut state);
+        let reply_slot = find_reply_cap(state.get_cap_space(server).unwrap(), caller).unwrap();
+
+        step(&mut state, server, Syscall::CapDelete { slot: reply_slot }, 3000);
+
+        assert!(matches!(state.calls.get(&caller), Some(CallState::Aborted)));
+        assert!(crate::invariants::check_all_invariants(&state).is_empty());
+    }
+
+    fn call_with_deadline(
+        state: &mut KernelState,
+        caller: ProcessId,
+        slot: CapSlot,
+        timestamp: u64,
+    ) -> StepResult {
+        let syscall = Syscall::CallTimeout {
+            endpoint_slot: slot,
+            tag: 7,
+            data: vec![1],
+            deadline_ns: 5000,
+        };
+        step(state, caller, syscall, timestamp)
+    }
+
+    #[test]
+    fn test_step_call_timeout_expires() {
+        let mut state = KernelState::new();
+        let caller = state.register_process("caller", 1000);
+        let (server, server_slot) = setup_endpoint_owner(&mut state, "server");
+        let slot = grant(&mut state, server, server_slot, caller);
+
+        let result = call_with_deadline(&mut state, caller, slot, 2000);
+        assert!(matches!(result.result, SyscallResult::WouldBlock));
+        assert_eq!(state.next_deadline(), Some(5000));
+        assert!(state.deadlines_due(4999).is_empty());
+
+        let result = call_with_deadline(&mut state, caller, slot, 5000);
+        assert!(matches!(result.result, SyscallResult::TimedOut));
+        assert!(result.commits.iter().any(|c| matches!(
+            c.commit_type,
+            CommitType::CallTimedOut { caller: p } if p == caller.0
+        )));
+
+        // The server's reply capability is gone and the caller runs again
+        assert!(state.calls.is_empty());
+        assert!(state.deadlines.is_empty());
+        assert_eq!(state.get_process(caller).unwrap().state, ProcessState::Running);
+        assert!(matches!(
+            reply(&mut state, server, caller),
+            SyscallResult::Err(KernelError::InvalidCapability)
+        ));
+        assert!(crate::invariants::check_all_invariants(&state).is_empty());
+    }
+
+    #[test]
+    fn test_step_call_timeout_reply_before_deadline() {
+        let mut state = KernelState::new();
+        let caller = state.register_process("caller", 1000);
+        let (server, server_slot) = setup_endpoint_owner(&mut state, "server");
+        let slot = grant(&mut state, server, server_slot, caller);
+
+        call_with_deadline(&mut state, caller, slot, 2000);
+        assert!(matches!(reply(&mut state, server, caller), SyscallResult::Ok(0)));
+        assert!(state.deadlines.is_empty());
+
+        // The reply wins even if it is collected after the deadline
+        let result = call_with_deadline(&mut state, caller, slot, 6000);
+        assert!(matches!(result.result, SyscallResult::Message(ref m) if m.tag == 8));
+    }
+
+    // ========================================================================
+    // ReceiveTimeout tests
+    // ========================================================================
+
+    fn receive_any(
+        state: &mut KernelState,
+        pid: ProcessId,
+        slots: &[CapSlot],
+        deadline_ns: u64,
+        timestamp: u64,
+    ) -> SyscallResult {
+        let syscall = Syscall::ReceiveTimeout {
+            slots: slots.to_vec(),
+            deadline_ns,
+        };
+        step(state, pid, syscall, timestamp).result
+    }
+
+    #[test]
+    fn test_receive_timeout_returns_first_ready_endpoint() {
+        let mut state = KernelState::new();
+        let (server, first) = setup_endpoint_owner(&mut state, "server");
+        let second = match step(&mut state, server, Syscall::CreateEndpoint, 1000).result {
+            SyscallResult::Ok(packed) => (packed >> 32) as CapSlot,
+            _ => panic!("Expected Ok"),
+        };
+        let client = state.register_process("client", 1000);
+        let to_second = grant(&mut state, server, second, client);
+        step(
+            &mut state,
+            client,
+            Syscall::Send {
+                endpoint_slot: to_second,
+                tag: 9,
+                data: vec![],
+            },
+            2000,
+        );
+
+        match receive_any(&mut state, server, &[first, second], 0, 3000) {
+            SyscallResult::MessageFrom { slot, message } => {
+                assert_eq!(slot, second);
+                assert_eq!(message.tag, 9);
+            }
+            _ => panic!("Expected MessageFrom"),
+        }
+    }
+
+    #[test]
+    fn test_receive_timeout_parks_until_deadline() {
+        let mut state = KernelState::new();
+        let (server, slot) = setup_endpoint_owner(&mut state, "server");
+
+        let result = receive_any(&mut state, server, &[slot], 5000, 2000);
+        assert!(matches!(result, SyscallResult::WouldBlock));
+        assert_eq!(state.get_process(server).unwrap().state, ProcessState::Blocked);
+        assert_eq!(state.deadlines_due(5000), vec![server]);
+        assert!(crate::invariants::check_all_invariants(&state).is_empty());
+
+        let result = receive_any(&mut state, server, &[slot], 5000, 5000);
+        assert!(matches!(result, SyscallResult::TimedOut));
+        assert_eq!(state.get_process(server).unwrap().state, ProcessState::Running);
+        assert_eq!(state.next_deadline(), None);
+    }
+
+    #[test]
+    fn test_send_wakes_parked_receiver() {
+        let mut state = KernelState::new();
+        let (server, slot) = setup_endpoint_owner(&mut state, "server");
+        let client = state.register_process("client", 1000);
+        let to_server = grant(&mut state, server, slot, client);
+
+        receive_any(&mut state, server, &[slot], 5000, 2000);
+        assert_eq!(state.get_process(server).unwrap().state, ProcessState::Blocked);
+
+        let send = Syscall::Send {
+            endpoint_slot: to_server,
+            tag: 7,
+            data: vec![],
+        };
+        step(&mut state, client, send, 3000);
+        assert_eq!(state.get_process(server).unwrap().state, ProcessState::Running);
+        assert!(state.deadlines.is_empty());
+        assert!(state.waits.is_empty());
+
+        match receive_any(&mut state, server, &[slot], 5000, 3000) {
+            SyscallResult::MessageFrom { message, .. } => assert_eq!(message.tag, 7),
+            _ => panic!("Expected MessageFrom"),
+        }
+    }
+
+    #[test]
+    fn test_receive_timeout_without_deadline_never_times_out() {
+        let mut state = KernelState::new();
+        let (server, slot) = setup_endpoint_owner(&mut state, "server");
+
+        let result = receive_any(&mut state, server, &[slot], 0, u64::MAX);
+        assert!(matches!(result, SyscallResult::WouldBlock));
+        assert_eq!(state.next_deadline(), None);
+    }
+
+    #[test]
+    fn test_receive_timeout_checks_every_slot() {
+        let mut state = KernelState::new();
+        let (server, slot) = setup_endpoint_owner(&mut state, "server");
+
+        let result = receive_any(&mut state, server, &[], 0, 2000);
+        assert!(matches!(result, SyscallResult::Err(KernelError::InvalidArgument)));
+
+        let result = receive_any(&mut state, server, &[slot, 99], 0, 2000);
+        assert!(matches!(result, SyscallResult::Err(KernelError::InvalidCapability)));
+
+        let too_many = vec![slot; MAX_WAIT_SLOTS + 1];
+        let result = receive_any(&mut state, server, &too_many, 0, 2000);
+        assert!(matches!(result, SyscallResult::Err(KernelError::InvalidArgument)));
+    }
+
+    #[test]
+    fn test_killing_parked_process_clears_deadline() {
+        let mut state = KernelState::new();
+        let (server, slot) = setup_endpoint_owner(&mut state, "server");
+        receive_any(&mut state, server, &[slot], 5000, 2000);
+
+        step(&mut state, server, Syscall::Exit { code: 0 }, 3000);
+        assert!(state.deadlines.is_empty());
+        assert!(state.waits.is_empty());
+    }
+
+    // ========================================================================
+    // CapRevoke tests
+    // ========================================================================
+
+    #[test]
+    fn test_step_cap_revoke() {
+        let mut state = KernelState::new();
+        let pid = state.register_process("test", 1000);
+
+        // Create endpoint and capability
+        state.create_endpoint(pid);
+        let endpoint_id = EndpointId(1);
+
+        let cap = Capability {
+            id: state.alloc_cap_id(),
+            object_type: ObjectType::Endpoint,
+            object_id: endpoint_id.0,
+            permissions: Permissions::full(),
+            generation: 0,
+            expires_at: 0,
+            badge: 0,
+        };
+        let slot = state.get_cap_space_mut(pid).unwrap().insert(cap);
+
+        assert!(state.get_cap_space(pid).unwrap().contains(slot));
+
+        let result = step(&mut state, pid, Syscall::CapRevoke { slot }, 2000);
+
+        assert!(matches!(result.result, SyscallResult::Ok(0)));
+        assert!(!state.get_cap_space(pid).unwrap().contains(slot));
+
+        // Should generate commit
+        assert_eq!(result.commits.len(), 1);
+        match &result.commits[0].commit_type {
+            CommitType::CapRevoked { pid: revoke_pid, slot: revoke_slot } => {
+                assert_eq!(*revoke_pid, pid.0);
+                assert_eq!(*revoke_slot, slot);
+            }
+            _ => panic!("Expected CapRevoked commit"),
+        }
+    }
+
+    #[test]
+    fn test_step_cap_revoke_invalid_slot() {
+        let mut state = KernelState::new();
+        let pid = state.register_process("test", 1000);
+
+        let result = step(&mut state, pid, Syscall::CapRevoke { slot: 999 }, 2000);
+
+        assert!(matches!(
+            result.result,
+            SyscallResult::Err(KernelError::InvalidCapability)
+        ));
+    }
+
+    /// Register a process that owns an endpoint and a full cap to it.
+    fn setup_endpoint_owner(state: &mut KernelState, name: &str) -> (ProcessId, CapSlot) {
+        let pid = state.register_process(name, 1000);
+        let result = step(state, pid, Syscall::CreateEndpoint, 1000);
+        let slot = match result.result {
+            SyscallResult::Ok(packed) => (packed >> 32) as CapSlot,
+            _ => panic!("Expected Ok"),
+        };
+        (pid, slot)
+    }
+
+    fn grant(state: &mut KernelState, from: ProcessId, slot: CapSlot, to: ProcessId) -> CapSlot {
+        let result = step(
+            state,
+            from,
+            Syscall::CapGrant {
+                from_slot: slot,
+                to_pid: to,
+                permissions: Permissions::full(),
+            },
+            1500,
+        );
+        match result.result {
+            SyscallResult::Ok(s) => s as CapSlot,
+            _ => panic!("Expected Ok"),
+        }
+    }
+
+    #[test]
+    fn test_step_cap_revoke_removes_subtree() {
+        let mut state = KernelState::new();
+        let (owner, owner_slot) = setup_endpoint_owner(&mut state, "owner");
+        let (a, _) = setup_endpoint_owner(&mut state, "a");
+        let (b, _) = setup_endpoint_owner(&mut state, "b");
+
+        // owner -> a -> b, and a derives a read-only copy for itself
+        let a_slot = grant(&mut state, owner, owner_slot, a);
+        let b_slot = grant(&mut state, a, a_slot, b);
+        let derived = step(
+            &mut state,
+            a,
+            Syscall::CapDerive {
+                slot: a_slot,
+                new_permissions: Permissions::read_only(),
+            },
+            1600,
+        );
+        let a_derived = match derived.result {
+            SyscallResult::Ok(s) => s as CapSlot,
+            _ => panic!("Expected Ok"),
+        };
+
+        let b_direct = grant(&mut state, owner, owner_slot, b);
+
+        // Revoking a's cap pulls back everything derived from it
+        let result = step(&mut state, a, Syscall::CapRevoke { slot: a_slot }, 2000);
+        assert!(matches!(result.result, SyscallResult::Ok(0)));
+
+        assert!(!state.get_cap_space(a).unwrap().contains(a_slot));
+        assert!(!state.get_cap_space(a).unwrap().contains(a_derived));
+        assert!(!state.get_cap_space(b).unwrap().contains(b_slot));
+
+        // Unrelated branches survive
+        assert!(state.get_cap_space(owner).unwrap().contains(owner_slot));
+        assert!(state.get_cap_space(b).unwrap().contains(b_direct));
+
+        let revoked: Vec<(u64, u32)> = result
+            .commits
+            .iter()
+            .filter_map(|c| match c.commit_type {
+                CommitType::CapRevoked { pid, slot } => Some((pid, slot)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(revoked, vec![(a.0, a_slot), (b.0, b_slot), (a.0, a_derived)]);
+
+        // Only the direct grant to b is still linked under owner's cap
+        let owner_cap_id = state.get_cap_space(owner).unwrap().get(owner_slot).unwrap().id;
+        assert_eq!(state.cap_derivations.subtree(owner_cap_id).len(), 2);
+    }
+
+    #[test]
+    fn test_step_cap_revoke_notifies_holders() {
+        let mut state = KernelState::new();
+        let (owner, owner_slot) = setup_endpoint_owner(&mut state, "owner");
+        let (holder, _) = setup_endpoint_owner(&mut state, "holder");
+
+        let holder_slot = grant(&mut state, owner, owner_slot, holder);
+        let object_id = state.get_cap_space(owner).unwrap().get(owner_slot).unwrap().object_id;
+
+        let result = step(&mut state, owner, Syscall::CapRevoke { slot: owner_slot }, 2000);
+        assert!(matches!(result.result, SyscallResult::Ok(0)));
+
+        // The holder's own endpoint (id 2) receives the notification
+        let endpoint = state.get_endpoint_mut(EndpointId(2)).unwrap();
+        assert_eq!(endpoint.owner, holder);
+        let msg = endpoint.dequeue().unwrap();
+        assert_eq!(msg.sender, ProcessId(0));
+        assert_eq!(msg.tag, MSG_CAP_REVOKED);
+        assert_eq!(
+            msg.data,
+            RevokeNotification {
+                pid: holder,
+                slot: holder_slot,
+                object_type: ObjectType::Endpoint as u8,
+                object_id,
+                reason: revoke_reason::EXPLICIT,
+            }
+            .to_payload()
+        );
+
+        // The revoker is not notified about its own slot
+        assert!(state.get_endpoint(EndpointId(1)).unwrap().pending_messages.is_empty());
+
+        assert!(result.commits.iter().any(|c| matches!(
+            c.commit_type,
+            CommitType::IpcSent { from: 0, endpoint: 2, tag: MSG_CAP_REVOKED, .. }
+        )));
+    }
+
+    #[test]
+    fn test_step_cap_delete_keeps_children_revocable() {
+        let mut state = KernelState::new();
+        let (owner, owner_slot) = setup_endpoint_owner(&mut state, "owner");
+        let (a, _) = setup_endpoint_owner(&mut state, "a");
+        let (b, _) = setup_endpoint_owner(&mut state, "b");
+
+        let a_slot = grant(&mut state, owner, owner_slot, a);
+        let b_slot = grant(&mut state, a, a_slot, b);
+
+        // a drops its copy; b's cap now hangs off owner's cap
+        step(&mut state, a, Syscall::CapDelete { slot: a_slot }, 1800);
+
+        step(&mut state, owner, Syscall::CapRevoke { slot: owner_slot }, 2000);
+        assert!(!state.get_cap_space(b).unwrap().contains(b_slot));
+        assert!(state.cap_derivations.is_empty());
+    }
+
+    // ========================================================================
+    // Capability TTL tests
+    // ========================================================================
+
+    #[test]
+    fn test_step_cap_grant_with_ttl_sets_expiry() {
+        let mut state = KernelState::new();
+        let (owner, owner_slot) = setup_endpoint_owner(&mut state, "owner");
+        let holder = state.register_process("holder", 1000);
+
+        let result = step(
+            &mut state,
+            owner,
+            Syscall::CapGrantWithTtl {
+                from_slot: owner_slot,
+                to_pid: holder,
+                permissions: Permissions::write_only(),
+                ttl_ns: 60_000,
+            },
+            2000,
+        );
+
+        let slot = match result.result {
+            SyscallResult::Ok(s) => s as CapSlot,
+            _ => panic!("Expected Ok"),
+        };
+        let cap = state.get_cap_space(holder).unwrap().get(slot).unwrap();
+        assert_eq!(cap.expires_at, 62_000);
+    }
+
+    #[test]
+    fn test_step_cap_ttl_zero_rejected() {
+        let mut state = KernelState::new();
+        let (owner, owner_slot) = setup_endpoint_owner(&mut state, "owner");
+
+        let result = step(
+            &mut state,
+            owner,
+            Syscall::CapDeriveWithTtl {
+                slot: owner_slot,
+                new_permissions: Permissions::read_only(),
+                ttl_ns: 0,
+            },
+            2000,
+        );
+
+        assert!(matches!(
+            result.result,
+            SyscallResult::Err(KernelError::InvalidArgument)
+        ));
+        assert!(result.commits.is_empty());
+    }
+
+    #[test]
+    fn test_step_cap_ttl_cannot_outlive_source() {
+        let mut state = KernelState::new();
+        let (owner, owner_slot) = setup_endpoint_owner(&mut state, "owner");
+        let holder = state.register_process("holder", 1000);
+
+        let result = step(
+            &mut state,
+            owner,
+            Syscall::CapGrantWithTtl {
+                from_slot: owner_slot,
+                to_pid: holder,
+                permissions: Permissions::full(),
+                ttl_ns: 1000,
+            },
+            2000,
+        );
+        let holder_slot = match result.result {
+            SyscallResult::Ok(s) => s as CapSlot,
+            _ => panic!("Expected Ok"),
+        };
+
+        // Asking for a longer lifetime is clamped to the source's expiry
+        let result = step(
+            &mut state,
+            holder,
+            Syscall::CapDeriveWithTtl {
+                slot: holder_slot,
+                new_permissions: Permissions::read_only(),
+                ttl_ns: 1_000_000,
+            },
+            2500,
+        );
+        let derived_slot = match result.result {
+            SyscallResult::Ok(s) => s as CapSlot,
+            _ => panic!("Expected Ok"),
+        };
+        assert_eq!(
+            state.get_cap_space(holder).unwrap().get(derived_slot).unwrap().expires_at,
+            3000
+        );
+
+        // A plain derive inherits the expiry
+        let result = step(
+            &mut state,
+            holder,
+            Syscall::CapDerive {
+                slot: holder_slot,
+                new_permissions: Permissions::read_only(),
             },
-            2000,
+            2500,
+        );
+        let plain_slot = match result.result {
+            SyscallResult::Ok(s) => s as CapSlot,
+            _ => panic!("Expected Ok"),
+        };
+        assert_eq!(
+            state.get_cap_space(holder).unwrap().get(plain_slot).unwrap().expires_at,
+            3000
         );
+    }
 
-        match result.result {
-            SyscallResult::Ok(new_slot) => {
-                // Verify grantee has the capability
-                let grantee_cap = state
-                    .get_cap_space(grantee)
-                    .unwrap()
-                    .get(new_slot as u32)
-                    .unwrap();
-                assert_eq!(grantee_cap.object_id, endpoint_id.0);
-                assert!(grantee_cap.permissions.read);
-                assert!(!grantee_cap.permissions.write);
-            }
+    #[test]
+    fn test_step_sweeps_expired_caps() {
+        let mut state = KernelState::new();
+        let (owner, owner_slot) = setup_endpoint_owner(&mut state, "owner");
+        let (holder, _) = setup_endpoint_owner(&mut state, "holder");
+
+        let result = step(
+            &mut state,
+            owner,
+            Syscall::CapGrantWithTtl {
+                from_slot: owner_slot,
+                to_pid: holder,
+                permissions: Permissions::write_only(),
+                ttl_ns: 1000,
+            },
+            2000,
+        );
+        let holder_slot = match result.result {
+            SyscallResult::Ok(s) => s as CapSlot,
             _ => panic!("Expected Ok"),
-        }
+        };
+
+        // Still valid at exactly the expiry time
+        let result = step(&mut state, owner, Syscall::Yield, 3000);
+        assert!(result.commits.is_empty());
+        assert!(state.get_cap_space(holder).unwrap().contains(holder_slot));
+
+        // Any later step reaps it, regardless of who is calling
+        let result = step(&mut state, owner, Syscall::Yield, 3001);
+        assert!(!state.get_cap_space(holder).unwrap().contains(holder_slot));
+        assert!(state.get_cap_space(owner).unwrap().contains(owner_slot));
+
+        assert!(matches!(
+            result.commits[0].commit_type,
+            CommitType::CapRevoked { pid, slot } if pid == holder.0 && slot == holder_slot
+        ));
+
+        let msg = state.get_endpoint_mut(EndpointId(2)).unwrap().dequeue().unwrap();
+        assert_eq!(msg.tag, MSG_CAP_REVOKED);
+        assert_eq!(msg.data[4], ObjectType::Endpoint as u8);
+        assert_eq!(msg.data[13], revoke_reason::EXPIRED);
+        assert!(state.cap_derivations.is_empty());
     }
 
     // ========================================================================
-    // Simple syscalls: Debug, Yield, Exit
+    // Badged endpoint tests
     // ========================================================================
 
+    fn derive_badged(state: &mut KernelState, pid: ProcessId, slot: CapSlot, badge: u64) -> StepResult {
+        step(
+            state,
+            pid,
+            Syscall::CapDeriveBadged {
+                slot,
+                new_permissions: Permissions::full(),
+                badge,
+            },
+            1500,
+        )
+    }
+
     #[test]
-    fn test_step_debug() {
+    fn test_step_badged_cap_stamps_messages() {
         let mut state = KernelState::new();
-        let pid = state.register_process("test", 1000);
+        let (server, server_slot) = setup_endpoint_owner(&mut state, "server");
+        let client = state.register_process("client", 1000);
 
-        let result = step(
+        let badged_slot = match derive_badged(&mut state, server, server_slot, 0xC11E).result {
+            SyscallResult::Ok(s) => s as CapSlot,
+            _ => panic!("Expected Ok"),
+        };
+        let client_slot = grant(&mut state, server, badged_slot, client);
+        assert_eq!(
+            state.get_cap_space(client).unwrap().get(client_slot).unwrap().badge,
+            0xC11E
+        );
+
+        step(
             &mut state,
-            pid,
-            Syscall::Debug {
-                msg: alloc::string::String::from("Hello debug!"),
+            client,
+            Syscall::Send {
+                endpoint_slot: client_slot,
+                tag: 1,
+                data: vec![],
+            },
+            2000,
+        );
+        step(
+            &mut state,
+            server,
+            Syscall::Send {
+                endpoint_slot: server_slot,
+                tag: 2,
+                data: vec![],
             },
             2000,
         );
 
-        assert!(matches!(result.result, SyscallResult::Ok(0)));
-        assert!(result.commits.is_empty()); // Debug doesn't generate commits
+        let endpoint = state.get_endpoint_mut(EndpointId(1)).unwrap();
+        assert_eq!(endpoint.dequeue().unwrap().badge, 0xC11E);
+        assert_eq!(endpoint.dequeue().unwrap().badge, 0);
     }
 
     #[test]
-    fn test_step_yield() {
+    fn test_step_badge_cannot_be_changed() {
+        let mut state = KernelState::new();
+        let (server, server_slot) = setup_endpoint_owner(&mut state, "server");
+
+        let badged_slot = match derive_badged(&mut state, server, server_slot, 7).result {
+            SyscallResult::Ok(s) => s as CapSlot,
+            _ => panic!("Expected Ok"),
+        };
+
+        let result = derive_badged(&mut state, server, badged_slot, 8);
+        assert!(matches!(
+            result.result,
+            SyscallResult::Err(KernelError::PermissionDenied)
+        ));
+        assert!(result.commits.is_empty());
+    }
+
+    #[test]
+    fn test_step_badge_requires_nonzero_endpoint() {
+        let mut state = KernelState::new();
+        let (server, server_slot) = setup_endpoint_owner(&mut state, "server");
+
+        let result = derive_badged(&mut state, server, server_slot, 0);
+        assert!(matches!(
+            result.result,
+            SyscallResult::Err(KernelError::InvalidArgument)
+        ));
+
+        let cap = Capability {
+            id: state.alloc_cap_id(),
+            object_type: ObjectType::Console,
+            object_id: 0,
+            permissions: Permissions::full(),
+            generation: 0,
+            expires_at: 0,
+            badge: 0,
+        };
+        let console_slot = state.get_cap_space_mut(server).unwrap().insert(cap);
+        let result = derive_badged(&mut state, server, console_slot, 7);
+        assert!(matches!(
+            result.result,
+            SyscallResult::Err(KernelError::InvalidArgument)
+        ));
+    }
+
+    // ========================================================================
+    // Bounded queue tests
+    // ========================================================================
+
+    fn create_bounded_endpoint(
+        state: &mut KernelState,
+        pid: ProcessId,
+        capacity: usize,
+        policy: OverflowPolicy,
+    ) -> CapSlot {
+        let result = step(
+            state,
+            pid,
+            Syscall::CreateEndpointWithCapacity { capacity, policy },
+            1000,
+        );
+        match result.result {
+            SyscallResult::Ok(packed) => (packed >> 32) as CapSlot,
+            _ => panic!("Expected Ok"),
+        }
+    }
+
+    fn send_tag(state: &mut KernelState, pid: ProcessId, slot: CapSlot, tag: u32) -> StepResult {
+        step(
+            state,
+            pid,
+            Syscall::Send {
+                endpoint_slot: slot,
+                tag,
+                data: vec![],
+            },
+            2000,
+        )
+    }
+
+    #[test]
+    fn test_step_send_to_full_queue_rejected() {
         let mut state = KernelState::new();
         let pid = state.register_process("test", 1000);
+        let slot = create_bounded_endpoint(&mut state, pid, 2, OverflowPolicy::Reject);
 
-        let result = step(&mut state, pid, Syscall::Yield, 2000);
+        assert!(matches!(send_tag(&mut state, pid, slot, 1).result, SyscallResult::Ok(0)));
+        assert!(matches!(send_tag(&mut state, pid, slot, 2).result, SyscallResult::Ok(0)));
 
-        assert!(matches!(result.result, SyscallResult::Ok(0)));
-        assert!(result.commits.is_empty()); // Yield doesn't generate commits
+        let result = send_tag(&mut state, pid, slot, 3);
+        assert!(matches!(
+            result.result,
+            SyscallResult::Err(KernelError::QueueFull)
+        ));
+        assert!(result.commits.is_empty());
+
+        let endpoint = state.get_endpoint(EndpointId(1)).unwrap();
+        assert_eq!(endpoint.pending_messages.len(), 2);
+        assert_eq!(endpoint.metrics.dropped_messages, 1);
+        assert_eq!(state.get_process(pid).unwrap().metrics.ipc_sent, 2);
+        assert_eq!(state.total_ipc_count, 2);
     }
 
     #[test]
-    fn test_step_exit() {
+    fn test_step_send_to_full_queue_blocks() {
         let mut state = KernelState::new();
         let pid = state.register_process("test", 1000);
+        let slot = create_bounded_endpoint(&mut state, pid, 1, OverflowPolicy::Block);
 
-        // Process should be running
-        assert_eq!(state.get_process(pid).unwrap().state, ProcessState::Running);
+        send_tag(&mut state, pid, slot, 1);
+        let result = send_tag(&mut state, pid, slot, 2);
+        assert!(matches!(result.result, SyscallResult::WouldBlock));
+        assert_eq!(state.get_endpoint(EndpointId(1)).unwrap().metrics.dropped_messages, 0);
 
-        let result = step(&mut state, pid, Syscall::Exit { code: 42 }, 2000);
+        // Once the receiver drains the queue the retried send goes through
+        step(&mut state, pid, Syscall::Receive { endpoint_slot: slot }, 2500);
+        assert!(matches!(send_tag(&mut state, pid, slot, 2).result, SyscallResult::Ok(0)));
+    }
 
-        // Should return exit code
-        match result.result {
-            SyscallResult::Ok(code) => assert_eq!(code, 42),
-            _ => panic!("Expected Ok with exit code"),
+    #[test]
+    fn test_step_create_endpoint_capacity_bounds() {
+        let mut state = KernelState::new();
+        let pid = state.register_process("test", 1000);
+
+        for capacity in [0, MAX_QUEUE_CAPACITY + 1] {
+            let result = step(
+                &mut state,
+                pid,
+                Syscall::CreateEndpointWithCapacity {
+                    capacity,
+                    policy: OverflowPolicy::Reject,
+                },
+                1000,
+            );
+            assert!(matches!(
+                result.result,
+                SyscallResult::Err(KernelError::InvalidArgument)
+            ));
         }
+        assert!(state.endpoints.is_empty());
 
-        // Process should be zombie
-        assert_eq!(state.get_process(pid).unwrap().state, ProcessState::Zombie);
+        step(&mut state, pid, Syscall::CreateEndpoint, 1000);
+        assert_eq!(
+            state.get_endpoint(EndpointId(1)).unwrap().capacity,
+            DEFAULT_QUEUE_CAPACITY
+        );
+    }
 
-        // Should generate commit
-        assert_eq!(result.commits.len(), 1);
-        match &result.commits[0].commit_type {
-            CommitType::ProcessExited { pid: exit_pid, code } => {
-                assert_eq!(*exit_pid, pid.0);
-                assert_eq!(*code, 42);
-            }
-            _ => panic!("Expected ProcessExited commit"),
+    // ========================================================================
+    // Notification tests
+    // ========================================================================
+
+    fn create_notification(state: &mut KernelState, pid: ProcessId) -> CapSlot {
+        match step(state, pid, Syscall::CreateNotification, 1000).result {
+            SyscallResult::Ok(packed) => (packed >> 32) as CapSlot,
+            _ => panic!("Expected Ok"),
         }
     }
 
     #[test]
-    fn test_step_exit_negative_code() {
+    fn test_step_create_notification() {
+        let mut state = KernelState::new();
+        let pid = state.register_process("test", 1000);
+
+        let result = step(&mut state, pid, Syscall::CreateNotification, 1000);
+        let (slot, id) = match result.result {
+            SyscallResult::Ok(packed) => ((packed >> 32) as CapSlot, packed & 0xFFFFFFFF),
+            _ => panic!("Expected Ok"),
+        };
+
+        let cap = state.get_cap_space(pid).unwrap().get(slot).unwrap();
+        assert_eq!(cap.object_type, ObjectType::Notification);
+        assert_eq!(cap.object_id, id);
+        assert!(matches!(
+            result.commits[0].commit_type,
+            CommitType::NotificationCreated { id: 1, owner } if owner == pid.0
+        ));
+    }
+
+    #[test]
+    fn test_step_signals_coalesce() {
+        let mut state = KernelState::new();
+        let pid = state.register_process("test", 1000);
+        let slot = create_notification(&mut state, pid);
+
+        for bits in [0b001, 0b100, 0b001] {
+            let result = step(
+                &mut state,
+                pid,
+                Syscall::Signal {
+                    notification_slot: slot,
+                    bits,
+                },
+                2000,
+            );
+            assert!(matches!(result.result, SyscallResult::Ok(0)));
+        }
+
+        let result = step(&mut state, pid, Syscall::Wait { notification_slot: slot }, 3000);
+        assert!(matches!(result.result, SyscallResult::Ok(0b101)));
+        assert!(matches!(
+            result.commits.as_slice(),
+            [Commit {
+                commit_type: CommitType::NotificationTaken { bits: 0b101, .. },
+                ..
+            }]
+        ));
+
+        // The word was consumed
+        let result = step(&mut state, pid, Syscall::Wait { notification_slot: slot }, 3000);
+        assert!(matches!(result.result, SyscallResult::WouldBlock));
+        let result = step(&mut state, pid, Syscall::Poll { notification_slot: slot }, 3000);
+        assert!(matches!(result.result, SyscallResult::Ok(0)));
+        assert!(result.commits.is_empty());
+    }
+
+    #[test]
+    fn test_step_signal_requires_write_and_wait_requires_read() {
+        let mut state = KernelState::new();
+        let owner = state.register_process("owner", 1000);
+        let waker = state.register_process("waker", 1000);
+        let slot = create_notification(&mut state, owner);
+
+        let result = step(
+            &mut state,
+            owner,
+            Syscall::CapGrant {
+                from_slot: slot,
+                to_pid: waker,
+                permissions: Permissions::write_only(),
+            },
+            1500,
+        );
+        let waker_slot = match result.result {
+            SyscallResult::Ok(s) => s as CapSlot,
+            _ => panic!("Expected Ok"),
+        };
+
+        let result = step(
+            &mut state,
+            waker,
+            Syscall::Signal {
+                notification_slot: waker_slot,
+                bits: 1,
+            },
+            2000,
+        );
+        assert!(matches!(result.result, SyscallResult::Ok(0)));
+
+        let result = step(&mut state, waker, Syscall::Poll { notification_slot: waker_slot }, 2000);
+        assert!(matches!(
+            result.result,
+            SyscallResult::Err(KernelError::PermissionDenied)
+        ));
+
+        let result = step(&mut state, owner, Syscall::Poll { notification_slot: slot }, 2000);
+        assert!(matches!(result.result, SyscallResult::Ok(1)));
+    }
+
+    #[test]
+    fn test_step_signal_rejects_endpoint_cap_and_zero_bits() {
+        let mut state = KernelState::new();
+        let (pid, endpoint_slot) = setup_endpoint_owner(&mut state, "test");
+        let slot = create_notification(&mut state, pid);
+
+        let result = step(
+            &mut state,
+            pid,
+            Syscall::Signal {
+                notification_slot: endpoint_slot,
+                bits: 1,
+            },
+            2000,
+        );
+        assert!(matches!(
+            result.result,
+            SyscallResult::Err(KernelError::InvalidCapability)
+        ));
+
+        let result = step(
+            &mut state,
+            pid,
+            Syscall::Signal {
+                notification_slot: slot,
+                bits: 0,
+            },
+            2000,
+        );
+        assert!(matches!(
+            result.result,
+            SyscallResult::Err(KernelError::InvalidArgument)
+        ));
+    }
+
+    // ========================================================================
+    // Shared memory tests
+    // ========================================================================
+
+    fn create_region(state: &mut KernelState, pid: ProcessId, size: usize) -> CapSlot {
+        match step(state, pid, Syscall::CreateRegion { size }, 1000).result {
+            SyscallResult::Ok(packed) => (packed >> 32) as CapSlot,
+            _ => panic!("Expected Ok"),
+        }
+    }
+
+    fn map_region(
+        state: &mut KernelState,
+        pid: ProcessId,
+        slot: CapSlot,
+        writable: bool,
+    ) -> SyscallResult {
+        step(
+            state,
+            pid,
+            Syscall::MapRegion {
+                region_slot: slot,
+                writable,
+            },
+            2000,
+        )
+        .result
+    }
+
+    #[test]
+    fn test_step_create_region_rejects_bad_sizes() {
         let mut state = KernelState::new();
         let pid = state.register_process("test", 1000);
 
-        let result = step(&mut state, pid, Syscall::Exit { code: -1 }, 2000);
-
-        // Should handle negative exit codes
-        match result.result {
-            SyscallResult::Ok(code) => assert_eq!(code as i64, -1i32 as i64),
-            _ => panic!("Expected Ok with exit code"),
+        for size in [0, MAX_REGION_SIZE + 1] {
+            let result = step(&mut state, pid, Syscall::CreateRegion { size }, 1000);
+            assert!(matches!(
+                result.result,
+                SyscallResult::Err(KernelError::InvalidArgument)
+            ));
         }
+        assert!(state.regions.is_empty());
     }
 
-    // ========================================================================
-    // Kill syscall tests
-    // ========================================================================
-
     #[test]
-    fn test_step_kill_success() {
+    fn test_step_share_region_read_only() {
         let mut state = KernelState::new();
-        let killer = state.register_process("killer", 1000);
-        let victim = state.register_process("victim", 1000);
+        let owner = state.register_process("vfs", 1000);
+        let client = state.register_process("client", 1000);
+        let slot = create_region(&mut state, owner, 10_000);
+
+        assert!(matches!(
+            map_region(&mut state, owner, slot, true),
+            SyscallResult::Ok(12288)
+        ));
 
         let result = step(
             &mut state,
-            killer,
-            Syscall::Kill { target_pid: victim },
-            2000,
+            owner,
+            Syscall::CapGrant {
+                from_slot: slot,
+                to_pid: client,
+                permissions: Permissions::read_only(),
+            },
+            1500,
         );
+        let client_slot = match result.result {
+            SyscallResult::Ok(s) => s as CapSlot,
+            _ => panic!("Expected Ok"),
+        };
+        assert!(matches!(
+            map_region(&mut state, client, client_slot, true),
+            SyscallResult::Err(KernelError::PermissionDenied)
+        ));
+        assert!(matches!(
+            map_region(&mut state, client, client_slot, false),
+            SyscallResult::Ok(12288)
+        ));
 
-        assert!(matches!(result.result, SyscallResult::Ok(0)));
-        assert_eq!(state.get_process(victim).unwrap().state, ProcessState::Zombie);
-
-        // Should generate commit
-        assert_eq!(result.commits.len(), 1);
-        match &result.commits[0].commit_type {
-            CommitType::ProcessKilled { pid, by } => {
-                assert_eq!(*pid, victim.0);
-                assert_eq!(*by, killer.0);
-            }
-            _ => panic!("Expected ProcessKilled commit"),
-        }
+        let region = state.regions.values().next().unwrap();
+        assert_eq!(region.mappings.get(&owner), Some(&true));
+        assert_eq!(region.mappings.get(&client), Some(&false));
+        assert!(crate::invariants::check_all_invariants(&state).is_empty());
     }
 
     #[test]
-    fn test_step_kill_target_not_found() {
+    fn test_step_unmap_region() {
         let mut state = KernelState::new();
         let pid = state.register_process("test", 1000);
+        let slot = create_region(&mut state, pid, 4096);
+        map_region(&mut state, pid, slot, false);
 
-        let result = step(
-            &mut state,
-            pid,
-            Syscall::Kill {
-                target_pid: ProcessId(999),
-            },
-            2000,
-        );
+        let result = step(&mut state, pid, Syscall::UnmapRegion { region_slot: slot }, 3000);
+        assert!(matches!(result.result, SyscallResult::Ok(0)));
+        assert!(matches!(
+            result.commits[0].commit_type,
+            CommitType::RegionUnmapped { id: 1, .. }
+        ));
 
+        // Not mapped any more
+        let result = step(&mut state, pid, Syscall::UnmapRegion { region_slot: slot }, 3000);
         assert!(matches!(
             result.result,
-            SyscallResult::Err(KernelError::ProcessNotFound)
+            SyscallResult::Err(KernelError::InvalidArgument)
         ));
-        assert!(result.commits.is_empty());
     }
 
     #[test]
-    fn test_step_kill_self() {
+    fn test_step_revoke_region_cap_unmaps_holder() {
+        let mut state = KernelState::new();
+        let owner = state.register_process("owner", 1000);
+        let client = state.register_process("client", 1000);
+        let slot = create_region(&mut state, owner, 4096);
+        let client_slot = grant(&mut state, owner, slot, client);
+        map_region(&mut state, client, client_slot, true);
+
+        let result = step(&mut state, owner, Syscall::CapRevoke { slot }, 4000);
+        assert!(result.commits.iter().any(|c| matches!(
+            c.commit_type,
+            CommitType::RegionUnmapped { pid, id: 1 } if pid == client.0
+        )));
+        assert!(state.regions.values().next().unwrap().mappings.is_empty());
+        assert!(crate::invariants::check_all_invariants(&state).is_empty());
+    }
+
+    #[test]
+    fn test_step_delete_region_cap_keeps_backed_mapping() {
         let mut state = KernelState::new();
         let pid = state.register_process("test", 1000);
+        let slot = create_region(&mut state, pid, 4096);
 
-        // Process can kill itself
-        let result = step(&mut state, pid, Syscall::Kill { target_pid: pid }, 2000);
+        // A second read-only cap still backs a read-only mapping
+        let result = step(
+            &mut state,
+            pid,
+            Syscall::CapDerive {
+                slot,
+                new_permissions: Permissions::read_only(),
+            },
+            1500,
+        );
+        assert!(matches!(result.result, SyscallResult::Ok(_)));
+        map_region(&mut state, pid, slot, false);
 
-        assert!(matches!(result.result, SyscallResult::Ok(0)));
-        assert_eq!(state.get_process(pid).unwrap().state, ProcessState::Zombie);
+        let result = step(&mut state, pid, Syscall::CapDelete { slot }, 3000);
+        assert_eq!(result.commits.len(), 1);
+        assert_eq!(
+            state.regions.values().next().unwrap().mappings.get(&pid),
+            Some(&false)
+        );
     }
 
     // ========================================================================
-    // ListProcesses tests
+    // Device capability tests
     // ========================================================================
 
+    /// A driver holding IRQ 4 and a notification it is bound to.
+    fn setup_irq_driver(state: &mut KernelState) -> (ProcessId, CapSlot, CapSlot) {
+        let driver = state.register_process("uart", 1000);
+        let irq_slot = state.mint_irq_cap(driver, 4).unwrap();
+        let notification_slot = create_notification(state, driver);
+
+        let result = step(
+            state,
+            driver,
+            Syscall::IrqBindNotification {
+                irq_slot,
+                notification_slot,
+                bits: 0b10,
+            },
+            1500,
+        );
+        assert!(matches!(result.result, SyscallResult::Ok(0)));
+        (driver, irq_slot, notification_slot)
+    }
+
     #[test]
-    fn test_step_list_processes() {
+    fn test_interrupt_signals_and_masks_until_ack() {
         let mut state = KernelState::new();
-        let pid1 = state.register_process("proc1", 1000);
-        let pid2 = state.register_process("proc2", 1000);
-        state.kill_process(pid2); // Make one a zombie
+        let (driver, irq_slot, notification_slot) = setup_irq_driver(&mut state);
 
-        let result = step(&mut state, pid1, Syscall::ListProcesses, 2000);
+        let commits = interrupt(&mut state, 4, 2000);
+        assert!(matches!(
+            commits.last().unwrap().commit_type,
+            CommitType::IrqRaised { irq: 4 }
+        ));
 
-        match result.result {
-            SyscallResult::ProcessList(procs) => {
-                assert_eq!(procs.len(), 2);
+        // Masked: a second interrupt is not delivered
+        assert!(interrupt(&mut state, 4, 2100).is_empty());
+        let result = step(&mut state, driver, Syscall::Poll { notification_slot }, 2200);
+        assert!(matches!(result.result, SyscallResult::Ok(0b10)));
 
-                let proc1 = procs.iter().find(|(p, _, _)| *p == pid1).unwrap();
-                assert_eq!(proc1.1, "proc1");
-                assert_eq!(proc1.2, ProcessState::Running);
+        let result = step(&mut state, driver, Syscall::IrqAck { irq_slot }, 2300);
+        assert!(matches!(result.result, SyscallResult::Ok(0)));
+        assert!(!interrupt(&mut state, 4, 2400).is_empty());
 
-                let proc2 = procs.iter().find(|(p, _, _)| *p == pid2).unwrap();
-                assert_eq!(proc2.1, "proc2");
-                assert_eq!(proc2.2, ProcessState::Zombie);
+        // Unbound lines are ignored
+        assert!(interrupt(&mut state, 5, 2500).is_empty());
+        assert!(crate::invariants::check_all_invariants(&state).is_empty());
+    }
+
+    #[test]
+    fn test_interrupt_to_endpoint_queues_irq_message() {
+        let mut state = KernelState::new();
+        let (driver, endpoint_slot) = setup_endpoint_owner(&mut state, "driver");
+        let irq_slot = state.mint_irq_cap(driver, 11).unwrap();
+
+        step(
+            &mut state,
+            driver,
+            Syscall::IrqBindEndpoint {
+                irq_slot,
+                endpoint_slot,
+            },
+            1500,
+        );
+        interrupt(&mut state, 11, 2000);
+
+        let result = step(&mut state, driver, Syscall::Receive { endpoint_slot }, 2100);
+        match result.result {
+            SyscallResult::Message(msg) => {
+                assert_eq!(msg.sender, ProcessId(0));
+                assert_eq!(msg.tag, MSG_IRQ);
+                assert_eq!(msg.data, vec![11]);
             }
-            _ => panic!("Expected ProcessList"),
+            _ => panic!("Expected IRQ message"),
         }
     }
 
-    // ========================================================================
-    // SendWithCaps tests
-    // ========================================================================
+    #[test]
+    fn test_irq_bind_refuses_second_driver() {
+        let mut state = KernelState::new();
+        setup_irq_driver(&mut state);
+        let other = state.register_process("other", 1000);
+        let irq_slot = state.mint_irq_cap(other, 4).unwrap();
+        let notification_slot = create_notification(&mut state, other);
+
+        let result = step(
+            &mut state,
+            other,
+            Syscall::IrqBindNotification {
+                irq_slot,
+                notification_slot,
+                bits: 1,
+            },
+            2000,
+        );
+        assert!(matches!(
+            result.result,
+            SyscallResult::Err(KernelError::InvalidArgument)
+        ));
+    }
 
     #[test]
-    fn test_step_send_with_caps() {
+    fn test_irq_unbound_when_driver_exits_or_loses_cap() {
         let mut state = KernelState::new();
-        let sender = state.register_process("sender", 1000);
-        let receiver = state.register_process("receiver", 1000);
+        let (driver, irq_slot, _) = setup_irq_driver(&mut state);
+
+        let result = step(&mut state, driver, Syscall::CapDelete { slot: irq_slot }, 2000);
+        assert!(result
+            .commits
+            .iter()
+            .any(|c| matches!(c.commit_type, CommitType::IrqUnbound { irq: 4 })));
+        assert!(state.irq_bindings.is_empty());
+
+        let (driver, _, _) = setup_irq_driver(&mut state);
+        step(&mut state, driver, Syscall::Exit { code: 0 }, 3000);
+        assert!(state.irq_bindings.is_empty());
+    }
 
-        // Create endpoint
-        state.create_endpoint(receiver);
-        let endpoint_id = EndpointId(1);
+    #[test]
+    fn test_io_port_access_checks_range_and_rights() {
+        let mut state = KernelState::new();
+        let pm = state.register_process("pm", 1000);
+        let driver = state.register_process("uart", 1000);
+        let range = IoPortRange { base: 0x3F8, len: 8 };
+        let pm_slot = state.mint_io_port_cap(pm, range).unwrap();
 
-        // Give sender capability to endpoint (with write permission)
-        let endpoint_cap = Capability {
-            id: state.alloc_cap_id(),
-            object_type: ObjectType::Endpoint,
-            object_id: endpoint_id.0,
-            permissions: Permissions::full(),
-            generation: 0,
-            expires_at: 0,
+        let result = step(
+            &mut state,
+            pm,
+            Syscall::CapGrant {
+                from_slot: pm_slot,
+                to_pid: driver,
+                permissions: Permissions::read_only(),
+            },
+            1500,
+        );
+        let port_slot = match result.result {
+            SyscallResult::Ok(s) => s as CapSlot,
+            _ => panic!("Expected Ok"),
         };
-        let endpoint_slot = state.get_cap_space_mut(sender).unwrap().insert(endpoint_cap);
 
-        // Create a capability to transfer
-        state.create_endpoint(sender);
-        let transfer_endpoint_id = EndpointId(2);
-        let transfer_cap = Capability {
-            id: state.alloc_cap_id(),
-            object_type: ObjectType::Endpoint,
-            object_id: transfer_endpoint_id.0,
-            permissions: Permissions::read_only(),
-            generation: 0,
-            expires_at: 0,
+        let read = |state: &mut KernelState, port, width| {
+            step(
+                state,
+                driver,
+                Syscall::IoPortIn {
+                    port_slot,
+                    port,
+                    width,
+                },
+                2000,
+            )
+            .result
         };
-        let transfer_slot = state.get_cap_space_mut(sender).unwrap().insert(transfer_cap);
-
-        // Sender should have the transfer cap
-        assert!(state.get_cap_space(sender).unwrap().contains(transfer_slot));
+        assert!(matches!(read(&mut state, 0x3FD, 1), SyscallResult::Ok(0)));
+        assert!(matches!(
+            read(&mut state, 0x3FE, 4),
+            SyscallResult::Err(KernelError::PermissionDenied)
+        ));
+        assert!(matches!(
+            read(&mut state, 0x3F8, 3),
+            SyscallResult::Err(KernelError::InvalidArgument)
+        ));
 
-        // Send message with capability
+        // Read-only grant cannot write
         let result = step(
             &mut state,
-            sender,
-            Syscall::SendWithCaps {
-                endpoint_slot,
-                tag: 42,
-                data: vec![1, 2, 3],
-                cap_slots: vec![transfer_slot],
+            driver,
+            Syscall::IoPortOut {
+                port_slot,
+                port: 0x3F8,
+                width: 1,
+                value: 0x41,
             },
             2000,
         );
+        assert!(matches!(
+            result.result,
+            SyscallResult::Err(KernelError::PermissionDenied)
+        ));
+    }
 
-        assert!(matches!(result.result, SyscallResult::Ok(0)));
+    // ========================================================================
+    // Process tree tests
+    // ========================================================================
 
-        // Message should be in endpoint queue with transferred caps
-        let endpoint = state.get_endpoint(endpoint_id).unwrap();
-        assert_eq!(endpoint.pending_messages.len(), 1);
-        let msg = &endpoint.pending_messages[0];
-        assert_eq!(msg.sender, sender);
-        assert_eq!(msg.tag, 42);
-        assert_eq!(msg.caps.len(), 1);
-        assert_eq!(msg.caps[0].object_id, transfer_endpoint_id.0);
+    /// Init with an endpoint, plus one service it spawned.
+    fn setup_supervised(state: &mut KernelState) -> (ProcessId, CapSlot, ProcessId) {
+        let (init, init_slot) = setup_endpoint_owner(state, "init");
+        let service = state.register_child(init, "vfs", 1000);
+        (init, init_slot, service)
     }
 
     #[test]
-    fn test_step_send_with_caps_too_many_caps() {
-        use crate::types::MAX_CAPS_PER_MESSAGE;
-
+    fn test_child_exit_notifies_parent_and_wait_reaps() {
         let mut state = KernelState::new();
-        let sender = state.register_process("sender", 1000);
-        let receiver = state.register_process("receiver", 1000);
+        let (init, init_slot, service) = setup_supervised(&mut state);
 
-        // Create endpoint
-        state.create_endpoint(receiver);
-        let endpoint_id = EndpointId(1);
+        let result = step(&mut state, init, Syscall::WaitChild { pid: None }, 1500);
+        assert!(matches!(result.result, SyscallResult::WouldBlock));
 
-        let endpoint_cap = Capability {
-            id: state.alloc_cap_id(),
-            object_type: ObjectType::Endpoint,
-            object_id: endpoint_id.0,
-            permissions: Permissions::full(),
-            generation: 0,
-            expires_at: 0,
-        };
-        let endpoint_slot = state.get_cap_space_mut(sender).unwrap().insert(endpoint_cap);
+        step(&mut state, service, Syscall::Exit { code: 3 }, 2000);
 
-        // Create too many capabilities
-        let mut cap_slots = Vec::new();
-        for _ in 0..=MAX_CAPS_PER_MESSAGE {
-            let cap = Capability {
-                id: state.alloc_cap_id(),
-                object_type: ObjectType::Endpoint,
-                object_id: 100,
-                permissions: Permissions::full(),
-                generation: 0,
-                expires_at: 0,
-            };
-            let slot = state.get_cap_space_mut(sender).unwrap().insert(cap);
-            cap_slots.push(slot);
+        let result = step(&mut state, init, Syscall::Receive { endpoint_slot: init_slot }, 2100);
+        match result.result {
+            SyscallResult::Message(msg) => {
+                assert_eq!(msg.tag, MSG_CHILD_EXITED);
+                let expected = ChildExitNotification {
+                    pid: service,
+                    status: ExitStatus::Exited(3),
+                };
+                assert_eq!(msg.data, expected.to_payload());
+            }
+            _ => panic!("Expected child exit message"),
         }
 
-        let result = step(
-            &mut state,
-            sender,
-            Syscall::SendWithCaps {
-                endpoint_slot,
-                tag: 42,
-                data: vec![],
-                cap_slots,
-            },
-            2000,
-        );
+        let result = step(&mut state, init, Syscall::WaitChild { pid: None }, 2200);
+        assert!(matches!(
+            result.result,
+            SyscallResult::Reaped { pid, status: ExitStatus::Exited(3) } if pid == service
+        ));
+        assert!(state.get_process(service).is_none());
+        assert!(crate::invariants::check_all_invariants(&state).is_empty());
+
+        // Nothing left to wait for
+        let result = step(&mut state, init, Syscall::WaitChild { pid: None }, 2300);
+        assert!(matches!(
+            result.result,
+            SyscallResult::Err(KernelError::ProcessNotFound)
+        ));
+    }
+
+    #[test]
+    fn test_child_exit_reaches_parent_with_full_queue() {
+        let mut state = KernelState::new();
+        let init = state.register_process("init", 1000);
+        let init_slot = create_bounded_endpoint(&mut state, init, 1, OverflowPolicy::Reject);
+        let service = state.register_child(init, "vfs", 1000);
+
+        assert!(matches!(send_tag(&mut state, init, init_slot, 1).result, SyscallResult::Ok(_)));
+        assert!(matches!(
+            send_tag(&mut state, init, init_slot, 2).result,
+            SyscallResult::Err(KernelError::QueueFull)
+        ));
+
+        step(&mut state, service, Syscall::Exit { code: 0 }, 2000);
 
+        let endpoint = state.get_endpoint(EndpointId(1)).unwrap();
+        assert_eq!(endpoint.pending_messages.len(), 2);
+        assert_eq!(endpoint.pending_messages[1].tag, MSG_CHILD_EXITED);
+
+        let result = step(&mut state, init, Syscall::WaitChild { pid: Some(service) }, 2100);
         assert!(matches!(
             result.result,
-            SyscallResult::Err(KernelError::TooManyCaps)
+            SyscallResult::Reaped { pid, status: ExitStatus::Exited(0) } if pid == service
         ));
     }
 
     #[test]
-    fn test_step_send_with_caps_invalid_cap_slot() {
+    fn test_wait_child_only_reaps_own_children() {
         let mut state = KernelState::new();
-        let sender = state.register_process("sender", 1000);
-        let receiver = state.register_process("receiver", 1000);
+        let (_, _, service) = setup_supervised(&mut state);
+        let stranger = state.register_process("stranger", 1000);
 
-        // Create endpoint
-        state.create_endpoint(receiver);
-        let endpoint_id = EndpointId(1);
+        step(&mut state, service, Syscall::Exit { code: 0 }, 2000);
 
-        let endpoint_cap = Capability {
-            id: state.alloc_cap_id(),
-            object_type: ObjectType::Endpoint,
-            object_id: endpoint_id.0,
-            permissions: Permissions::full(),
-            generation: 0,
-            expires_at: 0,
-        };
-        let endpoint_slot = state.get_cap_space_mut(sender).unwrap().insert(endpoint_cap);
+        let result = step(&mut state, stranger, Syscall::WaitChild { pid: Some(service) }, 2100);
+        assert!(matches!(
+            result.result,
+            SyscallResult::Err(KernelError::ProcessNotFound)
+        ));
+        assert!(state.get_process(service).is_some());
+    }
 
-        // Try to send with invalid cap slot
-        let result = step(
-            &mut state,
-            sender,
-            Syscall::SendWithCaps {
-                endpoint_slot,
-                tag: 42,
-                data: vec![],
-                cap_slots: vec![999], // Invalid slot
-            },
-            2000,
-        );
+    #[test]
+    fn test_fault_reports_reason() {
+        let mut state = KernelState::new();
+        let (init, _, service) = setup_supervised(&mut state);
 
+        let commits = fault(&mut state, service, 7, 2000);
+        assert!(matches!(
+            commits[0].commit_type,
+            CommitType::ProcessFaulted { reason: 7, .. }
+        ));
+
+        let result = step(&mut state, init, Syscall::WaitChild { pid: Some(service) }, 2100);
         assert!(matches!(
             result.result,
-            SyscallResult::Err(KernelError::InvalidCapability)
+            SyscallResult::Reaped {
+                status: ExitStatus::Faulted { reason: 7 },
+                ..
+            }
         ));
     }
 
-    // ========================================================================
-    // Message size limits tests
-    // ========================================================================
-
     #[test]
-    fn test_step_send_message_too_large() {
-        use crate::types::MAX_MESSAGE_SIZE;
+    fn test_orphans_adopted_by_grandparent() {
+        let mut state = KernelState::new();
+        let (init, _, service) = setup_supervised(&mut state);
+        let worker = state.register_child(service, "worker", 1000);
 
+        step(&mut state, service, Syscall::Exit { code: 0 }, 2000);
+        assert_eq!(state.get_process(worker).unwrap().parent, init);
+
+        step(&mut state, init, Syscall::WaitChild { pid: Some(service) }, 2100);
+        step(&mut state, worker, Syscall::Exit { code: 0 }, 2200);
+        let result = step(&mut state, init, Syscall::WaitChild { pid: None }, 2300);
+        assert!(matches!(result.result, SyscallResult::Reaped { pid, .. } if pid == worker));
+    }
+
+    #[test]
+    fn test_death_revokes_caps_to_dead_endpoints() {
         let mut state = KernelState::new();
-        let sender = state.register_process("sender", 1000);
-        let receiver = state.register_process("receiver", 1000);
+        let (init, _, _) = setup_supervised(&mut state);
+        let (server, server_slot) = setup_endpoint_owner(&mut state, "server");
+        if let Some(proc) = state.get_process_mut(server) {
+            proc.parent = init;
+        }
+        let client_slot = grant(&mut state, server, server_slot, init);
 
-        // Create endpoint
-        state.create_endpoint(receiver);
-        let endpoint_id = EndpointId(1);
+        // Endpoints go at death, before the zombie is reaped
+        let result = step(&mut state, server, Syscall::Exit { code: 0 }, 2000);
 
-        let cap = Capability {
-            id: state.alloc_cap_id(),
-            object_type: ObjectType::Endpoint,
-            object_id: endpoint_id.0,
-            permissions: Permissions::full(),
-            generation: 0,
-            expires_at: 0,
-        };
-        let slot = state.get_cap_space_mut(sender).unwrap().insert(cap);
+        assert!(result.commits.iter().any(|c| matches!(
+            c.commit_type,
+            CommitType::CapRevoked { pid, slot } if pid == init.0 && slot == client_slot
+        )));
+        assert!(state.endpoints.values().all(|e| e.owner != server));
+        assert!(crate::invariants::check_all_invariants(&state).is_empty());
+
+        step(&mut state, init, Syscall::WaitChild { pid: Some(server) }, 2100);
+        assert!(state.get_process(server).is_none());
+        assert!(crate::invariants::check_all_invariants(&state).is_empty());
+    }
+
+    #[test]
+    fn test_send_to_unreaped_dead_process_fails() {
+        // Minimal trace from the state-space explorer: create endpoint,
+        // grant it, exit, send. The zombie kept its endpoint and queued
+        // the message, violating endpoint_ownership.
+        let mut state = KernelState::new();
+        let (server, server_slot) = setup_endpoint_owner(&mut state, "server");
+        let (client, _) = setup_endpoint_owner(&mut state, "client");
+        let client_slot = grant(&mut state, server, server_slot, client);
+
+        step(&mut state, server, Syscall::Exit { code: 0 }, 2000);
+        assert_eq!(
+            state.get_process(server).map(|p| p.state),
+            Some(ProcessState::Zombie)
+        );
 
-        // Try to send oversized message
         let result = step(
             &mut state,
-            sender,
+            client,
             Syscall::Send {
-                endpoint_slot: slot,
-                tag: 42,
-                data: vec![0u8; MAX_MESSAGE_SIZE + 1],
+                endpoint_slot: client_slot,
+                tag: 1,
+                data: vec![],
             },
-            2000,
+            2100,
         );
-
         assert!(matches!(
             result.result,
-            SyscallResult::Err(KernelError::MessageTooLarge)
+            SyscallResult::Err(KernelError::InvalidCapability)
         ));
+        assert!(state.endpoints.values().all(|e| e.owner != server));
+        assert!(crate::invariants::check_all_invariants(&state).is_empty());
     }
 
     #[test]
-    fn test_step_send_with_caps_message_too_large() {
-        use crate::types::MAX_MESSAGE_SIZE;
+    fn test_kill_tree_kills_descendants() {
+        let mut state = KernelState::new();
+        let (init, _, service) = setup_supervised(&mut state);
+        let worker = state.register_child(service, "worker", 1000);
+        let sibling = state.register_child(init, "sibling", 1000);
+
+        let result = step(&mut state, init, Syscall::KillTree { target_pid: service }, 2000);
+        assert!(matches!(result.result, SyscallResult::Ok(2)));
+
+        assert!(!state.process_exists(service));
+        assert!(!state.process_exists(worker));
+        assert!(state.process_exists(sibling));
 
+        // Both dead processes are now init's to reap
+        for _ in 0..2 {
+            let result = step(&mut state, init, Syscall::WaitChild { pid: None }, 2100);
+            assert!(matches!(result.result, SyscallResult::Reaped { .. }));
+        }
+    }
+
+    #[test]
+    fn test_kill_tree_requires_ancestor() {
         let mut state = KernelState::new();
-        let sender = state.register_process("sender", 1000);
-        let receiver = state.register_process("receiver", 1000);
+        let (init, _, service) = setup_supervised(&mut state);
+        let worker = state.register_child(service, "worker", 1000);
+        let sibling = state.register_child(init, "sibling", 1000);
+
+        // Neither an unrelated process nor a descendant may kill the tree
+        for by in [sibling, worker] {
+            let result = step(&mut state, by, Syscall::KillTree { target_pid: service }, 2000);
+            assert!(matches!(
+                result.result,
+                SyscallResult::Err(KernelError::PermissionDenied)
+            ));
+            assert!(result.commits.is_empty());
+        }
+        assert!(state.process_exists(service));
+        assert!(state.process_exists(worker));
 
-        // Create endpoint
-        state.create_endpoint(receiver);
-        let endpoint_id = EndpointId(1);
+        // A process may take down its own subtree
+        let result = step(&mut state, service, Syscall::KillTree { target_pid: service }, 2100);
+        assert!(matches!(result.result, SyscallResult::Ok(2)));
+    }
 
-        let cap = Capability {
-            id: state.alloc_cap_id(),
-            object_type: ObjectType::Endpoint,
-            object_id: endpoint_id.0,
-            permissions: Permissions::full(),
-            generation: 0,
-            expires_at: 0,
+    // ========================================================================
+    // Quota tests
+    // ========================================================================
+
+    fn set_quota(
+        state: &mut KernelState,
+        by: ProcessId,
+        target_pid: ProcessId,
+        quota: ResourceQuota,
+    ) -> StepResult {
+        step(state, by, Syscall::SetQuota { target_pid, quota }, 1200)
+    }
+
+    fn assert_quota_exceeded(result: &StepResult, pid: ProcessId, resource: QuotaResource) {
+        assert!(matches!(
+            result.result,
+            SyscallResult::Err(KernelError::ResourceExhausted)
+        ));
+        assert!(result.commits.iter().any(|c| matches!(
+            c.commit_type,
+            CommitType::QuotaExceeded { pid: p, resource: r } if p == pid.0 && r == resource
+        )));
+    }
+
+    #[test]
+    fn test_set_quota_only_by_parent() {
+        let mut state = KernelState::new();
+        let (init, _, service) = setup_supervised(&mut state);
+        let other = state.register_process("other", 1000);
+        let quota = ResourceQuota {
+            max_endpoints: 2,
+            ..Default::default()
         };
-        let slot = state.get_cap_space_mut(sender).unwrap().insert(cap);
 
-        // Try to send oversized message
-        let result = step(
-            &mut state,
-            sender,
-            Syscall::SendWithCaps {
-                endpoint_slot: slot,
-                tag: 42,
-                data: vec![0u8; MAX_MESSAGE_SIZE + 1],
-                cap_slots: vec![],
-            },
-            2000,
-        );
+        let result = set_quota(&mut state, other, service, quota);
+        assert!(matches!(
+            result.result,
+            SyscallResult::Err(KernelError::PermissionDenied)
+        ));
 
+        let result = set_quota(&mut state, init, ProcessId(99), quota);
         assert!(matches!(
             result.result,
-            SyscallResult::Err(KernelError::MessageTooLarge)
+            SyscallResult::Err(KernelError::ProcessNotFound)
+        ));
+
+        let result = set_quota(&mut state, init, service, quota);
+        assert!(matches!(result.result, SyscallResult::Ok(0)));
+        assert!(matches!(
+            result.commits[0].commit_type,
+            CommitType::QuotaSet { pid, by, .. } if pid == service.0 && by == init.0
         ));
+        assert_eq!(state.get_process(service).unwrap().quota, quota);
     }
 
-    // ========================================================================
-    // Call syscall tests
-    // ========================================================================
+    #[test]
+    fn test_set_quota_cannot_exceed_callers() {
+        let mut state = KernelState::new();
+        let (init, _, service) = setup_supervised(&mut state);
+        let quota = ResourceQuota {
+            max_endpoints: 2,
+            ..Default::default()
+        };
+        set_quota(&mut state, init, service, quota);
+        let worker = state.register_child(service, "worker", 1000);
+
+        // Children inherit the spawner's quota
+        assert_eq!(state.get_process(worker).unwrap().quota, quota);
+
+        let result = set_quota(&mut state, service, worker, ResourceQuota::default());
+        assert!(matches!(
+            result.result,
+            SyscallResult::Err(KernelError::PermissionDenied)
+        ));
+
+        let tighter = ResourceQuota {
+            max_endpoints: 1,
+            ..Default::default()
+        };
+        let result = set_quota(&mut state, service, worker, tighter);
+        assert!(matches!(result.result, SyscallResult::Ok(0)));
+    }
 
     #[test]
-    fn test_step_call_sends_and_blocks() {
+    fn test_endpoint_quota() {
         let mut state = KernelState::new();
-        let caller = state.register_process("caller", 1000);
-        let receiver = state.register_process("receiver", 1000);
+        let (init, _, service) = setup_supervised(&mut state);
+        let quota = ResourceQuota {
+            max_endpoints: 1,
+            ..Default::default()
+        };
+        set_quota(&mut state, init, service, quota);
 
-        // Create endpoint
-        state.create_endpoint(receiver);
-        let endpoint_id = EndpointId(1);
+        let result = step(&mut state, service, Syscall::CreateEndpoint, 2000);
+        assert!(matches!(result.result, SyscallResult::Ok(_)));
 
-        let cap = Capability {
-            id: state.alloc_cap_id(),
-            object_type: ObjectType::Endpoint,
-            object_id: endpoint_id.0,
-            permissions: Permissions::full(),
-            generation: 0,
-            expires_at: 0,
+        let result = step(&mut state, service, Syscall::CreateEndpoint, 2100);
+        assert_quota_exceeded(&result, service, QuotaResource::Endpoints);
+        assert_eq!(state.quota_usage(service, QuotaResource::Endpoints), 1);
+    }
+
+    #[test]
+    fn test_cap_slot_quota_applies_to_grant_recipient() {
+        let mut state = KernelState::new();
+        let (init, init_slot, service) = setup_supervised(&mut state);
+        let quota = ResourceQuota {
+            max_cap_slots: 1,
+            ..Default::default()
         };
-        let slot = state.get_cap_space_mut(caller).unwrap().insert(cap);
+        set_quota(&mut state, init, service, quota);
 
+        grant(&mut state, init, init_slot, service);
         let result = step(
             &mut state,
-            caller,
-            Syscall::Call {
-                endpoint_slot: slot,
-                tag: 42,
-                data: vec![1, 2, 3],
+            init,
+            Syscall::CapGrant {
+                from_slot: init_slot,
+                to_pid: service,
+                permissions: Permissions::full(),
             },
             2000,
         );
+        assert_quota_exceeded(&result, service, QuotaResource::CapSlots);
 
-        // Call should return WouldBlock
-        assert!(matches!(result.result, SyscallResult::WouldBlock));
+        // Deriving needs a slot too
+        let result = step(
+            &mut state,
+            service,
+            Syscall::CapDerive {
+                slot: 0,
+                new_permissions: Permissions::read_only(),
+            },
+            2100,
+        );
+        assert_quota_exceeded(&result, service, QuotaResource::CapSlots);
+    }
 
-        // But message should be sent
-        let endpoint = state.get_endpoint(endpoint_id).unwrap();
-        assert_eq!(endpoint.pending_messages.len(), 1);
+    #[test]
+    fn test_queued_bytes_quota() {
+        let mut state = KernelState::new();
+        let (init, init_slot, service) = setup_supervised(&mut state);
+        let slot = grant(&mut state, init, init_slot, service);
+        let quota = ResourceQuota {
+            max_queued_bytes: 8,
+            ..Default::default()
+        };
+        set_quota(&mut state, init, service, quota);
 
-        // Should generate commit for the send
-        assert!(!result.commits.is_empty());
-    }
+        let send = |data: Vec<u8>| Syscall::Send {
+            endpoint_slot: slot,
+            tag: 1,
+            data,
+        };
+        let result = step(&mut state, service, send(vec![0; 8]), 2000);
+        assert!(matches!(result.result, SyscallResult::Ok(0)));
 
-    // ========================================================================
-    // CapRevoke tests
-    // ========================================================================
+        let result = step(&mut state, service, send(vec![0; 1]), 2100);
+        assert_quota_exceeded(&result, service, QuotaResource::QueuedBytes);
+
+        // Draining the queue frees the quota
+        step(&mut state, init, Syscall::Receive { endpoint_slot: init_slot }, 2200);
+        let result = step(&mut state, service, send(vec![0; 1]), 2300);
+        assert!(matches!(result.result, SyscallResult::Ok(0)));
+    }
 
     #[test]
-    fn test_step_cap_revoke() {
+    fn test_memory_quota_counts_owned_regions() {
         let mut state = KernelState::new();
-        let pid = state.register_process("test", 1000);
+        let (init, _, service) = setup_supervised(&mut state);
+        let quota = ResourceQuota {
+            max_memory: 2 * PAGE_SIZE,
+            ..Default::default()
+        };
+        set_quota(&mut state, init, service, quota);
 
-        // Create endpoint and capability
-        state.create_endpoint(pid);
-        let endpoint_id = EndpointId(1);
+        create_region(&mut state, service, 1);
+        let result = step(&mut state, service, Syscall::CreateRegion { size: PAGE_SIZE + 1 }, 2000);
+        assert_quota_exceeded(&result, service, QuotaResource::Memory);
 
-        let cap = Capability {
-            id: state.alloc_cap_id(),
-            object_type: ObjectType::Endpoint,
-            object_id: endpoint_id.0,
-            permissions: Permissions::full(),
-            generation: 0,
-            expires_at: 0,
-        };
-        let slot = state.get_cap_space_mut(pid).unwrap().insert(cap);
+        create_region(&mut state, service, PAGE_SIZE);
+        assert_eq!(state.quota_usage(service, QuotaResource::Memory), 2 * PAGE_SIZE as u64);
+    }
 
-        assert!(state.get_cap_space(pid).unwrap().contains(slot));
+    #[test]
+    fn test_syscall_rate_quota() {
+        let mut state = KernelState::new();
+        let (init, _, service) = setup_supervised(&mut state);
+        let quota = ResourceQuota {
+            max_syscalls_per_sec: 2,
+            ..Default::default()
+        };
+        set_quota(&mut state, init, service, quota);
 
-        let result = step(&mut state, pid, Syscall::CapRevoke { slot }, 2000);
+        let second = 1_000_000_000;
+        for i in 0..2 {
+            let result = step(&mut state, service, Syscall::Yield, second + i);
+            assert!(matches!(result.result, SyscallResult::Ok(0)));
+        }
+        let result = step(&mut state, service, Syscall::Yield, second + 2);
+        assert_quota_exceeded(&result, service, QuotaResource::SyscallRate);
 
+        // The next window starts fresh
+        let result = step(&mut state, service, Syscall::Yield, 2 * second);
         assert!(matches!(result.result, SyscallResult::Ok(0)));
-        assert!(!state.get_cap_space(pid).unwrap().contains(slot));
 
-        // Should generate commit
-        assert_eq!(result.commits.len(), 1);
-        match &result.commits[0].commit_type {
-            CommitType::CapRevoked { pid: revoke_pid, slot: revoke_slot } => {
-                assert_eq!(*revoke_pid, pid.0);
-                assert_eq!(*revoke_slot, slot);
-            }
-            _ => panic!("Expected CapRevoked commit"),
-        }
+        // Exit is never rate limited
+        step(&mut state, service, Syscall::Yield, 2 * second + 1);
+        let result = step(&mut state, service, Syscall::Exit { code: 0 }, 2 * second + 2);
+        assert!(matches!(result.result, SyscallResult::Ok(0)));
     }
 
     #[test]
-    fn test_step_cap_revoke_invalid_slot() {
+    fn test_list_processes_reports_quota() {
         let mut state = KernelState::new();
-        let pid = state.register_process("test", 1000);
-
-        let result = step(&mut state, pid, Syscall::CapRevoke { slot: 999 }, 2000);
+        let (init, _, service) = setup_supervised(&mut state);
+        let quota = ResourceQuota {
+            max_cap_slots: 16,
+            ..Default::default()
+        };
+        set_quota(&mut state, init, service, quota);
 
-        assert!(matches!(
-            result.result,
-            SyscallResult::Err(KernelError::InvalidCapability)
-        ));
+        match step(&mut state, init, Syscall::ListProcesses, 2000).result {
+            SyscallResult::ProcessList(procs) => {
+                let entry = procs.iter().find(|(p, _, _, _)| *p == service).unwrap();
+                assert_eq!(entry.3, quota);
+            }
+            _ => panic!("Expected ProcessList"),
+        }
     }
 
     // ========================================================================
@@ -1640,6 +5500,7 @@ mod tests {
             permissions: Permissions::read_only(), // No grant permission
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
         let slot = state.get_cap_space_mut(pid).unwrap().insert(cap);
 
@@ -1689,6 +5550,7 @@ mod tests {
             permissions: Permissions { read: true, write: false, grant: true },
             generation: 5,
             expires_at: 9999,
+            badge: 0,
         };
         let slot = state.get_cap_space_mut(pid).unwrap().insert(cap);
 
@@ -1744,6 +5606,7 @@ mod tests {
             permissions: Permissions::full(),
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
         let slot = state.get_cap_space_mut(pid).unwrap().insert(cap);
 
@@ -1800,6 +5663,7 @@ mod tests {
             permissions: Permissions::read_only(),
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
         let slot = state.get_cap_space_mut(pid).unwrap().insert(cap);
 
@@ -1858,6 +5722,7 @@ mod tests {
             permissions: Permissions::full(),
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
         let cap2 = Capability {
             id: 2,
@@ -1866,6 +5731,7 @@ mod tests {
             permissions: Permissions::read_only(),
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
         state.get_cap_space_mut(pid).unwrap().insert(cap1);
         state.get_cap_space_mut(pid).unwrap().insert(cap2);
@@ -1902,6 +5768,7 @@ mod tests {
             permissions: Permissions::full(),
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
         let slot = state.get_cap_space_mut(pid).unwrap().insert(cap);
 
@@ -1947,6 +5814,7 @@ mod tests {
             permissions: Permissions::write_only(),
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
         let sender_slot = state.get_cap_space_mut(sender).unwrap().insert(sender_cap);
 
@@ -1957,6 +5825,7 @@ mod tests {
             permissions: Permissions::read_only(),
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
         let receiver_slot = state.get_cap_space_mut(receiver).unwrap().insert(receiver_cap);
 
@@ -2009,6 +5878,7 @@ mod tests {
             permissions: Permissions { read: true, write: true, grant: false },
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
         let slot = state.get_cap_space_mut(granter).unwrap().insert(cap);
 
@@ -2045,6 +5915,7 @@ mod tests {
             permissions: Permissions::full(),
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
         let slot = state.get_cap_space_mut(granter).unwrap().insert(cap);
 
@@ -2118,6 +5989,7 @@ mod tests {
             permissions: Permissions::full(),
             generation: 0,
             expires_at: 1000,
+            badge: 0,
         };
         let slot = state.get_cap_space_mut(sender).unwrap().insert(cap);
 
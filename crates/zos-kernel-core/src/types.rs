@@ -3,10 +3,11 @@
 //! This module contains the fundamental types used throughout the kernel core.
 //! All types here are pure data - no behavior that depends on HAL.
 
-use alloc::collections::VecDeque;
+use alloc::collections::{BTreeMap, VecDeque};
 use alloc::string::String;
 use alloc::vec::Vec;
 use serde::{Deserialize, Serialize};
+use zos_ipc::exit_kind;
 
 /// Capability slot index
 pub type CapSlot = u32;
@@ -19,6 +20,14 @@ pub struct ProcessId(pub u64);
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct EndpointId(pub u64);
 
+/// Notification object identifier
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct NotificationId(pub u64);
+
+/// Shared memory region identifier
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct RegionId(pub u64);
+
 /// Process state
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ProcessState {
@@ -30,14 +39,43 @@ pub enum ProcessState {
     Zombie,
 }
 
+/// How a process died
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExitStatus {
+    /// Exited voluntarily with a code
+    Exited(i32),
+    /// Killed by another process
+    Killed { by: ProcessId },
+    /// Faulted (trap, out of fuel, ...) with a runtime-defined reason
+    Faulted { reason: u32 },
+}
+
+impl ExitStatus {
+    /// Encode as an `exit_kind` code and value
+    pub fn to_kind_value(self) -> (u8, u64) {
+        match self {
+            ExitStatus::Exited(code) => (exit_kind::EXITED, code as i64 as u64),
+            ExitStatus::Killed { by } => (exit_kind::KILLED, by.0),
+            ExitStatus::Faulted { reason } => (exit_kind::FAULTED, reason as u64),
+        }
+    }
+}
+
 /// Process descriptor
+#[derive(Clone)]
 pub struct Process {
     /// Process ID
     pub pid: ProcessId,
+    /// Parent (spawner), or `ProcessId(0)` for processes started by the kernel
+    pub parent: ProcessId,
     /// Process name
     pub name: String,
     /// Current state
     pub state: ProcessState,
+    /// Set when the process dies; read when it is reaped
+    pub exit_status: Option<ExitStatus>,
+    /// Limits enforced by the kernel (inherited from the parent)
+    pub quota: ResourceQuota,
     /// Detailed metrics for this process
     pub metrics: ProcessMetrics,
 }
@@ -61,6 +99,76 @@ pub struct ProcessMetrics {
     pub last_active_ns: u64,
     /// Process start time (nanos since boot)
     pub start_time_ns: u64,
+    /// Start of the current one-second syscall rate window
+    pub rate_window_start_ns: u64,
+    /// Syscalls made in the current rate window
+    pub rate_window_count: u64,
+}
+
+/// Length of a syscall rate window (1 second)
+pub const RATE_WINDOW_NS: u64 = 1_000_000_000;
+
+/// Per-process resource limits. A limit of 0 means unlimited.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResourceQuota {
+    /// Process memory plus owned shared regions (bytes)
+    pub max_memory: usize,
+    /// Endpoints owned
+    pub max_endpoints: usize,
+    /// Occupied capability slots
+    pub max_cap_slots: usize,
+    /// Bytes sent by the process that are still queued
+    pub max_queued_bytes: usize,
+    /// Syscalls per second
+    pub max_syscalls_per_sec: u64,
+}
+
+impl ResourceQuota {
+    /// Get the limit for a resource (0 = unlimited)
+    pub fn limit(&self, resource: QuotaResource) -> u64 {
+        match resource {
+            QuotaResource::Memory => self.max_memory as u64,
+            QuotaResource::Endpoints => self.max_endpoints as u64,
+            QuotaResource::CapSlots => self.max_cap_slots as u64,
+            QuotaResource::QueuedBytes => self.max_queued_bytes as u64,
+            QuotaResource::SyscallRate => self.max_syscalls_per_sec,
+        }
+    }
+
+    /// Check if every limit is at least as strict as the one in `other`
+    pub fn is_within(&self, other: &Self) -> bool {
+        QuotaResource::ALL.iter().all(|&r| {
+            let (mine, theirs) = (self.limit(r), other.limit(r));
+            theirs == 0 || (mine != 0 && mine <= theirs)
+        })
+    }
+}
+
+/// A resource limited by [`ResourceQuota`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[repr(u8)]
+pub enum QuotaResource {
+    /// `max_memory`
+    Memory = 1,
+    /// `max_endpoints`
+    Endpoints = 2,
+    /// `max_cap_slots`
+    CapSlots = 3,
+    /// `max_queued_bytes`
+    QueuedBytes = 4,
+    /// `max_syscalls_per_sec`
+    SyscallRate = 5,
+}
+
+impl QuotaResource {
+    /// Every quota resource
+    pub const ALL: [QuotaResource; 5] = [
+        QuotaResource::Memory,
+        QuotaResource::Endpoints,
+        QuotaResource::CapSlots,
+        QuotaResource::QueuedBytes,
+        QuotaResource::SyscallRate,
+    ];
 }
 
 /// Per-endpoint tracking
@@ -74,6 +182,8 @@ pub struct EndpointMetrics {
     pub total_bytes: u64,
     /// High water mark (max queue depth seen)
     pub queue_high_water: usize,
+    /// Messages rejected because the queue was full
+    pub dropped_messages: u64,
 }
 
 /// System-wide metrics
@@ -170,7 +280,7 @@ pub enum ObjectType {
     Endpoint = 1,
     /// Another process
     Process = 2,
-    /// Memory region (for VMM)
+    /// Shared memory region
     Memory = 3,
     /// IRQ handler
     Irq = 4,
@@ -178,6 +288,10 @@ pub enum ObjectType {
     IoPort = 5,
     /// Console/debug output
     Console = 6,
+    /// Notification (signal word)
+    Notification = 12,
+    /// One-shot reply right for an outstanding Call
+    Reply = 13,
 }
 
 impl ObjectType {
@@ -190,6 +304,8 @@ impl ObjectType {
             4 => Some(ObjectType::Irq),
             5 => Some(ObjectType::IoPort),
             6 => Some(ObjectType::Console),
+            12 => Some(ObjectType::Notification),
+            13 => Some(ObjectType::Reply),
             _ => None,
         }
     }
@@ -205,7 +321,30 @@ pub const MAX_MESSAGE_SIZE: usize = 4096;
 /// Maximum capabilities that can be transferred in one message
 pub const MAX_CAPS_PER_MESSAGE: usize = 4;
 
+/// Maximum endpoints a single `ReceiveTimeout` can wait on
+pub const MAX_WAIT_SLOTS: usize = zos_ipc::wait::MAX_WAIT_SLOTS;
+
+/// Queue capacity for endpoints created without an explicit capacity
+pub const DEFAULT_QUEUE_CAPACITY: usize = 64;
+
+/// Largest queue capacity a process may request
+pub const MAX_QUEUE_CAPACITY: usize = 1024;
+
+/// Extra queue slots only kernel notifications may use, so a full queue
+/// does not hide a child's death or a revocation from its owner
+pub const KERNEL_QUEUE_RESERVE: usize = 16;
+
+/// What `Send` does when the endpoint queue is full
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OverflowPolicy {
+    /// Fail the send with `QueueFull` and count the drop
+    Reject,
+    /// Leave the message with the sender, who blocks and retries
+    Block,
+}
+
 /// IPC endpoint
+#[derive(Clone)]
 pub struct Endpoint {
     /// Endpoint ID
     pub id: EndpointId,
@@ -213,23 +352,73 @@ pub struct Endpoint {
     pub owner: ProcessId,
     /// Queue of pending messages
     pub pending_messages: VecDeque<Message>,
+    /// Maximum number of pending messages
+    pub capacity: usize,
+    /// Behavior when the queue is full
+    pub policy: OverflowPolicy,
     /// Metrics
     pub metrics: EndpointMetrics,
 }
 
 impl Endpoint {
-    /// Create a new endpoint
+    /// Create a new endpoint with the default capacity and `Reject` policy
     pub fn new(id: EndpointId, owner: ProcessId) -> Self {
+        Self::with_capacity(id, owner, DEFAULT_QUEUE_CAPACITY, OverflowPolicy::Reject)
+    }
+
+    /// Create a new endpoint with an explicit capacity and overflow policy
+    pub fn with_capacity(
+        id: EndpointId,
+        owner: ProcessId,
+        capacity: usize,
+        policy: OverflowPolicy,
+    ) -> Self {
         Self {
             id,
             owner,
             pending_messages: VecDeque::new(),
+            capacity,
+            policy,
             metrics: EndpointMetrics::default(),
         }
     }
 
-    /// Enqueue a message
-    pub fn enqueue(&mut self, msg: Message) {
+    /// Check if the queue has reached its capacity
+    pub fn is_full(&self) -> bool {
+        self.pending_messages.len() >= self.capacity
+    }
+
+    /// Enqueue a message.
+    ///
+    /// If the queue is full the message is handed back and, under the
+    /// `Reject` policy, counted as dropped.
+    pub fn enqueue(&mut self, msg: Message) -> Result<(), Message> {
+        if self.is_full() {
+            if self.policy == OverflowPolicy::Reject {
+                self.metrics.dropped_messages += 1;
+            }
+            return Err(msg);
+        }
+        self.push(msg);
+        Ok(())
+    }
+
+    /// Enqueue a kernel notification.
+    ///
+    /// Kernel messages may also use `KERNEL_QUEUE_RESERVE` slots past the
+    /// capacity. Once those are taken too the message is handed back and
+    /// counted as dropped, whatever the policy.
+    pub fn enqueue_kernel(&mut self, msg: Message) -> Result<(), Message> {
+        if self.pending_messages.len() >= self.capacity + KERNEL_QUEUE_RESERVE {
+            self.metrics.dropped_messages += 1;
+            return Err(msg);
+        }
+        self.push(msg);
+        Ok(())
+    }
+
+    /// Append a message and update the queue metrics
+    fn push(&mut self, msg: Message) {
         let data_len = msg.data.len() as u64;
         self.pending_messages.push_back(msg);
         self.metrics.queue_depth = self.pending_messages.len();
@@ -253,6 +442,8 @@ impl Endpoint {
 pub struct Message {
     /// Sender process ID
     pub sender: ProcessId,
+    /// Badge of the capability the message was sent through (0 = unbadged)
+    pub badge: u64,
     /// Message tag (application-defined)
     pub tag: u32,
     /// Message payload
@@ -261,6 +452,169 @@ pub struct Message {
     pub caps: Vec<TransferredCap>,
 }
 
+// ============================================================================
+// Call Types
+// ============================================================================
+
+/// Progress of a process's outstanding `Call`.
+///
+/// While `Pending`, the server holds a reply capability naming the caller.
+/// The caller's retried `Call` collects the outcome and clears the entry.
+#[derive(Clone, Debug)]
+pub enum CallState {
+    /// Waiting for `server` to reply
+    Pending { server: ProcessId },
+    /// The server replied with this message
+    Replied(Message),
+    /// The server died or dropped the reply capability
+    Aborted,
+}
+
+// ============================================================================
+// Notification Types
+// ============================================================================
+
+/// Notification object - a word of signal bits.
+///
+/// Signals are OR-ed into the word, so repeated signals coalesce and never
+/// allocate. A wait or poll consumes the whole word at once.
+#[derive(Clone, Debug)]
+pub struct Notification {
+    /// Notification ID
+    pub id: NotificationId,
+    /// Owner process
+    pub owner: ProcessId,
+    /// Pending signal bits
+    pub bits: u64,
+}
+
+impl Notification {
+    /// Create a new notification with no pending signals
+    pub fn new(id: NotificationId, owner: ProcessId) -> Self {
+        Self { id, owner, bits: 0 }
+    }
+
+    /// OR `bits` into the pending word
+    pub fn signal(&mut self, bits: u64) {
+        self.bits |= bits;
+    }
+
+    /// Take and clear the pending word
+    pub fn take(&mut self) -> u64 {
+        core::mem::take(&mut self.bits)
+    }
+}
+
+// ============================================================================
+// Shared Memory Types
+// ============================================================================
+
+/// Page granularity for shared regions
+pub const PAGE_SIZE: usize = 4096;
+
+/// Maximum size of a single shared region (16 MiB, the VFS content limit)
+pub const MAX_REGION_SIZE: usize = 16 * 1024 * 1024;
+
+/// Shared memory region - bulk data passed by capability instead of copying.
+///
+/// The region itself is just a size; the HAL backs it with WASM linear
+/// memory or physical pages. `mappings` records which processes currently
+/// have it mapped and whether that mapping is writable.
+#[derive(Clone, Debug)]
+pub struct MemoryRegion {
+    /// Region ID
+    pub id: RegionId,
+    /// Process that allocated the region
+    pub owner: ProcessId,
+    /// Size in bytes (a multiple of `PAGE_SIZE`)
+    pub size: usize,
+    /// Current mappings: process -> writable
+    pub mappings: BTreeMap<ProcessId, bool>,
+}
+
+impl MemoryRegion {
+    /// Create an unmapped region, rounding `size` up to whole pages
+    pub fn new(id: RegionId, owner: ProcessId, size: usize) -> Self {
+        Self {
+            id,
+            owner,
+            size: size.div_ceil(PAGE_SIZE) * PAGE_SIZE,
+            mappings: BTreeMap::new(),
+        }
+    }
+
+    /// Map the region into `pid`, replacing any existing mapping
+    pub fn map(&mut self, pid: ProcessId, writable: bool) {
+        self.mappings.insert(pid, writable);
+    }
+
+    /// Unmap the region from `pid`. Returns false if it was not mapped.
+    pub fn unmap(&mut self, pid: ProcessId) -> bool {
+        self.mappings.remove(&pid).is_some()
+    }
+}
+
+// ============================================================================
+// Device Types
+// ============================================================================
+
+/// A contiguous range of x86 I/O ports.
+///
+/// Packed into a capability's `object_id` as `(base << 16) | len`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IoPortRange {
+    /// First port in the range
+    pub base: u16,
+    /// Number of ports
+    pub len: u16,
+}
+
+impl IoPortRange {
+    /// Encode as a capability object ID
+    pub fn to_object_id(self) -> u64 {
+        ((self.base as u64) << 16) | self.len as u64
+    }
+
+    /// Decode from a capability object ID
+    pub fn from_object_id(object_id: u64) -> Self {
+        Self {
+            base: (object_id >> 16) as u16,
+            len: object_id as u16,
+        }
+    }
+
+    /// Check that a `width`-byte access at `port` lies inside the range
+    pub fn contains(self, port: u16, width: u8) -> bool {
+        let start = self.base as u32;
+        let end = start + self.len as u32;
+        let port = port as u32;
+        port >= start && port + width as u32 <= end
+    }
+}
+
+/// Where a bound IRQ line is delivered
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IrqTarget {
+    /// OR `bits` into a notification
+    Notification { id: NotificationId, bits: u64 },
+    /// Queue a `MSG_IRQ` message on an endpoint
+    Endpoint(EndpointId),
+}
+
+/// A user-space driver's binding of an IRQ line.
+///
+/// Delivery masks the line; it is unmasked when the driver acknowledges
+/// the interrupt, so a level-triggered device cannot flood the target.
+#[derive(Clone, Copy, Debug)]
+pub struct IrqBinding {
+    /// Driver process that bound the line
+    pub owner: ProcessId,
+    /// Delivery target
+    pub target: IrqTarget,
+    /// Line is masked awaiting acknowledgement
+    pub masked: bool,
+}
+
 /// A capability being transferred via IPC
 #[derive(Clone, Debug)]
 pub struct TransferredCap {
@@ -293,6 +647,8 @@ pub struct CapInfo {
     pub generation: u32,
     /// Expiration timestamp
     pub expires_at: u64,
+    /// Badge (0 = unbadged)
+    pub badge: u64,
 }
 
 /// Endpoint info for listing
@@ -304,6 +660,8 @@ pub struct EndpointInfo {
     pub owner: ProcessId,
     /// Queue depth
     pub queue_depth: usize,
+    /// Queue capacity
+    pub capacity: usize,
 }
 
 /// Detailed endpoint info
@@ -358,6 +716,42 @@ impl RevokeNotification {
             reason: 0,
         }
     }
+
+    /// Encode as a `MSG_CAP_REVOKED` payload.
+    ///
+    /// Layout: `[slot: u32, object_type: u8, object_id: u64, reason: u8]`,
+    /// little-endian.
+    pub fn to_payload(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(14);
+        data.extend_from_slice(&self.slot.to_le_bytes());
+        data.push(self.object_type);
+        data.extend_from_slice(&self.object_id.to_le_bytes());
+        data.push(self.reason);
+        data
+    }
+}
+
+/// Child death notification data
+#[derive(Clone, Debug)]
+pub struct ChildExitNotification {
+    /// Child that died
+    pub pid: ProcessId,
+    /// How it died
+    pub status: ExitStatus,
+}
+
+impl ChildExitNotification {
+    /// Encode as a `MSG_CHILD_EXITED` payload.
+    ///
+    /// Layout: `[pid: u64, kind: u8, value: u64]`, little-endian.
+    pub fn to_payload(&self) -> Vec<u8> {
+        let (kind, value) = self.status.to_kind_value();
+        let mut data = Vec::with_capacity(17);
+        data.extend_from_slice(&self.pid.0.to_le_bytes());
+        data.push(kind);
+        data.extend_from_slice(&value.to_le_bytes());
+        data
+    }
 }
 
 #[cfg(test)]
@@ -492,6 +886,8 @@ mod tests {
         assert_eq!(ObjectType::from_u8(4), Some(ObjectType::Irq));
         assert_eq!(ObjectType::from_u8(5), Some(ObjectType::IoPort));
         assert_eq!(ObjectType::from_u8(6), Some(ObjectType::Console));
+        assert_eq!(ObjectType::from_u8(12), Some(ObjectType::Notification));
+        assert_eq!(ObjectType::from_u8(13), Some(ObjectType::Reply));
     }
 
     #[test]
@@ -511,6 +907,8 @@ mod tests {
         assert_eq!(ObjectType::Irq as u8, 4);
         assert_eq!(ObjectType::IoPort as u8, 5);
         assert_eq!(ObjectType::Console as u8, 6);
+        assert_eq!(ObjectType::Notification as u8, zos_ipc::ObjectType::Notification as u8);
+        assert_eq!(ObjectType::Reply as u8, zos_ipc::ObjectType::Reply as u8);
     }
 
     // ========================================================================
@@ -527,20 +925,22 @@ mod tests {
         // Enqueue a message
         endpoint.enqueue(Message {
             sender: ProcessId(1),
+            badge: 0,
             tag: 0,
             data: vec![],
             caps: vec![],
-        });
+        }).unwrap();
         assert_eq!(endpoint.metrics.queue_depth, 1);
         assert_eq!(endpoint.metrics.queue_high_water, 1);
 
         // Enqueue another
         endpoint.enqueue(Message {
             sender: ProcessId(1),
+            badge: 0,
             tag: 0,
             data: vec![],
             caps: vec![],
-        });
+        }).unwrap();
         assert_eq!(endpoint.metrics.queue_depth, 2);
         assert_eq!(endpoint.metrics.queue_high_water, 2);
 
@@ -565,20 +965,22 @@ mod tests {
         // Enqueue message with 10 bytes
         endpoint.enqueue(Message {
             sender: ProcessId(1),
+            badge: 0,
             tag: 0,
             data: vec![0u8; 10],
             caps: vec![],
-        });
+        }).unwrap();
         assert_eq!(endpoint.metrics.total_messages, 1);
         assert_eq!(endpoint.metrics.total_bytes, 10);
 
         // Enqueue message with 5 bytes
         endpoint.enqueue(Message {
             sender: ProcessId(1),
+            badge: 0,
             tag: 0,
             data: vec![0u8; 5],
             caps: vec![],
-        });
+        }).unwrap();
         assert_eq!(endpoint.metrics.total_messages, 2);
         assert_eq!(endpoint.metrics.total_bytes, 15);
 
@@ -596,19 +998,21 @@ mod tests {
 
         let msg1 = Message {
             sender: ProcessId(2),
+            badge: 0,
             tag: 100,
             data: vec![1, 2, 3],
             caps: vec![],
         };
         let msg2 = Message {
             sender: ProcessId(3),
+            badge: 0,
             tag: 200,
             data: vec![4, 5],
             caps: vec![],
         };
 
-        endpoint.enqueue(msg1);
-        endpoint.enqueue(msg2);
+        endpoint.enqueue(msg1).unwrap();
+        endpoint.enqueue(msg2).unwrap();
 
         assert_eq!(endpoint.pending_messages.len(), 2);
 
@@ -625,6 +1029,73 @@ mod tests {
         assert!(endpoint.dequeue().is_none());
     }
 
+    #[test]
+    fn test_endpoint_enqueue_respects_capacity() {
+        let mut endpoint =
+            Endpoint::with_capacity(EndpointId(1), ProcessId(1), 2, OverflowPolicy::Reject);
+        let msg = |tag| Message {
+            sender: ProcessId(2),
+            badge: 0,
+            tag,
+            data: vec![0; 8],
+            caps: vec![],
+        };
+
+        assert!(endpoint.enqueue(msg(1)).is_ok());
+        assert!(endpoint.enqueue(msg(2)).is_ok());
+        assert!(endpoint.is_full());
+
+        let rejected = endpoint.enqueue(msg(3)).unwrap_err();
+        assert_eq!(rejected.tag, 3);
+        assert_eq!(endpoint.pending_messages.len(), 2);
+        assert_eq!(endpoint.metrics.dropped_messages, 1);
+        assert_eq!(endpoint.metrics.total_messages, 2);
+        assert_eq!(endpoint.metrics.queue_high_water, 2);
+
+        // Draining makes room again
+        endpoint.dequeue();
+        assert!(endpoint.enqueue(msg(4)).is_ok());
+    }
+
+    #[test]
+    fn test_endpoint_block_policy_does_not_count_drops() {
+        let mut endpoint =
+            Endpoint::with_capacity(EndpointId(1), ProcessId(1), 1, OverflowPolicy::Block);
+        let msg = Message {
+            sender: ProcessId(2),
+            badge: 0,
+            tag: 0,
+            data: vec![],
+            caps: vec![],
+        };
+
+        endpoint.enqueue(msg.clone()).unwrap();
+        assert!(endpoint.enqueue(msg).is_err());
+        assert_eq!(endpoint.metrics.dropped_messages, 0);
+    }
+
+    #[test]
+    fn test_endpoint_kernel_messages_use_reserve() {
+        let mut endpoint =
+            Endpoint::with_capacity(EndpointId(1), ProcessId(1), 1, OverflowPolicy::Block);
+        let msg = Message {
+            sender: ProcessId(0),
+            badge: 0,
+            tag: 0,
+            data: vec![],
+            caps: vec![],
+        };
+
+        endpoint.enqueue(msg.clone()).unwrap();
+        assert!(endpoint.enqueue(msg.clone()).is_err());
+        for _ in 0..KERNEL_QUEUE_RESERVE {
+            endpoint.enqueue_kernel(msg.clone()).unwrap();
+        }
+        assert!(endpoint.enqueue_kernel(msg).is_err());
+        assert_eq!(endpoint.pending_messages.len(), 1 + KERNEL_QUEUE_RESERVE);
+        assert_eq!(endpoint.metrics.dropped_messages, 1);
+    }
+
     // ========================================================================
     // RevokeNotification tests
     // ========================================================================
@@ -640,6 +1111,136 @@ mod tests {
         assert_eq!(notification.reason, 0);
     }
 
+    #[test]
+    fn test_revoke_notification_payload_layout() {
+        let notification = RevokeNotification {
+            pid: ProcessId(7),
+            slot: 3,
+            object_type: ObjectType::Endpoint as u8,
+            object_id: 0x0102_0304_0506_0708,
+            reason: 1,
+        };
+
+        let payload = notification.to_payload();
+        assert_eq!(payload.len(), 14);
+        assert_eq!(&payload[0..4], &3u32.to_le_bytes());
+        assert_eq!(payload[4], ObjectType::Endpoint as u8);
+        assert_eq!(&payload[5..13], &0x0102_0304_0506_0708u64.to_le_bytes());
+        assert_eq!(payload[13], 1);
+    }
+
+    // ========================================================================
+    // MemoryRegion tests
+    // ========================================================================
+
+    #[test]
+    fn test_memory_region_rounds_to_pages() {
+        let region = MemoryRegion::new(RegionId(1), ProcessId(1), PAGE_SIZE + 1);
+        assert_eq!(region.size, 2 * PAGE_SIZE);
+
+        let region = MemoryRegion::new(RegionId(2), ProcessId(1), PAGE_SIZE);
+        assert_eq!(region.size, PAGE_SIZE);
+    }
+
+    #[test]
+    fn test_memory_region_map_unmap() {
+        let mut region = MemoryRegion::new(RegionId(1), ProcessId(1), PAGE_SIZE);
+
+        region.map(ProcessId(2), false);
+        region.map(ProcessId(2), true);
+        assert_eq!(region.mappings.get(&ProcessId(2)), Some(&true));
+
+        assert!(region.unmap(ProcessId(2)));
+        assert!(!region.unmap(ProcessId(2)));
+        assert!(region.mappings.is_empty());
+    }
+
+    // ========================================================================
+    // IoPortRange tests
+    // ========================================================================
+
+    #[test]
+    fn test_io_port_range_object_id_roundtrip() {
+        let range = IoPortRange { base: 0x3F8, len: 8 };
+        assert_eq!(IoPortRange::from_object_id(range.to_object_id()), range);
+    }
+
+    #[test]
+    fn test_io_port_range_contains() {
+        let range = IoPortRange { base: 0x3F8, len: 8 };
+
+        assert!(range.contains(0x3F8, 1));
+        assert!(range.contains(0x3FC, 4));
+        assert!(!range.contains(0x3FD, 4));
+        assert!(!range.contains(0x3F7, 1));
+        assert!(!range.contains(0xFFFF, 4));
+    }
+
+    // ========================================================================
+    // ChildExitNotification tests
+    // ========================================================================
+
+    #[test]
+    fn test_child_exit_payload_sign_extends_code() {
+        let notification = ChildExitNotification {
+            pid: ProcessId(9),
+            status: ExitStatus::Exited(-1),
+        };
+
+        let payload = notification.to_payload();
+        assert_eq!(payload.len(), 17);
+        assert_eq!(&payload[0..8], &9u64.to_le_bytes());
+        assert_eq!(payload[8], exit_kind::EXITED);
+        assert_eq!(&payload[9..17], &u64::MAX.to_le_bytes());
+    }
+
+    #[test]
+    fn test_exit_status_kind_values() {
+        assert_eq!(
+            ExitStatus::Killed { by: ProcessId(3) }.to_kind_value(),
+            (exit_kind::KILLED, 3)
+        );
+        assert_eq!(
+            ExitStatus::Faulted { reason: 7 }.to_kind_value(),
+            (exit_kind::FAULTED, 7)
+        );
+    }
+
+    // ========================================================================
+    // ResourceQuota tests
+    // ========================================================================
+
+    #[test]
+    fn test_quota_default_is_unlimited() {
+        let quota = ResourceQuota::default();
+        for resource in QuotaResource::ALL {
+            assert_eq!(quota.limit(resource), 0);
+        }
+    }
+
+    #[test]
+    fn test_quota_is_within() {
+        let parent = ResourceQuota {
+            max_endpoints: 4,
+            ..Default::default()
+        };
+        let tighter = ResourceQuota {
+            max_endpoints: 2,
+            max_cap_slots: 10,
+            ..Default::default()
+        };
+        assert!(tighter.is_within(&parent));
+        assert!(parent.is_within(&ResourceQuota::default()));
+
+        // Dropping a limit the parent has is not allowed
+        assert!(!ResourceQuota::default().is_within(&parent));
+        let looser = ResourceQuota {
+            max_endpoints: 5,
+            ..Default::default()
+        };
+        assert!(!looser.is_within(&parent));
+    }
+
     // ========================================================================
     // ProcessState tests
     // ========================================================================
@@ -0,0 +1,590 @@
+//! Bounded exhaustive state-space exploration of `step()`
+//!
+//! Enumerates every syscall sequence up to a depth bound over a small
+//! universe of processes, capability slots and arguments, runs each one
+//! through the real `step()` and checks `check_all_invariants` after every
+//! step. States are deduplicated by a `StateHasher` fingerprint, and any
+//! counterexample is shrunk to a minimal trace before it is reported.
+//!
+//! This is the code-level counterpart to the TLA+ specs in `docs/tla`: the
+//! specs model the protocols, this checks the implementation of them.
+//!
+//! The universe leaves out time-dependent syscalls (TTLs, deadlines), device
+//! capabilities and quotas, so the clock only feeds metrics. Any live
+//! process may issue any syscall, including a blocked one, which covers
+//! more than the runtime allows (it only retries the blocking call).
+//!
+//! The default bounds keep `cargo test` fast. The ignored deeper run:
+//!
+//! ```text
+//! cargo test -p zos-kernel-core --test explorer -- --ignored
+//! ```
+
+use std::collections::HashMap;
+
+use zos_axiom::StateHasher;
+use zos_kernel_core::{
+    check_all_invariants, step, CapSlot, InvariantViolation, KernelState, OverflowPolicy,
+    Permissions, ProcessId, ProcessState, Syscall, PAGE_SIZE,
+};
+
+// ============================================================================
+// Universe
+// ============================================================================
+
+/// One syscall issued by one process
+type Action = (ProcessId, Syscall);
+
+/// Property checked after every step (first violation only)
+type Check = fn(&KernelState) -> Option<InvariantViolation>;
+
+/// Nanoseconds of simulated time per step
+const STEP_NS: u64 = 1_000;
+
+/// The bounded world the explorer enumerates over.
+struct Universe {
+    /// Number of processes: an init process plus its children
+    processes: usize,
+    /// Syscall arguments name capability slots `0..slots`
+    slots: CapSlot,
+}
+
+impl Universe {
+    /// Starting state: every process is running and owns one endpoint
+    /// capability in slot 0.
+    fn initial_state(&self) -> KernelState {
+        let mut state = KernelState::new();
+        let init = state.register_process("init", 0);
+        for i in 1..self.processes {
+            state.register_child(init, &format!("p{}", i), 0);
+        }
+        for pid in self.pids(&state) {
+            step(&mut state, pid, Syscall::CreateEndpoint, 0);
+        }
+        state
+    }
+
+    /// All process IDs in the universe (fixed: there is no spawn syscall)
+    fn pids(&self, _state: &KernelState) -> Vec<ProcessId> {
+        (1..=self.processes as u64).map(ProcessId).collect()
+    }
+
+    /// Every action enabled in `state`.
+    fn actions(&self, state: &KernelState) -> Vec<Action> {
+        let pids = self.pids(state);
+        let slots: Vec<CapSlot> = (0..self.slots).collect();
+        let mut actions = Vec::new();
+
+        for &caller in &pids {
+            match state.get_process(caller) {
+                Some(proc) if proc.state != ProcessState::Zombie => {}
+                _ => continue,
+            }
+            let mut push = |syscall| actions.push((caller, syscall));
+
+            push(Syscall::CreateEndpoint);
+            push(Syscall::CreateEndpointWithCapacity {
+                capacity: 1,
+                policy: OverflowPolicy::Block,
+            });
+            push(Syscall::CreateNotification);
+            push(Syscall::CreateRegion { size: PAGE_SIZE });
+            push(Syscall::Exit { code: 0 });
+            push(Syscall::WaitChild { pid: None });
+
+            for &target in pids.iter().filter(|&&p| p != caller) {
+                push(Syscall::Kill { target_pid: target });
+                push(Syscall::KillTree { target_pid: target });
+                push(Syscall::Reply {
+                    caller: target,
+                    tag: 2,
+                    data: vec![],
+                });
+            }
+
+            for &slot in &slots {
+                push(Syscall::Send {
+                    endpoint_slot: slot,
+                    tag: 1,
+                    data: vec![slot as u8],
+                });
+                push(Syscall::Receive {
+                    endpoint_slot: slot,
+                });
+                push(Syscall::Call {
+                    endpoint_slot: slot,
+                    tag: 1,
+                    data: vec![],
+                });
+                push(Syscall::CapRevoke { slot });
+                push(Syscall::CapDelete { slot });
+                push(Syscall::CapDerive {
+                    slot,
+                    new_permissions: Permissions::read_only(),
+                });
+                push(Syscall::CapDeriveBadged {
+                    slot,
+                    new_permissions: Permissions::full(),
+                    badge: 7,
+                });
+                push(Syscall::Signal {
+                    notification_slot: slot,
+                    bits: 1,
+                });
+                push(Syscall::Wait {
+                    notification_slot: slot,
+                });
+                push(Syscall::Poll {
+                    notification_slot: slot,
+                });
+                push(Syscall::MapRegion {
+                    region_slot: slot,
+                    writable: true,
+                });
+                push(Syscall::UnmapRegion { region_slot: slot });
+
+                for &to_pid in &pids {
+                    push(Syscall::CapGrant {
+                        from_slot: slot,
+                        to_pid,
+                        permissions: Permissions::full(),
+                    });
+                }
+                for &cap_slot in &slots {
+                    push(Syscall::SendWithCaps {
+                        endpoint_slot: slot,
+                        tag: 3,
+                        data: vec![],
+                        cap_slots: vec![cap_slot],
+                    });
+                }
+            }
+        }
+        actions
+    }
+}
+
+/// Simulated time of the `index`th step of a trace
+fn clock(index: usize) -> u64 {
+    (index as u64 + 1) * STEP_NS
+}
+
+/// The kernel's own invariants
+fn kernel_invariants(state: &KernelState) -> Option<InvariantViolation> {
+    check_all_invariants(state).into_iter().next()
+}
+
+// ============================================================================
+// Fingerprinting
+// ============================================================================
+
+/// Hash everything that can influence future steps.
+///
+/// Metrics and the global ID counters are left out: they never change what
+/// a syscall does, and including them would make almost every state unique.
+fn fingerprint(state: &KernelState) -> [u8; 32] {
+    let mut h = StateHasher::new();
+
+    h.write_u64(state.processes.len() as u64);
+    for (pid, proc) in &state.processes {
+        h.write_u64(pid.0);
+        h.write_u64(proc.parent.0);
+        h.write_u8(proc.state as u8);
+        h.write_str(&format!("{:?}", proc.exit_status));
+    }
+
+    h.write_u64(state.cap_spaces.len() as u64);
+    for (pid, cspace) in &state.cap_spaces {
+        h.write_u64(pid.0);
+        h.write_u32(cspace.next_slot);
+        h.write_u64(cspace.slots.len() as u64);
+        for (slot, cap) in &cspace.slots {
+            h.write_u32(*slot);
+            h.write_u64(cap.id);
+            h.write_u8(cap.object_type as u8);
+            h.write_u64(cap.object_id);
+            h.write_u8(cap.permissions.to_byte());
+            h.write_u32(cap.generation);
+            h.write_u64(cap.expires_at);
+            h.write_u64(cap.badge);
+            h.write_u64(state.cap_derivations.parent(cap.id).unwrap_or(u64::MAX));
+        }
+    }
+
+    h.write_u64(state.endpoints.len() as u64);
+    for (id, ep) in &state.endpoints {
+        h.write_u64(id.0);
+        h.write_u64(ep.owner.0);
+        h.write_u64(ep.capacity as u64);
+        h.write_u8(ep.policy as u8);
+        h.write_u64(ep.pending_messages.len() as u64);
+        for msg in &ep.pending_messages {
+            h.write_str(&format!("{:?}", msg));
+        }
+    }
+
+    h.write_u64(state.notifications.len() as u64);
+    for (id, n) in &state.notifications {
+        h.write_u64(id.0);
+        h.write_u64(n.owner.0);
+        h.write_u64(n.bits);
+    }
+
+    h.write_u64(state.regions.len() as u64);
+    for (id, region) in &state.regions {
+        h.write_u64(id.0);
+        h.write_u64(region.owner.0);
+        h.write_u64(region.size as u64);
+        for (pid, writable) in &region.mappings {
+            h.write_u64(pid.0);
+            h.write_u8(*writable as u8);
+        }
+    }
+
+    h.write_u64(state.calls.len() as u64);
+    for (pid, call) in &state.calls {
+        h.write_u64(pid.0);
+        h.write_str(&format!("{:?}", call));
+    }
+
+    h.write_u64(state.irq_bindings.len() as u64);
+    for (irq, binding) in &state.irq_bindings {
+        h.write_u8(*irq);
+        h.write_str(&format!("{:?}", binding));
+    }
+
+    h.write_u64(state.deadlines.len() as u64);
+    for (pid, deadline) in &state.deadlines {
+        h.write_u64(pid.0);
+        h.write_u64(*deadline);
+    }
+
+    h.write_u64(state.waits.len() as u64);
+    for (pid, endpoints) in &state.waits {
+        h.write_u64(pid.0);
+        h.write_u64(endpoints.len() as u64);
+        for id in endpoints {
+            h.write_u64(id.0);
+        }
+    }
+
+    h.finalize()
+}
+
+// ============================================================================
+// Explorer
+// ============================================================================
+
+/// A trace that ends in a violation
+struct Counterexample {
+    trace: Vec<Action>,
+    violation: InvariantViolation,
+}
+
+/// Outcome of an exploration
+struct Report {
+    /// Distinct states reached (by fingerprint)
+    states: usize,
+    /// `step()` calls made
+    steps: usize,
+    /// Minimized counterexample, if a violation was found
+    counterexample: Option<Counterexample>,
+}
+
+struct Explorer<'a> {
+    universe: &'a Universe,
+    check: Check,
+    /// Fingerprint -> largest remaining depth it has been explored with
+    visited: HashMap<[u8; 32], usize>,
+    steps: usize,
+}
+
+impl<'a> Explorer<'a> {
+    fn new(universe: &'a Universe, check: Check) -> Self {
+        Self {
+            universe,
+            check,
+            visited: HashMap::new(),
+            steps: 0,
+        }
+    }
+
+    /// Depth-first search of every trace up to `depth` steps.
+    fn explore(mut self, depth: usize) -> Report {
+        let initial = self.universe.initial_state();
+        if let Some(violation) = (self.check)(&initial) {
+            panic!("initial state violates {}", violation.invariant);
+        }
+        self.visited.insert(fingerprint(&initial), depth);
+
+        let mut path = Vec::new();
+        let found = self.search(&initial, depth, &mut path);
+        Report {
+            states: self.visited.len(),
+            steps: self.steps,
+            counterexample: found.map(|violation| {
+                minimize(
+                    self.universe,
+                    self.check,
+                    Counterexample {
+                        trace: path,
+                        violation,
+                    },
+                )
+            }),
+        }
+    }
+
+    /// Explore successors of `state`; on a violation, `path` is left
+    /// holding the trace that reached it.
+    fn search(
+        &mut self,
+        state: &KernelState,
+        remaining: usize,
+        path: &mut Vec<Action>,
+    ) -> Option<InvariantViolation> {
+        for (pid, syscall) in self.universe.actions(state) {
+            let mut next = state.clone();
+            step(&mut next, pid, syscall.clone(), clock(path.len()));
+            self.steps += 1;
+            path.push((pid, syscall));
+
+            if let Some(violation) = (self.check)(&next) {
+                return Some(violation);
+            }
+
+            // Revisit a state only if we now have more depth left than
+            // the last time we expanded it
+            let left = remaining - 1;
+            let fp = fingerprint(&next);
+            if self.visited.get(&fp).is_none_or(|&seen| seen < left) {
+                self.visited.insert(fp, left);
+                if left > 0 {
+                    if let Some(violation) = self.search(&next, left, path) {
+                        return Some(violation);
+                    }
+                }
+            }
+            path.pop();
+        }
+        None
+    }
+}
+
+/// Run `trace` from the initial state; returns the index of the first step
+/// after which `check` fails, with the violation.
+fn replay(
+    universe: &Universe,
+    check: Check,
+    trace: &[Action],
+) -> Option<(usize, InvariantViolation)> {
+    let mut state = universe.initial_state();
+    for (i, (pid, syscall)) in trace.iter().enumerate() {
+        step(&mut state, *pid, syscall.clone(), clock(i));
+        if let Some(violation) = check(&state) {
+            return Some((i, violation));
+        }
+    }
+    None
+}
+
+/// Shrink a counterexample by deleting steps while it still violates the
+/// same invariant. Deletes halves, then quarters, ... then single steps,
+/// so the result is 1-minimal: removing any one step makes it pass.
+fn minimize(universe: &Universe, check: Check, mut cex: Counterexample) -> Counterexample {
+    let mut chunk = (cex.trace.len() / 2).max(1);
+    loop {
+        let mut i = 0;
+        let mut shrunk = false;
+        while i < cex.trace.len() {
+            let end = (i + chunk).min(cex.trace.len());
+            let candidate: Vec<Action> = cex.trace[..i]
+                .iter()
+                .chain(&cex.trace[end..])
+                .cloned()
+                .collect();
+            match replay(universe, check, &candidate) {
+                Some((last, violation)) if violation.invariant == cex.violation.invariant => {
+                    cex.trace = candidate;
+                    cex.trace.truncate(last + 1);
+                    cex.violation = violation;
+                    shrunk = true;
+                }
+                _ => i += chunk,
+            }
+        }
+        if chunk == 1 && !shrunk {
+            return cex;
+        }
+        chunk = (chunk / 2).max(1);
+    }
+}
+
+/// Fail the test with a readable trace if a counterexample was found.
+fn assert_no_counterexample(report: &Report) {
+    if let Some(cex) = &report.counterexample {
+        let steps: Vec<String> = cex
+            .trace
+            .iter()
+            .enumerate()
+            .map(|(i, (pid, syscall))| format!("  {:>2}. pid {}: {:?}", i + 1, pid.0, syscall))
+            .collect();
+        panic!(
+            "invariant {} violated: {}\nminimal trace ({} steps):\n{}",
+            cex.violation.invariant,
+            cex.violation.description,
+            cex.trace.len(),
+            steps.join("\n")
+        );
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[test]
+fn explore_two_processes() {
+    let universe = Universe {
+        processes: 2,
+        slots: 3,
+    };
+    let report = Explorer::new(&universe, kernel_invariants).explore(3);
+
+    assert_no_counterexample(&report);
+    assert!(
+        report.states > 100,
+        "explored only {} states",
+        report.states
+    );
+    assert!(report.steps > report.states);
+}
+
+#[test]
+fn explore_three_processes() {
+    let universe = Universe {
+        processes: 3,
+        slots: 2,
+    };
+    let report = Explorer::new(&universe, kernel_invariants).explore(2);
+
+    assert_no_counterexample(&report);
+}
+
+#[test]
+#[ignore = "deep exploration, run explicitly"]
+fn explore_three_processes_deep() {
+    let universe = Universe {
+        processes: 3,
+        slots: 3,
+    };
+    let report = Explorer::new(&universe, kernel_invariants).explore(4);
+
+    assert_no_counterexample(&report);
+    println!("{} states, {} steps", report.states, report.steps);
+}
+
+/// Planted property: no endpoint ever queues two messages
+fn single_message_queues(state: &KernelState) -> Option<InvariantViolation> {
+    state
+        .endpoints
+        .values()
+        .find(|ep| ep.pending_messages.len() > 1)
+        .map(|ep| InvariantViolation {
+            invariant: "SingleMessageQueues",
+            description: format!("endpoint {} holds {}", ep.id.0, ep.pending_messages.len()),
+        })
+}
+
+#[test]
+fn explorer_finds_planted_violation() {
+    let universe = Universe {
+        processes: 2,
+        slots: 2,
+    };
+    let report = Explorer::new(&universe, single_message_queues).explore(3);
+
+    let cex = report.counterexample.expect("planted violation not found");
+    assert_eq!(cex.violation.invariant, "SingleMessageQueues");
+    // Two messages on one endpoint (a send, or the MSG_CHILD_EXITED a kill
+    // queues for the parent, followed by a send) is the shortest way there
+    assert_eq!(cex.trace.len(), 2, "{:?}", cex.trace);
+    assert!(matches!(
+        cex.trace[1].1,
+        Syscall::Send { .. } | Syscall::SendWithCaps { .. } | Syscall::Call { .. }
+    ));
+
+    let (last, _) = replay(&universe, single_message_queues, &cex.trace).unwrap();
+    assert_eq!(last, cex.trace.len() - 1);
+}
+
+#[test]
+fn minimize_drops_irrelevant_steps() {
+    let universe = Universe {
+        processes: 2,
+        slots: 2,
+    };
+    let send = |pid| {
+        (
+            ProcessId(pid),
+            Syscall::Send {
+                endpoint_slot: 0,
+                tag: 1,
+                data: vec![],
+            },
+        )
+    };
+    let trace = vec![
+        (ProcessId(1), Syscall::CreateNotification),
+        send(1),
+        (ProcessId(2), Syscall::Yield),
+        (ProcessId(1), Syscall::CreateRegion { size: PAGE_SIZE }),
+        send(1),
+        (ProcessId(2), Syscall::CreateEndpoint),
+    ];
+    let (last, violation) = replay(&universe, single_message_queues, &trace).unwrap();
+    assert_eq!(last, 4);
+
+    let cex = minimize(
+        &universe,
+        single_message_queues,
+        Counterexample { trace, violation },
+    );
+    assert_eq!(cex.trace.len(), 2);
+    assert!(cex
+        .trace
+        .iter()
+        .all(|(pid, syscall)| { *pid == ProcessId(1) && matches!(syscall, Syscall::Send { .. }) }));
+}
+
+#[test]
+fn fingerprint_ignores_metrics_only() {
+    let universe = Universe {
+        processes: 2,
+        slots: 2,
+    };
+    let state = universe.initial_state();
+
+    // A failed syscall only touches metrics
+    let mut failed = state.clone();
+    step(
+        &mut failed,
+        ProcessId(1),
+        Syscall::CapDelete { slot: 9 },
+        clock(0),
+    );
+    assert_eq!(fingerprint(&state), fingerprint(&failed));
+
+    let mut sent = state.clone();
+    step(
+        &mut sent,
+        ProcessId(1),
+        Syscall::Send {
+            endpoint_slot: 0,
+            tag: 1,
+            data: vec![],
+        },
+        clock(0),
+    );
+    assert_ne!(fingerprint(&state), fingerprint(&sent));
+}
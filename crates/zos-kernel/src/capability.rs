@@ -1,10 +1,167 @@
 //! Capability-based access control
 //!
-//! This module re-exports capability types from zos-axiom.
+//! This module re-exports capability types from zos-axiom and keeps the
+//! kernel's record of which capability was derived from which.
 //!
 //! Per Invariant 10, all capability verification flows through Axiom's
-//! `axiom_check` function. This module exists only to maintain backwards
+//! `axiom_check` function. The re-exports exist only to maintain backwards
 //! compatibility for kernel code.
 
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
 // Re-export all capability types from zos-axiom
 pub use zos_axiom::{axiom_check, AxiomError, Capability, CapabilitySpace, Permissions};
+
+/// Parent/child links between capabilities, by capability ID.
+///
+/// Every grant or derive records the new capability as a child of its source,
+/// so revoking a capability can reach every downstream copy regardless of
+/// which process holds it.
+#[derive(Clone, Debug, Default)]
+pub struct DerivationTree {
+    /// Child cap ID -> parent cap ID
+    parents: BTreeMap<u64, u64>,
+    /// Parent cap ID -> child cap IDs (in derivation order)
+    children: BTreeMap<u64, Vec<u64>>,
+}
+
+impl DerivationTree {
+    /// Create an empty derivation tree
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record `child` as derived from `parent`
+    pub fn link(&mut self, parent: u64, child: u64) {
+        self.parents.insert(child, parent);
+        self.children.entry(parent).or_default().push(child);
+    }
+
+    /// Get the parent of a capability, if it was derived
+    pub fn parent(&self, cap_id: u64) -> Option<u64> {
+        self.parents.get(&cap_id).copied()
+    }
+
+    /// Get the direct children of a capability
+    pub fn children(&self, cap_id: u64) -> &[u64] {
+        self.children.get(&cap_id).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Iterate over `(child, parent)` links in child ID order
+    pub fn links(&self) -> impl Iterator<Item = (u64, u64)> + '_ {
+        self.parents.iter().map(|(&child, &parent)| (child, parent))
+    }
+
+    /// Get a capability and all of its descendants, in pre-order
+    pub fn subtree(&self, cap_id: u64) -> Vec<u64> {
+        let mut result = Vec::new();
+        let mut stack = alloc::vec![cap_id];
+        while let Some(id) = stack.pop() {
+            result.push(id);
+            // Push in reverse so children are visited in derivation order
+            stack.extend(self.children(id).iter().rev());
+        }
+        result
+    }
+
+    /// Remove a single capability from the tree.
+    ///
+    /// Its children are re-attached to its parent (or become roots), so a
+    /// later revoke of an ancestor still reaches them.
+    pub fn remove(&mut self, cap_id: u64) {
+        let parent = self.detach(cap_id);
+        let orphans = self.children.remove(&cap_id).unwrap_or_default();
+
+        match parent {
+            Some(parent) => {
+                for &orphan in &orphans {
+                    self.parents.insert(orphan, parent);
+                }
+                if !orphans.is_empty() {
+                    self.children.entry(parent).or_default().extend(orphans);
+                }
+            }
+            None => {
+                for orphan in orphans {
+                    self.parents.remove(&orphan);
+                }
+            }
+        }
+    }
+
+    /// Remove a capability and all of its descendants, returning the removed
+    /// IDs in pre-order
+    pub fn remove_subtree(&mut self, cap_id: u64) -> Vec<u64> {
+        let ids = self.subtree(cap_id);
+        self.detach(cap_id);
+        for id in &ids {
+            self.parents.remove(id);
+            self.children.remove(id);
+        }
+        ids
+    }
+
+    /// Unlink a capability from its parent, returning the parent
+    fn detach(&mut self, cap_id: u64) -> Option<u64> {
+        let parent = self.parents.remove(&cap_id)?;
+        if let Some(siblings) = self.children.get_mut(&parent) {
+            siblings.retain(|&id| id != cap_id);
+            if siblings.is_empty() {
+                self.children.remove(&parent);
+            }
+        }
+        Some(parent)
+    }
+
+    /// Number of derived capabilities tracked (capabilities with a parent)
+    pub fn len(&self) -> usize {
+        self.parents.len()
+    }
+
+    /// Check if no derivations are tracked
+    pub fn is_empty(&self) -> bool {
+        self.parents.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_derivation_tree_subtree_preorder() {
+        let mut tree = DerivationTree::new();
+        tree.link(1, 2);
+        tree.link(2, 4);
+        tree.link(1, 3);
+
+        assert_eq!(tree.subtree(1), alloc::vec![1, 2, 4, 3]);
+        assert_eq!(tree.subtree(9), alloc::vec![9]);
+        assert_eq!(tree.parent(4), Some(2));
+    }
+
+    #[test]
+    fn test_derivation_tree_remove_reparents_children() {
+        let mut tree = DerivationTree::new();
+        tree.link(1, 2);
+        tree.link(2, 3);
+
+        tree.remove(2);
+
+        assert_eq!(tree.parent(3), Some(1));
+        assert_eq!(tree.subtree(1), alloc::vec![1, 3]);
+    }
+
+    #[test]
+    fn test_derivation_tree_remove_subtree() {
+        let mut tree = DerivationTree::new();
+        tree.link(1, 2);
+        tree.link(2, 3);
+        tree.link(1, 4);
+
+        assert_eq!(tree.remove_subtree(2), alloc::vec![2, 3]);
+        assert_eq!(tree.children(1), &[4]);
+        assert_eq!(tree.len(), 1);
+    }
+}
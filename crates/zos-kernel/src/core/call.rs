@@ -0,0 +1,252 @@
+//! Call/Reply for KernelCore.
+//!
+//! This module contains methods for:
+//! - Calling an endpoint (send + mint a one-shot reply capability)
+//! - Replying to a caller (consuming the reply capability)
+//! - Unwinding calls when a caller or server dies
+//!
+//! The reply capability lives in the server's CSpace, names the caller and
+//! carries no grant right. Only its holder can answer the call, and only
+//! once. Waiting for the reply parks the caller and lives in `wait`.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::error::KernelError;
+use crate::ipc::{CallState, Message, WaitOutcome, MAX_MESSAGE_SIZE};
+use crate::types::{CapSlot, ObjectType, ProcessId};
+use crate::{axiom_check, Capability, CapabilitySpace, Permissions};
+use zos_axiom::{Commit, CommitType};
+use zos_hal::HAL;
+
+use super::capability::create_cap_removed_commit;
+use super::{map_axiom_error, KernelCore};
+
+impl<H: HAL> KernelCore<H> {
+    /// Send a request to the owner of the endpoint behind `endpoint_slot`
+    /// and park the caller until it replies.
+    ///
+    /// A reply capability naming the caller is minted into the server's
+    /// CSpace. If the server dies first the call ends with
+    /// `ProcessNotFound`.
+    ///
+    /// Returns (Result<WaitOutcome, KernelError>, Vec<Commit>).
+    pub fn call(
+        &mut self,
+        pid: ProcessId,
+        endpoint_slot: CapSlot,
+        tag: u32,
+        data: Vec<u8>,
+        timestamp: u64,
+    ) -> (Result<WaitOutcome, KernelError>, Vec<Commit>) {
+        if self.calls.contains_key(&pid) {
+            return (Err(KernelError::PermissionDenied), Vec::new());
+        }
+        let server = match self
+            .validate_send_cap(pid, endpoint_slot, timestamp)
+            .and_then(|(endpoint_id, _)| {
+                self.endpoints
+                    .get(&endpoint_id)
+                    .map(|endpoint| endpoint.owner)
+                    .ok_or(KernelError::EndpointNotFound)
+            }) {
+            Ok(server) => server,
+            Err(e) => return (Err(e), Vec::new()),
+        };
+        if !self.cap_spaces.contains_key(&server) {
+            return (Err(KernelError::ProcessNotFound), Vec::new());
+        }
+
+        let (sent, commit) = self.ipc_send(pid, endpoint_slot, tag, data, timestamp);
+        let mut commits: Vec<Commit> = commit.into_iter().collect();
+        if let Err(e) = sent {
+            return (Err(e), commits);
+        }
+
+        let cap_id = self.next_cap_id();
+        let perms = Permissions::write_only();
+        let reply_cap = Capability {
+            id: cap_id,
+            object_type: ObjectType::Reply,
+            object_id: pid.0,
+            permissions: perms,
+            generation: 0,
+            expires_at: 0,
+            badge: 0,
+        };
+        if let Some(cspace) = self.cap_spaces.get_mut(&server) {
+            let slot = cspace.insert(reply_cap);
+            commits.push(call_commit(
+                CommitType::CapInserted {
+                    pid: server.0,
+                    slot,
+                    cap_id,
+                    object_type: ObjectType::Reply as u8,
+                    object_id: pid.0,
+                    perms: perms.to_byte(),
+                },
+                timestamp,
+            ));
+        }
+        self.calls.insert(pid, CallState::Pending { server });
+
+        let (result, wait_commits) = self.wait_reply(pid);
+        commits.extend(wait_commits);
+        (result, commits)
+    }
+
+    /// Answer `caller`'s outstanding call, consuming the reply capability.
+    ///
+    /// Fails with `InvalidCapability` unless `pid` holds the reply
+    /// capability for a call `caller` is still waiting on.
+    ///
+    /// Returns (Result<(), KernelError>, Vec<Commit>).
+    pub fn reply(
+        &mut self,
+        pid: ProcessId,
+        caller: ProcessId,
+        tag: u32,
+        data: Vec<u8>,
+        timestamp: u64,
+    ) -> (Result<(), KernelError>, Vec<Commit>) {
+        if data.len() > MAX_MESSAGE_SIZE {
+            return (Err(KernelError::PermissionDenied), Vec::new());
+        }
+        let cspace = match self.cap_spaces.get(&pid) {
+            Some(cspace) => cspace,
+            None => return (Err(KernelError::ProcessNotFound), Vec::new()),
+        };
+        let slot = match find_reply_cap(cspace, caller) {
+            Some(slot) => slot,
+            None => return (Err(KernelError::InvalidCapability), Vec::new()),
+        };
+        if let Err(e) = axiom_check(
+            cspace,
+            slot,
+            &Permissions::write_only(),
+            Some(ObjectType::Reply),
+            timestamp,
+        ) {
+            return (Err(map_axiom_error(e)), Vec::new());
+        }
+        if !matches!(
+            self.calls.get(&caller),
+            Some(CallState::Pending { server }) if *server == pid
+        ) {
+            return (Err(KernelError::InvalidCapability), Vec::new());
+        }
+
+        // The capability answers exactly one call
+        if let Some(cspace) = self.cap_spaces.get_mut(&pid) {
+            cspace.remove(slot);
+        }
+
+        let size = data.len();
+        self.calls.insert(
+            caller,
+            CallState::Replied(Message {
+                from: pid,
+                badge: 0,
+                tag,
+                data,
+                transferred_caps: Vec::new(),
+            }),
+        );
+        if let Some(server) = self.processes.get_mut(&pid) {
+            server.metrics.ipc_sent += 1;
+            server.metrics.ipc_bytes_sent += size as u64;
+            server.metrics.last_active_ns = timestamp;
+        }
+        self.total_ipc_count += 1;
+
+        let commits = vec![
+            create_cap_removed_commit(pid, slot, timestamp),
+            call_commit(
+                CommitType::Replied {
+                    from: pid.0,
+                    to: caller.0,
+                    tag,
+                    size,
+                },
+                timestamp,
+            ),
+        ];
+        (Ok(()), commits)
+    }
+
+    /// Unwind the calls a dying process takes part in.
+    ///
+    /// Its own outstanding call is dropped together with the reply
+    /// capability the server holds for it. Calls it was serving are aborted
+    /// so their callers wake up instead of blocking forever.
+    pub(crate) fn abort_calls(&mut self, pid: ProcessId, timestamp: u64) -> Vec<Commit> {
+        let mut commits = Vec::new();
+
+        if let Some(CallState::Pending { server }) = self.calls.remove(&pid) {
+            let slot = self
+                .cap_spaces
+                .get(&server)
+                .and_then(|cspace| find_reply_cap(cspace, pid));
+            if let Some(slot) = slot {
+                if let Some(cspace) = self.cap_spaces.get_mut(&server) {
+                    cspace.remove(slot);
+                }
+                commits.push(create_cap_removed_commit(server, slot, timestamp));
+            }
+        }
+
+        let served: Vec<ProcessId> = self
+            .calls
+            .iter()
+            .filter(|(_, call)| matches!(call, CallState::Pending { server } if *server == pid))
+            .map(|(&caller, _)| caller)
+            .collect();
+        for caller in served {
+            commits.extend(self.abort_dropped_reply(pid, caller, timestamp));
+        }
+
+        commits
+    }
+
+    /// Abort `caller`'s call after `holder` dropped its reply capability
+    /// or died.
+    pub(crate) fn abort_dropped_reply(
+        &mut self,
+        holder: ProcessId,
+        caller: ProcessId,
+        timestamp: u64,
+    ) -> Option<Commit> {
+        if !matches!(
+            self.calls.get(&caller),
+            Some(CallState::Pending { server }) if *server == holder
+        ) {
+            return None;
+        }
+        self.calls.insert(caller, CallState::Aborted);
+        Some(call_commit(
+            CommitType::CallAborted { caller: caller.0 },
+            timestamp,
+        ))
+    }
+}
+
+/// Slot of the reply capability naming `caller`, if the CSpace holds one
+fn find_reply_cap(cspace: &CapabilitySpace, caller: ProcessId) -> Option<CapSlot> {
+    cspace
+        .slots
+        .iter()
+        .find(|(_, cap)| cap.object_type == ObjectType::Reply && cap.object_id == caller.0)
+        .map(|(&slot, _)| slot)
+}
+
+/// Wrap a call commit type in an unsequenced Commit
+fn call_commit(commit_type: CommitType, timestamp: u64) -> Commit {
+    Commit {
+        id: [0u8; 32],
+        prev_commit: [0u8; 32],
+        seq: 0,
+        timestamp,
+        commit_type,
+        caused_by: None,
+    }
+}
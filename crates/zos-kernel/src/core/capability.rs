@@ -3,19 +3,26 @@
 //! This module contains methods for:
 //! - Granting capabilities between processes
 //! - Granting capabilities to specific endpoints
-//! - Revoking capabilities (with permission check)
+//! - Revoking capabilities and everything derived from them (with
+//!   permission check)
 //! - Deleting capabilities (without permission check)
 //! - Deriving capabilities with reduced permissions
+//! - Deriving badged endpoint capabilities
+//! - Granting and deriving capabilities that expire after a lifetime
+//! - Sweeping expired capabilities
 
+use alloc::collections::BTreeMap;
 use alloc::vec;
 use alloc::vec::Vec;
 
 use crate::axiom_check;
 use crate::error::KernelError;
-use crate::types::{CapSlot, EndpointId, ObjectType, ProcessId};
+use crate::syscall::{RevokeNotification, MSG_CAP_REVOKED};
+use crate::types::{CapSlot, EndpointId, ObjectType, ProcessId, QuotaResource, RegionId};
 use crate::{Capability, Permissions};
 use zos_axiom::{Commit, CommitType};
 use zos_hal::HAL;
+use zos_ipc::revoke_reason;
 
 use super::{map_axiom_error, KernelCore};
 
@@ -30,54 +37,79 @@ impl<H: HAL> KernelCore<H> {
         to_pid: ProcessId,
         new_perms: Permissions,
         timestamp: u64,
+    ) -> (Result<CapSlot, KernelError>, Vec<Commit>) {
+        self.grant(from_pid, from_slot, to_pid, new_perms, None, timestamp)
+    }
+
+    /// Grant a capability that expires `ttl_ns` after the grant.
+    ///
+    /// The copy never outlives its source: the expiry is clamped to the
+    /// source's. A zero TTL is rejected, since `expires_at == 0` means
+    /// "never expires".
+    ///
+    /// Returns (Result<CapSlot, KernelError>, Vec<Commit>).
+    pub fn grant_capability_with_ttl(
+        &mut self,
+        from_pid: ProcessId,
+        from_slot: CapSlot,
+        to_pid: ProcessId,
+        new_perms: Permissions,
+        ttl_ns: u64,
+        timestamp: u64,
+    ) -> (Result<CapSlot, KernelError>, Vec<Commit>) {
+        self.grant(
+            from_pid,
+            from_slot,
+            to_pid,
+            new_perms,
+            Some(ttl_ns),
+            timestamp,
+        )
+    }
+
+    /// Grant with an optional lifetime
+    fn grant(
+        &mut self,
+        from_pid: ProcessId,
+        from_slot: CapSlot,
+        to_pid: ProcessId,
+        new_perms: Permissions,
+        ttl_ns: Option<u64>,
+        timestamp: u64,
     ) -> (Result<CapSlot, KernelError>, Vec<Commit>) {
         let mut commits = Vec::new();
 
+        let requested_expiry = match ttl_expiry(ttl_ns, timestamp) {
+            Ok(expiry) => expiry,
+            Err(e) => return (Err(e), commits),
+        };
+
         // Get and validate source capability
         let source_cap = match self.validate_grant_source(from_pid, from_slot, timestamp) {
             Ok(cap) => cap,
             Err(e) => return (Err(e), commits),
         };
 
+        if let Some(commit) = self.quota_violation(to_pid, QuotaResource::CapSlots, 1, timestamp) {
+            return (Err(KernelError::ResourceExhausted), vec![commit]);
+        }
+
         // Attenuate permissions (can only reduce, never amplify)
         let granted_perms = attenuate_permissions(&source_cap.permissions, &new_perms);
 
-        // Create and insert new capability
-        let (to_slot, cap_commits) =
-            match self.create_derived_cap(to_pid, &source_cap, granted_perms, timestamp) {
-                Ok(result) => result,
-                Err(e) => return (Err(e), commits),
-            };
-
-        // Log CapGranted commit
-        commits.push(Commit {
-            id: [0u8; 32],
-            prev_commit: [0u8; 32],
-            seq: 0,
+        // Create and insert new capability (logs CapGranted and CapInserted)
+        let (to_slot, cap_commits) = match self.create_derived_cap(
+            (from_pid, from_slot),
+            to_pid,
+            &source_cap,
+            granted_perms,
+            source_cap.badge,
+            child_expiry(source_cap.expires_at, requested_expiry),
             timestamp,
-            commit_type: CommitType::CapGranted {
-                from_pid: from_pid.0,
-                to_pid: to_pid.0,
-                from_slot,
-                to_slot,
-                new_cap_id: cap_commits
-                    .last()
-                    .map(|c| {
-                        if let CommitType::CapInserted { cap_id, .. } = c.commit_type {
-                            cap_id
-                        } else {
-                            0
-                        }
-                    })
-                    .unwrap_or(0),
-                perms: zos_axiom::Permissions {
-                    read: granted_perms.read,
-                    write: granted_perms.write,
-                    grant: granted_perms.grant,
-                },
-            },
-            caused_by: None,
-        });
+        ) {
+            Ok(result) => result,
+            Err(e) => return (Err(e), commits),
+        };
         commits.extend(cap_commits);
 
         (Ok(to_slot), commits)
@@ -110,6 +142,11 @@ impl<H: HAL> KernelCore<H> {
             return (Err(KernelError::PermissionDenied), commits);
         }
 
+        if let Some(commit) = self.quota_violation(to_pid, QuotaResource::CapSlots, 1, timestamp) {
+            commits.push(commit);
+            return (Err(KernelError::ResourceExhausted), commits);
+        }
+
         // Create new capability with new ID
         let new_cap_id = self.next_cap_id();
         let new_cap = Capability {
@@ -119,6 +156,7 @@ impl<H: HAL> KernelCore<H> {
             permissions: perms,
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
 
         // Insert into destination
@@ -154,10 +192,13 @@ impl<H: HAL> KernelCore<H> {
         (Ok(to_slot), commits)
     }
 
-    /// Revoke a capability (validates via axiom_check).
+    /// Revoke a capability and everything derived from it (validates via
+    /// axiom_check).
     ///
     /// Revocation requires the caller to have grant permission on the capability.
-    /// This removes the capability from the caller's CSpace.
+    /// The capability and every copy granted or derived from it, in any
+    /// process, are removed with a CapRemoved commit per slot. Holders other
+    /// than the caller are sent `MSG_CAP_REVOKED`.
     ///
     /// Returns (Result<(), KernelError>, Vec<Commit>).
     pub fn revoke_capability(
@@ -174,19 +215,41 @@ impl<H: HAL> KernelCore<H> {
             Err(e) => return (Err(e), commits),
         };
 
-        // Log and remove
-        commits.push(create_cap_removed_commit(pid, slot, timestamp));
+        let subtree = self.cap_derivations.remove_subtree(cap_id);
+        let locations = self.locate_caps(&subtree);
+        for id in &subtree {
+            let Some(&(holder, holder_slot)) = locations.get(id) else {
+                continue;
+            };
+            let removed = self
+                .cap_spaces
+                .get_mut(&holder)
+                .and_then(|cspace| cspace.remove(holder_slot));
+            let Some(cap) = removed else {
+                continue;
+            };
 
-        match self.cap_spaces.get_mut(&pid) {
-            Some(cspace) => cspace.remove(slot),
-            None => return (Err(KernelError::ProcessNotFound), commits),
-        };
+            commits.push(create_cap_removed_commit(holder, holder_slot, timestamp));
+            if *id != cap_id {
+                let notification = RevokeNotification {
+                    pid: holder,
+                    slot: holder_slot,
+                    object_type: cap.object_type as u8,
+                    object_id: cap.object_id,
+                    reason: revoke_reason::EXPLICIT,
+                };
+                let data = notification.to_payload();
+                commits.extend(self.notify_process(holder, MSG_CAP_REVOKED, data, timestamp));
+            }
+            commits.extend(self.release_removed_cap(holder, &cap, timestamp));
+        }
 
         self.hal.debug_write(&alloc::format!(
-            "[kernel] PID {} revoked capability {} (slot {})",
+            "[kernel] PID {} revoked capability {} (slot {}) and {} derived",
             pid.0,
             cap_id,
-            slot
+            slot,
+            subtree.len() - 1
         ));
 
         (Ok(()), commits)
@@ -218,10 +281,15 @@ impl<H: HAL> KernelCore<H> {
         // Log and remove
         commits.push(create_cap_removed_commit(pid, slot, timestamp));
 
-        match self.cap_spaces.get_mut(&pid) {
+        let removed = match self.cap_spaces.get_mut(&pid) {
             Some(cspace) => cspace.remove(slot),
             None => return (Err(KernelError::ProcessNotFound), commits),
         };
+        if let Some(cap) = removed {
+            // Copies derived from it stay revocable through its parent
+            self.cap_derivations.remove(cap.id);
+            commits.extend(self.release_removed_cap(pid, &cap, timestamp));
+        }
 
         self.hal.debug_write(&alloc::format!(
             "[kernel] PID {} deleted capability {} (slot {})",
@@ -242,29 +310,193 @@ impl<H: HAL> KernelCore<H> {
         slot: CapSlot,
         new_perms: Permissions,
         timestamp: u64,
+    ) -> (Result<CapSlot, KernelError>, Vec<Commit>) {
+        self.derive(pid, slot, new_perms, None, timestamp)
+    }
+
+    /// Derive a capability that expires `ttl_ns` after the derive.
+    ///
+    /// The expiry is clamped to the source's, like a grant with a TTL.
+    ///
+    /// Returns (Result<CapSlot, KernelError>, Vec<Commit>).
+    pub fn derive_capability_with_ttl(
+        &mut self,
+        pid: ProcessId,
+        slot: CapSlot,
+        new_perms: Permissions,
+        ttl_ns: u64,
+        timestamp: u64,
+    ) -> (Result<CapSlot, KernelError>, Vec<Commit>) {
+        self.derive(pid, slot, new_perms, Some(ttl_ns), timestamp)
+    }
+
+    /// Derive with an optional lifetime
+    fn derive(
+        &mut self,
+        pid: ProcessId,
+        slot: CapSlot,
+        new_perms: Permissions,
+        ttl_ns: Option<u64>,
+        timestamp: u64,
     ) -> (Result<CapSlot, KernelError>, Vec<Commit>) {
         let mut commits = Vec::new();
 
+        let requested_expiry = match ttl_expiry(ttl_ns, timestamp) {
+            Ok(expiry) => expiry,
+            Err(e) => return (Err(e), commits),
+        };
+
         // Validate source capability exists and is not expired
         let source_cap = match self.validate_derive_source(pid, slot, timestamp) {
             Ok(cap) => cap,
             Err(e) => return (Err(e), commits),
         };
 
+        if let Some(commit) = self.quota_violation(pid, QuotaResource::CapSlots, 1, timestamp) {
+            commits.push(commit);
+            return (Err(KernelError::ResourceExhausted), commits);
+        }
+
         // Attenuate permissions
         let derived_perms = attenuate_permissions(&source_cap.permissions, &new_perms);
 
         // Create and insert derived capability
-        let (new_slot, cap_commits) =
-            match self.create_derived_cap(pid, &source_cap, derived_perms, timestamp) {
-                Ok(result) => result,
-                Err(e) => return (Err(e), commits),
-            };
+        let (new_slot, cap_commits) = match self.create_derived_cap(
+            (pid, slot),
+            pid,
+            &source_cap,
+            derived_perms,
+            source_cap.badge,
+            child_expiry(source_cap.expires_at, requested_expiry),
+            timestamp,
+        ) {
+            Ok(result) => result,
+            Err(e) => return (Err(e), commits),
+        };
+
+        commits.extend(cap_commits);
+        (Ok(new_slot), commits)
+    }
+
+    /// Derive an endpoint capability stamped with a badge.
+    ///
+    /// Every message sent through the new capability (or capabilities
+    /// derived from or granted with it) carries `badge`, so the endpoint
+    /// owner can tell clients apart without trusting sender PIDs. The badge
+    /// must be non-zero and the source capability must be unbadged.
+    ///
+    /// Returns (Result<CapSlot, KernelError>, Vec<Commit>).
+    pub fn derive_badged_capability(
+        &mut self,
+        pid: ProcessId,
+        slot: CapSlot,
+        new_perms: Permissions,
+        badge: u64,
+        timestamp: u64,
+    ) -> (Result<CapSlot, KernelError>, Vec<Commit>) {
+        let mut commits = Vec::new();
+
+        let source_cap = match self.validate_derive_source(pid, slot, timestamp) {
+            Ok(cap) => cap,
+            Err(e) => return (Err(e), commits),
+        };
+
+        if badge == 0 || source_cap.object_type != ObjectType::Endpoint {
+            return (Err(KernelError::InvalidCapability), commits);
+        }
+        // A badge identifies one client; it cannot be replaced once set
+        if source_cap.badge != 0 {
+            return (Err(KernelError::PermissionDenied), commits);
+        }
+
+        if let Some(commit) = self.quota_violation(pid, QuotaResource::CapSlots, 1, timestamp) {
+            commits.push(commit);
+            return (Err(KernelError::ResourceExhausted), commits);
+        }
+
+        let derived_perms = attenuate_permissions(&source_cap.permissions, &new_perms);
+        let (new_slot, cap_commits) = match self.create_derived_cap(
+            (pid, slot),
+            pid,
+            &source_cap,
+            derived_perms,
+            badge,
+            source_cap.expires_at,
+            timestamp,
+        ) {
+            Ok(result) => result,
+            Err(e) => return (Err(e), commits),
+        };
 
         commits.extend(cap_commits);
         (Ok(new_slot), commits)
     }
 
+    /// Remove every capability that has expired at `timestamp`.
+    ///
+    /// Each slot gets a CapRemoved commit and its holder is sent
+    /// `MSG_CAP_REVOKED` with `revoke_reason::EXPIRED`. Copies made from an
+    /// expired capability expire no later than it, so they go in the same
+    /// sweep.
+    ///
+    /// Returns the commits for the removals and notifications.
+    pub fn expire_capabilities(&mut self, timestamp: u64) -> Vec<Commit> {
+        let expired: Vec<(ProcessId, CapSlot)> = self
+            .cap_spaces
+            .iter()
+            .flat_map(|(&pid, cspace)| {
+                cspace
+                    .slots
+                    .iter()
+                    .filter(|(_, cap)| cap.is_expired(timestamp))
+                    .map(move |(&slot, _)| (pid, slot))
+            })
+            .collect();
+
+        let mut commits = Vec::new();
+        for (pid, slot) in expired {
+            let removed = self
+                .cap_spaces
+                .get_mut(&pid)
+                .and_then(|cspace| cspace.remove(slot));
+            let Some(cap) = removed else {
+                continue;
+            };
+            self.cap_derivations.remove(cap.id);
+
+            commits.push(create_cap_removed_commit(pid, slot, timestamp));
+            let notification = RevokeNotification {
+                pid,
+                slot,
+                object_type: cap.object_type as u8,
+                object_id: cap.object_id,
+                reason: revoke_reason::EXPIRED,
+            };
+            let data = notification.to_payload();
+            commits.extend(self.notify_process(pid, MSG_CAP_REVOKED, data, timestamp));
+            commits.extend(self.release_removed_cap(pid, &cap, timestamp));
+
+            self.hal.debug_write(&alloc::format!(
+                "[kernel] Capability {} (PID {} slot {}) expired",
+                cap.id,
+                pid.0,
+                slot
+            ));
+        }
+        commits
+    }
+
+    /// Earliest time (nanos since boot) at which a capability will have
+    /// expired, so the runtime can sweep then.
+    pub fn next_cap_expiry(&self) -> Option<u64> {
+        self.cap_spaces
+            .values()
+            .flat_map(|cspace| cspace.slots.values())
+            .filter(|cap| cap.expires_at != 0)
+            .map(|cap| cap.expires_at.saturating_add(1))
+            .min()
+    }
+
     // ========================================================================
     // Private helper methods
     // ========================================================================
@@ -334,12 +566,64 @@ impl<H: HAL> KernelCore<H> {
             .map_err(map_axiom_error)
     }
 
-    /// Create a derived capability and insert it into target process
+    /// Undo what a capability removed from `pid` was holding open.
+    ///
+    /// Dropping a reply capability abandons the call it answers, dropping
+    /// the last IRQ capability for a bound line unbinds it, and dropping a
+    /// region capability may unmap or free the region.
+    fn release_removed_cap(
+        &mut self,
+        pid: ProcessId,
+        cap: &Capability,
+        timestamp: u64,
+    ) -> Vec<Commit> {
+        match cap.object_type {
+            ObjectType::Reply => self
+                .abort_dropped_reply(pid, ProcessId(cap.object_id), timestamp)
+                .into_iter()
+                .collect(),
+            ObjectType::Irq => self
+                .unbind_dropped_irq(pid, cap.object_id as u8, timestamp)
+                .into_iter()
+                .collect(),
+            ObjectType::Memory => {
+                self.release_dropped_region(pid, RegionId(cap.object_id), timestamp)
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Find the holder and slot of each capability in `cap_ids`.
+    ///
+    /// IDs that no longer exist in any capability space are omitted.
+    fn locate_caps(&self, cap_ids: &[u64]) -> BTreeMap<u64, (ProcessId, CapSlot)> {
+        let mut found = BTreeMap::new();
+        for (&pid, cspace) in &self.cap_spaces {
+            for (&slot, cap) in &cspace.slots {
+                if cap_ids.contains(&cap.id) {
+                    found.insert(cap.id, (pid, slot));
+                }
+            }
+        }
+        found
+    }
+
+    /// Create a capability derived from `source_cap` (held by `source`) and
+    /// insert it into the target process.
+    ///
+    /// The new capability is recorded as a child of its source, so revoking
+    /// the source reaches it. Grants and derives alike log a CapGranted
+    /// commit naming the source slot, from which replay rebuilds the link.
+    /// A badge or expiry is logged after the CapInserted it applies to.
+    #[allow(clippy::too_many_arguments)]
     fn create_derived_cap(
         &mut self,
+        source: (ProcessId, CapSlot),
         to_pid: ProcessId,
         source_cap: &Capability,
         new_perms: Permissions,
+        badge: u64,
+        expires_at: u64,
         timestamp: u64,
     ) -> Result<(CapSlot, Vec<Commit>), KernelError> {
         let new_cap_id = self.next_cap_id();
@@ -349,7 +633,8 @@ impl<H: HAL> KernelCore<H> {
             object_id: source_cap.object_id,
             permissions: new_perms,
             generation: source_cap.generation,
-            expires_at: source_cap.expires_at,
+            expires_at,
+            badge,
         };
 
         let to_slot = self
@@ -357,24 +642,59 @@ impl<H: HAL> KernelCore<H> {
             .get_mut(&to_pid)
             .ok_or(KernelError::ProcessNotFound)?
             .insert(new_cap);
-
-        let commit = Commit {
-            id: [0u8; 32],
-            prev_commit: [0u8; 32],
-            seq: 0,
-            timestamp,
-            commit_type: CommitType::CapInserted {
-                pid: to_pid.0,
-                slot: to_slot,
-                cap_id: new_cap_id,
-                object_type: source_cap.object_type as u8,
-                object_id: source_cap.object_id,
-                perms: new_perms.to_byte(),
+        self.cap_derivations.link(source_cap.id, new_cap_id);
+
+        let (from_pid, from_slot) = source;
+        let granted = CommitType::CapGranted {
+            from_pid: from_pid.0,
+            to_pid: to_pid.0,
+            from_slot,
+            to_slot,
+            new_cap_id,
+            perms: zos_axiom::Permissions {
+                read: new_perms.read,
+                write: new_perms.write,
+                grant: new_perms.grant,
             },
-            caused_by: None,
+        };
+        let inserted = CommitType::CapInserted {
+            pid: to_pid.0,
+            slot: to_slot,
+            cap_id: new_cap_id,
+            object_type: source_cap.object_type as u8,
+            object_id: source_cap.object_id,
+            perms: new_perms.to_byte(),
         };
 
-        Ok((to_slot, vec![commit]))
+        let mut commits: Vec<Commit> = [granted, inserted]
+            .into_iter()
+            .map(|commit_type| Commit {
+                id: [0u8; 32],
+                prev_commit: [0u8; 32],
+                seq: 0,
+                timestamp,
+                commit_type,
+                caused_by: None,
+            })
+            .collect();
+        if badge != 0 {
+            commits.push(create_cap_badged_commit(to_pid, to_slot, badge, timestamp));
+        }
+        if expires_at != 0 {
+            commits.push(Commit {
+                id: [0u8; 32],
+                prev_commit: [0u8; 32],
+                seq: 0,
+                timestamp,
+                commit_type: CommitType::CapExpiry {
+                    pid: to_pid.0,
+                    slot: to_slot,
+                    expires_at,
+                },
+                caused_by: None,
+            });
+        }
+        Ok((to_slot, commits))
     }
 }
 
@@ -387,8 +707,31 @@ fn attenuate_permissions(source: &Permissions, requested: &Permissions) -> Permi
     }
 }
 
+/// Absolute expiry for a requested lifetime, if any.
+///
+/// A zero TTL is rejected: `expires_at == 0` already means "never expires".
+fn ttl_expiry(ttl_ns: Option<u64>, timestamp: u64) -> Result<Option<u64>, KernelError> {
+    match ttl_ns {
+        None => Ok(None),
+        Some(0) => Err(KernelError::InvalidArgument),
+        Some(ttl) => Ok(Some(timestamp.saturating_add(ttl))),
+    }
+}
+
+/// Expiry of a capability created from one expiring at `source_expires_at`.
+///
+/// A copy can never outlive its source, so a requested expiry is clamped
+/// to the source's.
+fn child_expiry(source_expires_at: u64, requested: Option<u64>) -> u64 {
+    match (source_expires_at, requested) {
+        (source, None) => source,
+        (0, Some(requested)) => requested,
+        (source, Some(requested)) => source.min(requested),
+    }
+}
+
 /// Create a CapRemoved commit
-fn create_cap_removed_commit(pid: ProcessId, slot: CapSlot, timestamp: u64) -> Commit {
+pub(super) fn create_cap_removed_commit(pid: ProcessId, slot: CapSlot, timestamp: u64) -> Commit {
     Commit {
         id: [0u8; 32],
         prev_commit: [0u8; 32],
@@ -398,3 +741,24 @@ fn create_cap_removed_commit(pid: ProcessId, slot: CapSlot, timestamp: u64) -> C
         caused_by: None,
     }
 }
+
+/// Create a CapBadged commit for a badged capability just inserted at `slot`
+pub(super) fn create_cap_badged_commit(
+    pid: ProcessId,
+    slot: CapSlot,
+    badge: u64,
+    timestamp: u64,
+) -> Commit {
+    Commit {
+        id: [0u8; 32],
+        prev_commit: [0u8; 32],
+        seq: 0,
+        timestamp,
+        commit_type: CommitType::CapBadged {
+            pid: pid.0,
+            slot,
+            badge,
+        },
+        caused_by: None,
+    }
+}
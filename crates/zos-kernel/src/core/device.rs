@@ -0,0 +1,459 @@
+//! IRQ and I/O port capabilities for KernelCore.
+//!
+//! This module contains methods for:
+//! - Minting IRQ and I/O port capabilities (Init hands them to drivers)
+//! - Binding an IRQ line to a notification or endpoint
+//! - Acknowledging and unbinding IRQ lines
+//! - Checked I/O port access through the HAL
+//! - Delivering hardware interrupts to the bound driver
+//!
+//! Delivery masks the line until the driver acknowledges it, so a
+//! level-triggered device cannot flood its driver. The HAL is told about
+//! every bind, acknowledge and unbind so the hardware line matches.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::error::KernelError;
+use crate::ipc::Message;
+use crate::types::{
+    CapSlot, EndpointId, IoPortRange, IrqBinding, IrqTarget, ObjectType, ProcessId, QuotaResource,
+};
+use crate::{axiom_check, Capability, CapabilitySpace, Permissions};
+use zos_axiom::{Commit, CommitType};
+use zos_hal::HAL;
+use zos_ipc::kernel::MSG_IRQ;
+
+use super::{map_axiom_error, KernelCore};
+
+impl<H: HAL> KernelCore<H> {
+    /// Mint a full capability for IRQ line `irq` into a process's CSpace.
+    ///
+    /// Returns (Result<CapSlot, KernelError>, Vec<Commit>).
+    pub fn mint_irq_cap(
+        &mut self,
+        pid: ProcessId,
+        irq: u8,
+        timestamp: u64,
+    ) -> (Result<CapSlot, KernelError>, Vec<Commit>) {
+        self.mint_device_cap(pid, ObjectType::Irq, irq as u64, timestamp)
+    }
+
+    /// Mint a full capability for an I/O port range into a process's CSpace.
+    ///
+    /// The range must be non-empty and must not run past port 0xFFFF.
+    ///
+    /// Returns (Result<CapSlot, KernelError>, Vec<Commit>).
+    pub fn mint_io_port_cap(
+        &mut self,
+        pid: ProcessId,
+        range: IoPortRange,
+        timestamp: u64,
+    ) -> (Result<CapSlot, KernelError>, Vec<Commit>) {
+        if range.len == 0 || range.base as u32 + range.len as u32 > 0x1_0000 {
+            return (Err(KernelError::InvalidArgument), Vec::new());
+        }
+        self.mint_device_cap(pid, ObjectType::IoPort, range.to_object_id(), timestamp)
+    }
+
+    /// Deliver IRQ line `irq` to the notification behind `notification_slot`
+    /// by OR-ing in `bits` (IRQ cap needs read, notification needs write).
+    ///
+    /// Returns (Result<(), KernelError>, Vec<Commit>).
+    pub fn bind_irq_notification(
+        &mut self,
+        pid: ProcessId,
+        irq_slot: CapSlot,
+        notification_slot: CapSlot,
+        bits: u64,
+        timestamp: u64,
+    ) -> (Result<(), KernelError>, Vec<Commit>) {
+        if bits == 0 {
+            return (Err(KernelError::InvalidArgument), Vec::new());
+        }
+        let required = Permissions::write_only();
+        let target = self
+            .notification_for_slot(pid, notification_slot, &required, timestamp)
+            .map(|id| IrqTarget::Notification { id, bits });
+        self.bind_irq(pid, irq_slot, target, timestamp)
+    }
+
+    /// Deliver IRQ line `irq` to the endpoint behind `endpoint_slot` as
+    /// `MSG_IRQ` messages (IRQ cap needs read, endpoint needs write).
+    ///
+    /// Returns (Result<(), KernelError>, Vec<Commit>).
+    pub fn bind_irq_endpoint(
+        &mut self,
+        pid: ProcessId,
+        irq_slot: CapSlot,
+        endpoint_slot: CapSlot,
+        timestamp: u64,
+    ) -> (Result<(), KernelError>, Vec<Commit>) {
+        let target = self
+            .validate_send_cap(pid, endpoint_slot, timestamp)
+            .and_then(|(id, _)| match self.endpoints.contains_key(&id) {
+                true => Ok(IrqTarget::Endpoint(id)),
+                false => Err(KernelError::EndpointNotFound),
+            });
+        self.bind_irq(pid, irq_slot, target, timestamp)
+    }
+
+    /// Unmask a bound line after handling its interrupt (IRQ cap needs read).
+    ///
+    /// Acknowledging a line that is not masked is harmless and records
+    /// nothing.
+    ///
+    /// Returns (Result<(), KernelError>, Vec<Commit>).
+    pub fn ack_irq(
+        &mut self,
+        pid: ProcessId,
+        irq_slot: CapSlot,
+        timestamp: u64,
+    ) -> (Result<(), KernelError>, Vec<Commit>) {
+        let irq = match self.irq_for_slot(pid, irq_slot, &Permissions::read_only(), timestamp) {
+            Ok(irq) => irq,
+            Err(e) => return (Err(e), Vec::new()),
+        };
+        let masked = match self.irq_bindings.get(&irq) {
+            Some(binding) if binding.owner == pid => binding.masked,
+            _ => return (Err(KernelError::InvalidArgument), Vec::new()),
+        };
+        if !masked {
+            return (Ok(()), Vec::new());
+        }
+        if let Err(e) = self.hal.irq_enable(irq) {
+            return (Err(e.into()), Vec::new());
+        }
+
+        if let Some(binding) = self.irq_bindings.get_mut(&irq) {
+            binding.masked = false;
+        }
+        (
+            Ok(()),
+            vec![device_commit(CommitType::IrqAcked { irq }, timestamp)],
+        )
+    }
+
+    /// Stop delivering the line behind `irq_slot` (no rights needed).
+    ///
+    /// Returns (Result<(), KernelError>, Vec<Commit>).
+    pub fn unbind_irq(
+        &mut self,
+        pid: ProcessId,
+        irq_slot: CapSlot,
+        timestamp: u64,
+    ) -> (Result<(), KernelError>, Vec<Commit>) {
+        let irq = match self.irq_for_slot(pid, irq_slot, &Permissions::default(), timestamp) {
+            Ok(irq) => irq,
+            Err(e) => return (Err(e), Vec::new()),
+        };
+        match self.irq_bindings.get(&irq) {
+            Some(binding) if binding.owner == pid => {
+                (Ok(()), vec![self.remove_irq_binding(irq, timestamp)])
+            }
+            _ => (Err(KernelError::InvalidArgument), Vec::new()),
+        }
+    }
+
+    /// Read `width` (1, 2 or 4) bytes from `port` through the HAL.
+    ///
+    /// The port capability needs read and must cover the whole access.
+    pub fn io_port_read(
+        &self,
+        pid: ProcessId,
+        port_slot: CapSlot,
+        port: u16,
+        width: u8,
+        timestamp: u64,
+    ) -> Result<u32, KernelError> {
+        let required = Permissions::read_only();
+        self.check_io_port(pid, port_slot, port, width, &required, timestamp)?;
+        Ok(self.hal.io_port_read(port, width)?)
+    }
+
+    /// Write `width` (1, 2 or 4) bytes to `port` through the HAL.
+    ///
+    /// The port capability needs write and must cover the whole access.
+    pub fn io_port_write(
+        &self,
+        pid: ProcessId,
+        port_slot: CapSlot,
+        port: u16,
+        width: u8,
+        value: u32,
+        timestamp: u64,
+    ) -> Result<(), KernelError> {
+        let required = Permissions::write_only();
+        self.check_io_port(pid, port_slot, port, width, &required, timestamp)?;
+        Ok(self.hal.io_port_write(port, width, value)?)
+    }
+
+    /// Deliver a hardware interrupt on `irq` to its bound driver.
+    ///
+    /// This is the entry point for interrupts the runtime takes from the
+    /// HAL; it is not a syscall. Unbound and masked lines are ignored. On
+    /// delivery the line stays masked until the driver calls `ack_irq`.
+    pub fn interrupt(&mut self, irq: u8, timestamp: u64) -> Vec<Commit> {
+        let target = match self.irq_bindings.get(&irq) {
+            Some(binding) if !binding.masked => binding.target,
+            _ => return Vec::new(),
+        };
+
+        let delivered = match target {
+            IrqTarget::Notification { id, bits } => {
+                self.notifications.get_mut(&id).map(|notification| {
+                    notification.signal(bits);
+                    CommitType::NotificationSignaled {
+                        from: 0,
+                        id: id.0,
+                        bits,
+                    }
+                })
+            }
+            IrqTarget::Endpoint(id) => self.queue_irq_message(id, irq),
+        };
+        let Some(commit_type) = delivered else {
+            // The target is gone; the HAL masked the line when it fired,
+            // so unmask it until the driver rebinds or unbinds
+            let _ = self.hal.irq_enable(irq);
+            return Vec::new();
+        };
+
+        if let Some(binding) = self.irq_bindings.get_mut(&irq) {
+            binding.masked = true;
+        }
+        vec![
+            device_commit(commit_type, timestamp),
+            device_commit(CommitType::IrqRaised { irq }, timestamp),
+        ]
+    }
+
+    /// Get the binding of an IRQ line
+    pub fn get_irq_binding(&self, irq: u8) -> Option<&IrqBinding> {
+        self.irq_bindings.get(&irq)
+    }
+
+    /// Drop every IRQ binding held by a dying process.
+    pub(crate) fn unbind_irqs(&mut self, pid: ProcessId, timestamp: u64) -> Vec<Commit> {
+        let owned: Vec<u8> = self
+            .irq_bindings
+            .iter()
+            .filter(|(_, binding)| binding.owner == pid)
+            .map(|(&irq, _)| irq)
+            .collect();
+        owned
+            .into_iter()
+            .map(|irq| self.remove_irq_binding(irq, timestamp))
+            .collect()
+    }
+
+    /// Unbind `irq` if `pid` bound it and no longer holds any capability
+    /// for the line.
+    pub(crate) fn unbind_dropped_irq(
+        &mut self,
+        pid: ProcessId,
+        irq: u8,
+        timestamp: u64,
+    ) -> Option<Commit> {
+        let owned = matches!(self.irq_bindings.get(&irq), Some(b) if b.owner == pid);
+        let still_held = self
+            .cap_spaces
+            .get(&pid)
+            .is_some_and(|cspace| find_irq_cap(cspace, irq).is_some());
+        if owned && !still_held {
+            Some(self.remove_irq_binding(irq, timestamp))
+        } else {
+            None
+        }
+    }
+
+    /// Insert a full device capability and record it.
+    fn mint_device_cap(
+        &mut self,
+        pid: ProcessId,
+        object_type: ObjectType,
+        object_id: u64,
+        timestamp: u64,
+    ) -> (Result<CapSlot, KernelError>, Vec<Commit>) {
+        if !self.cap_spaces.contains_key(&pid) {
+            return (Err(KernelError::ProcessNotFound), Vec::new());
+        }
+        if let Some(commit) = self.quota_violation(pid, QuotaResource::CapSlots, 1, timestamp) {
+            return (Err(KernelError::ResourceExhausted), vec![commit]);
+        }
+
+        let cap_id = self.next_cap_id();
+        let perms = Permissions::full();
+        let cap = Capability {
+            id: cap_id,
+            object_type,
+            object_id,
+            permissions: perms,
+            generation: 0,
+            expires_at: 0,
+            badge: 0,
+        };
+        let slot = match self.cap_spaces.get_mut(&pid) {
+            Some(cspace) => cspace.insert(cap),
+            None => return (Err(KernelError::ProcessNotFound), Vec::new()),
+        };
+
+        let commit = device_commit(
+            CommitType::CapInserted {
+                pid: pid.0,
+                slot,
+                cap_id,
+                object_type: object_type as u8,
+                object_id,
+                perms: perms.to_byte(),
+            },
+            timestamp,
+        );
+        (Ok(slot), vec![commit])
+    }
+
+    /// Shared tail of the bind methods, once the target has been checked.
+    ///
+    /// A line bound by another driver is refused; the same driver may rebind
+    /// to move delivery elsewhere.
+    fn bind_irq(
+        &mut self,
+        pid: ProcessId,
+        irq_slot: CapSlot,
+        target: Result<IrqTarget, KernelError>,
+        timestamp: u64,
+    ) -> (Result<(), KernelError>, Vec<Commit>) {
+        let bound = target.and_then(|target| {
+            let irq = self.irq_for_slot(pid, irq_slot, &Permissions::read_only(), timestamp)?;
+            match self.irq_bindings.get(&irq) {
+                Some(binding) if binding.owner != pid => Err(KernelError::InvalidArgument),
+                _ => Ok((irq, target)),
+            }
+        });
+        let (irq, target) = match bound {
+            Ok(bound) => bound,
+            Err(e) => return (Err(e), Vec::new()),
+        };
+        if let Err(e) = self.hal.irq_enable(irq) {
+            return (Err(e.into()), Vec::new());
+        }
+
+        self.irq_bindings.insert(
+            irq,
+            IrqBinding {
+                owner: pid,
+                target,
+                masked: false,
+            },
+        );
+
+        let (target_type, target_id, bits) = target.to_parts();
+        let commit = device_commit(
+            CommitType::IrqBound {
+                irq,
+                pid: pid.0,
+                target_type,
+                target_id,
+                bits,
+            },
+            timestamp,
+        );
+
+        self.hal.debug_write(&alloc::format!(
+            "[kernel] PID {} bound IRQ {}",
+            pid.0,
+            irq
+        ));
+
+        (Ok(()), vec![commit])
+    }
+
+    /// Remove a binding and mask its line.
+    fn remove_irq_binding(&mut self, irq: u8, timestamp: u64) -> Commit {
+        self.irq_bindings.remove(&irq);
+        self.hal.irq_disable(irq);
+        device_commit(CommitType::IrqUnbound { irq }, timestamp)
+    }
+
+    /// Queue a `MSG_IRQ` message from the kernel on an endpoint.
+    fn queue_irq_message(&mut self, id: EndpointId, irq: u8) -> Option<CommitType> {
+        let message = Message {
+            from: ProcessId(0),
+            badge: 0,
+            tag: MSG_IRQ,
+            data: vec![irq],
+            transferred_caps: vec![],
+        };
+        self.queue_kernel_message(id, message).ok()?;
+        Some(CommitType::MessageSent {
+            from_pid: 0,
+            to_endpoint: id.0,
+            tag: MSG_IRQ,
+            size: 1,
+        })
+    }
+
+    /// Check an IRQ capability and return the line it names.
+    fn irq_for_slot(
+        &self,
+        pid: ProcessId,
+        slot: CapSlot,
+        required: &Permissions,
+        timestamp: u64,
+    ) -> Result<u8, KernelError> {
+        let cspace = self
+            .cap_spaces
+            .get(&pid)
+            .ok_or(KernelError::ProcessNotFound)?;
+        let cap = axiom_check(cspace, slot, required, Some(ObjectType::Irq), timestamp)
+            .map_err(map_axiom_error)?;
+        Ok(cap.object_id as u8)
+    }
+
+    /// Authorize a port access: the width must be 1, 2 or 4 and the whole
+    /// access must fall inside the capability's port range.
+    fn check_io_port(
+        &self,
+        pid: ProcessId,
+        port_slot: CapSlot,
+        port: u16,
+        width: u8,
+        required: &Permissions,
+        timestamp: u64,
+    ) -> Result<(), KernelError> {
+        if !matches!(width, 1 | 2 | 4) {
+            return Err(KernelError::InvalidArgument);
+        }
+        let cspace = self
+            .cap_spaces
+            .get(&pid)
+            .ok_or(KernelError::ProcessNotFound)?;
+        let cap = axiom_check(cspace, port_slot, required, Some(ObjectType::IoPort), timestamp)
+            .map_err(map_axiom_error)?;
+        if !IoPortRange::from_object_id(cap.object_id).contains(port, width) {
+            return Err(KernelError::PermissionDenied);
+        }
+        Ok(())
+    }
+}
+
+/// Slot of an IRQ capability for `irq`, if the CSpace holds one.
+fn find_irq_cap(cspace: &CapabilitySpace, irq: u8) -> Option<CapSlot> {
+    cspace
+        .slots
+        .iter()
+        .find(|(_, cap)| cap.object_type == ObjectType::Irq && cap.object_id == irq as u64)
+        .map(|(&slot, _)| slot)
+}
+
+/// Wrap a device commit type in an unsequenced Commit
+fn device_commit(commit_type: CommitType, timestamp: u64) -> Commit {
+    Commit {
+        id: [0u8; 32],
+        prev_commit: [0u8; 32],
+        seq: 0,
+        timestamp,
+        commit_type,
+        caused_by: None,
+    }
+}
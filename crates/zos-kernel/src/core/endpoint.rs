@@ -5,13 +5,15 @@
 //! - Listing endpoints
 //! - Getting endpoint details
 
-use alloc::collections::VecDeque;
 use alloc::vec;
 use alloc::vec::Vec;
 
 use crate::error::KernelError;
-use crate::ipc::{Endpoint, EndpointDetail, EndpointInfo, MessageSummary};
-use crate::types::{CapSlot, EndpointId, EndpointMetrics, ObjectType, ProcessId};
+use crate::ipc::{
+    Endpoint, EndpointDetail, EndpointInfo, MessageSummary, OverflowPolicy, DEFAULT_QUEUE_CAPACITY,
+    MAX_QUEUE_CAPACITY,
+};
+use crate::types::{CapSlot, EndpointId, ObjectType, ProcessId, QuotaResource};
 use crate::{Capability, Permissions};
 use zos_axiom::{Commit, CommitType};
 use zos_hal::HAL;
@@ -19,13 +21,29 @@ use zos_hal::HAL;
 use super::KernelCore;
 
 impl<H: HAL> KernelCore<H> {
-    /// Create an IPC endpoint owned by a process.
+    /// Create an IPC endpoint owned by a process, with the default queue
+    /// capacity and `Reject` policy.
     ///
     /// Returns (Result<(EndpointId, CapSlot), KernelError>, Vec<Commit>).
     pub fn create_endpoint(
         &mut self,
         owner: ProcessId,
         timestamp: u64,
+    ) -> (Result<(EndpointId, CapSlot), KernelError>, Vec<Commit>) {
+        let policy = OverflowPolicy::Reject;
+        self.create_endpoint_with_capacity(owner, DEFAULT_QUEUE_CAPACITY, policy, timestamp)
+    }
+
+    /// Create an IPC endpoint whose queue holds at most `capacity` messages
+    /// (1..=`MAX_QUEUE_CAPACITY`).
+    ///
+    /// Returns (Result<(EndpointId, CapSlot), KernelError>, Vec<Commit>).
+    pub fn create_endpoint_with_capacity(
+        &mut self,
+        owner: ProcessId,
+        capacity: usize,
+        policy: OverflowPolicy,
+        timestamp: u64,
     ) -> (Result<(EndpointId, CapSlot), KernelError>, Vec<Commit>) {
         let mut commits = Vec::new();
 
@@ -33,16 +51,22 @@ impl<H: HAL> KernelCore<H> {
             return (Err(KernelError::ProcessNotFound), commits);
         }
 
+        if capacity == 0 || capacity > MAX_QUEUE_CAPACITY {
+            return (Err(KernelError::InvalidArgument), commits);
+        }
+
+        // The endpoint and the owner's capability both count against quota
+        for resource in [QuotaResource::Endpoints, QuotaResource::CapSlots] {
+            if let Some(commit) = self.quota_violation(owner, resource, 1, timestamp) {
+                return (Err(KernelError::ResourceExhausted), vec![commit]);
+            }
+        }
+
         let id = EndpointId(self.next_endpoint_id);
         self.next_endpoint_id += 1;
 
         // Create and insert the endpoint
-        let endpoint = Endpoint {
-            id,
-            owner,
-            pending_messages: VecDeque::new(),
-            metrics: EndpointMetrics::default(),
-        };
+        let endpoint = Endpoint::with_capacity(id, owner, capacity, policy);
         self.endpoints.insert(id, endpoint);
 
         // Grant full capability to owner
@@ -144,6 +168,7 @@ impl<H: HAL> KernelCore<H> {
             permissions: perms,
             generation: 0,
             expires_at: 0, // Never expires
+            badge: 0,
         };
 
         let cspace = self
@@ -5,18 +5,20 @@
 //! - Receiving messages (with and without capability transfer)
 //! - Checking for pending messages
 //! - Direct process-to-process messaging (supervisor override)
+//! - Kernel notifications (child exits, revocations)
 
 use alloc::vec;
 use alloc::vec::Vec;
 
 use crate::axiom_check;
 use crate::error::KernelError;
-use crate::ipc::{Message, TransferredCap, MAX_CAPS_PER_MESSAGE, MAX_MESSAGE_SIZE};
-use crate::types::{CapSlot, EndpointId, ObjectType, ProcessId};
+use crate::ipc::{Message, OverflowPolicy, TransferredCap, MAX_CAPS_PER_MESSAGE, MAX_MESSAGE_SIZE};
+use crate::types::{CapSlot, EndpointId, ObjectType, ProcessId, QuotaResource, RegionId};
 use crate::Permissions;
 use zos_axiom::{Commit, CommitType};
 use zos_hal::HAL;
 
+use super::capability::create_cap_badged_commit;
 use super::{map_axiom_error, KernelCore};
 
 /// Result type for IPC receive with capability transfer
@@ -28,7 +30,8 @@ type ReceiveWithCapsResult = (
 impl<H: HAL> KernelCore<H> {
     /// Send IPC message (validates capability via axiom_check).
     ///
-    /// Returns (Result<(), KernelError>, Option<Commit>) - optional MessageSent commit.
+    /// Returns (Result<(), KernelError>, Option<Commit>) - the MessageSent commit,
+    /// or the QuotaExceeded commit if the sender's queued-bytes quota refused it.
     pub fn ipc_send(
         &mut self,
         from_pid: ProcessId,
@@ -38,16 +41,26 @@ impl<H: HAL> KernelCore<H> {
         timestamp: u64,
     ) -> (Result<(), KernelError>, Option<Commit>) {
         // Validate endpoint capability
-        let endpoint_id = match self.validate_send_cap(from_pid, endpoint_slot, timestamp) {
-            Ok(id) => id,
+        let (endpoint_id, badge) = match self.validate_send_cap(from_pid, endpoint_slot, timestamp)
+        {
+            Ok(target) => target,
             Err(e) => return (Err(e), None),
         };
 
         let data_len = data.len();
+        if let Some(commit) = self.quota_violation(
+            from_pid,
+            QuotaResource::QueuedBytes,
+            data_len as u64,
+            timestamp,
+        ) {
+            return (Err(KernelError::ResourceExhausted), Some(commit));
+        }
 
-        // Queue message
+        // Queue message, stamped with the badge of the cap it was sent through
         let message = Message {
             from: from_pid,
+            badge,
             tag,
             data,
             transferred_caps: vec![],
@@ -58,7 +71,7 @@ impl<H: HAL> KernelCore<H> {
         }
 
         // Update metrics
-        self.update_send_metrics(from_pid, endpoint_id, data_len, timestamp);
+        self.update_send_metrics(from_pid, data_len, timestamp);
 
         // Create MessageSent commit
         let commit = Commit {
@@ -101,14 +114,32 @@ impl<H: HAL> KernelCore<H> {
         }
 
         // Lookup and validate endpoint capability
-        let endpoint_id = match self.validate_send_cap_basic(from_pid, endpoint_slot) {
-            Ok(id) => id,
+        let (endpoint_id, badge) = match self.validate_send_cap_basic(from_pid, endpoint_slot) {
+            Ok(target) => target,
             Err(e) => return (Err(e), commits),
         };
 
-        // Verify endpoint exists
-        if !self.endpoints.contains_key(&endpoint_id) {
-            return (Err(KernelError::EndpointNotFound), commits);
+        // Verify endpoint exists and has room, before any capability leaves
+        // the sender
+        match self.endpoints.get_mut(&endpoint_id) {
+            None => return (Err(KernelError::EndpointNotFound), commits),
+            Some(endpoint) if endpoint.is_full() => {
+                if endpoint.policy == OverflowPolicy::Reject {
+                    endpoint.metrics.dropped_messages += 1;
+                }
+                return (Err(queue_full_error(endpoint.policy)), commits);
+            }
+            Some(_) => {}
+        }
+
+        if let Some(commit) = self.quota_violation(
+            from_pid,
+            QuotaResource::QueuedBytes,
+            data.len() as u64,
+            timestamp,
+        ) {
+            commits.push(commit);
+            return (Err(KernelError::ResourceExhausted), commits);
         }
 
         // Validate all capabilities exist before removing any
@@ -125,10 +156,16 @@ impl<H: HAL> KernelCore<H> {
         commits.extend(cap_commits);
 
         let data_len = data.len();
+        let regions: Vec<RegionId> = transferred_caps
+            .iter()
+            .filter(|t| t.capability.object_type == ObjectType::Memory)
+            .map(|t| RegionId(t.capability.object_id))
+            .collect();
 
         // Queue message with transferred capabilities
         let message = Message {
             from: from_pid,
+            badge,
             tag,
             data,
             transferred_caps,
@@ -138,8 +175,14 @@ impl<H: HAL> KernelCore<H> {
             return (Err(e), commits);
         }
 
+        // A sender that gave away its last capability for a region loses
+        // its mapping
+        for id in regions {
+            commits.extend(self.release_dropped_region(from_pid, id, timestamp));
+        }
+
         // Update metrics
-        self.update_send_metrics(from_pid, endpoint_id, data_len, timestamp);
+        self.update_send_metrics(from_pid, data_len, timestamp);
 
         (Ok(()), commits)
     }
@@ -229,13 +272,15 @@ impl<H: HAL> KernelCore<H> {
     // Private helper methods
     // ========================================================================
 
-    /// Validate send capability using axiom_check
-    fn validate_send_cap(
+    /// Validate send capability using axiom_check.
+    ///
+    /// Returns the endpoint and the badge to stamp on the message.
+    pub(super) fn validate_send_cap(
         &self,
         from_pid: ProcessId,
         endpoint_slot: CapSlot,
         timestamp: u64,
-    ) -> Result<EndpointId, KernelError> {
+    ) -> Result<(EndpointId, u64), KernelError> {
         let cspace = self
             .cap_spaces
             .get(&from_pid)
@@ -250,7 +295,7 @@ impl<H: HAL> KernelCore<H> {
         )
         .map_err(map_axiom_error)?;
 
-        Ok(EndpointId(cap.object_id))
+        Ok((EndpointId(cap.object_id), cap.badge))
     }
 
     /// Validate send capability without axiom_check (for send_with_caps)
@@ -258,7 +303,7 @@ impl<H: HAL> KernelCore<H> {
         &self,
         from_pid: ProcessId,
         endpoint_slot: CapSlot,
-    ) -> Result<EndpointId, KernelError> {
+    ) -> Result<(EndpointId, u64), KernelError> {
         let cspace = self
             .cap_spaces
             .get(&from_pid)
@@ -272,7 +317,7 @@ impl<H: HAL> KernelCore<H> {
             return Err(KernelError::PermissionDenied);
         }
 
-        Ok(EndpointId(cap.object_id))
+        Ok((EndpointId(cap.object_id), cap.badge))
     }
 
     /// Validate receive capability using axiom_check
@@ -396,13 +441,21 @@ impl<H: HAL> KernelCore<H> {
                 },
                 caused_by: None,
             });
+            if tcap.capability.badge != 0 {
+                commits.push(create_cap_badged_commit(
+                    pid,
+                    slot,
+                    tcap.capability.badge,
+                    timestamp,
+                ));
+            }
         }
 
         Ok((installed_slots, commits))
     }
 
     /// Queue a message to an endpoint
-    fn queue_message(
+    pub(super) fn queue_message(
         &mut self,
         endpoint_id: EndpointId,
         message: Message,
@@ -412,28 +465,77 @@ impl<H: HAL> KernelCore<H> {
             .get_mut(&endpoint_id)
             .ok_or(KernelError::EndpointNotFound)?;
 
-        endpoint.pending_messages.push_back(message);
-        Ok(())
+        endpoint
+            .enqueue(message)
+            .map_err(|_| queue_full_error(endpoint.policy))
     }
 
-    /// Update metrics after sending a message
-    fn update_send_metrics(
+    /// Queue a kernel message to an endpoint, using the kernel reserve past
+    /// its capacity
+    pub(super) fn queue_kernel_message(
         &mut self,
-        from_pid: ProcessId,
         endpoint_id: EndpointId,
-        data_len: usize,
+        message: Message,
+    ) -> Result<(), KernelError> {
+        let endpoint = self
+            .endpoints
+            .get_mut(&endpoint_id)
+            .ok_or(KernelError::EndpointNotFound)?;
+
+        endpoint
+            .enqueue_kernel(message)
+            .map_err(|_| KernelError::QueueFull)
+    }
+
+    /// Queue a kernel message on `pid`'s first endpoint.
+    ///
+    /// The kernel (PID 0) is the sender, so no capability check applies.
+    /// Missing processes and processes without an endpoint are not
+    /// notified. Returns the MessageSent commit if the message was queued.
+    pub(crate) fn notify_process(
+        &mut self,
+        pid: ProcessId,
+        tag: u32,
+        data: Vec<u8>,
         timestamp: u64,
-    ) {
-        // Update endpoint metrics
-        if let Some(endpoint) = self.endpoints.get_mut(&endpoint_id) {
-            endpoint.metrics.queue_depth = endpoint.pending_messages.len();
-            endpoint.metrics.total_messages += 1;
-            endpoint.metrics.total_bytes += data_len as u64;
-            if endpoint.metrics.queue_depth > endpoint.metrics.queue_high_water {
-                endpoint.metrics.queue_high_water = endpoint.metrics.queue_depth;
-            }
+    ) -> Option<Commit> {
+        if !self.processes.contains_key(&pid) {
+            return None;
         }
+        let endpoint_id = self
+            .endpoints
+            .values()
+            .find(|ep| ep.owner == pid)
+            .map(|ep| ep.id)?;
 
+        let size = data.len();
+        let message = Message {
+            from: ProcessId(0),
+            badge: 0,
+            tag,
+            data,
+            transferred_caps: vec![],
+        };
+        self.queue_kernel_message(endpoint_id, message).ok()?;
+
+        Some(Commit {
+            id: [0u8; 32],
+            prev_commit: [0u8; 32],
+            seq: 0,
+            timestamp,
+            commit_type: CommitType::MessageSent {
+                from_pid: 0,
+                to_endpoint: endpoint_id.0,
+                tag,
+                size,
+            },
+            caused_by: None,
+        })
+    }
+
+    /// Update sender metrics after sending a message (the endpoint keeps
+    /// its own as messages are queued)
+    fn update_send_metrics(&mut self, from_pid: ProcessId, data_len: usize, timestamp: u64) {
         // Update sender process metrics
         if let Some(sender) = self.processes.get_mut(&from_pid) {
             sender.metrics.ipc_sent += 1;
@@ -446,6 +548,17 @@ impl<H: HAL> KernelCore<H> {
     }
 }
 
+/// Error for a send that found the endpoint queue full.
+///
+/// Under `Block` the sender gets `WouldBlock` and retries later, just like
+/// a receive on an empty queue.
+fn queue_full_error(policy: OverflowPolicy) -> KernelError {
+    match policy {
+        OverflowPolicy::Reject => KernelError::QueueFull,
+        OverflowPolicy::Block => KernelError::WouldBlock,
+    }
+}
+
 /// Validate message size and cap count limits
 fn validate_message_limits(data_len: usize, cap_count: usize) -> Result<(), KernelError> {
     if data_len > MAX_MESSAGE_SIZE {
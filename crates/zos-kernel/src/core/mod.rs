@@ -3,25 +3,42 @@
 //! This module contains the core kernel state and its method implementations,
 //! split into logical submodules:
 //!
-//! - `process` - Process lifecycle (register, kill, fault)
+//! - `process` - Process lifecycle (register, kill, fault, reap)
+//! - `call` - Call/Reply with one-shot reply capabilities
 //! - `endpoint` - Endpoint management (create, list, get)
 //! - `capability` - Capability operations (grant, revoke, derive, delete)
+//! - `device` - IRQ and I/O port capabilities for user-space drivers
 //! - `ipc` - IPC send/receive operations
+//! - `notification` - Notification create/signal/poll
+//! - `quota` - Resource quota accounting and enforcement
+//! - `region` - Shared memory regions (create, map, flush, refresh)
 //! - `syscall` - Syscall dispatch and handling
+//! - `wait` - Timed receives, notification and reply waits, parked processes
 
+mod call;
 mod capability;
+mod device;
 mod endpoint;
 mod ipc;
+mod notification;
 mod process;
+mod quota;
+mod region;
 mod syscall;
+mod wait;
 
 use alloc::collections::BTreeMap;
+use alloc::vec;
 use alloc::vec::Vec;
 
 use crate::error::KernelError;
-use crate::ipc::Endpoint;
-use crate::types::{EndpointId, Process, ProcessId, SystemMetrics};
-use crate::{AxiomError, CapabilitySpace};
+use crate::ipc::{CallState, Endpoint, Notification};
+use crate::types::{
+    EndpointId, ExitRecord, IrqBinding, NotificationId, Process, ProcessId, QuotaResource,
+    RegionId, SharedRegion, SystemMetrics,
+};
+use crate::{AxiomError, CapabilitySpace, DerivationTree};
+use zos_axiom::Commit;
 use zos_hal::HAL;
 
 /// The kernel core holds all mutable state.
@@ -33,22 +50,44 @@ use zos_hal::HAL;
 /// This pattern ensures all state-mutating operations flow through AxiomGateway,
 /// making Axiom-bypass violations impossible at compile time.
 pub struct KernelCore<H: HAL> {
-    /// HAL reference for debug output only (no state changes)
+    /// HAL reference for debug output and driver device access (no kernel
+    /// state lives there)
     hal: H,
     /// Process table
     pub(crate) processes: BTreeMap<ProcessId, Process>,
     /// Capability spaces (per-process)
     pub(crate) cap_spaces: BTreeMap<ProcessId, CapabilitySpace>,
+    /// Capability derivation tree (parent/child links by cap ID)
+    pub(crate) cap_derivations: DerivationTree,
     /// IPC endpoints
     pub(crate) endpoints: BTreeMap<EndpointId, Endpoint>,
+    /// Notification objects
+    pub(crate) notifications: BTreeMap<NotificationId, Notification>,
     /// Next process ID
     pub(crate) next_pid: u64,
     /// Next endpoint ID
     pub(crate) next_endpoint_id: u64,
+    /// Next notification ID
+    pub(crate) next_notification_id: u64,
     /// Next capability ID
     pub(crate) next_cap_id: u64,
     /// Total IPC messages since boot
     pub(crate) total_ipc_count: u64,
+    /// Pending power-off (`false`) or reboot (`true`) accepted from Init
+    pub(crate) shutdown_request: Option<bool>,
+    /// Timed waits of parked processes
+    pub(crate) waits: BTreeMap<ProcessId, wait::Wait>,
+    /// Outstanding calls, keyed by caller
+    pub(crate) calls: BTreeMap<ProcessId, CallState>,
+    /// Dead processes awaiting reaping by their parent. Like queued
+    /// messages these are volatile: replay and snapshots do not restore them
+    pub(crate) exited: BTreeMap<ProcessId, ExitRecord>,
+    /// IRQ lines bound by user-space drivers
+    pub(crate) irq_bindings: BTreeMap<u8, IrqBinding>,
+    /// Shared memory regions
+    pub(crate) regions: BTreeMap<RegionId, SharedRegion>,
+    /// Next region ID
+    pub(crate) next_region_id: u64,
 }
 
 impl<H: HAL> KernelCore<H> {
@@ -58,11 +97,21 @@ impl<H: HAL> KernelCore<H> {
             hal,
             processes: BTreeMap::new(),
             cap_spaces: BTreeMap::new(),
+            cap_derivations: DerivationTree::new(),
             endpoints: BTreeMap::new(),
+            notifications: BTreeMap::new(),
             next_pid: 1,
             next_endpoint_id: 1,
+            next_notification_id: 1,
             next_cap_id: 1,
             total_ipc_count: 0,
+            shutdown_request: None,
+            waits: BTreeMap::new(),
+            calls: BTreeMap::new(),
+            exited: BTreeMap::new(),
+            irq_bindings: BTreeMap::new(),
+            regions: BTreeMap::new(),
+            next_region_id: 1,
         }
     }
 
@@ -132,7 +181,16 @@ impl<H: HAL> KernelCore<H> {
     // ========================================================================
 
     /// Allocate memory to a process (simulated)
+    ///
+    /// Fails with `ResourceExhausted` if the allocation would exceed the
+    /// process's memory quota.
     pub fn allocate_memory(&mut self, pid: ProcessId, bytes: usize) -> Result<usize, KernelError> {
+        if self
+            .quota_violation(pid, QuotaResource::Memory, bytes as u64, 0)
+            .is_some()
+        {
+            return Err(KernelError::ResourceExhausted);
+        }
         let proc = self
             .processes
             .get_mut(&pid)
@@ -164,9 +222,26 @@ impl<H: HAL> KernelCore<H> {
     }
 
     /// Update process memory size (called when WASM memory grows)
-    pub fn update_process_memory(&mut self, pid: ProcessId, new_size: usize) {
-        if let Some(proc) = self.processes.get_mut(&pid) {
-            proc.metrics.memory_size = new_size;
+    ///
+    /// The growth has already happened, so the new size is always recorded;
+    /// `ResourceExhausted` tells the caller the process is now over its
+    /// memory quota and should be stopped.
+    ///
+    /// Returns (Result<(), KernelError>, Vec<Commit>).
+    pub fn update_process_memory(
+        &mut self,
+        pid: ProcessId,
+        new_size: usize,
+        timestamp: u64,
+    ) -> (Result<(), KernelError>, Vec<Commit>) {
+        let Some(proc) = self.processes.get_mut(&pid) else {
+            return (Err(KernelError::ProcessNotFound), Vec::new());
+        };
+        proc.metrics.memory_size = new_size;
+
+        match self.quota_violation(pid, QuotaResource::Memory, 0, timestamp) {
+            None => (Ok(()), Vec::new()),
+            Some(commit) => (Err(KernelError::ResourceExhausted), vec![commit]),
         }
     }
 }
@@ -0,0 +1,224 @@
+//! Notification objects for KernelCore.
+//!
+//! This module contains methods for:
+//! - Creating notifications
+//! - Signalling a notification (OR-ing bits into its word)
+//! - Polling a notification (taking its word without blocking)
+//!
+//! Waiting for a notification parks the caller like a timed receive and
+//! lives in `wait`.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::error::KernelError;
+use crate::ipc::Notification;
+use crate::types::{CapSlot, NotificationId, ObjectType, ProcessId, QuotaResource};
+use crate::{axiom_check, Capability, Permissions};
+use zos_axiom::{Commit, CommitType};
+use zos_hal::HAL;
+
+use super::{map_axiom_error, KernelCore};
+
+impl<H: HAL> KernelCore<H> {
+    /// Create a notification owned by a process.
+    ///
+    /// The owner gets a full capability to it.
+    ///
+    /// Returns (Result<(NotificationId, CapSlot), KernelError>, Vec<Commit>).
+    pub fn create_notification(
+        &mut self,
+        owner: ProcessId,
+        timestamp: u64,
+    ) -> (Result<(NotificationId, CapSlot), KernelError>, Vec<Commit>) {
+        if !self.processes.contains_key(&owner) {
+            return (Err(KernelError::ProcessNotFound), Vec::new());
+        }
+        if let Some(commit) = self.quota_violation(owner, QuotaResource::CapSlots, 1, timestamp) {
+            return (Err(KernelError::ResourceExhausted), vec![commit]);
+        }
+
+        let id = NotificationId(self.next_notification_id);
+        self.next_notification_id += 1;
+        self.notifications.insert(id, Notification::new(id, owner));
+
+        let cap_id = self.next_cap_id();
+        let perms = Permissions::full();
+        let cap = Capability {
+            id: cap_id,
+            object_type: ObjectType::Notification,
+            object_id: id.0,
+            permissions: perms,
+            generation: 0,
+            expires_at: 0,
+            badge: 0,
+        };
+        let slot = match self.cap_spaces.get_mut(&owner) {
+            Some(cspace) => cspace.insert(cap),
+            None => {
+                self.notifications.remove(&id);
+                return (Err(KernelError::ProcessNotFound), Vec::new());
+            }
+        };
+
+        let commits = vec![
+            notification_commit(
+                CommitType::NotificationCreated {
+                    id: id.0,
+                    owner: owner.0,
+                },
+                timestamp,
+            ),
+            notification_commit(
+                CommitType::CapInserted {
+                    pid: owner.0,
+                    slot,
+                    cap_id,
+                    object_type: ObjectType::Notification as u8,
+                    object_id: id.0,
+                    perms: perms.to_byte(),
+                },
+                timestamp,
+            ),
+        ];
+
+        self.hal.debug_write(&alloc::format!(
+            "[kernel] Created notification {} for PID {}, cap slot {}",
+            id.0,
+            owner.0,
+            slot
+        ));
+
+        (Ok((id, slot)), commits)
+    }
+
+    /// OR `bits` into the notification behind `slot` (needs write).
+    ///
+    /// Signalling never allocates: pending bits simply coalesce.
+    ///
+    /// Returns (Result<(), KernelError>, Vec<Commit>).
+    pub fn signal_notification(
+        &mut self,
+        pid: ProcessId,
+        slot: CapSlot,
+        bits: u64,
+        timestamp: u64,
+    ) -> (Result<(), KernelError>, Vec<Commit>) {
+        if bits == 0 {
+            return (Err(KernelError::InvalidArgument), Vec::new());
+        }
+        let id = match self.notification_for_slot(pid, slot, &Permissions::write_only(), timestamp)
+        {
+            Ok(id) => id,
+            Err(e) => return (Err(e), Vec::new()),
+        };
+
+        if let Some(notification) = self.notifications.get_mut(&id) {
+            notification.signal(bits);
+        }
+
+        let commit = notification_commit(
+            CommitType::NotificationSignaled {
+                from: pid.0,
+                id: id.0,
+                bits,
+            },
+            timestamp,
+        );
+        (Ok(()), vec![commit])
+    }
+
+    /// Take the pending word of the notification behind `slot` without
+    /// blocking (needs read). The word may be 0.
+    ///
+    /// Returns (Result<u64, KernelError>, Vec<Commit>).
+    pub fn poll_notification(
+        &mut self,
+        pid: ProcessId,
+        slot: CapSlot,
+        timestamp: u64,
+    ) -> (Result<u64, KernelError>, Vec<Commit>) {
+        let id = match self.notification_for_slot(pid, slot, &Permissions::read_only(), timestamp) {
+            Ok(id) => id,
+            Err(e) => return (Err(e), Vec::new()),
+        };
+
+        let bits = self
+            .notifications
+            .get_mut(&id)
+            .map(|n| n.take())
+            .unwrap_or(0);
+
+        // Clearing the word is a state change, so replay must see it
+        let commits = if bits != 0 {
+            vec![notification_commit(
+                CommitType::NotificationTaken {
+                    pid: pid.0,
+                    id: id.0,
+                    bits,
+                },
+                timestamp,
+            )]
+        } else {
+            Vec::new()
+        };
+        (Ok(bits), commits)
+    }
+
+    /// Check whether the notification behind `slot` has pending bits
+    /// (needs read).
+    pub fn notification_pending(
+        &self,
+        pid: ProcessId,
+        slot: CapSlot,
+        timestamp: u64,
+    ) -> Result<bool, KernelError> {
+        let id = self.notification_for_slot(pid, slot, &Permissions::read_only(), timestamp)?;
+        Ok(self.notifications.get(&id).is_some_and(|n| n.bits != 0))
+    }
+
+    /// Get notification by ID
+    pub fn get_notification(&self, id: NotificationId) -> Option<&Notification> {
+        self.notifications.get(&id)
+    }
+
+    /// Look up the notification behind a slot, checking the required rights.
+    pub(super) fn notification_for_slot(
+        &self,
+        pid: ProcessId,
+        slot: CapSlot,
+        required: &Permissions,
+        timestamp: u64,
+    ) -> Result<NotificationId, KernelError> {
+        let cspace = self
+            .cap_spaces
+            .get(&pid)
+            .ok_or(KernelError::ProcessNotFound)?;
+        let cap = axiom_check(
+            cspace,
+            slot,
+            required,
+            Some(ObjectType::Notification),
+            timestamp,
+        )
+        .map_err(map_axiom_error)?;
+
+        let id = NotificationId(cap.object_id);
+        if !self.notifications.contains_key(&id) {
+            return Err(KernelError::InvalidCapability);
+        }
+        Ok(id)
+    }
+}
+
+/// Wrap a notification commit type in an unsequenced Commit
+fn notification_commit(commit_type: CommitType, timestamp: u64) -> Commit {
+    Commit {
+        id: [0u8; 32],
+        prev_commit: [0u8; 32],
+        seq: 0,
+        timestamp,
+        commit_type,
+        caused_by: None,
+    }
+}
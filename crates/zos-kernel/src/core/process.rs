@@ -2,18 +2,24 @@
 //!
 //! This module contains methods for:
 //! - Registering new processes
-//! - Killing processes (with and without capability checks)
-//! - Recording process faults
+//! - Killing processes (with and without capability checks), alone or
+//!   with all of their descendants
+//! - Recording process exits and faults
+//! - Telling parents about dead children and reaping them
 
 use alloc::string::String;
 use alloc::vec;
 use alloc::vec::Vec;
 
 use crate::error::KernelError;
-use crate::types::{ObjectType, Process, ProcessId, ProcessMetrics, ProcessState};
+use crate::types::{
+    ExitRecord, ExitStatus, ObjectType, Process, ProcessId, ProcessMetrics, ProcessState,
+    ResourceQuota,
+};
 use crate::CapabilitySpace;
 use zos_axiom::{Commit, CommitType};
 use zos_hal::HAL;
+use zos_ipc::kernel::MSG_CHILD_EXITED;
 
 use super::KernelCore;
 
@@ -27,6 +33,9 @@ impl<H: HAL> KernelCore<H> {
 
     /// Register a process with a specific parent (for fork/spawn tracking).
     ///
+    /// The parent is sent `MSG_CHILD_EXITED` when the child dies and may
+    /// then reap it with `wait_child`.
+    ///
     /// Returns (ProcessId, Vec<Commit>) - the commits describe the mutation.
     pub fn register_process_with_parent(
         &mut self,
@@ -37,7 +46,7 @@ impl<H: HAL> KernelCore<H> {
         let pid = ProcessId(self.next_pid);
         self.next_pid += 1;
 
-        let process = self.create_process_entry(pid, name, timestamp);
+        let process = self.create_process_entry(pid, parent, name, timestamp);
         self.processes.insert(pid, process);
         self.cap_spaces.insert(pid, CapabilitySpace::new());
 
@@ -79,8 +88,10 @@ impl<H: HAL> KernelCore<H> {
         // Supervisor processes have no initial memory allocation
         let process = Process {
             pid,
+            parent: ProcessId(0),
             name: String::from(name),
             state: ProcessState::Running,
+            quota: ResourceQuota::default(),
             metrics: ProcessMetrics {
                 memory_size: 0,
                 ipc_sent: 0,
@@ -90,6 +101,8 @@ impl<H: HAL> KernelCore<H> {
                 syscall_count: 0,
                 last_active_ns: timestamp,
                 start_time_ns: timestamp,
+                rate_window_start_ns: timestamp,
+                rate_window_count: 0,
             },
         };
         self.processes.insert(pid, process);
@@ -131,7 +144,7 @@ impl<H: HAL> KernelCore<H> {
                 "[kernel] Init (PID 1) killing process PID {} (implicit permission)",
                 target_pid.0
             ));
-            let commits = self.kill_process(target_pid, timestamp);
+            let commits = self.terminate(target_pid, ExitStatus::Killed { by: caller }, timestamp);
             return (Ok(()), commits);
         }
 
@@ -151,7 +164,7 @@ impl<H: HAL> KernelCore<H> {
             caller.0,
             target_pid.0
         ));
-        let commits = self.kill_process(target_pid, timestamp);
+        let commits = self.terminate(target_pid, ExitStatus::Killed { by: caller }, timestamp);
 
         (Ok(()), commits)
     }
@@ -160,9 +173,85 @@ impl<H: HAL> KernelCore<H> {
     ///
     /// Returns Vec<Commit> describing the mutations.
     pub fn kill_process(&mut self, pid: ProcessId, timestamp: u64) -> Vec<Commit> {
+        let status = ExitStatus::Killed { by: ProcessId(0) };
+        self.terminate(pid, status, timestamp)
+    }
+
+    /// Terminate a process that exited voluntarily with `code`.
+    ///
+    /// Returns Vec<Commit> describing the mutations.
+    pub fn exit_process(&mut self, pid: ProcessId, code: i32, timestamp: u64) -> Vec<Commit> {
+        self.terminate(pid, ExitStatus::Exited(code), timestamp)
+    }
+
+    /// Kill a process and every descendant (syscall-accessible).
+    ///
+    /// Only the target itself, one of its ancestors, or Init may do this.
+    /// Processes die top-down, so each victim's children are handed to the
+    /// target's parent, which is told about every death.
+    ///
+    /// Returns (Result<number of processes killed, KernelError>, Vec<Commit>).
+    pub fn kill_tree(
+        &mut self,
+        caller: ProcessId,
+        target_pid: ProcessId,
+        timestamp: u64,
+    ) -> (Result<usize, KernelError>, Vec<Commit>) {
+        if !self.processes.contains_key(&target_pid) {
+            return (Err(KernelError::ProcessNotFound), Vec::new());
+        }
+        if caller.0 != 1 && !self.is_ancestor_or_self(caller, target_pid) {
+            return (Err(KernelError::PermissionDenied), Vec::new());
+        }
+
+        let victims = self.descendants(target_pid);
+        let mut commits = Vec::new();
+        for &pid in &victims {
+            commits.extend(self.terminate(pid, ExitStatus::Killed { by: caller }, timestamp));
+        }
+
+        (Ok(victims.len()), commits)
+    }
+
+    /// Reap a dead child of `caller` (any child if `target` is `None`).
+    ///
+    /// Fails with `WouldBlock` if the matching children are all still
+    /// alive, and `ProcessNotFound` if `caller` has no such child. Reaping
+    /// only forgets the exit status, so it produces no commits.
+    pub fn wait_child(
+        &mut self,
+        caller: ProcessId,
+        target: Option<ProcessId>,
+    ) -> Result<(ProcessId, ExitStatus), KernelError> {
+        let matches =
+            |pid: ProcessId, parent: ProcessId| parent == caller && target.is_none_or(|t| t == pid);
+
+        let dead = self
+            .exited
+            .iter()
+            .find(|(&pid, record)| matches(pid, record.parent))
+            .map(|(&pid, record)| (pid, record.status));
+        if let Some((pid, status)) = dead {
+            self.exited.remove(&pid);
+            return Ok((pid, status));
+        }
+
+        if self.processes.values().any(|p| matches(p.pid, p.parent)) {
+            Err(KernelError::WouldBlock)
+        } else {
+            Err(KernelError::ProcessNotFound)
+        }
+    }
+
+    /// Remove a process, unwind everything it took part in and tell its
+    /// parent.
+    ///
+    /// Its children (live or awaiting reaping) are adopted by its parent.
+    fn terminate(&mut self, pid: ProcessId, status: ExitStatus, timestamp: u64) -> Vec<Commit> {
         let mut commits = Vec::new();
 
         // Remove the process and create exit commit
+        let mut parent = None;
         if let Some(proc) = self.processes.remove(&pid) {
             self.hal.debug_write(&alloc::format!(
                 "[kernel] Killed process: {} (PID {})",
@@ -170,25 +259,43 @@ impl<H: HAL> KernelCore<H> {
                 pid.0
             ));
 
+            let code = match status {
+                ExitStatus::Exited(code) => code,
+                _ => -1,
+            };
             commits.push(Commit {
                 id: [0u8; 32],
                 prev_commit: [0u8; 32],
                 seq: 0,
                 timestamp,
-                commit_type: CommitType::ProcessExited {
-                    pid: pid.0,
-                    code: -1,
-                },
+                commit_type: CommitType::ProcessExited { pid: pid.0, code },
                 caused_by: None,
             });
+            parent = Some(proc.parent);
         }
 
-        // Remove its capability space
-        self.cap_spaces.remove(&pid);
+        // Unwind calls it made or was serving and release its IRQ lines,
+        // then remove its capability space and any wait it was parked in
+        commits.extend(self.abort_calls(pid, timestamp));
+        commits.extend(self.unbind_irqs(pid, timestamp));
+        if let Some(cspace) = self.cap_spaces.remove(&pid) {
+            for cap in cspace.slots.values() {
+                self.cap_derivations.remove(cap.id);
+            }
+        }
+        self.waits.remove(&pid);
 
         // Remove endpoints owned by this process and create destruction commits
         commits.extend(self.cleanup_process_endpoints(pid, timestamp));
 
+        // Drop its region mappings and free regions nothing refers to now
+        commits.extend(self.release_regions(pid, timestamp));
+
+        if let Some(parent) = parent {
+            self.adopt_children(pid, parent);
+            commits.extend(self.report_exit(pid, parent, status, timestamp));
+        }
+
         commits
     }
 
@@ -238,7 +345,7 @@ impl<H: HAL> KernelCore<H> {
         }
 
         // Now kill the process (adds ProcessExited and EndpointDestroyed commits)
-        commits.extend(self.kill_process(pid, timestamp));
+        commits.extend(self.terminate(pid, ExitStatus::Faulted { reason }, timestamp));
 
         commits
     }
@@ -248,11 +355,19 @@ impl<H: HAL> KernelCore<H> {
     // ========================================================================
 
     /// Create a process entry with standard metrics initialization
-    fn create_process_entry(&self, pid: ProcessId, name: &str, timestamp: u64) -> Process {
+    fn create_process_entry(
+        &self,
+        pid: ProcessId,
+        parent: ProcessId,
+        name: &str,
+        timestamp: u64,
+    ) -> Process {
         Process {
             pid,
+            parent,
             name: String::from(name),
             state: ProcessState::Running,
+            quota: ResourceQuota::default(),
             metrics: ProcessMetrics {
                 memory_size: 65536, // Initial 64KB (1 WASM page)
                 ipc_sent: 0,
@@ -262,6 +377,8 @@ impl<H: HAL> KernelCore<H> {
                 syscall_count: 0,
                 last_active_ns: timestamp,
                 start_time_ns: timestamp,
+                rate_window_start_ns: timestamp,
+                rate_window_count: 0,
             },
         }
     }
@@ -299,6 +416,74 @@ impl<H: HAL> KernelCore<H> {
         })
     }
 
+    /// Check whether `ancestor` is `pid` or one of its ancestors
+    fn is_ancestor_or_self(&self, ancestor: ProcessId, pid: ProcessId) -> bool {
+        let mut current = pid;
+        // Bounded by the table size in case parent links ever form a cycle
+        for _ in 0..=self.processes.len() {
+            if current == ancestor {
+                return true;
+            }
+            match self.processes.get(&current) {
+                Some(proc) if proc.parent != current => current = proc.parent,
+                _ => return false,
+            }
+        }
+        false
+    }
+
+    /// A process and all of its live descendants, parents before children
+    fn descendants(&self, pid: ProcessId) -> Vec<ProcessId> {
+        let mut tree = vec![pid];
+        let mut next = 0;
+        while next < tree.len() {
+            let parent = tree[next];
+            let children: Vec<ProcessId> = self
+                .processes
+                .values()
+                .filter(|p| p.parent == parent && !tree.contains(&p.pid))
+                .map(|p| p.pid)
+                .collect();
+            tree.extend(children);
+            next += 1;
+        }
+        tree
+    }
+
+    /// Hand the children of dead process `pid` to its parent.
+    ///
+    /// Exit records of unreaped children move too, unless there is no
+    /// parent left to reap them.
+    pub(crate) fn adopt_children(&mut self, pid: ProcessId, parent: ProcessId) {
+        for proc in self.processes.values_mut().filter(|p| p.parent == pid) {
+            proc.parent = parent;
+        }
+        let reaper_alive = self.processes.contains_key(&parent);
+        self.exited.retain(|_, record| record.parent != pid || reaper_alive);
+        for record in self.exited.values_mut().filter(|r| r.parent == pid) {
+            record.parent = parent;
+        }
+    }
+
+    /// Record the death of `pid` for its parent and send it
+    /// `MSG_CHILD_EXITED`.
+    ///
+    /// The exit record is kept whether or not the message could be queued,
+    /// so `wait_child` still finds the child.
+    fn report_exit(
+        &mut self,
+        pid: ProcessId,
+        parent: ProcessId,
+        status: ExitStatus,
+        timestamp: u64,
+    ) -> Option<Commit> {
+        if !self.processes.contains_key(&parent) {
+            return None;
+        }
+        self.exited.insert(pid, ExitRecord { parent, status });
+        self.notify_process(parent, MSG_CHILD_EXITED, status.to_payload(pid), timestamp)
+    }
+
     /// Clean up endpoints owned by a process and return destruction commits
     fn cleanup_process_endpoints(&mut self, pid: ProcessId, timestamp: u64) -> Vec<Commit> {
         let owned_endpoints: Vec<_> = self
@@ -0,0 +1,160 @@
+//! Resource quotas for KernelCore.
+//!
+//! This module contains methods for:
+//! - Measuring a process's usage of each quota resource
+//! - Checking an allocation against the process's quota
+//! - Setting a process's quota (Init-only)
+//! - Syscall rate accounting
+//!
+//! A refused allocation returns `KernelError::ResourceExhausted` together
+//! with a `QuotaExceeded` commit, so every refusal is visible in the log.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::error::KernelError;
+use crate::types::{ProcessId, QuotaResource, ResourceQuota, RATE_WINDOW_NS};
+use zos_axiom::{Commit, CommitType};
+use zos_hal::HAL;
+use zos_ipc::pid::INIT;
+
+use super::KernelCore;
+
+impl<H: HAL> KernelCore<H> {
+    /// Current usage of `resource` by a process.
+    pub fn quota_usage(&self, pid: ProcessId, resource: QuotaResource) -> u64 {
+        match resource {
+            QuotaResource::Memory => {
+                let memory = self.processes.get(&pid).map_or(0, |p| p.metrics.memory_size);
+                (memory + self.region_bytes(pid)) as u64
+            }
+            QuotaResource::Endpoints => {
+                self.endpoints.values().filter(|e| e.owner == pid).count() as u64
+            }
+            QuotaResource::CapSlots => self.cap_spaces.get(&pid).map_or(0, |c| c.len() as u64),
+            QuotaResource::QueuedBytes => self
+                .endpoints
+                .values()
+                .flat_map(|e| e.pending_messages.iter())
+                .filter(|m| m.from == pid)
+                .map(|m| m.data.len() as u64)
+                .sum(),
+            QuotaResource::SyscallRate => self
+                .processes
+                .get(&pid)
+                .map_or(0, |p| p.metrics.rate_window_count),
+        }
+    }
+
+    /// Check whether a process may take `amount` more of `resource`.
+    ///
+    /// Returns the `QuotaExceeded` commit to record if it may not.
+    pub(crate) fn quota_violation(
+        &self,
+        pid: ProcessId,
+        resource: QuotaResource,
+        amount: u64,
+        timestamp: u64,
+    ) -> Option<Commit> {
+        let limit = self
+            .processes
+            .get(&pid)
+            .map_or(0, |p| p.quota.limit(resource));
+        if limit == 0 || self.quota_usage(pid, resource).saturating_add(amount) <= limit {
+            return None;
+        }
+
+        self.hal.debug_write(&alloc::format!(
+            "[kernel] PID {} exceeded its {:?} quota ({})",
+            pid.0,
+            resource,
+            limit
+        ));
+        Some(quota_exceeded_commit(pid, resource, timestamp))
+    }
+
+    /// Set the resource quota of a process. Only Init may do this.
+    ///
+    /// The new quota may not be more generous than Init's own.
+    ///
+    /// Returns (Result<(), KernelError>, Vec<Commit>).
+    pub fn set_quota(
+        &mut self,
+        by: ProcessId,
+        target: ProcessId,
+        quota: ResourceQuota,
+        timestamp: u64,
+    ) -> (Result<(), KernelError>, Vec<Commit>) {
+        if by.0 != INIT as u64 {
+            return (Err(KernelError::PermissionDenied), Vec::new());
+        }
+        let setter_quota = match self.processes.get(&by) {
+            Some(p) => p.quota,
+            None => return (Err(KernelError::ProcessNotFound), Vec::new()),
+        };
+        if !quota.is_within(&setter_quota) {
+            return (Err(KernelError::PermissionDenied), Vec::new());
+        }
+        let process = match self.processes.get_mut(&target) {
+            Some(p) => p,
+            None => return (Err(KernelError::ProcessNotFound), Vec::new()),
+        };
+        process.quota = quota;
+
+        self.hal.debug_write(&alloc::format!(
+            "[kernel] PID {} set quota of PID {}: {:?}",
+            by.0,
+            target.0,
+            quota
+        ));
+
+        let commit = Commit {
+            id: [0u8; 32],
+            prev_commit: [0u8; 32],
+            seq: 0,
+            timestamp,
+            commit_type: CommitType::QuotaSet {
+                pid: target.0,
+                by: by.0,
+                quota,
+            },
+            caused_by: None,
+        };
+        (Ok(()), vec![commit])
+    }
+
+    /// Count a syscall against the caller's rate quota.
+    ///
+    /// The rate is measured over fixed windows of `RATE_WINDOW_NS`. Returns
+    /// the `QuotaExceeded` commit to record if the call is refused.
+    pub(crate) fn charge_syscall(&mut self, pid: ProcessId, timestamp: u64) -> Option<Commit> {
+        let process = self.processes.get_mut(&pid)?;
+        let metrics = &mut process.metrics;
+        if timestamp.saturating_sub(metrics.rate_window_start_ns) >= RATE_WINDOW_NS {
+            metrics.rate_window_start_ns = timestamp;
+            metrics.rate_window_count = 0;
+        }
+        let refusal = self.quota_violation(pid, QuotaResource::SyscallRate, 1, timestamp);
+        if refusal.is_none() {
+            if let Some(process) = self.processes.get_mut(&pid) {
+                process.metrics.rate_window_count += 1;
+            }
+        }
+        refusal
+    }
+}
+
+/// Build the commit recording a refused allocation
+fn quota_exceeded_commit(pid: ProcessId, resource: QuotaResource, timestamp: u64) -> Commit {
+    Commit {
+        id: [0u8; 32],
+        prev_commit: [0u8; 32],
+        seq: 0,
+        timestamp,
+        commit_type: CommitType::QuotaExceeded {
+            pid: pid.0,
+            resource: resource as u8,
+        },
+        caused_by: None,
+    }
+}
@@ -0,0 +1,391 @@
+//! Shared memory regions for KernelCore.
+//!
+//! This module contains methods for:
+//! - Creating a region and its full capability
+//! - Mapping and unmapping a region through a capability
+//! - Flushing a process's window into a region and refreshing it back
+//! - Freeing a region once no capability refers to it
+//!
+//! Regions are handed to other processes like any capability, over IPC.
+//! The HAL holds the contents; a mapping is a window in the process's
+//! linear memory that is copied in when mapped or refreshed and published
+//! back when flushed. A read-only capability maps read-only, so its holder
+//! can never flush.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::error::KernelError;
+use crate::types::{
+    CapSlot, ObjectType, ProcessId, QuotaResource, RegionId, RegionMapping, SharedRegion,
+    MAX_REGION_SIZE,
+};
+use crate::{axiom_check, Capability, Permissions};
+use zos_axiom::{Commit, CommitType};
+use zos_hal::HAL;
+
+use super::{map_axiom_error, KernelCore};
+
+impl<H: HAL> KernelCore<H> {
+    /// Create a zeroed region of at least `size` bytes owned by `pid`.
+    ///
+    /// The size is rounded up to whole pages and charged to the Memory
+    /// quota of `pid`. The creator gets a full capability for the region.
+    ///
+    /// Returns (Result<(CapSlot, RegionId), KernelError>, Vec<Commit>).
+    pub fn create_region(
+        &mut self,
+        pid: ProcessId,
+        size: usize,
+        timestamp: u64,
+    ) -> (Result<(CapSlot, RegionId), KernelError>, Vec<Commit>) {
+        if size == 0 || size > MAX_REGION_SIZE {
+            return (Err(KernelError::InvalidArgument), Vec::new());
+        }
+        if !self.cap_spaces.contains_key(&pid) {
+            return (Err(KernelError::ProcessNotFound), Vec::new());
+        }
+        let id = RegionId(self.next_region_id);
+        let region = SharedRegion::new(id, pid, size);
+        let checks = [
+            (QuotaResource::Memory, region.size as u64),
+            (QuotaResource::CapSlots, 1),
+        ];
+        for (resource, amount) in checks {
+            if let Some(commit) = self.quota_violation(pid, resource, amount, timestamp) {
+                return (Err(KernelError::ResourceExhausted), vec![commit]);
+            }
+        }
+        if let Err(e) = self.hal.region_alloc(id.0, region.size) {
+            return (Err(e.into()), Vec::new());
+        }
+        self.next_region_id += 1;
+
+        let cap_id = self.next_cap_id();
+        let perms = Permissions::full();
+        let cap = Capability {
+            id: cap_id,
+            object_type: ObjectType::Memory,
+            object_id: id.0,
+            permissions: perms,
+            generation: 0,
+            expires_at: 0,
+            badge: 0,
+        };
+        let slot = match self.cap_spaces.get_mut(&pid) {
+            Some(cspace) => cspace.insert(cap),
+            None => return (Err(KernelError::ProcessNotFound), Vec::new()),
+        };
+
+        let commits = vec![
+            region_commit(
+                CommitType::RegionCreated {
+                    id: id.0,
+                    owner: pid.0,
+                    size: region.size,
+                },
+                timestamp,
+            ),
+            region_commit(
+                CommitType::CapInserted {
+                    pid: pid.0,
+                    slot,
+                    cap_id,
+                    object_type: ObjectType::Memory as u8,
+                    object_id: id.0,
+                    perms: perms.to_byte(),
+                },
+                timestamp,
+            ),
+        ];
+
+        self.hal.debug_write(&alloc::format!(
+            "[kernel] PID {} created region {} ({} bytes)",
+            pid.0,
+            id.0,
+            region.size
+        ));
+        self.regions.insert(id, region);
+
+        (Ok((slot, id)), commits)
+    }
+
+    /// Map the region behind `slot` at `window` in the caller's linear
+    /// memory and copy its current contents there.
+    ///
+    /// A writable mapping needs read and write on the capability, a
+    /// read-only one needs read. The window must be at least as long as the
+    /// region. Mapping again moves the window or changes its access.
+    ///
+    /// Returns (Result<usize, KernelError>, Vec<Commit>) with the region
+    /// size, which is how many bytes the window covers.
+    pub fn map_region(
+        &mut self,
+        pid: ProcessId,
+        slot: CapSlot,
+        writable: bool,
+        window: u32,
+        window_len: usize,
+        timestamp: u64,
+    ) -> (Result<usize, KernelError>, Vec<Commit>) {
+        let required = Permissions {
+            write: writable,
+            ..Permissions::read_only()
+        };
+        let region = match self.region_for_slot(pid, slot, &required, timestamp) {
+            Ok(region) => region,
+            Err(e) => return (Err(e), Vec::new()),
+        };
+        let (id, size) = (region.id, region.size);
+        if window_len < size {
+            return (Err(KernelError::InvalidArgument), Vec::new());
+        }
+        if let Err(e) = self
+            .hal
+            .region_copy_to_process(id.0, 0, pid.0, window, size)
+        {
+            return (Err(e.into()), Vec::new());
+        }
+
+        if let Some(region) = self.regions.get_mut(&id) {
+            region
+                .mappings
+                .insert(pid, RegionMapping { writable, window });
+        }
+        let commit = region_commit(
+            CommitType::RegionMapped {
+                pid: pid.0,
+                id: id.0,
+                writable,
+                window,
+            },
+            timestamp,
+        );
+        (Ok(size), vec![commit])
+    }
+
+    /// Drop the caller's mapping of the region behind `slot` (no rights
+    /// needed). The window's memory is left as it was.
+    ///
+    /// Returns (Result<(), KernelError>, Vec<Commit>).
+    pub fn unmap_region(
+        &mut self,
+        pid: ProcessId,
+        slot: CapSlot,
+        timestamp: u64,
+    ) -> (Result<(), KernelError>, Vec<Commit>) {
+        let id = match self.region_for_slot(pid, slot, &Permissions::default(), timestamp) {
+            Ok(region) if region.mappings.contains_key(&pid) => region.id,
+            Ok(_) => return (Err(KernelError::InvalidArgument), Vec::new()),
+            Err(e) => return (Err(e), Vec::new()),
+        };
+        (Ok(()), vec![self.remove_mapping(pid, id, timestamp)])
+    }
+
+    /// Publish `len` bytes at `offset` of the caller's window into the
+    /// region. Needs a writable mapping and write on the capability.
+    pub fn flush_region(
+        &self,
+        pid: ProcessId,
+        slot: CapSlot,
+        offset: usize,
+        len: usize,
+        timestamp: u64,
+    ) -> Result<(), KernelError> {
+        let region = self.region_for_slot(pid, slot, &Permissions::write_only(), timestamp)?;
+        let (id, addr) = window_range(region, pid, offset, len)?;
+        if !region.mappings.get(&pid).is_some_and(|m| m.writable) {
+            return Err(KernelError::PermissionDenied);
+        }
+        Ok(self
+            .hal
+            .region_copy_from_process(id.0, offset, pid.0, addr, len)?)
+    }
+
+    /// Copy `len` bytes at `offset` of the region into the caller's
+    /// window. Needs a mapping and read on the capability.
+    pub fn refresh_region(
+        &self,
+        pid: ProcessId,
+        slot: CapSlot,
+        offset: usize,
+        len: usize,
+        timestamp: u64,
+    ) -> Result<(), KernelError> {
+        let region = self.region_for_slot(pid, slot, &Permissions::read_only(), timestamp)?;
+        let (id, addr) = window_range(region, pid, offset, len)?;
+        Ok(self
+            .hal
+            .region_copy_to_process(id.0, offset, pid.0, addr, len)?)
+    }
+
+    /// Get a shared region
+    pub fn get_region(&self, id: RegionId) -> Option<&SharedRegion> {
+        self.regions.get(&id)
+    }
+
+    /// Release region `id` after `pid` dropped a capability for it.
+    ///
+    /// The mapping goes once `pid` holds no capability for the region, and
+    /// the region itself once nothing does.
+    pub(crate) fn release_dropped_region(
+        &mut self,
+        pid: ProcessId,
+        id: RegionId,
+        timestamp: u64,
+    ) -> Vec<Commit> {
+        let mut commits = Vec::new();
+        let mapped = self
+            .regions
+            .get(&id)
+            .is_some_and(|region| region.mappings.contains_key(&pid));
+        if mapped && !self.holds_region_cap(pid, id) {
+            commits.push(self.remove_mapping(pid, id, timestamp));
+        }
+        if self.regions.contains_key(&id) && !self.region_referenced(id) {
+            commits.push(self.destroy_region(id, timestamp));
+        }
+        commits
+    }
+
+    /// Drop every mapping of a dying process, then free the regions its
+    /// death left unreferenced (including those whose last capability was
+    /// queued on one of its endpoints).
+    pub(crate) fn release_regions(&mut self, pid: ProcessId, timestamp: u64) -> Vec<Commit> {
+        let mut commits = Vec::new();
+        let mapped: Vec<RegionId> = self
+            .regions
+            .values()
+            .filter(|region| region.mappings.contains_key(&pid))
+            .map(|region| region.id)
+            .collect();
+        for id in mapped {
+            commits.push(self.remove_mapping(pid, id, timestamp));
+        }
+        let unreferenced: Vec<RegionId> = self
+            .regions
+            .keys()
+            .copied()
+            .filter(|&id| !self.region_referenced(id))
+            .collect();
+        for id in unreferenced {
+            commits.push(self.destroy_region(id, timestamp));
+        }
+        commits
+    }
+
+    /// Bytes of region memory charged to a process
+    pub(crate) fn region_bytes(&self, pid: ProcessId) -> usize {
+        self.regions
+            .values()
+            .filter(|region| region.owner == pid)
+            .map(|region| region.size)
+            .sum()
+    }
+
+    /// Check a region capability and return the region it names.
+    fn region_for_slot(
+        &self,
+        pid: ProcessId,
+        slot: CapSlot,
+        required: &Permissions,
+        timestamp: u64,
+    ) -> Result<&SharedRegion, KernelError> {
+        let cspace = self
+            .cap_spaces
+            .get(&pid)
+            .ok_or(KernelError::ProcessNotFound)?;
+        let cap = axiom_check(cspace, slot, required, Some(ObjectType::Memory), timestamp)
+            .map_err(map_axiom_error)?;
+        self.regions
+            .get(&RegionId(cap.object_id))
+            .ok_or(KernelError::InvalidCapability)
+    }
+
+    /// Whether `pid` holds any capability for region `id`.
+    fn holds_region_cap(&self, pid: ProcessId, id: RegionId) -> bool {
+        self.cap_spaces
+            .get(&pid)
+            .is_some_and(|cspace| cspace.slots.values().any(|cap| is_region_cap(cap, id)))
+    }
+
+    /// Whether any CSpace or queued message still carries a capability
+    /// for region `id`.
+    fn region_referenced(&self, id: RegionId) -> bool {
+        let in_cspace = self
+            .cap_spaces
+            .values()
+            .flat_map(|cspace| cspace.slots.values())
+            .any(|cap| is_region_cap(cap, id));
+        let in_flight = self
+            .endpoints
+            .values()
+            .flat_map(|e| e.pending_messages.iter())
+            .flat_map(|m| m.transferred_caps.iter())
+            .any(|t| is_region_cap(&t.capability, id));
+        in_cspace || in_flight
+    }
+
+    /// Remove a mapping and record it.
+    fn remove_mapping(&mut self, pid: ProcessId, id: RegionId, timestamp: u64) -> Commit {
+        if let Some(region) = self.regions.get_mut(&id) {
+            region.mappings.remove(&pid);
+        }
+        region_commit(
+            CommitType::RegionUnmapped {
+                pid: pid.0,
+                id: id.0,
+            },
+            timestamp,
+        )
+    }
+
+    /// Remove a region, free its backing and record it.
+    fn destroy_region(&mut self, id: RegionId, timestamp: u64) -> Commit {
+        self.regions.remove(&id);
+        self.hal.region_free(id.0);
+        self.hal
+            .debug_write(&alloc::format!("[kernel] Region {} freed", id.0));
+        region_commit(CommitType::RegionDestroyed { id: id.0 }, timestamp)
+    }
+}
+
+/// Check that `offset..offset + len` lies inside the region and return the
+/// region ID and the matching address in `pid`'s window.
+fn window_range(
+    region: &SharedRegion,
+    pid: ProcessId,
+    offset: usize,
+    len: usize,
+) -> Result<(RegionId, u32), KernelError> {
+    let mapping = region
+        .mappings
+        .get(&pid)
+        .ok_or(KernelError::InvalidArgument)?;
+    if !region.contains(offset, len) {
+        return Err(KernelError::InvalidArgument);
+    }
+    // The region is at most MAX_REGION_SIZE, so the offset fits in a u32
+    let addr = mapping
+        .window
+        .checked_add(offset as u32)
+        .ok_or(KernelError::InvalidArgument)?;
+    Ok((region.id, addr))
+}
+
+/// Whether `cap` is a capability for region `id`.
+fn is_region_cap(cap: &Capability, id: RegionId) -> bool {
+    cap.object_type == ObjectType::Memory && cap.object_id == id.0
+}
+
+/// Wrap a region commit type in an unsequenced Commit
+fn region_commit(commit_type: CommitType, timestamp: u64) -> Commit {
+    Commit {
+        id: [0u8; 32],
+        prev_commit: [0u8; 32],
+        seq: 0,
+        timestamp,
+        commit_type,
+        caused_by: None,
+    }
+}
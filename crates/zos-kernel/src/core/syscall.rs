@@ -6,6 +6,7 @@ use alloc::vec;
 use alloc::vec::Vec;
 
 use crate::error::KernelError;
+use crate::ipc::WaitOutcome;
 use crate::syscall::{CapInfo, Syscall, SyscallResult};
 use crate::types::{ProcessId, ProcessState};
 use zos_axiom::{Commit, CommitType};
@@ -55,6 +56,9 @@ impl<H: HAL> KernelCore<H> {
                 tag,
                 data,
             } => self.handle_call(from_pid, endpoint_slot, tag, data, timestamp),
+            Syscall::Reply { caller, tag, data } => {
+                self.handle_reply(from_pid, caller, tag, data, timestamp)
+            }
 
             // Capability syscalls
             Syscall::ListCaps => self.handle_list_caps(from_pid),
@@ -70,12 +74,45 @@ impl<H: HAL> KernelCore<H> {
                 slot,
                 new_permissions,
             } => self.handle_cap_derive(from_pid, slot, new_permissions, timestamp),
+            Syscall::CapGrantWithTtl {
+                from_slot,
+                to_pid,
+                permissions,
+                ttl_ns,
+            } => self.handle_cap_grant_with_ttl(
+                from_pid,
+                from_slot,
+                to_pid,
+                permissions,
+                ttl_ns,
+                timestamp,
+            ),
+            Syscall::CapDeriveWithTtl {
+                slot,
+                new_permissions,
+                ttl_ns,
+            } => {
+                self.handle_cap_derive_with_ttl(from_pid, slot, new_permissions, ttl_ns, timestamp)
+            }
 
             // Process syscalls
             Syscall::ListProcesses => self.handle_list_processes(),
             Syscall::Exit { code } => self.handle_exit(from_pid, code, timestamp),
             Syscall::Kill { target_pid } => self.handle_kill(from_pid, target_pid, timestamp),
 
+            // Notification syscalls
+            Syscall::CreateNotification => self.handle_create_notification(from_pid, timestamp),
+            Syscall::Signal {
+                notification_slot,
+                bits,
+            } => self.handle_signal(from_pid, notification_slot, bits, timestamp),
+            Syscall::Wait { notification_slot } => {
+                self.handle_take_signals(from_pid, notification_slot, true, timestamp)
+            }
+            Syscall::Poll { notification_slot } => {
+                self.handle_take_signals(from_pid, notification_slot, false, timestamp)
+            }
+
             // Misc syscalls
             Syscall::GetTime => (SyscallResult::Ok(timestamp), vec![]),
             Syscall::Yield => (SyscallResult::Ok(0), vec![]),
@@ -177,11 +214,31 @@ impl<H: HAL> KernelCore<H> {
         data: Vec<u8>,
         timestamp: u64,
     ) -> (SyscallResult, Vec<Commit>) {
-        // Call = send + block for reply
-        let (result, commit) = self.ipc_send(from_pid, endpoint_slot, tag, data, timestamp);
-        let commits = commit.into_iter().collect();
+        // A retried call collects the outcome of the outstanding one
+        let (result, commits) = if self.calls.contains_key(&from_pid) {
+            self.wait_reply(from_pid)
+        } else {
+            self.call(from_pid, endpoint_slot, tag, data, timestamp)
+        };
+        let syscall_result = match result {
+            Ok(WaitOutcome::Message { message, .. }) => SyscallResult::Message(message),
+            Ok(_) => SyscallResult::WouldBlock,
+            Err(e) => SyscallResult::Err(e),
+        };
+        (syscall_result, commits)
+    }
+
+    fn handle_reply(
+        &mut self,
+        from_pid: ProcessId,
+        caller: ProcessId,
+        tag: u32,
+        data: Vec<u8>,
+        timestamp: u64,
+    ) -> (SyscallResult, Vec<Commit>) {
+        let (result, commits) = self.reply(from_pid, caller, tag, data, timestamp);
         let syscall_result = match result {
-            Ok(()) => SyscallResult::WouldBlock,
+            Ok(()) => SyscallResult::Ok(0),
             Err(e) => SyscallResult::Err(e),
         };
         (syscall_result, commits)
@@ -271,6 +328,99 @@ impl<H: HAL> KernelCore<H> {
         (syscall_result, commits)
     }
 
+    fn handle_cap_grant_with_ttl(
+        &mut self,
+        from_pid: ProcessId,
+        from_slot: u32,
+        to_pid: ProcessId,
+        permissions: crate::Permissions,
+        ttl_ns: u64,
+        timestamp: u64,
+    ) -> (SyscallResult, Vec<Commit>) {
+        let (result, commits) = self.grant_capability_with_ttl(
+            from_pid,
+            from_slot,
+            to_pid,
+            permissions,
+            ttl_ns,
+            timestamp,
+        );
+        let syscall_result = match result {
+            Ok(new_slot) => SyscallResult::Ok(new_slot as u64),
+            Err(e) => SyscallResult::Err(e),
+        };
+        (syscall_result, commits)
+    }
+
+    fn handle_cap_derive_with_ttl(
+        &mut self,
+        from_pid: ProcessId,
+        slot: u32,
+        new_permissions: crate::Permissions,
+        ttl_ns: u64,
+        timestamp: u64,
+    ) -> (SyscallResult, Vec<Commit>) {
+        let (result, commits) =
+            self.derive_capability_with_ttl(from_pid, slot, new_permissions, ttl_ns, timestamp);
+        let syscall_result = match result {
+            Ok(new_slot) => SyscallResult::Ok(new_slot as u64),
+            Err(e) => SyscallResult::Err(e),
+        };
+        (syscall_result, commits)
+    }
+
+    // ========================================================================
+    // Notification syscalls
+    // ========================================================================
+
+    fn handle_create_notification(
+        &mut self,
+        from_pid: ProcessId,
+        timestamp: u64,
+    ) -> (SyscallResult, Vec<Commit>) {
+        let (result, commits) = self.create_notification(from_pid, timestamp);
+        let syscall_result = match result {
+            // Pack as (slot << 32) | notification_id, same as endpoints
+            Ok((id, slot)) => SyscallResult::Ok(((slot as u64) << 32) | (id.0 & 0xFFFFFFFF)),
+            Err(e) => SyscallResult::Err(e),
+        };
+        (syscall_result, commits)
+    }
+
+    fn handle_signal(
+        &mut self,
+        from_pid: ProcessId,
+        slot: u32,
+        bits: u64,
+        timestamp: u64,
+    ) -> (SyscallResult, Vec<Commit>) {
+        let (result, commits) = self.signal_notification(from_pid, slot, bits, timestamp);
+        let syscall_result = match result {
+            Ok(()) => SyscallResult::Ok(0),
+            Err(e) => SyscallResult::Err(e),
+        };
+        (syscall_result, commits)
+    }
+
+    /// Shared body of `Wait` and `Poll`: take the pending signal word.
+    ///
+    /// With `block` set, an empty word yields `WouldBlock` instead of `Ok(0)`.
+    fn handle_take_signals(
+        &mut self,
+        from_pid: ProcessId,
+        slot: u32,
+        block: bool,
+        timestamp: u64,
+    ) -> (SyscallResult, Vec<Commit>) {
+        let (result, commits) = self.poll_notification(from_pid, slot, timestamp);
+        let syscall_result = match result {
+            Ok(0) if block => SyscallResult::WouldBlock,
+            Ok(bits) => SyscallResult::Ok(bits),
+            Err(e) => SyscallResult::Err(e),
+        };
+        (syscall_result, commits)
+    }
+
     // ========================================================================
     // Process syscalls
     // ========================================================================
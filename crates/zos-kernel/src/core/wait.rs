@@ -0,0 +1,260 @@
+//! Timed waits for KernelCore.
+//!
+//! This module contains methods for:
+//! - Receiving from the first ready of several endpoints
+//! - Waiting for a notification to be signalled
+//! - Waiting for the reply to a call
+//! - Parking a process until its wait can end or its deadline passes
+//! - Finding parked processes that are ready to resume
+//!
+//! The kernel never blocks the caller's thread itself. A parked process is
+//! left `Blocked` and its syscall is not completed; the runtime retries the
+//! wait once `parked_ready` reports it, and the retry then completes with a
+//! message, the signalled bits, the reply or `WaitOutcome::TimedOut`.
+
+use alloc::vec::Vec;
+
+use crate::error::KernelError;
+use crate::ipc::{CallState, WaitOutcome};
+use crate::types::{CapSlot, ProcessId, ProcessState};
+use zos_axiom::Commit;
+use zos_hal::HAL;
+
+use super::KernelCore;
+
+/// What a parked process waits on and when it gives up
+#[derive(Clone, Debug)]
+pub(crate) struct Wait {
+    /// Objects that can end the wait
+    pub(crate) on: WaitOn,
+    /// Absolute deadline in nanos since boot (0 = none)
+    pub(crate) deadline_ns: u64,
+}
+
+/// Objects a parked process waits on
+#[derive(Clone, Debug)]
+pub(crate) enum WaitOn {
+    /// Endpoint slots, in priority order
+    Endpoints(Vec<CapSlot>),
+    /// A notification slot
+    Notification(CapSlot),
+    /// The reply to the process's outstanding call
+    Reply,
+}
+
+impl<H: HAL> KernelCore<H> {
+    /// Receive from the first of `slots` with a message, or park the caller.
+    ///
+    /// Every slot must be receivable, even if an earlier one has a message.
+    /// Listed order is priority order. Once `deadline_ns` (0 = none) has
+    /// passed the wait ends with `TimedOut` instead of parking.
+    ///
+    /// Returns (Result<WaitOutcome, KernelError>, Vec<Commit>).
+    pub fn wait_receive(
+        &mut self,
+        pid: ProcessId,
+        slots: &[CapSlot],
+        deadline_ns: u64,
+        timestamp: u64,
+    ) -> (Result<WaitOutcome, KernelError>, Vec<Commit>) {
+        for &slot in slots {
+            if let Err(e) = self.ipc_has_message(pid, slot, timestamp) {
+                self.unpark(pid);
+                return (Err(e), Vec::new());
+            }
+        }
+
+        for &slot in slots {
+            match self.ipc_receive_with_caps(pid, slot, timestamp) {
+                (Ok(Some((message, installed_slots))), commits) => {
+                    self.unpark(pid);
+                    let outcome = WaitOutcome::Message {
+                        slot,
+                        message,
+                        installed_slots,
+                    };
+                    return (Ok(outcome), commits);
+                }
+                (Ok(None), _) => continue,
+                (Err(e), commits) => {
+                    self.unpark(pid);
+                    return (Err(e), commits);
+                }
+            }
+        }
+
+        if deadline_ns != 0 && timestamp >= deadline_ns {
+            self.unpark(pid);
+            return (Ok(WaitOutcome::TimedOut), Vec::new());
+        }
+
+        let wait = Wait {
+            on: WaitOn::Endpoints(slots.to_vec()),
+            deadline_ns,
+        };
+        (self.park(pid, wait), Vec::new())
+    }
+
+    /// Take the bits of the notification behind `slot`, or park the caller
+    /// until it is signalled.
+    ///
+    /// Once `deadline_ns` (0 = none) has passed the wait ends with
+    /// `TimedOut` instead of parking.
+    ///
+    /// Returns (Result<WaitOutcome, KernelError>, Vec<Commit>).
+    pub fn wait_notification(
+        &mut self,
+        pid: ProcessId,
+        slot: CapSlot,
+        deadline_ns: u64,
+        timestamp: u64,
+    ) -> (Result<WaitOutcome, KernelError>, Vec<Commit>) {
+        match self.poll_notification(pid, slot, timestamp) {
+            (Ok(0), _) => {}
+            (Ok(bits), commits) => {
+                self.unpark(pid);
+                return (Ok(WaitOutcome::Signaled { bits }), commits);
+            }
+            (Err(e), commits) => {
+                self.unpark(pid);
+                return (Err(e), commits);
+            }
+        }
+
+        if deadline_ns != 0 && timestamp >= deadline_ns {
+            self.unpark(pid);
+            return (Ok(WaitOutcome::TimedOut), Vec::new());
+        }
+
+        let wait = Wait {
+            on: WaitOn::Notification(slot),
+            deadline_ns,
+        };
+        (self.park(pid, wait), Vec::new())
+    }
+
+    /// Collect the outcome of the caller's outstanding call, or park the
+    /// caller until the server replies or dies.
+    ///
+    /// The reply arrives as a `WaitOutcome::Message` on slot 0. A call
+    /// aborted by the server's death ends with `ProcessNotFound`.
+    ///
+    /// Returns (Result<WaitOutcome, KernelError>, Vec<Commit>).
+    pub fn wait_reply(
+        &mut self,
+        pid: ProcessId,
+    ) -> (Result<WaitOutcome, KernelError>, Vec<Commit>) {
+        match self.calls.remove(&pid) {
+            Some(CallState::Replied(message)) => {
+                self.unpark(pid);
+                if let Some(proc) = self.processes.get_mut(&pid) {
+                    proc.metrics.ipc_received += 1;
+                    proc.metrics.ipc_bytes_received += message.data.len() as u64;
+                }
+                let outcome = WaitOutcome::Message {
+                    slot: 0,
+                    message,
+                    installed_slots: Vec::new(),
+                };
+                (Ok(outcome), Vec::new())
+            }
+            Some(CallState::Aborted) => {
+                self.unpark(pid);
+                (Err(KernelError::ProcessNotFound), Vec::new())
+            }
+            Some(pending) => {
+                self.calls.insert(pid, pending);
+                let wait = Wait {
+                    on: WaitOn::Reply,
+                    deadline_ns: 0,
+                };
+                (self.park(pid, wait), Vec::new())
+            }
+            None => {
+                self.unpark(pid);
+                (Err(KernelError::InvalidArgument), Vec::new())
+            }
+        }
+    }
+
+    /// Retry the wait a parked process is blocked in.
+    ///
+    /// Returns None if the process is not parked.
+    pub fn resume_wait(
+        &mut self,
+        pid: ProcessId,
+        timestamp: u64,
+    ) -> Option<(Result<WaitOutcome, KernelError>, Vec<Commit>)> {
+        let wait = self.waits.get(&pid)?.clone();
+        Some(match wait.on {
+            WaitOn::Endpoints(slots) => self.wait_receive(pid, &slots, wait.deadline_ns, timestamp),
+            WaitOn::Notification(slot) => {
+                self.wait_notification(pid, slot, wait.deadline_ns, timestamp)
+            }
+            WaitOn::Reply => self.wait_reply(pid),
+        })
+    }
+
+    /// Check whether a process is parked in a timed wait.
+    pub fn is_parked(&self, pid: ProcessId) -> bool {
+        self.waits.contains_key(&pid)
+    }
+
+    /// Parked processes whose wait can end now.
+    ///
+    /// A wait can end when its deadline has passed, one of its endpoints has
+    /// a message, its notification was signalled, its call was answered or
+    /// aborted, or one of its slots stopped being usable.
+    pub fn parked_ready(&self, timestamp: u64) -> Vec<ProcessId> {
+        self.waits
+            .iter()
+            .filter(|(pid, wait)| {
+                (wait.deadline_ns != 0 && timestamp >= wait.deadline_ns)
+                    || match &wait.on {
+                        WaitOn::Endpoints(slots) => slots.iter().any(|&slot| {
+                            self.ipc_has_message(**pid, slot, timestamp).unwrap_or(true)
+                        }),
+                        WaitOn::Notification(slot) => self
+                            .notification_pending(**pid, *slot, timestamp)
+                            .unwrap_or(true),
+                        WaitOn::Reply => {
+                            !matches!(self.calls.get(pid), Some(CallState::Pending { .. }))
+                        }
+                    }
+            })
+            .map(|(pid, _)| *pid)
+            .collect()
+    }
+
+    /// Earliest deadline of any parked process.
+    ///
+    /// The runtime can arm its timer for this instead of polling.
+    pub fn next_deadline(&self) -> Option<u64> {
+        self.waits
+            .values()
+            .map(|wait| wait.deadline_ns)
+            .filter(|&deadline| deadline != 0)
+            .min()
+    }
+
+    /// Block a process in `wait` until `resume_wait` ends it.
+    fn park(&mut self, pid: ProcessId, wait: Wait) -> Result<WaitOutcome, KernelError> {
+        match self.processes.get_mut(&pid) {
+            Some(proc) => proc.state = ProcessState::Blocked,
+            None => return Err(KernelError::ProcessNotFound),
+        }
+        self.waits.insert(pid, wait);
+        Ok(WaitOutcome::Parked)
+    }
+
+    /// Let a parked process run again and forget its wait.
+    fn unpark(&mut self, pid: ProcessId) {
+        if self.waits.remove(&pid).is_some() {
+            if let Some(proc) = self.processes.get_mut(&pid) {
+                if proc.state == ProcessState::Blocked {
+                    proc.state = ProcessState::Running;
+                }
+            }
+        }
+    }
+}
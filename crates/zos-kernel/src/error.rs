@@ -17,6 +17,12 @@ pub enum KernelError {
     PermissionDenied,
     /// No message available (would block)
     WouldBlock,
+    /// Operation would exceed the process's resource quota
+    ResourceExhausted,
+    /// Endpoint queue is full
+    QueueFull,
+    /// Malformed syscall argument
+    InvalidArgument,
     /// HAL error
     Hal(HalError),
 }
@@ -0,0 +1,386 @@
+//! Time-travel queries over the CommitLog.
+//!
+//! Replay answers "what does the kernel look like now"; this module answers
+//! the same question for any earlier sequence number, and compares two of
+//! them:
+//!
+//! - [`state_at`] materializes the kernel state right after a given commit,
+//!   starting from the newest snapshot at or before it
+//! - [`diff_states`] / [`diff_seqs`] list what changed in the process table,
+//!   the capability spaces and the endpoints
+//!
+//! Only state that commits describe is compared. Pending messages and
+//! metrics are not replayed, so they never show up in a diff.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::system::System;
+use crate::types::{CapSlot, EndpointId, ProcessId, ProcessState};
+use crate::Capability;
+use zos_axiom::{replay_to_seq, Commit, ReplayResult};
+use zos_hal::HAL;
+
+/// Materialize the kernel state right after commit `seq`.
+///
+/// The returned system is in replay mode: its own CommitLog is empty.
+pub fn state_at<H: HAL + Default>(commits: &[Commit], seq: u64) -> ReplayResult<System<H>> {
+    let mut system = System::new_for_replay();
+    replay_to_seq(&mut system, commits, seq)?;
+    Ok(system)
+}
+
+/// Compute what changed between the states after commits `from` and `to`.
+pub fn diff_seqs<H: HAL + Default>(
+    commits: &[Commit],
+    from: u64,
+    to: u64,
+) -> ReplayResult<StateDiff> {
+    let before = state_at::<H>(commits, from)?;
+    let after = state_at::<H>(commits, to)?;
+    Ok(diff_states(&before, &after))
+}
+
+/// How one entry differs between two states.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Change<T> {
+    /// Present only in the later state
+    Added(T),
+    /// Present only in the earlier state
+    Removed(T),
+    /// Present in both, with different contents
+    Modified {
+        /// Entry in the earlier state
+        before: T,
+        /// Entry in the later state
+        after: T,
+    },
+}
+
+/// The parts of a process that commits describe.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProcessView {
+    /// Process name
+    pub name: String,
+    /// Process state
+    pub state: ProcessState,
+}
+
+/// A change to the process table.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProcessDiff {
+    pub pid: ProcessId,
+    pub change: Change<ProcessView>,
+}
+
+/// A change to one capability slot.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CapDiff {
+    pub pid: ProcessId,
+    pub slot: CapSlot,
+    pub change: Change<Capability>,
+}
+
+/// A change to an endpoint (endpoints are compared by owner).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EndpointDiff {
+    pub id: EndpointId,
+    pub change: Change<ProcessId>,
+}
+
+/// Structured difference between two kernel states.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StateDiff {
+    /// Processes created, removed or changed state
+    pub processes: Vec<ProcessDiff>,
+    /// Capability slots filled, emptied or overwritten
+    pub capabilities: Vec<CapDiff>,
+    /// Endpoints created, destroyed or re-owned
+    pub endpoints: Vec<EndpointDiff>,
+}
+
+impl StateDiff {
+    /// Check if the two states are identical.
+    pub fn is_empty(&self) -> bool {
+        self.processes.is_empty() && self.capabilities.is_empty() && self.endpoints.is_empty()
+    }
+}
+
+/// Compare two kernel states.
+pub fn diff_states<H: HAL>(before: &System<H>, after: &System<H>) -> StateDiff {
+    let view = |p: &crate::types::Process| ProcessView {
+        name: p.name.clone(),
+        state: p.state,
+    };
+    let processes = diff_maps(
+        before
+            .kernel
+            .processes
+            .iter()
+            .map(|(pid, p)| (*pid, view(p))),
+        after
+            .kernel
+            .processes
+            .iter()
+            .map(|(pid, p)| (*pid, view(p))),
+    )
+    .into_iter()
+    .map(|(pid, change)| ProcessDiff { pid, change })
+    .collect();
+
+    let caps = |system: &System<H>| -> Vec<((ProcessId, CapSlot), Capability)> {
+        system
+            .kernel
+            .cap_spaces
+            .iter()
+            .flat_map(|(pid, cspace)| {
+                cspace
+                    .slots
+                    .iter()
+                    .map(move |(slot, cap)| ((*pid, *slot), cap.clone()))
+            })
+            .collect()
+    };
+    let capabilities = diff_maps(caps(before), caps(after))
+        .into_iter()
+        .map(|((pid, slot), change)| CapDiff { pid, slot, change })
+        .collect();
+
+    let endpoints = diff_maps(
+        before
+            .kernel
+            .endpoints
+            .iter()
+            .map(|(id, ep)| (*id, ep.owner)),
+        after
+            .kernel
+            .endpoints
+            .iter()
+            .map(|(id, ep)| (*id, ep.owner)),
+    )
+    .into_iter()
+    .map(|(id, change)| EndpointDiff { id, change })
+    .collect();
+
+    StateDiff {
+        processes,
+        capabilities,
+        endpoints,
+    }
+}
+
+/// Merge two key-ordered entry lists into a list of changes.
+fn diff_maps<K: Ord, V: PartialEq>(
+    before: impl IntoIterator<Item = (K, V)>,
+    after: impl IntoIterator<Item = (K, V)>,
+) -> Vec<(K, Change<V>)> {
+    let mut before = before.into_iter().peekable();
+    let mut after = after.into_iter().peekable();
+    let mut changes = Vec::new();
+
+    loop {
+        let order = match (before.peek(), after.peek()) {
+            (None, None) => break,
+            (Some(_), None) => core::cmp::Ordering::Less,
+            (None, Some(_)) => core::cmp::Ordering::Greater,
+            (Some((b, _)), Some((a, _))) => b.cmp(a),
+        };
+        match order {
+            core::cmp::Ordering::Less => {
+                let (key, value) = before.next().unwrap();
+                changes.push((key, Change::Removed(value)));
+            }
+            core::cmp::Ordering::Greater => {
+                let (key, value) = after.next().unwrap();
+                changes.push((key, Change::Added(value)));
+            }
+            core::cmp::Ordering::Equal => {
+                let (key, old) = before.next().unwrap();
+                let (_, new) = after.next().unwrap();
+                if old != new {
+                    changes.push((
+                        key,
+                        Change::Modified {
+                            before: old,
+                            after: new,
+                        },
+                    ));
+                }
+            }
+        }
+    }
+    changes
+}
+
+fn describe_cap(cap: &Capability) -> String {
+    format!(
+        "cap {} -> {:?} {} [{}{}{}]",
+        cap.id,
+        cap.object_type,
+        cap.object_id,
+        if cap.permissions.read { "R" } else { "-" },
+        if cap.permissions.write { "W" } else { "-" },
+        if cap.permissions.grant { "G" } else { "-" },
+    )
+}
+
+impl fmt::Display for StateDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "(no changes)");
+        }
+
+        for diff in &self.processes {
+            let pid = diff.pid.0;
+            match &diff.change {
+                Change::Added(p) => writeln!(f, "+ process {} '{}' ({:?})", pid, p.name, p.state)?,
+                Change::Removed(p) => {
+                    writeln!(f, "- process {} '{}' ({:?})", pid, p.name, p.state)?
+                }
+                Change::Modified { before, after } => writeln!(
+                    f,
+                    "~ process {} '{}' {:?} -> {:?}",
+                    pid, after.name, before.state, after.state
+                )?,
+            }
+        }
+
+        for diff in &self.capabilities {
+            let (pid, slot) = (diff.pid.0, diff.slot);
+            match &diff.change {
+                Change::Added(cap) => {
+                    writeln!(f, "+ pid {} slot {}: {}", pid, slot, describe_cap(cap))?
+                }
+                Change::Removed(cap) => {
+                    writeln!(f, "- pid {} slot {}: {}", pid, slot, describe_cap(cap))?
+                }
+                Change::Modified { before, after } => writeln!(
+                    f,
+                    "~ pid {} slot {}: {} => {}",
+                    pid,
+                    slot,
+                    describe_cap(before),
+                    describe_cap(after)
+                )?,
+            }
+        }
+
+        for diff in &self.endpoints {
+            let id = diff.id.0;
+            match &diff.change {
+                Change::Added(owner) => writeln!(f, "+ endpoint {} (owner {})", id, owner.0)?,
+                Change::Removed(owner) => writeln!(f, "- endpoint {} (owner {})", id, owner.0)?,
+                Change::Modified { before, after } => {
+                    writeln!(f, "~ endpoint {} owner {} -> {}", id, before.0, after.0)?
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<H: HAL + Default> System<H> {
+    /// Materialize the kernel state right after commit `seq` of this
+    /// system's CommitLog.
+    pub fn state_at(&self, seq: u64) -> ReplayResult<System<H>> {
+        state_at(self.commitlog().commits(), seq)
+    }
+
+    /// Compute what changed between commits `from` and `to` of this
+    /// system's CommitLog.
+    pub fn diff_seqs(&self, from: u64, to: u64) -> ReplayResult<StateDiff> {
+        diff_seqs::<H>(self.commitlog().commits(), from, to)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capability::Permissions;
+    use alloc::string::ToString;
+    use zos_axiom::ReplayError;
+    use zos_hal::TestHal;
+
+    /// Build a system with a short history; returns it with the seqs after
+    /// each step (setup, grant, kill)
+    fn system_with_history() -> (System<TestHal>, [u64; 3]) {
+        let mut system = System::new(TestHal::default());
+        let a = system.register_process("a");
+        let b = system.register_process("b");
+        let (_, slot) = system.create_endpoint(a).unwrap();
+        let setup = system.commitlog().current_seq();
+
+        system
+            .grant_capability(a, slot, b, Permissions::read_only())
+            .unwrap();
+        let grant = system.commitlog().current_seq();
+
+        system.kill_process(b);
+        let kill = system.commitlog().current_seq();
+        (system, [setup, grant, kill])
+    }
+
+    #[test]
+    fn test_state_at_sequence() {
+        let (system, [setup, grant, _]) = system_with_history();
+
+        let at_setup = system.state_at(setup).unwrap();
+        assert_eq!(at_setup.list_processes().len(), 2);
+        assert_eq!(at_setup.get_cap_space(ProcessId(2)).unwrap().slots.len(), 0);
+
+        let at_grant = system.state_at(grant).unwrap();
+        assert_eq!(at_grant.get_cap_space(ProcessId(2)).unwrap().slots.len(), 1);
+
+        let at_genesis = system.state_at(0).unwrap();
+        assert!(at_genesis.list_processes().is_empty());
+    }
+
+    #[test]
+    fn test_state_at_out_of_range() {
+        let (system, [_, _, kill]) = system_with_history();
+        assert!(matches!(
+            system.state_at(kill + 1),
+            Err(ReplayError::SeqOutOfRange { .. })
+        ));
+    }
+
+    #[test]
+    fn test_diff_between_sequences() {
+        let (system, [setup, grant, _]) = system_with_history();
+
+        let diff = system.diff_seqs(setup, grant).unwrap();
+        assert!(diff.processes.is_empty());
+        assert!(diff.endpoints.is_empty());
+        assert_eq!(diff.capabilities.len(), 1);
+        assert_eq!(diff.capabilities[0].pid, ProcessId(2));
+        assert!(matches!(diff.capabilities[0].change, Change::Added(_)));
+
+        // Reversed direction reports the same slot as removed
+        let back = system.diff_seqs(grant, setup).unwrap();
+        assert!(matches!(back.capabilities[0].change, Change::Removed(_)));
+
+        assert!(system.diff_seqs(grant, grant).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_diff_reports_process_changes() {
+        let (system, [_, grant, kill]) = system_with_history();
+
+        let diff = system.diff_seqs(grant, kill).unwrap();
+        let killed = diff
+            .processes
+            .iter()
+            .find(|d| d.pid == ProcessId(2))
+            .unwrap();
+        assert!(matches!(
+            &killed.change,
+            Change::Modified { before, .. } if before.state == ProcessState::Running
+        ));
+
+        let text = diff.to_string();
+        assert!(text.contains("process 2"));
+        assert_eq!(StateDiff::default().to_string(), "(no changes)\n");
+    }
+}
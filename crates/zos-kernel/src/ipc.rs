@@ -3,13 +3,15 @@
 //! This module contains types for IPC messaging:
 //! - Messages and transferred capabilities
 //! - Endpoints and their metrics
+//! - Notifications (words of signal bits)
+//! - Outstanding calls
 //! - IPC traffic monitoring
 
 use alloc::collections::VecDeque;
 use alloc::vec::Vec;
 
 use crate::capability::Capability;
-use crate::types::{EndpointId, EndpointMetrics, ProcessId};
+use crate::types::{EndpointId, EndpointMetrics, NotificationId, ProcessId};
 use zos_axiom::CapSlot;
 
 /// Maximum capabilities per IPC message
@@ -19,6 +21,25 @@ pub const MAX_CAPS_PER_MESSAGE: usize = 8;
 /// Sized to support large IPC responses (e.g., PQ hybrid keys ~6KB)
 pub const MAX_MESSAGE_SIZE: usize = 16384;
 
+/// Queue capacity for endpoints created without an explicit capacity
+pub const DEFAULT_QUEUE_CAPACITY: usize = 64;
+
+/// Largest queue capacity a process may request
+pub const MAX_QUEUE_CAPACITY: usize = 1024;
+
+/// Extra queue slots only kernel notifications may use, so a full queue
+/// does not hide a child's death or a revocation from its owner
+pub const KERNEL_QUEUE_RESERVE: usize = 16;
+
+/// What a send does when the endpoint queue is full
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Fail the send with `QueueFull` and count the drop
+    Reject,
+    /// Leave the message with the sender, who gets `WouldBlock` and retries
+    Block,
+}
+
 /// A capability being transferred via IPC.
 ///
 /// When a capability is transferred, it is moved from the sender's CSpace
@@ -36,6 +57,8 @@ pub struct TransferredCap {
 pub struct Message {
     /// Sender process
     pub from: ProcessId,
+    /// Badge of the capability the message was sent through (0 = unbadged)
+    pub badge: u64,
     /// Message tag (application-defined)
     pub tag: u32,
     /// Message payload
@@ -44,6 +67,39 @@ pub struct Message {
     pub transferred_caps: Vec<TransferredCap>,
 }
 
+/// Result of a timed receive on one or more endpoints
+#[derive(Clone, Debug)]
+pub enum WaitOutcome {
+    /// A message arrived on `slot`; transferred caps were installed at
+    /// `installed_slots`
+    Message {
+        slot: CapSlot,
+        message: Message,
+        installed_slots: Vec<CapSlot>,
+    },
+    /// Pending bits were taken from a notification
+    Signaled { bits: u64 },
+    /// The deadline passed with no message
+    TimedOut,
+    /// No message yet; the process is parked until one arrives or the
+    /// deadline passes
+    Parked,
+}
+
+/// Progress of a process's outstanding call.
+///
+/// While `Pending`, the server holds a reply capability naming the caller.
+/// The caller's wait collects the outcome and clears the entry.
+#[derive(Clone, Debug)]
+pub enum CallState {
+    /// Waiting for `server` to reply
+    Pending { server: ProcessId },
+    /// The server replied with this message
+    Replied(Message),
+    /// The server died before replying
+    Aborted,
+}
+
 /// IPC endpoint
 pub struct Endpoint {
     /// Endpoint ID
@@ -52,10 +108,114 @@ pub struct Endpoint {
     pub owner: ProcessId,
     /// Queue of pending messages
     pub pending_messages: VecDeque<Message>,
+    /// Maximum number of pending messages
+    pub capacity: usize,
+    /// Behavior when the queue is full
+    pub policy: OverflowPolicy,
     /// Endpoint metrics
     pub metrics: EndpointMetrics,
 }
 
+impl Endpoint {
+    /// Create an endpoint with the default capacity and `Reject` policy
+    pub fn new(id: EndpointId, owner: ProcessId) -> Self {
+        Self::with_capacity(id, owner, DEFAULT_QUEUE_CAPACITY, OverflowPolicy::Reject)
+    }
+
+    /// Create an endpoint with an explicit capacity and overflow policy
+    pub fn with_capacity(
+        id: EndpointId,
+        owner: ProcessId,
+        capacity: usize,
+        policy: OverflowPolicy,
+    ) -> Self {
+        Self {
+            id,
+            owner,
+            pending_messages: VecDeque::new(),
+            capacity,
+            policy,
+            metrics: EndpointMetrics::default(),
+        }
+    }
+
+    /// Check if the queue has reached its capacity
+    pub fn is_full(&self) -> bool {
+        self.pending_messages.len() >= self.capacity
+    }
+
+    /// Enqueue a message.
+    ///
+    /// If the queue is full the message is handed back and, under the
+    /// `Reject` policy, counted as dropped.
+    pub fn enqueue(&mut self, msg: Message) -> Result<(), Message> {
+        if self.is_full() {
+            if self.policy == OverflowPolicy::Reject {
+                self.metrics.dropped_messages += 1;
+            }
+            return Err(msg);
+        }
+        self.push(msg);
+        Ok(())
+    }
+
+    /// Enqueue a kernel notification.
+    ///
+    /// Kernel messages may also use `KERNEL_QUEUE_RESERVE` slots past the
+    /// capacity. Once those are taken too the message is handed back and
+    /// counted as dropped, whatever the policy.
+    pub fn enqueue_kernel(&mut self, msg: Message) -> Result<(), Message> {
+        if self.pending_messages.len() >= self.capacity + KERNEL_QUEUE_RESERVE {
+            self.metrics.dropped_messages += 1;
+            return Err(msg);
+        }
+        self.push(msg);
+        Ok(())
+    }
+
+    /// Append a message and update the queue metrics
+    fn push(&mut self, msg: Message) {
+        let data_len = msg.data.len() as u64;
+        self.pending_messages.push_back(msg);
+        self.metrics.queue_depth = self.pending_messages.len();
+        self.metrics.total_messages += 1;
+        self.metrics.total_bytes += data_len;
+        if self.metrics.queue_depth > self.metrics.queue_high_water {
+            self.metrics.queue_high_water = self.metrics.queue_depth;
+        }
+    }
+}
+
+/// Notification object - a word of signal bits.
+///
+/// Signals OR into the word, so repeated signals coalesce without queueing
+/// anything; a wait or poll takes and clears the whole word.
+pub struct Notification {
+    /// Notification ID
+    pub id: NotificationId,
+    /// Owning process
+    pub owner: ProcessId,
+    /// Pending signal bits
+    pub bits: u64,
+}
+
+impl Notification {
+    /// Create a notification with no pending signals
+    pub fn new(id: NotificationId, owner: ProcessId) -> Self {
+        Self { id, owner, bits: 0 }
+    }
+
+    /// OR `bits` into the pending word
+    pub fn signal(&mut self, bits: u64) {
+        self.bits |= bits;
+    }
+
+    /// Take and clear the pending word
+    pub fn take(&mut self) -> u64 {
+        core::mem::take(&mut self.bits)
+    }
+}
+
 /// Detailed info about an endpoint
 #[derive(Clone, Debug)]
 pub struct EndpointDetail {
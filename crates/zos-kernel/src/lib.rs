@@ -29,6 +29,9 @@
 //! - `error` - Kernel error types
 //! - `core` - KernelCore implementation
 //! - `replay` - Deterministic replay support
+//! - `history` - State-at-sequence queries and diffs between sequences
+//! - `trace_replay` - Syscall-level replay of one process from a recorded trace
+//! - `snapshot` - Kernel state snapshots for CommitLog trimming
 
 #![no_std]
 extern crate alloc;
@@ -36,32 +39,41 @@ extern crate alloc;
 // Submodules
 pub mod capability;
 pub mod error;
+pub mod history;
 pub mod ipc;
 pub mod syscall;
 pub mod system;
+pub mod trace_replay;
 pub mod types;
 
 // Internal modules (now public for System)
 pub mod core;
 mod replay;
+mod snapshot;
 
 // Re-export all public types
-pub use capability::{axiom_check, AxiomError, Capability, CapabilitySpace, Permissions};
+pub use capability::{
+    axiom_check, AxiomError, Capability, CapabilitySpace, DerivationTree, Permissions,
+};
 pub use error::KernelError;
 pub use ipc::{
-    Endpoint, EndpointDetail, EndpointInfo, Message, MessageSummary, TransferredCap,
-    MAX_CAPS_PER_MESSAGE, MAX_MESSAGE_SIZE,
+    Endpoint, EndpointDetail, EndpointInfo, Message, MessageSummary, Notification, OverflowPolicy,
+    TransferredCap, WaitOutcome, DEFAULT_QUEUE_CAPACITY, KERNEL_QUEUE_RESERVE, MAX_CAPS_PER_MESSAGE,
+    MAX_MESSAGE_SIZE, MAX_QUEUE_CAPACITY,
 };
 pub use syscall::{
     CapInfo, RevokeNotification, Syscall, SyscallResult, MSG_CAP_REVOKED, MSG_CONSOLE_INPUT,
-    SYS_CALL, SYS_CAP_DELETE, SYS_CAP_DERIVE, SYS_CAP_GRANT, SYS_CAP_INSPECT, SYS_CAP_LIST,
+    SYS_CALL, SYS_CAP_DELETE, SYS_CAP_DERIVE, SYS_CAP_DERIVE_TTL, SYS_CAP_GRANT, SYS_CAP_GRANT_TTL,
+    SYS_CAP_INSPECT, SYS_CAP_LIST,
     SYS_CAP_REVOKE, SYS_CONSOLE_WRITE, SYS_CREATE_ENDPOINT, SYS_DEBUG, SYS_DELETE_ENDPOINT,
-    SYS_EXIT, SYS_KILL, SYS_PS, SYS_RECV, SYS_REPLY, SYS_SEND, SYS_SEND_CAP, SYS_TIME,
-    SYS_WALLCLOCK, SYS_YIELD,
+    SYS_CALL_TIMEOUT, SYS_EXIT, SYS_KILL, SYS_NOTIFY_CREATE, SYS_NOTIFY_POLL, SYS_NOTIFY_SIGNAL,
+    SYS_NOTIFY_WAIT, SYS_PS, SYS_RECV, SYS_RECV_TIMEOUT, SYS_REPLY, SYS_SEND, SYS_SEND_CAP,
+    SYS_TIME, SYS_WALLCLOCK, SYS_YIELD,
 };
 pub use types::{
-    CapSlot, EndpointId, EndpointMetrics, ObjectType, Process, ProcessId, ProcessMetrics,
-    ProcessState, SystemMetrics,
+    CapSlot, EndpointId, EndpointMetrics, ExitRecord, ExitStatus, NotificationId, ObjectType,
+    Process, ProcessId, ProcessMetrics, ProcessState, QuotaResource, RegionId, RegionMapping,
+    ResourceQuota, SharedRegion, SystemMetrics, MAX_REGION_SIZE, REGION_PAGE_SIZE,
 };
 
 // Re-export HAL types
@@ -69,11 +81,13 @@ pub use zos_hal::{HalError, HAL as HalTrait};
 
 // Re-export Axiom types
 pub use zos_axiom::{
-    apply_commit, replay as axiom_replay, replay_and_verify, AxiomGateway, Commit, CommitId,
-    CommitLog, CommitType, ReplayError, ReplayResult, Replayable, StateHasher, SysEvent,
-    SysEventType, SysLog,
+    apply_commit, replay as axiom_replay, replay_and_verify, replay_start, replay_to_seq, AxiomGateway, Checkpoint,
+    CheckpointSigner, CheckpointVerifier, CodecError, Commit, CommitId, CommitLog, CommitLogFile, CommitType, ReplayError, ReplayResult, Replayable, StateHasher, SysEvent,
+    SysEventType, SysLog, SysLogFile, SyscallTrace, TraceDivergence,
 };
 
 // Re-export main types from modules
 pub use core::KernelCore;
-pub use system::System;
+pub use history::{Change, StateDiff};
+pub use system::{System, KERNEL_KEYSTORE_PREFIX};
+pub use trace_replay::TraceReplayDriver;
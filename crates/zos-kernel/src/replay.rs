@@ -3,13 +3,15 @@
 //! This module implements the `Replayable` trait, allowing system state to be
 //! reconstructed from a commit log for auditing and verification purposes.
 
-use alloc::collections::VecDeque;
 use alloc::string::String;
+use alloc::vec::Vec;
 
-use crate::ipc::Endpoint;
+use crate::ipc::{Endpoint, Notification};
+use crate::snapshot::{self, process_state_to_u8};
 use crate::system::System;
 use crate::types::{
-    EndpointId, EndpointMetrics, ObjectType, Process, ProcessId, ProcessMetrics, ProcessState,
+    EndpointId, IrqBinding, IrqTarget, NotificationId, ObjectType, Process, ProcessId,
+    ProcessMetrics, ProcessState, RegionId, RegionMapping, ResourceQuota, SharedRegion,
 };
 use crate::{Capability, CapabilitySpace, Permissions};
 use zos_axiom::{ReplayError, ReplayResult, Replayable, StateHasher};
@@ -20,11 +22,13 @@ impl<H: HAL> Replayable for System<H> {
         Ok(())
     }
 
-    fn replay_create_process(&mut self, pid: u64, _parent: u64, name: String) -> ReplayResult<()> {
+    fn replay_create_process(&mut self, pid: u64, parent: u64, name: String) -> ReplayResult<()> {
         let process = Process {
             pid: ProcessId(pid),
+            parent: ProcessId(parent),
             name,
             state: ProcessState::Running,
+            quota: ResourceQuota::default(),
             metrics: ProcessMetrics::default(),
         };
         self.kernel.processes.insert(ProcessId(pid), process);
@@ -47,6 +51,17 @@ impl<H: HAL> Replayable for System<H> {
             .get_mut(&ProcessId(pid))
             .ok_or(ReplayError::ProcessNotFound(pid))?;
         process.state = ProcessState::Zombie;
+        let parent = process.parent;
+
+        // Orphans are adopted by the grandparent, and copies derived from
+        // the dead process's capabilities hang off their grandparents, as
+        // at run time
+        self.kernel.adopt_children(ProcessId(pid), parent);
+        if let Some(cspace) = self.kernel.cap_spaces.get(&ProcessId(pid)) {
+            for cap in cspace.slots.values() {
+                self.kernel.cap_derivations.remove(cap.id);
+            }
+        }
         Ok(())
     }
 
@@ -83,6 +98,7 @@ impl<H: HAL> Replayable for System<H> {
             permissions: Permissions::from_byte(perms),
             generation: 0,
             expires_at: 0,
+            badge: 0,
         };
 
         let cspace = self
@@ -112,20 +128,66 @@ impl<H: HAL> Replayable for System<H> {
             .cap_spaces
             .get_mut(&ProcessId(pid))
             .ok_or(ReplayError::ProcessNotFound(pid))?;
-        cspace.slots.remove(&slot);
+        if let Some(cap) = cspace.slots.remove(&slot) {
+            self.kernel.cap_derivations.remove(cap.id);
+        }
+        Ok(())
+    }
+
+    fn replay_badge_capability(&mut self, pid: u64, slot: u32, badge: u64) -> ReplayResult<()> {
+        let cap = self
+            .kernel
+            .cap_spaces
+            .get_mut(&ProcessId(pid))
+            .ok_or(ReplayError::ProcessNotFound(pid))?
+            .slots
+            .get_mut(&slot)
+            .ok_or_else(|| ReplayError::InvalidCommit(alloc::format!("no cap at slot {}", slot)))?;
+        cap.badge = badge;
+        Ok(())
+    }
+
+    fn replay_expire_capability(
+        &mut self,
+        pid: u64,
+        slot: u32,
+        expires_at: u64,
+    ) -> ReplayResult<()> {
+        let cap = self
+            .kernel
+            .cap_spaces
+            .get_mut(&ProcessId(pid))
+            .ok_or(ReplayError::ProcessNotFound(pid))?
+            .slots
+            .get_mut(&slot)
+            .ok_or_else(|| ReplayError::InvalidCommit(alloc::format!("no cap at slot {}", slot)))?;
+        cap.expires_at = expires_at;
         Ok(())
     }
 
     fn replay_cap_granted(
         &mut self,
-        _from_pid: u64,
+        from_pid: u64,
         _to_pid: u64,
-        _from_slot: u32,
+        from_slot: u32,
         _to_slot: u32,
         new_cap_id: u64,
         _perms: zos_axiom::Permissions,
     ) -> ReplayResult<()> {
-        // Just update next_cap_id (actual insertion handled by CapInserted)
+        // Record the derivation (actual insertion handled by CapInserted)
+        let source_id = self
+            .kernel
+            .cap_spaces
+            .get(&ProcessId(from_pid))
+            .ok_or(ReplayError::ProcessNotFound(from_pid))?
+            .slots
+            .get(&from_slot)
+            .map(|cap| cap.id)
+            .ok_or_else(|| {
+                ReplayError::InvalidCommit(alloc::format!("no cap at slot {}", from_slot))
+            })?;
+        self.kernel.cap_derivations.link(source_id, new_cap_id);
+
         if new_cap_id >= self.kernel.next_cap_id {
             self.kernel.next_cap_id = new_cap_id + 1;
         }
@@ -137,12 +199,9 @@ impl<H: HAL> Replayable for System<H> {
             return Err(ReplayError::ProcessNotFound(owner));
         }
 
-        let endpoint = Endpoint {
-            id: EndpointId(id),
-            owner: ProcessId(owner),
-            pending_messages: VecDeque::new(),
-            metrics: EndpointMetrics::default(),
-        };
+        // Queue capacities are not recorded, so replayed endpoints get the
+        // default one
+        let endpoint = Endpoint::new(EndpointId(id), ProcessId(owner));
         self.kernel.endpoints.insert(EndpointId(id), endpoint);
 
         // Update next_endpoint_id to avoid collisions
@@ -158,6 +217,126 @@ impl<H: HAL> Replayable for System<H> {
         Ok(())
     }
 
+    fn replay_create_notification(&mut self, id: u64, owner: u64) -> ReplayResult<()> {
+        if !self.kernel.processes.contains_key(&ProcessId(owner)) {
+            return Err(ReplayError::ProcessNotFound(owner));
+        }
+
+        let id = NotificationId(id);
+        self.kernel
+            .notifications
+            .insert(id, Notification::new(id, ProcessId(owner)));
+
+        // Update next_notification_id to avoid collisions
+        if id.0 >= self.kernel.next_notification_id {
+            self.kernel.next_notification_id = id.0 + 1;
+        }
+
+        Ok(())
+    }
+
+    fn replay_signal_notification(&mut self, id: u64, bits: u64) -> ReplayResult<()> {
+        self.replay_notification_mut(id)?.signal(bits);
+        Ok(())
+    }
+
+    fn replay_take_notification(&mut self, id: u64, bits: u64) -> ReplayResult<()> {
+        self.replay_notification_mut(id)?.bits &= !bits;
+        Ok(())
+    }
+
+    fn replay_bind_irq(
+        &mut self,
+        irq: u8,
+        pid: u64,
+        target_type: u8,
+        target_id: u64,
+        bits: u64,
+    ) -> ReplayResult<()> {
+        if !self.kernel.processes.contains_key(&ProcessId(pid)) {
+            return Err(ReplayError::ProcessNotFound(pid));
+        }
+        let target = IrqTarget::from_parts(target_type, target_id, bits)
+            .ok_or(ReplayError::UnknownObjectType(target_type))?;
+        self.kernel.irq_bindings.insert(
+            irq,
+            IrqBinding {
+                owner: ProcessId(pid),
+                target,
+                masked: false,
+            },
+        );
+        Ok(())
+    }
+
+    fn replay_unbind_irq(&mut self, irq: u8) -> ReplayResult<()> {
+        self.kernel.irq_bindings.remove(&irq);
+        Ok(())
+    }
+
+    fn replay_mask_irq(&mut self, irq: u8, masked: bool) -> ReplayResult<()> {
+        let binding = self
+            .kernel
+            .irq_bindings
+            .get_mut(&irq)
+            .ok_or_else(|| ReplayError::InvalidCommit(alloc::format!("IRQ {} not bound", irq)))?;
+        binding.masked = masked;
+        Ok(())
+    }
+
+    fn replay_create_region(&mut self, id: u64, owner: u64, size: usize) -> ReplayResult<()> {
+        if !self.kernel.processes.contains_key(&ProcessId(owner)) {
+            return Err(ReplayError::ProcessNotFound(owner));
+        }
+        let id = RegionId(id);
+        self.kernel
+            .regions
+            .insert(id, SharedRegion::new(id, ProcessId(owner), size));
+
+        // Update next_region_id to avoid collisions
+        if id.0 >= self.kernel.next_region_id {
+            self.kernel.next_region_id = id.0 + 1;
+        }
+
+        Ok(())
+    }
+
+    fn replay_map_region(
+        &mut self,
+        pid: u64,
+        id: u64,
+        writable: bool,
+        window: u32,
+    ) -> ReplayResult<()> {
+        if !self.kernel.processes.contains_key(&ProcessId(pid)) {
+            return Err(ReplayError::ProcessNotFound(pid));
+        }
+        self.replay_region_mut(id)?
+            .mappings
+            .insert(ProcessId(pid), RegionMapping { writable, window });
+        Ok(())
+    }
+
+    fn replay_unmap_region(&mut self, pid: u64, id: u64) -> ReplayResult<()> {
+        self.replay_region_mut(id)?.mappings.remove(&ProcessId(pid));
+        Ok(())
+    }
+
+    fn replay_destroy_region(&mut self, id: u64) -> ReplayResult<()> {
+        self.kernel.regions.remove(&RegionId(id));
+        Ok(())
+    }
+
+    fn replay_set_quota(&mut self, pid: u64, quota: ResourceQuota) -> ReplayResult<()> {
+        let process = self
+            .kernel
+            .processes
+            .get_mut(&ProcessId(pid))
+            .ok_or(ReplayError::ProcessNotFound(pid))?;
+        process.quota = quota;
+        Ok(())
+    }
+
     fn replay_message_sent(
         &mut self,
         _from_pid: u64,
@@ -169,6 +348,14 @@ impl<H: HAL> Replayable for System<H> {
         Ok(())
     }
 
+    fn snapshot_state(&self) -> Vec<u8> {
+        snapshot::encode(&self.kernel)
+    }
+
+    fn replay_restore_snapshot(&mut self, state: &[u8]) -> ReplayResult<()> {
+        snapshot::restore(&mut self.kernel, state)
+    }
+
     fn state_hash(&self) -> [u8; 32] {
         let mut hasher = StateHasher::new();
 
@@ -176,8 +363,10 @@ impl<H: HAL> Replayable for System<H> {
         hasher.write_u64(self.kernel.processes.len() as u64);
         for (pid, proc) in &self.kernel.processes {
             hasher.write_u64(pid.0);
+            hasher.write_u64(proc.parent.0);
             hasher.write_str(&proc.name);
             hasher.write_u8(process_state_to_u8(proc.state));
+            hasher.write_bytes(&proc.quota.to_bytes());
         }
 
         // Hash capability spaces
@@ -193,9 +382,17 @@ impl<H: HAL> Replayable for System<H> {
                 hasher.write_u8(cap.permissions.to_byte());
                 hasher.write_u32(cap.generation);
                 hasher.write_u64(cap.expires_at);
+                hasher.write_u64(cap.badge);
             }
         }
 
+        // Hash capability derivations
+        hasher.write_u64(self.kernel.cap_derivations.len() as u64);
+        for (child, parent) in self.kernel.cap_derivations.links() {
+            hasher.write_u64(child);
+            hasher.write_u64(parent);
+        }
+
         // Hash endpoints
         hasher.write_u64(self.kernel.endpoints.len() as u64);
         for (id, ep) in &self.kernel.endpoints {
@@ -203,10 +400,62 @@ impl<H: HAL> Replayable for System<H> {
             hasher.write_u64(ep.owner.0);
         }
 
+        // Hash notifications
+        hasher.write_u64(self.kernel.notifications.len() as u64);
+        for (id, notification) in &self.kernel.notifications {
+            hasher.write_u64(id.0);
+            hasher.write_u64(notification.owner.0);
+            hasher.write_u64(notification.bits);
+        }
+
+        // Hash IRQ bindings
+        hasher.write_u64(self.kernel.irq_bindings.len() as u64);
+        for (irq, binding) in &self.kernel.irq_bindings {
+            let (target_type, target_id, bits) = binding.target.to_parts();
+            hasher.write_u8(*irq);
+            hasher.write_u64(binding.owner.0);
+            hasher.write_u8(target_type);
+            hasher.write_u64(target_id);
+            hasher.write_u64(bits);
+            hasher.write_u8(binding.masked as u8);
+        }
+
+        // Hash shared regions
+        hasher.write_u64(self.kernel.regions.len() as u64);
+        for (id, region) in &self.kernel.regions {
+            hasher.write_u64(id.0);
+            hasher.write_u64(region.owner.0);
+            hasher.write_u64(region.size as u64);
+            hasher.write_u64(region.mappings.len() as u64);
+            for (pid, mapping) in &region.mappings {
+                hasher.write_u64(pid.0);
+                hasher.write_u8(mapping.writable as u8);
+                hasher.write_u32(mapping.window);
+            }
+        }
+
         hasher.finalize()
     }
 }
 
+impl<H: HAL> System<H> {
+    /// Look up a notification a replayed commit refers to
+    fn replay_notification_mut(&mut self, id: u64) -> ReplayResult<&mut Notification> {
+        self.kernel
+            .notifications
+            .get_mut(&NotificationId(id))
+            .ok_or_else(|| ReplayError::InvalidCommit(alloc::format!("no notification {}", id)))
+    }
+
+    /// Look up a region a replayed commit refers to
+    fn replay_region_mut(&mut self, id: u64) -> ReplayResult<&mut SharedRegion> {
+        self.kernel
+            .regions
+            .get_mut(&RegionId(id))
+            .ok_or_else(|| ReplayError::InvalidCommit(alloc::format!("no region {}", id)))
+    }
+}
+
 /// Map object type byte to ObjectType enum
 fn map_object_type(object_type: u8) -> ReplayResult<ObjectType> {
     match object_type {
@@ -216,19 +465,12 @@ fn map_object_type(object_type: u8) -> ReplayResult<ObjectType> {
         4 => Ok(ObjectType::Irq),
         5 => Ok(ObjectType::IoPort),
         6 => Ok(ObjectType::Console),
+        12 => Ok(ObjectType::Notification),
+        13 => Ok(ObjectType::Reply),
         _ => Err(ReplayError::UnknownObjectType(object_type)),
     }
 }
 
-/// Convert ProcessState to u8 for hashing
-fn process_state_to_u8(state: ProcessState) -> u8 {
-    match state {
-        ProcessState::Running => 0,
-        ProcessState::Blocked => 1,
-        ProcessState::Zombie => 2,
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -305,6 +547,19 @@ mod tests {
         assert_eq!(proc.state, ProcessState::Zombie);
     }
 
+    #[test]
+    fn test_replay_exit_process_reparents_children() {
+        let mut system: System<TestHal> = System::new_for_replay();
+
+        system.replay_create_process(1, 0, String::from("init")).unwrap();
+        system.replay_create_process(2, 1, String::from("shell")).unwrap();
+        system.replay_create_process(3, 2, String::from("job")).unwrap();
+        assert_eq!(system.kernel.processes[&ProcessId(2)].parent, ProcessId(1));
+
+        system.replay_exit_process(2, 0).unwrap();
+        assert_eq!(system.kernel.processes[&ProcessId(3)].parent, ProcessId(1));
+    }
+
     #[test]
     fn test_replay_exit_process_not_found() {
         let mut system: System<TestHal> = System::new_for_replay();
@@ -455,11 +710,36 @@ mod tests {
             grant: false,
         };
 
-        // replay_cap_granted just updates next_cap_id
+        // The source cap must exist; replay records the derivation and
+        // updates next_cap_id (the copy itself arrives as CapInserted)
+        assert!(system.replay_cap_granted(1, 2, 0, 0, 100, perms).is_err());
+        system.replay_insert_capability(1, 0, 7, 1, 1, 0x07).unwrap();
         let result = system.replay_cap_granted(1, 2, 0, 0, 100, perms);
         assert!(result.is_ok());
 
         assert_eq!(system.kernel.next_cap_id, 101);
+        assert_eq!(system.kernel.cap_derivations.parent(100), Some(7));
+    }
+
+    #[test]
+    fn test_replay_remove_capability_keeps_derived_copies_revocable() {
+        let mut system: System<TestHal> = System::new_for_replay();
+        let perms = zos_axiom::Permissions {
+            read: true,
+            write: true,
+            grant: true,
+        };
+
+        system.replay_create_process(1, 0, String::from("a")).unwrap();
+        system.replay_insert_capability(1, 0, 1, 1, 1, 0x07).unwrap();
+        system.replay_cap_granted(1, 1, 0, 1, 2, perms).unwrap();
+        system.replay_insert_capability(1, 1, 2, 1, 1, 0x07).unwrap();
+        system.replay_cap_granted(1, 1, 1, 2, 3, perms).unwrap();
+        system.replay_insert_capability(1, 2, 3, 1, 1, 0x07).unwrap();
+
+        system.replay_remove_capability(1, 1).unwrap();
+        assert_eq!(system.kernel.cap_derivations.parent(3), Some(1));
+        assert_eq!(system.kernel.cap_derivations.parent(2), None);
     }
 
     // ========================================================================
@@ -604,6 +884,17 @@ mod tests {
         assert_ne!(hash1, hash2, "Process state change should affect hash");
     }
 
+    #[test]
+    fn test_state_hash_includes_parent() {
+        let mut system1: System<TestHal> = System::new_for_replay();
+        let mut system2: System<TestHal> = System::new_for_replay();
+
+        system1.replay_create_process(2, 0, String::from("test")).unwrap();
+        system2.replay_create_process(2, 1, String::from("test")).unwrap();
+
+        assert_ne!(system1.state_hash(), system2.state_hash());
+    }
+
     #[test]
     fn test_state_hash_includes_capabilities() {
         let mut system1: System<TestHal> = System::new_for_replay();
@@ -621,6 +912,24 @@ mod tests {
         assert_ne!(hash1, hash2, "Capability changes should affect hash");
     }
 
+    #[test]
+    fn test_state_hash_includes_badge() {
+        let mut system1: System<TestHal> = System::new_for_replay();
+        let mut system2: System<TestHal> = System::new_for_replay();
+
+        for system in [&mut system1, &mut system2] {
+            system.replay_create_process(1, 0, String::from("test")).unwrap();
+            system.replay_insert_capability(1, 0, 100, 1, 42, 0x07).unwrap();
+        }
+        system1.replay_badge_capability(1, 0, 0xb00c).unwrap();
+
+        assert_ne!(
+            system1.state_hash(),
+            system2.state_hash(),
+            "Badge changes should affect hash"
+        );
+    }
+
     #[test]
     fn test_state_hash_includes_endpoints() {
         let mut system1: System<TestHal> = System::new_for_replay();
@@ -650,6 +959,8 @@ mod tests {
         assert_eq!(map_object_type(4).unwrap(), ObjectType::Irq);
         assert_eq!(map_object_type(5).unwrap(), ObjectType::IoPort);
         assert_eq!(map_object_type(6).unwrap(), ObjectType::Console);
+        assert_eq!(map_object_type(12).unwrap(), ObjectType::Notification);
+        assert_eq!(map_object_type(13).unwrap(), ObjectType::Reply);
     }
 
     #[test]
@@ -658,4 +969,245 @@ mod tests {
         assert!(matches!(map_object_type(7), Err(ReplayError::UnknownObjectType(7))));
         assert!(matches!(map_object_type(255), Err(ReplayError::UnknownObjectType(255))));
     }
+
+    // ========================================================================
+    // snapshot tests
+    // ========================================================================
+
+    fn populated_system() -> System<TestHal> {
+        let mut system: System<TestHal> = System::new_for_replay();
+        system.replay_create_process(1, 0, String::from("init")).unwrap();
+        system.replay_create_process(2, 1, String::from("terminal")).unwrap();
+        system.replay_create_endpoint(1, 1).unwrap();
+        system.replay_insert_capability(1, 0, 1, 1, 1, 0x07).unwrap();
+        system.replay_insert_capability(2, 3, 2, 1, 1, 0x01).unwrap();
+        system.replay_exit_process(2, 0).unwrap();
+        system
+    }
+
+    #[test]
+    fn test_snapshot_roundtrip() {
+        let system = populated_system();
+        let snapshot = system.snapshot_state();
+
+        let mut restored: System<TestHal> = System::new_for_replay();
+        restored.replay_restore_snapshot(&snapshot).unwrap();
+
+        assert_eq!(restored.state_hash(), system.state_hash());
+        assert_eq!(restored.kernel.next_pid, system.kernel.next_pid);
+        assert_eq!(restored.kernel.next_endpoint_id, system.kernel.next_endpoint_id);
+        assert_eq!(restored.kernel.next_cap_id, system.kernel.next_cap_id);
+        assert_eq!(
+            restored.kernel.cap_spaces[&ProcessId(2)].next_slot,
+            system.kernel.cap_spaces[&ProcessId(2)].next_slot
+        );
+    }
+
+    #[test]
+    fn test_snapshot_preserves_quota() {
+        let mut system = populated_system();
+        let quota = ResourceQuota {
+            max_endpoints: 2,
+            ..Default::default()
+        };
+        let before = system.state_hash();
+        system.replay_set_quota(1, quota).unwrap();
+        assert_ne!(system.state_hash(), before, "Quota changes should affect hash");
+
+        let mut restored: System<TestHal> = System::new_for_replay();
+        restored
+            .replay_restore_snapshot(&system.snapshot_state())
+            .unwrap();
+        assert_eq!(restored.kernel.processes[&ProcessId(1)].quota, quota);
+        assert_eq!(restored.state_hash(), system.state_hash());
+    }
+
+    #[test]
+    fn test_snapshot_restores_version_1() {
+        // One running process holding one endpoint capability, in the
+        // version 1 layout: no quotas, no badges, nothing after endpoints
+        let mut v1 = alloc::vec![1u8];
+        for counter in [2u64, 2, 2] {
+            v1.extend_from_slice(&counter.to_le_bytes());
+        }
+        v1.extend_from_slice(&1u32.to_le_bytes());
+        v1.extend_from_slice(&1u64.to_le_bytes());
+        v1.extend_from_slice(&4u32.to_le_bytes());
+        v1.extend_from_slice(b"init");
+        v1.push(0);
+        v1.extend_from_slice(&1u32.to_le_bytes());
+        v1.extend_from_slice(&1u64.to_le_bytes());
+        v1.extend_from_slice(&1u32.to_le_bytes());
+        v1.extend_from_slice(&1u32.to_le_bytes());
+        v1.extend_from_slice(&0u32.to_le_bytes());
+        v1.extend_from_slice(&1u64.to_le_bytes());
+        v1.push(ObjectType::Endpoint as u8);
+        v1.extend_from_slice(&1u64.to_le_bytes());
+        v1.push(0x07);
+        v1.extend_from_slice(&0u32.to_le_bytes());
+        v1.extend_from_slice(&0u64.to_le_bytes());
+        v1.extend_from_slice(&1u32.to_le_bytes());
+        v1.extend_from_slice(&1u64.to_le_bytes());
+        v1.extend_from_slice(&1u64.to_le_bytes());
+
+        let mut expected: System<TestHal> = System::new_for_replay();
+        expected.replay_create_process(1, 0, String::from("init")).unwrap();
+        expected.replay_create_endpoint(1, 1).unwrap();
+        expected.replay_insert_capability(1, 0, 1, 1, 1, 0x07).unwrap();
+
+        let mut restored: System<TestHal> = System::new_for_replay();
+        restored.replay_restore_snapshot(&v1).unwrap();
+        assert_eq!(restored.state_hash(), expected.state_hash());
+        assert_eq!(restored.kernel.next_pid, 2);
+        assert!(restored.kernel.processes[&ProcessId(1)].quota.is_unlimited());
+    }
+
+    #[test]
+    fn test_snapshot_preserves_badge() {
+        let mut system = populated_system();
+        let before = system.state_hash();
+        system.replay_badge_capability(2, 3, 0xb00c).unwrap();
+        assert_ne!(system.state_hash(), before, "Badges should affect hash");
+
+        let mut restored: System<TestHal> = System::new_for_replay();
+        restored
+            .replay_restore_snapshot(&system.snapshot_state())
+            .unwrap();
+        assert_eq!(restored.kernel.cap_spaces[&ProcessId(2)].slots[&3].badge, 0xb00c);
+        assert_eq!(restored.state_hash(), system.state_hash());
+    }
+
+    #[test]
+    fn test_snapshot_preserves_notifications() {
+        let mut system = populated_system();
+        let before = system.state_hash();
+        system.replay_create_notification(1, 1).unwrap();
+        system.replay_signal_notification(1, 0b101).unwrap();
+        assert_ne!(system.state_hash(), before, "Notifications should affect hash");
+
+        let mut restored: System<TestHal> = System::new_for_replay();
+        restored
+            .replay_restore_snapshot(&system.snapshot_state())
+            .unwrap();
+        assert_eq!(
+            restored.kernel.notifications[&NotificationId(1)].bits,
+            0b101
+        );
+        assert_eq!(restored.kernel.next_notification_id, 2);
+        assert_eq!(restored.state_hash(), system.state_hash());
+
+        system.replay_take_notification(1, 0b101).unwrap();
+        assert_eq!(system.kernel.notifications[&NotificationId(1)].bits, 0);
+    }
+
+    #[test]
+    fn test_snapshot_preserves_irq_bindings() {
+        let mut system = populated_system();
+        system.replay_create_notification(1, 1).unwrap();
+        let before = system.state_hash();
+        system
+            .replay_bind_irq(5, 1, ObjectType::Notification as u8, 1, 0b10)
+            .unwrap();
+        system.replay_mask_irq(5, true).unwrap();
+        assert_ne!(system.state_hash(), before, "IRQ bindings should affect hash");
+
+        let mut restored: System<TestHal> = System::new_for_replay();
+        restored
+            .replay_restore_snapshot(&system.snapshot_state())
+            .unwrap();
+        let binding = &restored.kernel.irq_bindings[&5];
+        assert_eq!(binding.owner, ProcessId(1));
+        assert!(binding.masked);
+        assert_eq!(restored.state_hash(), system.state_hash());
+
+        system.replay_unbind_irq(5).unwrap();
+        assert!(system.replay_mask_irq(5, false).is_err());
+    }
+
+    #[test]
+    fn test_snapshot_preserves_regions() {
+        let mut system = populated_system();
+        let before = system.state_hash();
+        system.replay_create_region(1, 1, 8192).unwrap();
+        system.replay_map_region(2, 1, false, 0x4000).unwrap();
+        assert_ne!(system.state_hash(), before, "Regions should affect hash");
+
+        let mut restored: System<TestHal> = System::new_for_replay();
+        restored
+            .replay_restore_snapshot(&system.snapshot_state())
+            .unwrap();
+        let region = &restored.kernel.regions[&RegionId(1)];
+        assert_eq!(region.size, 8192);
+        assert_eq!(
+            region.mappings[&ProcessId(2)],
+            RegionMapping {
+                writable: false,
+                window: 0x4000
+            }
+        );
+        assert_eq!(restored.kernel.next_region_id, 2);
+        assert_eq!(restored.state_hash(), system.state_hash());
+
+        system.replay_destroy_region(1).unwrap();
+        assert!(system.replay_unmap_region(2, 1).is_err());
+    }
+
+    #[test]
+    fn test_snapshot_replaces_existing_state() {
+        let snapshot = System::<TestHal>::new_for_replay().snapshot_state();
+
+        let mut system = populated_system();
+        system.replay_restore_snapshot(&snapshot).unwrap();
+
+        assert!(system.kernel.processes.is_empty());
+        assert_eq!(system.kernel.next_pid, 1);
+    }
+
+    #[test]
+    fn test_snapshot_rejects_truncated_data() {
+        let system = populated_system();
+        let snapshot = system.snapshot_state();
+
+        let mut restored: System<TestHal> = System::new_for_replay();
+        let result = restored.replay_restore_snapshot(&snapshot[..snapshot.len() - 1]);
+        assert!(matches!(result, Err(ReplayError::InvalidCommit(_))));
+        // Failed restore leaves state untouched
+        assert!(restored.kernel.processes.is_empty());
+    }
+
+    #[test]
+    fn test_snapshot_rejects_unknown_version() {
+        let mut snapshot = populated_system().snapshot_state();
+        snapshot[0] = 0xFF;
+
+        let mut restored: System<TestHal> = System::new_for_replay();
+        let result = restored.replay_restore_snapshot(&snapshot);
+        assert!(matches!(result, Err(ReplayError::InvalidCommit(_))));
+    }
+
+    #[test]
+    fn test_replay_from_system_snapshot() {
+        use zos_axiom::replay_and_verify;
+
+        let mut live = System::new(TestHal::default());
+        let init = live.register_process("init");
+        live.create_endpoint(init).unwrap();
+        live.snapshot();
+
+        let worker = live.register_process("worker");
+        let (_, worker_slot) = live.create_endpoint(worker).unwrap();
+        live.delete_capability(worker, worker_slot).unwrap();
+
+        let commits = live.commitlog().commits();
+        let tail = live.commitlog().replay_base();
+        assert!(tail.len() < commits.len());
+
+        // Replaying just the tail reaches the same state as the live system
+        let mut from_snapshot: System<TestHal> = System::new_for_replay();
+        replay_and_verify(&mut from_snapshot, tail, live.state_hash()).unwrap();
+
+        // ...and so does replaying the entire log
+        let mut from_genesis: System<TestHal> = System::new_for_replay();
+        replay_and_verify(&mut from_genesis, commits, live.state_hash()).unwrap();
+    }
 }
@@ -0,0 +1,374 @@
+//! Kernel state snapshots for the CommitLog.
+//!
+//! A snapshot captures exactly the state that replay reconstructs: the
+//! process table, capability spaces and their derivation links, endpoints
+//! (without queued messages or queue capacities, which restore to the
+//! default), notifications, IRQ bindings, shared regions and the ID
+//! counters. Metrics and message queues are volatile and are reset on
+//! restore, matching what replay from genesis would produce.
+//!
+//! # Format (version 2)
+//!
+//! All integers are little-endian; strings are `u32` length + UTF-8 bytes.
+//!
+//! ```text
+//! version: u8
+//! next_pid: u64, next_endpoint_id: u64, next_cap_id: u64
+//! processes:  u32 count, then { pid: u64, name: str, state: u8, quota: [u8; 40],
+//!               parent: u64 }
+//! cap_spaces: u32 count, then { pid: u64, next_slot: u32, u32 count,
+//!               then { slot: u32, id: u64, object_type: u8, object_id: u64,
+//!                      perms: u8, generation: u32, expires_at: u64, badge: u64 } }
+//! endpoints:  u32 count, then { id: u64, owner: u64 }
+//! next_notification_id: u64
+//! notifications: u32 count, then { id: u64, owner: u64, bits: u64 }
+//! irq_bindings: u32 count, then { irq: u8, owner: u64, target_type: u8,
+//!                 target_id: u64, bits: u64, masked: u8 }
+//! next_region_id: u64
+//! regions: u32 count, then { id: u64, owner: u64, size: u64, u32 count,
+//!            then { pid: u64, writable: u8, window: u32 } }
+//! cap_derivations: u32 count, then { child: u64, parent: u64 }
+//! ```
+//!
+//! Version 1 snapshots have no `quota`, `parent` or `badge` fields and end
+//! after the endpoints. They restore with unlimited quotas, no parents,
+//! unbadged capabilities and no notifications, IRQ bindings, regions or
+//! derivation links.
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::core::KernelCore;
+use crate::ipc::{Endpoint, Notification};
+use crate::types::{
+    EndpointId, IrqBinding, IrqTarget, NotificationId, ObjectType, Process, ProcessId,
+    ProcessMetrics, ProcessState, RegionId, RegionMapping, ResourceQuota, SharedRegion,
+};
+use crate::{Capability, CapabilitySpace, DerivationTree, Permissions};
+use zos_axiom::{ReplayError, ReplayResult};
+use zos_hal::HAL;
+
+/// Current snapshot format version
+const SNAPSHOT_VERSION: u8 = 2;
+
+/// Oldest snapshot format version that can still be restored
+const MIN_SNAPSHOT_VERSION: u8 = 1;
+
+/// Serialize the replayable part of kernel state.
+pub(crate) fn encode<H: HAL>(kernel: &KernelCore<H>) -> Vec<u8> {
+    let mut w = Writer(Vec::new());
+    w.u8(SNAPSHOT_VERSION);
+    w.u64(kernel.next_pid);
+    w.u64(kernel.next_endpoint_id);
+    w.u64(kernel.next_cap_id);
+
+    w.u32(kernel.processes.len() as u32);
+    for (pid, proc) in &kernel.processes {
+        w.u64(pid.0);
+        w.str(&proc.name);
+        w.u8(process_state_to_u8(proc.state));
+        w.bytes(&proc.quota.to_bytes());
+        w.u64(proc.parent.0);
+    }
+
+    w.u32(kernel.cap_spaces.len() as u32);
+    for (pid, cspace) in &kernel.cap_spaces {
+        w.u64(pid.0);
+        w.u32(cspace.next_slot);
+        w.u32(cspace.slots.len() as u32);
+        for (slot, cap) in &cspace.slots {
+            w.u32(*slot);
+            w.u64(cap.id);
+            w.u8(cap.object_type as u8);
+            w.u64(cap.object_id);
+            w.u8(cap.permissions.to_byte());
+            w.u32(cap.generation);
+            w.u64(cap.expires_at);
+            w.u64(cap.badge);
+        }
+    }
+
+    w.u32(kernel.endpoints.len() as u32);
+    for (id, ep) in &kernel.endpoints {
+        w.u64(id.0);
+        w.u64(ep.owner.0);
+    }
+
+    w.u64(kernel.next_notification_id);
+    w.u32(kernel.notifications.len() as u32);
+    for (id, notification) in &kernel.notifications {
+        w.u64(id.0);
+        w.u64(notification.owner.0);
+        w.u64(notification.bits);
+    }
+
+    w.u32(kernel.irq_bindings.len() as u32);
+    for (irq, binding) in &kernel.irq_bindings {
+        let (target_type, target_id, bits) = binding.target.to_parts();
+        w.u8(*irq);
+        w.u64(binding.owner.0);
+        w.u8(target_type);
+        w.u64(target_id);
+        w.u64(bits);
+        w.u8(binding.masked as u8);
+    }
+
+    w.u64(kernel.next_region_id);
+    w.u32(kernel.regions.len() as u32);
+    for (id, region) in &kernel.regions {
+        w.u64(id.0);
+        w.u64(region.owner.0);
+        w.u64(region.size as u64);
+        w.u32(region.mappings.len() as u32);
+        for (pid, mapping) in &region.mappings {
+            w.u64(pid.0);
+            w.u8(mapping.writable as u8);
+            w.u32(mapping.window);
+        }
+    }
+
+    w.u32(kernel.cap_derivations.len() as u32);
+    for (child, parent) in kernel.cap_derivations.links() {
+        w.u64(child);
+        w.u64(parent);
+    }
+
+    w.0
+}
+
+/// Replace kernel state with a decoded snapshot.
+///
+/// The kernel is only modified if the whole snapshot decodes successfully.
+pub(crate) fn restore<H: HAL>(kernel: &mut KernelCore<H>, bytes: &[u8]) -> ReplayResult<()> {
+    let mut r = Reader { bytes, pos: 0 };
+
+    let version = r.u8()?;
+    if !(MIN_SNAPSHOT_VERSION..=SNAPSHOT_VERSION).contains(&version) {
+        return Err(ReplayError::InvalidCommit(format!(
+            "unsupported snapshot version {}",
+            version
+        )));
+    }
+    let next_pid = r.u64()?;
+    let next_endpoint_id = r.u64()?;
+    let next_cap_id = r.u64()?;
+
+    let mut processes = BTreeMap::new();
+    for _ in 0..r.u32()? {
+        let pid = ProcessId(r.u64()?);
+        let name = r.str()?;
+        let state = process_state_from_u8(r.u8()?)?;
+        let (quota, parent) = if version >= 2 {
+            let quota = ResourceQuota::from_bytes(r.take(ResourceQuota::ENCODED_SIZE)?)
+                .ok_or_else(|| ReplayError::InvalidCommit(String::from("invalid quota")))?;
+            (quota, ProcessId(r.u64()?))
+        } else {
+            (ResourceQuota::default(), ProcessId(0))
+        };
+        processes.insert(
+            pid,
+            Process {
+                pid,
+                parent,
+                name,
+                state,
+                quota,
+                metrics: ProcessMetrics::default(),
+            },
+        );
+    }
+
+    let mut cap_spaces = BTreeMap::new();
+    for _ in 0..r.u32()? {
+        let pid = ProcessId(r.u64()?);
+        let mut cspace = CapabilitySpace::new();
+        cspace.next_slot = r.u32()?;
+        for _ in 0..r.u32()? {
+            let slot = r.u32()?;
+            let id = r.u64()?;
+            let object_type = r.u8()?;
+            let cap = Capability {
+                id,
+                object_type: ObjectType::from_u8(object_type)
+                    .ok_or(ReplayError::UnknownObjectType(object_type))?,
+                object_id: r.u64()?,
+                permissions: Permissions::from_byte(r.u8()?),
+                generation: r.u32()?,
+                expires_at: r.u64()?,
+                badge: if version >= 2 { r.u64()? } else { 0 },
+            };
+            cspace.slots.insert(slot, cap);
+        }
+        cap_spaces.insert(pid, cspace);
+    }
+
+    let mut endpoints = BTreeMap::new();
+    for _ in 0..r.u32()? {
+        let id = EndpointId(r.u64()?);
+        let owner = ProcessId(r.u64()?);
+        endpoints.insert(id, Endpoint::new(id, owner));
+    }
+
+    let mut next_notification_id = 1;
+    let mut notifications = BTreeMap::new();
+    let mut irq_bindings = BTreeMap::new();
+    let mut next_region_id = 1;
+    let mut regions = BTreeMap::new();
+    let mut cap_derivations = DerivationTree::new();
+    if version >= 2 {
+        next_notification_id = r.u64()?;
+        for _ in 0..r.u32()? {
+            let id = NotificationId(r.u64()?);
+            let mut notification = Notification::new(id, ProcessId(r.u64()?));
+            notification.bits = r.u64()?;
+            notifications.insert(id, notification);
+        }
+
+        for _ in 0..r.u32()? {
+            let irq = r.u8()?;
+            let owner = ProcessId(r.u64()?);
+            let target_type = r.u8()?;
+            let target = IrqTarget::from_parts(target_type, r.u64()?, r.u64()?)
+                .ok_or(ReplayError::UnknownObjectType(target_type))?;
+            let masked = r.u8()? != 0;
+            irq_bindings.insert(
+                irq,
+                IrqBinding {
+                    owner,
+                    target,
+                    masked,
+                },
+            );
+        }
+
+        next_region_id = r.u64()?;
+        for _ in 0..r.u32()? {
+            let id = RegionId(r.u64()?);
+            let owner = ProcessId(r.u64()?);
+            let mut region = SharedRegion::new(id, owner, r.u64()? as usize);
+            for _ in 0..r.u32()? {
+                let pid = ProcessId(r.u64()?);
+                let writable = r.u8()? != 0;
+                let window = r.u32()?;
+                region
+                    .mappings
+                    .insert(pid, RegionMapping { writable, window });
+            }
+            regions.insert(id, region);
+        }
+
+        for _ in 0..r.u32()? {
+            let child = r.u64()?;
+            cap_derivations.link(r.u64()?, child);
+        }
+    }
+
+    if r.pos != bytes.len() {
+        return Err(ReplayError::InvalidCommit(String::from(
+            "trailing bytes in snapshot",
+        )));
+    }
+
+    kernel.processes = processes;
+    kernel.cap_spaces = cap_spaces;
+    kernel.cap_derivations = cap_derivations;
+    kernel.endpoints = endpoints;
+    kernel.notifications = notifications;
+    kernel.irq_bindings = irq_bindings;
+    kernel.regions = regions;
+    kernel.next_pid = next_pid;
+    kernel.next_endpoint_id = next_endpoint_id;
+    kernel.next_notification_id = next_notification_id;
+    kernel.next_region_id = next_region_id;
+    kernel.next_cap_id = next_cap_id;
+    Ok(())
+}
+
+/// Convert ProcessState to its stable byte encoding
+pub(crate) fn process_state_to_u8(state: ProcessState) -> u8 {
+    match state {
+        ProcessState::Running => 0,
+        ProcessState::Blocked => 1,
+        ProcessState::Zombie => 2,
+    }
+}
+
+fn process_state_from_u8(v: u8) -> ReplayResult<ProcessState> {
+    match v {
+        0 => Ok(ProcessState::Running),
+        1 => Ok(ProcessState::Blocked),
+        2 => Ok(ProcessState::Zombie),
+        _ => Err(ReplayError::InvalidCommit(format!(
+            "invalid process state {}",
+            v
+        ))),
+    }
+}
+
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn u8(&mut self, v: u8) {
+        self.0.push(v);
+    }
+
+    fn u32(&mut self, v: u32) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn u64(&mut self, v: u64) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn bytes(&mut self, b: &[u8]) {
+        self.0.extend_from_slice(b);
+    }
+
+    fn str(&mut self, s: &str) {
+        self.u32(s.len() as u32);
+        self.bytes(s.as_bytes());
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, len: usize) -> ReplayResult<&[u8]> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| ReplayError::InvalidCommit(String::from("truncated snapshot")))?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> ReplayResult<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> ReplayResult<u32> {
+        let mut buf = [0u8; 4];
+        buf.copy_from_slice(self.take(4)?);
+        Ok(u32::from_le_bytes(buf))
+    }
+
+    fn u64(&mut self) -> ReplayResult<u64> {
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    fn str(&mut self) -> ReplayResult<String> {
+        let len = self.u32()? as usize;
+        let bytes = self.take(len)?;
+        String::from_utf8(bytes.to_vec())
+            .map_err(|_| ReplayError::InvalidCommit(String::from("invalid UTF-8 in snapshot")))
+    }
+}
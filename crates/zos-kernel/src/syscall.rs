@@ -72,6 +72,19 @@ pub enum Syscall {
         slot: CapSlot,
         new_permissions: Permissions,
     },
+    /// Grant capability that expires `ttl_ns` after the grant (SYS_CAP_GRANT_TTL 0x36)
+    CapGrantWithTtl {
+        from_slot: CapSlot,
+        to_pid: ProcessId,
+        permissions: Permissions,
+        ttl_ns: u64,
+    },
+    /// Derive capability that expires `ttl_ns` after the derive (SYS_CAP_DERIVE_TTL 0x37)
+    CapDeriveWithTtl {
+        slot: CapSlot,
+        new_permissions: Permissions,
+        ttl_ns: u64,
+    },
 
     // === Enhanced IPC syscalls ===
     /// Send with capability transfer (SYS_SEND_CAP 0x44)
@@ -82,13 +95,34 @@ pub enum Syscall {
         cap_slots: Vec<CapSlot>,
     },
     /// Call (send + wait for reply) (SYS_CALL 0x42)
+    ///
+    /// Mints a one-shot reply capability for the endpoint owner and blocks.
+    /// Retrying the call after `WouldBlock` collects the reply.
     Call {
         endpoint_slot: CapSlot,
         tag: u32,
         data: Vec<u8>,
     },
+    /// Answer a caller's outstanding call, consuming the reply capability
+    /// (SYS_REPLY 0x43)
+    Reply {
+        caller: ProcessId,
+        tag: u32,
+        data: Vec<u8>,
+    },
     /// Kill a process (SYS_KILL 0x13 - requires Process capability)
     Kill { target_pid: ProcessId },
+
+    // === Notification syscalls ===
+    /// Create a notification (SYS_NOTIFY_CREATE 0x47)
+    CreateNotification,
+    /// OR `bits` into a notification's word (SYS_NOTIFY_SIGNAL 0x48)
+    Signal { notification_slot: CapSlot, bits: u64 },
+    /// Take the word, or `WouldBlock` if no bits are pending
+    /// (SYS_NOTIFY_WAIT 0x49)
+    Wait { notification_slot: CapSlot },
+    /// Take the word without blocking; may be 0 (SYS_NOTIFY_POLL 0x4A)
+    Poll { notification_slot: CapSlot },
 }
 
 /// Information about a capability (returned by CapInspect)
@@ -152,6 +186,19 @@ impl RevokeNotification {
     pub fn is_valid(&self) -> bool {
         self.object_type != 0
     }
+
+    /// Encode as a `MSG_CAP_REVOKED` payload.
+    ///
+    /// Layout: `[slot: u32, object_type: u8, object_id: u64, reason: u8]`,
+    /// little-endian.
+    pub fn to_payload(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(14);
+        data.extend_from_slice(&self.slot.to_le_bytes());
+        data.push(self.object_type);
+        data.extend_from_slice(&self.object_id.to_le_bytes());
+        data.push(self.reason);
+        data
+    }
 }
 
 /// Syscall result
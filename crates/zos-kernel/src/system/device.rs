@@ -0,0 +1,162 @@
+//! Device syscall handlers
+//!
+//! This module contains syscall handlers for user-space drivers:
+//! - `execute_device_cap_create()` - Mint IRQ and I/O port capabilities
+//!   (Init-only)
+//! - `execute_irq_bind()` - Bind an IRQ line to a notification or endpoint
+//! - `execute_io_in()` / `execute_io_out()` - Checked I/O port access
+//!
+//! Acknowledging and unbinding a line go straight to `KernelCore`.
+
+use alloc::vec::Vec;
+
+use crate::core::KernelCore;
+use crate::error::KernelError;
+use crate::types::{CapSlot, IoPortRange, ProcessId};
+use zos_axiom::{Commit, CommitType};
+use zos_hal::{HalError, HAL};
+use zos_ipc::{pid::INIT, syscall_error};
+
+/// Execute the device syscalls (0xA0 - 0xA6).
+pub(in crate::system) fn execute_device_syscall<H: HAL>(
+    core: &mut KernelCore<H>,
+    syscall_num: u32,
+    sender: ProcessId,
+    args: [u32; 4],
+    data: &[u8],
+    timestamp: u64,
+) -> (i64, Vec<CommitType>) {
+    match syscall_num {
+        0xA0 | 0xA1 => execute_device_cap_create(core, syscall_num, sender, args, timestamp),
+        0xA2 => execute_irq_bind(core, sender, args, data, timestamp),
+        0xA3 => {
+            let (result, commits) = core.ack_irq(sender, args[0], timestamp);
+            device_result(result.map(|()| 0), commits)
+        }
+        0xA4 => {
+            let (result, commits) = core.unbind_irq(sender, args[0], timestamp);
+            device_result(result.map(|()| 0), commits)
+        }
+        0xA5 => execute_io_in(core, sender, args, timestamp),
+        0xA6 => execute_io_out(core, sender, args, timestamp),
+        _ => (-1, Vec::new()),
+    }
+}
+
+/// Execute SYS_IRQ_CAP_CREATE (0xA0) or SYS_IO_PORT_CAP_CREATE (0xA1).
+///
+/// Only init (PID 1) can mint device capabilities; it grants them to
+/// drivers once the permission service approves.
+///
+/// # Arguments
+/// - `args[0]`: IRQ line, or first port
+/// - `args[1]`: Number of ports (0xA1 only)
+///
+/// # Returns
+/// - On success: `(slot, commits)` with the slot in Init's CSpace
+/// - On error: `(error_code as i64, commits)`
+fn execute_device_cap_create<H: HAL>(
+    core: &mut KernelCore<H>,
+    syscall_num: u32,
+    sender: ProcessId,
+    args: [u32; 4],
+    timestamp: u64,
+) -> (i64, Vec<CommitType>) {
+    if sender.0 != INIT as u64 {
+        return (syscall_error::PERMISSION_DENIED as i64, Vec::new());
+    }
+    let (result, commits) = if syscall_num == 0xA0 {
+        if args[0] > u8::MAX as u32 {
+            return (syscall_error::INVALID_ARGUMENT as i64, Vec::new());
+        }
+        core.mint_irq_cap(sender, args[0] as u8, timestamp)
+    } else {
+        if args[0] > u16::MAX as u32 || args[1] > u16::MAX as u32 {
+            return (syscall_error::INVALID_ARGUMENT as i64, Vec::new());
+        }
+        let range = IoPortRange {
+            base: args[0] as u16,
+            len: args[1] as u16,
+        };
+        core.mint_io_port_cap(sender, range, timestamp)
+    };
+    device_result(result.map(|slot: CapSlot| slot as i64), commits)
+}
+
+/// Execute SYS_IRQ_BIND (0xA2).
+///
+/// An 8-byte payload holds the bits to OR into a notification target;
+/// without a payload the target is an endpoint that receives `MSG_IRQ`.
+fn execute_irq_bind<H: HAL>(
+    core: &mut KernelCore<H>,
+    sender: ProcessId,
+    args: [u32; 4],
+    data: &[u8],
+    timestamp: u64,
+) -> (i64, Vec<CommitType>) {
+    let (irq_slot, target_slot) = (args[0], args[1]);
+    let (result, commits) = match data.len() {
+        0 => core.bind_irq_endpoint(sender, irq_slot, target_slot, timestamp),
+        8 => {
+            let mut bits = [0u8; 8];
+            bits.copy_from_slice(data);
+            let bits = u64::from_le_bytes(bits);
+            core.bind_irq_notification(sender, irq_slot, target_slot, bits, timestamp)
+        }
+        _ => return (syscall_error::INVALID_ARGUMENT as i64, Vec::new()),
+    };
+    device_result(result.map(|()| 0), commits)
+}
+
+/// Execute SYS_IO_IN (0xA5): args are [port slot, port, width].
+///
+/// Returns the value read, or a negative error code.
+fn execute_io_in<H: HAL>(
+    core: &KernelCore<H>,
+    sender: ProcessId,
+    args: [u32; 4],
+    timestamp: u64,
+) -> (i64, Vec<CommitType>) {
+    if args[1] > u16::MAX as u32 || args[2] > u8::MAX as u32 {
+        return (syscall_error::INVALID_ARGUMENT as i64, Vec::new());
+    }
+    let result = core.io_port_read(sender, args[0], args[1] as u16, args[2] as u8, timestamp);
+    device_result(result.map(|value| value as i64), Vec::new())
+}
+
+/// Execute SYS_IO_OUT (0xA6): args are [port slot, (width << 16) | port,
+/// value].
+fn execute_io_out<H: HAL>(
+    core: &KernelCore<H>,
+    sender: ProcessId,
+    args: [u32; 4],
+    timestamp: u64,
+) -> (i64, Vec<CommitType>) {
+    if args[1] >> 16 > u8::MAX as u32 {
+        return (syscall_error::INVALID_ARGUMENT as i64, Vec::new());
+    }
+    let (port, width) = (args[1] as u16, (args[1] >> 16) as u8);
+    let result = core.io_port_write(sender, args[0], port, width, args[2], timestamp);
+    device_result(result.map(|()| 0), Vec::new())
+}
+
+/// Map a device operation's result to a syscall result code.
+///
+/// Refusals still carry their commits (e.g. QuotaExceeded).
+fn device_result(
+    result: Result<i64, KernelError>,
+    commits: Vec<Commit>,
+) -> (i64, Vec<CommitType>) {
+    let commit_types = commits.into_iter().map(|c| c.commit_type).collect();
+    let code = match result {
+        Ok(value) => return (value, commit_types),
+        Err(KernelError::InvalidArgument | KernelError::Hal(HalError::InvalidArgument)) => {
+            syscall_error::INVALID_ARGUMENT
+        }
+        Err(KernelError::PermissionDenied) => syscall_error::PERMISSION_DENIED,
+        Err(KernelError::ResourceExhausted) => syscall_error::RESOURCE_EXHAUSTED,
+        Err(KernelError::Hal(HalError::NotSupported)) => syscall_error::NOT_SUPPORTED,
+        Err(_) => -1,
+    };
+    (code as i64, commit_types)
+}
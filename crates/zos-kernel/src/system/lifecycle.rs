@@ -7,24 +7,31 @@
 //! - `execute_create_endpoint_for()` - Handle endpoint creation for another process
 //! - `execute_load_binary()` - Handle binary loading (Init-only)
 //! - `execute_spawn_process()` - Handle process spawning (Init-only)
+//! - `execute_shutdown()` - Handle power-off/reboot requests (Init-only)
+//! - `execute_set_quota()` - Handle resource quota changes (Init-only)
+//! - `execute_wait_child()` - Handle reaping a dead child
+//! - `execute_kill_tree()` - Handle killing a process and its descendants
 
 use alloc::vec::Vec;
 
 use crate::core::KernelCore;
-use crate::types::ProcessId;
+use crate::error::KernelError;
+use crate::types::{ProcessId, QuotaResource, ResourceQuota};
 use zos_axiom::CommitType;
-use zos_hal::{HalError, HAL};
-use zos_ipc::{pid::INIT, syscall_error};
+use zos_hal::{HalError, Priority, HAL};
+use zos_ipc::{pid::INIT, priority, quota, syscall_error};
 
 /// Execute process exit syscall (0x11).
 ///
-/// Terminates the calling process and returns its commits.
+/// Terminates the calling process with exit code `args[0]` and returns its
+/// commits.
 pub(in crate::system) fn execute_exit<H: HAL>(
     core: &mut KernelCore<H>,
     sender: ProcessId,
+    args: [u32; 4],
     timestamp: u64,
 ) -> (i64, Vec<CommitType>) {
-    let commits = core.kill_process(sender, timestamp);
+    let commits = core.exit_process(sender, args[0] as i32, timestamp);
     let commit_types: Vec<CommitType> = commits.into_iter().map(|c| c.commit_type).collect();
     (0, commit_types)
 }
@@ -53,7 +60,7 @@ pub(in crate::system) fn execute_kill_with_cap<H: HAL>(
 
 /// Execute register process syscall (0x14).
 ///
-/// Creates a new process. Only init (PID 1) can call this.
+/// Creates a new process as a child of init. Only init (PID 1) can call this.
 /// Returns the new process ID or -1 on error.
 pub(in crate::system) fn execute_register_process<H: HAL>(
     core: &mut KernelCore<H>,
@@ -67,7 +74,7 @@ pub(in crate::system) fn execute_register_process<H: HAL>(
     }
 
     let name = core::str::from_utf8(data).unwrap_or("unknown");
-    let (pid, commits) = core.register_process(name, timestamp);
+    let (pid, commits) = core.register_process_with_parent(name, sender, timestamp);
     let commit_types = commits.into_iter().map(|c| c.commit_type).collect();
 
     (pid.0 as i64, commit_types)
@@ -102,6 +109,13 @@ pub(in crate::system) fn execute_create_endpoint_for<H: HAL>(
         Ok((eid, _owner_slot)) => {
             // Also grant a capability to Init so it can send to this endpoint
             let init_pid = ProcessId(1);
+            if let Some(commit) =
+                core.quota_violation(init_pid, QuotaResource::CapSlots, 1, timestamp)
+            {
+                let mut commit_types = commit_types;
+                commit_types.push(commit.commit_type);
+                return (-1, commit_types);
+            }
             let cap = Capability {
                 id: core.next_cap_id(),
                 object_type: ObjectType::Endpoint,
@@ -109,6 +123,7 @@ pub(in crate::system) fn execute_create_endpoint_for<H: HAL>(
                 permissions: Permissions::full(),
                 generation: 0,
                 expires_at: 0, // Never expires
+                badge: 0,
             };
             
             // Insert into Init's capability space
@@ -169,7 +184,9 @@ pub(in crate::system) fn execute_load_binary<H: HAL>(
 ///
 /// # Arguments
 /// - `sender`: Requesting process (must be Init)
-/// - `data`: [name_len: u32 (LE), name: [u8], binary: [u8]]
+/// - `args[1]`: Scheduling class (`zos_ipc::priority`)
+/// - `args[2]`: `quota::SPAWN_WITH_QUOTA` if a quota record follows the name
+/// - `data`: [name_len: u32 (LE), name: [u8], quota?: [u8; 40], binary: [u8]]
 /// - `timestamp`: For commit log
 ///
 /// # Returns
@@ -178,6 +195,7 @@ pub(in crate::system) fn execute_load_binary<H: HAL>(
 pub(in crate::system) fn execute_spawn_process<H: HAL>(
     core: &mut KernelCore<H>,
     sender: ProcessId,
+    args: [u32; 4],
     data: &[u8],
     timestamp: u64,
 ) -> (i64, Vec<CommitType>) {
@@ -186,6 +204,13 @@ pub(in crate::system) fn execute_spawn_process<H: HAL>(
         return (syscall_error::PERMISSION_DENIED as i64, Vec::new());
     }
 
+    let priority = match args[1] {
+        priority::BACKGROUND => Priority::Background,
+        priority::INTERACTIVE => Priority::Interactive,
+        priority::SYSTEM => Priority::System,
+        _ => return (syscall_error::INVALID_ARGUMENT as i64, Vec::new()),
+    };
+
     // Parse: [name_len: u32 (LE), name: [u8], binary: [u8]]
     if data.len() < 4 {
         return (syscall_error::INVALID_ARGUMENT as i64, Vec::new());
@@ -198,18 +223,46 @@ pub(in crate::system) fn execute_spawn_process<H: HAL>(
         Ok(n) => n,
         Err(_) => return (syscall_error::INVALID_UTF8 as i64, Vec::new()),
     };
-    let binary = &data[4 + name_len..];
+    let mut binary = &data[4 + name_len..];
+
+    let spawn_quota = match args[2] {
+        0 => None,
+        quota::SPAWN_WITH_QUOTA => {
+            if binary.len() < quota::RECORD_SIZE {
+                return (syscall_error::INVALID_ARGUMENT as i64, Vec::new());
+            }
+            let (record, rest) = binary.split_at(quota::RECORD_SIZE);
+            binary = rest;
+            ResourceQuota::from_bytes(record)
+        }
+        _ => return (syscall_error::INVALID_ARGUMENT as i64, Vec::new()),
+    };
 
     if binary.is_empty() {
         return (syscall_error::INVALID_ARGUMENT as i64, Vec::new());
     }
 
+    // A child may not be given more than its spawner has
+    let init_quota = core
+        .get_process(sender)
+        .map(|p| p.quota)
+        .unwrap_or_default();
+    if spawn_quota.is_some_and(|q| !q.is_within(&init_quota)) {
+        return (syscall_error::PERMISSION_DENIED as i64, Vec::new());
+    }
+
     // Register process in kernel first (this allocates PID and creates CSpace)
-    let (pid, commits) = core.register_process(name, timestamp);
-    let commit_types: Vec<CommitType> = commits.into_iter().map(|c| c.commit_type).collect();
+    let (pid, commits) = core.register_process_with_parent(name, sender, timestamp);
+    let mut commit_types: Vec<CommitType> = commits.into_iter().map(|c| c.commit_type).collect();
+
+    // Apply the quota before the process can run
+    if let Some(spawn_quota) = spawn_quota {
+        let (_, commits) = core.set_quota(sender, pid, spawn_quota, timestamp);
+        commit_types.extend(commits.into_iter().map(|c| c.commit_type));
+    }
 
     // Spawn via HAL with the kernel-allocated PID (this starts the WASM runtime)
-    match core.hal().spawn_process_with_pid(pid.0, name, binary) {
+    match core.hal().spawn_process_with_priority(pid.0, name, binary, priority) {
         Ok(_handle) => (pid.0 as i64, commit_types),
         Err(_) => {
             // Process was registered but spawn failed - kernel state is inconsistent
@@ -219,3 +272,123 @@ pub(in crate::system) fn execute_spawn_process<H: HAL>(
         }
     }
 }
+
+/// Execute shutdown syscall (0x18).
+///
+/// Records a power-off or reboot request. Only init (PID 1) can call this.
+/// The boot loop picks the request up via `System::take_shutdown_request`,
+/// asks Init to stop services and then cuts power.
+///
+/// # Arguments
+/// - `sender`: Requesting process (must be Init)
+/// - `args[0]`: 1 to reboot, 0 to power off
+///
+/// # Returns
+/// - `0` if the request was accepted
+/// - `PERMISSION_DENIED` if the caller is not Init
+/// - `NOT_SUPPORTED` if the platform has no power control
+pub(in crate::system) fn execute_shutdown<H: HAL>(
+    core: &mut KernelCore<H>,
+    sender: ProcessId,
+    args: [u32; 4],
+) -> i64 {
+    if sender.0 != INIT as u64 {
+        return syscall_error::PERMISSION_DENIED as i64;
+    }
+    if !core.hal().supports_power_control() {
+        return syscall_error::NOT_SUPPORTED as i64;
+    }
+
+    core.shutdown_request = Some(args[0] != 0);
+    0
+}
+
+/// Execute set quota syscall (0x19).
+///
+/// Replaces the resource quota of a process. Only init (PID 1) can call
+/// this, and the new quota may not exceed Init's own.
+///
+/// # Arguments
+/// - `sender`: Requesting process (must be Init)
+/// - `args[0]`: Target PID
+/// - `data`: Quota record (`zos_ipc::quota`)
+/// - `timestamp`: For commit log
+///
+/// # Returns
+/// - On success: `(0, commits)`
+/// - On error: `(error_code as i64, Vec::new())`
+pub(in crate::system) fn execute_set_quota<H: HAL>(
+    core: &mut KernelCore<H>,
+    sender: ProcessId,
+    args: [u32; 4],
+    data: &[u8],
+    timestamp: u64,
+) -> (i64, Vec<CommitType>) {
+    if sender.0 != INIT as u64 {
+        return (syscall_error::PERMISSION_DENIED as i64, Vec::new());
+    }
+    let new_quota = match ResourceQuota::from_bytes(data) {
+        Some(q) if data.len() == quota::RECORD_SIZE => q,
+        _ => return (syscall_error::INVALID_ARGUMENT as i64, Vec::new()),
+    };
+
+    let target = ProcessId(args[0] as u64);
+    match core.set_quota(sender, target, new_quota, timestamp) {
+        (Ok(()), commits) => (0, commits.into_iter().map(|c| c.commit_type).collect()),
+        (Err(KernelError::ProcessNotFound), _) => (syscall_error::NOT_FOUND as i64, Vec::new()),
+        (Err(_), _) => (syscall_error::PERMISSION_DENIED as i64, Vec::new()),
+    }
+}
+
+/// Execute wait child syscall (0x1A).
+///
+/// Reaps a dead child of the caller without blocking.
+///
+/// # Arguments
+/// - `sender`: The parent
+/// - `args[0]`: Child PID, or 0 for any child
+///
+/// # Returns
+/// - On success: `(child_pid as i64, [pid: u64, kind: u8, value: u64])`
+/// - `WOULD_BLOCK` if every matching child is alive, `NOT_FOUND` if there
+///   is no matching child
+pub(in crate::system) fn execute_wait_child<H: HAL>(
+    core: &mut KernelCore<H>,
+    sender: ProcessId,
+    args: [u32; 4],
+) -> (i64, Vec<u8>) {
+    let target = match args[0] {
+        0 => None,
+        pid => Some(ProcessId(pid as u64)),
+    };
+
+    match core.wait_child(sender, target) {
+        Ok((pid, status)) => (pid.0 as i64, status.to_payload(pid)),
+        Err(KernelError::WouldBlock) => (syscall_error::WOULD_BLOCK as i64, Vec::new()),
+        Err(_) => (syscall_error::NOT_FOUND as i64, Vec::new()),
+    }
+}
+
+/// Execute kill tree syscall (0x1B).
+///
+/// Kills `args[0]` and all of its descendants. The caller must be the
+/// target, one of its ancestors, or Init.
+///
+/// # Returns
+/// - On success: `(killed_count as i64, commits)`
+/// - `NOT_FOUND` or `PERMISSION_DENIED` on error
+pub(in crate::system) fn execute_kill_tree<H: HAL>(
+    core: &mut KernelCore<H>,
+    sender: ProcessId,
+    args: [u32; 4],
+    timestamp: u64,
+) -> (i64, Vec<CommitType>) {
+    let target_pid = ProcessId(args[0] as u64);
+    let (result, commits) = core.kill_tree(sender, target_pid, timestamp);
+    let commit_types = commits.into_iter().map(|c| c.commit_type).collect();
+    match result {
+        Ok(killed) => (killed as i64, commit_types),
+        Err(KernelError::PermissionDenied) => (syscall_error::PERMISSION_DENIED as i64, commit_types),
+        Err(_) => (syscall_error::NOT_FOUND as i64, commit_types),
+    }
+}
@@ -24,7 +24,7 @@ pub(in crate::system) fn get_syscall_rich_result<H: HAL>(
     kernel: &mut KernelCore<H>,
     sender: ProcessId,
     syscall_num: u32,
-    args: [u32; 4],
+    _args: [u32; 4],
     _data: &[u8],
     result: i64,
     timestamp: u64,
@@ -32,7 +32,7 @@ pub(in crate::system) fn get_syscall_rich_result<H: HAL>(
     match syscall_num {
         0x35 => format_caps_list(kernel, sender, result, timestamp), // SYS_CAP_LIST
         0x50 => format_process_list(kernel, sender, result, timestamp), // SYS_PS
-        0x41 => format_receive_result(kernel, sender, args, result, timestamp),
+        0x41 => format_receive_result(result),
         _ => default_rich_result(result),
     }
 }
@@ -78,6 +78,7 @@ pub(in crate::system) fn format_caps_list<H: HAL>(
 ///   - u32: process ID
 ///   - u16: name length
 ///   - bytes: process name (UTF-8)
+///   - [u8; 40]: resource quota record (`zos_ipc::quota`)
 pub(in crate::system) fn format_process_list<H: HAL>(
     kernel: &mut KernelCore<H>,
     sender: ProcessId,
@@ -95,6 +96,11 @@ pub(in crate::system) fn format_process_list<H: HAL>(
             bytes.extend_from_slice(&(proc_pid.0 as u32).to_le_bytes());
             bytes.extend_from_slice(&(name.len() as u16).to_le_bytes());
             bytes.extend_from_slice(name.as_bytes());
+            let quota = kernel
+                .get_process(*proc_pid)
+                .map(|p| p.quota)
+                .unwrap_or_default();
+            bytes.extend_from_slice(&quota.to_bytes());
         }
 
         (rich_result, bytes, Vec::new())
@@ -105,36 +111,13 @@ pub(in crate::system) fn format_process_list<H: HAL>(
 
 /// Format IPC receive result for syscall 0x41 (IPC_RECEIVE).
 ///
-/// Returns (SyscallResult, response_bytes, commits) where commits contains
-/// the state changes from actually receiving the message (capability transfers, etc.).
-pub(in crate::system) fn format_receive_result<H: HAL>(
-    kernel: &mut KernelCore<H>,
-    sender: ProcessId,
-    args: [u32; 4],
+/// The message itself was dequeued and encoded by the syscall handler; this
+/// only maps the result code.
+pub(in crate::system) fn format_receive_result(
     result: i64,
-    timestamp: u64,
 ) -> (SyscallResult, Vec<u8>, Vec<CommitType>) {
     if result == 1 {
-        let slot = args[0];
-        let (recv_result, commits) = kernel.ipc_receive_with_caps(sender, slot, timestamp);
-        
-        // Convert commits to CommitTypes so they can be recorded
-        let commit_types: Vec<CommitType> = commits.into_iter().map(|c| c.commit_type).collect();
-
-        match recv_result {
-            Ok(Some((msg, installed_slots))) => {
-                let mut msg_bytes = Vec::new();
-                msg_bytes.extend_from_slice(&(msg.from.0 as u32).to_le_bytes());
-                msg_bytes.extend_from_slice(&msg.tag.to_le_bytes());
-                msg_bytes.push(installed_slots.len() as u8);
-                for cap_slot in &installed_slots {
-                    msg_bytes.extend_from_slice(&cap_slot.to_le_bytes());
-                }
-                msg_bytes.extend_from_slice(&msg.data);
-                (SyscallResult::Message(msg), msg_bytes, commit_types)
-            }
-            _ => (SyscallResult::Ok(result as u64), Vec::new(), commit_types),
-        }
+        (SyscallResult::Ok(1), Vec::new(), Vec::new())
     } else if result == 0 {
         (SyscallResult::WouldBlock, Vec::new(), Vec::new())
     } else {
@@ -35,20 +35,36 @@
 //!
 //! All syscalls flow: `Process → System.process_syscall() → Axiom (log) → KernelCore (execute) → Axiom (record) → Process`
 
+mod device;
 mod lifecycle;
 mod metrics;
+mod region;
+mod wait;
 
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
 use alloc::vec::Vec;
 
 use crate::capability::Permissions;
 use crate::core::KernelCore;
 use crate::error::KernelError;
-use crate::ipc::{Endpoint, EndpointDetail, EndpointInfo, Message};
+use crate::ipc::{
+    Endpoint, EndpointDetail, EndpointInfo, Message, Notification, OverflowPolicy,
+    DEFAULT_QUEUE_CAPACITY,
+};
 use crate::syscall::{RevokeNotification, Syscall, SyscallResult};
-use crate::types::{CapSlot, EndpointId, Process, ProcessId, SystemMetrics};
+use crate::types::{
+    CapSlot, EndpointId, NotificationId, Process, ProcessId, RegionId, ResourceQuota,
+    SharedRegion, SystemMetrics,
+};
 use crate::CapabilitySpace;
-use zos_axiom::{AxiomGateway, Commit, CommitLog, CommitType, SysLog};
+use zos_axiom::{
+    AxiomGateway, Checkpoint, CheckpointSigner, Commit, CommitId, CommitLog, CommitType,
+    Replayable, SysLog, SyscallTrace,
+};
 use zos_hal::HAL;
+use zos_ipc::syscall::{ENDPOINT_BLOCK_WHEN_FULL, SYS_EXIT};
+use zos_ipc::syscall_error;
 
 /// System combines the Axiom verification layer with the KernelCore execution layer.
 ///
@@ -64,6 +80,8 @@ pub struct System<H: HAL> {
     pub kernel: KernelCore<H>,
     /// Boot time (for uptime calculation)
     boot_time: u64,
+    /// Syscalls whose callers are parked in a timed wait
+    parked: BTreeMap<ProcessId, wait::ParkedSyscall>,
 }
 
 impl<H: HAL> System<H> {
@@ -74,6 +92,7 @@ impl<H: HAL> System<H> {
             axiom: AxiomGateway::new(boot_time),
             kernel: KernelCore::new(hal),
             boot_time,
+            parked: BTreeMap::new(),
         }
     }
 
@@ -92,6 +111,14 @@ impl<H: HAL> System<H> {
         self.boot_time
     }
 
+    /// Take a pending power-off/reboot request accepted from Init.
+    ///
+    /// Returns `Some(reboot)` once per request; the caller is expected to
+    /// stop services and then power off or reboot the machine.
+    pub fn take_shutdown_request(&mut self) -> Option<bool> {
+        self.kernel.shutdown_request.take()
+    }
+
     // ========================================================================
     // Main Syscall Entry Point - ALL syscalls flow through here
     // ========================================================================
@@ -124,25 +151,65 @@ impl<H: HAL> System<H> {
             .syslog_mut()
             .log_request(sender.0, syscall_num, args, timestamp);
 
-        // 2. Execute syscall via KernelCore
-        let (result, commit_types, kernel_response_data) =
-            execute_syscall_kernel_fn(&mut self.kernel, syscall_num, sender, args, data, timestamp);
+        // 2. Execute syscall via KernelCore, unless the caller has used up its
+        //    syscall rate quota (exiting is always allowed)
+        let refusal = if syscall_num == SYS_EXIT {
+            None
+        } else {
+            self.kernel.charge_syscall(sender, timestamp)
+        };
+        let refused = refusal.is_some();
+        let (result, commit_types, kernel_response_data) = match refusal {
+            None => execute_syscall_kernel_fn(
+                &mut self.kernel,
+                syscall_num,
+                sender,
+                args,
+                data,
+                timestamp,
+            ),
+            Some(commit) => (
+                syscall_error::RESOURCE_EXHAUSTED as i64,
+                alloc::vec![commit.commit_type],
+                Vec::new(),
+            ),
+        };
 
-        // 3. Record commits to CommitLog
+        // 3. Record commits to CommitLog, then reap capabilities that have
+        //    expired by now
         for ct in commit_types {
             self.axiom.append_internal_commit(ct, timestamp);
         }
+        self.expire_capabilities(timestamp);
+
+        // A parked syscall is answered later by `resume_parked`, which logs
+        // the response and records the trace then
+        if result == wait::PARKED {
+            let parked = wait::ParkedSyscall {
+                req_id,
+                syscall_num,
+                args,
+                data: data.to_vec(),
+            };
+            self.parked.insert(sender, parked);
+            self.snapshot_if_needed();
+            return (0, SyscallResult::WouldBlock, Vec::new());
+        }
 
         // 4. Get rich result and response data
-        let (rich_result, metrics_response_data, additional_commits) = metrics::get_syscall_rich_result(
-            &mut self.kernel,
-            sender,
-            syscall_num,
-            args,
-            data,
-            result,
-            timestamp,
-        );
+        let (rich_result, metrics_response_data, additional_commits) = if refused {
+            metrics::default_rich_result(result)
+        } else {
+            metrics::get_syscall_rich_result(
+                &mut self.kernel,
+                sender,
+                syscall_num,
+                args,
+                data,
+                result,
+                timestamp,
+            )
+        };
 
         // 5. Record additional commits from formatters (e.g., IPC receive)
         for ct in additional_commits {
@@ -154,6 +221,8 @@ impl<H: HAL> System<H> {
             .syslog_mut()
             .log_response(sender.0, req_id, result, timestamp);
 
+        self.snapshot_if_needed();
+
         // Use kernel response data if present, otherwise metrics response data
         let response_data = if !kernel_response_data.is_empty() {
             kernel_response_data
@@ -161,9 +230,96 @@ impl<H: HAL> System<H> {
             metrics_response_data
         };
 
+        // 7. Record the full syscall if a trace is being recorded
+        self.axiom.record_syscall(
+            sender.0,
+            syscall_num,
+            args,
+            data,
+            result,
+            &response_data,
+            timestamp,
+        );
+
         (result, rich_result, response_data)
     }
 
+    /// Check whether a process's last syscall parked it.
+    ///
+    /// The runtime must not answer a parked syscall; it completes it with
+    /// the result from `resume_parked` instead.
+    pub fn is_parked(&self, pid: ProcessId) -> bool {
+        self.parked.contains_key(&pid)
+    }
+
+    /// Earliest deadline (nanos since boot) of any parked syscall or
+    /// capability expiry.
+    ///
+    /// Both are handled by the next `resume_parked` at or after it.
+    pub fn next_deadline(&self) -> Option<u64> {
+        match (self.kernel.next_deadline(), self.kernel.next_cap_expiry()) {
+            (Some(wait), Some(expiry)) => Some(wait.min(expiry)),
+            (wait, expiry) => wait.or(expiry),
+        }
+    }
+
+    /// Answer the parked syscalls whose wait has ended.
+    ///
+    /// A wait ends when a message arrives on one of its endpoints or its
+    /// deadline passes. Returns (pid, result, response_data) for each
+    /// syscall to complete; the runtime delivers them like any other
+    /// syscall result. Callers that died while parked are dropped.
+    ///
+    /// Expired capabilities are reaped first, so a holder parked on its
+    /// endpoint is woken by the `MSG_CAP_REVOKED` notification.
+    pub fn resume_parked(&mut self) -> Vec<(ProcessId, i64, Vec<u8>)> {
+        let timestamp = self.uptime_nanos();
+        self.expire_capabilities(timestamp);
+        self.parked.retain(|pid, _| self.kernel.is_parked(*pid));
+
+        let mut completed = Vec::new();
+        for pid in self.kernel.parked_ready(timestamp) {
+            let Some((outcome, commits)) = self.kernel.resume_wait(pid, timestamp) else {
+                continue;
+            };
+            let Some(parked) = self.parked.remove(&pid) else {
+                continue;
+            };
+            let (result, response) = wait::wait_result(parked.syscall_num, outcome);
+            self.record_commits(commits, timestamp);
+            if result == wait::PARKED {
+                self.parked.insert(pid, parked);
+                continue;
+            }
+
+            self.axiom
+                .syslog_mut()
+                .log_response(pid.0, parked.req_id, result, timestamp);
+            self.axiom.record_syscall(
+                pid.0,
+                parked.syscall_num,
+                parked.args,
+                &parked.data,
+                result,
+                &response,
+                timestamp,
+            );
+            completed.push((pid, result, response));
+        }
+        completed
+    }
+
+    /// Deliver a hardware interrupt taken from the HAL to the driver that
+    /// bound the line, and log the mutation.
+    ///
+    /// A driver parked waiting on the target is answered by the next
+    /// `resume_parked`.
+    pub fn interrupt(&mut self, irq: u8) {
+        let timestamp = self.uptime_nanos();
+        let commits = self.kernel.interrupt(irq, timestamp);
+        self.record_commits(commits, timestamp);
+    }
+
     // ========================================================================
     // Process Management (routed through Axiom)
     // ========================================================================
@@ -176,6 +332,19 @@ impl<H: HAL> System<H> {
         pid
     }
 
+    /// Register a process spawned on behalf of `parent` and log the mutation.
+    ///
+    /// The parent is told when the process dies and reaps it with
+    /// `SYS_WAIT_CHILD`.
+    pub fn register_process_with_parent(&mut self, name: &str, parent: ProcessId) -> ProcessId {
+        let timestamp = self.uptime_nanos();
+        let (pid, commits) = self
+            .kernel
+            .register_process_with_parent(name, parent, timestamp);
+        self.record_commits(commits, timestamp);
+        pid
+    }
+
     /// Register a process with a specific PID (for supervisor and special processes).
     pub fn register_process_with_pid(&mut self, pid: ProcessId, name: &str) -> ProcessId {
         let timestamp = self.uptime_nanos();
@@ -230,6 +399,21 @@ impl<H: HAL> System<H> {
         result
     }
 
+    /// Create an endpoint whose queue holds at most `capacity` messages.
+    pub fn create_endpoint_with_capacity(
+        &mut self,
+        owner: ProcessId,
+        capacity: usize,
+        policy: OverflowPolicy,
+    ) -> Result<(EndpointId, CapSlot), KernelError> {
+        let timestamp = self.uptime_nanos();
+        let (result, commits) = self
+            .kernel
+            .create_endpoint_with_capacity(owner, capacity, policy, timestamp);
+        self.record_commits(commits, timestamp);
+        result
+    }
+
     /// List all endpoints.
     pub fn list_endpoints(&self) -> Vec<EndpointInfo> {
         self.kernel.list_endpoints()
@@ -245,6 +429,60 @@ impl<H: HAL> System<H> {
         self.kernel.get_endpoint_detail(id)
     }
 
+    // ========================================================================
+    // Notifications
+    // ========================================================================
+
+    /// Create a notification and log the mutation.
+    pub fn create_notification(
+        &mut self,
+        owner: ProcessId,
+    ) -> Result<(NotificationId, CapSlot), KernelError> {
+        let timestamp = self.uptime_nanos();
+        let (result, commits) = self.kernel.create_notification(owner, timestamp);
+        self.record_commits(commits, timestamp);
+        result
+    }
+
+    /// Signal a notification and log the mutation.
+    pub fn signal_notification(
+        &mut self,
+        pid: ProcessId,
+        slot: CapSlot,
+        bits: u64,
+    ) -> Result<(), KernelError> {
+        let timestamp = self.uptime_nanos();
+        let (result, commits) = self.kernel.signal_notification(pid, slot, bits, timestamp);
+        self.record_commits(commits, timestamp);
+        result
+    }
+
+    /// Get notification info.
+    pub fn get_notification(&self, id: NotificationId) -> Option<&Notification> {
+        self.kernel.get_notification(id)
+    }
+
+    // ========================================================================
+    // Shared Regions
+    // ========================================================================
+
+    /// Create a shared region and log the mutation.
+    pub fn create_region(
+        &mut self,
+        owner: ProcessId,
+        size: usize,
+    ) -> Result<(CapSlot, RegionId), KernelError> {
+        let timestamp = self.uptime_nanos();
+        let (result, commits) = self.kernel.create_region(owner, size, timestamp);
+        self.record_commits(commits, timestamp);
+        result
+    }
+
+    /// Get shared region info.
+    pub fn get_region(&self, id: RegionId) -> Option<&SharedRegion> {
+        self.kernel.get_region(id)
+    }
+
     // ========================================================================
     // Capability Management
     // ========================================================================
@@ -265,6 +503,23 @@ impl<H: HAL> System<H> {
         result
     }
 
+    /// Grant a capability that expires `ttl_ns` from now and log the mutation.
+    pub fn grant_capability_with_ttl(
+        &mut self,
+        from_pid: ProcessId,
+        from_slot: CapSlot,
+        to_pid: ProcessId,
+        perms: Permissions,
+        ttl_ns: u64,
+    ) -> Result<CapSlot, KernelError> {
+        let timestamp = self.uptime_nanos();
+        let (result, commits) = self
+            .kernel
+            .grant_capability_with_ttl(from_pid, from_slot, to_pid, perms, ttl_ns, timestamp);
+        self.record_commits(commits, timestamp);
+        result
+    }
+
     /// Grant capability to a specific endpoint directly.
     pub fn grant_capability_to_endpoint(
         &mut self,
@@ -346,6 +601,47 @@ impl<H: HAL> System<H> {
         result
     }
 
+    /// Derive a capability that expires `ttl_ns` from now and log the mutation.
+    pub fn derive_capability_with_ttl(
+        &mut self,
+        pid: ProcessId,
+        slot: CapSlot,
+        new_perms: Permissions,
+        ttl_ns: u64,
+    ) -> Result<CapSlot, KernelError> {
+        let timestamp = self.uptime_nanos();
+        let (result, commits) = self
+            .kernel
+            .derive_capability_with_ttl(pid, slot, new_perms, ttl_ns, timestamp);
+        self.record_commits(commits, timestamp);
+        result
+    }
+
+    /// Derive a badged endpoint capability and log the mutation.
+    pub fn derive_badged_capability(
+        &mut self,
+        pid: ProcessId,
+        slot: CapSlot,
+        new_perms: Permissions,
+        badge: u64,
+    ) -> Result<CapSlot, KernelError> {
+        let timestamp = self.uptime_nanos();
+        let (result, commits) = self
+            .kernel
+            .derive_badged_capability(pid, slot, new_perms, badge, timestamp);
+        self.record_commits(commits, timestamp);
+        result
+    }
+
+    /// Remove the capabilities that have expired at `timestamp` and log
+    /// the mutations.
+    fn expire_capabilities(&mut self, timestamp: u64) {
+        let commits = self.kernel.expire_capabilities(timestamp);
+        if !commits.is_empty() {
+            self.record_commits(commits, timestamp);
+        }
+    }
+
     /// Get capability space for a process.
     pub fn get_cap_space(&self, pid: ProcessId) -> Option<&CapabilitySpace> {
         self.kernel.get_cap_space(pid)
@@ -391,6 +687,7 @@ impl<H: HAL> System<H> {
         // Create message from kernel (PID 0)
         let message = Message {
             from: ProcessId(0), // Kernel/supervisor identity
+            badge: 0,
             tag,
             data: data.to_vec(),
             transferred_caps: alloc::vec![],
@@ -401,7 +698,9 @@ impl<H: HAL> System<H> {
             .kernel
             .get_endpoint_mut(init_endpoint)
             .ok_or(KernelError::EndpointNotFound)?;
-        endpoint.pending_messages.push_back(message);
+        endpoint
+            .enqueue_kernel(message)
+            .map_err(|_| KernelError::QueueFull)?;
 
         // Log the injection to CommitLog for audit trail
         self.axiom.append_internal_commit(
@@ -413,6 +712,7 @@ impl<H: HAL> System<H> {
             },
             timestamp,
         );
+        self.snapshot_if_needed();
 
         Ok(())
     }
@@ -431,6 +731,7 @@ impl<H: HAL> System<H> {
             .ipc_send(from_pid, endpoint_slot, tag, data, timestamp);
         if let Some(c) = commit {
             self.axiom.append_internal_commit(c.commit_type, timestamp);
+            self.snapshot_if_needed();
         }
         result
     }
@@ -497,7 +798,7 @@ impl<H: HAL> System<H> {
     // Memory Management
     // ========================================================================
 
-    /// Allocate memory to a process.
+    /// Allocate memory to a process (refused if over its memory quota).
     pub fn allocate_memory(&mut self, pid: ProcessId, bytes: usize) -> Result<usize, KernelError> {
         self.kernel.allocate_memory(pid, bytes)
     }
@@ -507,9 +808,32 @@ impl<H: HAL> System<H> {
         self.kernel.free_memory(pid, bytes)
     }
 
-    /// Update process memory size.
-    pub fn update_process_memory(&mut self, pid: ProcessId, new_size: usize) {
-        self.kernel.update_process_memory(pid, new_size)
+    /// Update process memory size and log any quota violation.
+    ///
+    /// Returns `ResourceExhausted` if the process has grown past its memory
+    /// quota; the caller should then stop it.
+    pub fn update_process_memory(
+        &mut self,
+        pid: ProcessId,
+        new_size: usize,
+    ) -> Result<(), KernelError> {
+        let timestamp = self.uptime_nanos();
+        let (result, commits) = self.kernel.update_process_memory(pid, new_size, timestamp);
+        self.record_commits(commits, timestamp);
+        result
+    }
+
+    /// Set a process's resource quota and log the mutation.
+    pub fn set_quota(
+        &mut self,
+        by: ProcessId,
+        target: ProcessId,
+        quota: ResourceQuota,
+    ) -> Result<(), KernelError> {
+        let timestamp = self.uptime_nanos();
+        let (result, commits) = self.kernel.set_quota(by, target, quota, timestamp);
+        self.record_commits(commits, timestamp);
+        result
     }
 
     // ========================================================================
@@ -545,6 +869,53 @@ impl<H: HAL> System<H> {
         self.axiom.syslog()
     }
 
+    /// Sign a CommitLog checkpoint every `interval` commits.
+    ///
+    /// Called once at boot with the machine's checkpoint key.
+    pub fn set_checkpoint_signer(&mut self, signer: Box<dyn CheckpointSigner>, interval: u64) {
+        self.axiom.set_checkpoint_signer(signer, interval);
+    }
+
+    /// Get the signed checkpoints taken so far (oldest first).
+    pub fn checkpoints(&self) -> &[Checkpoint] {
+        self.axiom.commitlog().checkpoints()
+    }
+
+    // ========================================================================
+    // Syscall Recording
+    // ========================================================================
+
+    /// Start recording full syscall payloads and results for replay.
+    pub fn start_recording(&mut self) {
+        self.axiom.start_recording();
+    }
+
+    /// Stop recording and take the trace, if recording was on.
+    pub fn stop_recording(&mut self) -> Option<SyscallTrace> {
+        self.axiom.stop_recording()
+    }
+
+    /// Record an async HAL result (storage, keystore, network) for `pid`.
+    pub fn record_async_result(&mut self, pid: ProcessId, tag: u32, payload: &[u8]) {
+        let timestamp = self.uptime_nanos();
+        self.axiom
+            .record_async_result(pid.0, tag, payload, timestamp);
+    }
+
+    /// Append a snapshot of the current kernel state to the CommitLog.
+    ///
+    /// Replay starts from the newest snapshot, and the CommitLog only trims
+    /// commits that lie behind one. Snapshots are taken automatically as the
+    /// log grows; call this directly before persisting the log.
+    pub fn snapshot(&mut self) -> CommitId {
+        let timestamp = self.uptime_nanos();
+        let commit_type = CommitType::Snapshot {
+            state: self.snapshot_state(),
+            state_hash: self.state_hash(),
+        };
+        self.axiom.append_internal_commit(commit_type, timestamp)
+    }
+
     // ========================================================================
     // Private helpers
     // ========================================================================
@@ -555,6 +926,14 @@ impl<H: HAL> System<H> {
             self.axiom
                 .append_internal_commit(commit.commit_type, timestamp);
         }
+        self.snapshot_if_needed();
+    }
+
+    /// Snapshot the kernel once enough commits have accumulated.
+    fn snapshot_if_needed(&mut self) {
+        if self.axiom.commitlog().needs_snapshot() {
+            self.snapshot();
+        }
     }
 }
 
@@ -566,6 +945,7 @@ impl<H: HAL + Default> System<H> {
             kernel: KernelCore::new(hal),
             axiom: AxiomGateway::new(0),
             boot_time: 0,
+            parked: BTreeMap::new(),
         }
     }
 }
@@ -592,15 +972,27 @@ fn execute_syscall_kernel_fn<H: HAL>(
             let (r, c) = execute_basic_syscall(core, syscall_num, sender, args);
             (r, c, Vec::new())
         }
-        0x11..=0x17 => execute_process_syscall(core, syscall_num, sender, args, data, timestamp),
-        0x30 | 0x31 | 0x35 => {
-            let (r, c) = execute_capability_syscall(core, syscall_num, sender, args, timestamp);
+        0x11..=0x1B => execute_process_syscall(core, syscall_num, sender, args, data, timestamp),
+        0x30 | 0x31 | 0x34..=0x37 => {
+            let (r, c) =
+                execute_capability_syscall(core, syscall_num, sender, args, data, timestamp);
             (r, c, Vec::new())
         }
-        0x40 | 0x41 => {
+        0x40 | 0x41 | 0x43 => {
             execute_ipc_syscall(core, syscall_num, sender, args, data, timestamp)
         }
+        0x42 => wait::execute_call(core, sender, args, data, timestamp),
+        0x45 => wait::execute_receive_timeout(core, sender, data, timestamp),
+        0x46 => wait::execute_call_timeout(core, sender, args, data, timestamp),
+        0x47 | 0x48 | 0x4A => {
+            execute_notification_syscall(core, syscall_num, sender, args, timestamp)
+        }
+        0x49 => wait::execute_notify_wait(core, sender, args, timestamp),
         0x50 => (0, Vec::new(), Vec::new()), // SYS_PS - success, data formatted in metrics.rs
+        0x60..=0x64 => {
+            let (r, c) = region::execute_region_syscall(core, syscall_num, sender, args, timestamp);
+            (r, c, Vec::new())
+        }
         0x70..=0x74 => {
             let (r, c) = execute_storage_syscall(core, syscall_num, sender, data);
             (r, c, Vec::new())
@@ -613,6 +1005,11 @@ fn execute_syscall_kernel_fn<H: HAL>(
             let (r, c) = execute_network_syscall(core, sender, data);
             (r, c, Vec::new())
         }
+        0xA0..=0xA6 => {
+            let (r, c) =
+                device::execute_device_syscall(core, syscall_num, sender, args, data, timestamp);
+            (r, c, Vec::new())
+        }
         _ => (-1, Vec::new(), Vec::new()),
     }
 }
@@ -662,7 +1059,7 @@ fn execute_process_syscall<H: HAL>(
 ) -> (i64, Vec<CommitType>, Vec<u8>) {
     match syscall_num {
         0x11 => {
-            let (r, c) = lifecycle::execute_exit(core, sender, timestamp);
+            let (r, c) = lifecycle::execute_exit(core, sender, args, timestamp);
             (r, c, Vec::new())
         }
         0x12 => (0, Vec::new(), Vec::new()),
@@ -680,7 +1077,20 @@ fn execute_process_syscall<H: HAL>(
         }
         0x16 => lifecycle::execute_load_binary(core, sender, data),
         0x17 => {
-            let (r, c) = lifecycle::execute_spawn_process(core, sender, data, timestamp);
+            let (r, c) = lifecycle::execute_spawn_process(core, sender, args, data, timestamp);
+            (r, c, Vec::new())
+        }
+        0x18 => (lifecycle::execute_shutdown(core, sender, args), Vec::new(), Vec::new()),
+        0x19 => {
+            let (r, c) = lifecycle::execute_set_quota(core, sender, args, data, timestamp);
+            (r, c, Vec::new())
+        }
+        0x1A => {
+            let (r, d) = lifecycle::execute_wait_child(core, sender, args);
+            (r, Vec::new(), d)
+        }
+        0x1B => {
+            let (r, c) = lifecycle::execute_kill_tree(core, sender, args, timestamp);
             (r, c, Vec::new())
         }
         _ => (-1, Vec::new(), Vec::new()),
@@ -692,6 +1102,7 @@ fn execute_capability_syscall<H: HAL>(
     syscall_num: u32,
     sender: ProcessId,
     args: [u32; 4],
+    data: &[u8],
     timestamp: u64,
 ) -> (i64, Vec<CommitType>) {
     match syscall_num {
@@ -700,13 +1111,14 @@ fn execute_capability_syscall<H: HAL>(
             let to_pid = ProcessId(args[1] as u64);
             let perms = Permissions::from_byte(args[2] as u8);
 
-            match core.grant_capability(sender, from_slot, to_pid, perms, timestamp) {
-                (Ok(new_slot), commits) => {
-                    let commit_types: Vec<CommitType> =
-                        commits.into_iter().map(|c| c.commit_type).collect();
-                    (new_slot as i64, commit_types)
-                }
-                (Err(_), _) => (-1, Vec::new()),
+            let (result, commits) =
+                core.grant_capability(sender, from_slot, to_pid, perms, timestamp);
+            // A refused grant still carries its QuotaExceeded commit
+            let commit_types: Vec<CommitType> =
+                commits.into_iter().map(|c| c.commit_type).collect();
+            match result {
+                Ok(new_slot) => (new_slot as i64, commit_types),
+                Err(_) => (-1, commit_types),
             }
         }
         0x31 => {
@@ -722,12 +1134,88 @@ fn execute_capability_syscall<H: HAL>(
                 (Err(_), _) => (-1, Vec::new()),
             }
         }
+        0x34 => {
+            let slot = args[0];
+            let perms = Permissions::from_byte(args[1] as u8);
+            let badge = match data.len() {
+                0 => 0,
+                8 => u64::from_le_bytes([
+                    data[0], data[1], data[2], data[3], data[4], data[5], data[6], data[7],
+                ]),
+                _ => return (syscall_error::INVALID_ARGUMENT as i64, Vec::new()),
+            };
+
+            let (result, commits) = if badge == 0 {
+                core.derive_capability(sender, slot, perms, timestamp)
+            } else {
+                core.derive_badged_capability(sender, slot, perms, badge, timestamp)
+            };
+            let commit_types: Vec<CommitType> =
+                commits.into_iter().map(|c| c.commit_type).collect();
+            match result {
+                Ok(new_slot) => (new_slot as i64, commit_types),
+                Err(_) => (-1, commit_types),
+            }
+        }
         0x35 => {
-            let (result, commits) = core.create_endpoint(sender, timestamp);
+            // arg1 = queue capacity (0 = default), arg2 = overflow flags
+            let capacity = match args[0] {
+                0 => DEFAULT_QUEUE_CAPACITY,
+                n => n as usize,
+            };
+            let policy = if args[1] & ENDPOINT_BLOCK_WHEN_FULL != 0 {
+                OverflowPolicy::Block
+            } else {
+                OverflowPolicy::Reject
+            };
+            let (result, commits) =
+                core.create_endpoint_with_capacity(sender, capacity, policy, timestamp);
             let commit_types: Vec<CommitType> =
                 commits.into_iter().map(|c| c.commit_type).collect();
             match result {
                 Ok((eid, _slot)) => (eid.0 as i64, commit_types),
+                Err(KernelError::InvalidArgument) => {
+                    (syscall_error::INVALID_ARGUMENT as i64, commit_types)
+                }
+                Err(_) => (-1, commit_types),
+            }
+        }
+        0x36 => {
+            let Some(ttl_ns) = parse_ttl(data) else {
+                return (syscall_error::INVALID_ARGUMENT as i64, Vec::new());
+            };
+            let from_slot = args[0];
+            let to_pid = ProcessId(args[1] as u64);
+            let perms = Permissions::from_byte(args[2] as u8);
+
+            let (result, commits) =
+                core.grant_capability_with_ttl(sender, from_slot, to_pid, perms, ttl_ns, timestamp);
+            let commit_types: Vec<CommitType> =
+                commits.into_iter().map(|c| c.commit_type).collect();
+            match result {
+                Ok(new_slot) => (new_slot as i64, commit_types),
+                Err(KernelError::InvalidArgument) => {
+                    (syscall_error::INVALID_ARGUMENT as i64, commit_types)
+                }
+                Err(_) => (-1, commit_types),
+            }
+        }
+        0x37 => {
+            let Some(ttl_ns) = parse_ttl(data) else {
+                return (syscall_error::INVALID_ARGUMENT as i64, Vec::new());
+            };
+            let slot = args[0];
+            let perms = Permissions::from_byte(args[1] as u8);
+
+            let (result, commits) =
+                core.derive_capability_with_ttl(sender, slot, perms, ttl_ns, timestamp);
+            let commit_types: Vec<CommitType> =
+                commits.into_iter().map(|c| c.commit_type).collect();
+            match result {
+                Ok(new_slot) => (new_slot as i64, commit_types),
+                Err(KernelError::InvalidArgument) => {
+                    (syscall_error::INVALID_ARGUMENT as i64, commit_types)
+                }
                 Err(_) => (-1, commit_types),
             }
         }
@@ -735,6 +1223,11 @@ fn execute_capability_syscall<H: HAL>(
     }
 }
 
+/// Read the `[ttl_ns: u64]` payload of a TTL grant or derive
+fn parse_ttl(data: &[u8]) -> Option<u64> {
+    data.try_into().ok().map(u64::from_le_bytes)
+}
+
 fn execute_ipc_syscall<H: HAL>(
     core: &mut KernelCore<H>,
     syscall_num: u32,
@@ -751,44 +1244,110 @@ fn execute_ipc_syscall<H: HAL>(
             let commit_types: Vec<CommitType> = commit.into_iter().map(|c| c.commit_type).collect();
             match result {
                 Ok(()) => (0, commit_types, Vec::new()),
+                Err(KernelError::QueueFull) => {
+                    (syscall_error::QUEUE_FULL as i64, commit_types, Vec::new())
+                }
+                Err(KernelError::WouldBlock) => {
+                    (syscall_error::WOULD_BLOCK as i64, commit_types, Vec::new())
+                }
                 Err(_) => (-1, commit_types, Vec::new()),
             }
         }
         0x41 => {
-            // SYS_RECV: Actually receive and return message data
+            // SYS_RECV: dequeue once, installing any transferred capabilities
             let slot = args[0];
-            match core.ipc_receive(sender, slot, timestamp) {
-                Ok(Some(msg)) => {
-                    // Serialize the message for return to the caller
-                    let response_data = serialize_ipc_message(&msg);
-                    // Return 1 to indicate message received, with serialized message data
-                    (1, Vec::new(), response_data)
+            let (result, commits) = core.ipc_receive_with_caps(sender, slot, timestamp);
+            let commit_types: Vec<CommitType> = commits.into_iter().map(|c| c.commit_type).collect();
+            match result {
+                Ok(Some((msg, installed_slots))) => {
+                    (1, commit_types, serialize_ipc_message(&msg, &installed_slots))
+                }
+                Ok(None) => (0, commit_types, Vec::new()),
+                Err(_) => (-1, commit_types, Vec::new()),
+            }
+        }
+        0x43 => {
+            // SYS_REPLY: only the holder of the caller's reply capability
+            // can answer, and only once
+            let caller = ProcessId(args[0] as u64);
+            let tag = args[1];
+            let (result, commits) = core.reply(sender, caller, tag, data.to_vec(), timestamp);
+            let commit_types: Vec<CommitType> = commits.into_iter().map(|c| c.commit_type).collect();
+            match result {
+                Ok(()) => (0, commit_types, Vec::new()),
+                Err(_) => (-1, commit_types, Vec::new()),
+            }
+        }
+        _ => (-1, Vec::new(), Vec::new()),
+    }
+}
+
+fn execute_notification_syscall<H: HAL>(
+    core: &mut KernelCore<H>,
+    syscall_num: u32,
+    sender: ProcessId,
+    args: [u32; 4],
+    timestamp: u64,
+) -> (i64, Vec<CommitType>, Vec<u8>) {
+    match syscall_num {
+        0x47 => {
+            let (result, commits) = core.create_notification(sender, timestamp);
+            let commit_types: Vec<CommitType> =
+                commits.into_iter().map(|c| c.commit_type).collect();
+            match result {
+                // Pack as (slot << 32) | notification_id, like endpoints
+                Ok((id, slot)) => (
+                    (((slot as u64) << 32) | (id.0 & 0xFFFFFFFF)) as i64,
+                    commit_types,
+                    Vec::new(),
+                ),
+                Err(_) => (-1, commit_types, Vec::new()),
+            }
+        }
+        0x48 => {
+            let bits = (args[1] as u64) | ((args[2] as u64) << 32);
+            let (result, commits) = core.signal_notification(sender, args[0], bits, timestamp);
+            let commit_types: Vec<CommitType> =
+                commits.into_iter().map(|c| c.commit_type).collect();
+            match result {
+                Ok(()) => (0, commit_types, Vec::new()),
+                Err(KernelError::InvalidArgument) => {
+                    (syscall_error::INVALID_ARGUMENT as i64, commit_types, Vec::new())
                 }
-                Ok(None) => (0, Vec::new(), Vec::new()),
-                Err(_) => (-1, Vec::new(), Vec::new()),
+                Err(_) => (-1, commit_types, Vec::new()),
+            }
+        }
+        0x4A => {
+            let (result, commits) = core.poll_notification(sender, args[0], timestamp);
+            let commit_types: Vec<CommitType> =
+                commits.into_iter().map(|c| c.commit_type).collect();
+            match result {
+                Ok(bits) => (0, commit_types, bits.to_le_bytes().to_vec()),
+                Err(_) => (-1, commit_types, Vec::new()),
             }
         }
         _ => (-1, Vec::new(), Vec::new()),
     }
 }
 
-/// Serialize an IPC message for syscall response
-/// Format: [from_pid: u32 LE][tag: u32 LE][num_caps: u8][cap_slots: u32 LE * num_caps][data: [u8]]
-fn serialize_ipc_message(msg: &crate::ipc::Message) -> Vec<u8> {
-    let num_caps = msg.transferred_caps.len() as u8;
+/// Serialize a received IPC message for syscall response
+/// Format: [from_pid: u32 LE][tag: u32 LE][badge: u64 LE][num_caps: u8][cap_slots: u32 LE * num_caps][data: [u8]]
+fn serialize_ipc_message(msg: &crate::ipc::Message, installed_slots: &[CapSlot]) -> Vec<u8> {
+    let num_caps = installed_slots.len() as u8;
     let cap_data_len = (num_caps as usize) * 4;
-    let mut buf = Vec::with_capacity(9 + cap_data_len + msg.data.len());
+    let mut buf = Vec::with_capacity(17 + cap_data_len + msg.data.len());
     
     // from_pid as u32
     buf.extend_from_slice(&(msg.from.0 as u32).to_le_bytes());
     // tag as u32
     buf.extend_from_slice(&msg.tag.to_le_bytes());
+    // badge of the sending capability (0 = unbadged)
+    buf.extend_from_slice(&msg.badge.to_le_bytes());
     // num_caps as u8
     buf.push(num_caps);
-    // cap_slots as u32 each (receiver_slot hint, or 0 if not specified)
-    for cap in &msg.transferred_caps {
-        let slot = cap.receiver_slot.unwrap_or(0);
-        buf.extend_from_slice(&slot.to_le_bytes());
+    // slots the transferred capabilities were installed in
+    for cap_slot in installed_slots {
+        buf.extend_from_slice(&cap_slot.to_le_bytes());
     }
     // data
     buf.extend_from_slice(&msg.data);
@@ -898,6 +1457,14 @@ fn execute_storage_exists<H: HAL>(
 // Keystore Syscalls (0x80-0x84)
 // ============================================================================
 
+/// Keystore namespace reserved for the kernel's own secrets (e.g. the
+/// checkpoint signing key). Keystore syscalls refuse keys under it.
+pub const KERNEL_KEYSTORE_PREFIX: &str = "/kernel/";
+
+fn is_kernel_key(key: &str) -> bool {
+    key.starts_with(KERNEL_KEYSTORE_PREFIX)
+}
+
 fn execute_keystore_syscall<H: HAL>(
     core: &KernelCore<H>,
     syscall_num: u32,
@@ -923,6 +1490,9 @@ fn execute_keystore_read<H: HAL>(
         Ok(k) => k,
         Err(_) => return (-1, Vec::new()),
     };
+    if is_kernel_key(key) {
+        return (syscall_error::PERMISSION_DENIED as i64, Vec::new());
+    }
     match core.hal().keystore_read_async(sender.0, key) {
         Ok(request_id) => (request_id as i64, Vec::new()),
         Err(_) => (-1, Vec::new()),
@@ -945,6 +1515,9 @@ fn execute_keystore_write<H: HAL>(
         Ok(k) => k,
         Err(_) => return (-1, Vec::new()),
     };
+    if is_kernel_key(key) {
+        return (syscall_error::PERMISSION_DENIED as i64, Vec::new());
+    }
     let value = &data[4 + key_len..];
     match core.hal().keystore_write_async(sender.0, key, value) {
         Ok(request_id) => (request_id as i64, Vec::new()),
@@ -961,6 +1534,9 @@ fn execute_keystore_delete<H: HAL>(
         Ok(k) => k,
         Err(_) => return (-1, Vec::new()),
     };
+    if is_kernel_key(key) {
+        return (syscall_error::PERMISSION_DENIED as i64, Vec::new());
+    }
     match core.hal().keystore_delete_async(sender.0, key) {
         Ok(request_id) => (request_id as i64, Vec::new()),
         Err(_) => (-1, Vec::new()),
@@ -976,6 +1552,9 @@ fn execute_keystore_list<H: HAL>(
         Ok(p) => p,
         Err(_) => return (-1, Vec::new()),
     };
+    if is_kernel_key(prefix) {
+        return (syscall_error::PERMISSION_DENIED as i64, Vec::new());
+    }
     match core.hal().keystore_list_async(sender.0, prefix) {
         Ok(request_id) => (request_id as i64, Vec::new()),
         Err(_) => (-1, Vec::new()),
@@ -991,6 +1570,9 @@ fn execute_keystore_exists<H: HAL>(
         Ok(k) => k,
         Err(_) => return (-1, Vec::new()),
     };
+    if is_kernel_key(key) {
+        return (syscall_error::PERMISSION_DENIED as i64, Vec::new());
+    }
     match core.hal().keystore_exists_async(sender.0, key) {
         Ok(request_id) => (request_id as i64, Vec::new()),
         Err(_) => (-1, Vec::new()),
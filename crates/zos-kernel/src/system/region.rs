@@ -0,0 +1,77 @@
+//! Shared region syscall handlers
+//!
+//! This module contains syscall handlers for shared memory regions:
+//! - `SYS_REGION_CREATE` - Create a region and return its capability slot
+//! - `SYS_REGION_MAP` / `SYS_REGION_UNMAP` - Map or drop the caller's window
+//! - `SYS_REGION_FLUSH` / `SYS_REGION_REFRESH` - Copy between the window and
+//!   the region
+//!
+//! The handlers only unpack arguments; `KernelCore` checks capabilities and
+//! mappings before the HAL copies anything.
+
+use alloc::vec::Vec;
+
+use crate::core::KernelCore;
+use crate::error::KernelError;
+use crate::types::ProcessId;
+use zos_axiom::{Commit, CommitType};
+use zos_hal::{HalError, HAL};
+use zos_ipc::{syscall::REGION_MAP_WRITABLE, syscall_error};
+
+/// Execute the region syscalls (0x60 - 0x64).
+pub(in crate::system) fn execute_region_syscall<H: HAL>(
+    core: &mut KernelCore<H>,
+    syscall_num: u32,
+    sender: ProcessId,
+    args: [u32; 4],
+    timestamp: u64,
+) -> (i64, Vec<CommitType>) {
+    match syscall_num {
+        0x60 => {
+            let (result, commits) = core.create_region(sender, args[0] as usize, timestamp);
+            region_result(result.map(|(slot, _)| slot as i64), commits)
+        }
+        0x61 => {
+            let writable = args[2] & REGION_MAP_WRITABLE != 0;
+            let window_len = (args[2] & !REGION_MAP_WRITABLE) as usize;
+            let (result, commits) =
+                core.map_region(sender, args[0], writable, args[1], window_len, timestamp);
+            region_result(result.map(|size| size as i64), commits)
+        }
+        0x62 => {
+            let (result, commits) = core.unmap_region(sender, args[0], timestamp);
+            region_result(result.map(|()| 0), commits)
+        }
+        0x63 => {
+            let (offset, len) = (args[1] as usize, args[2] as usize);
+            let result = core.flush_region(sender, args[0], offset, len, timestamp);
+            region_result(result.map(|()| 0), Vec::new())
+        }
+        0x64 => {
+            let (offset, len) = (args[1] as usize, args[2] as usize);
+            let result = core.refresh_region(sender, args[0], offset, len, timestamp);
+            region_result(result.map(|()| 0), Vec::new())
+        }
+        _ => (-1, Vec::new()),
+    }
+}
+
+/// Map a region operation's result to a syscall result code.
+///
+/// Refusals still carry their commits (e.g. QuotaExceeded).
+fn region_result(result: Result<i64, KernelError>, commits: Vec<Commit>) -> (i64, Vec<CommitType>) {
+    let commit_types = commits.into_iter().map(|c| c.commit_type).collect();
+    let code = match result {
+        Ok(value) => return (value, commit_types),
+        Err(KernelError::InvalidArgument | KernelError::Hal(HalError::InvalidArgument)) => {
+            syscall_error::INVALID_ARGUMENT
+        }
+        Err(KernelError::PermissionDenied) => syscall_error::PERMISSION_DENIED,
+        Err(KernelError::ResourceExhausted | KernelError::Hal(HalError::OutOfMemory)) => {
+            syscall_error::RESOURCE_EXHAUSTED
+        }
+        Err(KernelError::Hal(HalError::NotSupported)) => syscall_error::NOT_SUPPORTED,
+        Err(_) => -1,
+    };
+    (code as i64, commit_types)
+}
@@ -0,0 +1,193 @@
+//! Timed receive and call syscall handlers
+//!
+//! This module contains the System-level handlers for:
+//! - `SYS_CALL` - call an endpoint's owner and wait for its reply
+//! - `SYS_RECV_TIMEOUT` - receive from the first ready of several endpoints
+//! - `SYS_CALL_TIMEOUT` - send a request and wait for the reply
+//! - `SYS_NOTIFY_WAIT` - wait for a notification to be signalled
+//!
+//! All of them park the caller instead of returning "no message". A parked syscall
+//! is not answered: the runtime leaves the process blocked and completes
+//! the syscall with the result of `System::resume_parked` later.
+
+use alloc::vec::Vec;
+
+use crate::core::KernelCore;
+use crate::error::KernelError;
+use crate::ipc::WaitOutcome;
+use crate::types::{CapSlot, ProcessId};
+use zos_axiom::CommitType;
+use zos_hal::HAL;
+use zos_ipc::syscall::{SYS_CALL, SYS_CALL_TIMEOUT, SYS_NOTIFY_WAIT, SYS_RECV_TIMEOUT};
+use zos_ipc::syscall_error;
+use zos_ipc::wait::MAX_WAIT_SLOTS;
+
+/// Result code of a syscall that parked its caller. Never returned to a
+/// process; `System::process_syscall` answers with `WouldBlock` instead.
+pub(in crate::system) const PARKED: i64 = i64::MIN;
+
+/// A syscall parked until its wait ends.
+///
+/// Kept so the SysLog response and the syscall trace can be recorded when
+/// the syscall is finally answered.
+pub(in crate::system) struct ParkedSyscall {
+    pub(in crate::system) req_id: u64,
+    pub(in crate::system) syscall_num: u32,
+    pub(in crate::system) args: [u32; 4],
+    pub(in crate::system) data: Vec<u8>,
+}
+
+/// Execute SYS_CALL.
+///
+/// args: [endpoint_slot, tag]. Payload: the request.
+pub(in crate::system) fn execute_call<H: HAL>(
+    core: &mut KernelCore<H>,
+    sender: ProcessId,
+    args: [u32; 4],
+    data: &[u8],
+    timestamp: u64,
+) -> (i64, Vec<CommitType>, Vec<u8>) {
+    let (result, commits) = core.call(sender, args[0], args[1], data.to_vec(), timestamp);
+    let commit_types = commits.into_iter().map(|c| c.commit_type).collect();
+    let (code, response) = wait_result(SYS_CALL, result);
+    (code, commit_types, response)
+}
+
+/// Execute SYS_RECV_TIMEOUT.
+///
+/// Payload: [timeout_ns: u64, slots: [u32]].
+pub(in crate::system) fn execute_receive_timeout<H: HAL>(
+    core: &mut KernelCore<H>,
+    sender: ProcessId,
+    data: &[u8],
+    timestamp: u64,
+) -> (i64, Vec<CommitType>, Vec<u8>) {
+    let Some((deadline_ns, rest)) = parse_timeout(data, timestamp) else {
+        return (
+            syscall_error::INVALID_ARGUMENT as i64,
+            Vec::new(),
+            Vec::new(),
+        );
+    };
+    if rest.is_empty() || rest.len() % 4 != 0 || rest.len() / 4 > MAX_WAIT_SLOTS {
+        return (
+            syscall_error::INVALID_ARGUMENT as i64,
+            Vec::new(),
+            Vec::new(),
+        );
+    }
+    let slots: Vec<CapSlot> = (0..rest.len() / 4)
+        .map(|i| {
+            let offset = i * 4;
+            u32::from_le_bytes([
+                rest[offset],
+                rest[offset + 1],
+                rest[offset + 2],
+                rest[offset + 3],
+            ])
+        })
+        .collect();
+
+    let (result, commits) = core.wait_receive(sender, &slots, deadline_ns, timestamp);
+    let commit_types = commits.into_iter().map(|c| c.commit_type).collect();
+    let (code, response) = wait_result(SYS_RECV_TIMEOUT, result);
+    (code, commit_types, response)
+}
+
+/// Execute SYS_CALL_TIMEOUT.
+///
+/// args: [endpoint_slot, tag, reply_slot]. Payload: [timeout_ns: u64, request].
+pub(in crate::system) fn execute_call_timeout<H: HAL>(
+    core: &mut KernelCore<H>,
+    sender: ProcessId,
+    args: [u32; 4],
+    data: &[u8],
+    timestamp: u64,
+) -> (i64, Vec<CommitType>, Vec<u8>) {
+    let Some((deadline_ns, request)) = parse_timeout(data, timestamp) else {
+        return (
+            syscall_error::INVALID_ARGUMENT as i64,
+            Vec::new(),
+            Vec::new(),
+        );
+    };
+    let (endpoint_slot, tag, reply_slot) = (args[0], args[1], args[2]);
+
+    let (sent, commit) = core.ipc_send(sender, endpoint_slot, tag, request.to_vec(), timestamp);
+    let mut commit_types: Vec<CommitType> = commit.into_iter().map(|c| c.commit_type).collect();
+    if sent.is_err() {
+        return (-1, commit_types, Vec::new());
+    }
+
+    let (result, commits) = core.wait_receive(sender, &[reply_slot], deadline_ns, timestamp);
+    commit_types.extend(commits.into_iter().map(|c| c.commit_type));
+    let (code, response) = wait_result(SYS_CALL_TIMEOUT, result);
+    (code, commit_types, response)
+}
+
+/// Execute SYS_NOTIFY_WAIT.
+///
+/// args: [notification_slot, timeout_lo, timeout_hi].
+pub(in crate::system) fn execute_notify_wait<H: HAL>(
+    core: &mut KernelCore<H>,
+    sender: ProcessId,
+    args: [u32; 4],
+    timestamp: u64,
+) -> (i64, Vec<CommitType>, Vec<u8>) {
+    let timeout_ns = (args[1] as u64) | ((args[2] as u64) << 32);
+    let deadline_ns = if timeout_ns == 0 {
+        0
+    } else {
+        timestamp.saturating_add(timeout_ns)
+    };
+
+    let (result, commits) = core.wait_notification(sender, args[0], deadline_ns, timestamp);
+    let commit_types = commits.into_iter().map(|c| c.commit_type).collect();
+    let (code, response) = wait_result(SYS_NOTIFY_WAIT, result);
+    (code, commit_types, response)
+}
+
+/// Encode the outcome of a wait as (result_code, response_data).
+///
+/// A message is returned as result 1 with the same layout as SYS_RECV;
+/// SYS_RECV_TIMEOUT prefixes it with the slot it arrived on. Signalled
+/// notification bits are returned as result 0 with the bits as a `u64`.
+pub(in crate::system) fn wait_result(
+    syscall_num: u32,
+    result: Result<WaitOutcome, KernelError>,
+) -> (i64, Vec<u8>) {
+    match result {
+        Ok(WaitOutcome::Message {
+            slot,
+            message,
+            installed_slots,
+        }) => {
+            let mut bytes = Vec::new();
+            if syscall_num == SYS_RECV_TIMEOUT {
+                bytes.extend_from_slice(&slot.to_le_bytes());
+            }
+            bytes.extend_from_slice(&super::serialize_ipc_message(&message, &installed_slots));
+            (1, bytes)
+        }
+        Ok(WaitOutcome::Signaled { bits }) => (0, bits.to_le_bytes().to_vec()),
+        Ok(WaitOutcome::TimedOut) => (syscall_error::TIMED_OUT as i64, Vec::new()),
+        Ok(WaitOutcome::Parked) => (PARKED, Vec::new()),
+        Err(_) => (-1, Vec::new()),
+    }
+}
+
+/// Split off the leading timeout and turn it into an absolute deadline
+/// (0 = none).
+fn parse_timeout(data: &[u8], timestamp: u64) -> Option<(u64, &[u8])> {
+    if data.len() < 8 {
+        return None;
+    }
+    let (timeout, rest) = data.split_at(8);
+    let timeout_ns = u64::from_le_bytes(timeout.try_into().ok()?);
+    let deadline_ns = if timeout_ns == 0 {
+        0
+    } else {
+        timestamp.saturating_add(timeout_ns)
+    };
+    Some((deadline_ns, rest))
+}
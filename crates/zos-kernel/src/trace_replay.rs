@@ -0,0 +1,140 @@
+//! Syscall-level replay of a single process.
+//!
+//! Commit replay (see `replay`) rebuilds kernel state; it cannot reproduce
+//! what one process saw. This module takes a trace recorded by
+//! `System::start_recording` and answers a process's syscalls with the
+//! recorded results instead of running them against a kernel, so a
+//! misbehaving service can be re-run deterministically in isolation.
+//!
+//! The driver owns a `TestHal` whose clock follows the recording: when a
+//! syscall is answered, `now_nanos()` reads the time it was handled at.
+
+use alloc::vec::Vec;
+
+use zos_axiom::{ProcessReplay, SyscallTrace, TraceDivergence};
+use zos_hal::TestHal;
+
+use crate::types::ProcessId;
+
+/// Answers one process's syscalls from a recorded trace.
+pub struct TraceReplayDriver<'a> {
+    pid: ProcessId,
+    replay: ProcessReplay<'a>,
+    hal: TestHal,
+}
+
+impl<'a> TraceReplayDriver<'a> {
+    /// Prepare to replay `pid`'s syscalls from `trace`.
+    pub fn new(trace: &'a SyscallTrace, pid: ProcessId) -> Self {
+        Self {
+            pid,
+            replay: ProcessReplay::new(trace, pid.0),
+            hal: TestHal::new(),
+        }
+    }
+
+    /// Get the process being replayed.
+    pub fn pid(&self) -> ProcessId {
+        self.pid
+    }
+
+    /// Get the HAL whose clock follows the recording.
+    pub fn hal(&self) -> &TestHal {
+        &self.hal
+    }
+
+    /// Handle the process's next syscall.
+    ///
+    /// Returns the recorded (result, response data), or the divergence if
+    /// the process did not make the recorded syscall.
+    pub fn handle_syscall(
+        &mut self,
+        syscall_num: u32,
+        args: [u32; 4],
+        data: &[u8],
+    ) -> Result<(i64, Vec<u8>), TraceDivergence> {
+        let recorded = self.replay.next_result(syscall_num, args, data)?;
+        self.hal.set_now_nanos(recorded.timestamp);
+        Ok((recorded.result, recorded.response.to_vec()))
+    }
+
+    /// Number of recorded syscalls not replayed yet.
+    pub fn remaining(&self) -> usize {
+        self.replay.remaining()
+    }
+
+    /// Check that the process made every recorded syscall.
+    pub fn finish(self) -> Result<(), TraceDivergence> {
+        self.replay.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::System;
+    use crate::{SYS_CREATE_ENDPOINT, SYS_DEBUG, SYS_TIME};
+    use zos_hal::HAL;
+
+    /// Record a short run of two processes through a real System
+    fn record_trace() -> (SyscallTrace, ProcessId, ProcessId) {
+        let mut system = System::new(TestHal::default());
+        let a = system.register_process("a");
+        let b = system.register_process("b");
+
+        system.start_recording();
+        system.process_syscall(a, SYS_CREATE_ENDPOINT, [0, 0, 0, 0], &[]);
+        system.hal().set_now_nanos(1_000);
+        system.process_syscall(b, SYS_TIME, [0, 0, 0, 0], &[]);
+        system.process_syscall(a, SYS_DEBUG, [0, 0, 0, 0], b"hello");
+        system.record_async_result(a, 0x80, &[1, 2, 3]);
+        (system.stop_recording().unwrap(), a, b)
+    }
+
+    #[test]
+    fn test_replay_returns_recorded_results() {
+        let (trace, a, _) = record_trace();
+        let recorded: Vec<_> = trace.events_for(a.0).cloned().collect();
+        assert_eq!(recorded.len(), 3);
+
+        let mut driver = TraceReplayDriver::new(&trace, a);
+        let (result, _) = driver.handle_syscall(SYS_CREATE_ENDPOINT, [0, 0, 0, 0], &[]).unwrap();
+        assert!(result >= 0);
+        assert_eq!(driver.hal().now_nanos(), 0);
+
+        driver.handle_syscall(SYS_DEBUG, [0, 0, 0, 0], b"hello").unwrap();
+        assert_eq!(driver.hal().now_nanos(), 1_000);
+        assert_eq!(driver.finish(), Ok(()));
+    }
+
+    #[test]
+    fn test_replay_survives_trace_file_roundtrip() {
+        let (trace, _, b) = record_trace();
+        let trace = SyscallTrace::decode(&trace.encode()).unwrap();
+
+        let mut driver = TraceReplayDriver::new(&trace, b);
+        assert_eq!(driver.remaining(), 1);
+        driver.handle_syscall(SYS_TIME, [0, 0, 0, 0], &[]).unwrap();
+        assert_eq!(driver.hal().now_nanos(), 1_000);
+        assert!(driver.finish().is_ok());
+    }
+
+    #[test]
+    fn test_replay_reports_divergence() {
+        let (trace, a, _) = record_trace();
+
+        let mut driver = TraceReplayDriver::new(&trace, a);
+        assert_eq!(
+            driver.handle_syscall(SYS_TIME, [0, 0, 0, 0], &[]),
+            Err(TraceDivergence::Mismatch {
+                index: 0,
+                expected: SYS_CREATE_ENDPOINT,
+                actual: SYS_TIME
+            })
+        );
+        assert_eq!(
+            driver.finish(),
+            Err(TraceDivergence::Incomplete { remaining: 2 })
+        );
+    }
+}
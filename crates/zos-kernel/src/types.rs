@@ -1,14 +1,22 @@
 //! Core kernel types
 //!
 //! This module contains the fundamental types used throughout the kernel:
-//! - Process and endpoint identifiers
-//! - Process state and metrics
+//! - Process, endpoint and notification identifiers
+//! - Process state, exit status and metrics
 //! - System-wide metrics
+//! - Device access (I/O port ranges, IRQ bindings)
+//! - Shared memory regions
 
+use alloc::collections::BTreeMap;
 use alloc::string::String;
+use alloc::vec::Vec;
+use zos_ipc::exit_kind;
 
 // Re-export types from zos-axiom to maintain backwards compatibility
-pub use zos_axiom::{CapSlot, ObjectType};
+pub use zos_axiom::{CapSlot, ObjectType, QuotaResource, ResourceQuota};
+
+/// Length of a syscall rate window (1 second)
+pub const RATE_WINDOW_NS: u64 = 1_000_000_000;
 
 /// Process identifier
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -18,6 +26,14 @@ pub struct ProcessId(pub u64);
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct EndpointId(pub u64);
 
+/// Notification object identifier
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NotificationId(pub u64);
+
+/// Shared memory region identifier
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RegionId(pub u64);
+
 /// Process state
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ProcessState {
@@ -29,14 +45,61 @@ pub enum ProcessState {
     Zombie,
 }
 
+/// How a process died, as reported to its parent
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExitStatus {
+    /// Exited voluntarily with a code
+    Exited(i32),
+    /// Killed by another process (`ProcessId(0)` = the kernel or supervisor)
+    Killed { by: ProcessId },
+    /// Faulted (trap, out of fuel, ...) with a reason code
+    Faulted { reason: u32 },
+}
+
+impl ExitStatus {
+    /// Encode as a `zos_ipc::exit_kind` code and value
+    pub fn to_kind_value(self) -> (u8, u64) {
+        match self {
+            ExitStatus::Exited(code) => (exit_kind::EXITED, code as i64 as u64),
+            ExitStatus::Killed { by } => (exit_kind::KILLED, by.0),
+            ExitStatus::Faulted { reason } => (exit_kind::FAULTED, reason as u64),
+        }
+    }
+
+    /// Encode as a `MSG_CHILD_EXITED` payload for `pid`.
+    ///
+    /// Layout: `[pid: u64, kind: u8, value: u64]`, little-endian.
+    pub fn to_payload(self, pid: ProcessId) -> Vec<u8> {
+        let (kind, value) = self.to_kind_value();
+        let mut data = Vec::with_capacity(17);
+        data.extend_from_slice(&pid.0.to_le_bytes());
+        data.push(kind);
+        data.extend_from_slice(&value.to_le_bytes());
+        data
+    }
+}
+
+/// A dead process its parent has not reaped yet
+#[derive(Clone, Copy, Debug)]
+pub struct ExitRecord {
+    /// Process that may reap it
+    pub parent: ProcessId,
+    /// How it died
+    pub status: ExitStatus,
+}
+
 /// Process descriptor
 pub struct Process {
     /// Process ID
     pub pid: ProcessId,
+    /// Spawning process, told when this one dies (`ProcessId(0)` = none)
+    pub parent: ProcessId,
     /// Process name
     pub name: String,
     /// Current state
     pub state: ProcessState,
+    /// Limits enforced by the kernel (set by the spawner)
+    pub quota: ResourceQuota,
     /// Detailed metrics for this process
     pub metrics: ProcessMetrics,
 }
@@ -60,6 +123,10 @@ pub struct ProcessMetrics {
     pub last_active_ns: u64,
     /// Process start time (nanos since boot)
     pub start_time_ns: u64,
+    /// Start of the current syscall rate window
+    pub rate_window_start_ns: u64,
+    /// Syscalls made in the current rate window
+    pub rate_window_count: u64,
 }
 
 /// Per-endpoint tracking
@@ -73,6 +140,8 @@ pub struct EndpointMetrics {
     pub total_bytes: u64,
     /// High water mark (max queue depth seen)
     pub queue_high_water: usize,
+    /// Messages rejected because the queue was full
+    pub dropped_messages: u64,
 }
 
 /// System-wide metrics
@@ -91,3 +160,132 @@ pub struct SystemMetrics {
     /// Uptime in nanoseconds
     pub uptime_ns: u64,
 }
+
+/// A contiguous range of x86 I/O ports.
+///
+/// Packed into a capability's `object_id` as `(base << 16) | len`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IoPortRange {
+    /// First port in the range
+    pub base: u16,
+    /// Number of ports
+    pub len: u16,
+}
+
+impl IoPortRange {
+    /// Encode as a capability object ID
+    pub fn to_object_id(self) -> u64 {
+        ((self.base as u64) << 16) | self.len as u64
+    }
+
+    /// Decode from a capability object ID
+    pub fn from_object_id(object_id: u64) -> Self {
+        Self {
+            base: (object_id >> 16) as u16,
+            len: object_id as u16,
+        }
+    }
+
+    /// Check that a `width`-byte access at `port` lies inside the range
+    pub fn contains(self, port: u16, width: u8) -> bool {
+        let start = self.base as u32;
+        let end = start + self.len as u32;
+        let port = port as u32;
+        port >= start && port + width as u32 <= end
+    }
+}
+
+/// Where a bound IRQ line is delivered
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IrqTarget {
+    /// OR `bits` into a notification
+    Notification { id: NotificationId, bits: u64 },
+    /// Queue a `MSG_IRQ` message on an endpoint
+    Endpoint(EndpointId),
+}
+
+impl IrqTarget {
+    /// Split into (`ObjectType` byte, object ID, bits) as recorded in
+    /// `IrqBound` commits and snapshots
+    pub fn to_parts(self) -> (u8, u64, u64) {
+        match self {
+            IrqTarget::Notification { id, bits } => (ObjectType::Notification as u8, id.0, bits),
+            IrqTarget::Endpoint(id) => (ObjectType::Endpoint as u8, id.0, 0),
+        }
+    }
+
+    /// Rebuild from the parts produced by `to_parts`
+    pub fn from_parts(target_type: u8, target_id: u64, bits: u64) -> Option<Self> {
+        match ObjectType::from_u8(target_type)? {
+            ObjectType::Notification => Some(IrqTarget::Notification {
+                id: NotificationId(target_id),
+                bits,
+            }),
+            ObjectType::Endpoint => Some(IrqTarget::Endpoint(EndpointId(target_id))),
+            _ => None,
+        }
+    }
+}
+
+/// A user-space driver's binding of an IRQ line.
+///
+/// Delivery masks the line; it is unmasked when the driver acknowledges
+/// the interrupt, so a level-triggered device cannot flood the target.
+#[derive(Clone, Copy, Debug)]
+pub struct IrqBinding {
+    /// Driver process that bound the line
+    pub owner: ProcessId,
+    /// Delivery target
+    pub target: IrqTarget,
+    /// Line is masked awaiting acknowledgement
+    pub masked: bool,
+}
+
+/// Granularity of shared region sizes
+pub const REGION_PAGE_SIZE: usize = 4096;
+
+/// Largest shared region (the VFS file size limit)
+pub const MAX_REGION_SIZE: usize = 16 * 1024 * 1024;
+
+/// Where a process has a shared region mapped
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RegionMapping {
+    /// The process may publish its window back into the region
+    pub writable: bool,
+    /// Address of the window in the process's linear memory
+    pub window: u32,
+}
+
+/// A shared memory region.
+///
+/// The contents live in the HAL. The kernel tracks who pays for the region
+/// and where it is mapped; the region is freed once no capability refers
+/// to it.
+#[derive(Clone, Debug)]
+pub struct SharedRegion {
+    /// Region ID
+    pub id: RegionId,
+    /// Process charged for the region's memory
+    pub owner: ProcessId,
+    /// Size in bytes (a multiple of `REGION_PAGE_SIZE`)
+    pub size: usize,
+    /// Current mappings
+    pub mappings: BTreeMap<ProcessId, RegionMapping>,
+}
+
+impl SharedRegion {
+    /// Create an unmapped region of at least `size` bytes
+    pub fn new(id: RegionId, owner: ProcessId, size: usize) -> Self {
+        Self {
+            id,
+            owner,
+            size: size.div_ceil(REGION_PAGE_SIZE) * REGION_PAGE_SIZE,
+            mappings: BTreeMap::new(),
+        }
+    }
+
+    /// Check that `offset..offset + len` lies inside the region
+    pub fn contains(&self, offset: usize, len: usize) -> bool {
+        offset.checked_add(len).is_some_and(|end| end <= self.size)
+    }
+}
@@ -12,8 +12,8 @@ use core::cell::RefCell;
 use core::sync::atomic::{AtomicU64, Ordering};
 use zos_hal::{HalError, NumericProcessHandle, HAL};
 use zos_kernel::{
-    axiom_check, AxiomError, Capability, CapabilitySpace, ObjectType, Permissions, ProcessId,
-    ProcessState, System,
+    axiom_check, AxiomError, Capability, CapabilitySpace, EndpointId, ObjectType, OverflowPolicy,
+    Permissions, ProcessId, ProcessState, System, DEFAULT_QUEUE_CAPACITY,
 };
 
 // ============================================================================
@@ -36,6 +36,11 @@ pub struct MockHal {
     next_pid: AtomicU64,
     processes: RefCell<BTreeMap<u64, MockProcess>>,
     incoming_messages: RefCell<Vec<(NumericProcessHandle, Vec<u8>)>>,
+    power_control: bool,
+    ports: RefCell<BTreeMap<u16, u32>>,
+    irq_lines: RefCell<BTreeMap<u8, bool>>,
+    regions: RefCell<BTreeMap<u64, Vec<u8>>>,
+    memories: RefCell<BTreeMap<u64, Vec<u8>>>,
 }
 
 impl MockHal {
@@ -48,6 +53,11 @@ impl MockHal {
             next_pid: AtomicU64::new(1),
             processes: RefCell::new(BTreeMap::new()),
             incoming_messages: RefCell::new(Vec::new()),
+            power_control: false,
+            ports: RefCell::new(BTreeMap::new()),
+            irq_lines: RefCell::new(BTreeMap::new()),
+            regions: RefCell::new(BTreeMap::new()),
+            memories: RefCell::new(BTreeMap::new()),
         }
     }
 
@@ -61,10 +71,24 @@ impl MockHal {
             next_pid: AtomicU64::new(1),
             processes: RefCell::new(BTreeMap::new()),
             incoming_messages: RefCell::new(Vec::new()),
+            power_control: false,
+            ports: RefCell::new(BTreeMap::new()),
+            irq_lines: RefCell::new(BTreeMap::new()),
+            regions: RefCell::new(BTreeMap::new()),
+            memories: RefCell::new(BTreeMap::new()),
         }
     }
 }
 
+impl MockHal {
+    /// Linear memory of a process (one 64 KiB WASM page)
+    fn memory(&self, pid: u64) -> core::cell::RefMut<'_, Vec<u8>> {
+        core::cell::RefMut::map(self.memories.borrow_mut(), |m| {
+            m.entry(pid).or_insert_with(|| alloc::vec![0; 65536])
+        })
+    }
+}
+
 impl Default for MockHal {
     fn default() -> Self {
         Self::new()
@@ -77,6 +101,10 @@ unsafe impl Sync for MockHal {}
 impl HAL for MockHal {
     type ProcessHandle = NumericProcessHandle;
 
+    fn supports_power_control(&self) -> bool {
+        self.power_control
+    }
+
     fn spawn_process(&self, name: &str, _binary: &[u8]) -> Result<Self::ProcessHandle, HalError> {
         let pid = self.next_pid.fetch_add(1, Ordering::SeqCst);
         let handle = NumericProcessHandle::new(pid);
@@ -191,6 +219,81 @@ impl HAL for MockHal {
         let mut messages = self.incoming_messages.borrow_mut();
         messages.drain(..).collect()
     }
+
+    fn io_port_read(&self, port: u16, _width: u8) -> Result<u32, HalError> {
+        Ok(self.ports.borrow().get(&port).copied().unwrap_or(0))
+    }
+
+    fn io_port_write(&self, port: u16, _width: u8, value: u32) -> Result<(), HalError> {
+        self.ports.borrow_mut().insert(port, value);
+        Ok(())
+    }
+
+    fn irq_enable(&self, irq: u8) -> Result<(), HalError> {
+        // Lines 0-15 exist; the PIT on line 0 belongs to the kernel
+        if irq == 0 || irq >= 16 {
+            return Err(HalError::InvalidArgument);
+        }
+        self.irq_lines.borrow_mut().insert(irq, true);
+        Ok(())
+    }
+
+    fn irq_disable(&self, irq: u8) {
+        self.irq_lines.borrow_mut().insert(irq, false);
+    }
+
+    fn region_alloc(&self, id: u64, size: usize) -> Result<(), HalError> {
+        self.regions.borrow_mut().insert(id, alloc::vec![0; size]);
+        Ok(())
+    }
+
+    fn region_free(&self, id: u64) {
+        self.regions.borrow_mut().remove(&id);
+    }
+
+    fn region_copy_to_process(
+        &self,
+        id: u64,
+        offset: usize,
+        pid: u64,
+        addr: u32,
+        len: usize,
+    ) -> Result<(), HalError> {
+        let regions = self.regions.borrow();
+        let src = regions
+            .get(&id)
+            .and_then(|r| r.get(offset..offset + len))
+            .ok_or(HalError::InvalidArgument)?;
+        let mut memory = self.memory(pid);
+        let addr = addr as usize;
+        memory
+            .get_mut(addr..addr + len)
+            .ok_or(HalError::InvalidArgument)?
+            .copy_from_slice(src);
+        Ok(())
+    }
+
+    fn region_copy_from_process(
+        &self,
+        id: u64,
+        offset: usize,
+        pid: u64,
+        addr: u32,
+        len: usize,
+    ) -> Result<(), HalError> {
+        let memory = self.memory(pid);
+        let addr = addr as usize;
+        let src = memory
+            .get(addr..addr + len)
+            .ok_or(HalError::InvalidArgument)?;
+        let mut regions = self.regions.borrow_mut();
+        regions
+            .get_mut(&id)
+            .and_then(|r| r.get_mut(offset..offset + len))
+            .ok_or(HalError::InvalidArgument)?
+            .copy_from_slice(src);
+        Ok(())
+    }
 }
 
 // ============================================================================
@@ -341,6 +444,7 @@ fn test_axiom_check_valid_capability() {
         permissions: Permissions::full(),
         generation: 0,
         expires_at: 0,
+        badge: 0,
     };
     let slot = cspace.insert(cap);
 
@@ -376,6 +480,7 @@ fn test_axiom_check_expired_capability() {
         permissions: Permissions::full(),
         generation: 0,
         expires_at: 1000,
+        badge: 0,
     };
     let slot = cspace.insert(cap);
 
@@ -433,6 +538,171 @@ fn test_sys_register_process_init_only() {
     );
 }
 
+/// Test that SYS_SHUTDOWN (0x18) is Init-only and hands the request to the
+/// boot loop exactly once.
+#[test]
+fn test_sys_shutdown_init_only() {
+    use zos_ipc::syscall::SYS_SHUTDOWN;
+    use zos_ipc::syscall_error::{NOT_SUPPORTED, PERMISSION_DENIED};
+
+    // Platforms without power control refuse the request
+    let mut kernel = System::new(MockHal::new());
+    let init_pid = kernel.register_process_with_pid(ProcessId(1), "init");
+    let (result, _rich, _data) = kernel.process_syscall(init_pid, SYS_SHUTDOWN, [0, 0, 0, 0], &[]);
+    assert_eq!(result, NOT_SUPPORTED as i64);
+    assert_eq!(kernel.take_shutdown_request(), None);
+
+    let mut hal = MockHal::new();
+    hal.power_control = true;
+    let mut kernel = System::new(hal);
+    let init_pid = kernel.register_process_with_pid(ProcessId(1), "init");
+    let other_pid = kernel.register_process("terminal");
+
+    // Only Init may power off the machine
+    let (result, _rich, _data) = kernel.process_syscall(other_pid, SYS_SHUTDOWN, [1, 0, 0, 0], &[]);
+    assert_eq!(result, PERMISSION_DENIED as i64);
+    assert_eq!(kernel.take_shutdown_request(), None);
+
+    let (result, _rich, _data) = kernel.process_syscall(init_pid, SYS_SHUTDOWN, [1, 0, 0, 0], &[]);
+    assert_eq!(result, 0);
+    assert_eq!(kernel.take_shutdown_request(), Some(true));
+    assert_eq!(kernel.take_shutdown_request(), None);
+}
+
+/// Test that SYS_SET_QUOTA (0x19) is Init-only, logged, and visible in SYS_PS.
+#[test]
+fn test_sys_set_quota_init_only() {
+    use zos_ipc::syscall::{SYS_PS, SYS_SET_QUOTA};
+    use zos_ipc::syscall_error::{NOT_FOUND, PERMISSION_DENIED};
+    use zos_kernel::{CommitType, ResourceQuota};
+
+    let mut kernel = System::new(MockHal::new());
+    let init_pid = kernel.register_process_with_pid(ProcessId(1), "init");
+    let other_pid = kernel.register_process("terminal");
+
+    let quota = ResourceQuota {
+        max_endpoints: 4,
+        max_syscalls_per_sec: 100,
+        ..Default::default()
+    };
+    let record = quota.to_bytes();
+    let target = [other_pid.0 as u32, 0, 0, 0];
+
+    // Other processes may not change quotas
+    let (result, _rich, _data) = kernel.process_syscall(other_pid, SYS_SET_QUOTA, target, &record);
+    assert_eq!(result, PERMISSION_DENIED as i64);
+    assert!(kernel.get_process(other_pid).unwrap().quota.is_unlimited());
+
+    let (result, _rich, _data) =
+        kernel.process_syscall(init_pid, SYS_SET_QUOTA, [99, 0, 0, 0], &record);
+    assert_eq!(result, NOT_FOUND as i64);
+
+    let (result, _rich, _data) = kernel.process_syscall(init_pid, SYS_SET_QUOTA, target, &record);
+    assert_eq!(result, 0);
+    assert_eq!(kernel.get_process(other_pid).unwrap().quota, quota);
+    assert!(kernel.commitlog().commits().iter().any(|c| matches!(
+        c.commit_type,
+        CommitType::QuotaSet { pid, by: 1, .. } if pid == other_pid.0
+    )));
+
+    // ps reports each process's quota after its name
+    let (_result, _rich, data) = kernel.process_syscall(init_pid, SYS_PS, [0; 4], &[]);
+    let mut offset = 4;
+    let mut reported = Vec::new();
+    for _ in 0..u32::from_le_bytes(data[..4].try_into().unwrap()) {
+        let name_len = u16::from_le_bytes([data[offset + 4], data[offset + 5]]) as usize;
+        offset += 6 + name_len;
+        reported.push(ResourceQuota::from_bytes(&data[offset..]).unwrap());
+        offset += ResourceQuota::ENCODED_SIZE;
+    }
+    assert_eq!(offset, data.len());
+    assert_eq!(reported, [ResourceQuota::default(), quota]);
+}
+
+/// Test that a quota set at spawn time is enforced and refusals are logged.
+#[test]
+fn test_spawn_quota_enforced() {
+    use zos_ipc::quota::SPAWN_WITH_QUOTA;
+    use zos_ipc::syscall::{SYS_CREATE_ENDPOINT_FOR, SYS_SPAWN_PROCESS};
+    use zos_kernel::{CommitType, QuotaResource, ResourceQuota};
+
+    let mut kernel = System::new(MockHal::new());
+    let init_pid = kernel.register_process_with_pid(ProcessId(1), "init");
+
+    let quota = ResourceQuota {
+        max_endpoints: 1,
+        ..Default::default()
+    };
+    let mut payload = Vec::new();
+    payload.extend_from_slice(&6u32.to_le_bytes());
+    payload.extend_from_slice(b"worker");
+    payload.extend_from_slice(&quota.to_bytes());
+    payload.extend_from_slice(b"\0asm");
+    let (result, _rich, _data) = kernel.process_syscall(
+        init_pid,
+        SYS_SPAWN_PROCESS,
+        [payload.len() as u32, 0, SPAWN_WITH_QUOTA, 0],
+        &payload,
+    );
+    assert!(result > 0, "spawn should succeed, got {}", result);
+    let worker = ProcessId(result as u64);
+    assert_eq!(kernel.get_process(worker).unwrap().quota, quota);
+
+    // The first endpoint fits the quota, the second does not
+    let args = [worker.0 as u32, 0, 0, 0];
+    let (result, _rich, _data) =
+        kernel.process_syscall(init_pid, SYS_CREATE_ENDPOINT_FOR, args, &[]);
+    assert!(result >= 0);
+    let (result, _rich, _data) =
+        kernel.process_syscall(init_pid, SYS_CREATE_ENDPOINT_FOR, args, &[]);
+    assert_eq!(result, -1);
+    assert_eq!(kernel.list_endpoints().len(), 1);
+
+    let last = kernel.commitlog().commits().last().unwrap();
+    assert!(matches!(
+        last.commit_type,
+        CommitType::QuotaExceeded { pid, resource }
+            if pid == worker.0 && resource == QuotaResource::Endpoints as u8
+    ));
+}
+
+/// Test that the syscall rate quota refuses calls past the limit but never exit.
+#[test]
+fn test_syscall_rate_quota() {
+    use zos_ipc::syscall::{SYS_EXIT, SYS_TIME};
+    use zos_ipc::syscall_error::RESOURCE_EXHAUSTED;
+    use zos_kernel::ResourceQuota;
+
+    let mut kernel = System::new(MockHal::new());
+    let init_pid = kernel.register_process_with_pid(ProcessId(1), "init");
+    let worker = kernel.register_process("worker");
+    let quota = ResourceQuota {
+        max_syscalls_per_sec: 2,
+        ..Default::default()
+    };
+    kernel.set_quota(init_pid, worker, quota).unwrap();
+
+    for _ in 0..2 {
+        let (result, _rich, _data) = kernel.process_syscall(worker, SYS_TIME, [0; 4], &[]);
+        assert_ne!(result, RESOURCE_EXHAUSTED as i64);
+    }
+    let (result, _rich, _data) = kernel.process_syscall(worker, SYS_TIME, [0; 4], &[]);
+    assert_eq!(result, RESOURCE_EXHAUSTED as i64);
+    let (result, _rich, _data) = kernel.process_syscall(worker, SYS_EXIT, [0; 4], &[]);
+    assert_ne!(result, RESOURCE_EXHAUSTED as i64);
+
+    // A new window resets the count
+    kernel.hal().time.fetch_add(1_000_000_000, Ordering::SeqCst);
+    let (result, _rich, _data) = kernel.process_syscall(worker, SYS_TIME, [0; 4], &[]);
+    assert_ne!(result, RESOURCE_EXHAUSTED as i64);
+
+    // Init cannot hand out more than it has itself
+    kernel.set_quota(init_pid, init_pid, quota).unwrap();
+    assert!(kernel
+        .set_quota(init_pid, worker, ResourceQuota::default())
+        .is_err());
+}
+
 /// Test that SYS_CREATE_ENDPOINT_FOR (0x15) is Init-only.
 ///
 /// This syscall should only succeed when called by Init (PID 1).
@@ -645,6 +915,211 @@ fn test_revoke_requires_grant_permission() {
     assert!(cap_space.get(owner_slot).is_none());
 }
 
+#[test]
+fn test_revoke_removes_everything_derived() {
+    use zos_ipc::{kernel::MSG_CAP_REVOKED, revoke_reason};
+
+    let mut kernel = System::new(MockHal::new());
+    let owner = kernel.register_process("owner");
+    let a = kernel.register_process("a");
+    let b = kernel.register_process("b");
+    let c = kernel.register_process("c");
+    let (_, a_inbox) = kernel.create_endpoint(a).unwrap();
+    let (_, b_inbox) = kernel.create_endpoint(b).unwrap();
+
+    let (eid, root) = kernel.create_endpoint(owner).unwrap();
+    let lent = kernel
+        .derive_capability(owner, root, Permissions::full())
+        .unwrap();
+    let a_slot = kernel
+        .grant_capability(owner, lent, a, Permissions::full())
+        .unwrap();
+    let b_slot = kernel
+        .grant_capability(a, a_slot, b, Permissions::write_only())
+        .unwrap();
+    let c_slot = kernel
+        .grant_capability(owner, root, c, Permissions::write_only())
+        .unwrap();
+
+    kernel.revoke_capability(owner, lent).unwrap();
+
+    // The whole subtree is gone, whoever held it; siblings survive
+    assert!(kernel.get_cap_space(owner).unwrap().get(lent).is_none());
+    assert!(kernel.get_cap_space(a).unwrap().get(a_slot).is_none());
+    assert!(kernel.get_cap_space(b).unwrap().get(b_slot).is_none());
+    assert!(kernel.get_cap_space(owner).unwrap().get(root).is_some());
+    assert!(kernel.get_cap_space(c).unwrap().get(c_slot).is_some());
+
+    // Holders other than the revoker are told
+    for (pid, inbox, slot) in [(a, a_inbox, a_slot), (b, b_inbox, b_slot)] {
+        let msg = kernel.ipc_receive(pid, inbox).unwrap().unwrap();
+        assert_eq!(msg.tag, MSG_CAP_REVOKED);
+        assert_eq!(&msg.data[..4], &slot.to_le_bytes());
+        assert_eq!(msg.data[4], ObjectType::Endpoint as u8);
+        assert_eq!(&msg.data[5..13], &eid.0.to_le_bytes());
+        assert_eq!(msg.data[13], revoke_reason::EXPLICIT);
+    }
+}
+
+#[test]
+fn test_revoke_reaches_copies_of_deleted_caps() {
+    let mut kernel = System::new(MockHal::new());
+    let owner = kernel.register_process("owner");
+    let middle = kernel.register_process("middle");
+    let last = kernel.register_process("last");
+
+    let (_, root) = kernel.create_endpoint(owner).unwrap();
+    let lent = kernel
+        .derive_capability(owner, root, Permissions::full())
+        .unwrap();
+    let middle_slot = kernel
+        .grant_capability(owner, lent, middle, Permissions::full())
+        .unwrap();
+    let last_slot = kernel
+        .grant_capability(middle, middle_slot, last, Permissions::read_only())
+        .unwrap();
+
+    // Dropping the intermediate copy (or its holder dying) must not let
+    // the copy made from it escape revocation
+    kernel.kill_process(middle);
+    kernel.revoke_capability(owner, lent).unwrap();
+    assert!(kernel.get_cap_space(last).unwrap().get(last_slot).is_none());
+}
+
+#[test]
+fn test_granted_capability_expires_after_ttl() {
+    use zos_axiom::{replay_and_verify, CommitType, Replayable};
+    use zos_ipc::{kernel::MSG_CAP_REVOKED, revoke_reason};
+
+    let mut kernel = System::new(MockHal::new());
+    let owner = kernel.register_process("owner");
+    let holder = kernel.register_process("holder");
+    let (_, inbox) = kernel.create_endpoint(holder).unwrap();
+    let (eid, root) = kernel.create_endpoint(owner).unwrap();
+
+    let now = kernel.uptime_nanos();
+    let lent = kernel
+        .grant_capability_with_ttl(owner, root, holder, Permissions::write_only(), 60_000)
+        .unwrap();
+    let cap = kernel.get_cap_space(holder).unwrap().get(lent).unwrap();
+    assert_eq!(cap.expires_at, now + 60_000);
+    let deadline = kernel.next_deadline().expect("expiring cap should set a deadline");
+    assert_eq!(deadline, now + 60_001);
+
+    // Still usable up to and including its expiry
+    kernel.hal().time.store(now + 60_000, Ordering::SeqCst);
+    kernel.resume_parked();
+    assert!(kernel.get_cap_space(holder).unwrap().get(lent).is_some());
+
+    kernel.hal().time.store(deadline, Ordering::SeqCst);
+    kernel.resume_parked();
+    assert!(kernel.get_cap_space(holder).unwrap().get(lent).is_none());
+    assert!(kernel.get_cap_space(owner).unwrap().get(root).is_some());
+    assert_eq!(kernel.next_deadline(), None);
+    assert!(kernel.commitlog().commits().iter().any(|c| matches!(
+        c.commit_type,
+        CommitType::CapRemoved { pid, slot } if pid == holder.0 && slot == lent
+    )));
+
+    let msg = kernel.ipc_receive(holder, inbox).unwrap().unwrap();
+    assert_eq!(msg.tag, MSG_CAP_REVOKED);
+    assert_eq!(&msg.data[..4], &lent.to_le_bytes());
+    assert_eq!(&msg.data[5..13], &eid.0.to_le_bytes());
+    assert_eq!(msg.data[13], revoke_reason::EXPIRED);
+
+    let mut replayed: System<MockHal> = System::new_for_replay();
+    replay_and_verify(&mut replayed, kernel.commitlog().commits(), kernel.state_hash()).unwrap();
+}
+
+#[test]
+fn test_capability_ttl_cannot_outlive_source() {
+    let mut kernel = System::new(MockHal::new());
+    let owner = kernel.register_process("owner");
+    let holder = kernel.register_process("holder");
+    let (_, root) = kernel.create_endpoint(owner).unwrap();
+
+    let now = kernel.uptime_nanos();
+    let lent = kernel
+        .grant_capability_with_ttl(owner, root, holder, Permissions::full(), 1_000)
+        .unwrap();
+
+    // A longer lifetime is clamped to the source's; a plain derive inherits it
+    let longer = kernel
+        .derive_capability_with_ttl(holder, lent, Permissions::read_only(), 1_000_000)
+        .unwrap();
+    let plain = kernel
+        .derive_capability(holder, lent, Permissions::read_only())
+        .unwrap();
+    let shorter = kernel
+        .derive_capability_with_ttl(holder, lent, Permissions::read_only(), 10)
+        .unwrap();
+    let cspace = kernel.get_cap_space(holder).unwrap();
+    assert_eq!(cspace.get(longer).unwrap().expires_at, now + 1_000);
+    assert_eq!(cspace.get(plain).unwrap().expires_at, now + 1_000);
+    assert_eq!(cspace.get(shorter).unwrap().expires_at, now + 10);
+
+    // Zero would mean "never expires"
+    assert_eq!(
+        kernel.derive_capability_with_ttl(holder, lent, Permissions::read_only(), 0),
+        Err(zos_kernel::KernelError::InvalidArgument)
+    );
+}
+
+#[test]
+fn test_syscall_dispatch_capability_ttl() {
+    use zos_ipc::syscall::{SYS_CAP_DERIVE_TTL, SYS_CAP_GRANT_TTL};
+    use zos_ipc::syscall_error::INVALID_ARGUMENT;
+
+    let mut kernel = System::new(MockHal::new());
+    let owner = kernel.register_process("owner");
+    let holder = kernel.register_process("holder");
+    let (_, root) = kernel.create_endpoint(owner).unwrap();
+
+    let ttl = 5_000u64.to_le_bytes();
+    let perms = Permissions::write_only().to_byte() as u32;
+    let args = [root, holder.0 as u32, perms, 0];
+    let (slot, _, _) = kernel.process_syscall(owner, SYS_CAP_GRANT_TTL, args, &ttl);
+    assert!(slot >= 0);
+    let cap = kernel.get_cap_space(holder).unwrap().get(slot as u32).unwrap();
+    assert_ne!(cap.expires_at, 0);
+
+    let (derived, _, _) = kernel.process_syscall(owner, SYS_CAP_DERIVE_TTL, [root, perms, 0, 0], &ttl);
+    assert!(derived >= 0);
+
+    // The lifetime is a required u64 payload and must be non-zero
+    let (missing, _, _) = kernel.process_syscall(owner, SYS_CAP_GRANT_TTL, args, &[]);
+    assert_eq!(missing, INVALID_ARGUMENT as i64);
+    let zero = 0u64.to_le_bytes();
+    let (zero, _, _) = kernel.process_syscall(owner, SYS_CAP_DERIVE_TTL, [root, perms, 0, 0], &zero);
+    assert_eq!(zero, INVALID_ARGUMENT as i64);
+}
+
+#[test]
+fn test_keystore_syscalls_refuse_kernel_keys() {
+    use zos_ipc::syscall_error::PERMISSION_DENIED;
+
+    let mut kernel = System::new(MockHal::new());
+    let pid = kernel.register_process("app");
+    let key = b"/kernel/checkpoint_key";
+
+    for syscall in [0x80, 0x82, 0x84] {
+        let (result, _, _) = kernel.process_syscall(pid, syscall, [0; 4], key);
+        assert_eq!(result, PERMISSION_DENIED as i64);
+    }
+    let (listed, _, _) = kernel.process_syscall(pid, 0x83, [0; 4], b"/kernel/");
+    assert_eq!(listed, PERMISSION_DENIED as i64);
+
+    let mut write = (key.len() as u32).to_le_bytes().to_vec();
+    write.extend_from_slice(key);
+    write.extend_from_slice(&[0; 32]);
+    let (written, _, _) = kernel.process_syscall(pid, 0x81, [0; 4], &write);
+    assert_eq!(written, PERMISSION_DENIED as i64);
+
+    // Other keys still reach the HAL
+    let (other, _, _) = kernel.process_syscall(pid, 0x80, [0; 4], b"/users/1/key");
+    assert_ne!(other, PERMISSION_DENIED as i64);
+}
+
 #[test]
 fn test_delete_works_without_grant_permission() {
     let hal = MockHal::new();
@@ -759,6 +1234,60 @@ fn test_derive_cannot_escalate_permissions() {
     }
 }
 
+#[test]
+fn test_badged_capability_stamps_received_messages() {
+    use zos_ipc::syscall::{SYS_CAP_DERIVE, SYS_RECV, SYS_SEND};
+
+    let hal = MockHal::new();
+    let mut kernel = System::new(hal);
+
+    let server = kernel.register_process("server");
+    let client = kernel.register_process("client");
+    let (_eid, server_slot) = kernel.create_endpoint(server).expect("should create endpoint");
+
+    // Derive a badged send capability through the syscall ABI
+    let badge: u64 = 0x1234_5678_9abc;
+    let (badged_slot, _, _) = kernel.process_syscall(
+        server,
+        SYS_CAP_DERIVE,
+        [server_slot, Permissions::full().to_byte() as u32, 0, 0],
+        &badge.to_le_bytes(),
+    );
+    assert!(badged_slot > 0, "badged derive should succeed");
+    let badged_slot = badged_slot as u32;
+    assert_eq!(kernel.get_cap_space(server).unwrap().get(badged_slot).unwrap().badge, badge);
+
+    // A badged capability cannot be re-badged
+    let (rebadge, _, _) = kernel.process_syscall(
+        server,
+        SYS_CAP_DERIVE,
+        [badged_slot, Permissions::full().to_byte() as u32, 0, 0],
+        &7u64.to_le_bytes(),
+    );
+    assert!(rebadge < 0);
+
+    let client_slot = kernel
+        .grant_capability(server, badged_slot, client, Permissions::write_only())
+        .expect("grant should succeed");
+    assert_eq!(kernel.get_cap_space(client).unwrap().get(client_slot).unwrap().badge, badge);
+
+    let (sent, _, _) = kernel.process_syscall(client, SYS_SEND, [client_slot, 9, 0, 0], b"hi");
+    assert_eq!(sent, 0);
+
+    // [from_pid u32][tag u32][badge u64][num_caps u8][data]
+    let (received, _, data) = kernel.process_syscall(server, SYS_RECV, [server_slot, 0, 0, 0], &[]);
+    assert_eq!(received, 1);
+    assert_eq!(u32::from_le_bytes(data[0..4].try_into().unwrap()), client.0 as u32);
+    assert_eq!(u32::from_le_bytes(data[4..8].try_into().unwrap()), 9);
+    assert_eq!(u64::from_le_bytes(data[8..16].try_into().unwrap()), badge);
+    assert_eq!(data[16], 0);
+    assert_eq!(&data[17..], b"hi");
+
+    // The message was dequeued exactly once
+    let (empty, _, _) = kernel.process_syscall(server, SYS_RECV, [server_slot, 0, 0, 0], &[]);
+    assert_eq!(empty, 0);
+}
+
 // ============================================================================
 // IPC with Capabilities Tests
 // ============================================================================
@@ -853,6 +1382,105 @@ fn test_ipc_has_message() {
     assert_eq!(ep.pending_messages.len(), 1);
 }
 
+// ============================================================================
+// IPC Queue Capacity Tests
+// ============================================================================
+
+#[test]
+fn test_send_to_full_queue_is_rejected_and_counted() {
+    use zos_ipc::syscall_error;
+
+    let mut kernel = System::new(MockHal::new());
+    let server = kernel.register_process("server");
+    let client = kernel.register_process("client");
+    let (ep, ep_slot) = kernel
+        .create_endpoint_with_capacity(server, 2, OverflowPolicy::Reject)
+        .unwrap();
+    let client_slot = kernel
+        .grant_capability(server, ep_slot, client, Permissions::write_only())
+        .unwrap();
+
+    // SYS_SEND = 0x40
+    for _ in 0..2 {
+        let (sent, _, _) = kernel.process_syscall(client, 0x40, [client_slot, 1, 0, 0], b"x");
+        assert_eq!(sent, 0);
+    }
+    let (full, _, _) = kernel.process_syscall(client, 0x40, [client_slot, 1, 0, 0], b"x");
+    assert_eq!(full, syscall_error::QUEUE_FULL as i64);
+
+    let detail = kernel.get_endpoint_detail(ep).unwrap();
+    assert_eq!(detail.queue_depth, 2);
+    assert_eq!(detail.metrics.dropped_messages, 1);
+    assert_eq!(detail.metrics.total_messages, 2);
+}
+
+#[test]
+fn test_send_to_full_blocking_queue_would_block() {
+    use zos_ipc::syscall_error;
+
+    let mut kernel = System::new(MockHal::new());
+    let server = kernel.register_process("server");
+    let client = kernel.register_process("client");
+    let (ep, ep_slot) = kernel
+        .create_endpoint_with_capacity(server, 1, OverflowPolicy::Block)
+        .unwrap();
+    let client_slot = kernel
+        .grant_capability(server, ep_slot, client, Permissions::write_only())
+        .unwrap();
+
+    kernel.ipc_send(client, client_slot, 1, Vec::new()).unwrap();
+    let (blocked, _, _) = kernel.process_syscall(client, 0x40, [client_slot, 2, 0, 0], &[]);
+    assert_eq!(blocked, syscall_error::WOULD_BLOCK as i64);
+    let detail = kernel.get_endpoint_detail(ep).unwrap();
+    assert_eq!(detail.metrics.dropped_messages, 0);
+
+    // Once the server drains the queue the retry goes through
+    kernel.ipc_receive(server, ep_slot).unwrap().unwrap();
+    let (retried, _, _) = kernel.process_syscall(client, 0x40, [client_slot, 2, 0, 0], &[]);
+    assert_eq!(retried, 0);
+}
+
+#[test]
+fn test_send_with_caps_to_full_queue_keeps_caps() {
+    let mut kernel = System::new(MockHal::new());
+    let server = kernel.register_process("server");
+    let client = kernel.register_process("client");
+    let (_, ep_slot) = kernel
+        .create_endpoint_with_capacity(server, 1, OverflowPolicy::Reject)
+        .unwrap();
+    let client_slot = kernel
+        .grant_capability(server, ep_slot, client, Permissions::write_only())
+        .unwrap();
+    let (_, own_slot) = kernel.create_endpoint(client).unwrap();
+
+    kernel.ipc_send(client, client_slot, 1, Vec::new()).unwrap();
+    let result = kernel.ipc_send_with_caps(client, client_slot, 2, Vec::new(), &[own_slot]);
+    assert_eq!(result, Err(zos_kernel::KernelError::QueueFull));
+    assert!(kernel.get_cap_space(client).unwrap().get(own_slot).is_some());
+}
+
+#[test]
+fn test_kernel_notifications_use_queue_reserve() {
+    let mut kernel = System::new(MockHal::new());
+    let init = kernel.register_process("init");
+    let (ep, ep_slot) = kernel
+        .create_endpoint_with_capacity(init, 1, OverflowPolicy::Reject)
+        .unwrap();
+    let child = kernel.register_process_with_parent("child", init);
+    let init_slot = kernel
+        .grant_capability(init, ep_slot, child, Permissions::write_only())
+        .unwrap();
+
+    kernel.ipc_send(child, init_slot, 1, Vec::new()).unwrap();
+    kernel.kill_process(child);
+
+    // The child's death still reaches its parent past the capacity
+    let endpoint = kernel.get_endpoint(ep).unwrap();
+    assert_eq!(endpoint.pending_messages.len(), 2);
+    let notice = &endpoint.pending_messages[1];
+    assert_eq!(notice.tag, zos_ipc::kernel::MSG_CHILD_EXITED);
+}
+
 // ============================================================================
 // System Tests - fault_process, syscall dispatch
 // ============================================================================
@@ -969,6 +1597,30 @@ fn test_syscall_dispatch_create_endpoint() {
     assert_eq!(endpoints.len(), 1);
 }
 
+#[test]
+fn test_syscall_dispatch_create_endpoint_with_capacity() {
+    use zos_ipc::syscall::ENDPOINT_BLOCK_WHEN_FULL;
+    use zos_ipc::syscall_error;
+
+    let mut kernel = System::new(MockHal::new());
+    let pid = kernel.register_process("test");
+
+    // arg1 = capacity, arg2 = overflow flags
+    let (invalid, _, _) = kernel.process_syscall(pid, 0x35, [2048, 0, 0, 0], &[]);
+    assert_eq!(invalid, syscall_error::INVALID_ARGUMENT as i64);
+
+    let (id, _, _) = kernel.process_syscall(pid, 0x35, [8, ENDPOINT_BLOCK_WHEN_FULL, 0, 0], &[]);
+    let endpoint = kernel.get_endpoint(EndpointId(id as u64)).unwrap();
+    assert_eq!(endpoint.capacity, 8);
+    assert_eq!(endpoint.policy, OverflowPolicy::Block);
+
+    // No capacity means the default one
+    let (id, _, _) = kernel.process_syscall(pid, 0x35, [0, 0, 0, 0], &[]);
+    let endpoint = kernel.get_endpoint(EndpointId(id as u64)).unwrap();
+    assert_eq!(endpoint.capacity, DEFAULT_QUEUE_CAPACITY);
+    assert_eq!(endpoint.policy, OverflowPolicy::Reject);
+}
+
 #[test]
 fn test_syscall_dispatch_ipc_send() {
     let hal = MockHal::new();
@@ -1004,6 +1656,715 @@ fn test_syscall_dispatch_ipc_send() {
     assert_eq!(ep.pending_messages.len(), 1);
 }
 
+/// Payload for SYS_RECV_TIMEOUT: timeout then the slots to wait on
+fn recv_timeout_payload(timeout_ns: u64, slots: &[u32]) -> Vec<u8> {
+    let mut data = timeout_ns.to_le_bytes().to_vec();
+    for slot in slots {
+        data.extend_from_slice(&slot.to_le_bytes());
+    }
+    data
+}
+
+#[test]
+fn test_recv_timeout_parks_until_send() {
+    use zos_ipc::syscall::{SYS_RECV_TIMEOUT, SYS_SEND};
+
+    let mut kernel = System::new(MockHal::new());
+    let sender = kernel.register_process("sender");
+    let receiver = kernel.register_process("receiver");
+    let (_, first) = kernel.create_endpoint(receiver).unwrap();
+    let (_, second) = kernel.create_endpoint(receiver).unwrap();
+    let sender_slot = kernel
+        .grant_capability(receiver, second, sender, Permissions::write_only())
+        .unwrap();
+
+    let payload = recv_timeout_payload(0, &[first, second]);
+    let (_, rich, _) = kernel.process_syscall(receiver, SYS_RECV_TIMEOUT, [0; 4], &payload);
+    assert!(matches!(rich, zos_kernel::SyscallResult::WouldBlock));
+    assert!(kernel.is_parked(receiver));
+    assert_eq!(kernel.get_process(receiver).unwrap().state, ProcessState::Blocked);
+    assert!(kernel.resume_parked().is_empty());
+
+    kernel.process_syscall(sender, SYS_SEND, [sender_slot, 7, 0, 0], b"hi");
+    let completed = kernel.resume_parked();
+    assert_eq!(completed.len(), 1);
+    let (pid, result, data) = &completed[0];
+    assert_eq!(*pid, receiver);
+    assert_eq!(*result, 1);
+    assert_eq!(&data[0..4], &second.to_le_bytes());
+    assert_eq!(&data[8..12], &7u32.to_le_bytes());
+    assert_eq!(&data[21..], b"hi");
+    assert!(!kernel.is_parked(receiver));
+    assert_eq!(kernel.get_process(receiver).unwrap().state, ProcessState::Running);
+}
+
+#[test]
+fn test_recv_timeout_expires() {
+    use zos_ipc::syscall::SYS_RECV_TIMEOUT;
+    use zos_ipc::syscall_error::TIMED_OUT;
+
+    let mut kernel = System::new(MockHal::new());
+    let receiver = kernel.register_process("receiver");
+    let (_, slot) = kernel.create_endpoint(receiver).unwrap();
+
+    let payload = recv_timeout_payload(5_000, &[slot]);
+    kernel.process_syscall(receiver, SYS_RECV_TIMEOUT, [0; 4], &payload);
+    let deadline = kernel.next_deadline().expect("parked wait should have a deadline");
+    assert!(kernel.resume_parked().is_empty());
+
+    kernel.hal().time.store(deadline, Ordering::SeqCst);
+    let completed = kernel.resume_parked();
+    assert_eq!(completed, vec![(receiver, TIMED_OUT as i64, Vec::new())]);
+    assert_eq!(kernel.next_deadline(), None);
+}
+
+#[test]
+fn test_call_timeout_waits_for_reply() {
+    use zos_ipc::syscall::{SYS_CALL_TIMEOUT, SYS_SEND};
+
+    let mut kernel = System::new(MockHal::new());
+    let client = kernel.register_process("client");
+    let server = kernel.register_process("server");
+    let (_, server_slot) = kernel.create_endpoint(server).unwrap();
+    let (_, reply_slot) = kernel.create_endpoint(client).unwrap();
+    let to_server = kernel
+        .grant_capability(server, server_slot, client, Permissions::write_only())
+        .unwrap();
+    let to_client = kernel
+        .grant_capability(client, reply_slot, server, Permissions::write_only())
+        .unwrap();
+
+    let mut payload = 0u64.to_le_bytes().to_vec();
+    payload.extend_from_slice(b"ping");
+    let args = [to_server, 1, reply_slot, 0];
+    kernel.process_syscall(client, SYS_CALL_TIMEOUT, args, &payload);
+    assert!(kernel.is_parked(client));
+    let request = kernel.ipc_receive(server, server_slot).unwrap().unwrap();
+    assert_eq!(request.data, b"ping");
+
+    kernel.process_syscall(server, SYS_SEND, [to_client, 2, 0, 0], b"pong");
+    let completed = kernel.resume_parked();
+    assert_eq!(completed.len(), 1);
+    let (_, result, data) = &completed[0];
+    assert_eq!(*result, 1);
+    assert_eq!(&data[4..8], &2u32.to_le_bytes());
+    assert_eq!(&data[17..], b"pong");
+}
+
+#[test]
+fn test_killed_parked_process_is_dropped() {
+    use zos_ipc::syscall::SYS_RECV_TIMEOUT;
+
+    let mut kernel = System::new(MockHal::new());
+    let receiver = kernel.register_process("receiver");
+    let (_, slot) = kernel.create_endpoint(receiver).unwrap();
+
+    let payload = recv_timeout_payload(5_000, &[slot]);
+    kernel.process_syscall(receiver, SYS_RECV_TIMEOUT, [0; 4], &payload);
+    kernel.kill_process(receiver);
+
+    kernel.hal().time.fetch_add(10_000, Ordering::SeqCst);
+    assert!(kernel.resume_parked().is_empty());
+    assert!(!kernel.is_parked(receiver));
+}
+
+/// Slot of the reply capability `holder` has for `caller`, if any
+fn reply_cap_slot(kernel: &System<MockHal>, holder: ProcessId, caller: ProcessId) -> Option<u32> {
+    kernel
+        .get_cap_space(holder)?
+        .slots
+        .iter()
+        .find(|(_, cap)| cap.object_type == ObjectType::Reply && cap.object_id == caller.0)
+        .map(|(&slot, _)| slot)
+}
+
+#[test]
+fn test_call_reply_consumes_reply_capability() {
+    use zos_axiom::{replay_and_verify, Replayable};
+    use zos_ipc::syscall::{SYS_CALL, SYS_RECV, SYS_REPLY};
+
+    let mut kernel = System::new(MockHal::new());
+    let server = kernel.register_process("server");
+    let client = kernel.register_process("client");
+    let spoofer = kernel.register_process("spoofer");
+    let (_, server_slot) = kernel.create_endpoint(server).unwrap();
+    let to_server = kernel
+        .grant_capability(server, server_slot, client, Permissions::write_only())
+        .unwrap();
+
+    let (_, rich, _) = kernel.process_syscall(client, SYS_CALL, [to_server, 1, 4, 0], b"ping");
+    assert!(matches!(rich, zos_kernel::SyscallResult::WouldBlock));
+    assert!(kernel.is_parked(client));
+    assert!(reply_cap_slot(&kernel, server, client).is_some());
+
+    let (received, _, request) =
+        kernel.process_syscall(server, SYS_RECV, [server_slot, 0, 0, 0], &[]);
+    assert_eq!(received, 1);
+    assert_eq!(&request[17..], b"ping");
+
+    // Without the reply capability nobody else can answer
+    let client_pid = client.0 as u32;
+    let (spoofed, _, _) = kernel.process_syscall(spoofer, SYS_REPLY, [client_pid, 2, 0, 0], b"x");
+    assert!(spoofed < 0);
+    assert!(kernel.resume_parked().is_empty());
+
+    let (replied, _, _) = kernel.process_syscall(server, SYS_REPLY, [client_pid, 2, 4, 0], b"pong");
+    assert_eq!(replied, 0);
+    assert_eq!(reply_cap_slot(&kernel, server, client), None);
+
+    let completed = kernel.resume_parked();
+    assert_eq!(completed.len(), 1);
+    let (pid, result, data) = &completed[0];
+    assert_eq!(*pid, client);
+    assert_eq!(*result, 1);
+    assert_eq!(u32::from_le_bytes(data[0..4].try_into().unwrap()), server.0 as u32);
+    assert_eq!(&data[4..8], &2u32.to_le_bytes());
+    assert_eq!(&data[17..], b"pong");
+    assert!(!kernel.is_parked(client));
+
+    // The capability answered exactly one call
+    let (again, _, _) = kernel.process_syscall(server, SYS_REPLY, [client_pid, 2, 0, 0], b"x");
+    assert!(again < 0);
+
+    // Minting and consuming the reply capability replays to the same state
+    let mut replayed: System<MockHal> = System::new_for_replay();
+    replay_and_verify(&mut replayed, kernel.commitlog().commits(), kernel.state_hash()).unwrap();
+}
+
+#[test]
+fn test_call_aborted_when_server_dies() {
+    use zos_axiom::CommitType;
+    use zos_ipc::syscall::SYS_CALL;
+
+    let mut kernel = System::new(MockHal::new());
+    let server = kernel.register_process("server");
+    let client = kernel.register_process("client");
+    let (_, server_slot) = kernel.create_endpoint(server).unwrap();
+    let to_server = kernel
+        .grant_capability(server, server_slot, client, Permissions::write_only())
+        .unwrap();
+
+    kernel.process_syscall(client, SYS_CALL, [to_server, 1, 0, 0], &[]);
+    assert!(kernel.is_parked(client));
+
+    kernel.kill_process(server);
+    let completed = kernel.resume_parked();
+    assert_eq!(completed, vec![(client, -1, Vec::new())]);
+    assert!(kernel.commitlog().commits().iter().any(|c| matches!(
+        c.commit_type,
+        CommitType::CallAborted { caller } if caller == client.0
+    )));
+}
+
+#[test]
+fn test_killed_caller_drops_reply_capability() {
+    use zos_ipc::syscall::{SYS_CALL, SYS_REPLY};
+
+    let mut kernel = System::new(MockHal::new());
+    let server = kernel.register_process("server");
+    let client = kernel.register_process("client");
+    let (_, server_slot) = kernel.create_endpoint(server).unwrap();
+    let to_server = kernel
+        .grant_capability(server, server_slot, client, Permissions::write_only())
+        .unwrap();
+
+    kernel.process_syscall(client, SYS_CALL, [to_server, 1, 0, 0], &[]);
+    assert!(reply_cap_slot(&kernel, server, client).is_some());
+
+    kernel.kill_process(client);
+    assert_eq!(reply_cap_slot(&kernel, server, client), None);
+    let (replied, _, _) =
+        kernel.process_syscall(server, SYS_REPLY, [client.0 as u32, 2, 0, 0], &[]);
+    assert!(replied < 0);
+}
+
+fn notification_bits(data: &[u8]) -> u64 {
+    u64::from_le_bytes(data.try_into().expect("bits should be a u64"))
+}
+
+#[test]
+fn test_notification_signals_coalesce() {
+    use zos_ipc::syscall::{SYS_NOTIFY_CREATE, SYS_NOTIFY_POLL, SYS_NOTIFY_SIGNAL};
+    use zos_ipc::syscall_error::INVALID_ARGUMENT;
+
+    let mut kernel = System::new(MockHal::new());
+    let waiter = kernel.register_process("waiter");
+    let signaller = kernel.register_process("signaller");
+
+    let (packed, _, _) = kernel.process_syscall(waiter, SYS_NOTIFY_CREATE, [0; 4], &[]);
+    assert!(packed > 0, "create should succeed");
+    let slot = (packed >> 32) as u32;
+    let signal_slot = kernel
+        .grant_capability(waiter, slot, signaller, Permissions::write_only())
+        .unwrap();
+
+    let (zero, _, _) =
+        kernel.process_syscall(signaller, SYS_NOTIFY_SIGNAL, [signal_slot, 0, 0, 0], &[]);
+    assert_eq!(zero, INVALID_ARGUMENT as i64);
+
+    // Two signals before a take coalesce into one word
+    kernel.process_syscall(signaller, SYS_NOTIFY_SIGNAL, [signal_slot, 0b01, 0, 0], &[]);
+    kernel.process_syscall(signaller, SYS_NOTIFY_SIGNAL, [signal_slot, 0, 1, 0], &[]);
+    let (result, _, data) = kernel.process_syscall(waiter, SYS_NOTIFY_POLL, [slot, 0, 0, 0], &[]);
+    assert_eq!(result, 0);
+    assert_eq!(notification_bits(&data), (1 << 32) | 0b01);
+
+    let (_, _, data) = kernel.process_syscall(waiter, SYS_NOTIFY_POLL, [slot, 0, 0, 0], &[]);
+    assert_eq!(notification_bits(&data), 0);
+
+    // A write-only capability cannot take the word
+    let (denied, _, _) =
+        kernel.process_syscall(signaller, SYS_NOTIFY_POLL, [signal_slot, 0, 0, 0], &[]);
+    assert!(denied < 0);
+}
+
+#[test]
+fn test_notification_wait_parks_until_signal() {
+    use zos_axiom::CommitType;
+    use zos_ipc::syscall::{SYS_NOTIFY_CREATE, SYS_NOTIFY_SIGNAL, SYS_NOTIFY_WAIT};
+
+    let mut kernel = System::new(MockHal::new());
+    let waiter = kernel.register_process("waiter");
+    let (packed, _, _) = kernel.process_syscall(waiter, SYS_NOTIFY_CREATE, [0; 4], &[]);
+    let slot = (packed >> 32) as u32;
+
+    let (_, rich, _) = kernel.process_syscall(waiter, SYS_NOTIFY_WAIT, [slot, 0, 0, 0], &[]);
+    assert!(matches!(rich, zos_kernel::SyscallResult::WouldBlock));
+    assert!(kernel.is_parked(waiter));
+    assert!(kernel.resume_parked().is_empty());
+
+    kernel.process_syscall(waiter, SYS_NOTIFY_SIGNAL, [slot, 0b100, 0, 0], &[]);
+    let completed = kernel.resume_parked();
+    assert_eq!(completed.len(), 1);
+    let (pid, result, data) = &completed[0];
+    assert_eq!(*pid, waiter);
+    assert_eq!(*result, 0);
+    assert_eq!(notification_bits(data), 0b100);
+    assert!(!kernel.is_parked(waiter));
+
+    // Taking the word is recorded so replay clears it too
+    assert!(kernel.commitlog().commits().iter().any(|c| matches!(
+        c.commit_type,
+        CommitType::NotificationTaken { bits: 0b100, .. }
+    )));
+}
+
+#[test]
+fn test_notification_wait_times_out() {
+    use zos_ipc::syscall::{SYS_NOTIFY_CREATE, SYS_NOTIFY_WAIT};
+    use zos_ipc::syscall_error::TIMED_OUT;
+
+    let mut kernel = System::new(MockHal::new());
+    let waiter = kernel.register_process("waiter");
+    let (packed, _, _) = kernel.process_syscall(waiter, SYS_NOTIFY_CREATE, [0; 4], &[]);
+    let slot = (packed >> 32) as u32;
+
+    kernel.process_syscall(waiter, SYS_NOTIFY_WAIT, [slot, 5_000, 0, 0], &[]);
+    let deadline = kernel.next_deadline().expect("parked wait should have a deadline");
+    kernel.hal().time.store(deadline, Ordering::SeqCst);
+    let completed = kernel.resume_parked();
+    assert_eq!(completed, vec![(waiter, TIMED_OUT as i64, Vec::new())]);
+}
+
+// ============================================================================
+// Device Tests (IRQs and I/O ports)
+// ============================================================================
+
+/// Mint a device capability as Init and grant it to `driver`.
+fn grant_device_cap(
+    kernel: &mut System<MockHal>,
+    init: ProcessId,
+    driver: ProcessId,
+    syscall: u32,
+    args: [u32; 4],
+    perms: Permissions,
+) -> u32 {
+    let (slot, _, _) = kernel.process_syscall(init, syscall, args, &[]);
+    assert!(slot >= 0, "mint should succeed");
+    kernel
+        .grant_capability(init, slot as u32, driver, perms)
+        .expect("grant should succeed")
+}
+
+fn irq_line(kernel: &System<MockHal>, irq: u8) -> Option<bool> {
+    kernel.hal().irq_lines.borrow().get(&irq).copied()
+}
+
+#[test]
+fn test_device_caps_init_only() {
+    use zos_ipc::syscall::{SYS_IO_PORT_CAP_CREATE, SYS_IRQ_CAP_CREATE};
+    use zos_ipc::syscall_error::{INVALID_ARGUMENT, PERMISSION_DENIED};
+
+    let mut kernel = System::new(MockHal::new());
+    let init = kernel.register_process("init");
+    let driver = kernel.register_process("driver");
+
+    let (denied, _, _) = kernel.process_syscall(driver, SYS_IRQ_CAP_CREATE, [5, 0, 0, 0], &[]);
+    assert_eq!(denied, PERMISSION_DENIED as i64);
+    let (denied, _, _) =
+        kernel.process_syscall(driver, SYS_IO_PORT_CAP_CREATE, [0x60, 1, 0, 0], &[]);
+    assert_eq!(denied, PERMISSION_DENIED as i64);
+
+    // Empty ranges and ranges past the 16-bit port space are refused
+    let (empty, _, _) = kernel.process_syscall(init, SYS_IO_PORT_CAP_CREATE, [0x60, 0, 0, 0], &[]);
+    assert_eq!(empty, INVALID_ARGUMENT as i64);
+    let (wraps, _, _) =
+        kernel.process_syscall(init, SYS_IO_PORT_CAP_CREATE, [0xFFFF, 2, 0, 0], &[]);
+    assert_eq!(wraps, INVALID_ARGUMENT as i64);
+
+    let (slot, _, _) = kernel.process_syscall(init, SYS_IRQ_CAP_CREATE, [5, 0, 0, 0], &[]);
+    assert!(slot >= 0);
+    let cap = kernel.get_cap_space(init).unwrap().get(slot as u32).unwrap();
+    assert_eq!(cap.object_type, ObjectType::Irq);
+    assert_eq!(cap.object_id, 5);
+}
+
+#[test]
+fn test_irq_notification_masked_until_ack() {
+    use zos_ipc::syscall::{
+        SYS_IRQ_ACK, SYS_IRQ_BIND, SYS_IRQ_CAP_CREATE, SYS_NOTIFY_CREATE, SYS_NOTIFY_POLL,
+    };
+    use zos_ipc::syscall_error::INVALID_ARGUMENT;
+
+    let mut kernel = System::new(MockHal::new());
+    let init = kernel.register_process("init");
+    let driver = kernel.register_process("driver");
+    let irq_slot = grant_device_cap(
+        &mut kernel,
+        init,
+        driver,
+        SYS_IRQ_CAP_CREATE,
+        [5, 0, 0, 0],
+        Permissions::read_only(),
+    );
+    let (packed, _, _) = kernel.process_syscall(driver, SYS_NOTIFY_CREATE, [0; 4], &[]);
+    let ntfn_slot = (packed >> 32) as u32;
+
+    let (zero_bits, _, _) = kernel.process_syscall(
+        driver,
+        SYS_IRQ_BIND,
+        [irq_slot, ntfn_slot, 0, 0],
+        &0u64.to_le_bytes(),
+    );
+    assert_eq!(zero_bits, INVALID_ARGUMENT as i64);
+
+    let (bound, _, _) = kernel.process_syscall(
+        driver,
+        SYS_IRQ_BIND,
+        [irq_slot, ntfn_slot, 0, 0],
+        &0b10u64.to_le_bytes(),
+    );
+    assert_eq!(bound, 0);
+    assert_eq!(irq_line(&kernel, 5), Some(true));
+
+    kernel.interrupt(5);
+    let (_, _, data) = kernel.process_syscall(driver, SYS_NOTIFY_POLL, [ntfn_slot, 0, 0, 0], &[]);
+    assert_eq!(notification_bits(&data), 0b10);
+
+    // The line stays masked until the driver acknowledges it
+    kernel.interrupt(5);
+    let (_, _, data) = kernel.process_syscall(driver, SYS_NOTIFY_POLL, [ntfn_slot, 0, 0, 0], &[]);
+    assert_eq!(notification_bits(&data), 0);
+
+    let (acked, _, _) = kernel.process_syscall(driver, SYS_IRQ_ACK, [irq_slot, 0, 0, 0], &[]);
+    assert_eq!(acked, 0);
+    kernel.interrupt(5);
+    let (_, _, data) = kernel.process_syscall(driver, SYS_NOTIFY_POLL, [ntfn_slot, 0, 0, 0], &[]);
+    assert_eq!(notification_bits(&data), 0b10);
+
+    // Unbound lines are ignored
+    kernel.interrupt(6);
+}
+
+#[test]
+fn test_irq_endpoint_receives_msg_irq() {
+    use zos_axiom::{replay_and_verify, Replayable};
+    use zos_ipc::kernel::MSG_IRQ;
+    use zos_ipc::syscall::{SYS_IRQ_BIND, SYS_IRQ_CAP_CREATE, SYS_RECV};
+
+    let mut kernel = System::new(MockHal::new());
+    let init = kernel.register_process("init");
+    let driver = kernel.register_process("driver");
+    let irq_slot = grant_device_cap(
+        &mut kernel,
+        init,
+        driver,
+        SYS_IRQ_CAP_CREATE,
+        [7, 0, 0, 0],
+        Permissions::read_only(),
+    );
+    let (_, ep_slot) = kernel.create_endpoint(driver).unwrap();
+
+    let (bound, _, _) =
+        kernel.process_syscall(driver, SYS_IRQ_BIND, [irq_slot, ep_slot, 0, 0], &[]);
+    assert_eq!(bound, 0);
+    kernel.interrupt(7);
+
+    // [from_pid u32][tag u32][badge u64][num_caps u8][data]
+    let (received, _, data) = kernel.process_syscall(driver, SYS_RECV, [ep_slot, 0, 0, 0], &[]);
+    assert_eq!(received, 1);
+    assert_eq!(u32::from_le_bytes(data[0..4].try_into().unwrap()), 0);
+    assert_eq!(u32::from_le_bytes(data[4..8].try_into().unwrap()), MSG_IRQ);
+    assert_eq!(&data[17..], &[7]);
+
+    // Binding and delivery replay to the same state
+    let mut replayed: System<MockHal> = System::new_for_replay();
+    replay_and_verify(&mut replayed, kernel.commitlog().commits(), kernel.state_hash()).unwrap();
+}
+
+#[test]
+fn test_irq_unbound_on_kill_and_cap_delete() {
+    use zos_ipc::syscall::{SYS_IRQ_BIND, SYS_IRQ_CAP_CREATE};
+    use zos_ipc::syscall_error::INVALID_ARGUMENT;
+
+    let mut kernel = System::new(MockHal::new());
+    let init = kernel.register_process("init");
+    let driver = kernel.register_process("driver");
+    let other = kernel.register_process("other");
+    let irq_slot = grant_device_cap(
+        &mut kernel,
+        init,
+        driver,
+        SYS_IRQ_CAP_CREATE,
+        [3, 0, 0, 0],
+        Permissions::read_only(),
+    );
+    let (_, ep_slot) = kernel.create_endpoint(driver).unwrap();
+    kernel.process_syscall(driver, SYS_IRQ_BIND, [irq_slot, ep_slot, 0, 0], &[]);
+
+    // A second driver cannot take a bound line
+    let other_slot = grant_device_cap(
+        &mut kernel,
+        init,
+        other,
+        SYS_IRQ_CAP_CREATE,
+        [3, 0, 0, 0],
+        Permissions::read_only(),
+    );
+    let (_, other_ep) = kernel.create_endpoint(other).unwrap();
+    let (taken, _, _) =
+        kernel.process_syscall(other, SYS_IRQ_BIND, [other_slot, other_ep, 0, 0], &[]);
+    assert_eq!(taken, INVALID_ARGUMENT as i64);
+
+    kernel.delete_capability(driver, irq_slot).unwrap();
+    assert_eq!(irq_line(&kernel, 3), Some(false));
+
+    let (bound, _, _) =
+        kernel.process_syscall(other, SYS_IRQ_BIND, [other_slot, other_ep, 0, 0], &[]);
+    assert_eq!(bound, 0);
+    kernel.kill_process(other);
+    assert_eq!(irq_line(&kernel, 3), Some(false));
+}
+
+#[test]
+fn test_io_port_range_and_rights() {
+    use zos_ipc::syscall::{SYS_IO_IN, SYS_IO_OUT, SYS_IO_PORT_CAP_CREATE};
+    use zos_ipc::syscall_error::{INVALID_ARGUMENT, PERMISSION_DENIED};
+
+    let mut kernel = System::new(MockHal::new());
+    let init = kernel.register_process("init");
+    let driver = kernel.register_process("driver");
+    let rw_slot = grant_device_cap(
+        &mut kernel,
+        init,
+        driver,
+        SYS_IO_PORT_CAP_CREATE,
+        [0x60, 5, 0, 0],
+        Permissions::full(),
+    );
+    let ro_slot = grant_device_cap(
+        &mut kernel,
+        init,
+        driver,
+        SYS_IO_PORT_CAP_CREATE,
+        [0x60, 5, 0, 0],
+        Permissions::read_only(),
+    );
+
+    let (wrote, _, _) =
+        kernel.process_syscall(driver, SYS_IO_OUT, [rw_slot, (1 << 16) | 0x64, 0xAE, 0], &[]);
+    assert_eq!(wrote, 0);
+    let (value, _, _) = kernel.process_syscall(driver, SYS_IO_IN, [ro_slot, 0x64, 1, 0], &[]);
+    assert_eq!(value, 0xAE);
+
+    // Outside the range, without write rights, or with a bad width
+    let (outside, _, _) = kernel.process_syscall(driver, SYS_IO_IN, [rw_slot, 0x65, 1, 0], &[]);
+    assert_eq!(outside, PERMISSION_DENIED as i64);
+    let (wide, _, _) = kernel.process_syscall(driver, SYS_IO_IN, [rw_slot, 0x63, 4, 0], &[]);
+    assert_eq!(wide, PERMISSION_DENIED as i64);
+    let (read_only, _, _) =
+        kernel.process_syscall(driver, SYS_IO_OUT, [ro_slot, (1 << 16) | 0x60, 1, 0], &[]);
+    assert_eq!(read_only, PERMISSION_DENIED as i64);
+    let (width, _, _) = kernel.process_syscall(driver, SYS_IO_IN, [rw_slot, 0x60, 3, 0], &[]);
+    assert_eq!(width, INVALID_ARGUMENT as i64);
+}
+
+// ============================================================================
+// Shared Region Tests
+// ============================================================================
+
+/// Copy `bytes` into a process's linear memory at `addr`.
+fn poke(kernel: &System<MockHal>, pid: ProcessId, addr: usize, bytes: &[u8]) {
+    kernel.hal().memory(pid.0)[addr..addr + bytes.len()].copy_from_slice(bytes);
+}
+
+/// Read `len` bytes of a process's linear memory at `addr`.
+fn peek(kernel: &System<MockHal>, pid: ProcessId, addr: usize, len: usize) -> Vec<u8> {
+    kernel.hal().memory(pid.0)[addr..addr + len].to_vec()
+}
+
+#[test]
+fn test_region_create_limits() {
+    use zos_ipc::syscall::SYS_REGION_CREATE;
+    use zos_ipc::syscall_error::INVALID_ARGUMENT;
+    use zos_kernel::{RegionId, MAX_REGION_SIZE};
+
+    let mut kernel = System::new(MockHal::new());
+    let pid = kernel.register_process("writer");
+
+    let (empty, _, _) = kernel.process_syscall(pid, SYS_REGION_CREATE, [0; 4], &[]);
+    assert_eq!(empty, INVALID_ARGUMENT as i64);
+    let too_big = MAX_REGION_SIZE as u32 + 1;
+    let (big, _, _) = kernel.process_syscall(pid, SYS_REGION_CREATE, [too_big, 0, 0, 0], &[]);
+    assert_eq!(big, INVALID_ARGUMENT as i64);
+
+    // Sizes round up to whole pages
+    let (slot, _, _) = kernel.process_syscall(pid, SYS_REGION_CREATE, [5000, 0, 0, 0], &[]);
+    assert!(slot >= 0);
+    let cap = kernel.get_cap_space(pid).unwrap().get(slot as u32).unwrap();
+    assert_eq!(cap.object_type, ObjectType::Memory);
+    let region = kernel.get_region(RegionId(cap.object_id)).unwrap();
+    assert_eq!(region.size, 8192);
+    assert_eq!(region.owner, pid);
+}
+
+#[test]
+fn test_region_read_only_grant_sees_flushed_data() {
+    use zos_axiom::{replay_and_verify, Replayable};
+    use zos_ipc::syscall::{
+        REGION_MAP_WRITABLE as WRITABLE, SYS_REGION_FLUSH, SYS_REGION_MAP, SYS_REGION_REFRESH,
+        SYS_REGION_UNMAP,
+    };
+    use zos_ipc::syscall_error::{INVALID_ARGUMENT, PERMISSION_DENIED};
+
+    let mut kernel = System::new(MockHal::new());
+    let writer = kernel.register_process("writer");
+    let reader = kernel.register_process("reader");
+    let (rw_slot, _) = kernel.create_region(writer, 4096).unwrap();
+    let ro_slot = kernel
+        .grant_capability(writer, rw_slot, reader, Permissions::read_only())
+        .unwrap();
+
+    let (size, _, _) = kernel.process_syscall(
+        writer,
+        SYS_REGION_MAP,
+        [rw_slot, 0x1000, WRITABLE | 4096, 0],
+        &[],
+    );
+    assert_eq!(size, 4096);
+    poke(&kernel, writer, 0x1000, b"file body");
+    let (flushed, _, _) = kernel.process_syscall(writer, SYS_REGION_FLUSH, [rw_slot, 0, 9, 0], &[]);
+    assert_eq!(flushed, 0);
+
+    // A read-only capability maps read-only and cannot publish
+    let (denied, _, _) = kernel.process_syscall(
+        reader,
+        SYS_REGION_MAP,
+        [ro_slot, 0x8000, WRITABLE | 4096, 0],
+        &[],
+    );
+    assert_eq!(denied, PERMISSION_DENIED as i64);
+    let (short, _, _) =
+        kernel.process_syscall(reader, SYS_REGION_MAP, [ro_slot, 0x8000, 100, 0], &[]);
+    assert_eq!(short, INVALID_ARGUMENT as i64);
+    let (size, _, _) =
+        kernel.process_syscall(reader, SYS_REGION_MAP, [ro_slot, 0x8000, 4096, 0], &[]);
+    assert_eq!(size, 4096);
+    assert_eq!(peek(&kernel, reader, 0x8000, 9), b"file body");
+    let (denied, _, _) = kernel.process_syscall(reader, SYS_REGION_FLUSH, [ro_slot, 0, 9, 0], &[]);
+    assert_eq!(denied, PERMISSION_DENIED as i64);
+
+    // Later writes show up on refresh; ranges past the end are refused
+    poke(&kernel, writer, 0x1000, b"FILE");
+    kernel.process_syscall(writer, SYS_REGION_FLUSH, [rw_slot, 0, 4, 0], &[]);
+    assert_eq!(peek(&kernel, reader, 0x8000, 4), b"file");
+    let (refreshed, _, _) =
+        kernel.process_syscall(reader, SYS_REGION_REFRESH, [ro_slot, 0, 4, 0], &[]);
+    assert_eq!(refreshed, 0);
+    assert_eq!(peek(&kernel, reader, 0x8000, 9), b"FILE body");
+    let (past_end, _, _) =
+        kernel.process_syscall(reader, SYS_REGION_REFRESH, [ro_slot, 4090, 8, 0], &[]);
+    assert_eq!(past_end, INVALID_ARGUMENT as i64);
+
+    let (unmapped, _, _) =
+        kernel.process_syscall(reader, SYS_REGION_UNMAP, [ro_slot, 0, 0, 0], &[]);
+    assert_eq!(unmapped, 0);
+    let (not_mapped, _, _) =
+        kernel.process_syscall(reader, SYS_REGION_REFRESH, [ro_slot, 0, 4, 0], &[]);
+    assert_eq!(not_mapped, INVALID_ARGUMENT as i64);
+
+    // Regions and mappings replay to the same state
+    let mut replayed: System<MockHal> = System::new_for_replay();
+    replay_and_verify(
+        &mut replayed,
+        kernel.commitlog().commits(),
+        kernel.state_hash(),
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_region_freed_when_last_cap_goes() {
+    use zos_ipc::syscall::{REGION_MAP_WRITABLE as WRITABLE, SYS_REGION_MAP};
+
+    let mut kernel = System::new(MockHal::new());
+    let client = kernel.register_process("client");
+    let service = kernel.register_process("vfs");
+    let (_, ep_slot) = kernel.create_endpoint(service).unwrap();
+    let client_ep = kernel
+        .grant_capability(service, ep_slot, client, Permissions::write_only())
+        .unwrap();
+    let (slot, id) = kernel.create_region(client, 4096).unwrap();
+    kernel.process_syscall(
+        client,
+        SYS_REGION_MAP,
+        [slot, 0x1000, WRITABLE | 4096, 0],
+        &[],
+    );
+
+    // Sending the only capability unmaps the sender but keeps the region
+    // alive while the message is queued
+    kernel
+        .ipc_send_with_caps(client, client_ep, 1, Vec::new(), &[slot])
+        .unwrap();
+    let region = kernel.get_region(id).unwrap();
+    assert!(region.mappings.is_empty());
+    assert_eq!(region.owner, client);
+
+    let (_, slots) = kernel
+        .ipc_receive_with_caps(service, ep_slot)
+        .unwrap()
+        .unwrap();
+    kernel.process_syscall(service, SYS_REGION_MAP, [slots[0], 0x2000, 4096, 0], &[]);
+    assert!(kernel
+        .get_region(id)
+        .unwrap()
+        .mappings
+        .contains_key(&service));
+
+    kernel.delete_capability(service, slots[0]).unwrap();
+    assert!(kernel.get_region(id).is_none());
+    assert!(kernel.hal().regions.borrow().is_empty());
+
+    // A region still queued on a dying process's endpoint dies with it
+    let (slot, id) = kernel.create_region(client, 4096).unwrap();
+    kernel
+        .ipc_send_with_caps(client, client_ep, 1, Vec::new(), &[slot])
+        .unwrap();
+    kernel.kill_process(service);
+    assert!(kernel.get_region(id).is_none());
+}
+
 #[test]
 fn test_syscall_dispatch_ipc_has_message() {
     let hal = MockHal::new();
@@ -1035,6 +2396,105 @@ fn test_syscall_dispatch_list_processes() {
     assert!(!data.is_empty(), "Should return process data");
 }
 
+// ============================================================================
+// Process Tree Tests - child exit notification, wait, kill tree
+// ============================================================================
+
+#[test]
+fn test_child_exit_notifies_parent_and_wait_reaps() {
+    use zos_ipc::syscall::{SYS_KILL, SYS_WAIT_CHILD};
+    use zos_ipc::{exit_kind, kernel::MSG_CHILD_EXITED, syscall_error};
+
+    let mut kernel = System::new(MockHal::new());
+    let init = kernel.register_process("init");
+    let (_, init_slot) = kernel.create_endpoint(init).unwrap();
+    let child = kernel.register_process_with_parent("worker", init);
+    assert_eq!(kernel.get_process(child).unwrap().parent, init);
+
+    let (pending, _, _) = kernel.process_syscall(init, SYS_WAIT_CHILD, [0, 0, 0, 0], &[]);
+    assert_eq!(pending, syscall_error::WOULD_BLOCK as i64);
+
+    kernel.process_syscall(init, SYS_KILL, [child.0 as u32, 0, 0, 0], &[]);
+    assert!(kernel.get_process(child).is_none());
+
+    // The parent hears about the death on its first endpoint
+    let msg = kernel.ipc_receive(init, init_slot).unwrap().unwrap();
+    assert_eq!(msg.tag, MSG_CHILD_EXITED);
+    assert_eq!(msg.from, ProcessId(0));
+    assert_eq!(&msg.data[..8], &child.0.to_le_bytes());
+    assert_eq!(msg.data[8], exit_kind::KILLED);
+    assert_eq!(&msg.data[9..17], &init.0.to_le_bytes());
+
+    // Waiting reaps the exit exactly once
+    let (reaped, _, data) =
+        kernel.process_syscall(init, SYS_WAIT_CHILD, [child.0 as u32, 0, 0, 0], &[]);
+    assert_eq!(reaped, child.0 as i64);
+    assert_eq!(data, msg.data);
+    let (again, _, _) = kernel.process_syscall(init, SYS_WAIT_CHILD, [0, 0, 0, 0], &[]);
+    assert_eq!(again, syscall_error::NOT_FOUND as i64);
+}
+
+#[test]
+fn test_kill_tree_kills_descendants_and_reports_to_parent() {
+    use zos_ipc::syscall::{SYS_KILL_TREE, SYS_WAIT_CHILD};
+    use zos_ipc::syscall_error;
+
+    let mut kernel = System::new(MockHal::new());
+    let init = kernel.register_process("init");
+    kernel.create_endpoint(init).unwrap();
+    let shell = kernel.register_process_with_parent("shell", init);
+    let job = kernel.register_process_with_parent("job", shell);
+    let helper = kernel.register_process_with_parent("helper", job);
+    let stranger = kernel.register_process_with_parent("stranger", init);
+
+    // Only ancestors (or init) may kill a tree
+    let (denied, _, _) =
+        kernel.process_syscall(stranger, SYS_KILL_TREE, [shell.0 as u32, 0, 0, 0], &[]);
+    assert_eq!(denied, syscall_error::PERMISSION_DENIED as i64);
+
+    let (killed, _, _) =
+        kernel.process_syscall(shell, SYS_KILL_TREE, [job.0 as u32, 0, 0, 0], &[]);
+    assert_eq!(killed, 2);
+    assert!(kernel.get_process(job).is_none());
+    assert!(kernel.get_process(helper).is_none());
+    assert!(kernel.get_process(shell).is_some());
+    assert!(kernel.get_process(stranger).is_some());
+
+    // The shell reaps its own child; the grandchild was adopted by it first
+    let mut reaped = Vec::new();
+    loop {
+        let (pid, _, _) = kernel.process_syscall(shell, SYS_WAIT_CHILD, [0, 0, 0, 0], &[]);
+        if pid < 0 {
+            assert_eq!(pid, syscall_error::NOT_FOUND as i64);
+            break;
+        }
+        reaped.push(pid as u64);
+    }
+    reaped.sort();
+    assert_eq!(reaped, vec![job.0, helper.0]);
+}
+
+#[test]
+fn test_orphans_are_adopted_by_grandparent() {
+    use zos_ipc::syscall::SYS_WAIT_CHILD;
+
+    let mut kernel = System::new(MockHal::new());
+    let init = kernel.register_process("init");
+    kernel.create_endpoint(init).unwrap();
+    let parent = kernel.register_process_with_parent("parent", init);
+    let child = kernel.register_process_with_parent("child", parent);
+
+    kernel.kill_process(parent);
+    assert_eq!(kernel.get_process(child).unwrap().parent, init);
+
+    kernel.kill_process(child);
+    let (first, _, _) = kernel.process_syscall(init, SYS_WAIT_CHILD, [0, 0, 0, 0], &[]);
+    let (second, _, _) = kernel.process_syscall(init, SYS_WAIT_CHILD, [0, 0, 0, 0], &[]);
+    let mut reaped = vec![first as u64, second as u64];
+    reaped.sort();
+    assert_eq!(reaped, vec![parent.0, child.0]);
+}
+
 // ============================================================================
 // Commitlog Tests
 // ============================================================================
@@ -77,6 +77,8 @@ pub mod error {
         BufferOverflow,
         /// Parse error (malformed message data)
         ParseError,
+        /// A timed receive or call reached its deadline
+        TimedOut,
     }
 
     impl RecvError {
@@ -84,6 +86,7 @@ pub mod error {
         pub fn from_code(code: i32) -> Self {
             match code {
                 0 => RecvError::NoMessage,
+                code if code == zos_ipc::syscall_error::TIMED_OUT => RecvError::TimedOut,
                 code if code == -(E_PERM as i32) => RecvError::PermissionDenied,
                 code if code == -(E_BADF as i32) => RecvError::InvalidEndpoint,
                 code if code == -(E_OVERFLOW as i32) => RecvError::BufferOverflow,
@@ -147,17 +150,20 @@ pub mod error {
 }
 
 // Re-export types
-pub use types::{CapInfo, Permissions, ProcessInfo, ReceivedMessage};
+pub use types::{CapInfo, ChildExit, Permissions, ProcessInfo, ReceivedMessage, ResourceQuota};
 
 // Re-export ObjectType from zos-ipc (single source of truth for capability object types)
 pub use zos_ipc::ObjectType;
 
 // Re-export core syscalls
 pub use syscalls::{
-    call, cap_delete, cap_derive, cap_grant, cap_inspect, cap_revoke, cap_revoke_from,
-    console_write, create_endpoint, create_endpoint_for, debug, exit, get_pid, get_time,
-    get_wallclock, kill, list_caps, list_processes, load_binary, receive, receive_blocking,
-    receive_opt, register_process, reply, send, send_with_caps, spawn_process, yield_now,
+    call, call_timeout, cap_delete, cap_derive, cap_derive_badged, cap_derive_with_ttl, cap_grant,
+    cap_grant_with_ttl, cap_inspect,
+    cap_revoke, cap_revoke_from, console_write, create_endpoint, create_endpoint_for, create_endpoint_with_capacity, debug, exit,
+    get_pid, get_time, get_wallclock, io_in, io_out, io_port_cap_create, irq_ack,
+    irq_bind_endpoint, irq_bind_notification, irq_cap_create, irq_unbind, kill, kill_tree, list_caps, list_processes, load_binary, notify_create,
+    notify_poll, notify_signal, notify_wait, receive, receive_blocking, receive_opt, receive_timeout, register_process, reply, send, send_with_caps, set_quota, shutdown,
+    spawn_process, spawn_process_with_quota, wait_child, yield_now,
 };
 
 // Re-export typed error types
@@ -178,6 +184,9 @@ pub use syscalls::keystore::{
 // Re-export network syscalls
 pub use syscalls::network::network_fetch_async;
 
+// Re-export shared region syscalls
+pub use syscalls::region::{region_create, MappedRegion};
+
 
 // ============================================================================
 // IPC Message Constants (re-exported from zos-ipc)
@@ -187,8 +196,9 @@ pub use syscalls::network::network_fetch_async;
 pub use zos_ipc::{
     console, diagnostics, identity_cred, identity_key, identity_machine, identity_perm,
     identity_prefs, identity_query, identity_reg, identity_remote, identity_session, identity_tier,
-    identity_user, identity_zid, init, kernel, keystore, net, permission, pid, pm, revoke_reason,
-    slots, storage, supervisor, syscall_error, vfs_dir, vfs_file, vfs_meta, vfs_quota,
+    identity_user, identity_zid, init, kernel, keystore, net, permission, pid, pm, priority,
+    revoke_reason, slots, storage, supervisor, syscall_error, vfs_dir, vfs_file, vfs_meta,
+    vfs_quota,
 };
 
 /// Console input message tag - used by terminal for receiving keyboard input.
@@ -216,6 +226,9 @@ pub use zos_ipc::init::MSG_SPAWN_RESPONSE;
 /// Service ready notification (service → init after registration complete)
 pub use zos_ipc::init::MSG_SERVICE_READY;
 
+/// Power-off or reboot request (terminal → init): data = [reboot: u8]
+pub use zos_ipc::init::MSG_SHUTDOWN_REQUEST;
+
 // =============================================================================
 // Capability Revocation Notification (IPC → Process)
 // =============================================================================
@@ -224,6 +237,10 @@ pub use zos_ipc::init::MSG_SERVICE_READY;
 /// Payload: [slot: u32, object_type: u8, object_id: u64, reason: u8]
 pub use zos_ipc::kernel::MSG_CAP_REVOKED;
 
+/// Notification that a child of this process died and awaits `wait_child`
+/// Payload: [pid: u64, kind: u8, value: u64] (see `ChildExit`)
+pub use zos_ipc::kernel::MSG_CHILD_EXITED;
+
 /// Revocation reason: Supervisor/user explicitly revoked the capability
 pub const REVOKE_REASON_EXPLICIT: u8 = zos_ipc::revoke_reason::EXPLICIT;
 /// Revocation reason: Capability expired
@@ -276,6 +293,10 @@ pub use zos_ipc::supervisor::MSG_SUPERVISOR_KILL_PROCESS;
 /// Payload: [target_pid: u32, endpoint_slot: u32, tag: u32, data_len: u16, data: [u8]]
 pub use zos_ipc::supervisor::MSG_SUPERVISOR_IPC_DELIVERY;
 
+/// Supervisor requests Init to stop all services before power-off or reboot.
+/// Payload: [reboot: u8]
+pub use zos_ipc::supervisor::MSG_SUPERVISOR_SHUTDOWN;
+
 // =============================================================================
 // Supervisor → PermissionService Protocol
 // =============================================================================
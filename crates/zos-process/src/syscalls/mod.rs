@@ -7,16 +7,19 @@ use crate::error;
 // Import syscall numbers (re-exported from zos-ipc at crate root)
 #[allow(unused_imports)]
 use crate::{
-    SYS_CALL, SYS_CAP_DELETE, SYS_CAP_DERIVE, SYS_CAP_GRANT, SYS_CAP_INSPECT, SYS_CAP_LIST,
+    SYS_CALL, SYS_CALL_TIMEOUT, SYS_CAP_DELETE, SYS_CAP_DERIVE, SYS_CAP_DERIVE_TTL, SYS_CAP_GRANT, SYS_CAP_GRANT_TTL, SYS_CAP_INSPECT, SYS_CAP_LIST,
     SYS_CAP_REVOKE, SYS_CONSOLE_WRITE, SYS_CREATE_ENDPOINT, SYS_CREATE_ENDPOINT_FOR, SYS_DEBUG,
-    SYS_DELETE_ENDPOINT, SYS_EXIT, SYS_KILL, SYS_LOAD_BINARY, SYS_PS, SYS_RECV, SYS_REGISTER_PROCESS,
+    SYS_DELETE_ENDPOINT, SYS_EXIT, SYS_IO_IN, SYS_IO_OUT, SYS_IO_PORT_CAP_CREATE, SYS_IRQ_ACK,
+    SYS_IRQ_BIND, SYS_IRQ_CAP_CREATE, SYS_IRQ_UNBIND, SYS_KILL, SYS_LOAD_BINARY, SYS_NOTIFY_CREATE, SYS_NOTIFY_POLL,
+    SYS_NOTIFY_SIGNAL, SYS_NOTIFY_WAIT, SYS_PS, SYS_RECV, SYS_RECV_TIMEOUT, SYS_REGISTER_PROCESS,
     SYS_REPLY, SYS_SEND, SYS_SEND_CAP, SYS_SPAWN_PROCESS, SYS_TIME, SYS_WALLCLOCK, SYS_YIELD,
 };
-use crate::types::{CapInfo, Permissions, ProcessInfo, ReceivedMessage};
+use crate::types::{CapInfo, ChildExit, Permissions, ProcessInfo, ReceivedMessage, ResourceQuota};
 use alloc::vec::Vec;
 
 pub mod keystore;
 pub mod network;
+pub mod region;
 pub mod storage;
 
 // ============================================================================
@@ -184,6 +187,63 @@ pub fn kill(_target_pid: u32) -> Result<(), u32> {
     Err(error::E_NOSYS)
 }
 
+/// Kill a process and all of its descendants.
+///
+/// The caller must be the target itself, one of its ancestors, or Init.
+/// The target's parent receives `MSG_CHILD_EXITED` for every process killed.
+///
+/// # Returns
+/// - `Ok(count)`: Number of processes killed
+/// - `Err(code)`: `NOT_FOUND (-2)` or `PERMISSION_DENIED (-4)`
+#[cfg(target_arch = "wasm32")]
+pub fn kill_tree(target_pid: u32) -> Result<u32, i32> {
+    use crate::SYS_KILL_TREE;
+
+    let result = unsafe { zos_syscall(SYS_KILL_TREE, target_pid, 0, 0) };
+    if result < 0 {
+        Err(result as i32)
+    } else {
+        Ok(result as u32)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn kill_tree(_target_pid: u32) -> Result<u32, i32> {
+    // NOT_SUPPORTED error code
+    Err(-3)
+}
+
+/// Reap a dead child without blocking.
+///
+/// Call this after `MSG_CHILD_EXITED` arrives; the exit is kept until it
+/// is reaped even if the notification could not be delivered.
+///
+/// # Arguments
+/// - `pid`: Child to reap, or `None` for any child
+///
+/// # Returns
+/// - `Ok(exit)`: The reaped child and how it died
+/// - `Err(code)`: `WOULD_BLOCK (-9)` if the matching children are all
+///   alive, `NOT_FOUND (-2)` if there are none
+#[cfg(target_arch = "wasm32")]
+pub fn wait_child(pid: Option<u32>) -> Result<ChildExit, i32> {
+    use crate::SYS_WAIT_CHILD;
+
+    let result = unsafe { zos_syscall(SYS_WAIT_CHILD, pid.unwrap_or(0), 0, 0) };
+    if result < 0 {
+        return Err(result as i32);
+    }
+    let mut buffer = [0u8; 17];
+    let len = unsafe { zos_recv_bytes(buffer.as_mut_ptr(), buffer.len() as u32) } as usize;
+    ChildExit::from_payload(&buffer[..len]).ok_or(zos_ipc::syscall_error::INVALID_ARGUMENT)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn wait_child(_pid: Option<u32>) -> Result<ChildExit, i32> {
+    // NOT_SUPPORTED error code
+    Err(-3)
+}
+
 // ============================================================================
 // IPC Syscalls
 // ============================================================================
@@ -238,43 +298,55 @@ pub fn receive(endpoint_slot: u32) -> Result<ReceivedMessage, error::RecvError>
             return Err(RecvError::ParseError);
         }
 
-        // Parse message format:
-        // [from_pid: u32][tag: u32][num_caps: u8][cap_slots: u32*num_caps][data: ...]
-        // Minimum: 4 + 4 + 1 = 9 bytes
-        if len < 9 {
-            return Err(RecvError::ParseError);
-        }
-        let from_pid = u32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]);
-        let tag = u32::from_le_bytes([buffer[4], buffer[5], buffer[6], buffer[7]]);
-        let num_caps = buffer[8] as usize;
+        parse_message(&buffer[..len as usize])
+    }
+}
 
-        // Parse capability slots with overflow check
-        let cap_data_len = num_caps.checked_mul(4).ok_or(RecvError::ParseError)?;
-        let data_start = 9usize.checked_add(cap_data_len).ok_or(RecvError::ParseError)?;
-        if (len as usize) < data_start {
-            return Err(RecvError::ParseError);
-        }
+/// Parse a message returned by a receive syscall.
+///
+/// Format: [from_pid: u32][tag: u32][badge: u64][num_caps: u8][cap_slots: u32*num_caps][data: ...]
+#[cfg(target_arch = "wasm32")]
+fn parse_message(buffer: &[u8]) -> Result<ReceivedMessage, error::RecvError> {
+    use error::RecvError;
 
-        let mut cap_slots = Vec::with_capacity(num_caps);
-        for i in 0..num_caps {
-            let offset = 9 + i * 4;
-            let slot = u32::from_le_bytes([
-                buffer[offset],
-                buffer[offset + 1],
-                buffer[offset + 2],
-                buffer[offset + 3],
-            ]);
-            cap_slots.push(slot);
-        }
+    // Minimum: 4 + 4 + 8 + 1 = 17 bytes
+    if buffer.len() < 17 {
+        return Err(RecvError::ParseError);
+    }
+    let from_pid = u32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]);
+    let tag = u32::from_le_bytes([buffer[4], buffer[5], buffer[6], buffer[7]]);
+    let mut badge_bytes = [0u8; 8];
+    badge_bytes.copy_from_slice(&buffer[8..16]);
+    let badge = u64::from_le_bytes(badge_bytes);
+    let num_caps = buffer[16] as usize;
+
+    // Parse capability slots with overflow check
+    let cap_data_len = num_caps.checked_mul(4).ok_or(RecvError::ParseError)?;
+    let data_start = 17usize.checked_add(cap_data_len).ok_or(RecvError::ParseError)?;
+    if buffer.len() < data_start {
+        return Err(RecvError::ParseError);
+    }
 
-        let data = buffer[data_start..len as usize].to_vec();
-        Ok(ReceivedMessage {
-            from_pid,
-            tag,
-            cap_slots,
-            data,
-        })
+    let mut cap_slots = Vec::with_capacity(num_caps);
+    for i in 0..num_caps {
+        let offset = 17 + i * 4;
+        let slot = u32::from_le_bytes([
+            buffer[offset],
+            buffer[offset + 1],
+            buffer[offset + 2],
+            buffer[offset + 3],
+        ]);
+        cap_slots.push(slot);
     }
+
+    let data = buffer[data_start..].to_vec();
+    Ok(ReceivedMessage {
+        from_pid,
+        tag,
+        badge,
+        cap_slots,
+        data,
+    })
 }
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -298,23 +370,66 @@ pub fn receive_opt(_endpoint_slot: u32) -> Option<ReceivedMessage> {
 
 /// Receive a message, blocking until one arrives.
 ///
-/// This polls `receive()` in a loop, yielding between attempts.
+/// The kernel parks the process until a message arrives (see
+/// `receive_timeout`), so waiting costs no CPU.
 /// Returns immediately on non-recoverable errors (permission denied, invalid endpoint).
 #[cfg(target_arch = "wasm32")]
 pub fn receive_blocking(endpoint_slot: u32) -> Result<ReceivedMessage, error::RecvError> {
+    receive_timeout(&[endpoint_slot], 0).map(|(_, msg)| msg)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn receive_blocking(_endpoint_slot: u32) -> Result<ReceivedMessage, error::RecvError> {
+    Err(error::RecvError::NoMessage)
+}
+
+/// Receive from whichever endpoint has a message first, parking until one
+/// arrives or `timeout_ns` passes.
+///
+/// # Arguments
+/// - `endpoint_slots`: Slots to wait on, highest priority first
+///   (at most `wait::MAX_WAIT_SLOTS`)
+/// - `timeout_ns`: How long to wait (0 = no timeout)
+///
+/// # Returns
+/// - `Ok((slot, msg))`: Message and the slot it arrived on
+/// - `Err(RecvError::TimedOut)`: No message before the timeout
+/// - `Err(RecvError::InvalidEndpoint)`: A slot is not a receivable endpoint
+#[cfg(target_arch = "wasm32")]
+pub fn receive_timeout(
+    endpoint_slots: &[u32],
+    timeout_ns: u64,
+) -> Result<(u32, ReceivedMessage), error::RecvError> {
     use error::RecvError;
-    
-    loop {
-        match receive(endpoint_slot) {
-            Ok(msg) => return Ok(msg),
-            Err(RecvError::NoMessage) => yield_now(), // Keep polling
-            Err(e) => return Err(e), // Non-recoverable error
+
+    let mut request = Vec::with_capacity(8 + endpoint_slots.len() * 4);
+    request.extend_from_slice(&timeout_ns.to_le_bytes());
+    for slot in endpoint_slots {
+        request.extend_from_slice(&slot.to_le_bytes());
+    }
+
+    let mut buffer = [0u8; 16384];
+    unsafe {
+        zos_send_bytes(request.as_ptr(), request.len() as u32);
+        let result = zos_syscall(SYS_RECV_TIMEOUT, 0, 0, request.len() as u32) as i32;
+        if result <= 0 {
+            return Err(RecvError::from_code(result));
         }
+
+        let len = zos_recv_bytes(buffer.as_mut_ptr(), buffer.len() as u32) as usize;
+        if len < 4 {
+            return Err(RecvError::ParseError);
+        }
+        let slot = u32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]);
+        Ok((slot, parse_message(&buffer[4..len])?))
     }
 }
 
 #[cfg(not(target_arch = "wasm32"))]
-pub fn receive_blocking(_endpoint_slot: u32) -> Result<ReceivedMessage, error::RecvError> {
+pub fn receive_timeout(
+    _endpoint_slots: &[u32],
+    _timeout_ns: u64,
+) -> Result<(u32, ReceivedMessage), error::RecvError> {
     Err(error::RecvError::NoMessage)
 }
 
@@ -370,6 +485,10 @@ pub fn send_with_caps(
 
 /// Call - send a message and wait for reply (RPC pattern)
 ///
+/// The kernel gives the endpoint owner a one-shot reply capability naming
+/// this process, so only that server can answer (with `reply`), and only
+/// once. The caller is parked until the reply arrives.
+///
 /// # Arguments
 /// - `endpoint_slot`: Capability slot for the destination endpoint
 /// - `tag`: Application-defined message tag
@@ -377,18 +496,19 @@ pub fn send_with_caps(
 ///
 /// # Returns
 /// - `Ok(ReceivedMessage)`: Reply message
-/// - `Err(code)`: Error code
+/// - `Err(code)`: Error code (also if the server died before replying)
 #[cfg(target_arch = "wasm32")]
 pub fn call(endpoint_slot: u32, tag: u32, data: &[u8]) -> Result<ReceivedMessage, u32> {
-    // Simple implementation: send then poll for reply
-    send(endpoint_slot, tag, data)?;
-
-    // Poll for reply (would block in a real implementation)
-    loop {
-        if let Ok(msg) = receive(endpoint_slot) {
-            return Ok(msg);
+    let mut buffer = [0u8; 16384];
+    unsafe {
+        zos_send_bytes(data.as_ptr(), data.len() as u32);
+        let result = zos_syscall(SYS_CALL, endpoint_slot, tag, data.len() as u32) as i32;
+        if result <= 0 {
+            return Err(error::E_BADF);
         }
-        yield_now();
+
+        let len = zos_recv_bytes(buffer.as_mut_ptr(), buffer.len() as u32) as usize;
+        parse_message(&buffer[..len]).map_err(|_| error::E_INVAL)
     }
 }
 
@@ -397,10 +517,67 @@ pub fn call(_endpoint_slot: u32, _tag: u32, _data: &[u8]) -> Result<ReceivedMess
     Err(error::E_NOSYS)
 }
 
+/// Call with a deadline - send a request and park until the reply arrives
+/// on `reply_slot` or `timeout_ns` passes.
+///
+/// # Arguments
+/// - `endpoint_slot`: Capability slot for the destination endpoint
+/// - `tag`: Application-defined message tag
+/// - `data`: Request payload
+/// - `reply_slot`: Slot of the endpoint the reply is sent to
+/// - `timeout_ns`: How long to wait for the reply (0 = no timeout)
+///
+/// # Returns
+/// - `Ok(ReceivedMessage)`: Reply message
+/// - `Err(RecvError::TimedOut)`: No reply before the timeout
+/// - `Err(RecvError::InvalidEndpoint)`: The request could not be sent or
+///   `reply_slot` is not receivable
+#[cfg(target_arch = "wasm32")]
+pub fn call_timeout(
+    endpoint_slot: u32,
+    tag: u32,
+    data: &[u8],
+    reply_slot: u32,
+    timeout_ns: u64,
+) -> Result<ReceivedMessage, error::RecvError> {
+    use error::RecvError;
+
+    let mut request = Vec::with_capacity(8 + data.len());
+    request.extend_from_slice(&timeout_ns.to_le_bytes());
+    request.extend_from_slice(data);
+
+    let mut buffer = [0u8; 16384];
+    unsafe {
+        zos_send_bytes(request.as_ptr(), request.len() as u32);
+        let result = zos_syscall(SYS_CALL_TIMEOUT, endpoint_slot, tag, reply_slot) as i32;
+        if result <= 0 {
+            return Err(RecvError::from_code(result));
+        }
+
+        let len = zos_recv_bytes(buffer.as_mut_ptr(), buffer.len() as u32) as usize;
+        parse_message(&buffer[..len])
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn call_timeout(
+    _endpoint_slot: u32,
+    _tag: u32,
+    _data: &[u8],
+    _reply_slot: u32,
+    _timeout_ns: u64,
+) -> Result<ReceivedMessage, error::RecvError> {
+    Err(error::RecvError::NoMessage)
+}
+
 /// Reply to a call
 ///
+/// Consumes the reply capability the kernel minted for `caller_pid`'s
+/// `call`. Fails if this process holds no such capability, so a reply
+/// cannot be spoofed or sent twice.
+///
 /// # Arguments
-/// - `caller_pid`: PID of the calling process
+/// - `caller_pid`: PID of the calling process (`ReceivedMessage::from_pid`)
 /// - `tag`: Reply message tag
 /// - `data`: Reply payload
 ///
@@ -456,6 +633,49 @@ pub fn cap_grant(_from_slot: u32, _to_pid: u32, _perms: Permissions) -> Result<u
     Err(error::E_NOSYS)
 }
 
+/// Grant a capability that expires after a lifetime
+///
+/// The kernel removes the copy once `ttl_ns` has passed (or earlier, if the
+/// source expires first) and tells the holder with `MSG_CAP_REVOKED`.
+///
+/// # Arguments
+/// - `from_slot`: Source capability slot in caller's CSpace
+/// - `to_pid`: Target process ID
+/// - `perms`: Permissions to grant (attenuated from source)
+/// - `ttl_ns`: Non-zero lifetime in nanoseconds
+///
+/// # Returns
+/// - `Ok(slot)`: Slot in target's CSpace where capability was placed
+/// - `Err(code)`: Error code
+#[cfg(target_arch = "wasm32")]
+pub fn cap_grant_with_ttl(
+    from_slot: u32,
+    to_pid: u32,
+    perms: Permissions,
+    ttl_ns: u64,
+) -> Result<u32, u32> {
+    let bytes = ttl_ns.to_le_bytes();
+    unsafe {
+        zos_send_bytes(bytes.as_ptr(), bytes.len() as u32);
+        let result = zos_syscall(SYS_CAP_GRANT_TTL, from_slot, to_pid, perms.to_byte() as u32);
+        if result & 0x80000000 == 0 {
+            Ok(result as u32)
+        } else {
+            Err((result & 0x7FFFFFFF) as u32)
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn cap_grant_with_ttl(
+    _from_slot: u32,
+    _to_pid: u32,
+    _perms: Permissions,
+    _ttl_ns: u64,
+) -> Result<u32, u32> {
+    Err(error::E_NOSYS)
+}
+
 /// Revoke a capability (requires grant permission)
 ///
 /// # Arguments
@@ -607,6 +827,67 @@ pub fn cap_derive(_slot: u32, _new_perms: Permissions) -> Result<u32, u32> {
     Err(error::E_NOSYS)
 }
 
+/// Derive a badged endpoint capability
+///
+/// Messages sent through the new capability arrive with `badge` set in
+/// `ReceivedMessage::badge`, so a server can tell its clients apart.
+///
+/// # Arguments
+/// - `slot`: Source capability slot (an unbadged endpoint capability)
+/// - `new_perms`: Requested permissions (will be intersected with source)
+/// - `badge`: Non-zero badge to stamp
+///
+/// # Returns
+/// - `Ok(new_slot)`: Slot of the new badged capability
+/// - `Err(code)`: Error code
+#[cfg(target_arch = "wasm32")]
+pub fn cap_derive_badged(slot: u32, new_perms: Permissions, badge: u64) -> Result<u32, u32> {
+    let bytes = badge.to_le_bytes();
+    unsafe {
+        zos_send_bytes(bytes.as_ptr(), bytes.len() as u32);
+        let result = zos_syscall(SYS_CAP_DERIVE, slot, new_perms.to_byte() as u32, 0);
+        if result & 0x80000000 == 0 {
+            Ok(result as u32)
+        } else {
+            Err((result & 0x7FFFFFFF) as u32)
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn cap_derive_badged(_slot: u32, _new_perms: Permissions, _badge: u64) -> Result<u32, u32> {
+    Err(error::E_NOSYS)
+}
+
+/// Derive a capability that expires after a lifetime
+///
+/// # Arguments
+/// - `slot`: Source capability slot
+/// - `new_perms`: Requested permissions (will be intersected with source)
+/// - `ttl_ns`: Non-zero lifetime in nanoseconds (clamped to the source's)
+///
+/// # Returns
+/// - `Ok(new_slot)`: Slot of the new capability
+/// - `Err(code)`: Error code
+#[cfg(target_arch = "wasm32")]
+pub fn cap_derive_with_ttl(slot: u32, new_perms: Permissions, ttl_ns: u64) -> Result<u32, u32> {
+    let bytes = ttl_ns.to_le_bytes();
+    unsafe {
+        zos_send_bytes(bytes.as_ptr(), bytes.len() as u32);
+        let result = zos_syscall(SYS_CAP_DERIVE_TTL, slot, new_perms.to_byte() as u32, 0);
+        if result & 0x80000000 == 0 {
+            Ok(result as u32)
+        } else {
+            Err((result & 0x7FFFFFFF) as u32)
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn cap_derive_with_ttl(_slot: u32, _new_perms: Permissions, _ttl_ns: u64) -> Result<u32, u32> {
+    Err(error::E_NOSYS)
+}
+
 /// Create an IPC endpoint
 ///
 /// # Returns
@@ -637,6 +918,311 @@ pub fn create_endpoint() -> Result<(u64, u32), u32> {
     Err(error::E_NOSYS)
 }
 
+/// Create an IPC endpoint whose queue holds at most `capacity` messages
+///
+/// With `block_when_full`, a send to a full queue fails with
+/// `WOULD_BLOCK` and should be retried; otherwise it fails with
+/// `QUEUE_FULL` and the message is dropped.
+///
+/// # Returns
+/// - `Ok((endpoint_id, slot))`: Endpoint ID and capability slot
+/// - `Err(code)`: Error code (a capacity of 0 or over 1024 is invalid)
+#[cfg(target_arch = "wasm32")]
+pub fn create_endpoint_with_capacity(
+    capacity: u32,
+    block_when_full: bool,
+) -> Result<(u64, u32), u32> {
+    if capacity == 0 {
+        return Err(error::E_INVAL);
+    }
+    use crate::ENDPOINT_BLOCK_WHEN_FULL;
+
+    let flags = if block_when_full { ENDPOINT_BLOCK_WHEN_FULL } else { 0 };
+    unsafe {
+        let result = zos_syscall(SYS_CREATE_ENDPOINT, capacity, flags, 0);
+        if result >= 0 {
+            let slot = (result >> 32) as u32;
+            let endpoint_id = (result & 0xFFFFFFFF) as u64;
+            Ok((endpoint_id, slot))
+        } else {
+            Err((-result) as u32)
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn create_endpoint_with_capacity(
+    _capacity: u32,
+    _block_when_full: bool,
+) -> Result<(u64, u32), u32> {
+    Err(error::E_NOSYS)
+}
+
+// ============================================================================
+// Notification Syscalls
+// ============================================================================
+
+/// Create a notification (a word of signal bits)
+///
+/// # Returns
+/// - `Ok((notification_id, slot))`: Notification ID and capability slot
+/// - `Err(code)`: Error code
+///
+/// The kernel packs the result like `create_endpoint`.
+#[cfg(target_arch = "wasm32")]
+pub fn notify_create() -> Result<(u64, u32), u32> {
+    unsafe {
+        let result = zos_syscall(SYS_NOTIFY_CREATE, 0, 0, 0);
+        if result >= 0 {
+            let slot = (result >> 32) as u32;
+            let notification_id = (result & 0xFFFFFFFF) as u64;
+            Ok((notification_id, slot))
+        } else {
+            Err((-result) as u32)
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn notify_create() -> Result<(u64, u32), u32> {
+    Err(error::E_NOSYS)
+}
+
+/// OR `bits` into a notification's word
+///
+/// Pending bits coalesce, so signalling never blocks or allocates.
+///
+/// # Arguments
+/// - `slot`: Notification capability slot (needs write)
+/// - `bits`: Non-zero bits to set
+#[cfg(target_arch = "wasm32")]
+pub fn notify_signal(slot: u32, bits: u64) -> Result<(), u32> {
+    unsafe {
+        let result = zos_syscall(SYS_NOTIFY_SIGNAL, slot, bits as u32, (bits >> 32) as u32);
+        if result >= 0 {
+            Ok(())
+        } else {
+            Err((-result) as u32)
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn notify_signal(_slot: u32, _bits: u64) -> Result<(), u32> {
+    Err(error::E_NOSYS)
+}
+
+/// Take a notification's word, parking until it is non-zero or
+/// `timeout_ns` passes (0 = no timeout)
+///
+/// # Returns
+/// - `Ok(bits)`: The bits signalled since the last take
+/// - `Err(RecvError::TimedOut)`: No signal before the timeout
+/// - `Err(RecvError::InvalidEndpoint)`: The slot is not a readable notification
+#[cfg(target_arch = "wasm32")]
+pub fn notify_wait(slot: u32, timeout_ns: u64) -> Result<u64, error::RecvError> {
+    use error::RecvError;
+
+    unsafe {
+        let result = zos_syscall(
+            SYS_NOTIFY_WAIT,
+            slot,
+            timeout_ns as u32,
+            (timeout_ns >> 32) as u32,
+        ) as i32;
+        if result < 0 {
+            return Err(RecvError::from_code(result));
+        }
+        read_notification_bits().ok_or(RecvError::ParseError)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn notify_wait(_slot: u32, _timeout_ns: u64) -> Result<u64, error::RecvError> {
+    Err(error::RecvError::NoMessage)
+}
+
+/// Take a notification's word without blocking
+///
+/// # Returns
+/// - `Ok(bits)`: The bits signalled since the last take (may be 0)
+/// - `Err(code)`: Error code
+#[cfg(target_arch = "wasm32")]
+pub fn notify_poll(slot: u32) -> Result<u64, u32> {
+    unsafe {
+        let result = zos_syscall(SYS_NOTIFY_POLL, slot, 0, 0);
+        if result < 0 {
+            return Err((-result) as u32);
+        }
+        read_notification_bits().ok_or(error::E_INVAL)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn notify_poll(_slot: u32) -> Result<u64, u32> {
+    Err(error::E_NOSYS)
+}
+
+/// Read the `u64` bits word a notification syscall left in the result buffer
+#[cfg(target_arch = "wasm32")]
+unsafe fn read_notification_bits() -> Option<u64> {
+    let mut buffer = [0u8; 8];
+    let len = zos_recv_bytes(buffer.as_mut_ptr(), buffer.len() as u32);
+    if len as usize == buffer.len() {
+        Some(u64::from_le_bytes(buffer))
+    } else {
+        None
+    }
+}
+
+// ============================================================================
+// Device Syscalls (User-Space Drivers)
+// ============================================================================
+
+/// Mint an IRQ capability for an ISA line (Init-only)
+///
+/// # Returns
+/// - `Ok(slot)`: Capability slot in Init's CSpace
+/// - `Err(code)`: Error code
+#[cfg(target_arch = "wasm32")]
+pub fn irq_cap_create(irq: u8) -> Result<u32, u32> {
+    unsafe { slot_result(zos_syscall(SYS_IRQ_CAP_CREATE, irq as u32, 0, 0)) }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn irq_cap_create(_irq: u8) -> Result<u32, u32> {
+    Err(error::E_NOSYS)
+}
+
+/// Mint a capability for the I/O ports `base..base + len` (Init-only)
+///
+/// # Returns
+/// - `Ok(slot)`: Capability slot in Init's CSpace
+/// - `Err(code)`: Error code
+#[cfg(target_arch = "wasm32")]
+pub fn io_port_cap_create(base: u16, len: u16) -> Result<u32, u32> {
+    unsafe { slot_result(zos_syscall(SYS_IO_PORT_CAP_CREATE, base as u32, len as u32, 0)) }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn io_port_cap_create(_base: u16, _len: u16) -> Result<u32, u32> {
+    Err(error::E_NOSYS)
+}
+
+/// Deliver an IRQ line by OR-ing `bits` into a notification
+///
+/// The line stays masked after each interrupt until `irq_ack`.
+///
+/// # Arguments
+/// - `irq_slot`: IRQ capability slot (needs read)
+/// - `notification_slot`: Notification capability slot (needs write)
+/// - `bits`: Non-zero bits to set on each interrupt
+#[cfg(target_arch = "wasm32")]
+pub fn irq_bind_notification(irq_slot: u32, notification_slot: u32, bits: u64) -> Result<(), u32> {
+    let payload = bits.to_le_bytes();
+    unsafe {
+        zos_send_bytes(payload.as_ptr(), payload.len() as u32);
+        unit_result(zos_syscall(SYS_IRQ_BIND, irq_slot, notification_slot, 0))
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn irq_bind_notification(_irq_slot: u32, _notification_slot: u32, _bits: u64) -> Result<(), u32> {
+    Err(error::E_NOSYS)
+}
+
+/// Deliver an IRQ line as `MSG_IRQ` messages on an endpoint
+///
+/// # Arguments
+/// - `irq_slot`: IRQ capability slot (needs read)
+/// - `endpoint_slot`: Endpoint capability slot (needs write)
+#[cfg(target_arch = "wasm32")]
+pub fn irq_bind_endpoint(irq_slot: u32, endpoint_slot: u32) -> Result<(), u32> {
+    unsafe {
+        zos_send_bytes([].as_ptr(), 0);
+        unit_result(zos_syscall(SYS_IRQ_BIND, irq_slot, endpoint_slot, 0))
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn irq_bind_endpoint(_irq_slot: u32, _endpoint_slot: u32) -> Result<(), u32> {
+    Err(error::E_NOSYS)
+}
+
+/// Acknowledge an interrupt, unmasking its line
+#[cfg(target_arch = "wasm32")]
+pub fn irq_ack(irq_slot: u32) -> Result<(), u32> {
+    unsafe { unit_result(zos_syscall(SYS_IRQ_ACK, irq_slot, 0, 0)) }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn irq_ack(_irq_slot: u32) -> Result<(), u32> {
+    Err(error::E_NOSYS)
+}
+
+/// Stop delivering an IRQ line and mask it
+#[cfg(target_arch = "wasm32")]
+pub fn irq_unbind(irq_slot: u32) -> Result<(), u32> {
+    unsafe { unit_result(zos_syscall(SYS_IRQ_UNBIND, irq_slot, 0, 0)) }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn irq_unbind(_irq_slot: u32) -> Result<(), u32> {
+    Err(error::E_NOSYS)
+}
+
+/// Read `width` bytes (1, 2 or 4) from an I/O port
+///
+/// # Arguments
+/// - `port_slot`: I/O port capability slot covering `port` (needs read)
+#[cfg(target_arch = "wasm32")]
+pub fn io_in(port_slot: u32, port: u16, width: u8) -> Result<u32, u32> {
+    unsafe {
+        let result = zos_syscall(SYS_IO_IN, port_slot, port as u32, width as u32);
+        if result >= 0 {
+            Ok(result as u32)
+        } else {
+            Err((-result) as u32)
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn io_in(_port_slot: u32, _port: u16, _width: u8) -> Result<u32, u32> {
+    Err(error::E_NOSYS)
+}
+
+/// Write `width` bytes (1, 2 or 4) of `value` to an I/O port
+///
+/// # Arguments
+/// - `port_slot`: I/O port capability slot covering `port` (needs write)
+#[cfg(target_arch = "wasm32")]
+pub fn io_out(port_slot: u32, port: u16, width: u8, value: u32) -> Result<(), u32> {
+    let port_and_width = ((width as u32) << 16) | port as u32;
+    unsafe { unit_result(zos_syscall(SYS_IO_OUT, port_slot, port_and_width, value)) }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn io_out(_port_slot: u32, _port: u16, _width: u8, _value: u32) -> Result<(), u32> {
+    Err(error::E_NOSYS)
+}
+
+/// Map a syscall result that carries a capability slot
+#[cfg(target_arch = "wasm32")]
+fn slot_result(result: i64) -> Result<u32, u32> {
+    if result >= 0 {
+        Ok(result as u32)
+    } else {
+        Err((-result) as u32)
+    }
+}
+
+/// Map a syscall result that carries no value
+#[cfg(target_arch = "wasm32")]
+fn unit_result(result: i64) -> Result<(), u32> {
+    slot_result(result).map(|_| ())
+}
+
 // ============================================================================
 // Init-Only Syscalls (Spawn Protocol)
 // ============================================================================
@@ -768,30 +1354,82 @@ pub fn load_binary(_name: &str) -> Result<Vec<u8>, i32> {
 /// # Arguments
 /// - `name`: Process name for debugging/identification
 /// - `binary`: WASM binary data
+/// - `priority`: Scheduling class (`zos_ipc::priority`)
 ///
 /// # Returns
 /// - `Ok(pid)`: PID of the spawned process
 /// - `Err(code)`: Error code
 ///   - `PERMISSION_DENIED (-4)`: Caller is not Init
-///   - `INVALID_ARGUMENT (-5)`: Missing or invalid payload
+///   - `INVALID_ARGUMENT (-5)`: Missing or invalid payload, or unknown class
 ///   - `SPAWN_FAILED (-6)`: HAL failed to spawn process
 #[cfg(target_arch = "wasm32")]
-pub fn spawn_process(name: &str, binary: &[u8]) -> Result<u32, i32> {
+pub fn spawn_process(name: &str, binary: &[u8], priority: u32) -> Result<u32, i32> {
+    spawn(name, binary, priority, None)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn spawn_process(_name: &str, _binary: &[u8], _priority: u32) -> Result<u32, i32> {
+    // NOT_SUPPORTED error code
+    Err(-3)
+}
+
+/// Spawn a process with a resource quota (Init-only syscall).
+///
+/// Like `spawn_process`, but the kernel applies `quota` before the process
+/// runs. The quota may not exceed the caller's own.
+///
+/// # Returns
+/// - `Ok(pid)`: PID of the spawned process
+/// - `Err(code)`: Error code, as for `spawn_process`; `PERMISSION_DENIED (-4)`
+///   also covers a quota more generous than the caller's
+#[cfg(target_arch = "wasm32")]
+pub fn spawn_process_with_quota(
+    name: &str,
+    binary: &[u8],
+    priority: u32,
+    quota: &ResourceQuota,
+) -> Result<u32, i32> {
+    spawn(name, binary, priority, Some(quota))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn spawn_process_with_quota(
+    _name: &str,
+    _binary: &[u8],
+    _priority: u32,
+    _quota: &ResourceQuota,
+) -> Result<u32, i32> {
+    // NOT_SUPPORTED error code
+    Err(-3)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn spawn(
+    name: &str,
+    binary: &[u8],
+    priority: u32,
+    quota: Option<&ResourceQuota>,
+) -> Result<u32, i32> {
     use crate::SYS_SPAWN_PROCESS;
-    
-    // Build payload: [name_len: u32 (LE), name: [u8], binary: [u8]]
+    use zos_ipc::quota::{RECORD_SIZE, SPAWN_WITH_QUOTA};
+
+    // Build payload: [name_len: u32 (LE), name: [u8], quota?: [u8; 40], binary: [u8]]
     let name_bytes = name.as_bytes();
     let name_len = name_bytes.len() as u32;
-    
-    let mut payload = Vec::with_capacity(4 + name_bytes.len() + binary.len());
+
+    let mut payload = Vec::with_capacity(4 + name_bytes.len() + RECORD_SIZE + binary.len());
     payload.extend_from_slice(&name_len.to_le_bytes());
     payload.extend_from_slice(name_bytes);
+    if let Some(quota) = quota {
+        payload.extend_from_slice(&quota.to_bytes());
+    }
     payload.extend_from_slice(binary);
-    
+    let flags = if quota.is_some() { SPAWN_WITH_QUOTA } else { 0 };
+
     unsafe {
         zos_send_bytes(payload.as_ptr(), payload.len() as u32);
-        let result = zos_syscall(SYS_SPAWN_PROCESS, payload.len() as u32, 0, 0) as i32;
-        
+        let result = zos_syscall(SYS_SPAWN_PROCESS, payload.len() as u32, priority, flags) as i32;
+
         if result < 0 {
             Err(result)
         } else {
@@ -800,8 +1438,63 @@ pub fn spawn_process(name: &str, binary: &[u8]) -> Result<u32, i32> {
     }
 }
 
+/// Replace the resource quota of a process (Init-only syscall).
+///
+/// # Returns
+/// - `Ok(())`: Quota applied
+/// - `Err(code)`: Error code
+///   - `NOT_FOUND (-2)`: No such process
+///   - `PERMISSION_DENIED (-4)`: Caller is not Init, or the quota exceeds its own
+///   - `INVALID_ARGUMENT (-5)`: Malformed quota record
+#[cfg(target_arch = "wasm32")]
+pub fn set_quota(pid: u32, quota: &ResourceQuota) -> Result<(), i32> {
+    use crate::SYS_SET_QUOTA;
+
+    let record = quota.to_bytes();
+    let result = unsafe {
+        zos_send_bytes(record.as_ptr(), record.len() as u32);
+        zos_syscall(SYS_SET_QUOTA, pid, 0, 0) as i32
+    };
+    if result < 0 {
+        Err(result)
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn set_quota(_pid: u32, _quota: &ResourceQuota) -> Result<(), i32> {
+    // NOT_SUPPORTED error code
+    Err(-3)
+}
+
+/// Power off or reboot the machine (Init-only syscall).
+///
+/// The kernel accepts the request, asks Init to stop services
+/// (`MSG_SUPERVISOR_SHUTDOWN`) and then cuts power once Init reports ready.
+///
+/// # Arguments
+/// - `reboot`: Reboot instead of powering off
+///
+/// # Returns
+/// - `Ok(())`: Request accepted
+/// - `Err(code)`: Error code
+///   - `NOT_SUPPORTED (-3)`: Platform has no power control (browser)
+///   - `PERMISSION_DENIED (-4)`: Caller is not Init
+#[cfg(target_arch = "wasm32")]
+pub fn shutdown(reboot: bool) -> Result<(), i32> {
+    use crate::SYS_SHUTDOWN;
+
+    let result = unsafe { zos_syscall(SYS_SHUTDOWN, reboot as u32, 0, 0) as i32 };
+    if result < 0 {
+        Err(result)
+    } else {
+        Ok(())
+    }
+}
+
 #[cfg(not(target_arch = "wasm32"))]
-pub fn spawn_process(_name: &str, _binary: &[u8]) -> Result<u32, i32> {
+pub fn shutdown(_reboot: bool) -> Result<(), i32> {
     // NOT_SUPPORTED error code
     Err(-3)
 }
@@ -884,7 +1577,8 @@ pub fn list_processes() -> Vec<ProcessInfo> {
         if len < 4 {
             return Vec::new();
         }
-        // Parse: first 4 bytes = count, then for each proc: pid(4) + name_len(2) + name(variable)
+        // Parse: first 4 bytes = count, then for each proc:
+        // pid(4) + name_len(2) + name(variable) + quota(40)
         let count = u32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]) as usize;
         let mut procs = Vec::with_capacity(count);
         let mut offset = 4;
@@ -907,10 +1601,19 @@ pub fn list_processes() -> Vec<ProcessInfo> {
                 .unwrap_or("???")
                 .to_string();
             offset += name_len;
+            let quota = match buffer[..len as usize]
+                .get(offset..)
+                .and_then(ResourceQuota::from_bytes)
+            {
+                Some(quota) => quota,
+                None => break,
+            };
+            offset += zos_ipc::quota::RECORD_SIZE;
             procs.push(ProcessInfo {
                 pid,
                 name,
                 state: 0, // Running (state not included in kernel response)
+                quota,
             });
         }
         procs
@@ -0,0 +1,152 @@
+//! Shared memory region syscalls for Zero OS
+//!
+//! A region is created by one process and handed to others as a capability
+//! over IPC (read-only or read-write). Each holder maps it through a window
+//! in its own memory: `refresh` copies the region into the window and
+//! `flush` publishes the window back, so bulk data such as file bodies
+//! moves without being copied through IPC messages.
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::error;
+#[allow(unused_imports)]
+use crate::{
+    REGION_MAP_WRITABLE, SYS_REGION_CREATE, SYS_REGION_FLUSH, SYS_REGION_MAP, SYS_REGION_REFRESH,
+    SYS_REGION_UNMAP,
+};
+use alloc::vec;
+use alloc::vec::Vec;
+
+#[cfg(target_arch = "wasm32")]
+extern "C" {
+    fn zos_syscall(syscall_num: u32, arg1: u32, arg2: u32, arg3: u32) -> i64;
+}
+
+/// Region sizes are whole pages of this many bytes
+pub const REGION_PAGE_SIZE: usize = 4096;
+
+/// Create a zeroed shared region of at least `size` bytes
+///
+/// # Returns
+/// - `Ok(slot)`: Slot of a full capability for the region
+/// - `Err(code)`: Error code
+#[cfg(target_arch = "wasm32")]
+pub fn region_create(size: usize) -> Result<u32, u32> {
+    unsafe { syscall_result(zos_syscall(SYS_REGION_CREATE, size as u32, 0, 0)).map(|s| s as u32) }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn region_create(_size: usize) -> Result<u32, u32> {
+    Err(error::E_NOSYS)
+}
+
+/// A shared region mapped through a window owned by this process.
+///
+/// The window is this process's view of the region. Writes to it stay
+/// private until flushed, and other holders' flushes show up after a
+/// refresh. The mapping is dropped together with the value.
+pub struct MappedRegion {
+    slot: u32,
+    writable: bool,
+    window: Vec<u8>,
+}
+
+impl MappedRegion {
+    /// Map the region behind `slot`, created with a size of `len` bytes.
+    ///
+    /// A writable mapping needs a read-write capability. The window starts
+    /// out holding the region's current contents.
+    pub fn map(slot: u32, len: usize, writable: bool) -> Result<Self, u32> {
+        let mut window = vec![0u8; len.div_ceil(REGION_PAGE_SIZE) * REGION_PAGE_SIZE];
+        region_map(slot, &mut window, writable)?;
+        Ok(Self {
+            slot,
+            writable,
+            window,
+        })
+    }
+
+    /// Capability slot of the region
+    pub fn slot(&self) -> u32 {
+        self.slot
+    }
+
+    /// Whether this mapping may flush
+    pub fn is_writable(&self) -> bool {
+        self.writable
+    }
+
+    /// The window
+    pub fn as_slice(&self) -> &[u8] {
+        &self.window
+    }
+
+    /// The window, for writing before a flush
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        &mut self.window
+    }
+
+    /// Publish `len` bytes of the window at `offset` into the region
+    pub fn flush(&self, offset: usize, len: usize) -> Result<(), u32> {
+        region_copy(SYS_REGION_FLUSH, self.slot, offset, len)
+    }
+
+    /// Copy `len` bytes of the region at `offset` into the window
+    pub fn refresh(&mut self, offset: usize, len: usize) -> Result<(), u32> {
+        region_copy(SYS_REGION_REFRESH, self.slot, offset, len)
+    }
+}
+
+impl Drop for MappedRegion {
+    fn drop(&mut self) {
+        // The kernel must not copy into the window once it is freed
+        let _ = region_unmap(self.slot);
+    }
+}
+
+/// Map a region at `window`, which must stay alive until it is unmapped
+#[cfg(target_arch = "wasm32")]
+fn region_map(slot: u32, window: &mut [u8], writable: bool) -> Result<usize, u32> {
+    let mut len_and_flags = window.len() as u32;
+    if writable {
+        len_and_flags |= REGION_MAP_WRITABLE;
+    }
+    let addr = window.as_mut_ptr() as u32;
+    unsafe { syscall_result(zos_syscall(SYS_REGION_MAP, slot, addr, len_and_flags)) }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn region_map(_slot: u32, _window: &mut [u8], _writable: bool) -> Result<usize, u32> {
+    Err(error::E_NOSYS)
+}
+
+/// Flush or refresh part of a mapped region
+#[cfg(target_arch = "wasm32")]
+fn region_copy(syscall: u32, slot: u32, offset: usize, len: usize) -> Result<(), u32> {
+    unsafe { syscall_result(zos_syscall(syscall, slot, offset as u32, len as u32)).map(|_| ()) }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn region_copy(_syscall: u32, _slot: u32, _offset: usize, _len: usize) -> Result<(), u32> {
+    Err(error::E_NOSYS)
+}
+
+/// Drop this process's mapping of a region
+#[cfg(target_arch = "wasm32")]
+fn region_unmap(slot: u32) -> Result<(), u32> {
+    unsafe { syscall_result(zos_syscall(SYS_REGION_UNMAP, slot, 0, 0)).map(|_| ()) }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn region_unmap(_slot: u32) -> Result<(), u32> {
+    Err(error::E_NOSYS)
+}
+
+/// Map a syscall result that carries a size or slot
+#[cfg(target_arch = "wasm32")]
+fn syscall_result(result: i64) -> Result<usize, u32> {
+    if result >= 0 {
+        Ok(result as usize)
+    } else {
+        Err((-result) as u32)
+    }
+}
@@ -87,6 +87,8 @@ pub struct ReceivedMessage {
     pub from_pid: u32,
     /// Message tag
     pub tag: u32,
+    /// Badge of the capability the sender used (0 = unbadged)
+    pub badge: u64,
     /// Capability slots containing transferred capabilities
     /// These are slots in the receiver's CSpace where the kernel installed
     /// capabilities that were transferred with this message.
@@ -116,4 +118,86 @@ pub struct ProcessInfo {
     pub pid: u32,
     pub name: String,
     pub state: u8,
+    pub quota: ResourceQuota,
+}
+
+/// A dead child, as reported by `MSG_CHILD_EXITED` and `wait_child`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChildExit {
+    pub pid: u32,
+    /// How it died (`zos_ipc::exit_kind`)
+    pub kind: u8,
+    /// Exit code (sign-extended), killer PID or fault reason, by `kind`
+    pub value: u64,
+}
+
+impl ChildExit {
+    /// Decode a `[pid: u64, kind: u8, value: u64]` payload
+    pub fn from_payload(bytes: &[u8]) -> Option<Self> {
+        let pid = u64::from_le_bytes(bytes.get(0..8)?.try_into().ok()?);
+        let kind = *bytes.get(8)?;
+        let value = u64::from_le_bytes(bytes.get(9..17)?.try_into().ok()?);
+        Some(Self {
+            pid: pid as u32,
+            kind,
+            value,
+        })
+    }
+}
+
+// ============================================================================
+// Resource Quotas
+// ============================================================================
+
+/// Per-process resource limits. A limit of 0 means unlimited.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ResourceQuota {
+    /// Maximum memory in bytes
+    pub max_memory: u64,
+    /// Maximum number of owned endpoints
+    pub max_endpoints: u64,
+    /// Maximum number of capability slots in use
+    pub max_cap_slots: u64,
+    /// Maximum bytes of sent messages waiting in endpoint queues
+    pub max_queued_bytes: u64,
+    /// Maximum syscalls per second
+    pub max_syscalls_per_sec: u64,
+}
+
+impl ResourceQuota {
+    /// Check if no limit is set
+    pub fn is_unlimited(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Encode as a quota record (`zos_ipc::quota`)
+    pub fn to_bytes(&self) -> [u8; zos_ipc::quota::RECORD_SIZE] {
+        let mut out = [0u8; zos_ipc::quota::RECORD_SIZE];
+        let limits = [
+            self.max_memory,
+            self.max_endpoints,
+            self.max_cap_slots,
+            self.max_queued_bytes,
+            self.max_syscalls_per_sec,
+        ];
+        for (i, limit) in limits.into_iter().enumerate() {
+            out[i * 8..i * 8 + 8].copy_from_slice(&limit.to_le_bytes());
+        }
+        out
+    }
+
+    /// Decode a quota record (`None` if `bytes` is too short)
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let field = |i: usize| -> Option<u64> {
+            let chunk = bytes.get(i * 8..i * 8 + 8)?;
+            Some(u64::from_le_bytes(chunk.try_into().ok()?))
+        };
+        Some(Self {
+            max_memory: field(0)?,
+            max_endpoints: field(1)?,
+            max_cap_slots: field(2)?,
+            max_queued_bytes: field(3)?,
+            max_syscalls_per_sec: field(4)?,
+        })
+    }
 }
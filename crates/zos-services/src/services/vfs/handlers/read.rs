@@ -16,8 +16,8 @@ use zos_apps::syscall;
 use zos_apps::{AppContext, AppError, Message};
 use zos_process::storage_result;
 use zos_vfs::ipc::{
-    vfs_msg, ExistsRequest, ExistsResponse, ReadFileRequest, ReadFileResponse, ReaddirRequest,
-    ReaddirResponse, StatRequest, StatResponse,
+    shared_body, vfs_msg, ExistsRequest, ExistsResponse, ReadFileRequest, ReadFileResponse,
+    ReaddirRequest, ReaddirResponse, StatRequest, StatResponse,
 };
 use zos_vfs::service::{check_read, PermissionContext};
 use zos_vfs::{DirEntry, Inode};
@@ -129,6 +129,7 @@ impl VfsService {
             Err(e) => {
                 let response = ReadFileResponse {
                     result: Err(VfsError::InvalidRequest(format!("Failed to parse request: {}", e))),
+                    shared_len: None,
                 };
                 return self.send_response_via_debug(
                     msg.from_pid,
@@ -138,10 +139,18 @@ impl VfsService {
             }
         };
 
+        // Content can be returned in a region lent with the request
+        let mut client_ctx = ClientContext::from_message(msg);
+        if let Some(capacity) = request.shared_capacity {
+            client_ctx.lend_body_region(capacity);
+        }
+
         // Validate path
         if let Err(reason) = validate_path(&request.path) {
+            client_ctx.release_body_region();
             let response = ReadFileResponse {
                 result: Err(VfsError::InvalidPath(String::from(reason))),
+                shared_len: None,
             };
             return self.send_response_via_debug(
                 msg.from_pid,
@@ -154,7 +163,6 @@ impl VfsService {
 
         // Derive permission context from caller
         let perm_ctx = derive_permission_context(msg.from_pid, &request.path);
-        let client_ctx = ClientContext::from_message(msg);
 
         // First check inode exists and is a file
         self.start_storage_read(
@@ -303,6 +311,7 @@ impl VfsService {
                         ));
                         let response = ReadFileResponse {
                             result: Err(VfsError::PermissionDenied),
+                            shared_len: None,
                         };
                         return self.send_response(
                             client_ctx,
@@ -323,6 +332,7 @@ impl VfsService {
                 Ok(_) => {
                     let response = ReadFileResponse {
                         result: Err(VfsError::NotAFile),
+                        shared_len: None,
                     };
                     self.send_response(
                         client_ctx,
@@ -333,6 +343,7 @@ impl VfsService {
                 Err(e) => {
                     let response = ReadFileResponse {
                         result: Err(VfsError::StorageError(e.to_string())),
+                        shared_len: None,
                     };
                     self.send_response(
                         client_ctx,
@@ -344,6 +355,7 @@ impl VfsService {
         } else if result_type == storage_result::NOT_FOUND {
             let response = ReadFileResponse {
                 result: Err(VfsError::NotFound),
+                shared_len: None,
             };
             self.send_response(client_ctx, vfs_msg::MSG_VFS_READ_RESPONSE, &response)
         } else {
@@ -351,6 +363,7 @@ impl VfsService {
                 result: Err(VfsError::StorageError(
                     String::from_utf8_lossy(data).to_string(),
                 )),
+                shared_len: None,
             };
             self.send_response(client_ctx, vfs_msg::MSG_VFS_READ_RESPONSE, &response)
         }
@@ -366,8 +379,35 @@ impl VfsService {
     ) -> Result<(), AppError> {
         let response = match result_type {
             storage_result::READ_OK => {
+                // Fill a region lent by the client, falling back to inline
+                // content if it does not fit
+                if let Some((slot, capacity)) = client_ctx.body_region {
+                    let filled = shared_body::fill_lent(slot, capacity, data);
+                    let ctx = ClientContext {
+                        body_region: None,
+                        ..client_ctx.clone()
+                    };
+                    let response = match filled {
+                        Ok(()) => ReadFileResponse {
+                            result: Ok(Vec::new()),
+                            shared_len: Some(data.len() as u64),
+                        },
+                        Err(e) => {
+                            syscall::debug(&format!(
+                                "VfsService: read {} returning content inline: {:?}",
+                                path, e
+                            ));
+                            ReadFileResponse {
+                                result: Ok(data.to_vec()),
+                                shared_len: None,
+                            }
+                        }
+                    };
+                    return self.send_response(&ctx, vfs_msg::MSG_VFS_READ_RESPONSE, &response);
+                }
                 ReadFileResponse {
                     result: Ok(data.to_vec()),
+                    shared_len: None,
                 }
             }
             storage_result::NOT_FOUND => {
@@ -381,6 +421,7 @@ impl VfsService {
                     result: Err(VfsError::StorageError(
                         "Content missing for existing inode".into(),
                     )),
+                    shared_len: None,
                 }
             }
             _ => {
@@ -396,6 +437,7 @@ impl VfsService {
                         result_type,
                         result_type_name(result_type)
                     ))),
+                    shared_len: None,
                 }
             }
         };
@@ -84,7 +84,7 @@ impl VfsService {
     /// 4. Send response (only after inode succeeds)
     pub fn handle_write(&mut self, _ctx: &AppContext, msg: &Message) -> Result<(), AppError> {
         // Parse request
-        let request: WriteFileRequest = match serde_json::from_slice(&msg.data) {
+        let mut request: WriteFileRequest = match serde_json::from_slice(&msg.data) {
             Ok(r) => r,
             Err(e) => {
                 return self.send_write_error_via_debug(
@@ -94,6 +94,15 @@ impl VfsService {
             }
         };
 
+        // Large bodies arrive in a shared region rather than in the request
+        let mut client_ctx = ClientContext::from_message(msg);
+        if let Some(shared_len) = request.shared_len {
+            match client_ctx.take_shared_body(shared_len) {
+                Ok(content) => request.content = content,
+                Err(e) => return self.send_write_error_via_debug(msg.from_pid, e),
+            }
+        }
+
         // Validate path
         if let Err(reason) = validate_path(&request.path) {
             return self.send_write_error_via_debug(
@@ -130,7 +139,6 @@ impl VfsService {
 
         // Derive permission context from caller
         let perm_ctx = derive_permission_context(msg.from_pid, &request.path);
-        let client_ctx = ClientContext::from_message(msg);
 
         // Use inode/content pattern for VFS operations
         let parent = parent_path(&request.path);
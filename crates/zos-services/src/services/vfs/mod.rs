@@ -92,8 +92,9 @@ use crate::manifests::VFS_MANIFEST;
 use zos_apps::syscall;
 use zos_apps::{AppContext, AppError, AppManifest, ControlFlow, Message, ZeroApp};
 use zos_process::MSG_STORAGE_RESULT;
-use zos_vfs::ipc::vfs_msg;
+use zos_vfs::ipc::{shared_body, vfs_msg};
 use zos_vfs::service::{PermissionContext, ProcessClass};
+use zos_vfs::VfsError;
 
 // =============================================================================
 // Resource Limits (Rule 11)
@@ -150,12 +151,16 @@ pub fn result_type_name(result_type: u8) -> &'static str {
 /// Captures information needed to send responses:
 /// - `pid`: The client process ID
 /// - `reply_caps`: Capability slots for direct IPC reply (transferred from request)
+/// - `body_region`: Shared region lent by the client for a read's content
 #[derive(Clone, Debug)]
 pub struct ClientContext {
     /// Client process ID
     pub pid: u32,
     /// Reply capability slots (for direct IPC response)
     pub reply_caps: Vec<u32>,
+    /// Slot and capacity of a region to return content in (released with
+    /// the response)
+    pub body_region: Option<(u32, usize)>,
 }
 
 impl ClientContext {
@@ -164,6 +169,45 @@ impl ClientContext {
         Self {
             pid: msg.from_pid,
             reply_caps: msg.cap_slots.clone(),
+            body_region: None,
+        }
+    }
+
+    /// Copy out a write body of `len` bytes from the shared region attached
+    /// last to the request, releasing the region.
+    pub fn take_shared_body(&mut self, len: u64) -> Result<Vec<u8>, VfsError> {
+        let slot = self.reply_caps.pop().ok_or_else(|| {
+            VfsError::InvalidRequest(String::from("Shared body without a region capability"))
+        })?;
+        let len = len as usize;
+        if len > MAX_CONTENT_SIZE {
+            let _ = syscall::cap_delete(slot);
+            return Err(VfsError::InvalidRequest(format!(
+                "Content too large: {} bytes exceeds limit of {} bytes",
+                len, MAX_CONTENT_SIZE
+            )));
+        }
+        shared_body::take_body(slot, len, len)
+    }
+
+    /// Keep the region attached last to a read request to return the
+    /// content in. Oversized capacities are refused and the content is
+    /// returned inline instead.
+    pub fn lend_body_region(&mut self, capacity: u64) {
+        let Some(slot) = self.reply_caps.pop() else {
+            return;
+        };
+        if capacity as usize > MAX_CONTENT_SIZE {
+            let _ = syscall::cap_delete(slot);
+            return;
+        }
+        self.body_region = Some((slot, capacity as usize));
+    }
+
+    /// Drop the lent region, if any, without filling it
+    pub fn release_body_region(&self) {
+        if let Some((slot, _)) = self.body_region {
+            let _ = syscall::cap_delete(slot);
         }
     }
 }
@@ -865,6 +909,9 @@ impl VfsService {
         tag: u32,
         response: &T,
     ) -> Result<(), AppError> {
+        // A lent region not used for this response is no longer needed
+        ctx.release_body_region();
+
         match serde_json::to_vec(response) {
             Ok(data) => {
                 // Try direct IPC via reply capability first
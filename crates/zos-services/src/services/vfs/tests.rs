@@ -21,6 +21,7 @@ mod tests {
         ClientContext {
             pid,
             reply_caps: Vec::new(),
+            body_region: None,
         }
     }
 
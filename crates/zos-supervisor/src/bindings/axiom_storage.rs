@@ -1,10 +1,12 @@
 //! Axiom Storage - IndexedDB persistence for WASM targets
 //!
 //! Provides wasm-bindgen bindings to the ZosStorageAxiom JavaScript object
-//! for persisting CommitLog entries to IndexedDB.
+//! for persisting CommitLog entries and signed checkpoints to IndexedDB.
 
 use wasm_bindgen::prelude::*;
 
+use crate::util::{bytes_to_hex, hex_to_bytes};
+
 #[wasm_bindgen]
 extern "C" {
     /// ZosStorageAxiom JavaScript object for IndexedDB persistence
@@ -28,6 +30,50 @@ extern "C" {
 
     #[wasm_bindgen(js_namespace = ZosStorageAxiom)]
     pub async fn clear() -> JsValue;
+
+    #[wasm_bindgen(js_namespace = ZosStorageAxiom)]
+    pub async fn persistCheckpoints(checkpoints: JsValue) -> JsValue;
+
+    #[wasm_bindgen(js_namespace = ZosStorageAxiom)]
+    pub async fn loadCheckpoints() -> JsValue;
+}
+
+/// Serialize a signed checkpoint to a JavaScript object for IndexedDB storage
+pub(crate) fn checkpoint_to_js(checkpoint: &zos_kernel::Checkpoint) -> JsValue {
+    let obj = js_sys::Object::new();
+    let _ = js_sys::Reflect::set(&obj, &"seq".into(), &JsValue::from_f64(checkpoint.seq as f64));
+    let _ = js_sys::Reflect::set(
+        &obj,
+        &"timestamp".into(),
+        &JsValue::from_f64(checkpoint.timestamp as f64),
+    );
+    let _ = js_sys::Reflect::set(&obj, &"head".into(), &JsValue::from_str(&bytes_to_hex(&checkpoint.head)));
+    let _ = js_sys::Reflect::set(
+        &obj,
+        &"signer".into(),
+        &JsValue::from_str(&bytes_to_hex(&checkpoint.signer)),
+    );
+    let _ = js_sys::Reflect::set(
+        &obj,
+        &"signature".into(),
+        &JsValue::from_str(&bytes_to_hex(&checkpoint.signature)),
+    );
+    obj.into()
+}
+
+/// Parse a checkpoint stored by [`checkpoint_to_js`].
+///
+/// Returns `None` if any field is missing or malformed.
+pub(crate) fn checkpoint_from_js(value: &JsValue) -> Option<zos_kernel::Checkpoint> {
+    let field = |name: &str| js_sys::Reflect::get(value, &name.into()).ok();
+    let hex_field = |name: &str| hex_to_bytes(&field(name)?.as_string()?).ok();
+    Some(zos_kernel::Checkpoint {
+        seq: field("seq")?.as_f64()? as u64,
+        timestamp: field("timestamp")?.as_f64()? as u64,
+        head: hex_field("head")?.try_into().ok()?,
+        signer: hex_field("signer")?.try_into().ok()?,
+        signature: hex_field("signature")?,
+    })
 }
 
 /// Serialize a Commit entry to a JavaScript object for IndexedDB storage
@@ -102,12 +148,34 @@ pub(crate) fn commit_type_to_string(ct: &zos_kernel::CommitType) -> String {
             "CapGranted(from={}.{} to={}.{})",
             from_pid, from_slot, to_pid, to_slot
         ),
+        zos_kernel::CommitType::CapBadged { pid, slot, badge } => {
+            format!("CapBadged(pid={}, slot={}, badge={:#x})", pid, slot, badge)
+        }
+        zos_kernel::CommitType::CapExpiry {
+            pid,
+            slot,
+            expires_at,
+        } => format!(
+            "CapExpiry(pid={}, slot={}, expires_at={})",
+            pid, slot, expires_at
+        ),
         zos_kernel::CommitType::EndpointCreated { id, owner } => {
             format!("EndpointCreated(id={}, owner={})", id, owner)
         }
         zos_kernel::CommitType::EndpointDestroyed { id } => {
             format!("EndpointDestroyed(id={})", id)
         }
+        zos_kernel::CommitType::NotificationCreated { id, owner } => {
+            format!("NotificationCreated(id={}, owner={})", id, owner)
+        }
+        zos_kernel::CommitType::NotificationSignaled { from, id, bits } => format!(
+            "NotificationSignaled(from={}, id={}, bits={:#x})",
+            from, id, bits
+        ),
+        zos_kernel::CommitType::NotificationTaken { pid, id, bits } => format!(
+            "NotificationTaken(pid={}, id={}, bits={:#x})",
+            pid, id, bits
+        ),
         zos_kernel::CommitType::MessageSent {
             from_pid,
             to_endpoint,
@@ -117,6 +185,61 @@ pub(crate) fn commit_type_to_string(ct: &zos_kernel::CommitType) -> String {
             "MessageSent(from={}, ep={}, tag={}, size={})",
             from_pid, to_endpoint, tag, size
         ),
+        zos_kernel::CommitType::Replied {
+            from,
+            to,
+            tag,
+            size,
+        } => format!(
+            "Replied(from={}, to={}, tag={}, size={})",
+            from, to, tag, size
+        ),
+        zos_kernel::CommitType::CallAborted { caller } => {
+            format!("CallAborted(caller={})", caller)
+        }
+        zos_kernel::CommitType::Snapshot { state, state_hash } => format!(
+            "Snapshot(bytes={}, hash={:02x}{:02x}{:02x}{:02x})",
+            state.len(),
+            state_hash[0],
+            state_hash[1],
+            state_hash[2],
+            state_hash[3]
+        ),
+        zos_kernel::CommitType::QuotaSet { pid, by, .. } => {
+            format!("QuotaSet(pid={}, by={})", pid, by)
+        }
+        zos_kernel::CommitType::QuotaExceeded { pid, resource } => {
+            format!("QuotaExceeded(pid={}, resource={})", pid, resource)
+        }
+        zos_kernel::CommitType::IrqBound {
+            irq,
+            pid,
+            target_type,
+            target_id,
+            bits,
+        } => format!(
+            "IrqBound(irq={}, pid={}, target={}:{}, bits={:#x})",
+            irq, pid, target_type, target_id, bits
+        ),
+        zos_kernel::CommitType::IrqUnbound { irq } => format!("IrqUnbound(irq={})", irq),
+        zos_kernel::CommitType::IrqRaised { irq } => format!("IrqRaised(irq={})", irq),
+        zos_kernel::CommitType::IrqAcked { irq } => format!("IrqAcked(irq={})", irq),
+        zos_kernel::CommitType::RegionCreated { id, owner, size } => {
+            format!("RegionCreated(id={}, owner={}, size={})", id, owner, size)
+        }
+        zos_kernel::CommitType::RegionMapped {
+            pid,
+            id,
+            writable,
+            window,
+        } => format!(
+            "RegionMapped(pid={}, id={}, writable={}, window={:#x})",
+            pid, id, writable, window
+        ),
+        zos_kernel::CommitType::RegionUnmapped { pid, id } => {
+            format!("RegionUnmapped(pid={}, id={})", pid, id)
+        }
+        zos_kernel::CommitType::RegionDestroyed { id } => format!("RegionDestroyed(id={})", id),
     }
 }
 
@@ -131,8 +254,26 @@ pub(crate) fn commit_type_short(ct: &zos_kernel::CommitType) -> &'static str {
         zos_kernel::CommitType::CapInserted { .. } => "CapInsert",
         zos_kernel::CommitType::CapRemoved { .. } => "CapRemove",
         zos_kernel::CommitType::CapGranted { .. } => "CapGrant",
+        zos_kernel::CommitType::CapBadged { .. } => "CapBadge",
+        zos_kernel::CommitType::CapExpiry { .. } => "CapExpiry",
         zos_kernel::CommitType::EndpointCreated { .. } => "EpCreate",
         zos_kernel::CommitType::EndpointDestroyed { .. } => "EpDestroy",
+        zos_kernel::CommitType::NotificationCreated { .. } => "NtfnCreate",
+        zos_kernel::CommitType::NotificationSignaled { .. } => "NtfnSignal",
+        zos_kernel::CommitType::NotificationTaken { .. } => "NtfnTake",
         zos_kernel::CommitType::MessageSent { .. } => "MsgSent",
+        zos_kernel::CommitType::Replied { .. } => "Replied",
+        zos_kernel::CommitType::CallAborted { .. } => "CallAbort",
+        zos_kernel::CommitType::Snapshot { .. } => "Snapshot",
+        zos_kernel::CommitType::QuotaSet { .. } => "QuotaSet",
+        zos_kernel::CommitType::QuotaExceeded { .. } => "QuotaExceed",
+        zos_kernel::CommitType::IrqBound { .. } => "IrqBind",
+        zos_kernel::CommitType::IrqUnbound { .. } => "IrqUnbind",
+        zos_kernel::CommitType::IrqRaised { .. } => "IrqRaise",
+        zos_kernel::CommitType::IrqAcked { .. } => "IrqAck",
+        zos_kernel::CommitType::RegionCreated { .. } => "RegionCreate",
+        zos_kernel::CommitType::RegionMapped { .. } => "RegionMap",
+        zos_kernel::CommitType::RegionUnmapped { .. } => "RegionUnmap",
+        zos_kernel::CommitType::RegionDestroyed { .. } => "RegionDestroy",
     }
 }
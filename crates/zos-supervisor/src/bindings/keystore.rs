@@ -1,7 +1,8 @@
 //! Keystore - IndexedDB persistence for cryptographic keys
 //!
 //! This module provides async access to ZosKeystore for bootstrap operations.
-//! It initializes the zos-keystore IndexedDB database during supervisor boot.
+//! It initializes the zos-keystore IndexedDB database during supervisor boot,
+//! and holds the kernel's own secrets (the checkpoint key seed).
 //!
 //! ## Why This Module Exists
 //!
@@ -17,4 +18,16 @@ extern "C" {
     /// Note: Using unique Rust name to avoid wasm-bindgen conflict with vfs_storage::init
     #[wasm_bindgen(js_namespace = ZosKeystore, js_name = init)]
     pub async fn keystore_init() -> JsValue;
+
+    /// Read key bytes by path (resolves to a Uint8Array, or null if absent)
+    #[wasm_bindgen(js_namespace = ZosKeystore, js_name = getKey, catch)]
+    pub async fn keystore_get_key(path: &str) -> Result<JsValue, JsValue>;
+
+    /// Store key bytes under a path
+    #[wasm_bindgen(js_namespace = ZosKeystore, js_name = putKey, catch)]
+    pub async fn keystore_put_key(
+        path: &str,
+        data: &js_sys::Uint8Array,
+        user_id: &str,
+    ) -> Result<JsValue, JsValue>;
 }
@@ -44,6 +44,13 @@ pub const VFS_INPUT_SLOT: u32 = SERVICE_INPUT_SLOT;
 /// Keystore Service input slot
 pub const KEYSTORE_INPUT_SLOT: u32 = SERVICE_INPUT_SLOT;
 
+// =============================================================================
+// Axiom
+// =============================================================================
+
+/// Sign a CommitLog checkpoint every this many commits.
+pub const CHECKPOINT_INTERVAL: u64 = 256;
+
 // =============================================================================
 // Syscall Numbers (frequently used in supervisor)
 // =============================================================================
@@ -22,6 +22,7 @@ use crate::worker::{self, PendingSyscall, WasmProcessHandle, WorkerMessage, Work
 
 mod network;
 mod process;
+mod region;
 mod storage;
 
 /// Maximum number of pending storage requests to prevent unbounded growth.
@@ -59,6 +60,8 @@ pub struct WasmHal {
     next_keystore_request_id: AtomicU32,
     /// Pending keystore requests: request_id -> requesting PID
     pending_keystore_requests: Arc<Mutex<HashMap<u32, u64>>>,
+    /// Shared region contents: region_id -> bytes
+    regions: Arc<Mutex<HashMap<u64, Vec<u8>>>>,
 }
 
 impl WasmHal {
@@ -74,6 +77,7 @@ impl WasmHal {
             pending_network_requests: Arc::new(Mutex::new(HashMap::new())),
             next_keystore_request_id: AtomicU32::new(1),
             pending_keystore_requests: Arc::new(Mutex::new(HashMap::new())),
+            regions: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
@@ -257,6 +261,22 @@ impl WasmHal {
         }
     }
 
+    /// Mark a worker's syscall as parked
+    ///
+    /// The worker stays blocked in its mailbox, and `poll_syscalls` no longer
+    /// reports the syscall, until `complete_syscall` answers it.
+    pub fn park_syscall(&self, pid: u64) {
+        if let Ok(processes) = self.processes.lock() {
+            if let Some(proc) = processes.get(&pid) {
+                let _ = js_sys::Atomics::store(
+                    &proc.mailbox_view,
+                    worker::MAILBOX_STATUS,
+                    worker::STATUS_PARKED,
+                );
+            }
+        }
+    }
+
     /// Read data from a worker's syscall mailbox
     pub fn read_syscall_data(&self, pid: u64) -> Vec<u8> {
         let mut data = Vec::new();
@@ -537,4 +557,36 @@ impl HAL for WasmHal {
     fn take_network_request_pid(&self, request_id: NetworkRequestId) -> Option<u64> {
         self.do_take_network_request_pid(request_id)
     }
+
+    // === Shared Memory Regions ===
+
+    fn region_alloc(&self, id: u64, size: usize) -> Result<(), HalError> {
+        self.do_region_alloc(id, size)
+    }
+
+    fn region_free(&self, id: u64) {
+        self.do_region_free(id)
+    }
+
+    fn region_copy_to_process(
+        &self,
+        id: u64,
+        offset: usize,
+        pid: u64,
+        addr: u32,
+        len: usize,
+    ) -> Result<(), HalError> {
+        self.do_region_copy_to_process(id, offset, pid, addr, len)
+    }
+
+    fn region_copy_from_process(
+        &self,
+        id: u64,
+        offset: usize,
+        pid: u64,
+        addr: u32,
+        len: usize,
+    ) -> Result<(), HalError> {
+        self.do_region_copy_from_process(id, offset, pid, addr, len)
+    }
 }
@@ -0,0 +1,89 @@
+//! Shared memory regions for WASM HAL
+//!
+//! Region contents live in supervisor buffers. Each Worker's linear memory
+//! is a SharedArrayBuffer the supervisor already holds for the syscall
+//! mailbox, so mapping, flushing and refreshing a region are copies between
+//! that buffer and the region while the Worker is blocked in its syscall.
+
+use zos_hal::HalError;
+
+use super::WasmHal;
+
+impl WasmHal {
+    /// Allocate a zeroed buffer for region `id`
+    pub fn do_region_alloc(&self, id: u64, size: usize) -> Result<(), HalError> {
+        let mut regions = self.regions.lock().map_err(|_| HalError::OutOfMemory)?;
+        regions.insert(id, vec![0; size]);
+        Ok(())
+    }
+
+    /// Drop the buffer of region `id`
+    pub fn do_region_free(&self, id: u64) {
+        if let Ok(mut regions) = self.regions.lock() {
+            regions.remove(&id);
+        }
+    }
+
+    /// Copy part of region `id` into a Worker's linear memory
+    pub fn do_region_copy_to_process(
+        &self,
+        id: u64,
+        offset: usize,
+        pid: u64,
+        addr: u32,
+        len: usize,
+    ) -> Result<(), HalError> {
+        let regions = self.regions.lock().map_err(|_| HalError::InvalidArgument)?;
+        let src = regions
+            .get(&id)
+            .and_then(|region| region.get(offset..offset.checked_add(len)?))
+            .ok_or(HalError::InvalidArgument)?;
+        self.with_window(pid, addr, len, |window| window.copy_from(src))
+    }
+
+    /// Copy part of a Worker's linear memory into region `id`
+    pub fn do_region_copy_from_process(
+        &self,
+        id: u64,
+        offset: usize,
+        pid: u64,
+        addr: u32,
+        len: usize,
+    ) -> Result<(), HalError> {
+        let mut regions = self.regions.lock().map_err(|_| HalError::InvalidArgument)?;
+        let dst = regions
+            .get_mut(&id)
+            .and_then(|region| region.get_mut(offset..offset.checked_add(len)?))
+            .ok_or(HalError::InvalidArgument)?;
+        self.with_window(pid, addr, len, |window| window.copy_to(dst))
+    }
+
+    /// Run `f` on a view of `len` bytes at `addr` in a Worker's memory
+    fn with_window(
+        &self,
+        pid: u64,
+        addr: u32,
+        len: usize,
+        f: impl FnOnce(js_sys::Uint8Array),
+    ) -> Result<(), HalError> {
+        let processes = self
+            .processes
+            .lock()
+            .map_err(|_| HalError::ProcessNotFound)?;
+        let proc = processes.get(&pid).ok_or(HalError::ProcessNotFound)?;
+        // Workers that have not sent their memory yet have nothing to map
+        if proc.worker_id == 0 {
+            return Err(HalError::NotSupported);
+        }
+        let end = addr as u64 + len as u64;
+        if end > proc.syscall_buffer.byte_length() as u64 {
+            return Err(HalError::InvalidArgument);
+        }
+        f(js_sys::Uint8Array::new_with_byte_offset_and_length(
+            &proc.syscall_buffer,
+            addr,
+            len as u32,
+        ));
+        Ok(())
+    }
+}
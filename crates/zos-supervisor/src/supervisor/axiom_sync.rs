@@ -1,16 +1,45 @@
 //! Axiom IndexedDB persistence
 //!
 //! Handles syncing the kernel's CommitLog and SysLog to browser IndexedDB.
+//!
+//! The machine's checkpoint key seeds are generated on first boot and kept
+//! in the keystore database, under the namespace the kernel reserves for
+//! itself, so checkpoints from every boot verify under the same public key.
+//! They never share a store with the log they sign.
 
 use wasm_bindgen::prelude::*;
+use zos_hal::HAL;
+use zos_identity::{MachineCheckpointSigner, MachineCheckpointVerifier};
+use zos_kernel::{Checkpoint, CheckpointSigner};
 
 use super::{log, Supervisor};
-use crate::bindings::axiom_storage;
+use crate::bindings::{axiom_storage, keystore};
+use crate::constants::CHECKPOINT_INTERVAL;
+use crate::util::bytes_to_hex;
+
+/// Keystore path of the checkpoint key seeds, under
+/// [`zos_kernel::KERNEL_KEYSTORE_PREFIX`] so processes cannot read them.
+const CHECKPOINT_SEED_KEY: &str = "/kernel/checkpoint_seed";
+
+/// What axiom initialization found in IndexedDB.
+struct AxiomInit {
+    /// Next sequence number to persist
+    next_seq: u64,
+    /// Number of persisted commits
+    count: u64,
+    /// Checkpoint key seeds (signing seed, then encryption seed), if the
+    /// keystore could provide them
+    seed: Option<[u8; 64]>,
+    /// Checkpoints persisted by earlier boots
+    checkpoints: Vec<Checkpoint>,
+}
 
 /// Internal async helper for axiom initialization.
 /// This is a standalone async function that doesn't hold any borrows,
 /// avoiding wasm-bindgen closure issues with &mut self across await points.
-async fn do_axiom_init() -> Option<(u64, u64)> {
+///
+/// `fresh_seed` is stored as the checkpoint key if none exists yet.
+async fn do_axiom_init(fresh_seed: [u8; 64]) -> Option<AxiomInit> {
     let result = axiom_storage::init().await;
     if !result.is_truthy() {
         log("[axiom] Failed to initialize IndexedDB");
@@ -23,7 +52,64 @@ async fn do_axiom_init() -> Option<(u64, u64)> {
     let seq_num = last_seq.as_f64().map(|s| if s < 0.0 { 0 } else { s as u64 + 1 });
     let count_num = count.as_f64().map(|n| n as u64);
 
-    Some((seq_num.unwrap_or(0), count_num.unwrap_or(0)))
+    let seed = load_checkpoint_seed(fresh_seed).await;
+
+    let stored = js_sys::Array::from(&axiom_storage::loadCheckpoints().await);
+    let checkpoints = stored
+        .iter()
+        .filter_map(|value| axiom_storage::checkpoint_from_js(&value))
+        .collect();
+
+    Some(AxiomInit {
+        next_seq: seq_num.unwrap_or(0),
+        count: count_num.unwrap_or(0),
+        seed,
+        checkpoints,
+    })
+}
+
+/// Read the checkpoint key seeds from the keystore, storing `fresh_seed`
+/// there if none exist yet.
+async fn load_checkpoint_seed(fresh_seed: [u8; 64]) -> Option<[u8; 64]> {
+    if !keystore::keystore_init().await.is_truthy() {
+        log("[axiom] Failed to initialize the keystore for the checkpoint key");
+        return None;
+    }
+
+    let stored = match keystore::keystore_get_key(CHECKPOINT_SEED_KEY).await {
+        Ok(value) if value.is_null() => None,
+        Ok(value) => Some(js_sys::Uint8Array::new(&value).to_vec()),
+        Err(e) => {
+            log(&format!(
+                "[axiom] Failed to read the checkpoint key: {:?}",
+                e
+            ));
+            return None;
+        }
+    };
+    match stored {
+        Some(bytes) => match <[u8; 64]>::try_from(bytes) {
+            Ok(seed) => Some(seed),
+            Err(_) => {
+                log("[axiom] Stored checkpoint key is malformed");
+                None
+            }
+        },
+        None => {
+            log("[axiom] Generating checkpoint key");
+            let data = js_sys::Uint8Array::from(&fresh_seed[..]);
+            match keystore::keystore_put_key(CHECKPOINT_SEED_KEY, &data, "kernel").await {
+                Ok(_) => Some(fresh_seed),
+                Err(e) => {
+                    log(&format!(
+                        "[axiom] Failed to store the checkpoint key: {:?}",
+                        e
+                    ));
+                    None
+                }
+            }
+        }
+    }
 }
 
 #[wasm_bindgen]
@@ -47,17 +133,27 @@ impl Supervisor {
 
         log("[axiom] Initializing IndexedDB storage...");
 
+        let mut fresh_seed = [0u8; 64];
+        if self.system.hal().random_bytes(&mut fresh_seed).is_err() {
+            log("[axiom] No randomness for a checkpoint key");
+            return Ok(JsValue::from_bool(false));
+        }
+
         // Perform all async work in a standalone function that doesn't borrow self.
         // This avoids wasm-bindgen issues with holding &mut self across await points.
-        let result = do_axiom_init().await;
+        let result = do_axiom_init(fresh_seed).await;
 
         // Update state synchronously based on result
         match result {
-            Some((last_seq, count)) => {
+            Some(init) => {
                 self.axiom_storage_ready = true;
-                self.last_persisted_axiom_seq = last_seq;
-                log(&format!("[axiom] Storage ready, last_seq={}", last_seq));
-                log(&format!("[axiom] {} entries in IndexedDB", count));
+                self.last_persisted_axiom_seq = init.next_seq;
+                log(&format!("[axiom] Storage ready, last_seq={}", init.next_seq));
+                log(&format!("[axiom] {} entries in IndexedDB", init.count));
+                match init.seed {
+                    Some(seed) => self.enable_checkpoints(&seed, &init.checkpoints),
+                    None => log("[axiom] Checkpoints disabled: no checkpoint key"),
+                }
                 Ok(JsValue::from_bool(true))
             }
             None => Ok(JsValue::from_bool(false)),
@@ -98,9 +194,19 @@ impl Supervisor {
             js_entries.push(&axiom_storage::commit_to_js(commit));
         }
 
-        // Persist to IndexedDB - the only await point
+        // Checkpoints are taken as commits are appended, so the same window
+        // covers every checkpoint not yet persisted
+        let js_checkpoints = js_sys::Array::new();
+        for checkpoint in self.system.checkpoints() {
+            if checkpoint.seq >= self.last_persisted_axiom_seq {
+                js_checkpoints.push(&axiom_storage::checkpoint_to_js(checkpoint));
+            }
+        }
+
+        // Persist to IndexedDB - the only await points
         // Note: We prepare all data before awaiting to minimize borrow duration issues
         let result = axiom_storage::persistEntries(js_entries.into()).await;
+        axiom_storage::persistCheckpoints(js_checkpoints.into()).await;
         
         // Update state synchronously after await
         if let Some(count) = result.as_f64() {
@@ -136,3 +242,42 @@ impl Supervisor {
         }
     }
 }
+
+impl Supervisor {
+    /// Start signing CommitLog checkpoints with the machine's checkpoint key.
+    ///
+    /// Checkpoints persisted by earlier boots are verified against the key
+    /// first; a mismatch means the stored log was not produced here.
+    fn enable_checkpoints(&mut self, seed: &[u8; 64], stored: &[Checkpoint]) {
+        let (signing_seed, encryption_seed) = seed.split_at(32);
+        let signer = match MachineCheckpointSigner::from_seeds(
+            signing_seed.try_into().expect("32-byte half"),
+            encryption_seed.try_into().expect("32-byte half"),
+        ) {
+            Ok(signer) => signer,
+            Err(e) => {
+                log(&format!("[axiom] Checkpoints disabled: {:?}", e));
+                return;
+            }
+        };
+
+        let public_key = signer.public_key();
+        let verified = stored
+            .iter()
+            .filter(|cp| cp.verify(&public_key, &MachineCheckpointVerifier))
+            .count();
+        if verified == stored.len() {
+            log(&format!("[axiom] {} stored checkpoint(s) verified", verified));
+        } else {
+            log(&format!(
+                "[axiom] WARNING: {} of {} stored checkpoint(s) failed verification",
+                stored.len() - verified,
+                stored.len()
+            ));
+        }
+
+        log(&format!("[axiom] Checkpoint key {}", bytes_to_hex(&public_key)));
+        self.system
+            .set_checkpoint_signer(Box::new(signer), CHECKPOINT_INTERVAL);
+    }
+}
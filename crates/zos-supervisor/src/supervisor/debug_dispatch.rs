@@ -7,6 +7,7 @@
 //! - Capability operations (INIT:GRANT:, INIT:REVOKE:)
 //! - Permission responses
 //! - Service IPC responses
+//! - History queries (HISTORY:STATE_AT:, HISTORY:DIFF:)
 //! - Console output
 
 use zos_hal::HAL;
@@ -51,6 +52,10 @@ impl Supervisor {
             self.handle_init_endpoint_response(rest);
         } else if let Some(rest) = msg.strip_prefix(debug::CAP_RESPONSE) {
             self.handle_init_cap_response(rest);
+        } else if let Some(rest) = msg.strip_prefix(debug::HISTORY_STATE_AT) {
+            self.handle_history_state_at(pid, rest);
+        } else if let Some(rest) = msg.strip_prefix(debug::HISTORY_DIFF) {
+            self.handle_history_diff(pid, rest);
         } else if let Some(rest) = msg.strip_prefix("ERROR:IPC_DELIVERY_FAILED:no_capability:") {
             self.handle_ipc_delivery_failed(rest);
         } else if msg.starts_with(debug::AGENT_LOG) {
@@ -264,6 +269,53 @@ impl Supervisor {
         // No HAL cleanup needed since kernel kill failed
     }
 
+    /// Handle HISTORY:STATE_AT: from a terminal.
+    ///
+    /// Rebuilds the kernel state after the given commit and writes a summary
+    /// back to the requesting process's console.
+    ///
+    /// Format: "HISTORY:STATE_AT:{seq}"
+    fn handle_history_state_at(&mut self, pid: ProcessId, seq_str: &str) {
+        let text = match seq_str.trim().parse::<u64>() {
+            Err(_) => format!("at: invalid sequence '{}'\n", seq_str),
+            Ok(seq) => match self.system.state_at(seq) {
+                Err(e) => format!("at: cannot rebuild state at seq {}: {:?}\n", seq, e),
+                Ok(state) => {
+                    let mut out = format!("State at commit seq {}:\n", seq);
+                    for (p, proc) in state.list_processes() {
+                        let caps = state.get_cap_space(p).map(|cs| cs.len()).unwrap_or(0);
+                        out.push_str(&format!(
+                            "  PID {:>3} {:<16} {:?} ({} caps)\n",
+                            p.0, proc.name, proc.state, caps
+                        ));
+                    }
+                    for ep in state.list_endpoints() {
+                        out.push_str(&format!("  endpoint {} (owner {})\n", ep.id.0, ep.owner.0));
+                    }
+                    out
+                }
+            },
+        };
+        self.write_console_to_process(pid.0, &text);
+    }
+
+    /// Handle HISTORY:DIFF: from a terminal.
+    ///
+    /// Format: "HISTORY:DIFF:{from}:{to}"
+    fn handle_history_diff(&mut self, pid: ProcessId, rest: &str) {
+        let seqs = rest
+            .split_once(':')
+            .and_then(|(a, b)| Some((a.trim().parse::<u64>().ok()?, b.trim().parse::<u64>().ok()?)));
+        let text = match seqs {
+            None => format!("diff: invalid sequences '{}'\n", rest),
+            Some((from, to)) => match self.system.diff_seqs(from, to) {
+                Err(e) => format!("diff: cannot compare seq {} and {}: {:?}\n", from, to, e),
+                Ok(diff) => format!("Changes from seq {} to {}:\n{}", from, to, diff),
+            },
+        };
+        self.write_console_to_process(pid.0, &text);
+    }
+
     /// Handle INIT:SPAWN: debug message.
     fn handle_debug_spawn(&mut self, service_name: &str) {
         log(&format!(
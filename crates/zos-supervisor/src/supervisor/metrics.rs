@@ -164,6 +164,7 @@ impl Supervisor {
                     "ipc_sent": proc.metrics.ipc_sent,
                     "ipc_received": proc.metrics.ipc_received,
                     "syscalls": proc.metrics.syscall_count,
+                    "quota": proc.quota,
                     "worker_id": worker_id
                 })
             })
@@ -187,6 +188,8 @@ impl Supervisor {
                         zos_kernel::ObjectType::Irq => "IRQ",
                         zos_kernel::ObjectType::IoPort => "IoPort",
                         zos_kernel::ObjectType::Console => "Console",
+                        zos_kernel::ObjectType::Notification => "Notification",
+                        zos_kernel::ObjectType::Reply => "Reply",
                     };
                     serde_json::json!({
                         "slot": slot,
@@ -233,6 +236,8 @@ impl Supervisor {
                                     zos_kernel::ObjectType::Irq => "IRQ",
                                     zos_kernel::ObjectType::IoPort => "IoPort",
                                     zos_kernel::ObjectType::Console => "Console",
+                                    zos_kernel::ObjectType::Notification => "Notification",
+                                    zos_kernel::ObjectType::Reply => "Reply",
                                 };
                                 serde_json::json!({
                                     "slot": slot,
@@ -307,4 +312,151 @@ impl Supervisor {
         }))
         .unwrap_or_else(|_| "{}".to_string())
     }
+
+    /// Get the kernel state right after commit `seq` as JSON
+    ///
+    /// Rebuilt from the CommitLog, so only committed state (processes,
+    /// capabilities, endpoints) is included. Returns `{"error": ...}` if the
+    /// sequence is not covered by the in-memory log.
+    #[wasm_bindgen]
+    pub fn get_state_at_json(&self, seq: u64) -> String {
+        let state = match self.system.state_at(seq) {
+            Ok(state) => state,
+            Err(e) => return error_json(&e),
+        };
+
+        let processes: Vec<_> = state
+            .list_processes()
+            .iter()
+            .map(|(pid, proc)| {
+                let caps: Vec<_> = state
+                    .get_cap_space(*pid)
+                    .map(|cs| cs.list().iter().map(|(slot, cap)| cap_json(*slot, cap)).collect())
+                    .unwrap_or_default();
+                serde_json::json!({
+                    "pid": pid.0,
+                    "name": proc.name,
+                    "state": state_name(proc.state),
+                    "capabilities": caps
+                })
+            })
+            .collect();
+        let endpoints: Vec<_> = state
+            .list_endpoints()
+            .iter()
+            .map(|ep| serde_json::json!({ "id": ep.id.0, "owner": ep.owner.0 }))
+            .collect();
+
+        serde_json::to_string(&serde_json::json!({
+            "seq": seq,
+            "processes": processes,
+            "endpoints": endpoints
+        }))
+        .unwrap_or_else(|_| "{}".to_string())
+    }
+
+    /// Get the difference between the states after commits `from` and `to`
+    /// as JSON
+    ///
+    /// Each entry carries a `change` of "added", "removed" or "modified" and
+    /// the `before`/`after` values that apply.
+    #[wasm_bindgen]
+    pub fn get_state_diff_json(&self, from: u64, to: u64) -> String {
+        let diff = match self.system.diff_seqs(from, to) {
+            Ok(diff) => diff,
+            Err(e) => return error_json(&e),
+        };
+
+        let processes: Vec<_> = diff
+            .processes
+            .iter()
+            .map(|d| {
+                let mut entry = change_json(&d.change, |p| {
+                    serde_json::json!({ "name": p.name, "state": state_name(p.state) })
+                });
+                entry["pid"] = d.pid.0.into();
+                entry
+            })
+            .collect();
+        let capabilities: Vec<_> = diff
+            .capabilities
+            .iter()
+            .map(|d| {
+                let mut entry = change_json(&d.change, |cap| cap_json(d.slot, cap));
+                entry["pid"] = d.pid.0.into();
+                entry["slot"] = d.slot.into();
+                entry
+            })
+            .collect();
+        let endpoints: Vec<_> = diff
+            .endpoints
+            .iter()
+            .map(|d| {
+                let mut entry = change_json(&d.change, |owner| owner.0.into());
+                entry["id"] = d.id.0.into();
+                entry
+            })
+            .collect();
+
+        serde_json::to_string(&serde_json::json!({
+            "from": from,
+            "to": to,
+            "processes": processes,
+            "capabilities": capabilities,
+            "endpoints": endpoints
+        }))
+        .unwrap_or_else(|_| "{}".to_string())
+    }
+}
+
+fn state_name(state: zos_kernel::ProcessState) -> &'static str {
+    match state {
+        zos_kernel::ProcessState::Running => "Running",
+        zos_kernel::ProcessState::Blocked => "Blocked",
+        zos_kernel::ProcessState::Zombie => "Zombie",
+    }
+}
+
+fn cap_json(slot: u32, cap: &zos_kernel::Capability) -> serde_json::Value {
+    let type_str = match cap.object_type {
+        zos_kernel::ObjectType::Endpoint => "Endpoint",
+        zos_kernel::ObjectType::Process => "Process",
+        zos_kernel::ObjectType::Memory => "Memory",
+        zos_kernel::ObjectType::Irq => "IRQ",
+        zos_kernel::ObjectType::IoPort => "IoPort",
+        zos_kernel::ObjectType::Console => "Console",
+        zos_kernel::ObjectType::Notification => "Notification",
+        zos_kernel::ObjectType::Reply => "Reply",
+    };
+    serde_json::json!({
+        "slot": slot,
+        "objectType": type_str,
+        "permissions": {
+            "read": cap.permissions.read,
+            "write": cap.permissions.write,
+            "grant": cap.permissions.grant
+        },
+        "objectId": cap.object_id
+    })
+}
+
+fn change_json<T>(
+    change: &zos_kernel::Change<T>,
+    value: impl Fn(&T) -> serde_json::Value,
+) -> serde_json::Value {
+    match change {
+        zos_kernel::Change::Added(after) => {
+            serde_json::json!({ "change": "added", "after": value(after) })
+        }
+        zos_kernel::Change::Removed(before) => {
+            serde_json::json!({ "change": "removed", "before": value(before) })
+        }
+        zos_kernel::Change::Modified { before, after } => {
+            serde_json::json!({ "change": "modified", "before": value(before), "after": value(after) })
+        }
+    }
+}
+
+fn error_json(error: &zos_kernel::ReplayError) -> String {
+    serde_json::json!({ "error": format!("{:?}", error) }).to_string()
 }
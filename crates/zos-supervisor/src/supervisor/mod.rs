@@ -366,10 +366,19 @@ impl Supervisor {
                 &data,
             );
 
+            // A parked timed receive/call is answered by resume_parked_syscalls()
+            if self.system.is_parked(pid) {
+                self.system.hal().park_syscall(syscall_info.pid);
+                continue;
+            }
+
             // Write result and wake worker
             self.system.hal().complete_syscall(syscall_info.pid, result);
         }
 
+        // Wake workers whose timed receive/call got a message or timed out
+        self.resume_parked_syscalls();
+
         // Progress the ping-pong test state machine if running
         self.progress_pingpong_test();
 
@@ -631,6 +640,61 @@ impl Supervisor {
         self.on_network_result_internal(request_id, pid, result)
     }
 
+    // ==========================================================================
+    // Syscall recording (record/replay)
+    // ==========================================================================
+
+    /// Start recording full syscall payloads and results.
+    ///
+    /// Discards any recording in progress.
+    #[wasm_bindgen]
+    pub fn start_syscall_recording(&mut self) {
+        self.system.start_recording();
+        log("[supervisor] Syscall recording started");
+    }
+
+    /// Stop recording and return the trace file bytes.
+    ///
+    /// Returns an empty array if recording was not on.
+    #[wasm_bindgen]
+    pub fn stop_syscall_recording(&mut self) -> Vec<u8> {
+        match self.system.stop_recording() {
+            Some(trace) => {
+                log(&format!(
+                    "[supervisor] Syscall recording stopped ({} events)",
+                    trace.len()
+                ));
+                trace.encode()
+            }
+            None => Vec::new(),
+        }
+    }
+
+    // ==========================================================================
+    // Log export (zos-axiom log file format, readable by axiom-inspect)
+    // ==========================================================================
+
+    /// Export the in-memory CommitLog, with the current state hash and the
+    /// signed checkpoints.
+    #[wasm_bindgen]
+    pub fn export_commitlog(&self) -> Vec<u8> {
+        zos_kernel::CommitLogFile {
+            commits: self.system.commitlog().commits().to_vec(),
+            state_hash: Some(zos_kernel::Replayable::state_hash(&self.system)),
+            checkpoints: self.system.checkpoints().to_vec(),
+        }
+        .encode()
+    }
+
+    /// Export the in-memory SysLog.
+    #[wasm_bindgen]
+    pub fn export_syslog(&self) -> Vec<u8> {
+        zos_kernel::SysLogFile {
+            events: self.system.syslog().events().to_vec(),
+        }
+        .encode()
+    }
+
     // ==========================================================================
     // Wasm-bindgen wrappers for IPC methods
     // ==========================================================================
@@ -26,6 +26,7 @@
 
 use wasm_bindgen::prelude::*;
 use zos_hal::HAL;
+use zos_kernel::ProcessId;
 
 use crate::constants::SERVICE_INPUT_SLOT;
 use crate::util::log;
@@ -87,6 +88,9 @@ impl super::Supervisor {
 
     /// Deliver a network result to a process via IPC through Init.
     fn deliver_network_result(&mut self, pid: u64, payload: &[u8]) {
+        self.system
+            .record_async_result(ProcessId(pid), zos_ipc::net::MSG_NET_RESULT, payload);
+
         // Route through Init for capability-checked delivery
         self.route_ipc_via_init(pid, SERVICE_INPUT_SLOT, zos_ipc::net::MSG_NET_RESULT, payload);
     }
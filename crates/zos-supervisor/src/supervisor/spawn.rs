@@ -188,7 +188,14 @@ impl Supervisor {
         // TRANSITIONAL: Direct system call for process registration.
         // For Init, this is the bootstrap exception (see boot.rs).
         // For other processes, this should migrate to Init-driven spawn.
-        let process_pid = self.system.register_process(name);
+        // Everything but Init is spawned on Init's behalf, so Init is the
+        // parent that is told when it dies.
+        let process_pid = if name == "init" {
+            self.system.register_process(name)
+        } else {
+            self.system
+                .register_process_with_parent(name, ProcessId(zos_ipc::pid::INIT as u64))
+        };
         log(&format!(
             "[supervisor] System assigned PID {} for '{}'",
             process_pid.0, name
@@ -23,6 +23,7 @@
 //! - Payload corruption (data must match what JavaScript provided)
 
 use zos_hal::HAL;
+use zos_kernel::ProcessId;
 
 use crate::constants::SERVICE_INPUT_SLOT;
 use crate::util::log;
@@ -229,6 +230,12 @@ impl super::Supervisor {
 
     /// Deliver a storage result to a process via IPC through Init.
     pub(super) fn deliver_storage_result(&mut self, pid: u64, payload: &[u8]) {
+        self.system.record_async_result(
+            ProcessId(pid),
+            storage_const::MSG_STORAGE_RESULT,
+            payload,
+        );
+
         // Route through Init for capability-checked delivery
         // Use SERVICE_INPUT_SLOT for storage results to services.
         // Services like IdentityService and VfsService use storage syscalls and
@@ -248,6 +255,12 @@ impl super::Supervisor {
     /// instead of MSG_STORAGE_RESULT (0x80) so KeystoreService can distinguish
     /// keystore results from VFS storage results.
     pub(super) fn deliver_keystore_result(&mut self, pid: u64, payload: &[u8]) {
+        self.system.record_async_result(
+            ProcessId(pid),
+            storage_const::MSG_KEYSTORE_RESULT,
+            payload,
+        );
+
         self.route_ipc_via_init(
             pid,
             SERVICE_INPUT_SLOT,
@@ -76,6 +76,18 @@ impl Supervisor {
         result as i32
     }
 
+    /// Answer parked timed receives, calls and notification waits whose
+    /// wait ended.
+    ///
+    /// Parked workers stay blocked in their mailbox until this delivers the
+    /// result. Deadlines are checked on every `poll_syscalls`.
+    pub(super) fn resume_parked_syscalls(&mut self) {
+        for (pid, result, response_data) in self.system.resume_parked() {
+            self.system.hal().write_syscall_data(pid.0, &response_data);
+            self.system.hal().complete_syscall(pid.0, result as i32);
+        }
+    }
+
     /// Handle SYS_DEBUG syscall.
     ///
     /// Debug messages are used for inter-process communication with the supervisor:
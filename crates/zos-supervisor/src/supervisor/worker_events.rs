@@ -46,12 +46,11 @@ impl super::Supervisor {
                     self.system
                         .hal()
                         .update_process_memory(msg.pid, memory_size);
-                    let pid = ProcessId(msg.pid);
-                    self.system.update_process_memory(pid, memory_size);
                     log(&format!(
                         "[supervisor] Worker {} ready, memory: {} bytes",
                         msg.pid, memory_size
                     ));
+                    self.record_worker_memory(msg.pid, memory_size);
                 }
                 WorkerMessageType::Error { ref message } => {
                     log(&format!(
@@ -76,8 +75,7 @@ impl super::Supervisor {
                     self.system
                         .hal()
                         .update_process_memory(msg.pid, memory_size);
-                    let pid = ProcessId(msg.pid);
-                    self.system.update_process_memory(pid, memory_size);
+                    self.record_worker_memory(msg.pid, memory_size);
                 }
                 WorkerMessageType::Yield => {
                     // Worker yielded - nothing to do
@@ -87,4 +85,20 @@ impl super::Supervisor {
 
         count
     }
+
+    /// Record a worker's memory size and stop it if it is over its quota.
+    fn record_worker_memory(&mut self, pid: u64, memory_size: usize) {
+        if self
+            .system
+            .update_process_memory(ProcessId(pid), memory_size)
+            .is_err()
+            && self.system.get_process(ProcessId(pid)).is_some()
+        {
+            log(&format!(
+                "[supervisor] Worker {} exceeded its memory quota ({} bytes), killing",
+                pid, memory_size
+            ));
+            self.kill_process(pid);
+        }
+    }
 }
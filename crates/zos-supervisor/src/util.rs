@@ -31,3 +31,8 @@ pub fn hex_to_bytes(hex: &str) -> Result<Vec<u8>, &'static str> {
         .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| "Invalid hex character"))
         .collect()
 }
+
+/// Encode bytes as a lowercase hex string.
+pub fn bytes_to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -59,6 +59,9 @@ unsafe impl Sync for WorkerProcess {}
 pub const STATUS_IDLE: i32 = 0;
 pub const STATUS_PENDING: i32 = 1;
 pub const STATUS_READY: i32 = 2;
+/// Syscall parked by the kernel (timed receive/call); the worker keeps
+/// waiting until the supervisor completes it
+pub const STATUS_PARKED: i32 = 3;
 
 // Mailbox field offsets in i32 units (must match worker.js)
 pub const MAILBOX_STATUS: u32 = 0;
@@ -49,6 +49,7 @@ use crate::ipc::{
     ReadFileResponse, ReaddirRequest, ReaddirResponse, StatRequest, StatResponse, UnlinkRequest,
     UnlinkResponse, WriteFileRequest, WriteFileResponse,
 };
+use crate::ipc::shared_body::{self, SHARED_BODY_THRESHOLD};
 
 /// Default capability slot for VFS service endpoint (same as VfsClient).
 /// This is assigned by init when the process starts.
@@ -66,6 +67,7 @@ pub fn send_read_request(path: &str) -> Result<(), VfsError> {
         path: String::from(path),
         offset: None,
         length: None,
+        shared_capacity: None,
     };
     send_vfs_request(vfs_msg::MSG_VFS_READ, &request)
 }
@@ -73,11 +75,25 @@ pub fn send_read_request(path: &str) -> Result<(), VfsError> {
 /// Send a VFS write file request (non-blocking).
 ///
 /// The response will arrive as a message with tag `MSG_VFS_WRITE_RESPONSE`.
+/// Content of at least `SHARED_BODY_THRESHOLD` bytes travels in a shared
+/// region.
 pub fn send_write_request(path: &str, content: &[u8]) -> Result<(), VfsError> {
+    if content.len() >= SHARED_BODY_THRESHOLD {
+        let slot = shared_body::share_body(content)?;
+        let request = WriteFileRequest {
+            path: String::from(path),
+            content: Vec::new(),
+            encrypt: false,
+            shared_len: Some(content.len() as u64),
+        };
+        return send_vfs_request_with_caps(vfs_msg::MSG_VFS_WRITE, &request, &[slot]);
+    }
+
     let request = WriteFileRequest {
         path: String::from(path),
         content: content.to_vec(),
         encrypt: false,
+        shared_len: None,
     };
     send_vfs_request(vfs_msg::MSG_VFS_WRITE, &request)
 }
@@ -248,6 +264,37 @@ fn send_vfs_request<T: serde::Serialize>(_tag: u32, _request: &T) -> Result<(),
     Ok(())
 }
 
+/// Send a VFS request carrying capabilities, which are consumed even if the
+/// send fails.
+#[cfg(target_arch = "wasm32")]
+fn send_vfs_request_with_caps<T: serde::Serialize>(
+    tag: u32,
+    request: &T,
+    caps: &[u32],
+) -> Result<(), VfsError> {
+    let sent = serde_json::to_vec(request)
+        .map_err(|e| VfsError::StorageError(format!("Serialize error: {}", e)))
+        .and_then(|data| {
+            zos_process::send_with_caps(VFS_ENDPOINT_SLOT, tag, &data, caps)
+                .map_err(|e| VfsError::StorageError(format!("Send error: {}", e)))
+        });
+    if sent.is_err() {
+        for &slot in caps {
+            let _ = zos_process::cap_delete(slot);
+        }
+    }
+    sent
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn send_vfs_request_with_caps<T: serde::Serialize>(
+    _tag: u32,
+    _request: &T,
+    _caps: &[u32],
+) -> Result<(), VfsError> {
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -31,6 +31,7 @@ use crate::ipc::{
     StatResponse, UnlinkRequest, UnlinkResponse, WriteFileRequest, WriteFileResponse,
 };
 use crate::core::{DirEntry, Inode};
+use crate::ipc::shared_body::{self, SHARED_BODY_THRESHOLD};
 
 /// Default capability slot for VFS service endpoint
 /// This is assigned by init when the process starts
@@ -238,16 +239,33 @@ impl VfsClient {
     /// # Returns
     /// - `Ok(())` on success
     /// - `Err(VfsError)` on failure
+    ///
+    /// Content of at least `SHARED_BODY_THRESHOLD` bytes is handed to the
+    /// VFS through a shared region rather than in the request.
     pub fn write_file_with_options(
         &self,
         path: &str,
         content: &[u8],
         encrypt: bool,
     ) -> Result<(), VfsError> {
+        if content.len() >= SHARED_BODY_THRESHOLD {
+            let slot = shared_body::share_body(content)?;
+            let request = WriteFileRequest {
+                path: path.to_string(),
+                content: Vec::new(),
+                encrypt,
+                shared_len: Some(content.len() as u64),
+            };
+            let response: WriteFileResponse =
+                self.call_with_caps(vfs_msg::MSG_VFS_WRITE, &request, &[slot])?;
+            return response.result;
+        }
+
         let request = WriteFileRequest {
             path: path.to_string(),
             content: content.to_vec(),
             encrypt,
+            shared_len: None,
         };
         let response: WriteFileResponse = self.call(vfs_msg::MSG_VFS_WRITE, &request)?;
         response.result
@@ -275,19 +293,49 @@ impl VfsClient {
     /// # Returns
     /// - `Ok(Vec<u8>)` with file contents on success
     /// - `Err(VfsError)` on failure
+    ///
+    /// A `length` of at least `SHARED_BODY_THRESHOLD` bytes has the VFS
+    /// return the content through a shared region.
     pub fn read_file_with_options(
         &self,
         path: &str,
         offset: Option<u64>,
         length: Option<u64>,
     ) -> Result<Vec<u8>, VfsError> {
+        let capacity = length
+            .map(|len| len as usize)
+            .filter(|&len| len >= SHARED_BODY_THRESHOLD);
+        let Some(capacity) = capacity else {
+            let request = ReadFileRequest {
+                path: path.to_string(),
+                offset,
+                length,
+                shared_capacity: None,
+            };
+            let response: ReadFileResponse = self.call(vfs_msg::MSG_VFS_READ, &request)?;
+            return response.result;
+        };
+
+        let (kept, lent) = shared_body::lend_buffer(capacity)?;
         let request = ReadFileRequest {
             path: path.to_string(),
             offset,
             length,
+            shared_capacity: Some(capacity as u64),
         };
-        let response: ReadFileResponse = self.call(vfs_msg::MSG_VFS_READ, &request)?;
-        response.result
+        let response: Result<ReadFileResponse, VfsError> =
+            self.call_with_caps(vfs_msg::MSG_VFS_READ, &request, &[lent]);
+        match response {
+            Ok(ReadFileResponse {
+                result: Ok(_),
+                shared_len: Some(len),
+            }) => shared_body::take_body(kept, capacity, len as usize),
+            // Answered inline or failed; the region was never filled
+            response => {
+                let _ = zos_process::cap_delete(kept);
+                response.and_then(|r| r.result)
+            }
+        }
     }
 
     /// Delete a file.
@@ -363,24 +411,46 @@ impl VfsClient {
     }
 
     /// Internal: Send IPC request and receive response.
-    #[cfg(target_arch = "wasm32")]
     fn call<Req: serde::Serialize, Resp: serde::de::DeserializeOwned>(
         &self,
         tag: u32,
         request: &Req,
     ) -> Result<Resp, VfsError> {
-        use zos_process::{debug, receive_blocking, send};
+        self.call_with_caps(tag, request, &[])
+    }
+
+    /// Internal: Send IPC request with capabilities and receive response.
+    ///
+    /// The capabilities are consumed even if the send fails.
+    #[cfg(target_arch = "wasm32")]
+    fn call_with_caps<Req: serde::Serialize, Resp: serde::de::DeserializeOwned>(
+        &self,
+        tag: u32,
+        request: &Req,
+        caps: &[u32],
+    ) -> Result<Resp, VfsError> {
+        use zos_process::{cap_delete, debug, receive_blocking, send, send_with_caps};
 
         // VFS protocol: response tag = request tag + 1
         let expected_response_tag = tag + 1;
 
-        // Serialize request
-        let data = serde_json::to_vec(request)
-            .map_err(|e| VfsError::StorageError(alloc::format!("Serialize error: {}", e)))?;
-
-        // Send request to VFS service via our capability slot
-        send(self.vfs_endpoint, tag, &data)
-            .map_err(|e| VfsError::StorageError(alloc::format!("Send error: {}", e)))?;
+        // Serialize request and send it to VFS service via our capability slot
+        let sent = serde_json::to_vec(request)
+            .map_err(|e| VfsError::StorageError(alloc::format!("Serialize error: {}", e)))
+            .and_then(|data| {
+                let result = if caps.is_empty() {
+                    send(self.vfs_endpoint, tag, &data)
+                } else {
+                    send_with_caps(self.vfs_endpoint, tag, &data, caps)
+                };
+                result.map_err(|e| VfsError::StorageError(alloc::format!("Send error: {}", e)))
+            });
+        if let Err(e) = sent {
+            for &slot in caps {
+                let _ = cap_delete(slot);
+            }
+            return Err(e);
+        }
 
         // Wait for response on dedicated VFS response endpoint (slot 4)
         // This uses a separate endpoint from the general input slot (slot 1) to prevent
@@ -412,10 +482,11 @@ impl VfsClient {
     }
 
     #[cfg(not(target_arch = "wasm32"))]
-    fn call<Req: serde::Serialize, Resp: serde::de::DeserializeOwned>(
+    fn call_with_caps<Req: serde::Serialize, Resp: serde::de::DeserializeOwned>(
         &self,
         _tag: u32,
         _request: &Req,
+        _caps: &[u32],
     ) -> Result<Resp, VfsError> {
         Err(VfsError::StorageError(String::from(
             "VFS IPC not available outside WASM",
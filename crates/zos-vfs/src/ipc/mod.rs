@@ -3,6 +3,7 @@
 //! Note: VFS message constants are defined in `zos-ipc` as the single source of truth.
 //! This module re-exports them for backward compatibility and provides request/response types.
 
+pub mod shared_body;
 mod types;
 
 pub use types::*;
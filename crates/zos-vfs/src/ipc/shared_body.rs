@@ -0,0 +1,96 @@
+//! File bodies carried in shared memory regions
+//!
+//! Large bodies skip the JSON payload. A writer fills a region and attaches
+//! a read-only capability to its `WriteFileRequest` (`shared_len`); a reader
+//! lends a read-write capability with its `ReadFileRequest`
+//! (`shared_capacity`) and the VFS fills it, answering with `shared_len`.
+//! The region capability is always the last capability on the request.
+//!
+//! Every helper consumes the capability it is handed, so a region is freed
+//! once the last party is done with it.
+
+use alloc::format;
+use alloc::vec::Vec;
+use zos_process::{cap_delete, cap_derive, region_create, MappedRegion, Permissions};
+
+use crate::core::VfsError;
+
+/// Bodies at least this large travel through a shared region
+pub const SHARED_BODY_THRESHOLD: usize = 16 * 1024;
+
+/// Put `body` in a new region and return a read-only capability to it,
+/// ready to be attached to a request
+pub fn share_body(body: &[u8]) -> Result<u32, VfsError> {
+    let slot = region_create(body.len()).map_err(|e| region_error("create", e))?;
+    let shared = fill(slot, body.len(), body).and_then(|()| {
+        cap_derive(slot, Permissions::read_only()).map_err(|e| region_error("derive", e))
+    });
+    let _ = cap_delete(slot);
+    shared
+}
+
+/// Create a region of `capacity` bytes for the VFS to fill
+///
+/// # Returns
+/// `(kept, lent)`: a full capability to keep and read back with
+/// `take_body`, and a read-write one to attach to the request
+pub fn lend_buffer(capacity: usize) -> Result<(u32, u32), VfsError> {
+    let kept = region_create(capacity).map_err(|e| region_error("create", e))?;
+    let read_write = Permissions {
+        read: true,
+        write: true,
+        grant: false,
+    };
+    match cap_derive(kept, read_write) {
+        Ok(lent) => Ok((kept, lent)),
+        Err(e) => {
+            let _ = cap_delete(kept);
+            Err(region_error("derive", e))
+        }
+    }
+}
+
+/// Copy the first `len` bytes of the region of `size` bytes behind `slot`
+/// and drop the capability
+pub fn take_body(slot: u32, size: usize, len: usize) -> Result<Vec<u8>, VfsError> {
+    let body = if len > size {
+        Err(VfsError::InvalidRequest(format!(
+            "Shared body of {} bytes exceeds its {} byte region",
+            len, size
+        )))
+    } else {
+        MappedRegion::map(slot, size, false)
+            .map(|region| region.as_slice()[..len].to_vec())
+            .map_err(|e| region_error("map", e))
+    };
+    let _ = cap_delete(slot);
+    body
+}
+
+/// Write `body` into the lent region of `capacity` bytes behind `slot` and
+/// drop the capability
+pub fn fill_lent(slot: u32, capacity: usize, body: &[u8]) -> Result<(), VfsError> {
+    let filled = if body.len() > capacity {
+        Err(VfsError::InvalidRequest(format!(
+            "Body of {} bytes does not fit the {} byte shared region",
+            body.len(),
+            capacity
+        )))
+    } else {
+        fill(slot, capacity, body)
+    };
+    let _ = cap_delete(slot);
+    filled
+}
+
+/// Copy `body` into the start of the region of `size` bytes behind `slot`
+fn fill(slot: u32, size: usize, body: &[u8]) -> Result<(), VfsError> {
+    let mut region = MappedRegion::map(slot, size, true).map_err(|e| region_error("map", e))?;
+    region.as_mut_slice()[..body.len()].copy_from_slice(body);
+    region.flush(0, body.len()).map_err(|e| region_error("flush", e))
+}
+
+/// Report a failed region syscall
+fn region_error(op: &str, code: u32) -> VfsError {
+    VfsError::StorageError(format!("Shared region {} failed: {}", op, code))
+}
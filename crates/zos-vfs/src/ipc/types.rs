@@ -70,6 +70,10 @@ pub struct WriteFileRequest {
     pub content: Vec<u8>,
     /// Encrypt the file
     pub encrypt: bool,
+    /// Length of a body carried in an attached shared region instead of
+    /// `content` (see `shared_body`)
+    #[serde(default)]
+    pub shared_len: Option<u64>,
 }
 
 /// Write file response.
@@ -88,6 +92,10 @@ pub struct ReadFileRequest {
     pub offset: Option<u64>,
     /// Number of bytes to read (None = all)
     pub length: Option<u64>,
+    /// Capacity of an attached shared region to receive the content
+    /// (see `shared_body`)
+    #[serde(default)]
+    pub shared_capacity: Option<u64>,
 }
 
 /// Read file response.
@@ -95,6 +103,10 @@ pub struct ReadFileRequest {
 pub struct ReadFileResponse {
     /// Result containing file content or error
     pub result: Result<Vec<u8>, VfsError>,
+    /// Length of the content written into the request's shared region;
+    /// `result` then holds no bytes
+    #[serde(default)]
+    pub shared_len: Option<u64>,
 }
 
 /// Delete file request.
@@ -263,4 +275,21 @@ mod tests {
         assert_eq!(req.path, "/home/user/Documents");
         assert!(req.create_parents);
     }
+
+    #[test]
+    fn test_file_messages_without_shared_fields() {
+        // Peers that predate shared bodies still interoperate
+        let write: WriteFileRequest =
+            serde_json::from_str(r#"{"path":"/tmp/a","content":[1,2],"encrypt":false}"#).unwrap();
+        assert_eq!(write.content, [1, 2]);
+        assert_eq!(write.shared_len, None);
+
+        let read: ReadFileRequest =
+            serde_json::from_str(r#"{"path":"/tmp/a","offset":null,"length":null}"#).unwrap();
+        assert_eq!(read.shared_capacity, None);
+
+        let response: ReadFileResponse = serde_json::from_str(r#"{"result":{"Ok":[7]}}"#).unwrap();
+        assert_eq!(response.result.unwrap(), [7]);
+        assert_eq!(response.shared_len, None);
+    }
 }
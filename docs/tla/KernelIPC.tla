@@ -5,7 +5,7 @@
  * This specification models the IPC (Inter-Process Communication) mechanism
  * in Zero OS, including:
  * - Process states (Ready, Running, Blocked)
- * - Endpoint queues with message delivery
+ * - Bounded endpoint queues with a per-endpoint capacity and overflow policy
  * - Capability-checked send/receive operations
  * - No-deadlock guarantees
  *
@@ -14,6 +14,7 @@
  * 2. NoLostMessages - Every sent message is eventually delivered or sender faulted
  * 3. NoDeadlock - System can always make progress
  * 4. CapabilityConsistency - Only valid capabilities allow operations
+ * 5. QueueBoundRespected - No queue exceeds its endpoint's capacity
  *)
 
 EXTENDS Integers, Sequences, FiniteSets
@@ -21,27 +22,47 @@ EXTENDS Integers, Sequences, FiniteSets
 CONSTANTS
     Processes,          \* Set of process IDs
     Endpoints,          \* Set of endpoint IDs
-    MaxQueueSize,       \* Maximum messages per endpoint queue
-    MaxMessages         \* Maximum total messages in system
+    MaxQueueSize,       \* Largest capacity an endpoint may be created with
+    MaxMessages,        \* Maximum total messages in system
+    Capacity,           \* Capacity[e] \in 1..MaxQueueSize, fixed at CreateEndpoint
+    Policy              \* Policy[e] \in OverflowPolicies, fixed at CreateEndpoint
+
+ASSUME Capacity \in [Endpoints -> 1..MaxQueueSize]
 
 VARIABLES
     processState,       \* processState[p] \in {Ready, Running, Blocked, Zombie}
     endpoints,          \* endpoints[e] = [owner |-> p, queue |-> <<msg, ...>>]
     capabilities,       \* capabilities[p] = set of {[endpoint |-> e, perms |-> ...]}
-    messageCount        \* Total messages sent (for fairness)
+    messageCount,       \* Total messages sent (for fairness)
+    sendWaiting         \* sendWaiting[p] = endpoint p is blocked sending to, or NoEndpoint
+
+vars == <<processState, endpoints, capabilities, messageCount, sendWaiting>>
 
 (*
  * Type definitions
  *)
 ProcessStates == {"Ready", "Running", "Blocked", "Zombie"}
 
+(*
+ * Overflow policies (OverflowPolicy in zos-kernel-core):
+ * - "Reject": Send fails with QueueFull and the drop is counted
+ * - "Block":  The sender blocks until the queue has room
+ *)
+OverflowPolicies == {"Reject", "Block"}
+
+ASSUME Policy \in [Endpoints -> OverflowPolicies]
+
+NoEndpoint == CHOOSE x : x \notin Endpoints
+
 Permission == [read: BOOLEAN, write: BOOLEAN, grant: BOOLEAN]
 
 Capability == [endpoint: Endpoints, perms: Permission]
 
 Message == [sender: Processes, tag: Nat, data: Nat]
 
-EndpointRecord == [owner: Processes, queue: Seq(Message)]
+EndpointRecord == [owner: Processes, queue: Seq(Message),
+                   capacity: 1..MaxQueueSize, policy: OverflowPolicies,
+                   dropped: Nat]
 
 (*
  * Type invariant - all variables are well-typed
@@ -51,16 +72,21 @@ TypeInvariant ==
     /\ endpoints \in [Endpoints -> EndpointRecord]
     /\ capabilities \in [Processes -> SUBSET Capability]
     /\ messageCount \in Nat
+    /\ sendWaiting \in [Processes -> Endpoints \cup {NoEndpoint}]
 
 (*
  * Initial state
  *)
 Init ==
     /\ processState = [p \in Processes |-> "Ready"]
-    /\ endpoints = [e \in Endpoints |-> [owner |-> CHOOSE p \in Processes : TRUE, 
-                                          queue |-> <<>>]]
+    /\ endpoints = [e \in Endpoints |-> [owner |-> CHOOSE p \in Processes : TRUE,
+                                          queue |-> <<>>,
+                                          capacity |-> Capacity[e],
+                                          policy |-> Policy[e],
+                                          dropped |-> 0]]
     /\ capabilities = [p \in Processes |-> {}]
     /\ messageCount = 0
+    /\ sendWaiting = [p \in Processes |-> NoEndpoint]
 
 (*
  * Helper: Check if process has write capability to endpoint
@@ -83,13 +109,19 @@ HasReadCap(p, e) ==
  *)
 IsAlive(p) == processState[p] /= "Zombie"
 
+(*
+ * Helper: Check if an endpoint queue has reached its capacity
+ *)
+IsFull(e) == Len(endpoints[e].queue) >= endpoints[e].capacity
+
 (*
  * Action: Send a message to an endpoint
  * 
  * Preconditions:
  * - Sender is alive
  * - Sender has write capability to endpoint
- * - Endpoint queue is not full
+ * - Sender is not blocked on a full queue
+ * - Endpoint queue is below its capacity
  * 
  * Effects:
  * - Message added to endpoint queue
@@ -97,14 +129,61 @@ IsAlive(p) == processState[p] /= "Zombie"
  *)
 Send(sender, endpoint, tag, data) ==
     /\ IsAlive(sender)
+    /\ sendWaiting[sender] = NoEndpoint
     /\ HasWriteCap(sender, endpoint)
-    /\ Len(endpoints[endpoint].queue) < MaxQueueSize
+    /\ ~IsFull(endpoint)
     /\ messageCount < MaxMessages
     /\ LET msg == [sender |-> sender, tag |-> tag, data |-> data]
        IN endpoints' = [endpoints EXCEPT 
                         ![endpoint].queue = Append(@, msg)]
     /\ messageCount' = messageCount + 1
-    /\ UNCHANGED <<processState, capabilities>>
+    /\ UNCHANGED <<processState, capabilities, sendWaiting>>
+
+(*
+ * Action: Send to a full queue under the "Reject" policy
+ *
+ * Effects:
+ * - Queue unchanged; the sender gets QueueFull
+ * - Endpoint drop count incremented
+ *)
+SendRejected(sender, endpoint) ==
+    /\ IsAlive(sender)
+    /\ sendWaiting[sender] = NoEndpoint
+    /\ HasWriteCap(sender, endpoint)
+    /\ IsFull(endpoint)
+    /\ endpoints[endpoint].policy = "Reject"
+    /\ endpoints[endpoint].dropped < MaxMessages
+    /\ endpoints' = [endpoints EXCEPT ![endpoint].dropped = @ + 1]
+    /\ UNCHANGED <<processState, capabilities, messageCount, sendWaiting>>
+
+(*
+ * Action: Send to a full queue under the "Block" policy
+ *
+ * Effects:
+ * - Queue unchanged; the sender blocks until there is room
+ *)
+SendBlocked(sender, endpoint) ==
+    /\ processState[sender] \in {"Ready", "Running"}
+    /\ sendWaiting[sender] = NoEndpoint
+    /\ HasWriteCap(sender, endpoint)
+    /\ IsFull(endpoint)
+    /\ endpoints[endpoint].policy = "Block"
+    /\ processState' = [processState EXCEPT ![sender] = "Blocked"]
+    /\ sendWaiting' = [sendWaiting EXCEPT ![sender] = endpoint]
+    /\ UNCHANGED <<endpoints, capabilities, messageCount>>
+
+(*
+ * Action: Wake a blocked sender once its endpoint has room
+ *
+ * The sender retries the send from the Ready state.
+ *)
+UnblockSender(p) ==
+    /\ processState[p] = "Blocked"
+    /\ sendWaiting[p] /= NoEndpoint
+    /\ ~IsFull(sendWaiting[p])
+    /\ processState' = [processState EXCEPT ![p] = "Ready"]
+    /\ sendWaiting' = [sendWaiting EXCEPT ![p] = NoEndpoint]
+    /\ UNCHANGED <<endpoints, capabilities, messageCount>>
 
 (*
  * Action: Receive a message from an endpoint
@@ -124,7 +203,7 @@ Receive(receiver, endpoint) ==
     /\ Len(endpoints[endpoint].queue) > 0
     /\ endpoints' = [endpoints EXCEPT 
                      ![endpoint].queue = Tail(@)]
-    /\ UNCHANGED <<processState, capabilities, messageCount>>
+    /\ UNCHANGED <<processState, capabilities, messageCount, sendWaiting>>
 
 (*
  * Action: Block waiting for a message
@@ -142,7 +221,7 @@ Block(p, endpoint) ==
     /\ HasReadCap(p, endpoint)
     /\ Len(endpoints[endpoint].queue) = 0
     /\ processState' = [processState EXCEPT ![p] = "Blocked"]
-    /\ UNCHANGED <<endpoints, capabilities, messageCount>>
+    /\ UNCHANGED <<endpoints, capabilities, messageCount, sendWaiting>>
 
 (*
  * Action: Unblock a process when message arrives
@@ -156,11 +235,12 @@ Block(p, endpoint) ==
  *)
 Unblock(p) ==
     /\ processState[p] = "Blocked"
+    /\ sendWaiting[p] = NoEndpoint
     /\ \E e \in Endpoints :
         /\ HasReadCap(p, e)
         /\ Len(endpoints[e].queue) > 0
     /\ processState' = [processState EXCEPT ![p] = "Ready"]
-    /\ UNCHANGED <<endpoints, capabilities, messageCount>>
+    /\ UNCHANGED <<endpoints, capabilities, messageCount, sendWaiting>>
 
 (*
  * Action: Grant a capability to another process
@@ -186,7 +266,7 @@ Grant(granter, grantee, endpoint, newPerms) ==
     /\ LET newCap == [endpoint |-> endpoint, perms |-> newPerms]
        IN capabilities' = [capabilities EXCEPT 
                            ![grantee] = @ \cup {newCap}]
-    /\ UNCHANGED <<processState, endpoints, messageCount>>
+    /\ UNCHANGED <<processState, endpoints, messageCount, sendWaiting>>
 
 (*
  * Action: Kill a process
@@ -194,11 +274,13 @@ Grant(granter, grantee, endpoint, newPerms) ==
  * Effects:
  * - Process state becomes Zombie
  * - Process loses all capabilities
+ * - Any pending blocked send is abandoned
  *)
 Kill(p) ==
     /\ IsAlive(p)
     /\ processState' = [processState EXCEPT ![p] = "Zombie"]
     /\ capabilities' = [capabilities EXCEPT ![p] = {}]
+    /\ sendWaiting' = [sendWaiting EXCEPT ![p] = NoEndpoint]
     /\ UNCHANGED <<endpoints, messageCount>>
 
 (*
@@ -207,6 +289,12 @@ Kill(p) ==
 Next ==
     \/ \E p \in Processes, e \in Endpoints, tag \in 0..10, data \in 0..10 :
         Send(p, e, tag, data)
+    \/ \E p \in Processes, e \in Endpoints :
+        SendRejected(p, e)
+    \/ \E p \in Processes, e \in Endpoints :
+        SendBlocked(p, e)
+    \/ \E p \in Processes :
+        UnblockSender(p)
     \/ \E p \in Processes, e \in Endpoints :
         Receive(p, e)
     \/ \E p \in Processes, e \in Endpoints :
@@ -223,13 +311,13 @@ Next ==
  * Fairness: Weak fairness on unblocking - blocked processes eventually get checked
  *)
 Fairness == 
-    /\ WF_<<processState, endpoints, capabilities, messageCount>>(
-        \E p \in Processes : Unblock(p))
+    /\ WF_vars(\E p \in Processes : Unblock(p))
+    /\ WF_vars(\E p \in Processes : UnblockSender(p))
 
 (*
  * Specification
  *)
-Spec == Init /\ [][Next]_<<processState, endpoints, capabilities, messageCount>> /\ Fairness
+Spec == Init /\ [][Next]_vars /\ Fairness
 
 (*
  * ========================================================================
@@ -245,7 +333,8 @@ NoUnauthorizedSend ==
     \A p \in Processes, e \in Endpoints :
         \/ ~IsAlive(p)
         \/ ~HasWriteCap(p, e)
-        \/ Len(endpoints[e].queue) >= MaxQueueSize
+        \/ sendWaiting[p] /= NoEndpoint
+        \/ IsFull(e)
         \/ messageCount >= MaxMessages
         \/ ENABLED Send(p, e, 0, 0)
 
@@ -262,10 +351,19 @@ NoUnauthorizedReceive ==
 
 (*
  * Property 3: Queue bound respected
- * No endpoint queue exceeds MaxQueueSize
+ * No endpoint queue exceeds its capacity, and no capacity exceeds MaxQueueSize
  *)
 QueueBoundRespected ==
-    \A e \in Endpoints : Len(endpoints[e].queue) <= MaxQueueSize
+    \A e \in Endpoints :
+        /\ Len(endpoints[e].queue) <= endpoints[e].capacity
+        /\ endpoints[e].capacity <= MaxQueueSize
+
+(*
+ * Property 3b: Blocking endpoints never drop messages
+ *)
+BlockPolicyNoDrops ==
+    \A e \in Endpoints :
+        endpoints[e].policy = "Block" => endpoints[e].dropped = 0
 
 (*
  * Property 4: Zombie processes have no capabilities
@@ -297,6 +395,15 @@ BlockedEventuallyUnblocks ==
          \E e \in Endpoints : HasReadCap(p, e) /\ Len(endpoints[e].queue) > 0)
         ~> processState[p] /= "Blocked"
 
+(*
+ * Property 7: Senders blocked on a full queue resume once it drains
+ *)
+BlockedSendersEventuallyResume ==
+    \A p \in Processes :
+        (processState[p] = "Blocked" /\ sendWaiting[p] /= NoEndpoint /\
+         ~IsFull(sendWaiting[p]))
+        ~> processState[p] /= "Blocked"
+
 (*
  * ========================================================================
  * Theorems (to be verified by TLC)
@@ -305,8 +412,10 @@ BlockedEventuallyUnblocks ==
 
 THEOREM Spec => []TypeInvariant
 THEOREM Spec => []QueueBoundRespected
+THEOREM Spec => []BlockPolicyNoDrops
 THEOREM Spec => []ZombieNoCaps
 THEOREM Spec => []NoDeadlock
 THEOREM Spec => BlockedEventuallyUnblocks
+THEOREM Spec => BlockedSendersEventuallyResume
 
 =============================================================================
@@ -0,0 +1,145 @@
+//! Read-only access to the x86_64 key-value disk image.
+//!
+//! The format is the log written by `zos_hal::x86_64::storage::BlockStorage`
+//! and defined in `zos_hal::kvlog`; reading goes through the same recovery
+//! code, so the tool sees exactly what the next boot would. Images in the
+//! original append-only format are read as well.
+
+use std::collections::BTreeMap;
+
+use zos_hal::kvlog;
+
+pub use zos_hal::kvlog::KvError as DiskError;
+
+/// Check whether `bytes` starts with a storage superblock.
+pub fn is_disk_image(bytes: &[u8]) -> bool {
+    kvlog::is_image(bytes)
+}
+
+/// Read every live entry of a disk image.
+pub fn read_entries(image: &[u8]) -> Result<BTreeMap<String, Vec<u8>>, DiskError> {
+    kvlog::read_image(image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use zos_hal::kvlog::{BlockDevice, KvLog, Mutation, MIN_SECTORS, SECTOR_SIZE};
+
+    /// Disk image being built, shared so it outlives the log
+    #[derive(Clone)]
+    struct SharedImage(Rc<RefCell<Vec<u8>>>);
+
+    impl BlockDevice for SharedImage {
+        fn sector_count(&self) -> u64 {
+            (self.0.borrow().len() / SECTOR_SIZE) as u64
+        }
+
+        fn read(&self, sector: u64, buf: &mut [u8]) -> Result<(), DiskError> {
+            let start = sector as usize * SECTOR_SIZE;
+            buf.copy_from_slice(&self.0.borrow()[start..start + buf.len()]);
+            Ok(())
+        }
+
+        fn write(&mut self, sector: u64, data: &[u8]) -> Result<(), DiskError> {
+            let start = sector as usize * SECTOR_SIZE;
+            self.0.borrow_mut()[start..start + data.len()].copy_from_slice(data);
+            Ok(())
+        }
+
+        fn flush(&mut self) -> Result<(), DiskError> {
+            Ok(())
+        }
+    }
+
+    fn log_image(batches: &[Vec<Mutation>]) -> Vec<u8> {
+        let image = SharedImage(Rc::new(RefCell::new(vec![0u8; MIN_SECTORS as usize * SECTOR_SIZE])));
+        let (mut log, _) = KvLog::open(image.clone()).unwrap();
+        for batch in batches {
+            log.commit(batch).unwrap();
+        }
+        drop(log);
+        let bytes = image.0.borrow().clone();
+        bytes
+    }
+
+    fn put(key: &str, value: &[u8]) -> Mutation {
+        Mutation::Put(key.to_string(), value.to_vec())
+    }
+
+    /// Image in the original format: superblock, then one entry per sector run
+    fn legacy_image(entries: &[(u32, &str, &[u8])]) -> Vec<u8> {
+        let mut image = vec![0u8; SECTOR_SIZE];
+        for (flags, key, value) in entries {
+            let mut entry = Vec::new();
+            for word in [0x5A4F5345, *flags, key.len() as u32, value.len() as u32] {
+                entry.extend_from_slice(&word.to_le_bytes());
+            }
+            entry.extend_from_slice(key.as_bytes());
+            entry.extend_from_slice(value);
+            entry.resize(entry.len().div_ceil(SECTOR_SIZE) * SECTOR_SIZE, 0);
+            image.extend_from_slice(&entry);
+        }
+        let next_free = (image.len() / SECTOR_SIZE) as u32;
+        image[0..4].copy_from_slice(&0x5A4F5300u32.to_le_bytes());
+        image[4..8].copy_from_slice(&1u32.to_le_bytes());
+        image[12..16].copy_from_slice(&next_free.to_le_bytes());
+        let checksum = image[..SECTOR_SIZE - 4]
+            .iter()
+            .fold(0u32, |sum, b| sum.wrapping_add(*b as u32));
+        image[SECTOR_SIZE - 4..SECTOR_SIZE].copy_from_slice(&checksum.to_le_bytes());
+        // Unused disk space after the last entry
+        image.resize(image.len() + 4 * SECTOR_SIZE, 0);
+        image
+    }
+
+    #[test]
+    fn reads_live_entries() {
+        let big = vec![7u8; 1500];
+        let image = log_image(&[
+            vec![put("/a", b"old"), put("/big", &big)],
+            vec![Mutation::Delete("/a".to_string())],
+            vec![put("/a", b"new"), put("/gone", b"x")],
+            vec![Mutation::Delete("/gone".to_string())],
+        ]);
+        assert!(is_disk_image(&image));
+
+        let entries = read_entries(&image).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries["/a"], b"new");
+        assert_eq!(entries["/big"], big);
+    }
+
+    #[test]
+    fn reads_legacy_entries() {
+        let big = vec![7u8; 1500];
+        let image = legacy_image(&[
+            (1, "/a", b"old"),
+            (1, "/big", &big),
+            (0, "/a", b""),
+            (1, "/a", b"new"),
+        ]);
+        assert!(is_disk_image(&image));
+
+        let entries = read_entries(&image).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries["/a"], b"new");
+        assert_eq!(entries["/big"], big);
+    }
+
+    #[test]
+    fn rejects_bad_superblock() {
+        let mut image = legacy_image(&[(1, "/a", b"x")]);
+        image[8] ^= 1;
+        assert_eq!(read_entries(&image), Err(DiskError::Unformatted));
+
+        let mut image = log_image(&[vec![put("/a", b"x")]]);
+        for slot in 0..2 {
+            image[slot * SECTOR_SIZE + 8] ^= 1;
+        }
+        assert_eq!(read_entries(&image), Err(DiskError::Unformatted));
+        assert!(!is_disk_image(&[0u8; SECTOR_SIZE]));
+    }
+}
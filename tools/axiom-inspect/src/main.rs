@@ -0,0 +1,483 @@
+//! Zero OS Axiom Log Inspector
+//!
+//! Offline inspection of CommitLog and SysLog files in the zos-axiom log
+//! file format, as exported by the supervisor or persisted by the x86_64
+//! kernel. Inputs can be a log file or a raw data disk image, in which case
+//! the CommitLog is read from its storage key.
+
+mod disk;
+
+use std::{env, fs, process};
+
+use zos_axiom::{log_kind, CommitLogFile, LogKind, SysLogFile};
+use zos_hal::TestHal;
+use zos_kernel::history::diff_states;
+use zos_kernel::{
+    axiom_replay, replay_to_seq, Commit, CommitLog, CommitType, Replayable, SysEvent,
+    SysEventType, System,
+};
+
+/// Storage key of the persisted CommitLog on x86_64
+const COMMITLOG_KEY: &str = "/axiom/commitlog.bin";
+
+const USAGE: &str = "\
+Usage: axiom-inspect <command> [options]
+
+Commands:
+  verify <input>             Check the hash chain (and final state hash)
+  log <input> [filters]      Print commits or events
+      --pid <pid>            Only entries involving this process
+      --type <name>          Only this commit/event type (e.g. CapGranted)
+      --from <seq>           Start at this sequence number / event ID
+      --to <seq>             Stop at this sequence number / event ID
+  replay <input> [--seq <n>] Replay commits and summarize the final state
+  diff <a> <b>               Compare two CommitLogs
+
+<input> is a log file or a data disk image (CommitLog read from
+--key <key>, default /axiom/commitlog.bin).";
+
+/// A loaded log file.
+enum Log {
+    Commits(CommitLogFile),
+    Events(SysLogFile),
+}
+
+/// Parsed `--name value` options.
+#[derive(Default)]
+struct Options {
+    pid: Option<u64>,
+    commit_type: Option<String>,
+    from: Option<u64>,
+    to: Option<u64>,
+    seq: Option<u64>,
+    key: Option<String>,
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (command, rest) = match args.split_first() {
+        Some((command, rest)) => (command.as_str(), rest),
+        None => usage(),
+    };
+    let (inputs, options) = parse_options(rest);
+
+    let ok = match (command, inputs.as_slice()) {
+        ("verify", [input]) => verify(&load(input, &options)),
+        ("log", [input]) => {
+            print_log(&load(input, &options), &options);
+            true
+        }
+        ("replay", [input]) => match load(input, &options) {
+            Log::Commits(file) => replay_summary(&file, options.seq),
+            Log::Events(_) => fail("replay needs a CommitLog, not a SysLog"),
+        },
+        ("diff", [a, b]) => match (load(a, &options), load(b, &options)) {
+            (Log::Commits(a), Log::Commits(b)) => diff(&a, &b),
+            _ => fail("diff needs two CommitLogs"),
+        },
+        _ => usage(),
+    };
+    process::exit(if ok { 0 } else { 1 });
+}
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("Error: {}", message);
+    process::exit(1);
+}
+
+fn parse_options(args: &[String]) -> (Vec<String>, Options) {
+    let mut inputs = Vec::new();
+    let mut options = Options::default();
+    let mut iter = args.iter();
+
+    while let Some(arg) = iter.next() {
+        if !arg.starts_with("--") {
+            inputs.push(arg.clone());
+            continue;
+        }
+        let value = iter
+            .next()
+            .unwrap_or_else(|| fail(&format!("{} needs a value", arg)));
+        let number = || {
+            value
+                .parse::<u64>()
+                .unwrap_or_else(|_| fail(&format!("{}: not a number: {}", arg, value)))
+        };
+        match arg.as_str() {
+            "--pid" => options.pid = Some(number()),
+            "--type" => options.commit_type = Some(value.clone()),
+            "--from" => options.from = Some(number()),
+            "--to" => options.to = Some(number()),
+            "--seq" => options.seq = Some(number()),
+            "--key" => options.key = Some(value.clone()),
+            _ => usage(),
+        }
+    }
+    (inputs, options)
+}
+
+/// Load a log file, or the CommitLog stored in a disk image.
+fn load(path: &str, options: &Options) -> Log {
+    let bytes = fs::read(path).unwrap_or_else(|e| fail(&format!("{}: {}", path, e)));
+
+    let bytes = if disk::is_disk_image(&bytes) {
+        let key = options.key.as_deref().unwrap_or(COMMITLOG_KEY);
+        let mut entries = disk::read_entries(&bytes)
+            .unwrap_or_else(|e| fail(&format!("{}: {}", path, e)));
+        entries
+            .remove(key)
+            .unwrap_or_else(|| fail(&format!("{}: no entry for key {}", path, key)))
+    } else {
+        bytes
+    };
+
+    let decoded = match log_kind(&bytes) {
+        Ok(LogKind::Commits) => CommitLogFile::decode(&bytes).map(Log::Commits),
+        Ok(LogKind::Events) => SysLogFile::decode(&bytes).map(Log::Events),
+        Err(e) => Err(e),
+    };
+    decoded.unwrap_or_else(|e| fail(&format!("{}: {:?}", path, e)))
+}
+
+// ============================================================================
+// verify
+// ============================================================================
+
+fn verify(log: &Log) -> bool {
+    let file = match log {
+        Log::Commits(file) => file,
+        Log::Events(file) => {
+            println!("SysLog: {} events (no hash chain)", file.events.len());
+            return true;
+        }
+    };
+    let commits = &file.commits;
+    let (first, last) = match (commits.first(), commits.last()) {
+        (Some(first), Some(last)) => (first.seq, last.seq),
+        _ => {
+            println!("CommitLog is empty");
+            return true;
+        }
+    };
+    println!("CommitLog: {} commits (seq {}..={})", commits.len(), first, last);
+
+    if let Some(seq) = first_broken_link(commits) {
+        println!("Hash chain: BROKEN at seq {}", seq);
+        return false;
+    }
+    if first == 0 {
+        println!("Hash chain: OK (from genesis)");
+    } else {
+        println!("Hash chain: OK (anchored at seq {}, not genesis)", first);
+    }
+
+    // Signatures need the machine key's verifier from the identity layer;
+    // here each checkpoint must at least attest a commit in the chain.
+    if !file.checkpoints.is_empty() {
+        match file
+            .checkpoints
+            .iter()
+            .find(|cp| !CommitLog::attests(commits, cp))
+        {
+            Some(cp) => {
+                println!("Checkpoints: MISMATCH at seq {}", cp.seq);
+                return false;
+            }
+            None => println!(
+                "Checkpoints: {} attest the chain (signer {})",
+                file.checkpoints.len(),
+                hex(&file.checkpoints[0].signer)
+            ),
+        }
+    }
+
+    let expected = match file.state_hash {
+        Some(hash) => hash,
+        None => return true,
+    };
+    let mut system = System::<TestHal>::new_for_replay();
+    if let Err(e) = axiom_replay(&mut system, commits) {
+        println!("Replay: FAILED ({:?})", e);
+        return false;
+    }
+    let actual = system.state_hash();
+    if actual == expected {
+        println!("State hash: OK ({})", hex(&actual));
+        true
+    } else {
+        println!(
+            "State hash: MISMATCH (recorded {}, replayed {})",
+            hex(&expected),
+            hex(&actual)
+        );
+        false
+    }
+}
+
+/// Find the first commit that breaks the chain, if any.
+fn first_broken_link(commits: &[Commit]) -> Option<u64> {
+    let mut prev: Option<&Commit> = None;
+    for commit in commits {
+        let linked = match prev {
+            Some(p) => commit.prev_commit == p.id && commit.seq == p.seq + 1,
+            None => true,
+        };
+        if !linked || !CommitLog::verify_chain(std::slice::from_ref(commit)) {
+            return Some(commit.seq);
+        }
+        prev = Some(commit);
+    }
+    None
+}
+
+// ============================================================================
+// log
+// ============================================================================
+
+fn print_log(log: &Log, options: &Options) {
+    let in_range = |n: u64| {
+        options.from.is_none_or(|from| n >= from) && options.to.is_none_or(|to| n <= to)
+    };
+    let type_matches = |name: &str| {
+        options
+            .commit_type
+            .as_ref()
+            .is_none_or(|wanted| wanted.eq_ignore_ascii_case(name))
+    };
+
+    match log {
+        Log::Commits(file) => {
+            for commit in &file.commits {
+                if in_range(commit.seq)
+                    && type_matches(type_name(&commit.commit_type))
+                    && options
+                        .pid
+                        .is_none_or(|pid| commit_pids(&commit.commit_type).contains(&pid))
+                {
+                    println!("{}", describe_commit(commit));
+                }
+            }
+        }
+        Log::Events(file) => {
+            for event in &file.events {
+                if in_range(event.id)
+                    && type_matches(event_type_name(event))
+                    && options.pid.is_none_or(|pid| event.sender == pid)
+                {
+                    println!("{}", describe_event(event));
+                }
+            }
+        }
+    }
+}
+
+fn type_name(commit_type: &CommitType) -> &'static str {
+    match commit_type {
+        CommitType::Genesis => "Genesis",
+        CommitType::ProcessCreated { .. } => "ProcessCreated",
+        CommitType::ProcessExited { .. } => "ProcessExited",
+        CommitType::ProcessFaulted { .. } => "ProcessFaulted",
+        CommitType::CapInserted { .. } => "CapInserted",
+        CommitType::CapRemoved { .. } => "CapRemoved",
+        CommitType::CapGranted { .. } => "CapGranted",
+        CommitType::CapBadged { .. } => "CapBadged",
+        CommitType::CapExpiry { .. } => "CapExpiry",
+        CommitType::EndpointCreated { .. } => "EndpointCreated",
+        CommitType::EndpointDestroyed { .. } => "EndpointDestroyed",
+        CommitType::NotificationCreated { .. } => "NotificationCreated",
+        CommitType::NotificationSignaled { .. } => "NotificationSignaled",
+        CommitType::NotificationTaken { .. } => "NotificationTaken",
+        CommitType::MessageSent { .. } => "MessageSent",
+        CommitType::Replied { .. } => "Replied",
+        CommitType::CallAborted { .. } => "CallAborted",
+        CommitType::Snapshot { .. } => "Snapshot",
+        CommitType::QuotaSet { .. } => "QuotaSet",
+        CommitType::QuotaExceeded { .. } => "QuotaExceeded",
+        CommitType::IrqBound { .. } => "IrqBound",
+        CommitType::IrqUnbound { .. } => "IrqUnbound",
+        CommitType::IrqRaised { .. } => "IrqRaised",
+        CommitType::IrqAcked { .. } => "IrqAcked",
+        CommitType::RegionCreated { .. } => "RegionCreated",
+        CommitType::RegionMapped { .. } => "RegionMapped",
+        CommitType::RegionUnmapped { .. } => "RegionUnmapped",
+        CommitType::RegionDestroyed { .. } => "RegionDestroyed",
+    }
+}
+
+/// Processes a commit touches.
+fn commit_pids(commit_type: &CommitType) -> Vec<u64> {
+    match commit_type {
+        CommitType::ProcessCreated { pid, parent, .. } => vec![*pid, *parent],
+        CommitType::ProcessExited { pid, .. }
+        | CommitType::ProcessFaulted { pid, .. }
+        | CommitType::CapInserted { pid, .. }
+        | CommitType::CapRemoved { pid, .. }
+        | CommitType::CapBadged { pid, .. }
+        | CommitType::CapExpiry { pid, .. } => vec![*pid],
+        CommitType::CapGranted {
+            from_pid, to_pid, ..
+        } => vec![*from_pid, *to_pid],
+        CommitType::EndpointCreated { owner, .. }
+        | CommitType::NotificationCreated { owner, .. } => vec![*owner],
+        CommitType::NotificationSignaled { from, .. } => vec![*from],
+        CommitType::NotificationTaken { pid, .. } => vec![*pid],
+        CommitType::MessageSent { from_pid, .. } => vec![*from_pid],
+        CommitType::Replied { from, to, .. } => vec![*from, *to],
+        CommitType::CallAborted { caller } => vec![*caller],
+        CommitType::QuotaSet { pid, by, .. } => vec![*pid, *by],
+        CommitType::QuotaExceeded { pid, .. } | CommitType::IrqBound { pid, .. } => vec![*pid],
+        CommitType::RegionCreated { owner, .. } => vec![*owner],
+        CommitType::RegionMapped { pid, .. } | CommitType::RegionUnmapped { pid, .. } => vec![*pid],
+        CommitType::Genesis
+        | CommitType::EndpointDestroyed { .. }
+        | CommitType::RegionDestroyed { .. }
+        | CommitType::IrqUnbound { .. }
+        | CommitType::IrqRaised { .. }
+        | CommitType::IrqAcked { .. }
+        | CommitType::Snapshot { .. } => Vec::new(),
+    }
+}
+
+fn describe_commit(commit: &Commit) -> String {
+    let details = match &commit.commit_type {
+        // Snapshot state is opaque and can be large
+        CommitType::Snapshot { state, state_hash } => format!(
+            "Snapshot {{ state: {} bytes, state_hash: {} }}",
+            state.len(),
+            hex(state_hash)
+        ),
+        other => format!("{:?}", other),
+    };
+    let cause = commit
+        .caused_by
+        .map(|event| format!(" (event {})", event))
+        .unwrap_or_default();
+    format!(
+        "#{:<6} t={:<14} {} {}{}",
+        commit.seq,
+        commit.timestamp,
+        &hex(&commit.id)[..12],
+        details,
+        cause
+    )
+}
+
+fn event_type_name(event: &SysEvent) -> &'static str {
+    match event.event_type {
+        SysEventType::Request { .. } => "Request",
+        SysEventType::Response { .. } => "Response",
+    }
+}
+
+fn describe_event(event: &SysEvent) -> String {
+    let details = match &event.event_type {
+        SysEventType::Request { syscall_num, args } => format!(
+            "Request syscall={:#x} args=[{},{},{},{}]",
+            syscall_num, args[0], args[1], args[2], args[3]
+        ),
+        SysEventType::Response { request_id, result } => {
+            format!("Response req={} result={}", request_id, result)
+        }
+    };
+    format!(
+        "#{:<6} t={:<14} pid {:<4} {}",
+        event.id, event.timestamp, event.sender, details
+    )
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// ============================================================================
+// replay / diff
+// ============================================================================
+
+/// Replay `commits`, up to and including `seq` if given.
+fn replay_commits(commits: &[Commit], seq: Option<u64>) -> System<TestHal> {
+    let mut system = System::new_for_replay();
+    let result = match seq {
+        Some(seq) => replay_to_seq(&mut system, commits, seq),
+        None => axiom_replay(&mut system, commits),
+    };
+    if let Err(e) = result {
+        fail(&format!("replay failed: {:?}", e));
+    }
+    system
+}
+
+fn replay_summary(file: &CommitLogFile, seq: Option<u64>) -> bool {
+    let system = replay_commits(&file.commits, seq);
+    let last_seq = seq.or(file.commits.last().map(|c| c.seq)).unwrap_or(0);
+    println!("State after seq {}:", last_seq);
+
+    let processes = system.list_processes();
+    println!();
+    println!("Processes ({}):", processes.len());
+    for (pid, process) in &processes {
+        println!("  {:<4} {:<20} {:?}", pid.0, process.name, process.state);
+        if let Some(cspace) = system.get_cap_space(*pid) {
+            for (slot, cap) in &cspace.slots {
+                println!(
+                    "         slot {:<3} cap {} -> {:?} {} [{}{}{}]",
+                    slot,
+                    cap.id,
+                    cap.object_type,
+                    cap.object_id,
+                    if cap.permissions.read { "R" } else { "-" },
+                    if cap.permissions.write { "W" } else { "-" },
+                    if cap.permissions.grant { "G" } else { "-" },
+                );
+            }
+        }
+    }
+
+    let endpoints = system.list_endpoints();
+    println!();
+    println!("Endpoints ({}):", endpoints.len());
+    for endpoint in &endpoints {
+        println!("  {:<4} owner {}", endpoint.id.0, endpoint.owner.0);
+    }
+
+    println!();
+    println!("State hash: {}", hex(&system.state_hash()));
+    true
+}
+
+fn diff(a: &CommitLogFile, b: &CommitLogFile) -> bool {
+    let common = a
+        .commits
+        .iter()
+        .zip(&b.commits)
+        .take_while(|(x, y)| x.seq == y.seq && x.id == y.id)
+        .count();
+
+    if common == a.commits.len() && common == b.commits.len() {
+        println!("Logs are identical ({} commits)", common);
+        return true;
+    }
+    match common {
+        0 => println!("Logs share no commits"),
+        n => println!("Logs agree up to seq {}", a.commits[n - 1].seq),
+    }
+    for (label, file) in [("a", a), ("b", b)] {
+        let rest = &file.commits[common..];
+        println!();
+        println!("Only in {} ({} commits):", label, rest.len());
+        for commit in rest {
+            println!("  {}", describe_commit(commit));
+        }
+    }
+
+    let before = replay_commits(&a.commits, None);
+    let after = replay_commits(&b.commits, None);
+    println!();
+    println!("Final state a -> b:");
+    print!("{}", diff_states(&before, &after));
+    false
+}
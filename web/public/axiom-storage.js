@@ -9,15 +9,16 @@
  * Zero OS uses 3 separate IndexedDBs:
  * - **zos-filesystem**: VFS inodes and content (see zos-storage.js)
  * - **zos-keystore**: Cryptographic key storage (see zos-keystore.js)
- * - **zos-axiom** (this file): Commit log
+ * - **zos-axiom** (this file): Commit log and signed checkpoints
  *
  * @see docs/spec/v0.1.2/02-axiom/axiom-spec.md
  */
 window.ZosStorageAxiom = {
   db: null,
   DB_NAME: 'zos-axiom',
-  DB_VERSION: 2,
+  DB_VERSION: 4,
   STORE_NAME: 'commits',
+  CHECKPOINT_STORE: 'checkpoints',
 
   /**
    * Initialize the IndexedDB database.
@@ -40,6 +41,15 @@ window.ZosStorageAxiom = {
           store.createIndex('timestamp', 'timestamp', { unique: false });
           store.createIndex('commit_type', 'commit_type', { unique: false });
         }
+        if (!db.objectStoreNames.contains(this.CHECKPOINT_STORE)) {
+          db.createObjectStore(this.CHECKPOINT_STORE, { keyPath: 'seq' });
+        }
+        // The checkpoint key seed used to be kept here; it now lives in
+        // zos-keystore. Drop it along with the checkpoints it signed.
+        if (db.objectStoreNames.contains('meta')) {
+          db.deleteObjectStore('meta');
+          event.target.transaction.objectStore(this.CHECKPOINT_STORE).clear();
+        }
       };
 
       request.onsuccess = () => {
@@ -92,6 +102,46 @@ window.ZosStorageAxiom = {
     });
   },
 
+  /**
+   * Persist signed checkpoints in a single transaction.
+   * @param {Array<Object>} checkpoints - Checkpoints with a 'seq' property.
+   * @returns {Promise<number>} The count of persisted checkpoints.
+   */
+  async persistCheckpoints(checkpoints) {
+    if (!this.db) await this.init();
+    if (!checkpoints || checkpoints.length === 0) return 0;
+    return new Promise((resolve, reject) => {
+      const tx = this.db.transaction(this.CHECKPOINT_STORE, 'readwrite');
+      const store = tx.objectStore(this.CHECKPOINT_STORE);
+      let count = 0;
+      for (const checkpoint of checkpoints) {
+        const request = store.put(checkpoint);
+        request.onsuccess = () => count++;
+      }
+      tx.oncomplete = () => resolve(count);
+      tx.onerror = () => reject(tx.error);
+    });
+  },
+
+  /**
+   * Load all signed checkpoints, sorted by sequence number.
+   * @returns {Promise<Array<Object>>} Array of checkpoints.
+   */
+  async loadCheckpoints() {
+    if (!this.db) await this.init();
+    return new Promise((resolve, reject) => {
+      const tx = this.db.transaction(this.CHECKPOINT_STORE, 'readonly');
+      const store = tx.objectStore(this.CHECKPOINT_STORE);
+      const request = store.getAll();
+      request.onsuccess = () => {
+        const checkpoints = request.result || [];
+        checkpoints.sort((a, b) => a.seq - b.seq);
+        resolve(checkpoints);
+      };
+      request.onerror = () => reject(request.error);
+    });
+  },
+
   /**
    * Load all commit entries, sorted by sequence number.
    * @returns {Promise<Array<Object>>} Array of commit entries.
@@ -127,17 +177,20 @@ window.ZosStorageAxiom = {
   },
 
   /**
-   * Clear all commit entries from storage.
+   * Clear all commit entries and checkpoints from storage.
+   *
+   * The checkpoint key seed is kept, so later checkpoints are still
+   * signed by the same machine key.
    * @returns {Promise<void>}
    */
   async clear() {
     if (!this.db) await this.init();
     return new Promise((resolve, reject) => {
-      const tx = this.db.transaction(this.STORE_NAME, 'readwrite');
-      const store = tx.objectStore(this.STORE_NAME);
-      const request = store.clear();
-      request.onsuccess = () => resolve();
-      request.onerror = () => reject(request.error);
+      const tx = this.db.transaction([this.STORE_NAME, this.CHECKPOINT_STORE], 'readwrite');
+      tx.objectStore(this.STORE_NAME).clear();
+      tx.objectStore(this.CHECKPOINT_STORE).clear();
+      tx.oncomplete = () => resolve();
+      tx.onerror = () => reject(tx.error);
     });
   },
 
@@ -9,6 +9,7 @@ import {
   STATUS_IDLE,
   STATUS_PENDING,
   STATUS_READY,
+  STATUS_PARKED,
   MAILBOX_OFFSETS,
   MAILBOX_DATA_BYTE_OFFSET,
   MAILBOX_MAX_DATA_LEN,
@@ -27,6 +28,10 @@ describe('Mailbox Status Constants', () => {
   it('should have STATUS_READY as 2', () => {
     expect(STATUS_READY).toBe(2);
   });
+
+  it('should have STATUS_PARKED as 3', () => {
+    expect(STATUS_PARKED).toBe(3);
+  });
 });
 
 describe('Mailbox Offsets', () => {
@@ -7,6 +7,7 @@
 import {
   STATUS_IDLE,
   STATUS_PENDING,
+  STATUS_PARKED,
   MAILBOX_OFFSETS,
   MAILBOX_DATA_BYTE_OFFSET,
   MAILBOX_MAX_DATA_LEN,
@@ -99,13 +100,14 @@ export function zos_syscall(
   // Set status to PENDING (signals the supervisor)
   Atomics.store(view, MAILBOX_OFFSETS.STATUS, STATUS_PENDING);
 
-  // Wait for supervisor to process the syscall
+  // Wait for supervisor to process the syscall. A parked syscall (timed
+  // receive/call) stays PARKED until the kernel has its result.
   while (true) {
-    Atomics.wait(view, MAILBOX_OFFSETS.STATUS, STATUS_PENDING, 1000);
     const status = Atomics.load(view, MAILBOX_OFFSETS.STATUS);
-    if (status !== STATUS_PENDING) {
+    if (status !== STATUS_PENDING && status !== STATUS_PARKED) {
       break;
     }
+    Atomics.wait(view, MAILBOX_OFFSETS.STATUS, status, 1000);
   }
 
   // Read the result (as i64/BigInt to match WASM extern declaration)
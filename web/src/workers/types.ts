@@ -8,6 +8,8 @@
 export const STATUS_IDLE = 0;
 export const STATUS_PENDING = 1;
 export const STATUS_READY = 2;
+// Syscall parked by the kernel (timed receive/call), still waiting for a result
+export const STATUS_PARKED = 3;
 
 // Mailbox field offsets (in i32 units)
 export const MAILBOX_OFFSETS = {